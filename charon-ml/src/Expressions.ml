@@ -22,6 +22,21 @@ type assumed_fun_id =
   | ArrayRepeat
   | SliceIndexShared
   | SliceIndexMut
+  | VecNew
+  | VecPush
+  | VecLen
+  | VecIndexShared
+  | VecIndexMut
+  | OptionUnwrap
+  | OptionIsSome
+  | MemSwap
+  | MemReplace
+  | MemTake
+  | Transmute
+  | CopyNonOverlapping
+  | DiscriminantValue
+  | SizeOfVal
+  | Fmt
 [@@deriving show, ord]
 
 (** Ancestor the field_proj_kind iter visitor *)
@@ -103,6 +118,9 @@ type binop =
   | Mul
   | Shl
   | Shr
+  | And
+  | Or
+  | Offset
 [@@deriving show, ord]
 
 let all_binops =
@@ -123,6 +141,9 @@ let all_binops =
     Mul;
     Shl;
     Shr;
+    And;
+    Or;
+    Offset;
   ]
 
 (** Ancestor for the constant_expr iter visitor *)
@@ -145,7 +166,14 @@ class ['self] map_constant_expr_base =
 
 type cast_kind =
   | CastScalar of literal_type * literal_type
-  | CastFnPtr of ty * ty
+  | CastReifyFnPointer of ty * ty
+  | CastClosureFnPointer of ty * ty
+  | CastUnsize of ty * ty * trait_ref option
+      (** The trait ref is the vtable, if the target is a trait object *)
+  | CastPtrToPtr of ty * ty
+  | CastPtrToInt of ty * ty
+  | CastIntToPtr of ty * ty
+  | CastTransmute of ty * ty
 
 (* Remark: no `ArrayToSlice` variant: it gets eliminated in a micro-pass. *)
 and unop =
@@ -159,6 +187,9 @@ and raw_constant_expr =
   | CVar of const_generic_var_id
   | CTraitConst of trait_ref * generic_args * string
   | CFnPtr of fn_ptr
+  | CGlobalDeclRef of global_decl_id
+      (** The address of a static: the type of the constant is [&T] for the
+          immutable statics, and [*mut T] for the mutable statics *)
 
 and constant_expr = { value : raw_constant_expr; ty : ty }
 
@@ -195,11 +226,15 @@ and fun_id = FRegular of fun_decl_id | FAssumed of assumed_fun_id
         concrete = true;
       }]
 
+(** Nullary operations, which compute information about a type *)
+type nullop = SizeOf | AlignOf [@@deriving show, ord]
+
 (** Ancestor the operand iter visitor *)
 class ['self] iter_rvalue_base =
   object (_self : 'self)
     inherit [_] iter_constant_expr
     method visit_binop : 'env -> binop -> unit = fun _ _ -> ()
+    method visit_nullop : 'env -> nullop -> unit = fun _ _ -> ()
     method visit_borrow_kind : 'env -> borrow_kind -> unit = fun _ _ -> ()
   end
 
@@ -208,6 +243,7 @@ class ['self] map_rvalue_base =
   object (_self : 'self)
     inherit [_] map_constant_expr
     method visit_binop : 'env -> binop -> binop = fun _ x -> x
+    method visit_nullop : 'env -> nullop -> nullop = fun _ x -> x
     method visit_borrow_kind : 'env -> borrow_kind -> borrow_kind = fun _ x -> x
   end
 
@@ -238,17 +274,34 @@ and aggregate_kind =
   | AggregatedAdt of type_id * variant_id option * generic_args
   | AggregatedArray of ty * const_generic
   | AggregatedClosure of fun_decl_id * generic_args
+  | AggregatedUnion of type_decl_id * field_id * generic_args
+      (** A union value: the field id identifies the active field *)
+  | AggregatedGenerator of fun_decl_id * generic_args
 
 (* TODO: move the aggregate kind to operands *)
 (* TODO: we should prefix the type variants with "T", this would avoid collisions *)
 and rvalue =
   | Use of operand
   | RvRef of place * borrow_kind
+  | AddressOf of place * ref_kind
+      (** Raw borrow ([&raw const p], [&raw mut p]): creates a raw pointer
+          without creating a reference *)
   | UnaryOp of unop * operand
   | BinaryOp of binop * operand * operand
+  | Nullary of nullop * ty
+      (** The size or the alignment of a type (used by the low-level code) *)
   | Discriminant of place * type_decl_id
   | Aggregate of aggregate_kind * operand list
   | Global of global_decl_id
+  | Repeat of operand * ty * const_generic
+      (** [Repeat (x, ty, n)] creates an array where [x] (of type [ty]) is
+          copied [n] times. This only appears in the ULLBC: we desugar it to a
+          call to [ArrayRepeat] in the LLBC. *)
+  | ShallowInitBox of operand * ty
+      (** [ShallowInitBox (ptr, ty)] transforms a pointer to freshly allocated
+          memory into a box whose content is not initialized yet. We
+          reconstruct calls to [BoxNew] from this: it only remains if we
+          couldn't recognize the pattern. *)
 [@@deriving
   show,
     visitors
@@ -267,3 +320,29 @@ and rvalue =
         nude = true (* Don't inherit {!VisitorsRuntime.iter} *);
         concrete = true;
       }]
+
+(** A pure expression, computed for the small functions without loops, panics
+    or mutable borrows (see the [--emit-pure-forms] option). The shared
+    borrows and the boxes are modeled as values.
+ *)
+type pure_expr =
+  | PVar of var_id
+  | PConst of constant_expr
+  | PGlobal of global_decl_id
+  | PField of field_proj_kind * field_id * pure_expr
+  | PUnaryOp of unop * pure_expr
+  | PBinaryOp of binop * pure_expr * pure_expr
+  | PAggregate of aggregate_kind * pure_expr list
+  | PApp of fn_ptr * pure_expr list
+  | PLet of var_id * pure_expr * pure_expr  (** [let x = e1 in e2] *)
+  | PIte of pure_expr * pure_expr * pure_expr
+  | PSwitchInt of
+      pure_expr
+      * integer_type
+      * (switch_value list * pure_expr) list
+      * pure_expr
+  | PMatch of
+      pure_expr * (variant_id list * pure_expr) list * pure_expr option
+      (** The branches don't bind variables: they read the fields of the
+          scrutinee with [PField] projections *)
+[@@deriving show]
@@ -5,5 +5,5 @@ let unop_can_fail (unop : unop) : bool =
 
 let binop_can_fail (binop : binop) : bool =
   match binop with
-  | BitXor | BitAnd | BitOr | Eq | Lt | Le | Ne | Ge | Gt -> false
-  | Div | Rem | Add | Sub | Mul | Shl | Shr -> true
+  | BitXor | BitAnd | BitOr | Eq | Lt | Le | Ne | Ge | Gt | And | Or -> false
+  | Div | Rem | Add | Sub | Mul | Shl | Shr | Offset -> true
@@ -8,8 +8,10 @@ module GlobalDeclId = Expressions.GlobalDeclId
 module TraitDeclId = Types.TraitDeclId
 module TraitImplId = Types.TraitImplId
 module TraitClauseId = Types.TraitClauseId
+module ImplBlockId = Identifiers.IdGen ()
 
 type fun_decl_id = FunDeclId.id [@@deriving show, ord]
+type impl_block_id = ImplBlockId.id [@@deriving show, ord]
 type assumed_fun_id = Expressions.assumed_fun_id [@@deriving show, ord]
 type fun_id = Expressions.fun_id [@@deriving show, ord]
 
@@ -27,6 +29,18 @@ type var = {
 }
 [@@deriving show]
 
+(** The debug information of a variable of the source code *)
+type var_debug_info = {
+  name : string;  (** The name of the variable in the source code *)
+  span : span;  (** The span of the declaration of the variable *)
+  scope : span;  (** The span of the scope in which the variable is visible *)
+  var : VarId.id option;
+      (** The local which stores the value of the variable, if there is one *)
+  argument_index : int option;
+      (** If the variable is an argument: its (1-based) index *)
+}
+[@@deriving show]
+
 (** Ancestor the AST iter visitors *)
 class ['self] iter_ast_base =
   object (_self : 'self)
@@ -43,8 +57,29 @@ class ['self] map_ast_base =
 
 (* Below: the types need not be mutually recursive, but it makes it easier
    to derive the visitors *)
-type assertion = { cond : operand; expected : bool }
-and fn_operand = FnOpRegular of fn_ptr | FnOpMove of place
+type assertion = { cond : operand; expected : bool; kind : assert_kind }
+
+(** The kind of an assertion, which tells why the program panics if the
+    assertion fails *)
+and assert_kind =
+  | IndexOutOfBounds of operand * operand
+      (** Array/slice bounds check: the length, then the index *)
+  | Overflow of binop
+  | OverflowNeg
+  | DivisionByZero
+  | RemainderByZero
+  | ResumedGenerator
+  | MisalignedPointerDereference
+  | User
+      (** An assertion which comes from a conditional panic (introduced by
+          the [reconstruct_asserts] micro-pass) *)
+
+and fn_operand =
+  | FnOpRegular of fn_ptr
+  | FnOpMove of place
+  | FnOpVirtual of trait_decl_ref * trait_item_name * generic_args
+      (** Virtual call: call to a method of a trait object. The receiver is
+          the first argument of the call. *)
 
 and call = { func : fn_operand; args : operand list; dest : place }
 [@@deriving
@@ -89,12 +124,16 @@ type params_info = {
 }
 [@@deriving show]
 
-type closure_kind = Fn | FnMut | FnOnce [@@deriving show]
 type closure_info = { kind : closure_kind; state : ty list } [@@deriving show]
 
 (** A function signature for function declarations *)
 type fun_sig = {
   is_unsafe : bool;
+  is_const : bool;
+  is_async : bool;
+      (** If the function is an [async fn], the body is its desugaring *)
+  abi : string;  (** Ex.: ["Rust"] (the default), ["C"], ["rust-call"] *)
+  is_variadic : bool;
   is_closure : bool;
   closure_info : closure_info option;
   generics : generic_params;
@@ -107,6 +146,9 @@ type fun_sig = {
 
 type fun_kind =
   | RegularKind  (** A "normal" function *)
+  | InherentMethod of impl_block_id
+      (** Method defined in an inherent impl block (i.e., an impl block which
+          doesn't implement a trait) *)
   | TraitMethodImpl of trait_impl_id * trait_decl_id * string * bool
       (** Trait method implementation.
 
@@ -120,6 +162,8 @@ type fun_kind =
   | TraitMethodProvided of trait_decl_id * string
       (** Trait method provided function (trait method declaration which defines a
           default implementation at the same time *)
+  | ForeignKind
+      (** A function declared in an [extern] block: it doesn't have a body *)
 [@@deriving show]
 
 type 'body gexpr_body = {
@@ -130,18 +174,42 @@ type 'body gexpr_body = {
 
           See {!Identifiers.Id.mapi} for instance.
        *)
+  var_debug_info : var_debug_info list;
+      (** The debug information of the variables of the source code, which
+          allows mapping the locals back to those variables *)
+  backtraces : span list list;
+      (** The macro backtraces of the statements of the body, indexed by
+          {!Meta.BacktraceId.id} (see {!Meta.meta.backtrace}) *)
   body : 'body;
 }
 [@@deriving show]
 
+(** A bound on the depth of the nested calls performed by a function *)
+type call_depth =
+  | BoundedDepth of int
+      (** The function performs at most this number of nested calls *)
+  | UnboundedDepth
+      (** The function may be recursive, or calls such a function *)
+  | UnknownDepth
+      (** The function (transitively) calls opaque functions, or performs
+          calls which can't be resolved statically *)
+[@@deriving show]
+
 type 'body gfun_decl = {
   def_id : FunDeclId.id;
   meta : meta;
+  attr_info : attr_info;
   is_local : bool;
   name : name;
   signature : fun_sig;
   kind : fun_kind;
   body : 'body gexpr_body option;
+  pure_form : pure_expr option;
+      (** The pure form of the function, if the user asked for it (with
+          [--emit-pure-forms]) and the function qualified *)
+  call_depth : call_depth option;
+      (** The bound on the depth of the nested calls, if the user asked for it
+          (with [--call-depth]) *)
   is_global_decl_body : bool;
 }
 [@@deriving show]
@@ -151,6 +219,8 @@ type trait_decl = {
   is_local : bool;
   name : name;
   meta : meta;
+  attr_info : attr_info;
+  is_auto : bool;
   generics : generic_params;
   preds : predicates;
   parent_clauses : trait_clause list;
@@ -166,7 +236,12 @@ type trait_impl = {
   is_local : bool;
   name : name;
   meta : meta;
+  attr_info : attr_info;
   impl_trait : trait_decl_ref;
+  self_ty : ty;
+  header : string option;
+  is_negative : bool;
+  is_blanket : bool;
   generics : generic_params;
   preds : predicates;
   parent_trait_refs : trait_ref list;
@@ -177,6 +252,34 @@ type trait_impl = {
 }
 [@@deriving show]
 
+(** An inherent impl block, i.e., an impl block which doesn't implement a
+    trait. The methods are translated as functions: the impl blocks group them.
+ *)
+type impl_block = {
+  def_id : impl_block_id;
+  is_local : bool;
+  name : name;
+  meta : meta;
+  attr_info : attr_info;
+  self_ty : ty;
+  generics : generic_params;
+  preds : predicates;
+  methods : fun_decl_id list;
+      (** The methods which we translated, ordered by id *)
+}
+[@@deriving show]
+
+(** A warning about a declaration which became opaque (or, for the types,
+    erroneous) because we failed to translate it, rather than because the user
+    asked for it.
+ *)
+type opaque_warning = {
+  name : name;
+  span : span;
+  reason : string;  (** The error which prevented the translation *)
+}
+[@@deriving show]
+
 type 'id g_declaration_group = NonRecGroup of 'id | RecGroup of 'id list
 [@@deriving show]
 
@@ -194,24 +297,57 @@ type declaration_group =
   | TraitImplGroup of TraitImplId.id
 [@@deriving show]
 
+(** The kind of a global declaration *)
+type global_kind =
+  | ConstGlobal
+      (** A constant: [const] item, associated constant or promoted constant *)
+  | StaticGlobal of { is_mut : bool; interior_mut : bool }
+      (** A [static] item. [interior_mut] is true if the type of the static
+          has interior mutability (the static may then be mutated through
+          shared references). *)
+[@@deriving show]
+
 type 'body gglobal_decl = {
   meta : meta;
+  attr_info : attr_info;
   def_id : GlobalDeclId.id;
   is_local : bool;
   name : name;
+  parent_params_info : params_info option;
+      (** For the associated constants only *)
   ty : ty;
+  kind : global_kind;
   body : 'body;
 }
 [@@deriving show]
 
+(** The header of a generated file *)
+type header = {
+  format_version : int;
+  charon_version : string;
+  rustc_version : string;
+  hax_version : string;
+  crate_name : string;
+  edition : string;
+  target : string;  (** The target triple *)
+}
+[@@deriving show]
+
 (** A crate *)
 type ('fun_body, 'global_body) gcrate = {
+  header : header;
   name : string;
+  prelude : prelude;
+  target_info : target_info;
+      (** The information about the target the extraction assumed *)
   declarations : declaration_group list;
   type_decls : type_decl TypeDeclId.Map.t;
   fun_decls : 'fun_body gfun_decl FunDeclId.Map.t;
   global_decls : 'global_body gglobal_decl GlobalDeclId.Map.t;
   trait_decls : trait_decl TraitDeclId.Map.t;
   trait_impls : trait_impl TraitImplId.Map.t;
+  impl_blocks : impl_block ImplBlockId.Map.t;
+  opaque_warnings : opaque_warning list;
+      (** The declarations which became opaque because of errors *)
 }
 [@@deriving show]
@@ -87,10 +87,11 @@ let id_to_file_of_json (js : json) : (id_to_file_map, string) result =
 let loc_of_json (js : json) : (loc, string) result =
   combine_error_msgs js __FUNCTION__
     (match js with
-    | `Assoc [ ("line", line); ("col", col) ] ->
+    | `Assoc [ ("line", line); ("col", col); ("offset", offset) ] ->
         let* line = int_of_json line in
         let* col = int_of_json col in
-        Ok { line; col }
+        let* offset = int_of_json offset in
+        Ok { line; col; offset }
     | _ -> Error "")
 
 let span_of_json (id_to_file : id_to_file_map) (js : json) :
@@ -109,21 +110,62 @@ let meta_of_json (id_to_file : id_to_file_map) (js : json) :
     (meta, string) result =
   combine_error_msgs js __FUNCTION__
     (match js with
-    | `Assoc [ ("span", span); ("generated_from_span", generated_from_span) ] ->
+    | `Assoc
+        [
+          ("span", span);
+          ("generated_from_span", generated_from_span);
+          ("backtrace", backtrace);
+        ] ->
         let* span = span_of_json id_to_file span in
         let* generated_from_span =
           option_of_json (span_of_json id_to_file) generated_from_span
         in
-        Ok { span; generated_from_span }
+        let* backtrace = option_of_json BacktraceId.id_of_json backtrace in
+        Ok { span; generated_from_span; backtrace }
+    | _ -> Error "")
+
+let attribute_of_json (js : json) : (attribute, string) result =
+  combine_error_msgs js __FUNCTION__
+    (match js with
+    | `Assoc [ ("Inline", arg) ] ->
+        let* arg = string_option_of_json arg in
+        Ok (Inline arg)
+    | `Assoc [ ("MustUse", msg) ] ->
+        let* msg = string_option_of_json msg in
+        Ok (MustUse msg)
+    | `Assoc [ ("LinkName", name) ] ->
+        let* name = string_of_json name in
+        Ok (LinkName name)
+    | `String "NoMangle" -> Ok NoMangle
+    | `Assoc [ ("Tool", `Assoc [ ("path", path); ("args", args) ]) ] ->
+        let* path = string_of_json path in
+        let* args = string_option_of_json args in
+        Ok (Tool (path, args))
+    | _ -> Error "")
+
+let attr_info_of_json (js : json) : (attr_info, string) result =
+  combine_error_msgs js __FUNCTION__
+    (match js with
+    | `Assoc
+        [
+          ("doc_comments", doc_comments);
+          ("attributes", attributes);
+          ("source_text", source_text);
+        ] ->
+        let* doc_comments = list_of_json string_of_json doc_comments in
+        let* attributes = list_of_json attribute_of_json attributes in
+        let* source_text = option_of_json string_of_json source_text in
+        Ok { doc_comments; attributes; source_text }
     | _ -> Error "")
 
 let type_var_of_json (js : json) : (type_var, string) result =
   combine_error_msgs js __FUNCTION__
     (match js with
-    | `Assoc [ ("index", index); ("name", name) ] ->
+    | `Assoc [ ("index", index); ("name", name); ("sized", sized) ] ->
         let* index = TypeVarId.id_of_json index in
         let* name = string_of_json name in
-        Ok { index; name }
+        let* sized = bool_of_json sized in
+        Ok { index; name; sized }
     | _ -> Error "")
 
 let region_var_of_json (js : json) : (region_var, string) result =
@@ -172,14 +214,12 @@ let literal_type_of_json (js : json) : (literal_type, string) result =
     | `String "Char" -> Ok TChar
     | _ -> Error "")
 
-let const_generic_var_of_json (js : json) : (const_generic_var, string) result =
+let closure_kind_of_json (js : json) : (closure_kind, string) result =
   combine_error_msgs js __FUNCTION__
     (match js with
-    | `Assoc [ ("index", index); ("name", name); ("ty", ty) ] ->
-        let* index = ConstGenericVarId.id_of_json index in
-        let* name = string_of_json name in
-        let* ty = literal_type_of_json ty in
-        Ok { index; name; ty }
+    | `String "Fn" -> Ok Fn
+    | `String "FnMut" -> Ok FnMut
+    | `String "FnOnce" -> Ok FnOnce
     | _ -> Error "")
 
 let ref_kind_of_json (js : json) : (ref_kind, string) result =
@@ -192,6 +232,11 @@ let assumed_ty_of_json (js : json) : (assumed_ty, string) result =
   combine_error_msgs js __FUNCTION__
     (match js with
     | `String "Box" -> Ok TBox
+    | `String "Vec" -> Ok TVec
+    | `String "Rc" -> Ok TRc
+    | `String "Arc" -> Ok TArc
+    | `String "Cell" -> Ok TCell
+    | `String "RefCell" -> Ok TRefCell
     | `String "Array" -> Ok TArray
     | `String "Slice" -> Ok TSlice
     | `String "Str" -> Ok TStr
@@ -216,6 +261,108 @@ let big_int_of_json (js : json) : (big_int, string) result =
     | `String is -> Ok (Z.of_string is)
     | _ -> Error "")
 
+let literal_validity_of_json (js : json) : (literal_validity, string) result =
+  combine_error_msgs js __FUNCTION__
+    (match js with
+    | `Assoc [ ("ty", ty); ("size", size); ("valid_ranges", valid_ranges) ] ->
+        let* lit_ty = literal_type_of_json ty in
+        let* lit_size = int_of_json size in
+        let* valid_ranges =
+          list_of_json
+            (pair_of_json big_int_of_json big_int_of_json)
+            valid_ranges
+        in
+        Ok { lit_ty; lit_size; valid_ranges }
+    | _ -> Error "")
+
+let prelude_of_json (js : json) : (prelude, string) result =
+  combine_error_msgs js __FUNCTION__
+    (match js with
+    | `Assoc [ ("literal_validity", literal_validity) ] ->
+        let* literal_validity =
+          list_of_json literal_validity_of_json literal_validity
+        in
+        Ok { literal_validity }
+    | _ -> Error "")
+
+let endianness_of_json (js : json) : (endianness, string) result =
+  combine_error_msgs js __FUNCTION__
+    (match js with
+    | `String "Little" -> Ok Little
+    | `String "Big" -> Ok Big
+    | _ -> Error "")
+
+let target_info_of_json (js : json) : (target_info, string) result =
+  combine_error_msgs js __FUNCTION__
+    (match js with
+    | `Assoc
+        [ ("pointer_width", pointer_width); ("endianness", endianness) ] ->
+        let* pointer_width = int_of_json pointer_width in
+        let* endianness = endianness_of_json endianness in
+        Ok { pointer_width; endianness }
+    | _ -> Error "")
+
+(** The version of the format of the generated files we can read.
+
+    This must be kept in sync with [FORMAT_VERSION] in [charon/src/header.rs].
+ *)
+let format_version = 14
+
+let header_of_json (js : json) : (header, string) result =
+  (* We check the version before anything else, because the shape of the
+     header itself may change between two versions *)
+  match js with
+  | `Assoc (("format_version", `Int version) :: _)
+    when version <> format_version ->
+      Error
+        (Printf.sprintf
+           "The file uses the format version %d while we expect the format \
+            version %d: please regenerate it with a compatible version of \
+            Charon"
+           version format_version)
+  | _ ->
+      combine_error_msgs js __FUNCTION__
+        (match js with
+        | `Assoc
+            [
+              ("format_version", format_version);
+              ("charon_version", charon_version);
+              ("rustc_version", rustc_version);
+              ("hax_version", hax_version);
+              ("crate_name", crate_name);
+              ("edition", edition);
+              ("target", target);
+            ] ->
+            let* format_version = int_of_json format_version in
+            let* charon_version = string_of_json charon_version in
+            let* rustc_version = string_of_json rustc_version in
+            let* hax_version = string_of_json hax_version in
+            let* crate_name = string_of_json crate_name in
+            let* edition = string_of_json edition in
+            let* target = string_of_json target in
+            Ok
+              {
+                format_version;
+                charon_version;
+                rustc_version;
+                hax_version;
+                crate_name;
+                edition;
+                target;
+              }
+        | _ -> Error "")
+
+(** Retrieve and check the header of a crate, before deserializing the
+    declarations: if the file was generated by an incompatible version of
+    Charon, we would otherwise fail with an obscure error. *)
+let crate_header_of_json (js : json) : (header, string) result =
+  match js with
+  | `Assoc (("header", header) :: _) -> header_of_json header
+  | _ ->
+      Error
+        "The file doesn't have a header: it was probably generated by an \
+         older version of Charon"
+
 (** Deserialize a {!Values.scalar_value} from JSON and **check the ranges**.
 
     Note that in practice we also check that the values are in range
@@ -272,6 +419,24 @@ let scalar_value_of_json (js : json) : (scalar_value, string) result =
         raise (Failure ("Scalar value not in range: " ^ show_scalar_value sv)));
       res
 
+let switch_value_of_json (js : json) : (switch_value, string) result =
+  combine_error_msgs js __FUNCTION__
+    (match js with
+    | `Assoc [ ("Value", v) ] ->
+        let* v = scalar_value_of_json v in
+        Ok (SVValue v)
+    | `Assoc [ ("Range", `List [ lo; hi ]) ] ->
+        let* lo = scalar_value_of_json lo in
+        let* hi = scalar_value_of_json hi in
+        Ok (SVRange (lo, hi))
+    | _ -> Error "")
+
+let nullop_of_json (js : json) : (nullop, string) result =
+  match js with
+  | `String "SizeOf" -> Ok SizeOf
+  | `String "AlignOf" -> Ok AlignOf
+  | _ -> Error ("nullop_of_json failed on:" ^ show js)
+
 let literal_of_json (js : json) : (literal, string) result =
   combine_error_msgs js __FUNCTION__
     (match js with
@@ -284,20 +449,12 @@ let literal_of_json (js : json) : (literal, string) result =
     | `Assoc [ ("Char", v) ] ->
         let* v = char_of_json v in
         Ok (VChar v)
-    | _ -> Error "")
-
-let const_generic_of_json (js : json) : (const_generic, string) result =
-  combine_error_msgs js __FUNCTION__
-    (match js with
-    | `Assoc [ ("Global", id) ] ->
-        let* id = GlobalDeclId.id_of_json id in
-        Ok (CgGlobal id)
-    | `Assoc [ ("Var", id) ] ->
-        let* id = ConstGenericVarId.id_of_json id in
-        Ok (CgVar id)
-    | `Assoc [ ("Value", lit) ] ->
-        let* lit = literal_of_json lit in
-        Ok (CgValue lit)
+    | `Assoc [ ("Str", v) ] ->
+        let* v = string_of_json v in
+        Ok (VStr v)
+    | `Assoc [ ("ByteStr", v) ] ->
+        let* v = list_of_json int_of_json v in
+        Ok (VByteStr v)
     | _ -> Error "")
 
 let rec ty_of_json (js : json) : (ty, string) result =
@@ -336,6 +493,10 @@ let rec ty_of_json (js : json) : (ty, string) result =
         let* inputs = list_of_json ty_of_json inputs in
         let* output = ty_of_json output in
         Ok (TArrow (regions, inputs, output))
+    | `Assoc [ ("DynTrait", `List [ trait_ref; region ]) ] ->
+        let* trait_ref = trait_decl_ref_of_json trait_ref in
+        let* region = region_of_json region in
+        Ok (TDynTrait (trait_ref, region))
     | _ -> Error "")
 
 and trait_ref_of_json (js : json) : (trait_ref, string) result =
@@ -381,6 +542,29 @@ and generic_args_of_json (js : json) : (generic_args, string) result =
         Ok { regions; types; const_generics; trait_refs }
     | _ -> Error "")
 
+and const_generic_of_json (js : json) : (const_generic, string) result =
+  combine_error_msgs js __FUNCTION__
+    (match js with
+    | `Assoc [ ("Global", id) ] ->
+        let* id = GlobalDeclId.id_of_json id in
+        Ok (CgGlobal id)
+    | `Assoc [ ("Var", id) ] ->
+        let* id = ConstGenericVarId.id_of_json id in
+        Ok (CgVar id)
+    | `Assoc [ ("Value", lit) ] ->
+        let* lit = literal_of_json lit in
+        Ok (CgValue lit)
+    | `Assoc [ ("Adt", `List [ variant_id; fields ]) ] ->
+        let* variant_id = option_of_json VariantId.id_of_json variant_id in
+        let* fields = list_of_json const_generic_of_json fields in
+        Ok (CgAdt (variant_id, fields))
+    | `Assoc [ ("TraitConst", `List [ trait_ref; generics; const_name ]) ] ->
+        let* trait_ref = trait_ref_of_json trait_ref in
+        let* generics = generic_args_of_json generics in
+        let* const_name = string_of_json const_name in
+        Ok (CgTraitConst (trait_ref, generics, const_name))
+    | _ -> Error "")
+
 and trait_instance_id_of_json (js : json) : (trait_instance_id, string) result =
   combine_error_msgs js __FUNCTION__
     (match js with
@@ -406,9 +590,11 @@ and trait_instance_id_of_json (js : json) : (trait_instance_id, string) result =
         let* item_name = string_of_json item_name in
         let* clause_id = TraitClauseId.id_of_json clause_id in
         Ok (ItemClause (inst_id, decl_id, item_name, clause_id))
-    | `Assoc [ ("FnPointer", ty) ] ->
-        let* ty = ty_of_json ty in
-        Ok (FnPointer ty)
+    | `Assoc [ ("FnPointer", `List [ kind; inputs; output ]) ] ->
+        let* kind = closure_kind_of_json kind in
+        let* inputs = list_of_json ty_of_json inputs in
+        let* output = ty_of_json output in
+        Ok (FnPointer (kind, inputs, output))
     | `Assoc [ ("Closure", `List [ fid; generics ]) ] ->
         let* fid = FunDeclId.id_of_json fid in
         let* generics = generic_args_of_json generics in
@@ -430,11 +616,18 @@ let variant_of_json (id_to_file : id_to_file_map) (js : json) :
     (variant, string) result =
   combine_error_msgs js __FUNCTION__
     (match js with
-    | `Assoc [ ("meta", meta); ("name", name); ("fields", fields) ] ->
+    | `Assoc
+        [
+          ("meta", meta);
+          ("name", name);
+          ("fields", fields);
+          ("discriminant", discriminant);
+        ] ->
         let* meta = meta_of_json id_to_file meta in
         let* name = string_of_json name in
         let* fields = list_of_json (field_of_json id_to_file) fields in
-        Ok { meta; variant_name = name; fields }
+        let* discriminant = scalar_value_of_json discriminant in
+        Ok { meta; variant_name = name; fields; discriminant }
     | _ -> Error "")
 
 let type_decl_kind_of_json (id_to_file : id_to_file_map) (js : json) :
@@ -447,6 +640,9 @@ let type_decl_kind_of_json (id_to_file : id_to_file_map) (js : json) :
     | `Assoc [ ("Enum", variants) ] ->
         let* variants = list_of_json (variant_of_json id_to_file) variants in
         Ok (Enum variants)
+    | `Assoc [ ("Union", fields) ] ->
+        let* fields = list_of_json (field_of_json id_to_file) fields in
+        Ok (Union fields)
     | `String "Opaque" -> Ok Opaque
     | _ -> Error "")
 
@@ -463,6 +659,16 @@ let region_var_group_of_json (js : json) : (region_var_group, string) result =
 let region_var_groups_of_json (js : json) : (region_var_groups, string) result =
   combine_error_msgs js __FUNCTION__ (list_of_json region_var_group_of_json js)
 
+let trait_clause_origin_of_json (js : json) :
+    (trait_clause_origin, string) result =
+  combine_error_msgs js __FUNCTION__
+    (match js with
+    | `String "WhereClause" -> Ok WhereClause
+    | `String "InlineBound" -> Ok InlineBound
+    | `String "Supertrait" -> Ok Supertrait
+    | `String "Elaborated" -> Ok Elaborated
+    | _ -> Error "")
+
 let trait_clause_of_json (id_to_file : id_to_file_map) (js : json) :
     (trait_clause, string) result =
   combine_error_msgs js __FUNCTION__
@@ -471,14 +677,28 @@ let trait_clause_of_json (id_to_file : id_to_file_map) (js : json) :
         [
           ("clause_id", clause_id);
           ("meta", meta);
+          ("origin", origin);
           ("trait_id", trait_id);
           ("generics", generics);
         ] ->
         let* clause_id = TraitClauseId.id_of_json clause_id in
         let* meta = option_of_json (meta_of_json id_to_file) meta in
+        let* origin = trait_clause_origin_of_json origin in
         let* trait_id = TraitDeclId.id_of_json trait_id in
         let* clause_generics = generic_args_of_json generics in
-        Ok ({ clause_id; meta; trait_id; clause_generics } : trait_clause)
+        Ok
+          ({ clause_id; meta; origin; trait_id; clause_generics }
+            : trait_clause)
+    | _ -> Error "")
+
+let const_generic_var_of_json (js : json) : (const_generic_var, string) result =
+  combine_error_msgs js __FUNCTION__
+    (match js with
+    | `Assoc [ ("index", index); ("name", name); ("ty", ty) ] ->
+        let* index = ConstGenericVarId.id_of_json index in
+        let* name = string_of_json name in
+        let* ty = ty_of_json ty in
+        Ok { index; name; ty }
     | _ -> Error "")
 
 let generic_params_of_json (id_to_file : id_to_file_map) (js : json) :
@@ -594,6 +814,109 @@ let name_of_json (id_to_file : id_to_file_map) (js : json) :
   combine_error_msgs js __FUNCTION__
     (list_of_json (path_elem_of_json id_to_file) js)
 
+let repr_options_of_json (js : json) : (repr_options, string) result =
+  combine_error_msgs js __FUNCTION__
+    (match js with
+    | `Assoc
+        [
+          ("c", c);
+          ("transparent", transparent);
+          ("int", int);
+          ("pack", pack);
+          ("align", align);
+        ] ->
+        let* repr_c = bool_of_json c in
+        let* repr_transparent = bool_of_json transparent in
+        let* repr_int = option_of_json integer_type_of_json int in
+        let* repr_pack = option_of_json int_of_json pack in
+        let* repr_align = option_of_json int_of_json align in
+        Ok { repr_c; repr_transparent; repr_int; repr_pack; repr_align }
+    | _ -> Error "")
+
+let variance_of_json (js : json) : (variance, string) result =
+  combine_error_msgs js __FUNCTION__
+    (match js with
+    | `String "Covariant" -> Ok Covariant
+    | `String "Invariant" -> Ok Invariant
+    | `String "Contravariant" -> Ok Contravariant
+    | `String "Bivariant" -> Ok Bivariant
+    | _ -> Error "")
+
+let variances_of_json (js : json) : (variances, string) result =
+  combine_error_msgs js __FUNCTION__
+    (match js with
+    | `Assoc [ ("regions", regions); ("types", types) ] ->
+        let* region_variances = list_of_json variance_of_json regions in
+        let* type_variances = list_of_json variance_of_json types in
+        Ok { region_variances; type_variances }
+    | _ -> Error "")
+
+let drop_glue_of_json (js : json) : (drop_glue, string) result =
+  combine_error_msgs js __FUNCTION__
+    (match js with
+    | `Assoc
+        [
+          ("drop_impl", drop_impl);
+          ("needs_drop", needs_drop);
+          ("fields_drop_order", fields_drop_order);
+        ] ->
+        let* drop_impl = option_of_json TraitImplId.id_of_json drop_impl in
+        let* needs_drop = bool_of_json needs_drop in
+        let* fields_drop_order =
+          list_of_json (list_of_json FieldId.id_of_json) fields_drop_order
+        in
+        Ok { drop_impl; needs_drop; fields_drop_order }
+    | _ -> Error "")
+
+let tag_of_json (js : json) : (tag, string) result =
+  combine_error_msgs js __FUNCTION__
+    (match js with
+    | `Assoc [ ("offset", offset); ("ty", ty) ] ->
+        let* tag_offset = int_of_json offset in
+        let* tag_ty = integer_type_of_json ty in
+        Ok { tag_offset; tag_ty }
+    | _ -> Error "")
+
+let discriminant_encoding_of_json (js : json) :
+    (discriminant_encoding, string) result =
+  combine_error_msgs js __FUNCTION__
+    (match js with
+    | `Assoc [ ("Direct", tag) ] ->
+        let* tag = tag_of_json tag in
+        Ok (Direct tag)
+    | `Assoc
+        [
+          ( "Niche",
+            `Assoc
+              [
+                ("tag", tag);
+                ("untagged_variant", untagged_variant);
+                ("niche_variants", niche_variants);
+                ("niche_start", niche_start);
+              ] );
+        ] ->
+        let* niche_tag = tag_of_json tag in
+        let* untagged_variant = VariantId.id_of_json untagged_variant in
+        let* niche_variants =
+          pair_of_json VariantId.id_of_json VariantId.id_of_json niche_variants
+        in
+        let* niche_start = big_int_of_json niche_start in
+        Ok (Niche { niche_tag; untagged_variant; niche_variants; niche_start })
+    | _ -> Error "")
+
+let layout_of_json (js : json) : (layout, string) result =
+  combine_error_msgs js __FUNCTION__
+    (match js with
+    | `Assoc
+        [ ("size", size); ("align", align); ("discriminant", discriminant) ] ->
+        let* layout_size = int_of_json size in
+        let* layout_align = int_of_json align in
+        let* discriminant =
+          option_of_json discriminant_encoding_of_json discriminant
+        in
+        Ok { layout_size; layout_align; discriminant }
+    | _ -> Error "")
+
 let type_decl_of_json (id_to_file : id_to_file_map) (js : json) :
     (type_decl, string) result =
   combine_error_msgs js __FUNCTION__
@@ -602,20 +925,49 @@ let type_decl_of_json (id_to_file : id_to_file_map) (js : json) :
         [
           ("def_id", def_id);
           ("meta", meta);
+          ("attr_info", attr_info);
           ("is_local", is_local);
           ("name", name);
           ("generics", generics);
+          ("variances", variances);
           ("preds", preds);
           ("kind", kind);
+          ("repr", repr);
+          ("drop_glue", drop_glue);
+          ("layout", layout);
+          ("auto_trait_impls", auto_trait_impls);
         ] ->
         let* def_id = TypeDeclId.id_of_json def_id in
         let* meta = meta_of_json id_to_file meta in
+        let* attr_info = attr_info_of_json attr_info in
         let* is_local = bool_of_json is_local in
         let* name = name_of_json id_to_file name in
         let* generics = generic_params_of_json id_to_file generics in
+        let* variances = variances_of_json variances in
         let* preds = predicates_of_json preds in
         let* kind = type_decl_kind_of_json id_to_file kind in
-        Ok { def_id; meta; is_local; name; generics; preds; kind }
+        let* repr = repr_options_of_json repr in
+        let* drop_glue = drop_glue_of_json drop_glue in
+        let* layout = option_of_json layout_of_json layout in
+        let* auto_trait_impls =
+          list_of_json TraitDeclId.id_of_json auto_trait_impls
+        in
+        Ok
+          {
+            def_id;
+            meta;
+            attr_info;
+            is_local;
+            name;
+            generics;
+            variances;
+            preds;
+            kind;
+            repr;
+            drop_glue;
+            layout;
+            auto_trait_impls;
+          }
     | _ -> Error "")
 
 let var_of_json (js : json) : (var, string) result =
@@ -680,10 +1032,22 @@ let cast_kind_of_json (js : json) : (cast_kind, string) result =
         let* src_ty = literal_type_of_json src_ty in
         let* tgt_ty = literal_type_of_json tgt_ty in
         Ok (CastScalar (src_ty, tgt_ty))
-    | `Assoc [ ("FnPtr", `List [ src_ty; tgt_ty ]) ] ->
+    | `Assoc [ ("Unsize", `List [ src_ty; tgt_ty; vtable ]) ] ->
         let* src_ty = ty_of_json src_ty in
         let* tgt_ty = ty_of_json tgt_ty in
-        Ok (CastFnPtr (src_ty, tgt_ty))
+        let* vtable = option_of_json trait_ref_of_json vtable in
+        Ok (CastUnsize (src_ty, tgt_ty, vtable))
+    | `Assoc [ (kind, `List [ src_ty; tgt_ty ]) ] -> (
+        let* src_ty = ty_of_json src_ty in
+        let* tgt_ty = ty_of_json tgt_ty in
+        match kind with
+        | "ReifyFnPointer" -> Ok (CastReifyFnPointer (src_ty, tgt_ty))
+        | "ClosureFnPointer" -> Ok (CastClosureFnPointer (src_ty, tgt_ty))
+        | "PtrToPtr" -> Ok (CastPtrToPtr (src_ty, tgt_ty))
+        | "PtrToInt" -> Ok (CastPtrToInt (src_ty, tgt_ty))
+        | "IntToPtr" -> Ok (CastIntToPtr (src_ty, tgt_ty))
+        | "Transmute" -> Ok (CastTransmute (src_ty, tgt_ty))
+        | _ -> Error "")
     | _ -> Error "")
 
 let unop_of_json (js : json) : (unop, string) result =
@@ -714,6 +1078,9 @@ let binop_of_json (js : json) : (binop, string) result =
   | `String "Mul" -> Ok Mul
   | `String "Shl" -> Ok Shl
   | `String "Shr" -> Ok Shr
+  | `String "And" -> Ok And
+  | `String "Or" -> Ok Or
+  | `String "Offset" -> Ok Offset
   | _ -> Error ("binop_of_json failed on:" ^ show js)
 
 let literal_of_json (js : json) : (literal, string) result =
@@ -728,6 +1095,12 @@ let literal_of_json (js : json) : (literal, string) result =
     | `Assoc [ ("Char", v) ] ->
         let* v = char_of_json v in
         Ok (VChar v)
+    | `Assoc [ ("Str", v) ] ->
+        let* v = string_of_json v in
+        Ok (VStr v)
+    | `Assoc [ ("ByteStr", v) ] ->
+        let* v = list_of_json int_of_json v in
+        Ok (VByteStr v)
     | _ -> Error "")
 
 let assumed_fun_id_of_json (js : json) : (assumed_fun_id, string) result =
@@ -741,6 +1114,21 @@ let assumed_fun_id_of_json (js : json) : (assumed_fun_id, string) result =
   | `String "ArrayRepeat" -> Ok ArrayRepeat
   | `String "SliceIndexShared" -> Ok SliceIndexShared
   | `String "SliceIndexMut" -> Ok SliceIndexMut
+  | `String "VecNew" -> Ok VecNew
+  | `String "VecPush" -> Ok VecPush
+  | `String "VecLen" -> Ok VecLen
+  | `String "VecIndexShared" -> Ok VecIndexShared
+  | `String "VecIndexMut" -> Ok VecIndexMut
+  | `String "OptionUnwrap" -> Ok OptionUnwrap
+  | `String "OptionIsSome" -> Ok OptionIsSome
+  | `String "MemSwap" -> Ok MemSwap
+  | `String "MemReplace" -> Ok MemReplace
+  | `String "MemTake" -> Ok MemTake
+  | `String "Transmute" -> Ok Transmute
+  | `String "CopyNonOverlapping" -> Ok CopyNonOverlapping
+  | `String "DiscriminantValue" -> Ok DiscriminantValue
+  | `String "SizeOfVal" -> Ok SizeOfVal
+  | `String "Fmt" -> Ok Fmt
   | _ -> Error ("assumed_fun_id_of_json failed on:" ^ show js)
 
 let fun_id_of_json (js : json) : (fun_id, string) result =
@@ -794,6 +1182,11 @@ let fn_operand_of_json (js : json) : (fn_operand, string) result =
     | `Assoc [ ("Move", p) ] ->
         let* p = place_of_json p in
         Ok (FnOpMove p)
+    | `Assoc [ ("Virtual", `List [ trait_ref; method_name; generics ]) ] ->
+        let* trait_ref = trait_decl_ref_of_json trait_ref in
+        let* method_name = string_of_json method_name in
+        let* generics = generic_args_of_json generics in
+        Ok (FnOpVirtual (trait_ref, method_name, generics))
     | _ -> Error "")
 
 let rec constant_expr_of_json (js : json) : (constant_expr, string) result =
@@ -822,6 +1215,9 @@ and raw_constant_expr_of_json (js : json) : (raw_constant_expr, string) result =
     | `Assoc [ ("FnPtr", fn_ptr) ] ->
         let* fn_ptr = fn_ptr_of_json fn_ptr in
         Ok (CFnPtr fn_ptr)
+    | `Assoc [ ("GlobalDeclRef", gid) ] ->
+        let* gid = GlobalDeclId.id_of_json gid in
+        Ok (CGlobalDeclRef gid)
     | _ -> Error "")
 
 let operand_of_json (js : json) : (operand, string) result =
@@ -856,6 +1252,15 @@ let aggregate_kind_of_json (js : json) : (aggregate_kind, string) result =
         let* fid = FunDeclId.id_of_json fid in
         let* generics = generic_args_of_json generics in
         Ok (AggregatedClosure (fid, generics))
+    | `Assoc [ ("Union", `List [ id; field_id; generics ]) ] ->
+        let* id = TypeDeclId.id_of_json id in
+        let* field_id = FieldId.id_of_json field_id in
+        let* generics = generic_args_of_json generics in
+        Ok (AggregatedUnion (id, field_id, generics))
+    | `Assoc [ ("Generator", `List [ fid; generics ]) ] ->
+        let* fid = FunDeclId.id_of_json fid in
+        let* generics = generic_args_of_json generics in
+        Ok (AggregatedGenerator (fid, generics))
     | _ -> Error "")
 
 let rvalue_of_json (js : json) : (rvalue, string) result =
@@ -868,6 +1273,10 @@ let rvalue_of_json (js : json) : (rvalue, string) result =
         let* place = place_of_json place in
         let* borrow_kind = borrow_kind_of_json borrow_kind in
         Ok (RvRef (place, borrow_kind))
+    | `Assoc [ ("AddressOf", `List [ place; ref_kind ]) ] ->
+        let* place = place_of_json place in
+        let* ref_kind = ref_kind_of_json ref_kind in
+        Ok (AddressOf (place, ref_kind))
     | `Assoc [ ("UnaryOp", `List [ unop; op ]) ] ->
         let* unop = unop_of_json unop in
         let* op = operand_of_json op in
@@ -877,6 +1286,10 @@ let rvalue_of_json (js : json) : (rvalue, string) result =
         let* op1 = operand_of_json op1 in
         let* op2 = operand_of_json op2 in
         Ok (BinaryOp (binop, op1, op2))
+    | `Assoc [ ("Nullary", `List [ nullop; ty ]) ] ->
+        let* nullop = nullop_of_json nullop in
+        let* ty = ty_of_json ty in
+        Ok (Nullary (nullop, ty))
     | `Assoc [ ("Discriminant", `List [ place; adt_id ]) ] ->
         let* place = place_of_json place in
         let* adt_id = TypeDeclId.id_of_json adt_id in
@@ -888,6 +1301,84 @@ let rvalue_of_json (js : json) : (rvalue, string) result =
         let* aggregate_kind = aggregate_kind_of_json aggregate_kind in
         let* ops = list_of_json operand_of_json ops in
         Ok (Aggregate (aggregate_kind, ops))
+    | `Assoc [ ("Repeat", `List [ op; ty; cg ]) ] ->
+        let* op = operand_of_json op in
+        let* ty = ty_of_json ty in
+        let* cg = const_generic_of_json cg in
+        Ok (Repeat (op, ty, cg))
+    | `Assoc [ ("ShallowInitBox", `List [ op; ty ]) ] ->
+        let* op = operand_of_json op in
+        let* ty = ty_of_json ty in
+        Ok (ShallowInitBox (op, ty))
+    | _ -> Error "")
+
+let rec pure_expr_of_json (js : json) : (pure_expr, string) result =
+  combine_error_msgs js __FUNCTION__
+    (match js with
+    | `Assoc [ ("Var", id) ] ->
+        let* id = VarId.id_of_json id in
+        Ok (PVar id)
+    | `Assoc [ ("Const", cv) ] ->
+        let* cv = constant_expr_of_json cv in
+        Ok (PConst cv)
+    | `Assoc [ ("Global", gid) ] ->
+        let* gid = GlobalDeclId.id_of_json gid in
+        Ok (PGlobal gid)
+    | `Assoc [ ("Field", `List [ proj_kind; field_id; e ]) ] ->
+        let* proj_kind = field_proj_kind_of_json proj_kind in
+        let* field_id = FieldId.id_of_json field_id in
+        let* e = pure_expr_of_json e in
+        Ok (PField (proj_kind, field_id, e))
+    | `Assoc [ ("UnaryOp", `List [ unop; e ]) ] ->
+        let* unop = unop_of_json unop in
+        let* e = pure_expr_of_json e in
+        Ok (PUnaryOp (unop, e))
+    | `Assoc [ ("BinaryOp", `List [ binop; e1; e2 ]) ] ->
+        let* binop = binop_of_json binop in
+        let* e1 = pure_expr_of_json e1 in
+        let* e2 = pure_expr_of_json e2 in
+        Ok (PBinaryOp (binop, e1, e2))
+    | `Assoc [ ("Aggregate", `List [ aggregate_kind; es ]) ] ->
+        let* aggregate_kind = aggregate_kind_of_json aggregate_kind in
+        let* es = list_of_json pure_expr_of_json es in
+        Ok (PAggregate (aggregate_kind, es))
+    | `Assoc [ ("App", `List [ fn_ptr; args ]) ] ->
+        let* fn_ptr = fn_ptr_of_json fn_ptr in
+        let* args = list_of_json pure_expr_of_json args in
+        Ok (PApp (fn_ptr, args))
+    | `Assoc [ ("Let", `List [ id; e1; e2 ]) ] ->
+        let* id = VarId.id_of_json id in
+        let* e1 = pure_expr_of_json e1 in
+        let* e2 = pure_expr_of_json e2 in
+        Ok (PLet (id, e1, e2))
+    | `Assoc [ ("Ite", `List [ e0; e1; e2 ]) ] ->
+        let* e0 = pure_expr_of_json e0 in
+        let* e1 = pure_expr_of_json e1 in
+        let* e2 = pure_expr_of_json e2 in
+        Ok (PIte (e0, e1, e2))
+    | `Assoc [ ("SwitchInt", `List [ e; int_ty; branches; otherwise ]) ] ->
+        let* e = pure_expr_of_json e in
+        let* int_ty = integer_type_of_json int_ty in
+        let* branches =
+          list_of_json
+            (pair_of_json
+               (list_of_json switch_value_of_json)
+               pure_expr_of_json)
+            branches
+        in
+        let* otherwise = pure_expr_of_json otherwise in
+        Ok (PSwitchInt (e, int_ty, branches, otherwise))
+    | `Assoc [ ("Match", `List [ e; branches; otherwise ]) ] ->
+        let* e = pure_expr_of_json e in
+        let* branches =
+          list_of_json
+            (pair_of_json
+               (list_of_json VariantId.id_of_json)
+               pure_expr_of_json)
+            branches
+        in
+        let* otherwise = option_of_json pure_expr_of_json otherwise in
+        Ok (PMatch (e, branches, otherwise))
     | _ -> Error "")
 
 let params_info_of_json (js : json) : (params_info, string) result =
@@ -924,14 +1415,6 @@ let params_info_of_json (js : json) : (params_info, string) result =
           }
     | _ -> Error "")
 
-let closure_kind_of_json (js : json) : (closure_kind, string) result =
-  combine_error_msgs js __FUNCTION__
-    (match js with
-    | `String "Fn" -> Ok Fn
-    | `String "FnMut" -> Ok FnMut
-    | `String "FnOnce" -> Ok FnOnce
-    | _ -> Error "")
-
 let closure_info_of_json (js : json) : (closure_info, string) result =
   combine_error_msgs js __FUNCTION__
     (match js with
@@ -948,6 +1431,10 @@ let fun_sig_of_json (id_to_file : id_to_file_map) (js : json) :
     | `Assoc
         [
           ("is_unsafe", is_unsafe);
+          ("is_const", is_const);
+          ("is_async", is_async);
+          ("abi", abi);
+          ("is_variadic", is_variadic);
           ("is_closure", is_closure);
           ("closure_info", closure_info);
           ("generics", generics);
@@ -957,6 +1444,10 @@ let fun_sig_of_json (id_to_file : id_to_file_map) (js : json) :
           ("output", output);
         ] ->
         let* is_unsafe = bool_of_json is_unsafe in
+        let* is_const = bool_of_json is_const in
+        let* is_async = bool_of_json is_async in
+        let* abi = string_of_json abi in
+        let* is_variadic = bool_of_json is_variadic in
         let* is_closure = bool_of_json is_closure in
         let* closure_info = option_of_json closure_info_of_json closure_info in
 
@@ -970,6 +1461,10 @@ let fun_sig_of_json (id_to_file : id_to_file_map) (js : json) :
         Ok
           {
             is_unsafe;
+            is_const;
+            is_async;
+            abi;
+            is_variadic;
             is_closure;
             closure_info;
             generics;
@@ -990,6 +1485,45 @@ let call_of_json (js : json) : (call, string) result =
         Ok { func; args; dest }
     | _ -> Error "")
 
+let assert_kind_of_json (js : json) : (assert_kind, string) result =
+  combine_error_msgs js __FUNCTION__
+    (match js with
+    | `Assoc
+        [ ("IndexOutOfBounds", `Assoc [ ("len", len); ("index", index) ]) ] ->
+        let* len = operand_of_json len in
+        let* index = operand_of_json index in
+        Ok (IndexOutOfBounds (len, index))
+    | `Assoc [ ("Overflow", binop) ] ->
+        let* binop = binop_of_json binop in
+        Ok (Overflow binop)
+    | `String "OverflowNeg" -> Ok OverflowNeg
+    | `String "DivisionByZero" -> Ok DivisionByZero
+    | `String "RemainderByZero" -> Ok RemainderByZero
+    | `String "ResumedGenerator" -> Ok ResumedGenerator
+    | `String "MisalignedPointerDereference" -> Ok MisalignedPointerDereference
+    | `String "User" -> Ok User
+    | _ -> Error "")
+
+let var_debug_info_of_json (id_to_file : id_to_file_map) (js : json) :
+    (var_debug_info, string) result =
+  combine_error_msgs js __FUNCTION__
+    (match js with
+    | `Assoc
+        [
+          ("name", name);
+          ("span", span);
+          ("scope", scope);
+          ("var", var);
+          ("argument_index", argument_index);
+        ] ->
+        let* name = string_of_json name in
+        let* span = span_of_json id_to_file span in
+        let* scope = span_of_json id_to_file scope in
+        let* var = option_of_json VarId.id_of_json var in
+        let* argument_index = option_of_json int_of_json argument_index in
+        Ok { name; span; scope; var; argument_index }
+    | _ -> Error "")
+
 let gexpr_body_of_json (body_of_json : json -> ('body, string) result)
     (id_to_file : id_to_file_map) (js : json) :
     ('body gexpr_body, string) result =
@@ -1000,19 +1534,30 @@ let gexpr_body_of_json (body_of_json : json -> ('body, string) result)
           ("meta", meta);
           ("arg_count", arg_count);
           ("locals", locals);
+          ("var_debug_info", var_debug_info);
+          ("backtraces", backtraces);
           ("body", body);
         ] ->
         let* meta = meta_of_json id_to_file meta in
         let* arg_count = int_of_json arg_count in
         let* locals = list_of_json var_of_json locals in
+        let* var_debug_info =
+          list_of_json (var_debug_info_of_json id_to_file) var_debug_info
+        in
+        let* backtraces =
+          list_of_json (list_of_json (span_of_json id_to_file)) backtraces
+        in
         let* body = body_of_json body in
-        Ok { meta; arg_count; locals; body }
+        Ok { meta; arg_count; locals; var_debug_info; backtraces; body }
     | _ -> Error "")
 
 let fun_kind_of_json (js : json) : (fun_kind, string) result =
   combine_error_msgs js __FUNCTION__
     (match js with
     | `String "Regular" -> Ok RegularKind
+    | `Assoc [ ("InherentMethod", impl_id) ] ->
+        let* impl_id = ImplBlockId.id_of_json impl_id in
+        Ok (InherentMethod impl_id)
     | `Assoc
         [
           ( "TraitMethodImpl",
@@ -1037,6 +1582,17 @@ let fun_kind_of_json (js : json) : (fun_kind, string) result =
         let* trait_id = TraitDeclId.id_of_json trait_id in
         let* item_name = string_of_json item_name in
         Ok (TraitMethodProvided (trait_id, item_name))
+    | `String "Foreign" -> Ok ForeignKind
+    | _ -> Error "")
+
+let call_depth_of_json (js : json) : (call_depth, string) result =
+  combine_error_msgs js __FUNCTION__
+    (match js with
+    | `Assoc [ ("Bounded", n) ] ->
+        let* n = int_of_json n in
+        Ok (BoundedDepth n)
+    | `String "Unbounded" -> Ok UnboundedDepth
+    | `String "Unknown" -> Ok UnknownDepth
     | _ -> Error "")
 
 let gfun_decl_of_json (body_of_json : json -> ('body, string) result)
@@ -1048,14 +1604,18 @@ let gfun_decl_of_json (body_of_json : json -> ('body, string) result)
         [
           ("def_id", def_id);
           ("meta", meta);
+          ("attr_info", attr_info);
           ("is_local", is_local);
           ("name", name);
           ("signature", signature);
           ("kind", kind);
           ("body", body);
+          ("pure_form", pure_form);
+          ("call_depth", call_depth);
         ] ->
         let* def_id = FunDeclId.id_of_json def_id in
         let* meta = meta_of_json id_to_file meta in
+        let* attr_info = attr_info_of_json attr_info in
         let* is_local = bool_of_json is_local in
         let* name = name_of_json id_to_file name in
         let* signature = fun_sig_of_json id_to_file signature in
@@ -1063,19 +1623,38 @@ let gfun_decl_of_json (body_of_json : json -> ('body, string) result)
         let* body =
           option_of_json (gexpr_body_of_json body_of_json id_to_file) body
         in
+        let* pure_form = option_of_json pure_expr_of_json pure_form in
+        let* call_depth = option_of_json call_depth_of_json call_depth in
         Ok
           {
             def_id;
             meta;
+            attr_info;
             is_local;
             name;
             signature;
             kind;
             body;
+            pure_form;
+            call_depth;
             is_global_decl_body = false;
           }
     | _ -> Error "")
 
+let global_kind_of_json (js : json) : (global_kind, string) result =
+  combine_error_msgs js __FUNCTION__
+    (match js with
+    | `String "Const" -> Ok ConstGlobal
+    | `Assoc
+        [
+          ( "Static",
+            `Assoc [ ("is_mut", is_mut); ("interior_mut", interior_mut) ] );
+        ] ->
+        let* is_mut = bool_of_json is_mut in
+        let* interior_mut = bool_of_json interior_mut in
+        Ok (StaticGlobal { is_mut; interior_mut })
+    | _ -> Error "")
+
 let gglobal_decl_of_json (body_of_json : json -> ('body, string) result)
     (id_to_file : id_to_file_map) (js : json) :
     ('body gexpr_body option gglobal_decl, string) result =
@@ -1085,20 +1664,40 @@ let gglobal_decl_of_json (body_of_json : json -> ('body, string) result)
         [
           ("def_id", def_id);
           ("meta", meta);
+          ("attr_info", attr_info);
           ("is_local", is_local);
           ("name", name);
+          ("parent_params_info", parent_params_info);
           ("ty", ty);
+          ("kind", kind);
           ("body", body);
         ] ->
         let* global_id = GlobalDeclId.id_of_json def_id in
         let* meta = meta_of_json id_to_file meta in
+        let* attr_info = attr_info_of_json attr_info in
         let* is_local = bool_of_json is_local in
         let* name = name_of_json id_to_file name in
+        let* parent_params_info =
+          option_of_json params_info_of_json parent_params_info
+        in
         let* ty = ty_of_json ty in
+        let* kind = global_kind_of_json kind in
         let* body =
           option_of_json (gexpr_body_of_json body_of_json id_to_file) body
         in
-        let global = { def_id = global_id; meta; body; is_local; name; ty } in
+        let global =
+          {
+            def_id = global_id;
+            meta;
+            attr_info;
+            body;
+            is_local;
+            name;
+            parent_params_info;
+            ty;
+            kind;
+          }
+        in
         Ok global
     | _ -> Error "")
 
@@ -1112,6 +1711,8 @@ let trait_decl_of_json (id_to_file : id_to_file_map) (js : json) :
           ("is_local", is_local);
           ("name", name);
           ("meta", meta);
+          ("attr_info", attr_info);
+          ("is_auto", is_auto);
           ("generics", generics);
           ("preds", preds);
           ("parent_clauses", parent_clauses);
@@ -1124,6 +1725,8 @@ let trait_decl_of_json (id_to_file : id_to_file_map) (js : json) :
         let* is_local = bool_of_json is_local in
         let* name = name_of_json id_to_file name in
         let* meta = meta_of_json id_to_file meta in
+        let* attr_info = attr_info_of_json attr_info in
+        let* is_auto = bool_of_json is_auto in
         let* generics = generic_params_of_json id_to_file generics in
         let* preds = predicates_of_json preds in
         let* parent_clauses =
@@ -1160,6 +1763,8 @@ let trait_decl_of_json (id_to_file : id_to_file_map) (js : json) :
             is_local;
             name;
             meta;
+            attr_info;
+            is_auto;
             generics;
             preds;
             parent_clauses;
@@ -1180,7 +1785,12 @@ let trait_impl_of_json (id_to_file : id_to_file_map) (js : json) :
           ("is_local", is_local);
           ("name", name);
           ("meta", meta);
+          ("attr_info", attr_info);
           ("impl_trait", impl_trait);
+          ("self_ty", self_ty);
+          ("header", header);
+          ("is_negative", is_negative);
+          ("is_blanket", is_blanket);
           ("generics", generics);
           ("preds", preds);
           ("parent_trait_refs", parent_trait_refs);
@@ -1193,7 +1803,12 @@ let trait_impl_of_json (id_to_file : id_to_file_map) (js : json) :
         let* is_local = bool_of_json is_local in
         let* name = name_of_json id_to_file name in
         let* meta = meta_of_json id_to_file meta in
+        let* attr_info = attr_info_of_json attr_info in
         let* impl_trait = trait_decl_ref_of_json impl_trait in
+        let* self_ty = ty_of_json self_ty in
+        let* header = string_option_of_json header in
+        let* is_negative = bool_of_json is_negative in
+        let* is_blanket = bool_of_json is_blanket in
         let* generics = generic_params_of_json id_to_file generics in
         let* preds = predicates_of_json preds in
         let* parent_trait_refs =
@@ -1222,7 +1837,12 @@ let trait_impl_of_json (id_to_file : id_to_file_map) (js : json) :
              is_local;
              name;
              meta;
+             attr_info;
              impl_trait;
+             self_ty;
+             header;
+             is_negative;
+             is_blanket;
              generics;
              preds;
              parent_trait_refs;
@@ -1234,6 +1854,57 @@ let trait_impl_of_json (id_to_file : id_to_file_map) (js : json) :
             : trait_impl)
     | _ -> Error "")
 
+let impl_block_of_json (id_to_file : id_to_file_map) (js : json) :
+    (impl_block, string) result =
+  combine_error_msgs js __FUNCTION__
+    (match js with
+    | `Assoc
+        [
+          ("def_id", def_id);
+          ("is_local", is_local);
+          ("name", name);
+          ("meta", meta);
+          ("attr_info", attr_info);
+          ("self_ty", self_ty);
+          ("generics", generics);
+          ("preds", preds);
+          ("methods", methods);
+        ] ->
+        let* def_id = ImplBlockId.id_of_json def_id in
+        let* is_local = bool_of_json is_local in
+        let* name = name_of_json id_to_file name in
+        let* meta = meta_of_json id_to_file meta in
+        let* attr_info = attr_info_of_json attr_info in
+        let* self_ty = ty_of_json self_ty in
+        let* generics = generic_params_of_json id_to_file generics in
+        let* preds = predicates_of_json preds in
+        let* methods = list_of_json FunDeclId.id_of_json methods in
+        Ok
+          ({
+             def_id;
+             is_local;
+             name;
+             meta;
+             attr_info;
+             self_ty;
+             generics;
+             preds;
+             methods;
+           }
+            : impl_block)
+    | _ -> Error "")
+
+let opaque_warning_of_json (id_to_file : id_to_file_map) (js : json) :
+    (opaque_warning, string) result =
+  combine_error_msgs js __FUNCTION__
+    (match js with
+    | `Assoc [ ("name", name); ("span", span); ("reason", reason) ] ->
+        let* name = name_of_json id_to_file name in
+        let* span = span_of_json id_to_file span in
+        let* reason = string_of_json reason in
+        Ok ({ name; span; reason } : opaque_warning)
+    | _ -> Error "")
+
 let g_declaration_group_of_json (id_of_json : json -> ('id, string) result)
     (js : json) : ('id g_declaration_group, string) result =
   combine_error_msgs js __FUNCTION__
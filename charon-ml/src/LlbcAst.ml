@@ -18,24 +18,65 @@ and raw_statement =
   | Call of call
   | Panic
   | Return
-  | Break of int
+  | Break of int * int option
       (** Break to (outer) loop. The [int] identifies the loop to break to:
           * 0: break to the first outer loop (the current loop)
           * 1: break to the second outer loop
           * ...
+          If we break to an outer loop which is not the current loop, we also
+          give the label of this loop (see {!Loop}).
           *)
-  | Continue of int
-      (** Continue to (outer) loop. The loop identifier works
+  | Continue of int * int option
+      (** Continue to (outer) loop. The loop identifier and the label work
           the same way as for {!Break} *)
   | Nop
-  | Sequence of statement * statement
+  | Block of statement list
+      (** A block of statements, executed in sequence. The statements of a
+          block are never blocks (the nested blocks are flattened). *)
   | Switch of switch
-  | Loop of statement
+  | Loop of statement * int option
+      (** A loop, with an optional label. We introduce labels only for the
+          loops which are the targets of a [break] or a [continue] exiting
+          several loops at once. *)
+  | ForLoop of for_loop
+      (** A [for] loop. Those are introduced by the (optional)
+          [reconstruct_for_loops] micro-pass. *)
+  | QuestionMark of question_mark
+      (** An application of the [?] operator. Those are introduced by the
+          (optional) [reconstruct_question_marks] micro-pass. *)
+
+(** A [for] loop, reconstructed from its desugaring (with calls to
+    [IntoIterator::into_iter] and [Iterator::next]). *)
+and for_loop = {
+  iter : operand;  (** The value we iterate over *)
+  iter_var : var_id;  (** The local variable which holds the iterator *)
+  into_iter : fn_ptr;
+      (** The implementation of [IntoIterator::into_iter] we call *)
+  next : fn_ptr;  (** The implementation of [Iterator::next] we call *)
+  binder : place option;
+      (** The place in which we store the items ([None] if the items are
+          ignored) *)
+  body : statement;
+  label : int option;
+}
+
+(** An application of the [?] operator, reconstructed from its desugaring
+    (with calls to [Try::branch] and [FromResidual::from_residual]). *)
+and question_mark = {
+  op : operand;  (** The operand we apply [?] to *)
+  branch : fn_ptr;  (** The implementation of [Try::branch] we call *)
+  from_residual : fn_ptr;
+      (** The implementation of [FromResidual::from_residual] we call when
+          we return early *)
+  dest : place option;
+      (** The place in which we store the output ([None] if the output is
+          ignored) *)
+}
 
 and switch =
   | If of operand * statement * statement
   | SwitchInt of
-      operand * integer_type * (scalar_value list * statement) list * statement
+      operand * integer_type * (switch_value list * statement) list * statement
       (** The targets for a switch over an integer are:
           - the list [(matched values, statement to execute)]
             We need a list for the matched values in case we do something like this:
@@ -7,7 +7,8 @@ let statement_has_loops (st : statement) : bool =
   let obj =
     object
       inherit [_] iter_statement
-      method! visit_Loop _ _ = raise Found
+      method! visit_Loop _ _ _ = raise Found
+      method! visit_ForLoop _ _ = raise Found
     end
   in
   try
@@ -21,10 +22,14 @@ let fun_decl_has_loops (fd : fun_decl) : bool =
   | Some body -> statement_has_loops body.body
   | None -> false
 
-(** Create a sequence *)
+(** The statements of a block, or the statement itself if it is not a block *)
+let block_statements (st : statement) : statement list =
+  match st.content with Block sts -> sts | _ -> [ st ]
+
+(** Create a sequence (flattening the blocks) *)
 let mk_sequence (st1 : statement) (st2 : statement) : statement =
   let meta = MetaUtils.combine_meta st1.meta st2.meta in
-  let content = Sequence (st1, st2) in
+  let content = Block (block_statements st1 @ block_statements st2) in
   { meta; content }
 
 (** Chain two statements into a sequence, by pushing the second statement
@@ -33,7 +38,7 @@ let mk_sequence (st1 : statement) (st2 : statement) : statement =
 let rec chain_statements (st1 : statement) (st2 : statement) : statement =
   match st1.content with
   | SetDiscriminant _ | Assert _ | Call _ | Assign _ | FakeRead _ | Drop _
-  | Loop _ ->
+  | Loop _ | ForLoop _ | QuestionMark _ ->
       (* Simply create a sequence *)
       mk_sequence st1 st2
   | Nop -> (* Ignore the nop *) st2
@@ -44,9 +49,15 @@ let rec chain_statements (st1 : statement) (st2 : statement) : statement =
       let meta = MetaUtils.combine_meta st1.meta st2.meta in
       let content = Switch (chain_statements_in_switch switch st2) in
       { meta; content }
-  | Sequence (st3, st4) ->
-      (* Insert at the end of the statement *)
-      mk_sequence st3 (chain_statements st4 st2)
+  | Block sts -> (
+      (* Insert at the end of the block *)
+      match List.rev sts with
+      | [] -> st2
+      | last :: firsts ->
+          let last = chain_statements last st2 in
+          let meta = MetaUtils.combine_meta st1.meta st2.meta in
+          let content = Block (List.rev firsts @ block_statements last) in
+          { meta; content })
 
 and chain_statements_in_switch (switch : switch) (st : statement) : switch =
   match switch with
@@ -11,10 +11,11 @@ open LlbcAst
 let assertion_of_json (js : json) : (assertion, string) result =
   combine_error_msgs js __FUNCTION__
     (match js with
-    | `Assoc [ ("cond", cond); ("expected", expected) ] ->
+    | `Assoc [ ("cond", cond); ("expected", expected); ("kind", kind) ] ->
         let* cond = operand_of_json cond in
         let* expected = bool_of_json expected in
-        Ok { cond; expected }
+        let* kind = assert_kind_of_json kind in
+        Ok { cond; expected; kind }
     | _ -> Error "")
 
 let rec statement_of_json (id_to_file : id_to_file_map) (js : json) :
@@ -53,23 +54,72 @@ and raw_statement_of_json (id_to_file : id_to_file_map) (js : json) :
         Ok (Call call)
     | `String "Panic" -> Ok Panic
     | `String "Return" -> Ok Return
-    | `Assoc [ ("Break", i) ] ->
+    | `Assoc [ ("Break", `List [ i; label ]) ] ->
         let* i = int_of_json i in
-        Ok (Break i)
-    | `Assoc [ ("Continue", i) ] ->
+        let* label = option_of_json int_of_json label in
+        Ok (Break (i, label))
+    | `Assoc [ ("Continue", `List [ i; label ]) ] ->
         let* i = int_of_json i in
-        Ok (Continue i)
+        let* label = option_of_json int_of_json label in
+        Ok (Continue (i, label))
     | `String "Nop" -> Ok Nop
-    | `Assoc [ ("Sequence", `List [ st1; st2 ]) ] ->
-        let* st1 = statement_of_json id_to_file st1 in
-        let* st2 = statement_of_json id_to_file st2 in
-        Ok (Sequence (st1, st2))
+    | `Assoc [ ("Block", sts) ] ->
+        let* sts = list_of_json (statement_of_json id_to_file) sts in
+        Ok (Block sts)
     | `Assoc [ ("Switch", tgt) ] ->
         let* switch = switch_of_json id_to_file tgt in
         Ok (Switch switch)
-    | `Assoc [ ("Loop", st) ] ->
+    | `Assoc [ ("Loop", `List [ st; label ]) ] ->
         let* st = statement_of_json id_to_file st in
-        Ok (Loop st)
+        let* label = option_of_json int_of_json label in
+        Ok (Loop (st, label))
+    | `Assoc [ ("ForLoop", for_loop) ] ->
+        let* for_loop = for_loop_of_json id_to_file for_loop in
+        Ok (ForLoop for_loop)
+    | `Assoc [ ("QuestionMark", question_mark) ] ->
+        let* question_mark = question_mark_of_json question_mark in
+        Ok (QuestionMark question_mark)
+    | _ -> Error "")
+
+and for_loop_of_json (id_to_file : id_to_file_map) (js : json) :
+    (for_loop, string) result =
+  combine_error_msgs js __FUNCTION__
+    (match js with
+    | `Assoc
+        [
+          ("iter", iter);
+          ("iter_var", iter_var);
+          ("into_iter", into_iter);
+          ("next", next);
+          ("binder", binder);
+          ("body", body);
+          ("label", label);
+        ] ->
+        let* iter = operand_of_json iter in
+        let* iter_var = Expressions.VarId.id_of_json iter_var in
+        let* into_iter = fn_ptr_of_json into_iter in
+        let* next = fn_ptr_of_json next in
+        let* binder = option_of_json place_of_json binder in
+        let* body = statement_of_json id_to_file body in
+        let* label = option_of_json int_of_json label in
+        Ok { iter; iter_var; into_iter; next; binder; body; label }
+    | _ -> Error "")
+
+and question_mark_of_json (js : json) : (question_mark, string) result =
+  combine_error_msgs js __FUNCTION__
+    (match js with
+    | `Assoc
+        [
+          ("op", op);
+          ("branch", branch);
+          ("from_residual", from_residual);
+          ("dest", dest);
+        ] ->
+        let* op = operand_of_json op in
+        let* branch = fn_ptr_of_json branch in
+        let* from_residual = fn_ptr_of_json from_residual in
+        let* dest = option_of_json place_of_json dest in
+        Ok { op; branch; from_residual; dest }
     | _ -> Error "")
 
 and switch_of_json (id_to_file : id_to_file_map) (js : json) :
@@ -87,7 +137,7 @@ and switch_of_json (id_to_file : id_to_file_map) (js : json) :
         let* tgts =
           list_of_json
             (pair_of_json
-               (list_of_json scalar_value_of_json)
+               (list_of_json switch_value_of_json)
                (statement_of_json id_to_file))
             tgts
         in
@@ -134,24 +184,48 @@ let global_decl_of_json (id_to_file : id_to_file_map) (js : json)
      let* global =
        gglobal_decl_of_json (statement_of_json id_to_file) id_to_file js
      in
-     let { def_id = global_id; meta; body; is_local; name; ty } = global in
+     let {
+       def_id = global_id;
+       meta;
+       body;
+       is_local;
+       name;
+       parent_params_info;
+       ty;
+       kind;
+     } =
+       global
+     in
      (* Decompose into a global and a function *)
      let fun_id = global_to_fun_id gid_conv global.def_id in
      let signature : fun_sig =
        {
          (* Not sure about `is_unsafe` actually *)
          is_unsafe = false;
+         is_const = false;
+         is_async = false;
+         abi = "Rust";
+         is_variadic = false;
          is_closure = false;
          closure_info = None;
          generics = TypesUtils.empty_generic_params;
          preds = TypesUtils.empty_predicates;
-         parent_params_info = None;
+         parent_params_info;
          inputs = [];
          output = ty;
        }
      in
      let global_decl : global_decl =
-       { def_id = global_id; meta; body = fun_id; is_local; name; ty }
+       {
+         def_id = global_id;
+         meta;
+         body = fun_id;
+         is_local;
+         name;
+         parent_params_info;
+         ty;
+         kind;
+       }
      in
      let fun_decl : fun_decl =
        {
@@ -162,17 +236,23 @@ let global_decl_of_json (id_to_file : id_to_file_map) (js : json)
          signature;
          kind = RegularKind;
          body;
+         pure_form = None;
+         call_depth = None;
          is_global_decl_body = true;
        }
      in
      Ok (global_decl, fun_decl))
 
 let crate_of_json (js : json) : (crate, string) result =
+  let* header = crate_header_of_json js in
   combine_error_msgs js __FUNCTION__
     (match js with
     | `Assoc
         [
+          ("header", _);
           ("name", name);
+          ("prelude", prelude);
+          ("target_info", target_info);
           ("id_to_file", id_to_file);
           ("declarations", declarations);
           ("types", types);
@@ -180,10 +260,15 @@ let crate_of_json (js : json) : (crate, string) result =
           ("globals", globals);
           ("trait_decls", trait_decls);
           ("trait_impls", trait_impls);
+          ("impl_blocks", impl_blocks);
+          ("opaque_warnings", opaque_warnings);
+          ("index", _);
         ] ->
         (* We first deserialize the declaration groups (which simply contain ids)
          * and all the declarations *butù* the globals *)
         let* name = string_of_json name in
+        let* prelude = prelude_of_json prelude in
+        let* target_info = target_info_of_json target_info in
         let* id_to_file = id_to_file_of_json id_to_file in
         let* declarations =
           list_of_json declaration_group_of_json declarations
@@ -224,6 +309,9 @@ let crate_of_json (js : json) : (crate, string) result =
         let* trait_impls =
           list_of_json (trait_impl_of_json id_to_file) trait_impls
         in
+        let* impl_blocks =
+          list_of_json (impl_block_of_json id_to_file) impl_blocks
+        in
         let trait_decls =
           TraitDeclId.Map.of_list
             (List.map (fun (d : trait_decl) -> (d.def_id, d)) trait_decls)
@@ -232,14 +320,26 @@ let crate_of_json (js : json) : (crate, string) result =
           TraitImplId.Map.of_list
             (List.map (fun (d : trait_impl) -> (d.def_id, d)) trait_impls)
         in
+        let impl_blocks =
+          ImplBlockId.Map.of_list
+            (List.map (fun (d : impl_block) -> (d.def_id, d)) impl_blocks)
+        in
+        let* opaque_warnings =
+          list_of_json (opaque_warning_of_json id_to_file) opaque_warnings
+        in
         Ok
           {
+            header;
             name;
+            prelude;
+            target_info;
             declarations;
             type_decls;
             fun_decls;
             global_decls;
             trait_decls;
             trait_impls;
+            impl_blocks;
+            opaque_warnings;
           }
     | _ -> Error "")
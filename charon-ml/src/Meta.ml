@@ -1,9 +1,13 @@
 (** Meta data like code spans *)
 
+module BacktraceId = Identifiers.IdGen ()
+
 (** A line location *)
 type loc = {
   line : int;  (** The (1-based) line number. *)
   col : int;  (** The (0-based) column offset. *)
+  offset : int;
+      (** The (0-based) byte offset from the beginning of the file. *)
 }
 [@@deriving show, ord]
 
@@ -41,5 +45,37 @@ type meta = {
        *)
   generated_from_span : span option;
       (** Where the code actually comes from, in case of macro expansion/inlining/etc. *)
+  backtrace : BacktraceId.id option;
+      (** The full macro backtrace, in case of nested macro expansions/inlinings:
+          the call sites of the macros the code was expanded from, followed by
+          the spans of the scopes it was inlined from, from the innermost to the
+          outermost.
+
+          The backtraces are stored in the bodies (see
+          {!GAst.gexpr_body.backtraces}).
+       *)
+}
+[@@deriving show, ord]
+
+(** An attribute of an item that we keep *)
+type attribute =
+  | Inline of string option
+      (** [#[inline]], [#[inline(always)]] or [#[inline(never)]] *)
+  | MustUse of string option  (** [#[must_use]], with the optional message *)
+  | LinkName of string
+      (** [#[link_name = "..."]], on the items of the [extern] blocks *)
+  | NoMangle  (** [#[no_mangle]] *)
+  | Tool of string * string option
+      (** A tool attribute, ex.: [#[verify::requires(x > 0)]]. We keep the
+          path ([verify::requires]) and the arguments ([x > 0]). *)
+[@@deriving show, ord]
+
+(** The doc comments and the attributes of an item *)
+type attr_info = {
+  doc_comments : string list;
+      (** The lines of the doc comments, in order *)
+  attributes : attribute list;
+  source_text : string option;
+      (** The source text of the item (see the option [--emit-source]) *)
 }
 [@@deriving show, ord]
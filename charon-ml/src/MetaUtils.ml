@@ -1,12 +1,12 @@
 open Meta
 
 let loc_min (l0 : loc) (l1 : loc) : loc =
-  if l0.line = l1.line then { line = l0.line; col = Int.min l0.col l1.col }
+  if l0.line = l1.line then if l0.col <= l1.col then l0 else l1
   else if l0.line < l1.line then l0
   else l1
 
 let loc_max (l0 : loc) (l1 : loc) : loc =
-  if l0.line = l1.line then { line = l0.line; col = Int.max l0.col l1.col }
+  if l0.line = l1.line then if l0.col >= l1.col then l0 else l1
   else if l0.line > l1.line then l0
   else l1
 
@@ -20,4 +20,4 @@ let combine_meta (m0 : meta) (m1 : meta) : meta =
       end_loc = loc_max m0.span.end_loc m1.span.end_loc;
     }
   in
-  { span; generated_from_span = None }
+  { span; generated_from_span = None; backtrace = None }
@@ -387,6 +387,16 @@ let match_literal (pl : literal) (l : Values.literal) : bool =
   | LChar pv, VChar v -> pv = v
   | _ -> false
 
+(** The paths of the assumed types which we refer to with their full paths
+    (the path of the parent module, and the name of the type) *)
+let assumed_ty_path (id : T.assumed_ty) : (string list * string) option =
+  match id with
+  | TRc -> Some ([ "alloc"; "rc" ], "Rc")
+  | TArc -> Some ([ "alloc"; "sync" ], "Arc")
+  | TCell -> Some ([ "core"; "cell" ], "Cell")
+  | TRefCell -> Some ([ "core"; "cell" ], "RefCell")
+  | TBox | TVec | TArray | TSlice | TStr -> None
+
 let rec match_name_with_generics (ctx : ctx) (c : match_config) (p : pattern)
     (n : T.name) (g : T.generic_args) : bool =
   match (p, n) with
@@ -437,6 +447,23 @@ and match_pattern_with_type_id (ctx : ctx) (c : match_config) (m : maps)
               PIdent ("Box", pgenerics);
             ] ) ) ->
           match_generic_args ctx c m pgenerics generics
+      | ( TVec,
+          ( [ PIdent ("Vec", pgenerics) ]
+          | [
+              PIdent ("alloc", []);
+              PIdent ("vec", []);
+              PIdent ("Vec", pgenerics);
+            ] ) ) ->
+          match_generic_args ctx c m pgenerics generics
+      | (TRc | TArc | TCell | TRefCell), _ -> (
+          let prefix, name = Option.get (assumed_ty_path id) in
+          let prefix = List.map (fun s -> PIdent (s, [])) prefix in
+          match List.rev pid with
+          | PIdent (pname, pgenerics) :: pprefix ->
+              pname = name
+              && List.rev pprefix = prefix
+              && match_generic_args ctx c m pgenerics generics
+          | _ -> false)
       | TStr, [ PIdent ("str", []) ] -> generics = TypesUtils.empty_generic_args
       | _ -> false)
 
@@ -570,6 +597,21 @@ let assumed_fun_id_to_string (fid : E.assumed_fun_id) : string =
   | ArrayRepeat -> "ArrayRepeat"
   | SliceIndexShared -> "SliceIndexShared"
   | SliceIndexMut -> "SliceIndexMut"
+  | VecNew -> "VecNew"
+  | VecPush -> "VecPush"
+  | VecLen -> "VecLen"
+  | VecIndexShared -> "VecIndexShared"
+  | VecIndexMut -> "VecIndexMut"
+  | OptionUnwrap -> "OptionUnwrap"
+  | OptionIsSome -> "OptionIsSome"
+  | MemSwap -> "MemSwap"
+  | MemReplace -> "MemReplace"
+  | MemTake -> "MemTake"
+  | Transmute -> "Transmute"
+  | CopyNonOverlapping -> "CopyNonOverlapping"
+  | DiscriminantValue -> "DiscriminantValue"
+  | SizeOfVal -> "SizeOfVal"
+  | Fmt -> "Fmt"
 
 let match_fn_ptr (ctx : ctx) (c : match_config) (p : pattern) (func : E.fn_ptr)
     : bool =
@@ -745,6 +787,8 @@ let literal_to_pattern (_c : to_pat_config) (lit : Values.literal) : literal =
   | VScalar sv -> LInt sv.value
   | VBool v -> LBool v
   | VChar v -> LChar v
+  | VStr _ | VByteStr _ ->
+      raise (Failure "String literals can't appear in the names")
 
 let rec name_with_generic_args_to_pattern_aux (ctx : ctx) (c : to_pat_config)
     (n : T.name) (generics : generic_args option) : pattern =
@@ -786,6 +830,11 @@ and ty_to_pattern_aux (ctx : ctx) (c : to_pat_config) (m : constraints)
       | TAssumed TArray -> EPrimAdt (TArray, generics)
       | TAssumed TSlice -> EPrimAdt (TSlice, generics)
       | TAssumed TBox -> EComp [ PIdent ("Box", generics) ]
+      | TAssumed TVec -> EComp [ PIdent ("Vec", generics) ]
+      | TAssumed ((TRc | TArc | TCell | TRefCell) as id) ->
+          let prefix, name = Option.get (assumed_ty_path id) in
+          let prefix = List.map (fun s -> PIdent (s, [])) prefix in
+          EComp (prefix @ [ PIdent (name, generics) ])
       | TAssumed TStr -> EComp [ PIdent ("str", generics) ])
   | TVar v -> EVar (type_var_to_pattern m v)
   | TLiteral lit -> literal_type_to_pattern c lit
@@ -812,6 +861,7 @@ and ty_to_pattern_aux (ctx : ctx) (c : to_pat_config) (m : constraints)
   | TRawPtr (ty, RMut) -> ERawPtr (Mut, ty_to_pattern_aux ctx c m ty)
   | TRawPtr (ty, RShared) -> ERawPtr (Not, ty_to_pattern_aux ctx c m ty)
   | TNever -> raise (Failure "Unimplemented: Never")
+  | TDynTrait _ -> raise (Failure "Unimplemented: DynTrait")
 
 and trait_ref_item_with_generics_to_pattern (ctx : ctx) (c : to_pat_config)
     (m : constraints) (trait_ref : T.trait_ref) (item_name : string)
@@ -840,6 +890,10 @@ and const_generic_to_pattern (ctx : ctx) (c : to_pat_config) (m : constraints)
   match cg with
   | CgVar v -> GExpr (EVar (const_generic_var_to_pattern m v))
   | CgValue v -> GValue (literal_to_pattern c v)
+  | CgAdt _ | CgTraitConst _ ->
+      (* The patterns can't express the structured values nor the trait
+         constants: we use a wildcard *)
+      GExpr (EVar None)
   | CgGlobal gid ->
       let d = T.GlobalDeclId.Map.find gid ctx.global_decls in
       let n = name_to_pattern_aux ctx c d.name in
@@ -57,8 +57,32 @@ let cast_kind_to_string (env : ('a, 'b) fmt_env) (cast : cast_kind) : string =
   | CastScalar (src, tgt) ->
       "cast<" ^ literal_type_to_string src ^ "," ^ literal_type_to_string tgt
       ^ ">"
-  | CastFnPtr (src, tgt) ->
-      "cast<" ^ ty_to_string env src ^ "," ^ ty_to_string env tgt ^ ">"
+  | CastUnsize (src, tgt, vtable) ->
+      let vtable =
+        match vtable with
+        | None -> ""
+        | Some vtable -> "[" ^ trait_ref_to_string env vtable ^ "]"
+      in
+      "cast_unsize<" ^ ty_to_string env src ^ "," ^ ty_to_string env tgt ^ ">"
+      ^ vtable
+  | CastReifyFnPointer (src, tgt)
+  | CastClosureFnPointer (src, tgt)
+  | CastPtrToPtr (src, tgt)
+  | CastPtrToInt (src, tgt)
+  | CastIntToPtr (src, tgt)
+  | CastTransmute (src, tgt) ->
+      let kind =
+        match cast with
+        | CastReifyFnPointer _ -> "reify_fn_pointer"
+        | CastClosureFnPointer _ -> "closure_fn_pointer"
+        | CastPtrToPtr _ -> "ptr_to_ptr"
+        | CastPtrToInt _ -> "ptr_to_int"
+        | CastIntToPtr _ -> "int_to_ptr"
+        | CastTransmute _ -> "transmute"
+        | CastScalar _ | CastUnsize _ -> raise (Failure "Unreachable")
+      in
+      "cast_" ^ kind ^ "<" ^ ty_to_string env src ^ "," ^ ty_to_string env tgt
+      ^ ">"
 
 let unop_to_string (env : ('a, 'b) fmt_env) (unop : unop) : string =
   match unop with
@@ -66,6 +90,9 @@ let unop_to_string (env : ('a, 'b) fmt_env) (unop : unop) : string =
   | Neg -> "-"
   | Cast cast_kind -> cast_kind_to_string env cast_kind
 
+let nullop_to_string (nullop : nullop) : string =
+  match nullop with SizeOf -> "size_of" | AlignOf -> "align_of"
+
 let binop_to_string (binop : binop) : string =
   match binop with
   | BitXor -> "^"
@@ -84,6 +111,9 @@ let binop_to_string (binop : binop) : string =
   | Mul -> "*"
   | Shl -> "<<"
   | Shr -> ">>"
+  | And -> "&&"
+  | Or -> "||"
+  | Offset -> "offset"
 
 let assumed_fun_id_to_string (aid : assumed_fun_id) : string =
   match aid with
@@ -96,6 +126,21 @@ let assumed_fun_id_to_string (aid : assumed_fun_id) : string =
   | ArrayRepeat -> "@ArrayRepeat"
   | SliceIndexShared -> "@SliceIndexShared"
   | SliceIndexMut -> "@SliceIndexMut"
+  | VecNew -> "alloc::vec::Vec::new"
+  | VecPush -> "alloc::vec::Vec::push"
+  | VecLen -> "alloc::vec::Vec::len"
+  | VecIndexShared -> "@VecIndexShared"
+  | VecIndexMut -> "@VecIndexMut"
+  | OptionUnwrap -> "core::option::Option::unwrap"
+  | OptionIsSome -> "core::option::Option::is_some"
+  | MemSwap -> "core::mem::swap"
+  | MemReplace -> "core::mem::replace"
+  | MemTake -> "core::mem::take"
+  | Transmute -> "core::intrinsics::transmute"
+  | CopyNonOverlapping -> "core::intrinsics::copy_nonoverlapping"
+  | DiscriminantValue -> "core::intrinsics::discriminant_value"
+  | SizeOfVal -> "core::intrinsics::size_of_val"
+  | Fmt -> "@Fmt"
 
 let fun_id_to_string (env : ('a, 'b) fmt_env) (fid : fun_id) : string =
   match fid with
@@ -124,6 +169,7 @@ let constant_expr_to_string (env : ('a, 'b) fmt_env) (cv : constant_expr) :
       let generics = generic_args_to_string env generics in
       trait_ref ^ generics ^ const_name
   | CFnPtr fn_ptr -> fn_ptr_to_string env fn_ptr
+  | CGlobalDeclRef gid -> "&" ^ global_decl_id_to_string env gid
 
 let operand_to_string (env : ('a, 'b) fmt_env) (op : operand) : string =
   match op with
@@ -141,13 +187,25 @@ let rvalue_to_string (env : ('a, 'b) fmt_env) (rv : rvalue) : string =
       | BMut -> "&mut " ^ p
       | BTwoPhaseMut -> "&two-phase " ^ p
       | BShallow -> "&shallow " ^ p)
+  | AddressOf (p, rk) -> (
+      let p = place_to_string env p in
+      match rk with RShared -> "&raw const " ^ p | RMut -> "&raw mut " ^ p)
   | UnaryOp (unop, op) ->
       unop_to_string env unop ^ " " ^ operand_to_string env op
   | BinaryOp (binop, op1, op2) ->
       operand_to_string env op1 ^ " " ^ binop_to_string binop ^ " "
       ^ operand_to_string env op2
+  | Nullary (nullop, ty) ->
+      nullop_to_string nullop ^ "<" ^ ty_to_string env ty ^ ">"
   | Discriminant (p, _) -> "discriminant(" ^ place_to_string env p ^ ")"
   | Global gid -> "global " ^ global_decl_id_to_string env gid
+  | Repeat (op, _, cg) ->
+      "[" ^ operand_to_string env op ^ "; "
+      ^ const_generic_to_string env cg
+      ^ "]"
+  | ShallowInitBox (op, ty) ->
+      "shallow_init_box<" ^ ty_to_string env ty ^ ">("
+      ^ operand_to_string env op ^ ")"
   | Aggregate (akind, ops) -> (
       let ops = List.map (operand_to_string env) ops in
       match akind with
@@ -183,4 +241,17 @@ let rvalue_to_string (env : ('a, 'b) fmt_env) (rv : rvalue) : string =
           "{"
           ^ fun_decl_id_to_string env fid
           ^ generic_args_to_string env generics
+          ^ "}" ^ " {" ^ String.concat ", " ops ^ "}"
+      | AggregatedUnion (def_id, field_id, _generics) ->
+          let adt_name = type_decl_id_to_string env def_id in
+          let field =
+            match adt_field_to_string env def_id None field_id with
+            | Some field_name -> field_name
+            | None -> FieldId.to_string field_id
+          in
+          adt_name ^ " { " ^ field ^ ": " ^ String.concat ", " ops ^ " }"
+      | AggregatedGenerator (fid, generics) ->
+          "{generator "
+          ^ fun_decl_id_to_string env fid
+          ^ generic_args_to_string env generics
           ^ "}" ^ " {" ^ String.concat ", " ops ^ "}")
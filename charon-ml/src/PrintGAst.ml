@@ -11,6 +11,11 @@ let fn_operand_to_string (env : ('a, 'b) fmt_env) (op : fn_operand) : string =
   match op with
   | FnOpRegular func -> fn_ptr_to_string env func
   | FnOpMove p -> "move " ^ place_to_string env p
+  | FnOpVirtual (trait_ref, method_name, generics) ->
+      "(dyn "
+      ^ trait_decl_ref_to_string env trait_ref
+      ^ ")::" ^ method_name
+      ^ generic_args_to_string env generics
 
 let call_to_string (env : ('a, 'b) fmt_env) (indent : string) (call : call) :
     string =
@@ -32,8 +37,15 @@ let fun_sig_with_name_to_string (env : ('a, 'b) fmt_env) (indent : string)
     (args : var list option) (sg : fun_sig) : string =
   let ty_to_string = ty_to_string env in
 
-  (* Unsafe keyword *)
-  let unsafe = if sg.is_unsafe then "unsafe " else "" in
+  (* Qualifiers *)
+  let qualifiers =
+    (if sg.is_const then "const " else "")
+    ^ (if sg.is_async then "async " else "")
+    ^ (if sg.is_unsafe then "unsafe " else "")
+    ^
+    if sg.abi = "Rust" || sg.is_closure then ""
+    else "extern \"" ^ sg.abi ^ "\" "
+  in
 
   (* Generics and predicates *)
   let params, trait_clauses = generic_params_to_strings env sg.generics in
@@ -64,17 +76,24 @@ let fun_sig_with_name_to_string (env : ('a, 'b) fmt_env) (indent : string)
         in
         String.concat ", " args
   in
+  let args = if sg.is_variadic then args ^ ", ..." else args in
 
   (* Put everything together *)
   let attribute = match attribute with None -> "" | Some attr -> attr ^ " " in
   let name = match name with None -> "" | Some name -> " " ^ name in
-  indent ^ attribute ^ unsafe ^ "fn" ^ name ^ params ^ "(" ^ args ^ ")" ^ ret_ty
-  ^ clauses
+  indent ^ attribute ^ qualifiers ^ "fn" ^ name ^ params ^ "(" ^ args ^ ")"
+  ^ ret_ty ^ clauses
 
 let fun_sig_to_string (env : ('a, 'b) fmt_env) (indent : string)
     (indent_incr : string) (sg : fun_sig) : string =
   fun_sig_with_name_to_string env indent indent_incr None None None sg
 
+let global_kind_to_string (kind : global_kind) : string =
+  match kind with
+  | ConstGlobal -> "global"
+  | StaticGlobal { is_mut = false; _ } -> "static"
+  | StaticGlobal { is_mut = true; _ } -> "static mut"
+
 let gfun_decl_to_string (env : ('a, 'b) fmt_env) (indent : string)
     (indent_incr : string)
     (body_to_string : ('a, 'b) fmt_env -> string -> string -> 'body -> string)
@@ -215,7 +234,8 @@ let trait_decl_to_string (env : ('a, 'b) fmt_env) (indent : string)
     if items = [] then "" else "\n{\n" ^ String.concat "" items ^ "}"
   in
 
-  "trait " ^ name ^ params ^ clauses ^ items
+  let auto = if def.is_auto then "auto " else "" in
+  auto ^ "trait " ^ name ^ params ^ clauses ^ items
 
 let trait_impl_to_string (env : ('a, 'b) fmt_env) (indent : string)
     (indent_incr : string) (def : trait_impl) : string =
@@ -291,5 +311,7 @@ let trait_impl_to_string (env : ('a, 'b) fmt_env) (indent : string)
     if items = [] then "" else "\n{\n" ^ String.concat "" items ^ "}"
   in
 
+  let negative = if def.is_negative then "!" else "" in
   let impl_trait = trait_decl_ref_to_string env def.impl_trait in
-  "impl" ^ params ^ " " ^ name ^ params ^ " : " ^ impl_trait ^ clauses ^ items
+  "impl" ^ params ^ " " ^ name ^ params ^ " : " ^ negative ^ impl_trait
+  ^ clauses ^ items
@@ -32,13 +32,14 @@ module Ast = struct
     | Call call -> call_to_string env indent call
     | Panic -> indent ^ "panic"
     | Return -> indent ^ "return"
-    | Break i -> indent ^ "break " ^ string_of_int i
-    | Continue i -> indent ^ "continue " ^ string_of_int i
+    | Break (_, Some label) -> indent ^ "break 'l" ^ string_of_int label
+    | Break (i, None) -> indent ^ "break " ^ string_of_int i
+    | Continue (_, Some label) -> indent ^ "continue 'l" ^ string_of_int label
+    | Continue (i, None) -> indent ^ "continue " ^ string_of_int i
     | Nop -> indent ^ "nop"
-    | Sequence (st1, st2) ->
-        statement_to_string env indent indent_incr st1
-        ^ ";\n"
-        ^ statement_to_string env indent indent_incr st2
+    | Block sts ->
+        String.concat ";\n"
+          (List.map (statement_to_string env indent indent_incr) sts)
     | Switch switch -> (
         match switch with
         | If (op, true_st, false_st) ->
@@ -62,7 +63,9 @@ module Ast = struct
               List.map
                 (fun (svl, be) ->
                   let svl =
-                    List.map (fun sv -> "| " ^ scalar_value_to_string sv) svl
+                    List.map
+                      (fun sv -> "| " ^ switch_value_to_string sv)
+                      svl
                   in
                   let svl = String.concat " " svl in
                   indent ^ svl ^ " => {\n" ^ inner_to_string2 be ^ "\n"
@@ -103,10 +106,46 @@ module Ast = struct
             in
             let branches = branches ^ otherwise in
             indent ^ "match (" ^ p ^ ") {\n" ^ branches ^ "\n" ^ indent ^ "}")
-    | Loop loop_st ->
-        indent ^ "loop {\n"
+    | Loop (loop_st, label) ->
+        let label =
+          match label with
+          | None -> ""
+          | Some label -> "'l" ^ string_of_int label ^ ": "
+        in
+        indent ^ label ^ "loop {\n"
         ^ statement_to_string env (indent ^ indent_incr) indent_incr loop_st
         ^ "\n" ^ indent ^ "}"
+    | ForLoop lp ->
+        let label =
+          match lp.label with
+          | None -> ""
+          | Some label -> "'l" ^ string_of_int label ^ ": "
+        in
+        let binder =
+          match lp.binder with None -> "_" | Some p -> place_to_string env p
+        in
+        indent ^ label ^ "for " ^ binder ^ " in ("
+        ^ var_id_to_string env lp.iter_var
+        ^ " := "
+        ^ fn_ptr_to_string env lp.into_iter
+        ^ "("
+        ^ operand_to_string env lp.iter
+        ^ ")) {\n"
+        ^ statement_to_string env (indent ^ indent_incr) indent_incr lp.body
+        ^ "\n" ^ indent ^ "}"
+    | QuestionMark qm ->
+        let dest =
+          match qm.dest with
+          | None -> ""
+          | Some p -> place_to_string env p ^ " := "
+        in
+        indent ^ dest
+        ^ fn_ptr_to_string env qm.branch
+        ^ "("
+        ^ operand_to_string env qm.op
+        ^ ")? (from_residual: "
+        ^ fn_ptr_to_string env qm.from_residual
+        ^ ")"
 
   let fun_sig_to_string (env : fmt_env) (indent : string) (indent_incr : string)
       (sg : fun_sig) : string =
@@ -126,7 +165,8 @@ module Ast = struct
     let ty = ty_to_string env def.ty in
 
     let body_id = fun_decl_id_to_string env def.body in
-    indent ^ "global " ^ name ^ " : " ^ ty ^ " = " ^ body_id
+    let kind = global_kind_to_string def.kind in
+    indent ^ kind ^ " " ^ name ^ " : " ^ ty ^ " = " ^ body_id
 end
 
 (** Pretty-printing for ASTs (functions based on a declaration context) *)
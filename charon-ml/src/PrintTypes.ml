@@ -7,6 +7,11 @@ open GAst
 open PrintUtils
 
 let type_var_to_string (tv : type_var) : string = tv.name
+
+(** Print a type variable as a parameter (with its [?Sized] bound, if it has
+    one) *)
+let type_var_to_param_string (tv : type_var) : string =
+  if tv.sized then tv.name else tv.name ^ " : ?Sized"
 let const_generic_var_to_string (v : const_generic_var) : string = v.name
 
 let region_var_to_string (rv : region_var) : string =
@@ -17,6 +22,9 @@ let region_var_to_string (rv : region_var) : string =
 let ref_kind_to_string (rk : ref_kind) : string =
   match rk with RMut -> "Mut" | RShared -> "Shared"
 
+let closure_kind_to_string (k : closure_kind) : string =
+  match k with Fn -> "Fn" | FnMut -> "FnMut" | FnOnce -> "FnOnce"
+
 let assumed_ty_to_string (_ : assumed_ty) : string = "Box"
 
 let trait_clause_id_to_pretty_string (id : trait_clause_id) : string =
@@ -99,6 +107,11 @@ let rec type_id_to_string (env : ('a, 'b) fmt_env) (id : type_id) : string =
   | TAssumed aty -> (
       match aty with
       | TBox -> "alloc::boxed::Box"
+      | TVec -> "alloc::vec::Vec"
+      | TRc -> "alloc::rc::Rc"
+      | TArc -> "alloc::sync::Arc"
+      | TCell -> "core::cell::Cell"
+      | TRefCell -> "core::cell::RefCell"
       | TStr -> "str"
       | TArray -> "@Array"
       | TSlice -> "@Slice")
@@ -136,6 +149,18 @@ and const_generic_to_string (env : ('a, 'b) fmt_env) (cg : const_generic) :
   | CgGlobal id -> global_decl_id_to_string env id
   | CgVar id -> const_generic_var_id_to_string env id
   | CgValue lit -> literal_to_string lit
+  | CgAdt (variant_id, fields) ->
+      let variant_id =
+        match variant_id with
+        | None -> "None"
+        | Some id -> "Some(" ^ VariantId.to_string id ^ ")"
+      in
+      let fields = List.map (const_generic_to_string env) fields in
+      "ConstAdt " ^ variant_id ^ " [" ^ String.concat ", " fields ^ "]"
+  | CgTraitConst (trait_ref, generics, const_name) ->
+      let trait_ref = trait_ref_to_string env trait_ref in
+      let generics = generic_args_to_string env generics in
+      trait_ref ^ generics ^ "::" ^ const_name
 
 and ty_to_string (env : ('a, 'b) fmt_env) (ty : ty) : string =
   match ty with
@@ -166,6 +191,9 @@ and ty_to_string (env : ('a, 'b) fmt_env) (ty : ty) : string =
         "(" ^ String.concat ", " (List.map (ty_to_string env) inputs) ^ ") -> "
       in
       inputs ^ ty_to_string env output
+  | TDynTrait (trait_ref, region) ->
+      "dyn (" ^ trait_decl_ref_to_string env trait_ref ^ " + "
+      ^ region_to_string env region ^ ")"
 
 and params_to_string (env : ('a, 'b) fmt_env) (is_tuple : bool)
     (generics : generic_args) : string =
@@ -232,7 +260,11 @@ and trait_instance_id_to_string (env : ('a, 'b) fmt_env)
       let clause_id = trait_clause_id_to_string env clause_id in
       "(" ^ inst_id ^ ")::" ^ item_name ^ "::[" ^ clause_id ^ "]"
   | TraitRef tr -> trait_ref_to_string env tr
-  | FnPointer ty -> "fn_ptr(" ^ ty_to_string env ty ^ ")"
+  | FnPointer (kind, inputs, output) ->
+      let inputs = List.map (ty_to_string env) inputs in
+      "impl " ^ closure_kind_to_string kind ^ "("
+      ^ String.concat ", " inputs
+      ^ ") -> " ^ ty_to_string env output
   | Closure (fid, generics) ->
       "closure("
       ^ fun_decl_id_to_string env fid
@@ -276,7 +308,7 @@ let generic_params_to_strings (env : ('a, 'b) fmt_env)
     generics
   in
   let regions = List.map region_var_to_string regions in
-  let types = List.map type_var_to_string types in
+  let types = List.map type_var_to_param_string types in
   let cgs = List.map const_generic_var_to_string const_generics in
   let params = List.flatten [ regions; types; cgs ] in
   let trait_clauses = List.map (trait_clause_to_string env) trait_clauses in
@@ -389,6 +421,12 @@ let type_decl_to_string (env : ('a, 'b) fmt_env) (def : type_decl) : string =
       in
       let variants = String.concat "\n" variants in
       "enum " ^ name ^ params ^ clauses ^ "\n  =\n" ^ variants
+  | Union fields ->
+      let fields =
+        String.concat ","
+          (List.map (fun f -> "\n  " ^ field_to_string env f) fields)
+      in
+      "union " ^ name ^ params ^ clauses ^ "\n{" ^ fields ^ "\n}"
   | Opaque -> "opaque type " ^ name ^ params ^ clauses
 
 let adt_variant_to_string (env : ('a, 'b) fmt_env) (def_id : TypeDeclId.id)
@@ -400,7 +438,7 @@ let adt_variant_to_string (env : ('a, 'b) fmt_env) (def_id : TypeDeclId.id)
       ^ variant_id_to_pretty_string variant_id
   | Some def -> (
       match def.kind with
-      | Struct _ | Opaque -> raise (Failure "Unreachable")
+      | Struct _ | Union _ | Opaque -> raise (Failure "Unreachable")
       | Enum variants ->
           let variant = VariantId.nth variants variant_id in
           name_to_string env def.name ^ "::" ^ variant.variant_name)
@@ -42,7 +42,8 @@ module Ast = struct
         let branches =
           List.map
             (fun (sv, bid) ->
-              scalar_value_to_string sv ^ " -> " ^ block_id_to_string bid ^ "; ")
+              switch_value_to_string sv ^ " -> " ^ block_id_to_string bid
+              ^ "; ")
             branches
         in
         let branches = String.concat "" branches in
@@ -60,18 +61,26 @@ module Ast = struct
     | Switch (op, tgts) ->
         indent ^ "switch " ^ operand_to_string env op
         ^ switch_to_string indent tgts
-    | Panic -> indent ^ "panic"
+    | Panic (Unwind unwind) -> indent ^ "panic" ^ unwind_to_string unwind
+    | Panic Abort -> indent ^ "abort"
     | Return -> indent ^ "return"
     | Unreachable -> indent ^ "unreachable"
-    | Drop (p, bid) ->
+    | Drop (p, bid, unwind) ->
         indent ^ "drop " ^ place_to_string env p ^ ";\n" ^ indent ^ "goto "
-        ^ block_id_to_string bid
-    | Call (call, bid) ->
+        ^ block_id_to_string bid ^ unwind_to_string unwind
+    | Call (call, bid, unwind) ->
         call_to_string env indent call
         ^ ";\n" ^ indent ^ "goto " ^ block_id_to_string bid
-    | Assert (a, bid) ->
+        ^ unwind_to_string unwind
+    | Assert (a, bid, unwind) ->
         assertion_to_string env indent a
         ^ ";\n" ^ indent ^ "goto " ^ block_id_to_string bid
+        ^ unwind_to_string unwind
+
+  and unwind_to_string (unwind : BlockId.id option) : string =
+    match unwind with
+    | None -> ""
+    | Some bid -> " (unwind: " ^ block_id_to_string bid ^ ")"
 
   let block_to_string (env : fmt_env) (indent : string) (indent_incr : string)
       (id : BlockId.id) (block : block) : string =
@@ -100,16 +109,17 @@ module Ast = struct
     (* We don't need to update the environment *)
     let name = name_to_string env def.name in
     let ty = ty_to_string env def.ty in
+    let kind = global_kind_to_string def.kind in
 
     (* We print the declaration differently if it is opaque (no body) or transparent
      * (we have access to a body) *)
     match def.body with
     | None ->
         (* Put everything together *)
-        indent ^ "opaque global " ^ name ^ " : " ^ ty
+        indent ^ "opaque " ^ kind ^ " " ^ name ^ " : " ^ ty
     | Some body ->
         let body = blocks_to_string env indent indent_incr body.body in
-        indent ^ "global " ^ name ^ " : " ^ ty ^ " =\n" ^ body
+        indent ^ kind ^ " " ^ name ^ " : " ^ ty ^ " =\n" ^ body
 end
 
 (** Pretty-printing for ASTs (functions based on a declaration context) *)
@@ -28,8 +28,18 @@ let big_int_to_string (bi : big_int) : string = Z.to_string bi
 let scalar_value_to_string (sv : scalar_value) : string =
   big_int_to_string sv.value ^ ": " ^ integer_type_to_string sv.int_ty
 
+let switch_value_to_string (sv : switch_value) : string =
+  match sv with
+  | SVValue v -> scalar_value_to_string v
+  | SVRange (lo, hi) ->
+      big_int_to_string lo.value ^ "..=" ^ scalar_value_to_string hi
+
 let literal_to_string (lit : literal) : string =
   match lit with
   | VScalar sv -> scalar_value_to_string sv
   | VBool b -> Bool.to_string b
   | VChar c -> String.make 1 c
+  | VStr s -> "\"" ^ String.escaped s ^ "\""
+  | VByteStr bs ->
+      let bs = List.map (fun b -> Char.escaped (Char.chr b)) bs in
+      "b\"" ^ String.concat "" bs ^ "\""
@@ -231,7 +231,7 @@ let type_decl_get_instantiated_variants_fields_types (def : type_decl)
     match def.kind with
     | Enum variants ->
         List.mapi (fun i v -> (Some (VariantId.of_int i), v.fields)) variants
-    | Struct fields -> [ (None, fields) ]
+    | Struct fields | Union fields -> [ (None, fields) ]
     | Opaque ->
         raise
           (Failure
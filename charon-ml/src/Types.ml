@@ -68,25 +68,26 @@ type ('id, 'name) indexed_var = {
 }
 [@@deriving show, ord]
 
-type type_var = (TypeVarId.id, string) indexed_var [@@deriving show, ord]
+type type_var = {
+  index : TypeVarId.id;  (** Unique index identifying the variable *)
+  name : string;  (** Variable name *)
+  sized : bool;
+      (** [false] if the variable is declared with [?Sized] (we need to record
+          this because we filter the [Sized] clauses) *)
+}
+[@@deriving show, ord]
 
 type region_var = (RegionVarId.id, string option) indexed_var
 [@@deriving show, ord]
 
 type literal_type = Values.literal_type [@@deriving show, ord]
 
-type const_generic_var = {
-  index : ConstGenericVarId.id;
-  name : string;
-  ty : literal_type;
-}
-[@@deriving show, ord]
-
 let all_signed_int_types = [ Isize; I8; I16; I32; I64; I128 ]
 let all_unsigned_int_types = [ Usize; U8; U16; U32; U64; U128 ]
 let all_int_types = List.append all_signed_int_types all_unsigned_int_types
 
 type ref_kind = RMut | RShared [@@deriving show, ord]
+type closure_kind = Fn | FnMut | FnOnce [@@deriving show, ord]
 
 (** The variant id for [Option::None] *)
 let option_none_id = VariantId.of_int 0
@@ -100,6 +101,7 @@ class ['self] iter_const_generic_base =
     inherit [_] iter_literal
     method visit_type_decl_id : 'env -> type_decl_id -> unit = fun _ _ -> ()
     method visit_global_decl_id : 'env -> global_decl_id -> unit = fun _ _ -> ()
+    method visit_variant_id : 'env -> variant_id -> unit = fun _ _ -> ()
 
     method visit_const_generic_var_id : 'env -> const_generic_var_id -> unit =
       fun _ _ -> ()
@@ -116,98 +118,25 @@ class ['self] map_const_generic_base =
     method visit_global_decl_id : 'env -> global_decl_id -> global_decl_id =
       fun _ x -> x
 
+    method visit_variant_id : 'env -> variant_id -> variant_id = fun _ x -> x
+
     method visit_const_generic_var_id
         : 'env -> const_generic_var_id -> const_generic_var_id =
       fun _ x -> x
   end
 
-(** Ancestor for reduce visitor for {!Types.const_generic} *)
-class virtual ['self] reduce_const_generic_base =
-  object (self : 'self)
-    inherit [_] reduce_literal
-
-    method visit_type_decl_id : 'env -> type_decl_id -> 'a =
-      fun _ _ -> self#zero
-
-    method visit_global_decl_id : 'env -> global_decl_id -> 'a =
-      fun _ _ -> self#zero
-
-    method visit_const_generic_var_id : 'env -> const_generic_var_id -> 'a =
-      fun _ _ -> self#zero
-  end
-
-(** Ancestor for mapreduce visitor for {!Types.const_generic} *)
-class virtual ['self] mapreduce_const_generic_base =
-  object (self : 'self)
-    inherit [_] mapreduce_literal
-
-    method visit_type_decl_id : 'env -> type_decl_id -> type_decl_id * 'a =
-      fun _ x -> (x, self#zero)
-
-    method visit_global_decl_id : 'env -> global_decl_id -> global_decl_id * 'a
-        =
-      fun _ x -> (x, self#zero)
-
-    method visit_const_generic_var_id
-        : 'env -> const_generic_var_id -> const_generic_var_id * 'a =
-      fun _ x -> (x, self#zero)
-  end
-
-(** Remark: we have to use long names because otherwise we have collisions in
-    the functions derived for the visitors. *)
-type const_generic =
-  | CgGlobal of global_decl_id
-  | CgVar of const_generic_var_id
-  | CgValue of literal
-[@@deriving
-  show,
-    ord,
-    visitors
-      {
-        name = "iter_const_generic";
-        variety = "iter";
-        ancestors = [ "iter_const_generic_base" ];
-        nude = true (* Don't inherit {!VisitorsRuntime.iter} *);
-        concrete = true;
-        polymorphic = false;
-      },
-    visitors
-      {
-        name = "map_const_generic";
-        variety = "map";
-        ancestors = [ "map_const_generic_base" ];
-        nude = true (* Don't inherit {!VisitorsRuntime.map} *);
-        concrete = true;
-        polymorphic = false;
-      },
-    visitors
-      {
-        name = "reduce_const_generic";
-        variety = "reduce";
-        ancestors = [ "reduce_const_generic_base" ];
-        nude = true (* Don't inherit {!VisitorsRuntime.reduce} *);
-        polymorphic = false;
-      },
-    visitors
-      {
-        name = "mapreduce_const_generic";
-        variety = "mapreduce";
-        ancestors = [ "mapreduce_const_generic_base" ];
-        nude = true (* Don't inherit {!VisitorsRuntime.mapreduce} *);
-        polymorphic = false;
-      }]
-
 type trait_item_name = string [@@deriving show, ord]
 
 (** Ancestor for iter visitor for {!type: Types.ty} *)
 class ['self] iter_ty_base =
   object (self : 'self)
-    inherit [_] iter_const_generic
+    inherit [_] iter_const_generic_base
     method visit_region_db_id : 'env -> region_db_id -> unit = fun _ _ -> ()
     method visit_region_var_id : 'env -> region_var_id -> unit = fun _ _ -> ()
     method visit_region_id : 'env -> region_id -> unit = fun _ _ -> ()
     method visit_type_var_id : 'env -> type_var_id -> unit = fun _ _ -> ()
     method visit_ref_kind : 'env -> ref_kind -> unit = fun _ _ -> ()
+    method visit_closure_kind : 'env -> closure_kind -> unit = fun _ _ -> ()
 
     method visit_trait_item_name : 'env -> trait_item_name -> unit =
       fun _ _ -> ()
@@ -229,7 +158,7 @@ class ['self] iter_ty_base =
 (** Ancestor for map visitor for {!type: Types.ty} *)
 class virtual ['self] map_ty_base =
   object (self : 'self)
-    inherit [_] map_const_generic
+    inherit [_] map_const_generic_base
 
     method visit_region_db_id : 'env -> region_db_id -> region_db_id =
       fun _ id -> id
@@ -244,6 +173,9 @@ class virtual ['self] map_ty_base =
 
     method visit_ref_kind : 'env -> ref_kind -> ref_kind = fun _ rk -> rk
 
+    method visit_closure_kind : 'env -> closure_kind -> closure_kind =
+      fun _ k -> k
+
     method visit_trait_item_name : 'env -> trait_item_name -> trait_item_name =
       fun _ x -> x
 
@@ -267,7 +199,16 @@ class virtual ['self] map_ty_base =
   end
 
 (* TODO: Str should be a literal *)
-type assumed_ty = TBox | TArray | TSlice | TStr
+type assumed_ty =
+  | TBox
+  | TVec
+  | TRc
+  | TArc
+  | TCell
+  | TRefCell
+  | TArray
+  | TSlice
+  | TStr
 
 (** Type identifier for ADTs.
 
@@ -287,6 +228,9 @@ and ty =
   | TTraitType of trait_ref * generic_args * string
       (** The string is for the name of the associated type *)
   | TArrow of region_var list * ty list * ty
+  | TDynTrait of trait_decl_ref * region
+      (** A trait object. We only keep the principal trait. Note that the
+          generics of the trait reference don't contain the [Self] type. *)
 
 and trait_ref = {
   trait_id : trait_instance_id;
@@ -306,6 +250,19 @@ and generic_args = {
   trait_refs : trait_ref list;
 }
 
+(** Remark: we have to use long names because otherwise we have collisions in
+    the functions derived for the visitors. *)
+and const_generic =
+  | CgGlobal of global_decl_id
+  | CgVar of const_generic_var_id
+  | CgValue of literal
+  | CgAdt of variant_id option * const_generic list
+      (** A structured value (only with [#![feature(adt_const_params)]]).
+          The variant id is [None] if the value is a structure. *)
+  | CgTraitConst of trait_ref * generic_args * string
+      (** A trait associated constant, ex.: [<T as Foo>::LEN]. The string is
+          for the name of the constant. *)
+
 (** Identifier of a trait instance. *)
 and trait_instance_id =
   | Self
@@ -328,7 +285,11 @@ and trait_instance_id =
           trait clause (which can thus be substituted). In the other cases, it references
           a sub-clause relative to a trait instance id.
        *)
-  | FnPointer of ty
+  | FnPointer of closure_kind * ty list * ty
+      (** A function pointer used as an object implementing [Fn], [FnMut] or
+          [FnOnce]: the kind of the trait, the types of the arguments (i.e.,
+          the components of the tupled argument of the trait) and the output
+          type. *)
   | Closure of fun_decl_id * generic_args
   | UnknownTrait of string
       (** Not present in the Rust version of Charon.
@@ -372,24 +333,48 @@ and region =
         polymorphic = false;
       }]
 
+(** The type of a const generic is a literal type, unless the crate uses
+    [#![feature(adt_const_params)]], in which case it can also be an ADT. *)
+type const_generic_var = {
+  index : ConstGenericVarId.id;
+  name : string;
+  ty : ty;
+}
+[@@deriving show, ord]
+
+(** Where a trait clause comes from (the span of the bound is given by the
+    meta information of the clause) *)
+type trait_clause_origin =
+  | WhereClause  (** [where T : Trait] *)
+  | InlineBound  (** [<T : Trait>], or an [impl Trait] argument *)
+  | Supertrait  (** Implied by a supertrait *)
+  | Elaborated
+      (** Introduced by the elaboration of the bounds (bounds on the
+          associated types, etc.) *)
+[@@deriving show, ord]
+
 (** Ancestor for iter visitor for {!type: Types.predicates} *)
 class ['self] iter_predicates_base =
   object (self : 'self)
     inherit [_] iter_ty
     method visit_meta : 'env -> meta -> unit = fun _ _ -> ()
 
+    method visit_trait_clause_origin : 'env -> trait_clause_origin -> unit =
+      fun _ _ -> ()
+
     method visit_type_var : 'env -> type_var -> unit =
       fun env x ->
-        let { index; name } : type_var = x in
+        let { index; name; sized } : type_var = x in
         self#visit_type_var_id env index;
-        self#visit_string env name
+        self#visit_string env name;
+        self#visit_bool env sized
 
     method visit_const_generic_var : 'env -> const_generic_var -> unit =
       fun env x ->
         let { index; name; ty } : const_generic_var = x in
         self#visit_const_generic_var_id env index;
         self#visit_string env name;
-        self#visit_literal_type env ty
+        self#visit_ty env ty
   end
 
 (** Ancestor for map visitor for {!type: Types.ty} *)
@@ -398,12 +383,17 @@ class virtual ['self] map_predicates_base =
     inherit [_] map_ty
     method visit_meta : 'env -> meta -> meta = fun _ x -> x
 
+    method visit_trait_clause_origin
+        : 'env -> trait_clause_origin -> trait_clause_origin =
+      fun _ x -> x
+
     method visit_type_var : 'env -> type_var -> type_var =
       fun env x ->
-        let { index; name } : type_var = x in
+        let { index; name; sized } : type_var = x in
         let index = self#visit_type_var_id env index in
         let name = self#visit_string env name in
-        { index; name }
+        let sized = self#visit_bool env sized in
+        { index; name; sized }
 
     method visit_const_generic_var
         : 'env -> const_generic_var -> const_generic_var =
@@ -411,7 +401,7 @@ class virtual ['self] map_predicates_base =
         let { index; name; ty } : const_generic_var = x in
         let index = self#visit_const_generic_var_id env index in
         let name = self#visit_string env name in
-        let ty = self#visit_literal_type env ty in
+        let ty = self#visit_ty env ty in
         { index; name; ty }
   end
 
@@ -424,6 +414,7 @@ and rty = ty
 and trait_clause = {
   clause_id : trait_clause_id;
   meta : meta option;
+  origin : trait_clause_origin;
   trait_id : trait_decl_id;
   clause_generics : generic_args;
 }
@@ -529,6 +520,8 @@ type variant = {
 
           See {!Identifiers.Id.mapi} for instance.
        *)
+  discriminant : scalar_value;
+      (** The discriminant of the variant (not necessarily equal to its index) *)
 }
 [@@deriving show]
 
@@ -543,17 +536,143 @@ type type_decl_kind =
 
           See {!Identifiers.Id.mapi} for instance.
        *)
+  | Union of field list
+      (** The fields of the union share the same memory: only one of them is
+          active at a time. They can be indexed with {!FieldId.id}.
+       *)
   | Opaque
       (** An opaque type: either a local type marked as opaque, or an external type *)
 [@@deriving show]
 
+(** The representation options of a type (the [#[repr(...)]] attributes) *)
+type repr_options = {
+  repr_c : bool;
+  repr_transparent : bool;
+  repr_int : integer_type option;
+      (** The integer type of the discriminant, for enumerations *)
+  repr_pack : int option;  (** [#[repr(packed(N))]] *)
+  repr_align : int option;  (** [#[repr(align(N))]] *)
+}
+[@@deriving show]
+
+(** The variance of a generic parameter of a type *)
+type variance =
+  | Covariant
+  | Invariant
+  | Contravariant
+  | Bivariant  (** The parameter is not used *)
+[@@deriving show]
+
+(** The variances of the generic parameters of a type declaration. The const
+    generics don't have variances. *)
+type variances = {
+  region_variances : variance list;  (** In the order of the regions *)
+  type_variances : variance list;  (** In the order of the types *)
+}
+[@@deriving show]
+
+(** The "drop glue" of a type: what happens when a value of this type is
+    dropped. We first call the [drop] method of the implementation of [Drop],
+    if there is one, then drop the fields (for enumerations: the fields of
+    the active variant). *)
+type drop_glue = {
+  drop_impl : trait_impl_id option;
+      (** The implementation of [Drop] for this type, if there is one *)
+  needs_drop : bool;
+      (** [false] if dropping a value of this type is a no-op (this is an
+          over-approximation for the polymorphic types) *)
+  fields_drop_order : field_id list list;
+      (** For every variant (exactly one for the structures), the fields which
+          need to be dropped, in the order in which they are dropped *)
+}
+[@@deriving show]
+
+(** The tag which stores the discriminant of an enumeration *)
+type tag = {
+  tag_offset : int;  (** The offset of the tag, in bytes *)
+  tag_ty : integer_type;
+      (** The integer type of the tag (pointer tags are encoded as [usize]) *)
+}
+[@@deriving show]
+
+(** The niche optimization: the tag is a field of the untagged variant, and the
+    other variants are encoded with values which are invalid for this field
+    (ex.: [Option<&T>] is encoded as a nullable pointer).
+
+    A variant [v] in the range [niche_variants] (inclusive) is encoded by the
+    value [niche_start + (v - fst niche_variants)], with a wrapping arithmetic
+    on the type of the tag. Any other value means that the active variant is
+    [untagged_variant]. *)
+type niche_encoding = {
+  niche_tag : tag;
+  untagged_variant : variant_id;
+  niche_variants : variant_id * variant_id;
+  niche_start : big_int;
+}
+[@@deriving show]
+
+(** How the discriminant of an enumeration is encoded in memory *)
+type discriminant_encoding = Direct of tag | Niche of niche_encoding
+[@@deriving show]
+
+(** The layout of a type, as computed by rustc for the target *)
+type layout = {
+  layout_size : int;  (** The size of the type, in bytes *)
+  layout_align : int;  (** The ABI alignment of the type, in bytes *)
+  discriminant : discriminant_encoding option;
+      (** For the enumerations with several variants *)
+}
+[@@deriving show]
+
 type type_decl = {
   def_id : TypeDeclId.id;
   meta : meta;
+  attr_info : attr_info;
   is_local : bool;
   name : name;
   generics : generic_params;
+  variances : variances;
   preds : predicates;
   kind : type_decl_kind;
+  repr : repr_options;
+  drop_glue : drop_glue;
+  layout : layout option;
+      (** Only computed if the user asked for it, and if the layout doesn't
+          depend on the type parameters *)
+  auto_trait_impls : trait_decl_id list;
+      (** The auto traits implemented by rustc for this type, without an
+          explicit implementation. Only computed if the user asked to keep the
+          marker traits. *)
+}
+[@@deriving show]
+
+(** The validity invariant of a literal type: the bit patterns which are valid
+    values of this type, given as a list of inclusive ranges (for instance,
+    the only valid values of a boolean are 0 and 1). *)
+type literal_validity = {
+  lit_ty : literal_type;
+  lit_size : int;  (** The size of the type, in bytes *)
+  valid_ranges : (big_int * big_int) list;
+}
+[@@deriving show]
+
+(** Information which doesn't depend on the crate, but that Charon exports
+    with every crate so that the consumers don't have to hard-code it. *)
+type prelude = {
+  literal_validity : literal_validity list;
+      (** The validity invariants of the literal types which have invalid bit
+          patterns (the integers don't have any) *)
+}
+[@@deriving show]
+
+(** The endianness of a target *)
+type endianness = Little | Big [@@deriving show]
+
+(** The information about the target the extraction assumed *)
+type target_info = {
+  pointer_width : int;
+      (** The width of the pointers, in bits. This is also the width of
+          [usize] and [isize]. *)
+  endianness : endianness;
 }
 [@@deriving show]
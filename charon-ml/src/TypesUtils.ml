@@ -21,7 +21,7 @@ let as_ident (e : path_elem) : string =
   match e with PeIdent (s, _) -> s | _ -> raise (Failure "Unexpected")
 
 let type_decl_is_opaque (d : type_decl) : bool =
-  match d.kind with Struct _ | Enum _ -> false | Opaque -> true
+  match d.kind with Struct _ | Enum _ | Union _ -> false | Opaque -> true
 
 (** Retrieve the list of fields for the given variant of a {!Charon.Types.type_decl}.
 
@@ -31,7 +31,7 @@ let type_decl_get_fields (def : type_decl)
     (opt_variant_id : VariantId.id option) : field list =
   match (def.kind, opt_variant_id) with
   | Enum variants, Some variant_id -> (VariantId.nth variants variant_id).fields
-  | Struct fields, None -> fields
+  | (Struct fields | Union fields), None -> fields
   | _ ->
       let opt_variant_id =
         match opt_variant_id with None -> "None" | Some _ -> "Some"
@@ -44,7 +44,10 @@ let type_decl_get_fields (def : type_decl)
           ^ opt_variant_id))
 
 let type_decl_is_enum (def : type_decl) : bool =
-  match def.kind with Struct _ -> false | Enum _ -> true | Opaque -> false
+  match def.kind with
+  | Struct _ | Union _ -> false
+  | Enum _ -> true
+  | Opaque -> false
 
 (** Return [true] if a {!type:Charon.Types.ty} is actually [unit] *)
 let ty_is_unit (ty : ty) : bool =
@@ -217,12 +220,15 @@ let rec ty_is_primitively_copyable (ty : ty) : bool =
   match ty with
   | TAdt (TAdtId _, generics) ->
       List.for_all ty_is_primitively_copyable generics.types
-  | TAdt (TAssumed (TBox | TStr | TSlice), _) -> false
+  | TAdt
+      ( TAssumed (TBox | TVec | TRc | TArc | TCell | TRefCell | TStr | TSlice),
+        _ ) ->
+      false
   | TAdt ((TTuple | TAssumed TArray), generics) ->
       List.for_all ty_is_primitively_copyable generics.types
   | TVar _ | TNever -> false
   | TLiteral (TBool | TChar | TInteger _) -> true
-  | TTraitType _ | TArrow (_, _, _) -> false
+  | TTraitType _ | TArrow (_, _, _) | TDynTrait _ -> false
   | TRef (_, _, RMut) -> false
   | TRef (_, _, RShared) -> true
   | TRawPtr (_, _) ->
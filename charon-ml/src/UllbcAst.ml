@@ -57,7 +57,7 @@ and raw_statement =
 
 type switch =
   | If of block_id * block_id
-  | SwitchInt of integer_type * (scalar_value * block_id) list * block_id
+  | SwitchInt of integer_type * (switch_value * block_id) list * block_id
 [@@deriving
   show,
     visitors
@@ -82,15 +82,23 @@ type terminator = {
   content : raw_terminator;  (** The terminator itself *)
 }
 
+(** The optional block ids below are the cleanup blocks to go to if the
+    operation panics: they are always [None] unless we keep the unwinding
+    edges (option [--keep-unwind]). *)
 and raw_terminator =
   | Goto of block_id
   | Switch of operand * switch
-  | Panic
+  | Panic of panic_kind
   | Return
   | Unreachable
-  | Drop of place * block_id
-  | Call of call * block_id
-  | Assert of assertion * block_id
+  | Drop of place * block_id * block_id option
+  | Call of call * block_id * block_id option
+  | Assert of assertion * block_id * block_id option
+
+and panic_kind =
+  | Unwind of block_id option
+      (** Unwind the stack, starting with the cleanup block if there is one *)
+  | Abort
 [@@deriving
   show,
     visitors
@@ -52,7 +52,7 @@ let switch_of_json (js : json) : (switch, string) result =
         let* int_ty = integer_type_of_json int_ty in
         let* tgts =
           list_of_json
-            (pair_of_json scalar_value_of_json BlockId.id_of_json)
+            (pair_of_json switch_value_of_json BlockId.id_of_json)
             tgts
         in
         let* otherwise = BlockId.id_of_json otherwise in
@@ -62,11 +62,11 @@ let switch_of_json (js : json) : (switch, string) result =
 let call_of_json (js : json) : (raw_terminator, string) result =
   combine_error_msgs js __FUNCTION__
     (match js with
-    | `Assoc [ ("call", call); ("target", target) ] ->
+    | `Assoc [ ("call", call); ("target", target); ("unwind", unwind) ] ->
         let* call = call_of_json call in
         let* target = BlockId.id_of_json target in
-
-        Ok (Call (call, target))
+        let* unwind = option_of_json BlockId.id_of_json unwind in
+        Ok (Call (call, target, unwind))
     | _ -> Error "")
 
 let rec terminator_of_json (id_to_file : id_to_file_map) (js : json) :
@@ -90,24 +90,49 @@ and raw_terminator_of_json (js : json) : (raw_terminator, string) result =
         let* discr = operand_of_json discr in
         let* targets = switch_of_json targets in
         Ok (Switch (discr, targets))
-    | `String "Panic" -> Ok Panic
+    | `Assoc [ ("Panic", kind) ] ->
+        let* kind = panic_kind_of_json kind in
+        Ok (Panic kind)
     | `String "Return" -> Ok Return
     | `String "Unreachable" -> Ok Unreachable
-    | `Assoc [ ("Drop", `Assoc [ ("place", place); ("target", target) ]) ] ->
+    | `Assoc
+        [
+          ( "Drop",
+            `Assoc [ ("place", place); ("target", target); ("unwind", unwind) ]
+          );
+        ] ->
         let* place = place_of_json place in
         let* target = BlockId.id_of_json target in
-        Ok (Drop (place, target))
+        let* unwind = option_of_json BlockId.id_of_json unwind in
+        Ok (Drop (place, target, unwind))
     | `Assoc [ ("Call", call) ] -> call_of_json call
     | `Assoc
         [
           ( "Assert",
             `Assoc
-              [ ("cond", cond); ("expected", expected); ("target", target) ] );
+              [
+                ("cond", cond);
+                ("expected", expected);
+                ("kind", kind);
+                ("target", target);
+                ("unwind", unwind);
+              ] );
         ] ->
         let* cond = operand_of_json cond in
         let* expected = bool_of_json expected in
+        let* kind = assert_kind_of_json kind in
         let* target = BlockId.id_of_json target in
-        Ok (Assert ({ cond; expected }, target))
+        let* unwind = option_of_json BlockId.id_of_json unwind in
+        Ok (Assert ({ cond; expected; kind }, target, unwind))
+    | _ -> Error "")
+
+and panic_kind_of_json (js : json) : (panic_kind, string) result =
+  combine_error_msgs js __FUNCTION__
+    (match js with
+    | `Assoc [ ("Unwind", unwind) ] ->
+        let* unwind = option_of_json BlockId.id_of_json unwind in
+        Ok (Unwind unwind)
+    | `String "Abort" -> Ok Abort
     | _ -> Error "")
 
 let block_of_json (id_to_file : id_to_file_map) (js : json) :
@@ -138,11 +163,15 @@ let global_decl_of_json (id_to_file : id_to_file_map) (js : json) :
     (gglobal_decl_of_json (blocks_of_json id_to_file) id_to_file js)
 
 let crate_of_json (js : json) : (crate, string) result =
+  let* header = crate_header_of_json js in
   combine_error_msgs js __FUNCTION__
     (match js with
     | `Assoc
         [
+          ("header", _);
           ("name", name);
+          ("prelude", prelude);
+          ("target_info", target_info);
           ("id_to_file", id_to_file);
           ("declarations", declarations);
           ("types", types);
@@ -150,8 +179,13 @@ let crate_of_json (js : json) : (crate, string) result =
           ("globals", globals);
           ("trait_decls", trait_decls);
           ("trait_impls", trait_impls);
+          ("impl_blocks", impl_blocks);
+          ("opaque_warnings", opaque_warnings);
+          ("index", _);
         ] ->
         let* name = string_of_json name in
+        let* prelude = prelude_of_json prelude in
+        let* target_info = target_info_of_json target_info in
         let* id_to_file = id_to_file_of_json id_to_file in
         let* declarations =
           list_of_json declaration_group_of_json declarations
@@ -165,6 +199,9 @@ let crate_of_json (js : json) : (crate, string) result =
         let* trait_impls =
           list_of_json (trait_impl_of_json id_to_file) trait_impls
         in
+        let* impl_blocks =
+          list_of_json (impl_block_of_json id_to_file) impl_blocks
+        in
         let type_decls =
           TypeDeclId.Map.of_list
             (List.map (fun (d : type_decl) -> (d.def_id, d)) types)
@@ -185,14 +222,26 @@ let crate_of_json (js : json) : (crate, string) result =
           TraitImplId.Map.of_list
             (List.map (fun (d : trait_impl) -> (d.def_id, d)) trait_impls)
         in
+        let impl_blocks =
+          ImplBlockId.Map.of_list
+            (List.map (fun (d : impl_block) -> (d.def_id, d)) impl_blocks)
+        in
+        let* opaque_warnings =
+          list_of_json (opaque_warning_of_json id_to_file) opaque_warnings
+        in
         Ok
           {
+            header;
             name;
+            prelude;
+            target_info;
             declarations;
             type_decls;
             fun_decls;
             global_decls;
             trait_decls;
             trait_impls;
+            impl_blocks;
+            opaque_warnings;
           }
     | _ -> Error "")
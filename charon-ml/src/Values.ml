@@ -79,7 +79,22 @@ and scalar_value = { value : big_int; int_ty : integer_type }
     Can be used by operands (in which case it represents a constant) or by
     the interpreter to represent a concrete, literal value.
  *)
-and literal = VScalar of scalar_value | VBool of bool | VChar of char
+and literal =
+  | VScalar of scalar_value
+  | VBool of bool
+  | VChar of char
+  | VStr of string  (** A string literal (of type [&str]) *)
+  | VByteStr of int list
+      (** A byte-string literal (of type [&[u8; N]]): we store the raw bytes *)
+
+(** A value (or a range of values) a switch over integers can branch on.
+
+    The ranges group the consecutive values which lead to the same branch.
+ *)
+and switch_value =
+  | SVValue of scalar_value
+  | SVRange of scalar_value * scalar_value
+      (** A range of values (the bounds are included) *)
 [@@deriving
   show,
     ord,
@@ -0,0 +1,16 @@
+//! Retrieve the revision of hax we depend on from the lock file, so that we
+//! can record it in the generated files (see `src/header.rs`).
+
+fn main() {
+    println!("cargo:rerun-if-changed=Cargo.lock");
+    let lock = std::fs::read_to_string("Cargo.lock").unwrap_or_default();
+    // The source of the package is of the shape:
+    // `git+https://github.com/hacspec/hacspec-v2.git?branch=...#REVISION`
+    let version = lock
+        .split("[[package]]")
+        .find(|package| package.contains("name = \"hax-frontend-exporter\""))
+        .and_then(|package| package.lines().find(|l| l.starts_with("source = ")))
+        .and_then(|source| source.trim_end_matches('"').rsplit_once('#'))
+        .map_or("unknown", |(_, rev)| rev);
+    println!("cargo:rustc-env=CHARON_HAX_VERSION={version}");
+}
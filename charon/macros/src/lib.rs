@@ -99,6 +99,16 @@ pub mod {} {{
             serializer.serialize_u32(self.index as u32)
         }}
     }}
+
+    impl<'de> serde::Deserialize<'de> for Id {{
+        fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+        where
+            D: serde::Deserializer<'de>,
+        {{
+            let index = <u32 as serde::Deserialize>::deserialize(deserializer)?;
+            Ok(Id::new(index as usize))
+        }}
+    }}
  
     impl Generator {{
         pub fn new() -> Generator {{
@@ -118,6 +128,11 @@ pub mod {} {{
             self.counter = self.counter.checked_add(1).unwrap();
             index
         }}
+
+        /// The id the next call to [Self::fresh_id] will return.
+        pub fn peek(&self) -> Id {{
+            Id::new(self.counter)
+        }}
     }}
 
     // TODO: factor this out in a specific file, make it an immutable map to have O(1) clone
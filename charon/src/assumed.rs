@@ -9,28 +9,37 @@ use crate::names::*;
 use crate::types::*;
 use crate::ullbc_ast;
 use macros::EnumIsA;
+use serde::Deserialize;
+use std::path::Path;
 
-/// Ignore the builtin/auto traits like [core::marker::Sized] or [core::marker::Sync].
-pub const IGNORE_BUILTIN_MARKER_TRAITS: bool = true;
-
-// Ignored traits (includes marker traits, and others)
+// The builtin/auto traits like [core::marker::Sized] or [core::marker::Sync]:
+// we ignore them, unless the user asks to keep them (see
+// [crate::cli_options::CliOpts::keep_marker_traits])
 pub static MARKER_SIZED_NAME: [&str; 3] = ["core", "marker", "Sized"];
 pub static MARKER_TUPLE_NAME: [&str; 3] = ["core", "marker", "Tuple"];
 pub static SYNC_NAME: [&str; 3] = ["core", "marker", "SYNC"];
 pub static SEND_NAME: [&str; 3] = ["core", "marker", "SEND"];
 pub static UNPIN_NAME: [&str; 3] = ["core", "marker", "UNPIN"];
-pub static ALLOC_ALLOCATOR: [&str; 3] = ["core", "alloc", "Allocator"];
-pub static IGNORED_TRAITS_NAMES: [&[&str]; 6] = [
+pub static MARKER_TRAITS_NAMES: [&[&str]; 5] = [
     &MARKER_SIZED_NAME,
     &MARKER_TUPLE_NAME,
     &SYNC_NAME,
     &SEND_NAME,
     &UNPIN_NAME,
-    &ALLOC_ALLOCATOR,
 ];
 
+// The traits we always ignore: we ignore the allocators (see
+// [type_to_used_params])
+pub static ALLOC_ALLOCATOR: [&str; 3] = ["core", "alloc", "Allocator"];
+pub static IGNORED_TRAITS_NAMES: [&[&str]; 1] = [&ALLOC_ALLOCATOR];
+
 // Assumed types
 pub static BOX_NAME: [&str; 3] = ["alloc", "boxed", "Box"];
+pub static VEC_NAME: [&str; 3] = ["alloc", "vec", "Vec"];
+pub static RC_NAME: [&str; 3] = ["alloc", "rc", "Rc"];
+pub static ARC_NAME: [&str; 3] = ["alloc", "sync", "Arc"];
+pub static CELL_NAME: [&str; 3] = ["core", "cell", "Cell"];
+pub static REF_CELL_NAME: [&str; 3] = ["core", "cell", "RefCell"];
 
 //
 // Assumed functions
@@ -39,9 +48,41 @@ pub static PANIC_NAME: [&str; 3] = ["core", "panicking", "panic"];
 pub static BEGIN_PANIC_NAME: [&str; 3] = ["std", "panicking", "begin_panic"];
 pub static ASSERT_FAILED_NAME: [&str; 3] = ["core", "panicking", "assert_failed"];
 
+// The formatting machinery (see [crate::cli_options::CliOpts::fmt_machinery]):
+// we consider all the functions inside those modules
+pub static CORE_FMT_NAME: [&str; 2] = ["core", "fmt"];
+pub static ALLOC_FMT_NAME: [&str; 2] = ["alloc", "fmt"];
+// The functions called by `print!` and `eprint!`
+pub static PRINT_NAME: [&str; 4] = ["std", "io", "stdio", "_print"];
+pub static EPRINT_NAME: [&str; 4] = ["std", "io", "stdio", "_eprint"];
+
 // Boxes - remark: there misses `Box::new` which has an impl block (TODO: remove?)
 // Only Box::free needs to have a special treatment.
 pub static BOX_FREE_NAME: [&str; 3] = ["alloc", "alloc", "box_free"];
+// The allocation of the boxes, when the MIR doesn't call `Box::new` (see
+// [crate::reconstruct_box_new])
+pub static EXCHANGE_MALLOC_NAME: [&str; 3] = ["alloc", "alloc", "exchange_malloc"];
+
+// The modules which contain the inherent impl blocks of the types whose
+// methods we model (see [BUILTIN_METHODS])
+pub static BOXED_MODULE: [&str; 2] = ["alloc", "boxed"];
+pub static VEC_MODULE: [&str; 2] = ["alloc", "vec"];
+pub static OPTION_MODULE: [&str; 2] = ["core", "option"];
+
+// Memory
+pub static MEM_SWAP_NAME: [&str; 3] = ["core", "mem", "swap"];
+pub static MEM_REPLACE_NAME: [&str; 3] = ["core", "mem", "replace"];
+pub static MEM_TAKE_NAME: [&str; 3] = ["core", "mem", "take"];
+
+// The intrinsics we model (see [get_intrinsic_id])
+pub static TRANSMUTE_NAME: [&str; 3] = ["core", "intrinsics", "transmute"];
+pub static COPY_NONOVERLAPPING_NAME: [&str; 3] = ["core", "intrinsics", "copy_nonoverlapping"];
+pub static DISCRIMINANT_VALUE_NAME: [&str; 3] = ["core", "intrinsics", "discriminant_value"];
+pub static SIZE_OF_VAL_NAME: [&str; 3] = ["core", "intrinsics", "size_of_val"];
+
+// Indexing (see [trait_method_to_assumed])
+pub static INDEX_NAME: [&str; 5] = ["core", "ops", "index", "Index", "index"];
+pub static INDEX_MUT_NAME: [&str; 5] = ["core", "ops", "index", "IndexMut", "index_mut"];
 
 // Pointers
 pub static PTR_UNIQUE_NAME: [&str; 3] = ["core", "ptr", "Unique"];
@@ -53,17 +94,240 @@ pub static PTR_NON_NULL_NAME: [&str; 3] = ["core", "ptr", "NonNull"];
 ///   to functions: there are thus missing identifiers.
 /// - some of the ids here are actually traits, that we disambiguate later
 /// TODO: merge with the other enum?
-#[derive(EnumIsA)]
-enum FunId {
+///
+/// Those are the builtin ids the users can map their own functions to (see
+/// [AssumedFuns]).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIsA, Deserialize)]
+pub enum FunId {
     /// `core::panicking::panic`
     Panic,
     /// `std::panicking::begin_panic` - TODO: remove?
     BeginPanic,
+    /// `core::panicking::assert_failed`, called by `assert_eq!` and
+    /// `assert_ne!`
+    AssertFailed,
     BoxNew,
     BoxFree,
+    VecNew,
+    VecPush,
+    VecLen,
+    OptionUnwrap,
+    OptionIsSome,
+    MemSwap,
+    MemReplace,
+    MemTake,
+}
+
+/// The information about the parameters of an assumed function.
+#[derive(Debug, Clone)]
+pub struct FunInfo {
+    /// The type parameters we keep (`None` if we keep all of them)
+    pub used_type_params: Option<Vec<bool>>,
+    /// The arguments we keep (`None` if we keep all of them)
+    // TODO: rename. "value_args"?
+    pub used_args: Option<Vec<bool>>,
+}
+
+/// The builtin table of assumed functions, identified by their paths.
+///
+/// Remark: the methods like `Box::new` are not in this table because their
+/// names contain impl blocks (see [BUILTIN_METHODS]).
+static BUILTIN_FUNS: [(&[&str], FunId); 7] = [
+    (&PANIC_NAME, FunId::Panic),
+    (&BEGIN_PANIC_NAME, FunId::BeginPanic),
+    (&ASSERT_FAILED_NAME, FunId::AssertFailed),
+    (&BOX_FREE_NAME, FunId::BoxFree),
+    (&MEM_SWAP_NAME, FunId::MemSwap),
+    (&MEM_REPLACE_NAME, FunId::MemReplace),
+    (&MEM_TAKE_NAME, FunId::MemTake),
+];
+
+/// The builtin table of assumed methods, which are defined in inherent impl
+/// blocks: we identify them by the module which contains the impl block, the
+/// type of the impl block (`None` if it is not an assumed type, like
+/// `Option`) and the name of the method.
+static BUILTIN_METHODS: [(&[&str], Option<AssumedTy>, &str, FunId); 6] = [
+    (&BOXED_MODULE, Some(AssumedTy::Box), "new", FunId::BoxNew),
+    (&VEC_MODULE, Some(AssumedTy::Vec), "new", FunId::VecNew),
+    (&VEC_MODULE, Some(AssumedTy::Vec), "push", FunId::VecPush),
+    (&VEC_MODULE, Some(AssumedTy::Vec), "len", FunId::VecLen),
+    (&OPTION_MODULE, None, "unwrap", FunId::OptionUnwrap),
+    (&OPTION_MODULE, None, "is_some", FunId::OptionIsSome),
+];
+
+/// The default information about the parameters of the builtin functions.
+///
+/// Remark: the methods of vectors are parameterized by an allocator, which we
+/// ignore (see [type_to_used_params]).
+fn builtin_info(id: FunId) -> FunInfo {
+    let (used_type_params, used_args) = match id {
+        FunId::Panic => (vec![], vec![true]),
+        FunId::BeginPanic => (vec![true], vec![true]),
+        // `assert_failed<T, U>(kind, left: &T, right: &U, args)`
+        FunId::AssertFailed => (vec![true, true], vec![true, true, true, true]),
+        FunId::BoxNew => (vec![true], vec![true]),
+        FunId::BoxFree => (vec![true, false], vec![true, false]),
+        FunId::VecNew => (vec![true], vec![]),
+        FunId::VecPush => (vec![true, false], vec![true, true]),
+        FunId::VecLen => (vec![true, false], vec![true]),
+        FunId::OptionUnwrap | FunId::OptionIsSome => (vec![true], vec![true]),
+        FunId::MemSwap | FunId::MemReplace => (vec![true], vec![true, true]),
+        FunId::MemTake => (vec![true], vec![true]),
+    };
+    FunInfo {
+        used_type_params: Some(used_type_params),
+        used_args: Some(used_args),
+    }
+}
+
+/// A function declared as assumed in the file given by the user (see
+/// [crate::cli_options::CliOpts::assumed_funs]).
+#[derive(Deserialize)]
+struct AssumedFunDecl {
+    /// The path to the function (ex.: `my_crate::module::fatal_error`)
+    path: String,
+    /// The builtin function it maps to
+    builtin: FunId,
+    /// The type parameters we keep (all of them by default)
+    used_type_params: Option<Vec<bool>>,
+    /// The arguments we keep (all of them by default)
+    used_args: Option<Vec<bool>>,
+}
+
+/// The content of the file given by the user.
+#[derive(Deserialize)]
+struct AssumedFunsFile {
+    functions: Vec<AssumedFunDecl>,
+}
+
+/// The registry of the assumed functions: the builtin table, extended with
+/// the functions declared by the user.
+pub struct AssumedFuns {
+    /// The paths, the builtin ids and the information about the parameters.
+    /// The user declarations come first, so that they take precedence over
+    /// the builtin table.
+    funs: Vec<(Vec<String>, FunId, FunInfo)>,
+}
+
+impl AssumedFuns {
+    /// The registry which only contains the builtin table.
+    pub fn builtin() -> Self {
+        let funs = BUILTIN_FUNS
+            .iter()
+            .map(|(path, id)| {
+                let path = path.iter().map(|s| s.to_string()).collect();
+                (path, *id, builtin_info(*id))
+            })
+            .collect();
+        AssumedFuns { funs }
+    }
+
+    /// Extend the builtin table with the functions declared in a file. The
+    /// file can be a TOML file (if its extension is `.toml`) or a JSON file:
+    /// ```toml
+    /// [[functions]]
+    /// path = "my_crate::fatal_error"
+    /// builtin = "Panic"
+    /// used_args = [true, false]
+    /// ```
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let content = match std::fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(err) => return Err(format!("Could not read {path:?}: {err}")),
+        };
+        let file: AssumedFunsFile = if path.extension().map_or(false, |ext| ext == "toml") {
+            toml::from_str(&content).map_err(|err| format!("Could not parse {path:?}: {err}"))?
+        } else {
+            serde_json::from_str(&content)
+                .map_err(|err| format!("Could not parse {path:?}: {err}"))?
+        };
+
+        let mut funs: Vec<(Vec<String>, FunId, FunInfo)> = file
+            .functions
+            .into_iter()
+            .map(|decl| {
+                let path = decl.path.split("::").map(|s| s.to_string()).collect();
+                let info = FunInfo {
+                    used_type_params: decl.used_type_params,
+                    used_args: decl.used_args,
+                };
+                (path, decl.builtin, info)
+            })
+            .collect();
+        funs.extend(AssumedFuns::builtin().funs);
+        Ok(AssumedFuns { funs })
+    }
+
+    /// Lookup a function in the registry.
+    fn lookup(&self, name: &Name) -> Option<(FunId, FunInfo)> {
+        for (path, id, info) in &self.funs {
+            let path: Vec<&str> = path.iter().map(|s| s.as_str()).collect();
+            if name.equals_ref_name(&path) {
+                return Some((*id, info.clone()));
+            }
+        }
+        for (module, ty, method, id) in &BUILTIN_METHODS {
+            if is_inherent_method(name, module, *ty, method) {
+                return Some((*id, builtin_info(*id)));
+            }
+        }
+        None
+    }
+
+    /// Return `true` if the function panics (we don't translate the calls to
+    /// those functions to function calls, but to panics).
+    pub fn is_panic(&self, name: &Name) -> bool {
+        matches!(
+            self.lookup(name),
+            Some((FunId::Panic | FunId::BeginPanic | FunId::AssertFailed, _))
+        )
+    }
+
+    /// Return `true` if the function deallocates a box (this function requires
+    /// a special treatment: see [ullbc_ast::AssumedFunId::BoxFree]).
+    pub fn is_box_free(&self, name: &Name) -> bool {
+        matches!(self.lookup(name), Some((FunId::BoxFree, _)))
+    }
+
+    pub fn get_fun_id_from_name(&self, name: &Name) -> Option<ullbc_ast::AssumedFunId> {
+        match self.lookup(name) {
+            Option::Some((id, _)) => {
+                let id = match id {
+                    FunId::Panic | FunId::BeginPanic | FunId::AssertFailed => unreachable!(),
+                    FunId::BoxNew => ullbc_ast::AssumedFunId::BoxNew,
+                    FunId::BoxFree => ullbc_ast::AssumedFunId::BoxFree,
+                    FunId::VecNew => ullbc_ast::AssumedFunId::VecNew,
+                    FunId::VecPush => ullbc_ast::AssumedFunId::VecPush,
+                    FunId::VecLen => ullbc_ast::AssumedFunId::VecLen,
+                    FunId::OptionUnwrap => ullbc_ast::AssumedFunId::OptionUnwrap,
+                    FunId::OptionIsSome => ullbc_ast::AssumedFunId::OptionIsSome,
+                    FunId::MemSwap => ullbc_ast::AssumedFunId::MemSwap,
+                    FunId::MemReplace => ullbc_ast::AssumedFunId::MemReplace,
+                    FunId::MemTake => ullbc_ast::AssumedFunId::MemTake,
+                };
+                Option::Some(id)
+            }
+            Option::None => Option::None,
+        }
+    }
+
+    /// See the comments for [type_to_used_params]
+    pub fn function_to_info(&self, name: &Name) -> Option<FunInfo> {
+        trace!("{:?}", name);
+        self.lookup(name).map(|(_, info)| info)
+    }
 }
 
 pub fn is_marker_trait(name: &Name) -> bool {
+    for n in MARKER_TRAITS_NAMES {
+        if name.equals_ref_name(n) {
+            return true;
+        }
+    }
+    false
+}
+
+pub fn is_ignored_trait(name: &Name) -> bool {
     for n in IGNORED_TRAITS_NAMES {
         if name.equals_ref_name(n) {
             return true;
@@ -72,6 +336,16 @@ pub fn is_marker_trait(name: &Name) -> bool {
     false
 }
 
+/// Return `true` if the function belongs to the formatting machinery
+pub fn is_fmt_machinery(name: &Name) -> bool {
+    name.prefix_is_same(&CORE_FMT_NAME) || name.prefix_is_same(&ALLOC_FMT_NAME)
+}
+
+/// Return `true` if the function is called by `print!` or `eprint!`
+pub fn is_print(name: &Name) -> bool {
+    name.equals_ref_name(&PRINT_NAME) || name.equals_ref_name(&EPRINT_NAME)
+}
+
 pub fn get_type_id_from_name(name: &Name) -> Option<AssumedTy> {
     if name.equals_ref_name(&BOX_NAME) {
         Option::Some(AssumedTy::Box)
@@ -79,6 +353,16 @@ pub fn get_type_id_from_name(name: &Name) -> Option<AssumedTy> {
         Option::Some(AssumedTy::PtrUnique)
     } else if name.equals_ref_name(&PTR_NON_NULL_NAME) {
         Option::Some(AssumedTy::PtrNonNull)
+    } else if name.equals_ref_name(&VEC_NAME) {
+        Option::Some(AssumedTy::Vec)
+    } else if name.equals_ref_name(&RC_NAME) {
+        Option::Some(AssumedTy::Rc)
+    } else if name.equals_ref_name(&ARC_NAME) {
+        Option::Some(AssumedTy::Arc)
+    } else if name.equals_ref_name(&CELL_NAME) {
+        Option::Some(AssumedTy::Cell)
+    } else if name.equals_ref_name(&REF_CELL_NAME) {
+        Option::Some(AssumedTy::RefCell)
     } else {
         Option::None
     }
@@ -89,68 +373,114 @@ pub fn get_name_from_type_id(id: AssumedTy) -> Vec<String> {
         AssumedTy::Box => BOX_NAME.iter().map(|s| s.to_string()).collect(),
         AssumedTy::PtrUnique => PTR_UNIQUE_NAME.iter().map(|s| s.to_string()).collect(),
         AssumedTy::PtrNonNull => PTR_NON_NULL_NAME.iter().map(|s| s.to_string()).collect(),
+        AssumedTy::Vec => VEC_NAME.iter().map(|s| s.to_string()).collect(),
+        AssumedTy::Rc => RC_NAME.iter().map(|s| s.to_string()).collect(),
+        AssumedTy::Arc => ARC_NAME.iter().map(|s| s.to_string()).collect(),
+        AssumedTy::Cell => CELL_NAME.iter().map(|s| s.to_string()).collect(),
+        AssumedTy::RefCell => REF_CELL_NAME.iter().map(|s| s.to_string()).collect(),
         AssumedTy::Str => vec!["Str".to_string()],
         AssumedTy::Array => vec!["Array".to_string()],
         AssumedTy::Slice => vec!["Slice".to_string()],
     }
 }
 
-fn get_fun_id_from_name_full(name: &Name) -> Option<FunId> {
-    if name.equals_ref_name(&PANIC_NAME) {
-        Option::Some(FunId::Panic)
-    } else if name.equals_ref_name(&BEGIN_PANIC_NAME) {
-        Option::Some(FunId::BeginPanic)
-    } else if name.equals_ref_name(&BOX_FREE_NAME) {
-        Option::Some(FunId::BoxFree)
-    } else {
-        // Box::new is peculiar because there is an impl block
-        use PathElem::*;
-        match name.name.as_slice() {
-            [Ident(alloc, _), Ident(boxed, _), Impl(impl_elem), Ident(new, _)] => {
-                if alloc == "alloc" && boxed == "boxed" && new == "new" {
-                    match &impl_elem.ty {
-                        Ty::Adt(TypeId::Assumed(AssumedTy::Box), generics) => {
-                            let GenericArgs {
-                                regions,
-                                types,
-                                const_generics,
-                                trait_refs,
-                            } = generics;
-                            if regions.is_empty()
-                                && types.len() == 1
-                                && const_generics.is_empty()
-                                && trait_refs.is_empty()
-                            {
-                                match types.as_slice() {
-                                    [Ty::TypeVar(_)] => Option::Some(FunId::BoxNew),
-                                    _ => Option::None,
-                                }
-                            } else {
-                                Option::None
-                            }
-                        }
-                        _ => Option::None,
+/// Check if a name is of the shape `module::{impl Ty<T>}::method`, where
+/// `impl Ty<T>` is an inherent impl block for a type with a single type
+/// parameter (ex.: `alloc::boxed::{impl Box<T>}::new`).
+///
+/// If `ty` is `None`, we don't check the identifier of the type: the module
+/// should be enough to identify it.
+fn is_inherent_method(name: &Name, module: &[&str], ty: Option<AssumedTy>, method: &str) -> bool {
+    use PathElem::*;
+    match name.name.as_slice() {
+        [prefix @ .., Impl(impl_elem), Ident(m, _)] => {
+            if prefix.len() == module.len()
+                && prefix
+                    .iter()
+                    .zip(module.iter())
+                    .all(|(elem, id)| matches!(elem, Ident(s, _) if s == id))
+                && m == method
+            {
+                match &impl_elem.ty {
+                    Ty::Adt(id, generics) => {
+                        let GenericArgs {
+                            regions,
+                            types,
+                            const_generics,
+                            trait_refs,
+                        } = generics;
+                        let id_is_ok = match (ty, id) {
+                            (Some(ty), TypeId::Assumed(aty)) => ty == *aty,
+                            (None, TypeId::Adt(_)) => true,
+                            _ => false,
+                        };
+                        id_is_ok
+                            && regions.is_empty()
+                            && const_generics.is_empty()
+                            && trait_refs.is_empty()
+                            && matches!(types.as_slice(), [Ty::TypeVar(_)])
                     }
-                } else {
-                    Option::None
+                    _ => false,
                 }
+            } else {
+                false
             }
-            _ => Option::None,
         }
+        _ => false,
     }
 }
 
-pub fn get_fun_id_from_name(name: &Name) -> Option<ullbc_ast::AssumedFunId> {
-    match get_fun_id_from_name_full(name) {
-        Option::Some(id) => {
-            let id = match id {
-                FunId::Panic | FunId::BeginPanic => unreachable!(),
-                FunId::BoxNew => ullbc_ast::AssumedFunId::BoxNew,
-                FunId::BoxFree => ullbc_ast::AssumedFunId::BoxFree,
+/// If the function is one of the intrinsics we model, return its identifier.
+///
+/// Remark: we don't give those functions a special treatment in the
+/// [AssumedFuns] registry, because we may have to make the functions which
+/// call them opaque (see [crate::cli_options::CliOpts::intrinsics]).
+pub fn get_intrinsic_id(name: &Name) -> Option<ullbc_ast::AssumedFunId> {
+    use ullbc_ast::AssumedFunId;
+    if name.equals_ref_name(&TRANSMUTE_NAME) {
+        Some(AssumedFunId::Transmute)
+    } else if name.equals_ref_name(&COPY_NONOVERLAPPING_NAME) {
+        Some(AssumedFunId::CopyNonOverlapping)
+    } else if name.equals_ref_name(&DISCRIMINANT_VALUE_NAME) {
+        Some(AssumedFunId::DiscriminantValue)
+    } else if name.equals_ref_name(&SIZE_OF_VAL_NAME) {
+        Some(AssumedFunId::SizeOfVal)
+    } else {
+        None
+    }
+}
+
+/// Some trait methods are modelled by assumed functions when they are called
+/// on specific types: indexing a vector or a slice with a `usize`. We can't
+/// identify those calls from the name only: we also need the generic
+/// arguments of the trait method (the first type is the `Self` type).
+///
+/// Return the assumed function and its generic arguments.
+pub fn trait_method_to_assumed(
+    name: &Name,
+    generics: &GenericArgs,
+) -> Option<(ullbc_ast::AssumedFunId, GenericArgs)> {
+    use ullbc_ast::AssumedFunId;
+    let is_mut = if name.equals_ref_name(&INDEX_NAME) {
+        false
+    } else if name.equals_ref_name(&INDEX_MUT_NAME) {
+        true
+    } else {
+        return None;
+    };
+    let usize_ty = Ty::Literal(LiteralTy::Integer(IntegerTy::Usize));
+    match generics.types.as_slice() {
+        [Ty::Adt(TypeId::Assumed(aty), self_generics), idx_ty] if *idx_ty == usize_ty => {
+            let id = match (aty, is_mut) {
+                (AssumedTy::Vec, false) => AssumedFunId::VecIndexShared,
+                (AssumedTy::Vec, true) => AssumedFunId::VecIndexMut,
+                (AssumedTy::Slice, false) => AssumedFunId::SliceIndexShared,
+                (AssumedTy::Slice, true) => AssumedFunId::SliceIndexMut,
+                _ => return None,
             };
-            Option::Some(id)
+            Some((id, GenericArgs::new_from_types(self_generics.types.clone())))
         }
-        Option::None => Option::None,
+        _ => None,
     }
 }
 
@@ -158,18 +488,29 @@ pub fn get_fun_id_from_name(name: &Name) -> Option<ullbc_ast::AssumedFunId> {
 /// assumed types.
 /// For instance, many types like box or vec are parameterized (in MIR) by an allocator
 /// (`std::alloc::Allocator`): we ignore it.
-pub fn type_to_used_params(name: &Name) -> Option<Vec<bool>> {
+///
+/// `num_params` is the number of generic arguments of the type in the MIR: `Rc`
+/// and `Arc` are parameterized by an allocator only in the recent versions of
+/// the standard library.
+pub fn type_to_used_params(name: &Name, num_params: usize) -> Option<Vec<bool>> {
     trace!("{:?}", name);
     match get_type_id_from_name(name) {
         Option::None => Option::None,
         Option::Some(id) => {
             let id = match id {
-                AssumedTy::Box => {
+                AssumedTy::Box | AssumedTy::Vec => {
                     vec![true, false]
                 }
+                AssumedTy::Rc | AssumedTy::Arc => {
+                    // Ignore the allocator, if there is one
+                    let mut used = vec![true];
+                    used.resize(num_params.max(1), false);
+                    used
+                }
                 AssumedTy::PtrUnique | AssumedTy::PtrNonNull => {
                     vec![true]
                 }
+                AssumedTy::Cell | AssumedTy::RefCell => vec![true],
                 AssumedTy::Str => {
                     vec![]
                 }
@@ -179,38 +520,3 @@ pub fn type_to_used_params(name: &Name) -> Option<Vec<bool>> {
         }
     }
 }
-
-pub struct FunInfo {
-    pub used_type_params: Vec<bool>,
-    // TODO: rename. "value_args"?
-    pub used_args: Vec<bool>,
-}
-
-/// See the comments for [type_to_used_params]
-pub fn function_to_info(name: &Name) -> Option<FunInfo> {
-    trace!("{:?}", name);
-    match get_fun_id_from_name_full(name) {
-        Option::None => Option::None,
-        Option::Some(id) => {
-            let info = match id {
-                FunId::Panic => FunInfo {
-                    used_type_params: vec![],
-                    used_args: vec![true],
-                },
-                FunId::BeginPanic => FunInfo {
-                    used_type_params: vec![true],
-                    used_args: vec![true],
-                },
-                FunId::BoxNew => FunInfo {
-                    used_type_params: vec![true],
-                    used_args: vec![true],
-                },
-                FunId::BoxFree => FunInfo {
-                    used_type_params: vec![true, false],
-                    used_args: vec![true, false],
-                },
-            };
-            Option::Some(info)
-        }
-    }
-}
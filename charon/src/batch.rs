@@ -0,0 +1,413 @@
+//! Extract several crates in a row (for instance, a crate and its
+//! dependencies), while sharing the file table and the id spaces.
+//!
+//! The crates are extracted by separate invocations of the driver: we share
+//! the state between those invocations through a *batch index*, a JSON file
+//! which records the file table, the first free id of every id space, and
+//! the crates extracted so far. Every invocation starts from the state
+//! recorded in the index, then registers the crate it extracted. As a
+//! consequence, the ids of the declarations of the different crates are
+//! disjoint, and the file ids are the same in all the generated files: we can
+//! combine those files without renumbering anything (see [merge]).
+//!
+//! The crates are also *linked*: the index records the local declarations of
+//! every crate, and when a crate refers to a declaration of a crate extracted
+//! before it in the batch, it reuses the id of this declaration (see
+//! [BatchIndex::seed]). Every generated file stays self-contained, as it
+//! keeps its own (opaque) copy of the external declarations it uses, but those
+//! copies have the ids of the original declarations: a reader can resolve
+//! them to the full declarations with [BatchIndex::defining_crate], and when
+//! merging the files we only keep the original declarations.
+//!
+//! Remark: the assumed types and functions (see [crate::assumed]) are not
+//! identified by ids, and are thus naturally shared. Note that the crates
+//! still keep their own copies of the declarations of the crates outside the
+//! batch (the standard library, etc.).
+
+use crate::cache::StableHash;
+use crate::cli_options::{CliOpts, MergeOpts};
+use crate::deserialize;
+use crate::deserialize::{gload, GCrate, LlbcCrate, UllbcCrate};
+use crate::export;
+use crate::gast::{GFunDecl, GGlobalDecl};
+use crate::id_vector::ToUsize;
+use crate::meta::{FileId, FileName, LocalFileId, VirtualFileId};
+use crate::reorder_decls::{AnyTransId, DeclarationGroup, GDeclarationGroup};
+use crate::translate_ctx::TransCtx;
+use crate::types::TypeDeclId;
+use crate::ullbc_ast::{FunDeclId, GlobalDeclId, ImplBlockId, TraitDeclId, TraitImplId};
+use rustc_hir::def_id::{CrateNum, DefId, StableCrateId, CRATE_DEF_ID, LOCAL_CRATE};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+/// The first free id of every id space.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct NextIds {
+    pub local_file: usize,
+    pub virtual_file: usize,
+    pub type_decl: usize,
+    pub fun_decl: usize,
+    pub global_decl: usize,
+    pub trait_decl: usize,
+    pub trait_impl: usize,
+    pub impl_block: usize,
+}
+
+impl NextIds {
+    /// Return the index of a declaration id, together with the first id of
+    /// its id space.
+    fn split(&self, id: AnyTransId) -> (usize, usize) {
+        match id {
+            AnyTransId::Type(id) => (id.to_usize(), self.type_decl),
+            AnyTransId::Fun(id) => (id.to_usize(), self.fun_decl),
+            AnyTransId::Global(id) => (id.to_usize(), self.global_decl),
+            AnyTransId::TraitDecl(id) => (id.to_usize(), self.trait_decl),
+            AnyTransId::TraitImpl(id) => (id.to_usize(), self.trait_impl),
+        }
+    }
+}
+
+/// A crate registered in a batch index.
+#[derive(Serialize, Deserialize)]
+pub struct BatchCrate {
+    pub crate_name: String,
+    /// The (absolute) path to the generated file
+    pub output_file: PathBuf,
+    /// The ids of the declarations of this crate are the ids between those
+    /// ids (included) and the first ids of the next crate (excluded).
+    pub first_ids: NextIds,
+    /// The local declarations of this crate, identified by their
+    /// [rustc_hir::def_id::DefPathHash]. The next crates of the batch reuse
+    /// those ids when they refer to those declarations.
+    pub items: Vec<(StableHash, AnyTransId)>,
+    /// The hash of the crate (its SVH), which changes whenever the crate is
+    /// modified. We use it to detect that the index is stale, i.e., that a
+    /// crate was rebuilt since we registered it (in which case its
+    /// declarations may have changed).
+    pub crate_hash: String,
+}
+
+/// A batch index (see the explanations at the top of the file).
+#[derive(Default, Serialize, Deserialize)]
+pub struct BatchIndex {
+    /// The shared file table, sorted by id
+    pub files: Vec<(FileId::Id, FileName)>,
+    pub next_ids: NextIds,
+    /// The crates extracted so far, in order
+    pub crates: Vec<BatchCrate>,
+}
+
+impl BatchIndex {
+    /// Load a batch index. If the file doesn't exist, we are extracting the
+    /// first crate of the batch: we return an empty index.
+    pub fn load(path: &Path) -> Result<BatchIndex, String> {
+        if !path.exists() {
+            return Ok(BatchIndex::default());
+        }
+        let file = match File::open(path) {
+            Ok(file) => file,
+            Err(err) => return Err(format!("Could not open {path:?}: {err}")),
+        };
+        let reader = std::io::BufReader::new(file);
+        match serde_json::from_reader(reader) {
+            Ok(index) => Ok(index),
+            Err(err) => Err(format!("Could not deserialize {path:?}: {err}")),
+        }
+    }
+
+    /// Make a fresh translation context start from the state recorded in the
+    /// index. This must be called before we register any declaration.
+    pub(crate) fn seed(&self, ctx: &mut TransCtx) {
+        // We only fill [TransCtx::file_to_id]: the files are added to
+        // [TransCtx::id_to_file] when the crate actually uses them.
+        for (id, file) in &self.files {
+            ctx.file_to_id.insert(file.clone(), *id);
+        }
+
+        let ids = &self.next_ids;
+        ctx.real_file_counter = LocalFileId::Generator::new_with_init_value(ids.local_file);
+        ctx.virtual_file_counter = VirtualFileId::Generator::new_with_init_value(ids.virtual_file);
+        ctx.type_id_map.counter = TypeDeclId::Generator::new_with_init_value(ids.type_decl);
+        ctx.fun_id_map.counter = FunDeclId::Generator::new_with_init_value(ids.fun_decl);
+        ctx.global_id_map.counter = GlobalDeclId::Generator::new_with_init_value(ids.global_decl);
+        ctx.trait_decl_id_map.counter = TraitDeclId::Generator::new_with_init_value(ids.trait_decl);
+        ctx.trait_impl_id_map.counter = TraitImplId::Generator::new_with_init_value(ids.trait_impl);
+        ctx.impl_block_id_map.counter = ImplBlockId::Generator::new_with_init_value(ids.impl_block);
+
+        // Link the declarations of the previous crates: we can only refer to
+        // the crates we depend on
+        let tcx = ctx.tcx;
+        let dependencies: HashMap<StableCrateId, CrateNum> = tcx
+            .crates(())
+            .iter()
+            .map(|krate| (tcx.stable_crate_id(*krate), *krate))
+            .collect();
+        for c in &self.crates {
+            // The items of a crate all have the same stable crate id
+            let Some((item, _)) = c.items.first() else {
+                continue;
+            };
+            let Some(krate) = dependencies.get(&item.def_path_hash().stable_crate_id()) else {
+                continue;
+            };
+            // Check that the crate didn't change since we registered it: its
+            // items may otherwise not exist anymore
+            if tcx.crate_hash(*krate).to_string() != c.crate_hash {
+                let span = tcx.def_span(CRATE_DEF_ID);
+                ctx.span_err(
+                    span,
+                    &format!(
+                        "The batch index is stale: the crate {} changed since it was \
+                         extracted, and must be extracted again",
+                        c.crate_name
+                    ),
+                );
+                continue;
+            }
+            for (item, id) in &c.items {
+                let rust_id = tcx.def_path_hash_to_def_id(item.def_path_hash(), &mut || {
+                    unreachable!("Unknown item in the batch index")
+                });
+                ctx.linked_ids.insert(rust_id, *id);
+            }
+        }
+    }
+
+    /// Find the crate which defines a declaration: a generated file may refer
+    /// to the declarations of the crates extracted before it in the batch,
+    /// and only contains opaque copies of those declarations.
+    pub fn defining_crate(&self, id: AnyTransId) -> Option<&BatchCrate> {
+        // The crates are sorted by increasing ids
+        self.crates.iter().rev().find(|c| {
+            let (index, first) = c.first_ids.split(id);
+            first <= index
+        })
+    }
+
+    /// Register a crate we just exported, and save the index.
+    #[allow(clippy::result_unit_err)]
+    pub(crate) fn register(
+        mut self,
+        ctx: &TransCtx,
+        crate_name: &str,
+        extension: &str,
+        options: &CliOpts,
+    ) -> Result<(), ()> {
+        let path = options.batch_index.as_ref().unwrap();
+        if self.crates.iter().any(|c| c.crate_name == crate_name) {
+            error!(
+                "The crate {} was already registered in the batch index: {:?}",
+                crate_name, path
+            );
+            return Err(());
+        }
+
+        // Note that the file was created when exporting the crate
+        let dest_dir = options
+            .dest_dir
+            .as_deref()
+            .map_or_else(PathBuf::new, |d| d.to_path_buf());
+        let output_file =
+            std::fs::canonicalize(dest_dir.join(format!("{crate_name}.{extension}"))).unwrap();
+
+        // Merge the file tables
+        let mut files: std::collections::BTreeMap<FileId::Id, FileName> =
+            self.files.into_iter().collect();
+        for (id, file) in &ctx.id_to_file {
+            files.insert(*id, file.clone());
+        }
+        self.files = files.into_iter().collect();
+
+        // Record the local declarations, so that the next crates can refer to
+        // them
+        let tcx = ctx.tcx;
+        let mut items = Vec::new();
+        let mut add_item = |rust_id: &DefId, id: AnyTransId| {
+            if rust_id.is_local() {
+                items.push((StableHash::of_def_id(tcx, *rust_id), id));
+            }
+        };
+        for (rust_id, id) in &ctx.type_id_map.map {
+            add_item(rust_id, AnyTransId::Type(*id));
+        }
+        for (rust_id, id) in &ctx.fun_id_map.map {
+            add_item(rust_id, AnyTransId::Fun(*id));
+        }
+        for (rust_id, id) in &ctx.global_id_map.map {
+            add_item(rust_id, AnyTransId::Global(*id));
+        }
+        for (rust_id, id) in &ctx.trait_decl_id_map.map {
+            add_item(rust_id, AnyTransId::TraitDecl(*id));
+        }
+        for (rust_id, id) in &ctx.trait_impl_id_map.map {
+            add_item(rust_id, AnyTransId::TraitImpl(*id));
+        }
+
+        let first_ids = self.next_ids;
+        self.next_ids = NextIds {
+            local_file: ctx.real_file_counter.peek().to_usize(),
+            virtual_file: ctx.virtual_file_counter.peek().to_usize(),
+            type_decl: ctx.type_id_map.counter.peek().to_usize(),
+            fun_decl: ctx.fun_id_map.counter.peek().to_usize(),
+            global_decl: ctx.global_id_map.counter.peek().to_usize(),
+            trait_decl: ctx.trait_decl_id_map.counter.peek().to_usize(),
+            trait_impl: ctx.trait_impl_id_map.counter.peek().to_usize(),
+            impl_block: ctx.impl_block_id_map.counter.peek().to_usize(),
+        };
+        self.crates.push(BatchCrate {
+            crate_name: crate_name.to_string(),
+            output_file,
+            first_ids,
+            items,
+            crate_hash: tcx.crate_hash(LOCAL_CRATE).to_string(),
+        });
+
+        match File::create(path) {
+            std::io::Result::Ok(outfile) => match serde_json::to_writer(&outfile, &self) {
+                std::result::Result::Ok(()) => {
+                    info!("Registered {} in the batch index: {:?}", crate_name, path);
+                    Ok(())
+                }
+                std::result::Result::Err(_) => {
+                    error!("Could not write to: {:?}", path);
+                    Err(())
+                }
+            },
+            std::io::Result::Err(_) => {
+                error!("Could not open: {:?}", path);
+                Err(())
+            }
+        }
+    }
+}
+
+/// Remove the declarations which don't satisfy `keep` from a declaration
+/// group. Return `None` if the group becomes empty.
+fn filter_group<Id: Copy>(
+    group: GDeclarationGroup<Id>,
+    keep: impl Fn(Id) -> bool,
+) -> Option<GDeclarationGroup<Id>> {
+    match group {
+        GDeclarationGroup::NonRec(id) => keep(id).then_some(GDeclarationGroup::NonRec(id)),
+        GDeclarationGroup::Rec(ids) => {
+            let ids: Vec<Id> = ids.into_iter().filter(|id| keep(*id)).collect();
+            (!ids.is_empty()).then_some(GDeclarationGroup::Rec(ids))
+        }
+    }
+}
+
+/// Merge the crates of a batch into a single crate.
+///
+/// This is a generic function, used both for LLBC and ULLBC.
+fn gmerge<T>(index: &BatchIndex, name: &str) -> Result<GCrate<GFunDecl<T>, GGlobalDecl<T>>, String>
+where
+    T: Clone + for<'de> Deserialize<'de>,
+{
+    // The index is not empty (we checked it in [merge])
+    let mut crates = index.crates.iter();
+    let mut merged = gload::<T>(&crates.next().unwrap().output_file)?;
+    for c in crates {
+        let krate = gload::<T>(&c.output_file)?;
+        if krate.target_info != merged.target_info {
+            return Err(format!(
+                "Can't merge crates extracted for different targets: {:?}",
+                c.output_file
+            ));
+        }
+
+        // The ids are disjoint, except for the copies of the declarations of
+        // the previous crates (see the explanations at the top of the file):
+        // we concatenate the declarations, and ignore those copies.
+        merged.id_to_file.extend(krate.id_to_file);
+        merged.opaque_warnings.extend(krate.opaque_warnings);
+        for group in krate.declarations {
+            use DeclarationGroup::*;
+            let group = match group {
+                Type(g) => filter_group(g, |id| merged.type_decls.get(id).is_none()).map(Type),
+                Fun(g) => filter_group(g, |id| merged.fun_decls.get(id).is_none()).map(Fun),
+                Global(g) => {
+                    filter_group(g, |id| merged.global_decls.get(id).is_none()).map(Global)
+                }
+                TraitDecl(g) => {
+                    filter_group(g, |id| merged.trait_decls.get(id).is_none()).map(TraitDecl)
+                }
+                TraitImpl(g) => {
+                    filter_group(g, |id| merged.trait_impls.get(id).is_none()).map(TraitImpl)
+                }
+            };
+            merged.declarations.extend(group);
+        }
+        for (id, d) in &krate.type_decls {
+            if merged.type_decls.get(*id).is_none() {
+                merged.type_decls.insert(*id, d.clone());
+            }
+        }
+        for (id, d) in &krate.fun_decls {
+            if merged.fun_decls.get(*id).is_none() {
+                merged.fun_decls.insert(*id, d.clone());
+            }
+        }
+        for (id, d) in &krate.global_decls {
+            if merged.global_decls.get(*id).is_none() {
+                merged.global_decls.insert(*id, d.clone());
+            }
+        }
+        for (id, d) in &krate.trait_decls {
+            if merged.trait_decls.get(*id).is_none() {
+                merged.trait_decls.insert(*id, d.clone());
+            }
+        }
+        for (id, d) in &krate.trait_impls {
+            if merged.trait_impls.get(*id).is_none() {
+                merged.trait_impls.insert(*id, d.clone());
+            }
+        }
+        for (id, d) in &krate.impl_blocks {
+            merged.impl_blocks.insert(*id, d.clone());
+        }
+    }
+
+    merged.name = name.to_string();
+    merged.header.crate_name = name.to_string();
+    Ok(merged)
+}
+
+/// Merge the crates registered in a batch index into a single file, which we
+/// generate next to the index.
+pub fn merge(opts: &MergeOpts) -> Result<(), String> {
+    let index = BatchIndex::load(&opts.index)?;
+    if index.crates.is_empty() {
+        return Err(format!("The batch index is empty: {:?}", opts.index));
+    }
+
+    // We can't mix the LLBC and the ULLBC
+    let extension = index.crates[0].output_file.extension();
+    if index
+        .crates
+        .iter()
+        .any(|c| c.output_file.extension() != extension)
+    {
+        return Err("Can't merge LLBC and ULLBC files".to_string());
+    }
+    let extension = extension.and_then(|ext| ext.to_str()).unwrap_or("");
+    // We generate the merged file in the format of the first crate
+    let format = deserialize::detect_format(&index.crates[0].output_file)?;
+
+    let dest_dir = opts.index.parent().unwrap_or(Path::new(""));
+    let target_filename = dest_dir.join(format!("{}.{extension}", opts.name));
+    match extension {
+        "llbc" => {
+            let krate: LlbcCrate = gmerge(&index, &opts.name)?;
+            export::export_crate(&krate, &target_filename, format)?
+        }
+        "ullbc" => {
+            let krate: UllbcCrate = gmerge(&index, &opts.name)?;
+            export::export_crate(&krate, &target_filename, format)?
+        }
+        _ => return Err(format!("Unexpected file extension: {extension}")),
+    }
+    info!("Generated the file: {:?}", target_filename);
+    Ok(())
+}
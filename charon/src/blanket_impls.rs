@@ -0,0 +1,111 @@
+//! Report the call sites whose trait resolution went through a blanket
+//! implementation, and export the report to a JSON file.
+//!
+//! A blanket implementation is an implementation for a type parameter, like
+//! `impl<T: Foo> Bar for T`. When calling a method of `Bar` on a concrete type,
+//! we resolve the instance to the blanket implementation (instantiated with the
+//! concrete type): the implementation which is actually used is then given by
+//! the trait clauses of the blanket implementation, which makes those calls
+//! harder to reason about.
+
+use crate::expressions::SharedExprVisitor;
+use crate::formatter::{Formatter, IntoFormatter, SetGenerics, SetLocals};
+use crate::gast::FnOperand;
+use crate::translate_ctx::TransCtx;
+use crate::types::*;
+use crate::ullbc_ast::{FunDeclId, RawTerminator, TraitImpls};
+use serde::Serialize;
+
+/// A call whose resolution went through blanket implementations.
+#[derive(Debug, Clone, Serialize)]
+pub struct BlanketCall {
+    /// The called function, as printed by Charon.
+    pub callee: String,
+    /// The location of the call.
+    pub span: String,
+    /// The blanket implementations we went through, as printed by Charon.
+    pub blanket_impls: Vec<String>,
+}
+
+/// The calls of a function which went through blanket implementations.
+#[derive(Debug, Clone, Serialize)]
+pub struct FunBlanketCalls {
+    pub def_id: FunDeclId::Id,
+    pub name: String,
+    pub calls: Vec<BlanketCall>,
+}
+
+/// Collect the blanket implementations used in a call.
+struct BlanketImplsCollector<'a> {
+    trait_impls: &'a TraitImpls,
+    blanket_impls: Vec<TraitImplId::Id>,
+}
+
+impl<'a> SharedTypeVisitor for BlanketImplsCollector<'a> {
+    fn visit_trait_impl_id(&mut self, id: &TraitImplId::Id) {
+        let is_blanket = self
+            .trait_impls
+            .get(*id)
+            .map_or(false, |trait_impl| trait_impl.is_blanket);
+        if is_blanket && !self.blanket_impls.contains(id) {
+            self.blanket_impls.push(*id);
+        }
+    }
+}
+
+impl<'a> SharedExprVisitor for BlanketImplsCollector<'a> {}
+
+/// Compute the calls which went through blanket implementations, for all the
+/// functions which have a body.
+pub fn compute(ctx: &TransCtx) -> Vec<FunBlanketCalls> {
+    let fmt_ctx = ctx.into_fmt();
+    let source_map = ctx.session.source_map();
+    let mut report = Vec::new();
+    let mut num_calls = 0;
+    for decl in ctx.fun_decls.iter() {
+        let Some(body) = &decl.body else { continue };
+        let generics_ctx = fmt_ctx.set_generics(&decl.signature.generics);
+        let body_ctx = generics_ctx.set_locals(&body.locals);
+
+        let mut calls = Vec::new();
+        for block in body.body.iter() {
+            let RawTerminator::Call { call, .. } = &block.terminator.content else {
+                continue;
+            };
+            let FnOperand::Regular(fn_ptr) = &call.func else {
+                continue;
+            };
+            let mut collector = BlanketImplsCollector {
+                trait_impls: &ctx.trait_impls,
+                blanket_impls: Vec::new(),
+            };
+            collector.visit_fn_ptr(fn_ptr);
+            if collector.blanket_impls.is_empty() {
+                continue;
+            }
+            calls.push(BlanketCall {
+                callee: call.func.fmt_with_ctx(&body_ctx),
+                span: source_map.span_to_diagnostic_string(block.terminator.meta.span.rust_span),
+                blanket_impls: collector
+                    .blanket_impls
+                    .into_iter()
+                    .map(|id| fmt_ctx.format_object(id))
+                    .collect(),
+            });
+        }
+
+        if !calls.is_empty() {
+            num_calls += calls.len();
+            report.push(FunBlanketCalls {
+                def_id: decl.def_id,
+                name: decl.name.fmt_with_ctx(&fmt_ctx),
+                calls,
+            });
+        }
+    }
+    info!(
+        "Calls resolved through blanket implementations: {}",
+        num_calls
+    );
+    report
+}
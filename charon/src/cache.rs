@@ -0,0 +1,591 @@
+//! A cache storing the translated functions and globals on disk, so that we
+//! don't have to translate them again when we re-extract a crate after a
+//! small edit (see [crate::cli_options::CliOpts::cache]).
+//!
+//! The cache is stored in one file per crate (`CRATE.cache.json`). The file
+//! is keyed by the versions of Charon and of the Rust compiler, the options
+//! which influence the translation, and the hashes of the dependency crates:
+//! if any of those changes, we start from an empty cache. Inside the file,
+//! the entries are identified by the [DefPathHash] of their item, which is
+//! stable across runs.
+//!
+//! An entry records, together with the translated declaration:
+//! - a fingerprint of the item: its signature, its attributes and its MIR
+//!   body (which includes the spans, and thus the positions in the file)
+//! - the items and the files the translation registered, in order, together
+//!   with the ids they received
+//! - a fingerprint of the interfaces (signatures, generics, predicates...) of
+//!   the registered items
+//!
+//! We reuse an entry only if the fingerprints are unchanged. We then replay
+//! the registrations, which is exactly what the translation would have done:
+//! this way, we still translate the items the declaration refers to, and the
+//! ids we assign don't depend on the state of the cache. Finally, as the ids
+//! are assigned in encounter order, the ids the declaration refers to may have
+//! changed (if new items were registered before): in this case we translate
+//! the declaration again.
+//!
+//! Remarks:
+//! - we only store the declarations we translated without errors
+//! - the warnings emitted during the translation of a declaration are not
+//!   emitted again when we reuse it
+//! - the fingerprints are computed with the stable hashes of rustc: they
+//!   don't depend on the order in which the items are encountered
+
+use crate::cli_options::CliOpts;
+use crate::get_mir::get_mir_for_def_id_and_level;
+use crate::header::FORMAT_VERSION;
+use crate::meta;
+use crate::meta::{FileId, FileName, Meta};
+use crate::reorder_decls::AnyTransId;
+use crate::translate_ctx::{OrdRustId, TransCtx};
+use crate::ullbc_ast::*;
+use rustc_data_structures::fingerprint::Fingerprint;
+use rustc_data_structures::stable_hasher::{HashStable, StableHasher};
+use rustc_hir::def::DefKind;
+use rustc_hir::def_id::{DefId, DefPathHash};
+use rustc_middle::ty::TyCtxt;
+use rustc_query_system::ich::StableHashingContext;
+use rustc_span::BytePos;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+/// A 128-bit stable hash: we use it both to identify the items (it is then
+/// their [DefPathHash]) and to detect the changes in their sources.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct StableHash(u64, u64);
+
+impl StableHash {
+    pub(crate) fn of_def_id(tcx: TyCtxt, id: DefId) -> Self {
+        StableHash::from(tcx.def_path_hash(id).0)
+    }
+
+    /// Convert back to a [DefPathHash] (if the hash identifies an item).
+    pub(crate) fn def_path_hash(self) -> DefPathHash {
+        DefPathHash(Fingerprint::new(self.0, self.1))
+    }
+}
+
+impl From<Fingerprint> for StableHash {
+    fn from(fingerprint: Fingerprint) -> Self {
+        let (hi, lo) = fingerprint.as_value();
+        StableHash(hi, lo)
+    }
+}
+
+/// Something the translation of a declaration registered.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum Registration {
+    /// A declaration. For the promoted constants, `item` identifies the
+    /// function (or global) they come from, and we also record their index.
+    Decl {
+        item: StableHash,
+        promoted: Option<usize>,
+        id: AnyTransId,
+    },
+    /// An inherent impl block
+    ImplBlock {
+        item: StableHash,
+        id: ImplBlockId::Id,
+    },
+    File {
+        name: FileName,
+        id: FileId::Id,
+    },
+}
+
+/// A cached declaration.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum CachedDecl {
+    Fun(FunDecl),
+    Global(GlobalDecl),
+}
+
+/// An entry of the cache (see the explanations at the top of the file).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CacheEntry {
+    pub item: StableHash,
+    pub item_fingerprint: StableHash,
+    pub registrations: Vec<Registration>,
+    pub deps_fingerprint: StableHash,
+    pub decl: CachedDecl,
+}
+
+/// The content of a cache file.
+#[derive(Serialize, Deserialize)]
+struct CacheFile {
+    key: String,
+    entries: Vec<CacheEntry>,
+}
+
+/// The cache of the current extraction.
+pub struct ExtractionCache {
+    /// The cache file
+    path: PathBuf,
+    key: String,
+    /// The entries we loaded from the cache file, and haven't used yet
+    entries: HashMap<StableHash, CacheEntry>,
+    /// The entries we will save at the end of the extraction
+    new_entries: Vec<CacheEntry>,
+    /// The registrations of the declaration we are translating, if we are
+    /// recording them. We also keep the ids of the registered items, to
+    /// compute the fingerprint of their interfaces.
+    recorder: Option<Vec<(Option<DefId>, Registration)>>,
+    /// The map from the [DefPathHash] of the local items to their ids. We
+    /// compute it lazily.
+    local_ids: Option<HashMap<StableHash, DefId>>,
+    hits: usize,
+    misses: usize,
+}
+
+impl ExtractionCache {
+    /// Compute the key of the cache file, which summarizes everything which
+    /// influences the translation apart from the sources of the crate.
+    pub fn compute_key(tcx: TyCtxt, options: &CliOpts) -> String {
+        let dependencies: Vec<(String, String)> = tcx
+            .crates(())
+            .iter()
+            .map(|krate| {
+                (
+                    tcx.crate_name(*krate).to_string(),
+                    tcx.crate_hash(*krate).to_string(),
+                )
+            })
+            .collect();
+        let key = serde_json::json!({
+            "format_version": FORMAT_VERSION,
+            "charon_version": env!("CARGO_PKG_VERSION"),
+            "hax_version": env!("CHARON_HAX_VERSION"),
+            "rustc_version": tcx.sess.cfg_version,
+            "target": tcx.sess.opts.target_triple.to_string(),
+            "dependencies": dependencies,
+            "mir_promoted": options.mir_promoted,
+            "mir_optimized": options.mir_optimized,
+            "opaque_modules": options.opaque_modules,
+            "skip_derived": options.skip_derived,
+            "extract_dependencies": options.extract_dependencies,
+            "names": options.names,
+            "keep_unwind": options.keep_unwind,
+            "keep_marker_traits": options.keep_marker_traits,
+            "layouts": options.layouts,
+            "fmt_machinery": options.fmt_machinery,
+            "intrinsics": options.intrinsics,
+            "assumed_funs": options.assumed_funs,
+            "path_prefix_map": options.path_prefix_map,
+            "emit_source": options.emit_source,
+        });
+        key.to_string()
+    }
+
+    /// Load the cache of a crate from the cache directory. If the cache file
+    /// doesn't exist, or was generated with a different key, we start from an
+    /// empty cache.
+    pub fn load(dir: &Path, crate_name: &str, key: String) -> Self {
+        let path = dir.join(format!("{crate_name}.cache.json"));
+        let file: Option<CacheFile> = File::open(&path)
+            .ok()
+            .and_then(|file| serde_json::from_reader(std::io::BufReader::new(file)).ok());
+        let entries = match file {
+            Some(file) if file.key == key => file
+                .entries
+                .into_iter()
+                .map(|entry| (entry.item, entry))
+                .collect(),
+            Some(_) => {
+                info!("The extraction cache is outdated: {:?}", path);
+                HashMap::new()
+            }
+            None => HashMap::new(),
+        };
+        ExtractionCache {
+            path,
+            key,
+            entries,
+            new_entries: Vec::new(),
+            recorder: None,
+            local_ids: None,
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    /// Save the entries of the current extraction. The entries we didn't use
+    /// are discarded.
+    #[allow(clippy::result_unit_err)]
+    pub fn save(&self) -> Result<(), ()> {
+        info!(
+            "Extraction cache: reused {} declarations, translated {}",
+            self.hits, self.misses
+        );
+        if let Some(dir) = self.path.parent()
+            && std::fs::create_dir_all(dir).is_err()
+        {
+            error!("Could not create the directory: {:?}", dir);
+            return Err(());
+        }
+        let file = CacheFile {
+            key: self.key.clone(),
+            entries: self.new_entries.clone(),
+        };
+        match File::create(&self.path) {
+            std::io::Result::Ok(outfile) => match serde_json::to_writer(&outfile, &file) {
+                std::result::Result::Ok(()) => Ok(()),
+                std::result::Result::Err(_) => {
+                    error!("Could not write to: {:?}", self.path);
+                    Err(())
+                }
+            },
+            std::io::Result::Err(_) => {
+                error!("Could not open: {:?}", self.path);
+                Err(())
+            }
+        }
+    }
+
+    /// Retrieve the id of an item from its [DefPathHash].
+    fn resolve(&mut self, tcx: TyCtxt, item: StableHash) -> Option<DefId> {
+        let hash = item.def_path_hash();
+        if hash.stable_crate_id() == tcx.sess.local_stable_crate_id() {
+            // The item may have been removed
+            let local_ids = self.local_ids.get_or_insert_with(|| {
+                tcx.iter_local_def_id()
+                    .map(|id| {
+                        let id = id.to_def_id();
+                        (StableHash::of_def_id(tcx, id), id)
+                    })
+                    .collect()
+            });
+            local_ids.get(&item).copied()
+        } else {
+            // The key of the cache contains the hashes of the dependency
+            // crates: their items didn't change
+            Some(tcx.def_path_hash_to_def_id(hash, &mut || {
+                unreachable!("Unknown item in the extraction cache")
+            }))
+        }
+    }
+}
+
+/// Hash the interface of an item: everything the declarations which refer
+/// to this item may depend on.
+fn hash_interface(
+    tcx: TyCtxt,
+    hcx: &mut StableHashingContext,
+    hasher: &mut StableHasher,
+    id: DefId,
+) {
+    id.hash_stable(hcx, hasher);
+    let kind = tcx.def_kind(id);
+    kind.hash_stable(hcx, hasher);
+    match kind {
+        DefKind::Fn | DefKind::AssocFn => tcx.fn_sig(id).hash_stable(hcx, hasher),
+        DefKind::Impl { .. } => {
+            tcx.type_of(id).hash_stable(hcx, hasher);
+            tcx.impl_trait_ref(id).hash_stable(hcx, hasher);
+        }
+        DefKind::Struct
+        | DefKind::Enum
+        | DefKind::Union
+        | DefKind::ForeignTy
+        | DefKind::Const
+        | DefKind::AssocConst
+        | DefKind::Static(_)
+        | DefKind::Closure
+        | DefKind::Generator => tcx.type_of(id).hash_stable(hcx, hasher),
+        _ => (),
+    }
+    tcx.generics_of(id).hash_stable(hcx, hasher);
+    tcx.predicates_of(id).hash_stable(hcx, hasher);
+}
+
+/// Restore the Rust spans of a declaration we loaded from the cache: they are
+/// not serialized (see [meta::Span::rust_span]), and we need them to report
+/// errors in the micro-passes.
+struct SpanRestorer {
+    /// The start positions of the files in the source map
+    files: HashMap<FileId::Id, BytePos>,
+}
+
+impl SpanRestorer {
+    fn restore_span(&self, span: &mut meta::Span) {
+        if let Some(start) = self.files.get(&span.file_id) {
+            let lo = *start + BytePos(span.beg.offset as u32);
+            let hi = *start + BytePos(span.end.offset as u32);
+            span.rust_span = rustc_span::Span::with_root_ctxt(lo, hi);
+        }
+    }
+
+    fn restore_meta(&self, meta: &mut Meta) {
+        self.restore_span(&mut meta.span);
+        if let Some(span) = &mut meta.generated_from_span {
+            self.restore_span(span);
+        }
+        if let Some(backtrace) = &mut meta.backtrace {
+            backtrace
+                .iter_mut()
+                .for_each(|span| self.restore_span(span));
+        }
+    }
+
+    fn restore_body(&mut self, body: &mut ExprBody) {
+        self.restore_meta(&mut body.meta);
+        for info in body.var_debug_info.iter_mut() {
+            self.restore_span(&mut info.span);
+            self.restore_span(&mut info.scope);
+        }
+        for backtrace in body.backtraces.iter_mut() {
+            backtrace
+                .iter_mut()
+                .for_each(|span| self.restore_span(span));
+        }
+        for block in body.body.iter_mut() {
+            self.visit_block_data(block);
+        }
+    }
+}
+
+impl MutTypeVisitor for SpanRestorer {}
+impl MutExprVisitor for SpanRestorer {}
+impl MutAstVisitor for SpanRestorer {
+    fn visit_meta(&mut self, meta: &mut Meta) {
+        self.restore_meta(meta)
+    }
+}
+
+impl<'tcx, 'ctx> TransCtx<'tcx, 'ctx> {
+    /// The registrations of the declaration we are translating, if we are
+    /// recording them.
+    fn recorder(&mut self) -> Option<&mut Vec<(Option<DefId>, Registration)>> {
+        self.cache.as_mut()?.recorder.as_mut()
+    }
+
+    /// Record the registration of a declaration, if we are recording the
+    /// registrations (see the explanations at the top of the file).
+    pub(crate) fn record_decl(&mut self, id: OrdRustId, trans_id: AnyTransId) {
+        let tcx = self.tcx;
+        if let Some(recorder) = self.recorder() {
+            let promoted = match id {
+                OrdRustId::Promoted(_, index) => Some(index),
+                _ => None,
+            };
+            let reg = Registration::Decl {
+                item: StableHash::of_def_id(tcx, id.get_id()),
+                promoted,
+                id: trans_id,
+            };
+            recorder.push((Some(id.get_id()), reg));
+        }
+    }
+
+    pub(crate) fn record_impl_block(&mut self, rust_id: DefId, id: ImplBlockId::Id) {
+        let tcx = self.tcx;
+        if let Some(recorder) = self.recorder() {
+            let item = StableHash::of_def_id(tcx, rust_id);
+            recorder.push((Some(rust_id), Registration::ImplBlock { item, id }));
+        }
+    }
+
+    pub(crate) fn record_file(&mut self, name: &FileName, id: FileId::Id) {
+        if let Some(recorder) = self.recorder() {
+            let name = name.clone();
+            recorder.push((None, Registration::File { name, id }));
+        }
+    }
+
+    /// Compute the fingerprint of an item we are about to translate.
+    fn item_fingerprint(&mut self, rust_id: DefId) -> StableHash {
+        let tcx = self.tcx;
+        // We only retrieve the body if the translation retrieves it, and at
+        // the same moment: this doesn't introduce stealing issues.
+        let body = match rust_id.as_local() {
+            Some(id)
+                if tcx.hir().maybe_body_owned_by(id).is_some()
+                    && self.id_is_transparent(rust_id) =>
+            {
+                Some(get_mir_for_def_id_and_level(tcx, id, self.mir_level))
+            }
+            _ => None,
+        };
+        let fingerprint: Fingerprint = tcx.with_stable_hashing_context(|mut hcx| {
+            let mut hasher = StableHasher::new();
+            hash_interface(tcx, &mut hcx, &mut hasher, rust_id);
+            tcx.def_span(rust_id).hash_stable(&mut hcx, &mut hasher);
+            tcx.get_attrs_unchecked(rust_id)
+                .hash_stable(&mut hcx, &mut hasher);
+            body.hash_stable(&mut hcx, &mut hasher);
+            hasher.finish()
+        });
+        StableHash::from(fingerprint)
+    }
+
+    /// Compute the fingerprint of the interfaces of the items registered by
+    /// the translation of a declaration.
+    fn deps_fingerprint(&self, deps: &[(Option<DefId>, Registration)]) -> StableHash {
+        let tcx = self.tcx;
+        let fingerprint: Fingerprint = tcx.with_stable_hashing_context(|mut hcx| {
+            let mut hasher = StableHasher::new();
+            for id in deps.iter().filter_map(|(id, _)| *id) {
+                hash_interface(tcx, &mut hcx, &mut hasher, id);
+            }
+            hasher.finish()
+        });
+        StableHash::from(fingerprint)
+    }
+
+    /// Replay a registration, and check that the registered item received
+    /// the same id as when we translated the declaration.
+    fn replay_registration(&mut self, id: Option<DefId>, reg: &Registration) -> bool {
+        match (id, reg) {
+            (Some(rust_id), Registration::Decl { promoted, id, .. }) => match (id, promoted) {
+                (AnyTransId::Type(id), _) => self.register_type_decl_id(&None, rust_id) == *id,
+                (AnyTransId::Fun(id), _) => self.register_fun_decl_id(&None, rust_id) == *id,
+                (AnyTransId::Global(id), None) => {
+                    self.register_global_decl_id(&None, rust_id) == *id
+                }
+                (AnyTransId::Global(id), Some(index)) => {
+                    self.register_promoted_global_decl_id(&None, rust_id, *index) == *id
+                }
+                (AnyTransId::TraitDecl(id), _) => {
+                    self.register_trait_decl_id(&None, rust_id) == Some(*id)
+                }
+                (AnyTransId::TraitImpl(id), _) => {
+                    self.register_trait_impl_id(&None, rust_id) == Some(*id)
+                }
+            },
+            (Some(rust_id), Registration::ImplBlock { id, .. }) => {
+                self.register_impl_block_id(&None, rust_id) == *id
+            }
+            (_, Registration::File { name, id }) => self.register_file(name.clone()) == *id,
+            (None, _) => unreachable!(),
+        }
+    }
+
+    /// Try to reuse the cached translation of a declaration. Return `true` if
+    /// we inserted the declaration.
+    fn reuse_cached_decl(&mut self, rust_id: DefId, entry: CacheEntry) -> bool {
+        let tcx = self.tcx;
+        // Retrieve the registered items: some of them may have been removed
+        let cache = self.cache.as_mut().unwrap();
+        let mut deps = Vec::new();
+        for reg in entry.registrations.iter() {
+            let id = match reg {
+                Registration::Decl { item, .. } | Registration::ImplBlock { item, .. } => {
+                    match cache.resolve(tcx, *item) {
+                        Some(id) => Some(id),
+                        None => return false,
+                    }
+                }
+                Registration::File { .. } => None,
+            };
+            deps.push((id, reg.clone()));
+        }
+        if self.deps_fingerprint(&deps) != entry.deps_fingerprint {
+            return false;
+        }
+
+        // The translation would register the same items, in the same order:
+        // we can replay the registrations.
+        let mut same_ids = true;
+        for (id, reg) in deps.iter() {
+            same_ids &= self.replay_registration(*id, reg);
+        }
+        if !same_ids {
+            return false;
+        }
+
+        // Insert the declaration
+        let mut restorer = SpanRestorer {
+            files: HashMap::new(),
+        };
+        let source_map = self.session.source_map();
+        for file in source_map.files().iter() {
+            let name = match &file.name {
+                rustc_span::FileName::Real(rustc_span::RealFileName::LocalPath(path)) => {
+                    FileName::Local(path.to_string_lossy().into_owned())
+                }
+                rustc_span::FileName::Real(rustc_span::RealFileName::Remapped {
+                    virtual_name,
+                    ..
+                }) => FileName::Virtual(virtual_name.to_string_lossy().into_owned()),
+                _ => continue,
+            };
+            let name = meta::remap_filename(&self.path_prefix_map, name);
+            if let Some(id) = self.file_to_id.get(&name) {
+                restorer.files.insert(*id, file.start_pos);
+            }
+        }
+        match entry.decl.clone() {
+            CachedDecl::Fun(mut decl) => {
+                decl.rust_id = rust_id;
+                restorer.restore_meta(&mut decl.meta);
+                if let Some(body) = &mut decl.body {
+                    restorer.restore_body(body);
+                }
+                self.fun_decls.insert(decl.def_id, decl);
+            }
+            CachedDecl::Global(mut decl) => {
+                decl.rust_id = rust_id;
+                restorer.restore_meta(&mut decl.meta);
+                if let Some(body) = &mut decl.body {
+                    restorer.restore_body(body);
+                }
+                self.global_decls.insert(decl.def_id, decl);
+            }
+        }
+        self.cache.as_mut().unwrap().new_entries.push(entry);
+        true
+    }
+
+    /// Translate a function or a global, using the cache if the user asked
+    /// for it (see the explanations at the top of the file).
+    pub(crate) fn translate_with_cache(&mut self, rust_id: DefId, translate: fn(&mut Self, DefId)) {
+        let Some(cache) = &mut self.cache else {
+            return translate(self, rust_id);
+        };
+        let item = StableHash::of_def_id(self.tcx, rust_id);
+        let entry = cache.entries.remove(&item);
+        let item_fingerprint = self.item_fingerprint(rust_id);
+        if let Some(entry) = entry
+            && entry.item_fingerprint == item_fingerprint
+            && self.reuse_cached_decl(rust_id, entry)
+        {
+            self.cache.as_mut().unwrap().hits += 1;
+            return;
+        }
+
+        // Translate the declaration, while recording the registrations
+        let error_count = self.error_count;
+        self.cache.as_mut().unwrap().recorder = Some(Vec::new());
+        translate(self, rust_id);
+        let cache = self.cache.as_mut().unwrap();
+        let deps = cache.recorder.take().unwrap();
+        cache.misses += 1;
+        if self.error_count != error_count || self.decls_with_errors.contains(&rust_id) {
+            return;
+        }
+
+        // Save the declaration
+        let decl = if let Some(id) = self.fun_id_map.get(&rust_id)
+            && let Some(decl) = self.fun_decls.get(id)
+        {
+            CachedDecl::Fun(decl.clone())
+        } else if let Some(id) = self.global_id_map.get(&rust_id)
+            && let Some(decl) = self.global_decls.get(id)
+        {
+            CachedDecl::Global(decl.clone())
+        } else {
+            return;
+        };
+        let deps_fingerprint = self.deps_fingerprint(&deps);
+        let entry = CacheEntry {
+            item,
+            item_fingerprint,
+            registrations: deps.into_iter().map(|(_, reg)| reg).collect(),
+            deps_fingerprint,
+            decl,
+        };
+        self.cache.as_mut().unwrap().new_entries.push(entry);
+    }
+}
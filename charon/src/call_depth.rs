@@ -0,0 +1,137 @@
+//! # Micro-pass (optional, see [crate::cli_options::CliOpts::call_depth]):
+//! compute a conservative bound on the depth of the nested calls performed by
+//! the functions (see [CallDepth]). This is useful for the stack-safety
+//! arguments (typically, for embedded code).
+//!
+//! We compute the strongly connected components of the call graph: the
+//! functions which belong to a recursive component, or which call such a
+//! function, have an unbounded call depth. Note that the loops don't increase
+//! the call depth: the frames of the functions called in the body of a loop
+//! are popped at every iteration.
+
+use crate::expressions::*;
+use crate::gast::{Call, CallDepth, FnOperand};
+use crate::translate_ctx::TransCtx;
+use crate::types::*;
+use crate::ullbc_ast::{FunDeclId, RawTerminator};
+use petgraph::algo::tarjan_scc;
+use petgraph::graphmap::DiGraphMap;
+use std::collections::HashMap;
+
+/// The function called by a call site.
+enum Callee {
+    /// A function of the crate (which may be opaque)
+    Fun(FunDeclId::Id),
+    /// An assumed function: we consider they don't call other functions
+    Assumed,
+    /// We can't resolve the call statically (the call depends on a trait
+    /// clause, or is performed through a trait object or a function pointer)
+    Unknown,
+}
+
+fn resolve_call(ctx: &TransCtx, call: &Call) -> Callee {
+    match &call.func {
+        FnOperand::Regular(fn_ptr) => match &fn_ptr.func {
+            FunIdOrTraitMethodRef::Fun(FunId::Regular(id)) => Callee::Fun(*id),
+            FunIdOrTraitMethodRef::Fun(FunId::Assumed(_)) => Callee::Assumed,
+            FunIdOrTraitMethodRef::Trait(trait_ref, name, method_id) => {
+                match &trait_ref.trait_id {
+                    TraitInstanceId::TraitImpl(impl_id) => {
+                        // Look for the method in the implementation: if it is not
+                        // there, this is a provided method which is not
+                        // re-implemented, and we call the method of the trait
+                        // declaration.
+                        let id = ctx
+                            .trait_impls
+                            .get(*impl_id)
+                            .and_then(|timpl| {
+                                timpl
+                                    .required_methods
+                                    .iter()
+                                    .chain(timpl.provided_methods.iter())
+                                    .find(|(n, _)| n == name)
+                                    .map(|(_, id)| *id)
+                            })
+                            .unwrap_or(*method_id);
+                        Callee::Fun(id)
+                    }
+                    _ => Callee::Unknown,
+                }
+            }
+        },
+        FnOperand::Move(_) | FnOperand::Virtual(..) => Callee::Unknown,
+    }
+}
+
+/// Combine the call depths of two call sites of a function.
+fn join(d0: CallDepth, d1: CallDepth) -> CallDepth {
+    use CallDepth::*;
+    match (d0, d1) {
+        (Unbounded, _) | (_, Unbounded) => Unbounded,
+        (Unknown, _) | (_, Unknown) => Unknown,
+        (Bounded(n0), Bounded(n1)) => Bounded(std::cmp::max(n0, n1)),
+    }
+}
+
+/// The call depth of a call site, given the call depth of the callee.
+fn call(d: CallDepth) -> CallDepth {
+    match d {
+        CallDepth::Bounded(n) => CallDepth::Bounded(n + 1),
+        CallDepth::Unbounded | CallDepth::Unknown => d,
+    }
+}
+
+pub fn compute(ctx: &mut TransCtx) {
+    // Build the call graph
+    let mut graph: DiGraphMap<FunDeclId::Id, ()> = DiGraphMap::new();
+    let mut callees: HashMap<FunDeclId::Id, Vec<Callee>> = HashMap::new();
+    for decl in ctx.fun_decls.iter() {
+        let Some(body) = &decl.body else { continue };
+        let _ = graph.add_node(decl.def_id);
+        let calls: Vec<Callee> = body
+            .body
+            .iter()
+            .filter_map(|block| match &block.terminator.content {
+                RawTerminator::Call { call, .. } => Some(resolve_call(ctx, call)),
+                _ => None,
+            })
+            .collect();
+        for callee in &calls {
+            if let Callee::Fun(id) = callee {
+                let _ = graph.add_edge(decl.def_id, *id, ());
+            }
+        }
+        let _ = callees.insert(decl.def_id, calls);
+    }
+
+    // Tarjan's algorithm returns the SCCs in reverse topological order: we
+    // explore the callees before their callers.
+    let mut depths: HashMap<FunDeclId::Id, CallDepth> = HashMap::new();
+    for scc in tarjan_scc(&graph) {
+        let is_recursive = scc.len() > 1 || graph.contains_edge(scc[0], scc[0]);
+        for id in &scc {
+            // The functions without a body don't have a call depth
+            let Some(calls) = callees.get(id) else {
+                continue;
+            };
+            let depth = if is_recursive {
+                CallDepth::Unbounded
+            } else {
+                calls
+                    .iter()
+                    .map(|callee| match callee {
+                        Callee::Fun(id) => call(*depths.get(id).unwrap_or(&CallDepth::Unknown)),
+                        Callee::Assumed => CallDepth::Bounded(1),
+                        Callee::Unknown => CallDepth::Unknown,
+                    })
+                    .fold(CallDepth::Bounded(0), join)
+            };
+            let _ = depths.insert(*id, depth);
+        }
+    }
+
+    for decl in ctx.fun_decls.iter_mut() {
+        decl.call_depth = depths.get(&decl.def_id).copied();
+        trace!("Call depth of {}: {:?}", decl.def_id, decl.call_depth);
+    }
+}
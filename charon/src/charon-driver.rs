@@ -18,8 +18,10 @@ extern crate linked_hash_set;
 extern crate log;
 extern crate rustc_abi;
 extern crate rustc_ast;
+extern crate rustc_ast_pretty;
 extern crate rustc_borrowck;
 extern crate rustc_const_eval;
+extern crate rustc_data_structures;
 extern crate rustc_driver;
 extern crate rustc_error_messages;
 extern crate rustc_errors;
@@ -30,6 +32,7 @@ extern crate rustc_middle;
 extern crate rustc_mir_dataflow;
 extern crate rustc_mir_transform;
 extern crate rustc_monomorphize;
+extern crate rustc_query_system;
 extern crate rustc_resolve;
 extern crate rustc_session;
 extern crate rustc_span;
@@ -39,17 +42,32 @@ extern crate take_mut;
 #[macro_use]
 mod common;
 mod assumed;
+mod batch;
+mod blanket_impls;
+mod cache;
+mod call_depth;
+mod check_generic_args;
+mod check_regions;
+mod check_trait_impls;
 mod cli_options;
+mod dependencies;
 mod deps_errors;
+mod deserialize;
+mod devirtualization;
+mod diff;
 mod driver;
 mod export;
 mod expressions;
 mod expressions_utils;
+mod fold_constants;
 mod formatter;
 mod gast;
 mod gast_utils;
 mod get_mir;
 mod graphs;
+mod header;
+mod hidden_effects;
+mod hooks;
 mod id_map;
 mod id_vector;
 mod index_to_function_calls;
@@ -57,19 +75,37 @@ mod insert_assign_return_unit;
 mod llbc_ast;
 mod llbc_ast_utils;
 mod logger;
+mod manifest;
 mod meta;
 mod meta_utils;
+mod metrics;
 mod names;
 mod names_utils;
 mod ops_to_function_calls;
+mod print_crate;
+mod progress;
+mod pure_forms;
+mod query;
+mod reachability;
+mod reconstruct_aggregates;
 mod reconstruct_asserts;
+mod reconstruct_boolean_ops;
+mod reconstruct_box_new;
+mod reconstruct_for_loops;
+mod reconstruct_question_marks;
+mod remove_dead_assignments;
 mod remove_drop_never;
 mod remove_dynamic_checks;
+mod remove_fmt_calls;
 mod remove_nops;
 mod remove_read_discriminant;
 mod remove_unused_locals;
 mod reorder_decls;
+mod simplify_cfg;
 mod simplify_constants;
+mod stats;
+mod stubs;
+mod target;
 mod translate_constants;
 mod translate_crate_to_ullbc;
 mod translate_ctx;
@@ -77,6 +113,7 @@ mod translate_functions_to_ullbc;
 mod translate_predicates;
 mod translate_traits;
 mod translate_types;
+mod type_check;
 mod types;
 mod types_utils;
 mod ullbc_ast;
@@ -111,6 +148,22 @@ fn main() {
     let options: cli_options::CliOpts =
         serde_json::from_str(std::env::var(cli_options::CHARON_ARGS).unwrap().as_str()).unwrap();
 
+    // If we were called for a subcommand, execute it and stop there
+    // (see [cli_options::Command]).
+    if let Some(command) = &options.command {
+        let res = match command {
+            cli_options::Command::Print(opts) => print_crate::print(opts),
+            cli_options::Command::Query(opts) => query::query(opts),
+            cli_options::Command::Diff(opts) => diff::diff(opts),
+            cli_options::Command::Merge(opts) => batch::merge(opts),
+        };
+        if let Err(msg) = res {
+            log::error!("{}", msg);
+            std::process::exit(1);
+        }
+        return;
+    }
+
     // Compute the sysroot (the path to the executable of the compiler):
     // - if it is already in the command line arguments, just retrieve it from there
     // - otherwise retrieve the sysroot from a call to rustc
@@ -213,6 +266,7 @@ fn main() {
     let errors_as_warnings = options.errors_as_warnings;
     let mut callback = CharonCallbacks {
         options,
+        hook: None,
         error_count: 0,
     };
     let res = RunCompiler::new(&compiler_args, &mut callback).run();
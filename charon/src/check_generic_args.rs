@@ -0,0 +1,291 @@
+//! # Micro-pass: check that the instantiations of the generic parameters are
+//! consistent with the parameters they instantiate: the arguments of the
+//! function calls must match the generic parameters of the callee, and the
+//! arguments of the type applications must match the generic parameters of
+//! the type declaration (number of arguments of every kind, and traits of the
+//! trait references).
+//!
+//! The mismatches otherwise only surface as panics deep inside the consumers
+//! of the generated files. This pass doesn't modify the declarations: it
+//! reports the mismatches as warnings, with their locations.
+//!
+//! Remarks:
+//! - we don't check the regions of the function calls: the late-bound regions
+//!   of the callee are not instantiated at the call sites
+//! - for the calls to trait methods, we only check the arguments of the method
+//!   itself (the arguments of the trait are given by the trait reference)
+use crate::expressions::{FnPtr, FunId, FunIdOrTraitMethodRef, SharedExprVisitor};
+use crate::formatter::{Formatter, IntoFormatter};
+use crate::meta::Meta;
+use crate::names::Name;
+use crate::translate_ctx::TransCtx;
+use crate::types::*;
+use crate::ullbc_ast::{ExprBody, FunDeclId, SharedAstVisitor};
+
+/// The kinds of generic parameters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GenericParamKind {
+    Region,
+    Type,
+    ConstGeneric,
+    TraitClause,
+}
+
+impl std::fmt::Display for GenericParamKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GenericParamKind::Region => write!(f, "region"),
+            GenericParamKind::Type => write!(f, "type"),
+            GenericParamKind::ConstGeneric => write!(f, "const generic"),
+            GenericParamKind::TraitClause => write!(f, "trait"),
+        }
+    }
+}
+
+/// An inconsistency between generic arguments and the generic parameters they
+/// instantiate.
+#[derive(Debug, Clone)]
+pub enum GenericArgsMismatch {
+    /// The number of arguments of some kind doesn't match the number of
+    /// parameters
+    Arity {
+        kind: GenericParamKind,
+        expected: usize,
+        found: usize,
+    },
+    /// A trait reference doesn't implement the trait of the clause it is
+    /// given for
+    TraitRef {
+        index: usize,
+        expected: TraitDeclId::Id,
+        found: TraitDeclId::Id,
+    },
+}
+
+struct GenericArgsChecker<'a, 'tcx, 'ctx> {
+    ctx: &'a TransCtx<'tcx, 'ctx>,
+    /// The mismatches we found and haven't reported yet, together with the
+    /// name of the function or the type we instantiate
+    mismatches: Vec<(Name, GenericArgsMismatch)>,
+}
+
+impl<'a, 'tcx, 'ctx> GenericArgsChecker<'a, 'tcx, 'ctx> {
+    fn check_arity(&mut self, name: &Name, kind: GenericParamKind, expected: usize, found: usize) {
+        if expected != found {
+            self.mismatches.push((
+                name.clone(),
+                GenericArgsMismatch::Arity {
+                    kind,
+                    expected,
+                    found,
+                },
+            ));
+        }
+    }
+
+    fn check_trait_refs(&mut self, name: &Name, clauses: &[&TraitClause], trait_refs: &[TraitRef]) {
+        self.check_arity(
+            name,
+            GenericParamKind::TraitClause,
+            clauses.len(),
+            trait_refs.len(),
+        );
+        for (index, (clause, trait_ref)) in clauses.iter().zip(trait_refs).enumerate() {
+            let found = trait_ref.trait_decl_ref.trait_id;
+            if clause.trait_id != found {
+                self.mismatches.push((
+                    name.clone(),
+                    GenericArgsMismatch::TraitRef {
+                        index,
+                        expected: clause.trait_id,
+                        found,
+                    },
+                ));
+            }
+        }
+    }
+
+    /// Check the arguments of a type application.
+    fn check_type_args(&mut self, id: TypeDeclId::Id, args: &GenericArgs) {
+        // The declaration may be missing if its translation failed: the
+        // error was already reported
+        let Some(decl) = self.ctx.type_decls.get(id) else {
+            return;
+        };
+        let params = &decl.generics;
+        use GenericParamKind::*;
+        self.check_arity(&decl.name, Region, params.regions.len(), args.regions.len());
+        self.check_arity(&decl.name, Type, params.types.len(), args.types.len());
+        self.check_arity(
+            &decl.name,
+            ConstGeneric,
+            params.const_generics.len(),
+            args.const_generics.len(),
+        );
+        let clauses: Vec<&TraitClause> = params.trait_clauses.iter().collect();
+        self.check_trait_refs(&decl.name, &clauses, &args.trait_refs);
+    }
+
+    /// Check the arguments of a function call. For the trait methods, we skip
+    /// the parameters of the trait (see the remarks at the top of the file).
+    fn check_fun_args(&mut self, id: FunDeclId::Id, args: &GenericArgs, is_method: bool) {
+        let Some(decl) = self.ctx.fun_decls.get(id) else {
+            return;
+        };
+        let params = &decl.signature.generics;
+        let (num_types, num_const_generics, num_trait_clauses) =
+            match (is_method, &decl.signature.parent_params_info) {
+                (true, Some(info)) => (
+                    info.num_type_params,
+                    info.num_const_generic_params,
+                    info.num_trait_clauses,
+                ),
+                (true, None) => return,
+                (false, _) => (0, 0, 0),
+            };
+        use GenericParamKind::*;
+        self.check_arity(
+            &decl.name,
+            Type,
+            params.types.len().saturating_sub(num_types),
+            args.types.len(),
+        );
+        self.check_arity(
+            &decl.name,
+            ConstGeneric,
+            params
+                .const_generics
+                .len()
+                .saturating_sub(num_const_generics),
+            args.const_generics.len(),
+        );
+        let clauses: Vec<&TraitClause> = params
+            .trait_clauses
+            .iter()
+            .skip(num_trait_clauses)
+            .collect();
+        self.check_trait_refs(&decl.name, &clauses, &args.trait_refs);
+    }
+
+    /// Report the mismatches we found so far, if there are any.
+    fn report(&mut self, meta: &Meta) {
+        let fmt_ctx = self.ctx.into_fmt();
+        for (name, mismatch) in &self.mismatches {
+            let msg = match mismatch {
+                GenericArgsMismatch::Arity {
+                    kind,
+                    expected,
+                    found,
+                } => format!("expected {expected} {kind} arguments, found {found}"),
+                GenericArgsMismatch::TraitRef {
+                    index,
+                    expected,
+                    found,
+                } => format!(
+                    "the trait reference {index} implements {}, expected {}",
+                    fmt_ctx.format_object(*found),
+                    fmt_ctx.format_object(*expected)
+                ),
+            };
+            self.ctx.session.span_warn(
+                meta.span.rust_span,
+                format!(
+                    "Inconsistent instantiation of {}: {msg}",
+                    name.fmt_with_ctx(&fmt_ctx)
+                ),
+            );
+        }
+        self.mismatches.clear();
+    }
+
+    fn check_body(&mut self, body: &ExprBody) {
+        for var in body.locals.iter() {
+            self.visit_ty(&var.ty);
+        }
+        self.report(&body.meta);
+
+        for block in body.body.iter() {
+            for st in &block.statements {
+                self.visit_statement(st);
+                self.report(&st.meta);
+            }
+            self.visit_terminator(&block.terminator);
+            self.report(&block.terminator.meta);
+        }
+    }
+}
+
+impl<'a, 'tcx, 'ctx> SharedTypeVisitor for GenericArgsChecker<'a, 'tcx, 'ctx> {
+    fn visit_ty_adt(&mut self, id: &TypeId, args: &GenericArgs) {
+        if let TypeId::Adt(id) = id {
+            self.check_type_args(*id, args);
+        }
+        self.visit_type_id(id);
+        self.visit_generic_args(args);
+    }
+}
+
+impl<'a, 'tcx, 'ctx> SharedExprVisitor for GenericArgsChecker<'a, 'tcx, 'ctx> {
+    fn visit_fn_ptr(&mut self, fn_ptr: &FnPtr) {
+        match &fn_ptr.func {
+            FunIdOrTraitMethodRef::Fun(FunId::Regular(id)) => {
+                self.check_fun_args(*id, &fn_ptr.generics, false)
+            }
+            FunIdOrTraitMethodRef::Trait(_, _, id) => {
+                self.check_fun_args(*id, &fn_ptr.generics, true)
+            }
+            FunIdOrTraitMethodRef::Fun(FunId::Assumed(_)) => (),
+        }
+        self.visit_fun_id_or_trait_ref(&fn_ptr.func);
+        self.visit_generic_args(&fn_ptr.generics);
+        if let Some(generics) = &fn_ptr.trait_and_method_generic_args {
+            self.visit_generic_args(generics);
+        }
+    }
+}
+
+impl<'a, 'tcx, 'ctx> SharedAstVisitor for GenericArgsChecker<'a, 'tcx, 'ctx> {}
+
+pub fn check(ctx: &TransCtx) {
+    let mut checker = GenericArgsChecker {
+        ctx,
+        mismatches: Vec::new(),
+    };
+
+    for decl in ctx.type_decls.iter() {
+        match &decl.kind {
+            TypeDeclKind::Struct(fields) | TypeDeclKind::Union(fields) => {
+                for field in fields.iter() {
+                    checker.visit_ty(&field.ty);
+                }
+            }
+            TypeDeclKind::Enum(variants) => {
+                for variant in variants.iter() {
+                    for field in variant.fields.iter() {
+                        checker.visit_ty(&field.ty);
+                    }
+                }
+            }
+            TypeDeclKind::Opaque | TypeDeclKind::Error(_) => (),
+        }
+        checker.report(&decl.meta);
+    }
+
+    for decl in ctx.fun_decls.iter() {
+        checker.visit_fun_sig(&decl.signature);
+        checker.report(&decl.meta);
+
+        if let Some(body) = &decl.body {
+            checker.check_body(body);
+        }
+    }
+
+    for decl in ctx.global_decls.iter() {
+        checker.visit_ty(&decl.ty);
+        checker.report(&decl.meta);
+
+        if let Some(body) = &decl.body {
+            checker.check_body(body);
+        }
+    }
+}
@@ -0,0 +1,122 @@
+//! # Micro-pass: check that the bodies only contain erased regions, and that
+//! the signatures only contain region variables and the static region.
+//! Mixing the two kinds of regions leads to confusing failures in the
+//! consumers of the generated files: we report the leakages early, with
+//! their locations.
+//!
+//! Remarks:
+//! - the regions bound below a binder (i.e., in an arrow type) can appear
+//!   everywhere
+//! - we allow the static region in the bodies: it can appear in the types of
+//!   the constants
+use crate::expressions::SharedExprVisitor;
+use crate::formatter::IntoFormatter;
+use crate::meta::Meta;
+use crate::names::Name;
+use crate::translate_ctx::TransCtx;
+use crate::types::*;
+use crate::ullbc_ast::{ExprBody, SharedAstVisitor};
+
+/// Collects the regions which shouldn't appear in the visited values.
+struct RegionsChecker {
+    /// `true` if we are exploring a body, `false` if we are exploring a
+    /// signature.
+    in_body: bool,
+    /// The number of region groups we dived into
+    depth: usize,
+    /// The unexpected regions we found and haven't reported yet
+    leaked: Vec<Region>,
+}
+
+impl RegionsChecker {
+    /// Report the regions we found so far, if there are any.
+    fn report(&mut self, ctx: &TransCtx, name: &Name, meta: &Meta, what: &str) {
+        if self.leaked.is_empty() {
+            return;
+        }
+        let regions: Vec<String> = self.leaked.iter().map(|r| r.to_string()).collect();
+        let expected = if self.in_body {
+            "erased regions"
+        } else {
+            "region variables and the static region"
+        };
+        ctx.session.span_warn(
+            meta.span.rust_span,
+            format!(
+                "Unexpected regions in the {what} of {}: {} (expected only {expected})",
+                name.fmt_with_ctx(&ctx.into_fmt()),
+                regions.join(", ")
+            ),
+        );
+        self.leaked.clear();
+    }
+
+    fn check_body(&mut self, ctx: &TransCtx, name: &Name, body: &ExprBody) {
+        self.in_body = true;
+        for var in body.locals.iter() {
+            self.visit_ty(&var.ty);
+        }
+        self.report(ctx, name, &body.meta, "local variables");
+
+        for block in body.body.iter() {
+            for st in &block.statements {
+                self.visit_statement(st);
+                self.report(ctx, name, &st.meta, "body");
+            }
+            self.visit_terminator(&block.terminator);
+            self.report(ctx, name, &block.terminator.meta, "body");
+        }
+    }
+}
+
+impl SharedTypeVisitor for RegionsChecker {
+    fn visit_region(&mut self, r: &Region) {
+        let ok = match r {
+            Region::BVar(grid, _) if grid.index < self.depth => true,
+            Region::BVar(..) => !self.in_body,
+            Region::Static => true,
+            Region::Erased => self.in_body,
+            Region::Unknown => false,
+        };
+        if !ok {
+            self.leaked.push(*r);
+        }
+    }
+
+    fn enter_region_group(
+        &mut self,
+        _regions: &RegionId::Vector<RegionVar>,
+        visitor: &mut dyn FnMut(&mut Self),
+    ) {
+        self.depth += 1;
+        visitor(self);
+        self.depth -= 1;
+    }
+}
+
+impl SharedExprVisitor for RegionsChecker {}
+impl SharedAstVisitor for RegionsChecker {}
+
+pub fn check(ctx: &TransCtx) {
+    let mut checker = RegionsChecker {
+        in_body: false,
+        depth: 0,
+        leaked: Vec::new(),
+    };
+
+    for decl in ctx.fun_decls.iter() {
+        checker.in_body = false;
+        checker.visit_fun_sig(&decl.signature);
+        checker.report(ctx, &decl.name, &decl.meta, "signature");
+
+        if let Some(body) = &decl.body {
+            checker.check_body(ctx, &decl.name, body);
+        }
+    }
+
+    for decl in ctx.global_decls.iter() {
+        if let Some(body) = &decl.body {
+            checker.check_body(ctx, &decl.name, body);
+        }
+    }
+}
@@ -0,0 +1,567 @@
+//! # Micro-pass: check that the trait implementations are consistent with the
+//! trait declarations they implement: an impl must provide exactly the items
+//! the trait requires (it may also override the provided methods), and the
+//! types of those items must be the types declared in the trait, once we
+//! substitute the parameters of the trait with the arguments of the impl.
+//!
+//! The bugs of the translation of the traits otherwise only surface as type
+//! errors in the consumers of the generated files, which are hard to trace
+//! back. This pass doesn't modify the declarations: it reports the mismatches
+//! as warnings, with the location of the impl.
+//!
+//! Remarks:
+//! - we compare the types up to the regions (we erase them)
+//! - we normalize the projections of the associated types of the impl (i.e.,
+//!   `Self::Item`) with the types provided by the impl
+use crate::formatter::{IntoFormatter, SetGenerics};
+use crate::gast::{TraitDecl, TraitImpl};
+use crate::translate_ctx::TransCtx;
+use crate::types::*;
+use std::collections::HashSet;
+
+/// The kinds of items of a trait.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TraitItemKind {
+    Const,
+    Type,
+    Method,
+}
+
+impl std::fmt::Display for TraitItemKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TraitItemKind::Const => write!(f, "associated constant"),
+            TraitItemKind::Type => write!(f, "associated type"),
+            TraitItemKind::Method => write!(f, "method"),
+        }
+    }
+}
+
+/// An inconsistency between a trait implementation and its trait declaration.
+#[derive(Debug, Clone)]
+pub enum TraitImplMismatch {
+    /// The impl doesn't provide an item the trait requires
+    MissingItem(TraitItemKind, TraitItemName),
+    /// The impl provides an item the trait doesn't declare
+    UnknownItem(TraitItemKind, TraitItemName),
+    /// The impl provides the same item several times
+    DuplicateItem(TraitItemKind, TraitItemName),
+    /// The generic arguments of the implemented trait don't match the
+    /// generic parameters of the trait declaration
+    TraitGenerics,
+    /// The number of trait references for the parent clauses doesn't match the
+    /// number of parent clauses
+    ParentClauses { expected: usize, found: usize },
+    /// The number of trait references of an associated type doesn't match the
+    /// number of clauses of its declaration
+    TypeClauses {
+        name: TraitItemName,
+        expected: usize,
+        found: usize,
+    },
+    /// The type of an associated constant is not the type declared in the
+    /// trait
+    ConstType {
+        name: TraitItemName,
+        expected: Ty,
+        found: Ty,
+    },
+    /// The generic parameters of a method don't match the parameters of its
+    /// declaration
+    MethodGenerics(TraitItemName),
+    /// The number of inputs of a method doesn't match its declaration
+    MethodArity {
+        name: TraitItemName,
+        expected: usize,
+        found: usize,
+    },
+    /// The type of an input of a method is not the type declared in the trait
+    MethodInput {
+        name: TraitItemName,
+        index: usize,
+        expected: Ty,
+        found: Ty,
+    },
+    /// The output type of a method is not the type declared in the trait
+    MethodOutput {
+        name: TraitItemName,
+        expected: Ty,
+        found: Ty,
+    },
+}
+
+/// Replace the projections of the associated types of the impl we check with
+/// the types provided by the impl. The impl is referred to by [TraitInstanceId::SelfId]
+/// in the signatures of the methods (both in the trait declaration and in the
+/// impl), or by its id.
+struct NormalizeImplTypes<'a> {
+    impl_id: TraitImplId::Id,
+    types: &'a [(TraitItemName, (Vec<TraitRef>, Ty))],
+}
+
+impl<'a> MutTypeVisitor for NormalizeImplTypes<'a> {
+    fn visit_ty(&mut self, ty: &mut Ty) {
+        if let Ty::TraitType(trait_ref, _, name) = ty
+            && match trait_ref.trait_id {
+                TraitInstanceId::SelfId => true,
+                TraitInstanceId::TraitImpl(id) => id == self.impl_id,
+                _ => false,
+            }
+            && let Some((_, (_, new_ty))) = self.types.iter().find(|(n, _)| n == name)
+        {
+            *ty = new_ty.clone();
+        }
+        self.default_visit_ty(ty)
+    }
+}
+
+struct Checker<'a, 'tcx, 'ctx> {
+    ctx: &'a TransCtx<'tcx, 'ctx>,
+    trait_decl: &'a TraitDecl,
+    trait_impl: &'a TraitImpl,
+    mismatches: Vec<TraitImplMismatch>,
+}
+
+impl<'a, 'tcx, 'ctx> Checker<'a, 'tcx, 'ctx> {
+    fn new(ctx: &'a TransCtx<'tcx, 'ctx>, trait_impl: &'a TraitImpl) -> Option<Self> {
+        // The trait declaration may be missing if its translation failed: the
+        // error was already reported
+        let trait_decl = ctx.trait_decls.get(trait_impl.impl_trait.trait_id)?;
+        Some(Checker {
+            ctx,
+            trait_decl,
+            trait_impl,
+            mismatches: Vec::new(),
+        })
+    }
+
+    /// Put a type in normal form, before comparing it.
+    fn normalize(&self, mut ty: Ty) -> Ty {
+        let mut normalizer = NormalizeImplTypes {
+            impl_id: self.trait_impl.def_id,
+            types: &self.trait_impl.types,
+        };
+        normalizer.visit_ty(&mut ty);
+        ty.erase_regions()
+    }
+
+    /// The substitution from the parameters of the trait to the arguments
+    /// given by the impl, if the latter are consistent with the former.
+    fn trait_subst(&self) -> Option<SubstEnv> {
+        let params = &self.trait_decl.generics;
+        let args = &self.trait_impl.impl_trait.generics;
+        // The trait references of the implemented trait may also contain the
+        // instantiations of the parent clauses (which are not parameters)
+        if params.regions.len() != args.regions.len()
+            || params.types.len() != args.types.len()
+            || params.const_generics.len() != args.const_generics.len()
+            || params.trait_clauses.len() > args.trait_refs.len()
+        {
+            return None;
+        }
+        Some(SubstEnv {
+            regions: args.regions.clone(),
+            types: args.types.clone(),
+            const_generics: args.const_generics.clone(),
+            trait_refs: args.trait_refs[..params.trait_clauses.len()].to_vec(),
+        })
+    }
+
+    /// Check that the impl provides exactly the items the trait requires
+    /// (plus, possibly, some of the provided methods).
+    fn check_items(&mut self) {
+        use TraitItemKind::*;
+        let decl = self.trait_decl;
+        let timpl = self.trait_impl;
+        let mut provided: Vec<(TraitItemKind, &TraitItemName)> = Vec::new();
+        provided.extend(timpl.consts.iter().map(|(name, _)| (Const, name)));
+        provided.extend(timpl.types.iter().map(|(name, _)| (Type, name)));
+        provided.extend(
+            timpl
+                .required_methods
+                .iter()
+                .map(|(name, _)| (Method, name)),
+        );
+        provided.extend(
+            timpl
+                .provided_methods
+                .iter()
+                .map(|(name, _)| (Method, name)),
+        );
+
+        // The items of the impl must be declared by the trait
+        let mut seen = HashSet::new();
+        for (kind, name) in &provided {
+            let declared = match kind {
+                Const => decl.consts.iter().any(|(n, _)| n == *name),
+                Type => decl.types.iter().any(|(n, _)| n == *name),
+                Method => {
+                    decl.required_methods.iter().any(|(n, _)| n == *name)
+                        || decl.provided_methods.iter().any(|(n, _)| n == *name)
+                }
+            };
+            if !declared {
+                self.mismatches
+                    .push(TraitImplMismatch::UnknownItem(*kind, (*name).clone()));
+            } else if !seen.insert((*kind, *name)) {
+                self.mismatches
+                    .push(TraitImplMismatch::DuplicateItem(*kind, (*name).clone()));
+            }
+        }
+
+        // The impl must provide the required items. Remark: when the trait
+        // declares default values for the associated constants and types, the
+        // impl contains those values if it doesn't override them.
+        let required = decl
+            .consts
+            .iter()
+            .map(|(name, _)| (Const, name))
+            .chain(decl.types.iter().map(|(name, _)| (Type, name)))
+            .chain(decl.required_methods.iter().map(|(name, _)| (Method, name)));
+        for (kind, name) in required {
+            if !provided.iter().any(|(k, n)| *k == kind && *n == name) {
+                self.mismatches
+                    .push(TraitImplMismatch::MissingItem(kind, name.clone()));
+            }
+        }
+    }
+
+    /// Check the trait references of the parent clauses and of the associated
+    /// types.
+    fn check_clauses(&mut self) {
+        let decl = self.trait_decl;
+        let timpl = self.trait_impl;
+        if decl.parent_clauses.len() != timpl.parent_trait_refs.len() {
+            self.mismatches.push(TraitImplMismatch::ParentClauses {
+                expected: decl.parent_clauses.len(),
+                found: timpl.parent_trait_refs.len(),
+            });
+        }
+
+        for (name, (trait_refs, _)) in &timpl.types {
+            if let Some((_, (clauses, _))) = decl.types.iter().find(|(n, _)| n == name)
+                && clauses.len() != trait_refs.len()
+            {
+                self.mismatches.push(TraitImplMismatch::TypeClauses {
+                    name: name.clone(),
+                    expected: clauses.len(),
+                    found: trait_refs.len(),
+                });
+            }
+        }
+    }
+
+    fn check_consts(&mut self, subst: &SubstEnv) {
+        for (name, (ty, _)) in &self.trait_impl.consts {
+            let Some((_, (decl_ty, _))) = self.trait_decl.consts.iter().find(|(n, _)| n == name)
+            else {
+                continue;
+            };
+            let expected = self.normalize(decl_ty.substitute_with(subst));
+            let found = self.normalize(ty.clone());
+            if expected != found {
+                self.mismatches.push(TraitImplMismatch::ConstType {
+                    name: name.clone(),
+                    expected,
+                    found,
+                });
+            }
+        }
+    }
+
+    /// Check the signature of a method of the impl against the signature of
+    /// its declaration.
+    fn check_method(
+        &mut self,
+        subst: &SubstEnv,
+        name: &TraitItemName,
+        decl_id: FunDeclId::Id,
+        impl_id: FunDeclId::Id,
+    ) {
+        // The declarations may be missing if their translation failed: the
+        // error was already reported
+        let (Some(decl_fun), Some(impl_fun)) = (
+            self.ctx.fun_decls.get(decl_id),
+            self.ctx.fun_decls.get(impl_id),
+        ) else {
+            return;
+        };
+        let decl_sig = &decl_fun.signature;
+        let impl_sig = &impl_fun.signature;
+        let (Some(decl_info), Some(impl_info)) =
+            (&decl_sig.parent_params_info, &impl_sig.parent_params_info)
+        else {
+            return;
+        };
+
+        // The generics of the methods start with the generics of their parent
+        // (the trait or the impl), followed by their own generics: the own
+        // generics of the declaration are mapped to the own generics of the
+        // impl method.
+        let decl_generics = &decl_sig.generics;
+        let impl_generics = &impl_sig.generics;
+        let own_count = |total: usize, parent: usize| total.checked_sub(parent);
+        let own_decl = (
+            own_count(decl_generics.regions.len(), decl_info.num_region_params),
+            own_count(decl_generics.types.len(), decl_info.num_type_params),
+            own_count(
+                decl_generics.const_generics.len(),
+                decl_info.num_const_generic_params,
+            ),
+            own_count(
+                decl_generics.trait_clauses.len(),
+                decl_info.num_trait_clauses,
+            ),
+        );
+        let own_impl = (
+            own_count(impl_generics.regions.len(), impl_info.num_region_params),
+            own_count(impl_generics.types.len(), impl_info.num_type_params),
+            own_count(
+                impl_generics.const_generics.len(),
+                impl_info.num_const_generic_params,
+            ),
+            own_count(
+                impl_generics.trait_clauses.len(),
+                impl_info.num_trait_clauses,
+            ),
+        );
+        if own_decl != own_impl
+            || decl_info.num_type_params != subst.types.len()
+            || decl_info.num_const_generic_params != subst.const_generics.len()
+            || decl_info.num_trait_clauses != subst.trait_refs.len()
+        {
+            self.mismatches
+                .push(TraitImplMismatch::MethodGenerics(name.clone()));
+            return;
+        }
+
+        // We erase the regions when comparing the types: we don't need to map
+        // them precisely
+        let method_subst = SubstEnv {
+            regions: vec![Region::Erased; decl_generics.regions.len()],
+            types: subst
+                .types
+                .iter()
+                .cloned()
+                .chain(
+                    impl_generics
+                        .types
+                        .iter()
+                        .skip(impl_info.num_type_params)
+                        .map(|v| Ty::TypeVar(v.index)),
+                )
+                .collect(),
+            const_generics: subst
+                .const_generics
+                .iter()
+                .cloned()
+                .chain(
+                    impl_generics
+                        .const_generics
+                        .iter()
+                        .skip(impl_info.num_const_generic_params)
+                        .map(|v| ConstGeneric::Var(v.index)),
+                )
+                .collect(),
+            trait_refs: subst
+                .trait_refs
+                .iter()
+                .cloned()
+                .chain(
+                    impl_generics
+                        .trait_clauses
+                        .iter()
+                        .skip(impl_info.num_trait_clauses)
+                        .map(|clause| TraitRef {
+                            trait_id: TraitInstanceId::Clause(clause.clause_id),
+                            generics: clause.generics.clone(),
+                            trait_decl_ref: TraitDeclRef {
+                                trait_id: clause.trait_id,
+                                generics: clause.generics.clone(),
+                            },
+                        }),
+                )
+                .collect(),
+        };
+
+        let (inputs, output) = decl_sig.substitute_inputs_output(&method_subst);
+        if inputs.len() != impl_sig.inputs.len() {
+            self.mismatches.push(TraitImplMismatch::MethodArity {
+                name: name.clone(),
+                expected: inputs.len(),
+                found: impl_sig.inputs.len(),
+            });
+            return;
+        }
+        for (index, (expected, found)) in inputs.into_iter().zip(&impl_sig.inputs).enumerate() {
+            let expected = self.normalize(expected);
+            let found = self.normalize(found.clone());
+            if expected != found {
+                self.mismatches.push(TraitImplMismatch::MethodInput {
+                    name: name.clone(),
+                    index,
+                    expected,
+                    found,
+                });
+            }
+        }
+        let expected = self.normalize(output);
+        let found = self.normalize(impl_sig.output.clone());
+        if expected != found {
+            self.mismatches.push(TraitImplMismatch::MethodOutput {
+                name: name.clone(),
+                expected,
+                found,
+            });
+        }
+    }
+
+    fn check_methods(&mut self, subst: &SubstEnv) {
+        let decl = self.trait_decl;
+        let timpl = self.trait_impl;
+        for (name, impl_id) in timpl.required_methods.iter().chain(&timpl.provided_methods) {
+            // Remark: we don't have the ids of the provided methods of the
+            // external traits
+            let decl_id = decl
+                .required_methods
+                .iter()
+                .find(|(n, _)| n == name)
+                .map(|(_, id)| *id)
+                .or_else(|| {
+                    decl.provided_methods
+                        .iter()
+                        .find(|(n, _)| n == name)
+                        .and_then(|(_, id)| *id)
+                });
+            if let Some(decl_id) = decl_id {
+                self.check_method(subst, name, decl_id, *impl_id);
+            }
+        }
+    }
+
+    fn check(&mut self) {
+        self.check_items();
+        self.check_clauses();
+        match self.trait_subst() {
+            Some(subst) => {
+                self.check_consts(&subst);
+                self.check_methods(&subst);
+            }
+            None => self.mismatches.push(TraitImplMismatch::TraitGenerics),
+        }
+    }
+
+    /// Report the mismatches we found.
+    fn report(&self) {
+        let ctx = self.ctx;
+        let fmt_ctx = ctx.into_fmt();
+        let timpl = self.trait_impl;
+        let impl_ctx = fmt_ctx.set_generics(&timpl.generics);
+        let impl_name = timpl.name.fmt_with_ctx(&fmt_ctx);
+        let trait_name = self.trait_decl.name.fmt_with_ctx(&fmt_ctx);
+        // The types of the methods refer to the generics of the methods
+        let method_ctx = |name: &TraitItemName| {
+            timpl
+                .required_methods
+                .iter()
+                .chain(&timpl.provided_methods)
+                .find(|(n, _)| n == name)
+                .and_then(|(_, id)| ctx.fun_decls.get(*id))
+                .map(|f| fmt_ctx.set_generics(&f.signature.generics))
+        };
+        let fmt_tys = |name: &TraitItemName, expected: &Ty, found: &Ty| match method_ctx(name) {
+            Some(c) => (expected.fmt_with_ctx(&c), found.fmt_with_ctx(&c)),
+            None => (
+                expected.fmt_with_ctx(&impl_ctx),
+                found.fmt_with_ctx(&impl_ctx),
+            ),
+        };
+
+        for mismatch in &self.mismatches {
+            use TraitImplMismatch::*;
+            let msg = match mismatch {
+                MissingItem(kind, name) => format!("missing {kind} `{name}`"),
+                UnknownItem(kind, name) => {
+                    format!("the {kind} `{name}` is not declared in the trait")
+                }
+                DuplicateItem(kind, name) => format!("the {kind} `{name}` is provided twice"),
+                TraitGenerics => {
+                    "the arguments of the trait don't match its parameters".to_string()
+                }
+                ParentClauses { expected, found } => format!(
+                    "expected {expected} trait references for the parent clauses, found {found}"
+                ),
+                TypeClauses {
+                    name,
+                    expected,
+                    found,
+                } => format!(
+                    "expected {expected} trait references for the associated type `{name}`, \
+                     found {found}"
+                ),
+                ConstType {
+                    name,
+                    expected,
+                    found,
+                } => format!(
+                    "the associated constant `{name}` has type {}, expected {}",
+                    found.fmt_with_ctx(&impl_ctx),
+                    expected.fmt_with_ctx(&impl_ctx)
+                ),
+                MethodGenerics(name) => format!(
+                    "the generic parameters of the method `{name}` don't match its declaration"
+                ),
+                MethodArity {
+                    name,
+                    expected,
+                    found,
+                } => format!("the method `{name}` has {found} inputs, expected {expected}"),
+                MethodInput {
+                    name,
+                    index,
+                    expected,
+                    found,
+                } => {
+                    let (expected, found) = fmt_tys(name, expected, found);
+                    format!(
+                        "the input {index} of the method `{name}` has type {found}, \
+                         expected {expected}"
+                    )
+                }
+                MethodOutput {
+                    name,
+                    expected,
+                    found,
+                } => {
+                    let (expected, found) = fmt_tys(name, expected, found);
+                    format!("the method `{name}` has output type {found}, expected {expected}")
+                }
+            };
+            ctx.session.span_warn(
+                timpl.meta.span.rust_span,
+                format!("Inconsistent implementation {impl_name} of trait {trait_name}: {msg}"),
+            );
+        }
+    }
+}
+
+/// Check a trait implementation, and return the mismatches with its trait
+/// declaration.
+pub fn check_trait_impl(ctx: &TransCtx, trait_impl: &TraitImpl) -> Vec<TraitImplMismatch> {
+    match Checker::new(ctx, trait_impl) {
+        Some(mut checker) => {
+            checker.check();
+            checker.mismatches
+        }
+        None => Vec::new(),
+    }
+}
+
+pub fn check(ctx: &TransCtx) {
+    for trait_impl in ctx.trait_impls.iter() {
+        if let Some(mut checker) = Checker::new(ctx, trait_impl) {
+            checker.check();
+            checker.report();
+        }
+    }
+}
@@ -91,15 +91,46 @@ performs: `y := (x as E2).1`). Producing a better reconstruction is non-trivial.
     pub no_code_duplication: bool,
     /// A list of modules of the extracted crate that we consider as opaque: we
     /// extract only the signature information, without the definition content
-    /// (of the functions, types, etc.).
+    /// (of the functions, types, etc.). We emit a warning if the body of an
+    /// opaque function or global may panic, contains unsafe code or accesses a
+    /// mutable static.
     #[structopt(long = "opaque")]
     pub opaque_modules: Vec<String>,
+    /// A list of traits (ex.: `Debug`, `Serialize`) whose implementations
+    /// produced by derive macros we consider as opaque. Those implementations
+    /// tend to dominate the size of the generated files, while they are rarely
+    /// verification targets.
+    #[structopt(long = "skip-derived")]
+    pub skip_derived: Vec<String>,
+    /// A list of dependency crates whose items we translate transparently: by
+    /// default, we only extract the signatures of the items of the other
+    /// crates. The bodies are retrieved from the crate metadata, which only
+    /// contains the optimized MIR of the generic and inline functions and of
+    /// the constants, unless the dependency was compiled with
+    /// `-Zalways-encode-mir`: the items whose MIR is not available remain
+    /// opaque. Note that the names of the items start with the name of their
+    /// crate (ex.: `mylib::foo`).
+    #[structopt(long = "extract-dependencies")]
+    pub extract_dependencies: Vec<String>,
+    /// The entry points of the crate, designated by their names as printed by
+    /// Charon (ex.: `mycrate::main`). The functions marked with
+    /// `#[charon::entry]` are also entry points. If there are entry points, we
+    /// remove the declarations which are not reachable from them after the
+    /// translation, while keeping the opaque declarations they use (see
+    /// [crate::reachability]).
+    #[structopt(long = "start-from")]
+    pub start_from: Vec<String>,
     /// Do not provide a Rust version argument to Cargo (e.g., `+nightly-2022-01-29`).
     /// This is for Nix: outside of Nix, we use Rustup to call the proper version
     /// of Cargo (and thus need this argument), but within Nix we build and call a very
     /// specific version of Cargo.
     #[structopt(long = "cargo-no-rust-version")]
     pub cargo_no_rust_version: bool,
+    /// The target triple to build the crate for (this is given to Cargo). The
+    /// extraction depends on the target: in particular, the size of `usize` and
+    /// `isize` is the width of the target pointers (see [crate::target]).
+    #[structopt(long = "target")]
+    pub target: Option<String>,
     /// Panic on the first error. This is useful for debugging.
     #[structopt(long = "abort-on-error")]
     pub abort_on_error: bool,
@@ -132,8 +163,408 @@ Print the final LLBC (after all the cleaning micro-passes).
 "
     )]
     pub print_llbc: bool,
+    /// How to print the names of the declarations (in the logs, and with the
+    /// `--print-*` options).
+    #[structopt(flatten)]
+    pub names: NameOpts,
+    /// Evaluate the operations on constant scalars, simplify the switches over
+    /// constant values and propagate the constants stored in temporaries (see
+    /// [crate::fold_constants]). The MIR built by rustc contains a lot of
+    /// such noise, in particular with `--mir_promoted`.
+    #[structopt(long = "fold-constants")]
+    pub fold_constants: bool,
+    /// Reconstruct the `for` loops from their desugaring (with calls to
+    /// `IntoIterator::into_iter` and `Iterator::next`), and introduce
+    /// dedicated [crate::llbc_ast::ForLoop] statements (see
+    /// [crate::reconstruct_for_loops]).
+    #[structopt(long = "reconstruct-for-loops")]
+    pub reconstruct_for_loops: bool,
+    /// Reconstruct the applications of the `?` operator from their desugaring
+    /// (with calls to `Try::branch` and `FromResidual::from_residual`), and
+    /// introduce dedicated [crate::llbc_ast::QuestionMark] statements (see
+    /// [crate::reconstruct_question_marks]).
+    #[structopt(long = "reconstruct-question-marks")]
+    pub reconstruct_question_marks: bool,
+    /// Compute the pure forms of the small functions without loops, panics or
+    /// mutable borrows (as expressions built from `let`, `if`, `match` and
+    /// applications), and export them together with the function bodies (see
+    /// [crate::pure_forms]). The proof assistants can consume them directly
+    /// as definitions.
+    #[structopt(long = "emit-pure-forms")]
+    pub emit_pure_forms: bool,
+    /// Compute a conservative bound on the depth of the nested calls of every
+    /// function (or mark it as unbounded, in case of recursion), from the call
+    /// graph, and export it together with the function declarations (see
+    /// [crate::call_depth]). This is useful for the stack-safety arguments.
+    #[structopt(long = "call-depth")]
+    pub call_depth: bool,
+    /// Type-check the generated bodies (the projections of the places, the
+    /// operands of the assignments, the aggregates and the calls) against the
+    /// translated declarations, and report the inconsistencies as errors (see
+    /// [crate::type_check]). This is useful to catch the translation bugs.
+    #[structopt(long = "check")]
+    pub check: bool,
+    /// Compute metrics about the function bodies (number of statements, of
+    /// blocks, of loops, etc.) and export them to a `.metrics.json` file,
+    /// next to the generated `.llbc` (or `.ullbc`) file.
+    #[structopt(long = "metrics")]
+    pub metrics: bool,
+    /// Report the calls which we could not resolve statically (calls which
+    /// depend on a trait clause, calls to the methods of trait objects, calls
+    /// through function pointers) and export the report to a
+    /// `.devirtualization.json` file, next to the generated `.llbc` (or
+    /// `.ullbc`) file. This shows where generic reasoning will be required in
+    /// the proofs.
+    #[structopt(long = "devirtualization-report")]
+    pub devirtualization_report: bool,
+    /// Report the calls whose trait resolution went through blanket
+    /// implementations (implementations like `impl<T: Foo> Bar for T`) and
+    /// export the report to a `.blanket_impls.json` file, next to the generated
+    /// `.llbc` (or `.ullbc`) file.
+    #[structopt(long = "blanket-impls-report")]
+    pub blanket_impls_report: bool,
+    /// Export the dependency graph between the declarations (the graph we use
+    /// to order them) to a `.deps.json` file, next to the generated `.llbc`
+    /// (or `.ullbc`) file. The opaque declarations are marked, so that the
+    /// consumers can slice the crate (see [crate::dependencies]).
+    #[structopt(long = "dependency-graph")]
+    pub dependency_graph: bool,
+    /// Export statistics about the extraction to a `.stats.json` file, next to
+    /// the generated `.llbc` (or `.ullbc`) file: the number of translated,
+    /// opaque and erroneous declarations, the number of errors of every kind
+    /// (unsupported features, etc.), and the items which took the longest to
+    /// translate (see [crate::stats]).
+    #[structopt(long = "stats")]
+    pub stats: bool,
+    /// Generate Rust stubs for the opaque functions (with their generic
+    /// parameters and where clauses) and export them to a `.stubs.rs` file,
+    /// next to the generated `.llbc` (or `.ullbc`) file. This allows to inspect
+    /// the API surface that the extracted code assumes.
+    #[structopt(long = "stubs")]
+    pub stubs: bool,
+    /// Generate a manifest describing the extraction (the hash of the input
+    /// crate, the toolchain, the options, the micro-passes, the hash of the
+    /// generated file, etc.) in a `.manifest.json` file, next to the generated
+    /// `.llbc` (or `.ullbc`) file. This allows auditing and reproducing the
+    /// extraction.
+    #[structopt(long = "manifest")]
+    pub manifest: bool,
+    /// Keep the unwinding edges: the terminators record the cleanup blocks to
+    /// go to if they panic, and we distinguish the panics which unwind the stack
+    /// from the aborts. This allows analyzing the behaviour of the code in case
+    /// of panic (exception-safety, memory leaks, etc.). Note that the control-flow
+    /// reconstruction ignores the unwinding edges: this is mostly useful together
+    /// with `--ullbc`.
+    #[structopt(long = "keep-unwind")]
+    pub keep_unwind: bool,
+    /// Keep the builtin/auto traits like `Sized`, `Send` or `Sync`: by default,
+    /// we remove the clauses which refer to them (see [crate::assumed]). This
+    /// is useful for the backends which need those obligations. We then also
+    /// list the auto traits that rustc implements for the types of the crate.
+    #[structopt(long = "keep-marker-traits")]
+    pub keep_marker_traits: bool,
+    /// Compute the layouts of the type declarations for the target (size,
+    /// alignment, and how the discriminant is encoded, in particular in case of
+    /// niche optimization). This is needed for the verification of unsafe code
+    /// and for reasoning about FFI. Note that we can't compute the layouts of
+    /// the types whose layout depends on their type parameters.
+    #[structopt(long = "layouts")]
+    pub layouts: bool,
+    /// Report the progress of the extraction (item started/finished, pass
+    /// started, warning emitted) as JSON-lines events, on the given file
+    /// descriptor. This allows IDEs and build orchestrators to display live
+    /// progress for long extractions.
+    #[structopt(long = "progress-fd")]
+    pub progress_fd: Option<i32>,
+    /// How to handle the formatting machinery of the standard library
+    /// (`core::fmt`), which is used by `Debug`, `Display`, `format!`, etc.:
+    /// - `opaque` (the default): replace the calls to the formatting functions
+    ///   (`Arguments::new_v1`, the methods of `Formatter`, etc.) with calls to
+    ///   an opaque assumed function, so that we don't extract their signatures
+    ///   and the declarations they depend on
+    /// - `drop`: same as `opaque`, but also remove the calls whose result is
+    ///   never used, together with the calls to `print!` and `eprint!` (this
+    ///   only applies to the LLBC)
+    /// - `extract`: treat the formatting functions like any other function
+    #[structopt(
+        long = "fmt-machinery",
+        default_value = "opaque",
+        possible_values = &["opaque", "drop", "extract"]
+    )]
+    pub fmt_machinery: FmtMachinery,
+    /// How to handle the calls to the intrinsics of `core::intrinsics` that we
+    /// model (`transmute`, `copy_nonoverlapping`, `discriminant_value` and
+    /// `size_of_val`, see [crate::assumed::get_intrinsic_id]):
+    /// - `model` (the default): translate them to calls to dedicated assumed
+    ///   functions
+    /// - `opaque`: don't extract the bodies of the functions which call them
+    #[structopt(
+        long = "intrinsics",
+        default_value = "model",
+        possible_values = &["model", "opaque"]
+    )]
+    pub intrinsics: Intrinsics,
+    /// Extract the crates whose roots (the directories containing the
+    /// `Cargo.toml` files) are given, in order, while sharing the file table
+    /// and the id spaces between them (see [crate::batch]). This is useful to
+    /// extract a crate together with its dependencies, or the crates of a
+    /// workspace. The crates should be given in dependency order: the
+    /// references to the declarations of the crates extracted before use the
+    /// ids of those declarations. We generate one file per crate, and a batch
+    /// index listing them (`batch.index.json`), in the destination directory.
+    #[structopt(long = "batch", parse(from_os_str))]
+    pub batch: Vec<PathBuf>,
+    /// Together with `--batch`: also merge the generated files into a single
+    /// file (`batch.llbc` or `batch.ullbc`), in the destination directory
+    /// (see the `merge` subcommand).
+    #[structopt(long = "batch-merge")]
+    pub batch_merge: bool,
+    /// Share the file table and the id spaces with the crates previously
+    /// registered in the given batch index, and register the extracted crate
+    /// in this index (see [crate::batch]). The ids of the declarations of the
+    /// different crates are then disjoint, and the references to the
+    /// declarations of the previous crates are linked to those declarations,
+    /// which allows combining the generated files. This is set by `--batch`,
+    /// but can also be used by external build orchestrators.
+    #[structopt(long = "batch-index", parse(from_os_str))]
+    pub batch_index: Option<PathBuf>,
+    /// A file declaring additional assumed functions, which extends the
+    /// builtin table of [crate::assumed]. The file maps paths to the ids of
+    /// builtin functions (ex.: `Panic` or `VecPush`, see [crate::assumed::FunId])
+    /// and, optionally, to masks of the used type parameters and arguments. It
+    /// can be a TOML file (if its extension is `.toml`) or a JSON file (see
+    /// [crate::assumed::AssumedFuns::load] for the format).
+    #[structopt(long = "assumed", parse(from_os_str))]
+    pub assumed_funs: Option<PathBuf>,
+    /// The format of the generated files:
+    /// - `json` (the default)
+    /// - `bincode` and `cbor`: binary formats, which are more compact and
+    ///   faster to load. Note that only the Rust reader of
+    ///   [crate::deserialize] supports them (not the OCaml one).
+    /// The extension of the generated files doesn't change: the readers
+    /// detect the format (see [crate::deserialize::detect_format]).
+    #[structopt(
+        long = "format",
+        default_value = "json",
+        possible_values = &["json", "bincode", "cbor"]
+    )]
+    pub format: OutputFormat,
+    /// Rewrite the prefixes of the file names in the spans, given as
+    /// `FROM=TO` (ex.: `--path-prefix-map /home/me/project=`). Giving the root
+    /// of the workspace with an empty replacement makes the file names
+    /// relative to it, and the generated files reproducible across machines.
+    /// This option can be repeated: if several prefixes match, the last one
+    /// wins (like with the `--remap-path-prefix` option of rustc).
+    #[structopt(long = "path-prefix-map")]
+    pub path_prefix_map: Vec<PathPrefix>,
+    /// Store the exact source text of every translated item in the generated
+    /// files, so that the downstream tools can display the code (for instance
+    /// to report counterexamples) without having access to the original
+    /// sources. Combined with the byte offsets of the spans, this gives the
+    /// text of every span of the bodies.
+    #[structopt(long = "emit-source")]
+    pub emit_source: bool,
+    /// Store the translated functions and globals in a cache, in the given
+    /// directory (`CRATE.cache.json`), and reuse them in the next runs: when
+    /// we extract a crate again after a small edit, we only translate again
+    /// the items which changed, or whose dependencies changed (see
+    /// [crate::cache]). The generated files are the same as without the
+    /// cache.
+    #[structopt(long = "cache", parse(from_os_str))]
+    pub cache: Option<PathBuf>,
+    /// Subcommands which operate on the files generated by Charon (if no
+    /// subcommand is given, we extract the crate).
+    #[structopt(subcommand)]
+    pub command: Option<Command>,
+}
+
+/// A rewriting of the prefixes of the file names (see
+/// [CliOpts::path_prefix_map]).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PathPrefix {
+    pub from: PathBuf,
+    pub to: PathBuf,
+}
+
+impl std::str::FromStr for PathPrefix {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // We split at the last `=`, like rustc
+        match s.rsplit_once('=') {
+            Some((from, to)) => Ok(PathPrefix {
+                from: PathBuf::from(from),
+                to: PathBuf::from(to),
+            }),
+            None => Err(format!("Expected a path prefix mapping `FROM=TO`: {s}")),
+        }
+    }
+}
+
+/// How to handle the formatting machinery (see [CliOpts::fmt_machinery]).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FmtMachinery {
+    Opaque,
+    Drop,
+    Extract,
+}
+
+impl std::str::FromStr for FmtMachinery {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "opaque" => Ok(FmtMachinery::Opaque),
+            "drop" => Ok(FmtMachinery::Drop),
+            "extract" => Ok(FmtMachinery::Extract),
+            _ => Err(format!("Unknown formatting policy: {s}")),
+        }
+    }
+}
+
+/// How to handle the intrinsics (see [CliOpts::intrinsics]).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Intrinsics {
+    Model,
+    Opaque,
 }
 
+impl std::str::FromStr for Intrinsics {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "model" => Ok(Intrinsics::Model),
+            "opaque" => Ok(Intrinsics::Opaque),
+            _ => Err(format!("Unknown policy for the intrinsics: {s}")),
+        }
+    }
+}
+
+/// The format of the generated files (see [CliOpts::format]).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OutputFormat {
+    Json,
+    Bincode,
+    Cbor,
+}
+
+impl std::str::FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "json" => Ok(OutputFormat::Json),
+            "bincode" => Ok(OutputFormat::Bincode),
+            "cbor" => Ok(OutputFormat::Cbor),
+            _ => Err(format!("Unknown output format: {s}")),
+        }
+    }
+}
+
+#[derive(StructOpt, Serialize, Deserialize)]
+pub enum Command {
+    /// Pretty-print the declarations of a `.llbc` (or `.ullbc`) file, without
+    /// re-running the extraction.
+    #[structopt(name = "print")]
+    Print(PrintOpts),
+    /// Answer simple queries about the declarations of a `.llbc` (or `.ullbc`)
+    /// file, without re-running the extraction.
+    #[structopt(name = "query")]
+    Query(QueryOpts),
+    /// Compare the summary statistics (number of items, of statements, of
+    /// opaque declarations, etc.) of two `.llbc` (or `.ullbc`) files. This
+    /// allows detecting when a change (for instance, a toolchain bump) degrades
+    /// the coverage of the extraction.
+    #[structopt(name = "diff")]
+    Diff(DiffOpts),
+    /// Merge the files registered in a batch index (see `--batch`) into a
+    /// single file, generated next to the index.
+    #[structopt(name = "merge")]
+    Merge(MergeOpts),
+}
+
+#[derive(StructOpt, Serialize, Deserialize)]
+pub struct PrintOpts {
+    /// The file to print
+    #[structopt(parse(from_os_str))]
+    pub file: PathBuf,
+    /// Only print the declarations whose name matches this regular expression
+    /// (note that we match the names as they are printed)
+    #[structopt(long = "filter")]
+    pub filter: Option<String>,
+    #[structopt(flatten)]
+    pub names: NameOpts,
+}
+
+/// The options to print the names of the declarations.
+#[derive(StructOpt, Clone, Default, Serialize, Deserialize)]
+pub struct NameOpts {
+    /// Don't print the crate prefixes of the names (ex.: print `option::Option`
+    /// rather than `core::option::Option`). Note that the names may then be
+    /// ambiguous.
+    #[structopt(long = "no-crate-prefix")]
+    pub no_crate_prefix: bool,
+    /// Shorten the names of the declarations of the current crate by omitting
+    /// their crate prefix, while still printing the crate prefixes of the names
+    /// coming from external crates.
+    #[structopt(long = "relative-names")]
+    pub relative_names: bool,
+}
+
+#[derive(StructOpt, Serialize, Deserialize)]
+pub struct QueryOpts {
+    /// The file to query
+    #[structopt(parse(from_os_str))]
+    pub file: PathBuf,
+    #[structopt(subcommand)]
+    pub query: Query,
+}
+
+/// The queries. The declarations are designated by their names, as printed
+/// by Charon (ex.: `crate_name::module::function`).
+#[derive(StructOpt, Serialize, Deserialize)]
+pub enum Query {
+    /// List the functions which call the function NAME
+    #[structopt(name = "callers-of")]
+    CallersOf { name: String },
+    /// Print the signature of the function NAME, or the type of the global NAME,
+    /// or the definition of the type NAME
+    #[structopt(name = "type-of")]
+    TypeOf { name: String },
+    /// List the implementations of the trait NAME
+    #[structopt(name = "impls-of")]
+    ImplsOf { name: String },
+}
+
+#[derive(StructOpt, Serialize, Deserialize)]
+pub struct DiffOpts {
+    /// The reference file
+    #[structopt(parse(from_os_str))]
+    pub old: PathBuf,
+    /// The file to compare to the reference
+    #[structopt(parse(from_os_str))]
+    pub new: PathBuf,
+    /// Fail if the coverage of the extraction degraded, that is if there are
+    /// fewer declarations, more opaque declarations or more unresolved trait
+    /// instances in the new file
+    #[structopt(long = "fail-on-regression")]
+    pub fail_on_regression: bool,
+}
+
+#[derive(StructOpt, Serialize, Deserialize)]
+pub struct MergeOpts {
+    /// The batch index
+    #[structopt(parse(from_os_str))]
+    pub index: PathBuf,
+    /// The name of the merged crate (and of the generated file)
+    #[structopt(long = "name", default_value = "batch")]
+    pub name: String,
+}
+
+/// The name of the batch index we generate when using `--batch`.
+pub const BATCH_INDEX: &str = "batch.index.json";
+
 /// The name of the environment variable we use to save the serialized Cli options
 /// when calling charon-driver from cargo-charon.
 pub const CHARON_ARGS: &str = "CHARON_ARGS";
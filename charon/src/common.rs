@@ -5,6 +5,10 @@ pub static TAB_INCR: &str = "    ";
 pub struct Error {
     pub span: rustc_span::Span,
     pub msg: String,
+    /// We didn't actually fail: we chose not to translate the item (for
+    /// instance, the bodies which call intrinsics with `--intrinsics opaque`),
+    /// and shouldn't report it as an error.
+    pub skipped: bool,
 }
 
 /// Custom function to pretty-print elements from an iterator
@@ -0,0 +1,76 @@
+//! Export the dependency graph between the declarations (types, functions,
+//! globals, trait declarations and trait implementations) to a JSON file.
+//!
+//! This is the graph we use to order the declarations (see
+//! [crate::reorder_decls::compute_dependencies]). It allows the consumers to
+//! slice the crate: the declarations needed to verify a function are the
+//! declarations reachable from it. We mark the opaque declarations (the
+//! declarations without bodies, the opaque types, and the external traits and
+//! implementations): the slices can stop at those declarations, whose own
+//! dependencies only come from their signatures.
+
+use crate::reorder_decls::{compute_dependencies, AnyTransId};
+use crate::translate_ctx::TransCtx;
+use crate::types::TypeDeclKind;
+use serde::Serialize;
+
+/// An edge of the dependency graph.
+#[derive(Debug, Clone, Serialize)]
+pub struct Dependency {
+    pub id: AnyTransId,
+    /// The name of the declaration, as printed by Charon.
+    pub name: String,
+    /// `true` if the dependency is an opaque declaration: we only depend on
+    /// its signature.
+    pub to_opaque: bool,
+}
+
+/// A declaration, together with its dependencies.
+#[derive(Debug, Clone, Serialize)]
+pub struct DeclDependencies {
+    pub id: AnyTransId,
+    /// The name of the declaration, as printed by Charon.
+    pub name: String,
+    pub is_opaque: bool,
+    pub dependencies: Vec<Dependency>,
+}
+
+/// Check if a declaration is opaque (see the explanations at the top of the
+/// file). The declarations which failed to translate are considered opaque.
+pub fn is_opaque(ctx: &TransCtx, id: AnyTransId) -> bool {
+    match id {
+        AnyTransId::Type(id) => ctx.type_decls.get(id).map_or(true, |d| {
+            matches!(d.kind, TypeDeclKind::Opaque | TypeDeclKind::Error(_))
+        }),
+        AnyTransId::Fun(id) => ctx.fun_decls.get(id).map_or(true, |d| d.body.is_none()),
+        AnyTransId::Global(id) => ctx.global_decls.get(id).map_or(true, |d| d.body.is_none()),
+        AnyTransId::TraitDecl(id) => ctx.trait_decls.get(id).map_or(true, |d| !d.is_local),
+        AnyTransId::TraitImpl(id) => ctx.trait_impls.get(id).map_or(true, |d| !d.is_local),
+    }
+}
+
+/// Compute the dependency graph.
+pub fn compute(ctx: &TransCtx) -> Vec<DeclDependencies> {
+    let graph: Vec<DeclDependencies> = compute_dependencies(ctx)
+        .into_iter()
+        .map(|(id, deps)| DeclDependencies {
+            id,
+            name: id.fmt_with_ctx(ctx),
+            is_opaque: is_opaque(ctx, id),
+            dependencies: deps
+                .into_iter()
+                .map(|dep| Dependency {
+                    id: dep,
+                    name: dep.fmt_with_ctx(ctx),
+                    to_opaque: is_opaque(ctx, dep),
+                })
+                .collect(),
+        })
+        .collect();
+    info!(
+        "Dependency graph: {} declarations, {} edges",
+        graph.len(),
+        graph.iter().map(|d| d.dependencies.len()).sum::<usize>()
+    );
+    graph
+}
@@ -0,0 +1,183 @@
+//! Deserialize the files generated by Charon (`.ullbc` and `.llbc`), so that
+//! we can manipulate the translated crates without re-running the extraction.
+//!
+//! Remark: the rust ids and the rust spans are not serialized: we replace them
+//! with dummy values when deserializing.
+
+use crate::cli_options::OutputFormat;
+use crate::export::CrateIndex;
+use crate::gast::*;
+use crate::header::Header;
+use crate::llbc_ast;
+use crate::meta::{FileId, FileName};
+use crate::reorder_decls::DeclarationGroup;
+use crate::target::TargetInfo;
+use crate::types::*;
+use crate::ullbc_ast;
+use crate::ullbc_ast::{GlobalDeclId, ImplBlocks, TraitDecls, TraitImpls};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+/// The data of a serialized crate (see [crate::export::gexport]).
+///
+/// We load all the declarations at once, and thus ignore the index which
+/// gives their positions in the file (see [crate::export::CrateIndex]). We
+/// still need the field, because the binary formats are not self-describing.
+#[derive(Deserialize)]
+#[serde(rename = "Crate")]
+struct GCrateData<FD, GD> {
+    header: Header,
+    name: String,
+    prelude: Prelude,
+    target_info: TargetInfo,
+    id_to_file: Vec<(FileId::Id, FileName)>,
+    declarations: Vec<DeclarationGroup>,
+    types: Vec<TypeDecl>,
+    functions: Vec<FD>,
+    globals: Vec<GD>,
+    trait_decls: Vec<TraitDecl>,
+    trait_impls: Vec<TraitImpl>,
+    impl_blocks: Vec<ImplBlock>,
+    opaque_warnings: Vec<OpaqueWarning>,
+    #[allow(dead_code)]
+    index: Option<CrateIndex>,
+}
+
+/// The header of a serialized crate: we check it before deserializing the
+/// declarations (the other fields are ignored).
+#[derive(Deserialize)]
+struct GCrateHeader {
+    header: Header,
+}
+
+/// A deserialized crate.
+///
+/// This is a generic structure, used both for LLBC and ULLBC.
+pub struct GCrate<FD, GD> {
+    pub header: Header,
+    pub name: String,
+    pub prelude: Prelude,
+    /// The information about the target the extraction assumed
+    pub target_info: TargetInfo,
+    pub id_to_file: HashMap<FileId::Id, FileName>,
+    /// The declarations, grouped by mutually recursive groups and ordered
+    /// in dependency order.
+    pub declarations: Vec<DeclarationGroup>,
+    pub type_decls: TypeDecls,
+    pub fun_decls: FunDeclId::Map<FD>,
+    pub global_decls: GlobalDeclId::Map<GD>,
+    pub trait_decls: TraitDecls,
+    pub trait_impls: TraitImpls,
+    pub impl_blocks: ImplBlocks,
+    /// The declarations which became opaque because of errors
+    pub opaque_warnings: Vec<OpaqueWarning>,
+}
+
+pub type UllbcCrate = GCrate<ullbc_ast::FunDecl, ullbc_ast::GlobalDecl>;
+pub type LlbcCrate = GCrate<llbc_ast::FunDecl, llbc_ast::GlobalDecl>;
+
+/// Detect the format of a generated file (see
+/// [crate::cli_options::CliOpts::format]) from its first byte: a JSON file
+/// starts with `{`, and a CBOR file with a map (major type 5). Otherwise, the
+/// file was generated with bincode, and starts with the format version (see
+/// [crate::header::FORMAT_VERSION]).
+pub fn detect_format(path: &Path) -> Result<OutputFormat, String> {
+    let mut file = match File::open(path) {
+        Ok(file) => file,
+        Err(err) => return Err(format!("Could not open {path:?}: {err}")),
+    };
+    let mut first = [0u8; 1];
+    if let Err(err) = file.read_exact(&mut first) {
+        return Err(format!("Could not read {path:?}: {err}"));
+    }
+    Ok(match first[0] {
+        b'{' => OutputFormat::Json,
+        0xa0..=0xbf => OutputFormat::Cbor,
+        _ => OutputFormat::Bincode,
+    })
+}
+
+/// Deserialize a file in the given format.
+fn read<T: DeserializeOwned>(path: &Path, format: OutputFormat) -> Result<T, String> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(err) => return Err(format!("Could not open {path:?}: {err}")),
+    };
+    let reader = std::io::BufReader::new(file);
+    match format {
+        OutputFormat::Json => serde_json::from_reader(reader).map_err(|err| err.to_string()),
+        OutputFormat::Bincode => bincode::deserialize_from(reader).map_err(|err| err.to_string()),
+        OutputFormat::Cbor => ciborium::de::from_reader(reader).map_err(|err| err.to_string()),
+    }
+}
+
+/// Load a crate from a generated file (the format is detected automatically,
+/// see [detect_format]).
+///
+/// This is a generic function, used both for LLBC and ULLBC.
+pub fn gload<T>(path: &Path) -> Result<GCrate<GFunDecl<T>, GGlobalDecl<T>>, String>
+where
+    T: Clone + for<'de> Deserialize<'de>,
+{
+    let format = detect_format(path)?;
+
+    // Check the header first: if the file was generated by an incompatible
+    // version of Charon, the deserialization of the declarations would fail
+    // with an obscure error.
+    let header = match read::<GCrateHeader>(path, format) {
+        Ok(data) => data.header,
+        Err(err) => return Err(format!("Could not read the header of {path:?}: {err}")),
+    };
+    if let Err(msg) = header.check() {
+        return Err(format!("{path:?}: {msg}"));
+    }
+
+    let data: GCrateData<GFunDecl<T>, GGlobalDecl<T>> = match read(path, format) {
+        Ok(data) => data,
+        Err(err) => return Err(format!("Could not deserialize {path:?}: {err}")),
+    };
+
+    // The maps are serialized as vectors: we rebuild them from the ids stored
+    // in the declarations.
+    Ok(GCrate {
+        header: data.header,
+        name: data.name,
+        prelude: data.prelude,
+        target_info: data.target_info,
+        id_to_file: data.id_to_file.into_iter().collect(),
+        declarations: data.declarations,
+        type_decls: data.types.into_iter().map(|d| (d.def_id, d)).collect(),
+        fun_decls: data.functions.into_iter().map(|d| (d.def_id, d)).collect(),
+        global_decls: data.globals.into_iter().map(|d| (d.def_id, d)).collect(),
+        trait_decls: data
+            .trait_decls
+            .into_iter()
+            .map(|d| (d.def_id, d))
+            .collect(),
+        trait_impls: data
+            .trait_impls
+            .into_iter()
+            .map(|d| (d.def_id, d))
+            .collect(),
+        impl_blocks: data
+            .impl_blocks
+            .into_iter()
+            .map(|d| (d.def_id, d))
+            .collect(),
+        opaque_warnings: data.opaque_warnings,
+    })
+}
+
+/// Load a crate from a `.ullbc` file.
+pub fn load_ullbc(path: &Path) -> Result<UllbcCrate, String> {
+    gload(path)
+}
+
+/// Load a crate from a `.llbc` file.
+pub fn load_llbc(path: &Path) -> Result<LlbcCrate, String> {
+    gload(path)
+}
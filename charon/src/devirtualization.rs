@@ -0,0 +1,140 @@
+//! Report the call sites which we could not resolve statically, and export
+//! the report to a JSON file.
+//!
+//! When translating the calls to trait methods, we resolve the implementation
+//! of the trait whenever it is known statically (we then call the method of a
+//! specific trait implementation). The calls which remain are the calls which
+//! depend on a trait clause (for instance, calls to trait methods from within
+//! a polymorphic function), the calls to the methods of trait objects, and the
+//! calls through function pointers. Those are the places where the users will
+//! need generic reasoning in their proofs.
+
+use crate::formatter::{Formatter, IntoFormatter, SetGenerics, SetLocals};
+use crate::gast::{Call, FnOperand};
+use crate::translate_ctx::TransCtx;
+use crate::types::*;
+use crate::ullbc_ast::{FunDeclId, RawTerminator};
+use serde::Serialize;
+
+/// Why a call could not be resolved statically.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VirtualCallReason {
+    /// A call to a trait method, whose implementation is given by a trait
+    /// clause (it depends on the instantiation of the generic parameters).
+    Clause,
+    /// A call to a method of the current trait, from within the declaration
+    /// of the trait (typically, from a provided method).
+    SelfClause,
+    /// A call to a method of a trait object (`dyn Trait`): the method is
+    /// looked up in the vtable at runtime.
+    TraitObject,
+    /// A call through a function pointer.
+    FunctionPointer,
+    /// We failed to resolve the implementation of the trait.
+    Unresolved(String),
+}
+
+/// A call which could not be resolved statically.
+#[derive(Debug, Clone, Serialize)]
+pub struct VirtualCall {
+    /// The called function, as printed by Charon.
+    pub callee: String,
+    /// The location of the call.
+    pub span: String,
+    pub reason: VirtualCallReason,
+}
+
+/// The call sites of a function.
+#[derive(Debug, Clone, Serialize)]
+pub struct FunCallSites {
+    pub def_id: FunDeclId::Id,
+    pub name: String,
+    /// The number of calls we resolved statically.
+    pub static_calls: usize,
+    /// The calls which remained virtual, or which depend on a trait clause.
+    pub virtual_calls: Vec<VirtualCall>,
+}
+
+/// Return the reason why a call to a method of the trait instance `id` can't
+/// be resolved statically, or `None` if it can.
+fn trait_instance_reason(id: &TraitInstanceId) -> Option<VirtualCallReason> {
+    match id {
+        TraitInstanceId::TraitImpl(_)
+        | TraitInstanceId::BuiltinOrAuto(_)
+        | TraitInstanceId::FnPointer(..)
+        | TraitInstanceId::Closure(..) => None,
+        TraitInstanceId::Clause(_) => Some(VirtualCallReason::Clause),
+        TraitInstanceId::SelfId => Some(VirtualCallReason::SelfClause),
+        // The parent clauses and the item clauses are as static as the
+        // instance they come from
+        TraitInstanceId::ParentClause(id, ..) | TraitInstanceId::ItemClause(id, ..) => {
+            trait_instance_reason(id)
+        }
+        TraitInstanceId::Unsolved(..) => Some(VirtualCallReason::Unresolved(
+            "unsolved trait obligation".to_string(),
+        )),
+        TraitInstanceId::Unknown(msg) => Some(VirtualCallReason::Unresolved(msg.clone())),
+    }
+}
+
+/// Return the reason why a call can't be resolved statically, or `None` if it
+/// can.
+fn call_reason(call: &Call) -> Option<VirtualCallReason> {
+    use crate::expressions::FunIdOrTraitMethodRef;
+    match &call.func {
+        FnOperand::Regular(fn_ptr) => match &fn_ptr.func {
+            FunIdOrTraitMethodRef::Fun(_) => None,
+            FunIdOrTraitMethodRef::Trait(trait_ref, ..) => {
+                trait_instance_reason(&trait_ref.trait_id)
+            }
+        },
+        FnOperand::Move(_) => Some(VirtualCallReason::FunctionPointer),
+        FnOperand::Virtual(..) => Some(VirtualCallReason::TraitObject),
+    }
+}
+
+/// Compute the call sites of all the functions which have a body.
+pub fn compute(ctx: &TransCtx) -> Vec<FunCallSites> {
+    let fmt_ctx = ctx.into_fmt();
+    let source_map = ctx.session.source_map();
+    let mut report = Vec::new();
+    let mut num_static_calls = 0;
+    let mut num_virtual_calls = 0;
+    for decl in ctx.fun_decls.iter() {
+        let Some(body) = &decl.body else { continue };
+        let generics_ctx = fmt_ctx.set_generics(&decl.signature.generics);
+        let body_ctx = generics_ctx.set_locals(&body.locals);
+
+        let mut static_calls = 0;
+        let mut virtual_calls = Vec::new();
+        for block in body.body.iter() {
+            let RawTerminator::Call { call, .. } = &block.terminator.content else {
+                continue;
+            };
+            match call_reason(call) {
+                None => static_calls += 1,
+                Some(reason) => virtual_calls.push(VirtualCall {
+                    callee: call.func.fmt_with_ctx(&body_ctx),
+                    span: source_map
+                        .span_to_diagnostic_string(block.terminator.meta.span.rust_span),
+                    reason,
+                }),
+            }
+        }
+
+        num_static_calls += static_calls;
+        num_virtual_calls += virtual_calls.len();
+        report.push(FunCallSites {
+            def_id: decl.def_id,
+            name: decl.name.fmt_with_ctx(&fmt_ctx),
+            static_calls,
+            virtual_calls,
+        });
+    }
+    info!(
+        "Call sites: {} resolved statically, {} virtual or depending on a trait clause",
+        num_static_calls, num_virtual_calls
+    );
+    report
+}
@@ -0,0 +1,203 @@
+//! Implementation of the `diff` subcommand, which compares the summary
+//! statistics of two `.llbc` (or `.ullbc`) files: the number of declarations,
+//! of statements, of opaque declarations and of unresolved trait instances.
+//!
+//! Projects which track the health of their extraction can use it (with
+//! `--fail-on-regression`) to detect when a change, typically a toolchain
+//! bump, silently degrades the coverage.
+
+use crate::cli_options::DiffOpts;
+use crate::deserialize;
+use crate::deserialize::GCrate;
+use crate::expressions::SharedExprVisitor;
+use crate::gast::*;
+use crate::llbc_ast;
+use crate::types::*;
+use crate::ullbc_ast;
+use std::path::Path;
+
+/// The summary statistics of a crate.
+#[derive(Debug, Clone, Default)]
+struct Stats {
+    types: usize,
+    functions: usize,
+    globals: usize,
+    trait_decls: usize,
+    trait_impls: usize,
+    /// The number of statements in the bodies. For the ULLBC, we also count
+    /// the terminators of the blocks.
+    statements: usize,
+    /// The number of opaque declarations (types, functions and globals)
+    opaque: usize,
+    /// The number of occurrences of [TraitInstanceId::Unknown], in the
+    /// signatures and the bodies of the functions.
+    unresolved_traits: usize,
+}
+
+/// Counts the statements and the unresolved trait instances.
+#[derive(Default)]
+struct Counter {
+    statements: usize,
+    unresolved_traits: usize,
+}
+
+impl SharedTypeVisitor for Counter {
+    fn visit_trait_instance_id(&mut self, id: &TraitInstanceId) {
+        if let TraitInstanceId::Unknown(_) = id {
+            self.unresolved_traits += 1;
+        }
+        self.default_visit_trait_instance_id(id)
+    }
+}
+
+impl SharedExprVisitor for Counter {}
+impl ullbc_ast::SharedAstVisitor for Counter {}
+
+impl llbc_ast::SharedAstVisitor for Counter {
+    fn spawn(&mut self, visitor: &mut dyn FnMut(&mut Self)) {
+        visitor(self)
+    }
+
+    fn merge(&mut self) {}
+
+    fn visit_statement(&mut self, st: &llbc_ast::Statement) {
+        use llbc_ast::RawStatement;
+        if !matches!(st.content, RawStatement::Block(_) | RawStatement::Nop) {
+            self.statements += 1;
+        }
+        self.default_visit_statement(st)
+    }
+}
+
+/// The bodies we can compute statistics about.
+///
+/// This allows us to compute the statistics generically for LLBC and ULLBC.
+trait CountInBody {
+    fn count(&self, counter: &mut Counter);
+}
+
+impl CountInBody for ullbc_ast::BlockId::Vector<ullbc_ast::BlockData> {
+    fn count(&self, counter: &mut Counter) {
+        use ullbc_ast::SharedAstVisitor;
+        for block in self.iter() {
+            counter.statements += block.statements.len() + 1;
+            counter.visit_block_data(block);
+        }
+    }
+}
+
+impl CountInBody for llbc_ast::Statement {
+    fn count(&self, counter: &mut Counter) {
+        use llbc_ast::SharedAstVisitor;
+        counter.visit_statement(self);
+    }
+}
+
+fn compute_stats<T: CountInBody>(krate: &GCrate<GFunDecl<T>, GGlobalDecl<T>>) -> Stats {
+    let mut counter = Counter::default();
+    let mut opaque = 0;
+    for decl in krate.fun_decls.iter() {
+        counter.visit_fun_sig(&decl.signature);
+        match &decl.body {
+            Some(body) => body.body.count(&mut counter),
+            None => opaque += 1,
+        }
+    }
+    for decl in krate.global_decls.iter() {
+        match &decl.body {
+            Some(body) => body.body.count(&mut counter),
+            None => opaque += 1,
+        }
+    }
+    opaque += krate
+        .type_decls
+        .iter()
+        .filter(|d| matches!(d.kind, TypeDeclKind::Opaque))
+        .count();
+
+    Stats {
+        types: krate.type_decls.len(),
+        functions: krate.fun_decls.len(),
+        globals: krate.global_decls.len(),
+        trait_decls: krate.trait_decls.len(),
+        trait_impls: krate.trait_impls.len(),
+        statements: counter.statements,
+        opaque,
+        unresolved_traits: counter.unresolved_traits,
+    }
+}
+
+/// Load a file and compute its statistics.
+///
+/// We determine the kind of the file (LLBC or ULLBC) from its extension.
+fn load_stats(file: &Path) -> Result<Stats, String> {
+    let is_ullbc = file.extension().map_or(false, |ext| ext == "ullbc");
+    if is_ullbc {
+        Ok(compute_stats(&deserialize::load_ullbc(file)?))
+    } else {
+        Ok(compute_stats(&deserialize::load_llbc(file)?))
+    }
+}
+
+/// Whether an increase of a statistic is an improvement or a regression.
+#[derive(PartialEq, Eq)]
+enum Direction {
+    /// More is better (ex.: the number of declarations)
+    More,
+    /// Less is better (ex.: the number of opaque declarations)
+    Less,
+    /// The statistic is only informative (ex.: the number of statements,
+    /// which decreases when the micro-passes are improved)
+    Neutral,
+}
+
+/// Compare the statistics of two files and print the comparison.
+///
+/// If `--fail-on-regression` is set, we return an error if the coverage of
+/// the extraction degraded.
+pub fn diff(opts: &DiffOpts) -> Result<(), String> {
+    let old = load_stats(&opts.old)?;
+    let new = load_stats(&opts.new)?;
+
+    use Direction::*;
+    let rows = [
+        ("types", old.types, new.types, More),
+        ("functions", old.functions, new.functions, More),
+        ("globals", old.globals, new.globals, More),
+        ("trait declarations", old.trait_decls, new.trait_decls, More),
+        (
+            "trait implementations",
+            old.trait_impls,
+            new.trait_impls,
+            More,
+        ),
+        ("statements", old.statements, new.statements, Neutral),
+        ("opaque declarations", old.opaque, new.opaque, Less),
+        (
+            "unresolved traits",
+            old.unresolved_traits,
+            new.unresolved_traits,
+            Less,
+        ),
+    ];
+
+    let mut regressions = Vec::new();
+    for (name, old, new, direction) in rows {
+        let delta = new as isize - old as isize;
+        let regressed = (direction == More && delta < 0) || (direction == Less && delta > 0);
+        let mark = if regressed { " (regression)" } else { "" };
+        println!("{name}: {old} -> {new} ({delta:+}){mark}");
+        if regressed {
+            regressions.push(name);
+        }
+    }
+
+    if opts.fail_on_regression && !regressions.is_empty() {
+        Err(format!(
+            "The coverage of the extraction degraded: {}",
+            regressions.join(", ")
+        ))
+    } else {
+        Ok(())
+    }
+}
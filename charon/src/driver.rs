@@ -1,19 +1,48 @@
+use crate::assumed;
+use crate::batch;
+use crate::blanket_impls;
+use crate::call_depth;
+use crate::check_generic_args;
+use crate::check_regions;
+use crate::check_trait_impls;
 use crate::cli_options;
+use crate::dependencies;
+use crate::devirtualization;
 use crate::export;
+use crate::fold_constants;
 use crate::get_mir::MirLevel;
+use crate::header::Header;
+use crate::hooks;
+use crate::hooks::ExtractionHook;
 use crate::index_to_function_calls;
 use crate::insert_assign_return_unit;
+use crate::llbc_ast;
+use crate::manifest;
+use crate::metrics;
 use crate::ops_to_function_calls;
+use crate::pure_forms;
+use crate::reachability;
+use crate::reconstruct_aggregates;
 use crate::reconstruct_asserts;
+use crate::reconstruct_boolean_ops;
+use crate::reconstruct_box_new;
+use crate::reconstruct_for_loops;
+use crate::reconstruct_question_marks;
+use crate::remove_dead_assignments;
 use crate::remove_drop_never;
 use crate::remove_dynamic_checks;
+use crate::remove_fmt_calls;
 use crate::remove_nops;
 use crate::remove_read_discriminant;
 use crate::remove_unused_locals;
 use crate::reorder_decls;
+use crate::simplify_cfg;
 use crate::simplify_constants;
+use crate::stats;
+use crate::stubs;
 use crate::translate_crate_to_ullbc;
 use crate::translate_ctx;
+use crate::type_check;
 use crate::ullbc_to_llbc;
 use crate::update_closure_signatures;
 use regex::Regex;
@@ -28,6 +57,9 @@ use std::ops::Deref;
 /// The callbacks for Charon
 pub struct CharonCallbacks {
     pub options: cli_options::CliOpts,
+    /// A hook to customize the extraction (see [crate::hooks]). Library users
+    /// can set it before calling the compiler.
+    pub hook: Option<Box<dyn ExtractionHook>>,
     /// This is to be filled during the extraction
     pub error_count: usize,
 }
@@ -151,12 +183,49 @@ pub fn translate(sess: &Session, tcx: TyCtxt, internal: &mut CharonCallbacks) ->
     let crate_info = translate_ctx::CrateInfo {
         crate_name: crate_name.clone(),
         opaque_mods: HashSet::from_iter(options.opaque_modules.clone().into_iter()),
+        skip_derived: HashSet::from_iter(options.skip_derived.clone().into_iter()),
+        extract_dependencies: HashSet::from_iter(options.extract_dependencies.clone().into_iter()),
+    };
+
+    // # Load the batch index, if we extract the crate as part of a batch
+    // (see [crate::batch]).
+    let batch_index = match &options.batch_index {
+        Some(path) => match batch::BatchIndex::load(path) {
+            Ok(index) => Some(index),
+            Err(msg) => {
+                error!("{}", msg);
+                return Err(());
+            }
+        },
+        None => None,
+    };
+
+    // # Load the registry of the assumed functions (see [crate::assumed]).
+    let assumed_funs = match &options.assumed_funs {
+        Some(path) => match assumed::AssumedFuns::load(path) {
+            Ok(funs) => funs,
+            Err(msg) => {
+                error!("{}", msg);
+                return Err(());
+            }
+        },
+        None => assumed::AssumedFuns::builtin(),
     };
 
     // # Translate the declarations in the crate.
     // We translate the declarations in an ad-hoc order, and do not group
     // the mutually recursive groups - we do this in the next step.
-    let mut ctx = translate_crate_to_ullbc::translate(crate_info, options, sess, tcx, mir_level);
+    let hook = internal.hook.take();
+    let mut ctx = translate_crate_to_ullbc::translate(
+        crate_info,
+        options,
+        batch_index.as_ref(),
+        assumed_funs,
+        hook,
+        sess,
+        tcx,
+        mir_level,
+    );
 
     trace!("# After translation from MIR:\n\n{}\n", ctx);
 
@@ -164,11 +233,48 @@ pub fn translate(sess: &Session, tcx: TyCtxt, internal: &mut CharonCallbacks) ->
         info!("# ULLBC after translation from MIR:\n\n{}\n", ctx);
     }
 
+    // # Remove the declarations which are not reachable from the entry points,
+    // if there are entry points.
+    ctx.start_pass("reachability");
+    reachability::prune(&mut ctx, &options.start_from);
+
+    // # Print a summary of the declarations which became opaque because we
+    // failed to translate them (see [crate::gast::OpaqueWarning]). We do this
+    // after the pruning, which removes the warnings about the pruned
+    // declarations.
+    if !ctx.opaque_warnings.is_empty() {
+        use crate::formatter::IntoFormatter;
+        let fmt_ctx = ctx.into_fmt();
+        let items: Vec<String> = ctx
+            .opaque_warnings
+            .iter()
+            .map(|w| {
+                let reason = w.reason.lines().next().unwrap_or("");
+                format!("- {}: {}", w.name.fmt_with_ctx(&fmt_ctx), reason)
+            })
+            .collect();
+        log::warn!(
+            "{} declarations became opaque because of errors:\n{}",
+            items.len(),
+            items.join("\n")
+        );
+    }
+
+    // # Call the extraction hook, if there is one: collect the metadata of
+    // the items and rewrite their names.
+    let metadata = if ctx.hook.is_some() {
+        ctx.start_pass("hooks");
+        hooks::apply(&mut ctx)
+    } else {
+        Vec::new()
+    };
+
     // # Reorder the graph of dependencies and compute the strictly
     // connex components to:
     // - compute the order in which to extract the definitions
     // - find the recursive definitions
     // - group the mutually recursive definitions
+    ctx.start_pass("reorder_decls");
     reorder_decls::reorder_declarations(&mut ctx);
 
     //
@@ -181,25 +287,81 @@ pub fn translate(sess: &Session, tcx: TyCtxt, internal: &mut CharonCallbacks) ->
 
     // # Micro-pass: desugar the constants to other values/operands as much
     // as possible.
+    ctx.start_pass("simplify_constants");
     simplify_constants::transform(&mut ctx);
 
+    // # Micro-pass: reconstruct the calls to `Box::new` from the low-level
+    // allocations of boxes (with `exchange_malloc` and `ShallowInitBox`).
+    ctx.start_pass("reconstruct_box_new");
+    reconstruct_box_new::transform(&mut ctx);
+
+    // # Micro-pass (optional): fold the constants. This must happen after
+    // [simplify_constants], which leaves only literals in the constant operands.
+    if options.fold_constants {
+        ctx.start_pass("fold_constants");
+        fold_constants::transform(&mut ctx);
+    }
+
+    // # Micro-pass: thread the jumps to the trampoline blocks and merge the
+    // identical blocks. This must happen after [fold_constants], which
+    // introduces trampolines when simplifying the switches.
+    ctx.start_pass("simplify_cfg");
+    simplify_cfg::transform(&mut ctx);
+
+    // # Micro-pass: check that the bodies only contain erased regions, and
+    // the signatures only region variables (this pass doesn't modify the
+    // declarations).
+    ctx.start_pass("check_regions");
+    check_regions::check(&ctx);
+
+    // # Micro-pass: check that the trait implementations are consistent with
+    // the trait declarations (this pass doesn't modify the declarations).
+    ctx.start_pass("check_trait_impls");
+    check_trait_impls::check(&ctx);
+
+    // # Micro-pass: check that the generic arguments of the function calls and
+    // of the type applications are consistent with the generic parameters they
+    // instantiate (this pass doesn't modify the declarations).
+    ctx.start_pass("check_generic_args");
+    check_generic_args::check(&ctx);
+
+    // # Micro-pass (optional): type-check the ULLBC bodies (this pass doesn't
+    // modify the declarations).
+    if options.check {
+        ctx.start_pass("type_check_ullbc");
+        type_check::check_ullbc(&mut ctx);
+    }
+
+    // # Micro-pass (optional): compute the bounds on the depth of the nested
+    // calls. We do this on the ULLBC, so that the bounds are available in both
+    // the ULLBC and the LLBC.
+    if options.call_depth {
+        ctx.start_pass("call_depth");
+        call_depth::compute(&mut ctx);
+    }
+
     // # There are two options:
     // - either the user wants the unstructured LLBC, in which case we stop there
     // - or they want the structured LLBC, in which case we reconstruct the
     //   control-flow and apply micro-passes
 
-    if options.ullbc {
+    let llbc_funs = if options.ullbc {
         // # Extract the files
         export::export_ullbc(
             &ctx,
-            crate_name,
+            crate_name.clone(),
+            Header::new(sess, &crate_name),
             &ctx.fun_decls,
             &ctx.global_decls,
             &options.dest_dir,
+            options.format,
         )?;
+
+        None
     } else {
         // # Go from ULLBC to LLBC (Low-Level Borrow Calculus) by reconstructing
         // the control flow.
+        ctx.start_pass("ullbc_to_llbc");
         let (mut llbc_funs, mut llbc_globals) = ullbc_to_llbc::translate_functions(&ctx);
 
         if options.print_built_llbc {
@@ -217,6 +379,7 @@ pub fn translate(sess: &Session, tcx: TyCtxt, internal: &mut CharonCallbacks) ->
         // # Micro-pass: the first local variable of closures is the
         // closure itself. This is not consistent with the closure signature,
         // which ignores this first variable. This micro-pass updates this.
+        ctx.start_pass("update_closure_signatures");
         update_closure_signatures::transform(&ctx, &mut llbc_funs);
 
         // # Micro-pass: remove the dynamic checks for array/slice bounds
@@ -225,11 +388,19 @@ pub fn translate(sess: &Session, tcx: TyCtxt, internal: &mut CharonCallbacks) ->
         // introduced by Rustc use a special "assert" construct. Because of
         // this, it must happen *before* the [reconstruct_asserts] pass.
         // See the comments in [crate::remove_dynamic_checks].
+        ctx.start_pass("remove_dynamic_checks");
         remove_dynamic_checks::transform(&mut ctx, &mut llbc_funs, &mut llbc_globals);
 
         // # Micro-pass: reconstruct the asserts
+        ctx.start_pass("reconstruct_asserts");
         reconstruct_asserts::transform(&mut ctx, &mut llbc_funs, &mut llbc_globals);
 
+        // # Micro-pass: merge the field initializations followed by a
+        // discriminant update into a single aggregate assignment, so that
+        // enumeration values are built atomically.
+        ctx.start_pass("reconstruct_aggregates");
+        reconstruct_aggregates::transform(&mut ctx, &mut llbc_funs, &mut llbc_globals);
+
         // TODO: we should mostly use the TransCtx to format declarations
         use crate::formatter::{Formatter, IntoFormatter};
         for (_, def) in &llbc_funs {
@@ -241,14 +412,17 @@ pub fn translate(sess: &Session, tcx: TyCtxt, internal: &mut CharonCallbacks) ->
 
         // # Micro-pass: replace some unops/binops and the array aggregates with
         // function calls (introduces: ArrayToSlice, etc.)
+        ctx.start_pass("ops_to_function_calls");
         ops_to_function_calls::transform(&mut ctx, &mut llbc_funs, &mut llbc_globals);
 
         // # Micro-pass: replace the arrays/slices index operations with function
         // calls.
         // (introduces: ArrayIndexShared, ArrayIndexMut, etc.)
+        ctx.start_pass("index_to_function_calls");
         index_to_function_calls::transform(&mut ctx, &mut llbc_funs, &mut llbc_globals);
 
         // # Micro-pass: Remove the discriminant reads (merge them with the switches)
+        ctx.start_pass("remove_read_discriminant");
         remove_read_discriminant::transform(&mut ctx, &mut llbc_funs, &mut llbc_globals);
 
         // # Micro-pass: add the missing assignments to the return value.
@@ -259,20 +433,74 @@ pub fn translate(sess: &Session, tcx: TyCtxt, internal: &mut CharonCallbacks) ->
         // an extra assignment just before returning.
         // This also applies to globals (for checking or executing code before
         // the main or at compile-time).
+        ctx.start_pass("insert_assign_return_unit");
         insert_assign_return_unit::transform(&mut ctx, &mut llbc_funs, &mut llbc_globals);
 
         // # Micro-pass: remove the drops of locals whose type is `Never` (`!`). This
         // is in preparation of the next transformation.
+        ctx.start_pass("remove_drop_never");
         remove_drop_never::transform(&mut ctx, &mut llbc_funs, &mut llbc_globals);
 
+        // # Micro-pass: rebuild the lazy boolean operations (`&&`, `||`),
+        // which MIR encodes with switches. We do this late, once the
+        // switches have been simplified by the previous passes.
+        ctx.start_pass("reconstruct_boolean_ops");
+        reconstruct_boolean_ops::transform(&mut ctx, &mut llbc_funs, &mut llbc_globals);
+
+        // # Micro-pass (optional): reconstruct the `for` loops. This must happen
+        // after [remove_read_discriminant], which introduces the matches.
+        if options.reconstruct_for_loops {
+            ctx.start_pass("reconstruct_for_loops");
+            reconstruct_for_loops::transform(&mut ctx, &mut llbc_funs, &mut llbc_globals);
+        }
+
+        // # Micro-pass (optional): reconstruct the applications of the `?`
+        // operator. This must happen after [remove_read_discriminant], which
+        // introduces the matches.
+        if options.reconstruct_question_marks {
+            ctx.start_pass("reconstruct_question_marks");
+            reconstruct_question_marks::transform(&mut ctx, &mut llbc_funs, &mut llbc_globals);
+        }
+
+        // # Micro-pass (optional): remove the calls to the formatting machinery
+        // whose result is never used, together with the temporaries used to
+        // build their arguments.
+        if options.fmt_machinery == cli_options::FmtMachinery::Drop {
+            ctx.start_pass("remove_fmt_calls");
+            remove_fmt_calls::transform(&mut ctx, &mut llbc_funs, &mut llbc_globals);
+        }
+
+        // # Micro-pass: remove the assignments to the locals which are never
+        // read (the unit temporaries, etc.). This is in preparation of the
+        // next transformation.
+        ctx.start_pass("remove_dead_assignments");
+        remove_dead_assignments::transform(&mut ctx, &mut llbc_funs, &mut llbc_globals);
+
         // # Micro-pass: remove the locals which are never used. After doing so, we
         // check that there are no remaining locals with type `Never`.
+        ctx.start_pass("remove_unused_locals");
         remove_unused_locals::transform(&mut ctx, &mut llbc_funs, &mut llbc_globals);
 
         // # Micro-pass (not necessary, but good for cleaning): remove the
         // useless no-ops.
+        ctx.start_pass("remove_nops");
         remove_nops::transform(&mut ctx, &mut llbc_funs, &mut llbc_globals);
 
+        // # Micro-pass (optional): compute the pure forms of the small
+        // functions. This must happen last, once the bodies are cleaned.
+        if options.emit_pure_forms {
+            ctx.start_pass("pure_forms");
+            pure_forms::transform(&ctx, &mut llbc_funs);
+        }
+
+        // # Micro-pass (optional): type-check the LLBC bodies, now that we
+        // applied all the transformations (this pass doesn't modify the
+        // declarations).
+        if options.check {
+            ctx.start_pass("type_check_llbc");
+            type_check::check_llbc(&mut ctx, &llbc_funs, &llbc_globals);
+        }
+
         trace!("# Final LLBC:\n");
         for (_, def) in &llbc_funs {
             trace!("#{}\n", ctx.into_fmt().format_object(def));
@@ -294,16 +522,114 @@ pub fn translate(sess: &Session, tcx: TyCtxt, internal: &mut CharonCallbacks) ->
         // # Final step: generate the files.
         export::export_llbc(
             &ctx,
-            crate_name,
+            crate_name.clone(),
+            Header::new(sess, &crate_name),
             &llbc_funs,
             &llbc_globals,
             &options.dest_dir,
+            options.format,
         )?;
-    }
+
+        Some(llbc_funs)
+    };
+
+    // # Generate the files we export next to the crate
+    export_side_files(
+        &ctx,
+        sess,
+        &crate_name,
+        options,
+        batch_index,
+        &metadata,
+        llbc_funs.as_ref(),
+    )?;
     trace!("Done");
 
     // Update the error count
     internal.error_count = ctx.error_count;
+    // Give the hook back to the user
+    internal.hook = ctx.hook.take();
+
+    Ok(())
+}
+
+/// Generate the files we export next to the crate (the manifest, the reports,
+/// the statistics, etc.), and register the crate in the batch index. This must
+/// be called after we exported the crate. If we generated LLBC, `llbc_funs`
+/// contains the LLBC function bodies.
+#[allow(clippy::result_unit_err)]
+#[allow(clippy::too_many_arguments)]
+fn export_side_files(
+    ctx: &translate_ctx::TransCtx,
+    sess: &Session,
+    crate_name: &str,
+    options: &cli_options::CliOpts,
+    batch_index: Option<batch::BatchIndex>,
+    metadata: &[hooks::ItemMetadata],
+    llbc_funs: Option<&llbc_ast::FunDecls>,
+) -> Result<(), ()> {
+    let extension = match llbc_funs {
+        Some(_) => "llbc",
+        None => "ullbc",
+    };
+
+    // # Generate the manifest, if necessary
+    if options.manifest {
+        let passes = &ctx.applied_passes;
+        let manifest = manifest::compute(ctx, sess, crate_name, options, passes, extension)?;
+        export::export_side_file(crate_name, "manifest.json", &manifest, &options.dest_dir)?;
+    }
+
+    // # Register the crate in the batch index, if necessary
+    if let Some(index) = batch_index {
+        index.register(ctx, crate_name, extension, options)?;
+    }
+
+    // # Compute and export the metrics, if necessary
+    if options.metrics {
+        let metrics = metrics::compute(ctx, llbc_funs);
+        export::export_side_file(crate_name, "metrics.json", &metrics, &options.dest_dir)?;
+    }
+
+    // # Export the metadata given by the extraction hook, if necessary
+    if !metadata.is_empty() {
+        export::export_side_file(crate_name, "metadata.json", metadata, &options.dest_dir)?;
+    }
+
+    // # Report the calls which could not be resolved statically, if necessary
+    if options.devirtualization_report {
+        let report = devirtualization::compute(ctx);
+        export::export_side_file(
+            crate_name,
+            "devirtualization.json",
+            &report,
+            &options.dest_dir,
+        )?;
+    }
+
+    // # Report the calls which went through blanket impls, if necessary
+    if options.blanket_impls_report {
+        let report = blanket_impls::compute(ctx);
+        export::export_side_file(crate_name, "blanket_impls.json", &report, &options.dest_dir)?;
+    }
+
+    // # Export the dependency graph, if necessary
+    if options.dependency_graph {
+        let graph = dependencies::compute(ctx);
+        export::export_side_file(crate_name, "deps.json", &graph, &options.dest_dir)?;
+    }
+
+    // # Generate the stubs of the opaque functions, if necessary
+    if options.stubs {
+        let stubs = stubs::generate(ctx);
+        export::export_side_text_file(crate_name, "stubs.rs", &stubs, &options.dest_dir)?;
+    }
+
+    // # Export the statistics about the extraction, if necessary
+    if options.stats {
+        let stats = stats::compute(ctx);
+        export::export_side_file(crate_name, "stats.json", &stats, &options.dest_dir)?;
+    }
 
     Ok(())
 }
@@ -1,41 +1,238 @@
+use crate::cli_options::OutputFormat;
+use crate::deserialize::GCrate;
+use crate::gast::OpaqueWarning;
+use crate::header::Header;
+use crate::id_map;
+use crate::id_vector::ToUsize;
 use crate::llbc_ast;
 use crate::meta::{FileId, FileName};
 use crate::reorder_decls::DeclarationGroup;
+use crate::target::TargetInfo;
 use crate::translate_ctx::*;
 use crate::types::*;
 use crate::ullbc_ast;
-use crate::ullbc_ast::{FunDeclId, GlobalDeclId, TraitDecl, TraitImpl};
-use serde::Serialize;
+use crate::ullbc_ast::{FunDeclId, GlobalDeclId, ImplBlocks, TraitDecls, TraitImpls};
+use serde::ser::SerializeStruct;
+use serde::{Deserialize, Serialize, Serializer};
 use std::fs::File;
-use std::path::PathBuf;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
 
-/// A generic crate, which implements the [Serialize] trait
-#[derive(Serialize)]
-#[serde(rename = "Crate")]
-struct GCrateSerializer<'a, FD, GD> {
-    name: String,
+/// The position of a serialized declaration in the generated file.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct IndexEntry {
+    pub id: usize,
+    /// The offset of the first byte of the declaration
+    pub start: u64,
+    /// The offset of the byte following the declaration
+    pub end: u64,
+}
+
+/// The index of a generated file, which we serialize last: it gives the
+/// positions of the declarations, so that the consumers can load them one
+/// at a time.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CrateIndex {
+    pub types: Vec<IndexEntry>,
+    pub functions: Vec<IndexEntry>,
+    pub globals: Vec<IndexEntry>,
+    pub trait_decls: Vec<IndexEntry>,
+    pub trait_impls: Vec<IndexEntry>,
+    pub impl_blocks: Vec<IndexEntry>,
+}
+
+/// A generic crate, to serialize.
+///
+/// We only borrow the declarations: we serialize them one at a time, directly
+/// to the output file (see [write_crate]). This way, the memory we need is
+/// proportional to the size of the biggest declaration, rather than to the
+/// size of the whole crate.
+struct CrateData<'a, FD, GD> {
+    /// We serialize the header first, so that the consumers can check it
+    /// before reading the rest of the file.
+    header: &'a Header,
+    name: &'a str,
+    prelude: &'a Prelude,
+    /// The information about the target the extraction assumed
+    target_info: TargetInfo,
     /// The `id_to_file` map is serialized as a vector.
     /// We use this map for the spans: the spans only store the file ids, not
     /// the file names, in order to save space.
-    id_to_file: &'a Vec<(FileId::Id, FileName)>,
-    declarations: &'a Vec<DeclarationGroup>,
-    types: Vec<TypeDecl>,
-    functions: Vec<FD>,
-    globals: Vec<GD>,
-    trait_decls: Vec<TraitDecl>,
-    trait_impls: Vec<TraitImpl>,
+    id_to_file: &'a [(FileId::Id, FileName)],
+    declarations: &'a [DeclarationGroup],
+    // The maps are serialized as vectors (the declarations contain their ids,
+    // so it is easy to reconstruct the maps from there).
+    types: &'a TypeDecls,
+    functions: &'a FunDeclId::Map<FD>,
+    globals: &'a GlobalDeclId::Map<GD>,
+    trait_decls: &'a TraitDecls,
+    trait_impls: &'a TraitImpls,
+    impl_blocks: &'a ImplBlocks,
+    /// The declarations which became opaque because of errors
+    opaque_warnings: &'a [OpaqueWarning],
+}
+
+/// A writer which serializes the fields of the top-level JSON object one at
+/// a time, and tracks the position in the output.
+struct CrateWriter<W> {
+    out: W,
+    /// The number of bytes written so far
+    pos: u64,
+    /// `true` if we haven't written any field yet
+    first_field: bool,
+}
+
+impl<W: Write> Write for CrateWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let n = self.out.write(buf)?;
+        self.pos += n as u64;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.out.flush()
+    }
+}
+
+impl<W: Write> CrateWriter<W> {
+    fn key(&mut self, key: &str) -> std::io::Result<()> {
+        if !self.first_field {
+            self.write_all(b",")?;
+        }
+        self.first_field = false;
+        serde_json::to_writer(&mut *self, key)?;
+        self.write_all(b":")
+    }
+
+    fn field<T: Serialize + ?Sized>(&mut self, key: &str, value: &T) -> std::io::Result<()> {
+        self.key(key)?;
+        serde_json::to_writer(&mut *self, value)?;
+        Ok(())
+    }
+
+    /// Serialize a map of declarations as a vector, one declaration at a
+    /// time, and return the positions of the declarations.
+    fn section<Id: ToUsize + Ord, T: Serialize>(
+        &mut self,
+        key: &str,
+        decls: &id_map::Map<Id, T>,
+    ) -> std::io::Result<Vec<IndexEntry>> {
+        self.key(key)?;
+        self.write_all(b"[")?;
+        let mut index = Vec::new();
+        for (i, (id, decl)) in decls.iter_indexed().enumerate() {
+            if i > 0 {
+                self.write_all(b",")?;
+            }
+            let start = self.pos;
+            serde_json::to_writer(&mut *self, decl)?;
+            index.push(IndexEntry {
+                id: id.to_usize(),
+                start,
+                end: self.pos,
+            });
+        }
+        self.write_all(b"]")?;
+        Ok(index)
+    }
+}
+
+/// Serialize a map of declarations as a vector.
+struct Section<'a, Id, T>(&'a id_map::Map<Id, T>);
+
+impl<Id: Ord, T: Serialize> Serialize for Section<'_, Id, T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.0.iter())
+    }
+}
+
+/// We use this implementation for the binary formats. We serialize the same
+/// fields as [write_json], but without the index: the positions of the
+/// declarations are only meaningful for JSON.
+impl<FD: Serialize, GD: Serialize> Serialize for CrateData<'_, FD, GD> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("Crate", 14)?;
+        s.serialize_field("header", self.header)?;
+        s.serialize_field("name", self.name)?;
+        s.serialize_field("prelude", self.prelude)?;
+        s.serialize_field("target_info", &self.target_info)?;
+        s.serialize_field("id_to_file", self.id_to_file)?;
+        s.serialize_field("declarations", self.declarations)?;
+        s.serialize_field("types", &Section(self.types))?;
+        s.serialize_field("functions", &Section(self.functions))?;
+        s.serialize_field("globals", &Section(self.globals))?;
+        s.serialize_field("trait_decls", &Section(self.trait_decls))?;
+        s.serialize_field("trait_impls", &Section(self.trait_impls))?;
+        s.serialize_field("impl_blocks", &Section(self.impl_blocks))?;
+        s.serialize_field("opaque_warnings", self.opaque_warnings)?;
+        s.serialize_field("index", &None::<CrateIndex>)?;
+        s.end()
+    }
+}
+
+/// Serialize a crate to JSON.
+fn write_json<W: Write, FD: Serialize, GD: Serialize>(
+    out: W,
+    krate: &CrateData<FD, GD>,
+) -> std::io::Result<()> {
+    let mut out = CrateWriter {
+        out,
+        pos: 0,
+        first_field: true,
+    };
+    out.write_all(b"{")?;
+    out.field("header", krate.header)?;
+    out.field("name", krate.name)?;
+    out.field("prelude", krate.prelude)?;
+    out.field("target_info", &krate.target_info)?;
+    out.field("id_to_file", krate.id_to_file)?;
+    out.field("declarations", krate.declarations)?;
+    let index = CrateIndex {
+        types: out.section("types", krate.types)?,
+        functions: out.section("functions", krate.functions)?,
+        globals: out.section("globals", krate.globals)?,
+        trait_decls: out.section("trait_decls", krate.trait_decls)?,
+        trait_impls: out.section("trait_impls", krate.trait_impls)?,
+        impl_blocks: out.section("impl_blocks", krate.impl_blocks)?,
+    };
+    out.field("opaque_warnings", krate.opaque_warnings)?;
+    out.field("index", &Some(index))?;
+    out.write_all(b"}")?;
+    out.flush()
+}
+
+/// Serialize a crate in the given format.
+fn write_crate<W: Write, FD: Serialize, GD: Serialize>(
+    mut out: W,
+    krate: &CrateData<FD, GD>,
+    format: OutputFormat,
+) -> std::io::Result<()> {
+    let other = |err: String| std::io::Error::new(std::io::ErrorKind::Other, err);
+    match format {
+        OutputFormat::Json => return write_json(out, krate),
+        OutputFormat::Bincode => {
+            bincode::serialize_into(&mut out, krate).map_err(|err| other(err.to_string()))?
+        }
+        OutputFormat::Cbor => {
+            ciborium::ser::into_writer(krate, &mut out).map_err(|err| other(err.to_string()))?
+        }
+    }
+    out.flush()
 }
 
 /// Export the translated definitions to a JSON file.
 ///
 /// This is a generic function, used both for LLBC and ULLBC.
 #[allow(clippy::result_unit_err)]
-pub fn gexport<FD: Serialize + Clone, GD: Serialize + Clone>(
+#[allow(clippy::too_many_arguments)]
+pub fn gexport<FD: Serialize, GD: Serialize>(
     ctx: &TransCtx,
     crate_name: String,
+    header: Header,
     fun_decls: &FunDeclId::Map<FD>,
     global_decls: &GlobalDeclId::Map<GD>,
     dest_dir: &Option<PathBuf>,
+    format: OutputFormat,
     extension: &str,
 ) -> Result<(), ()> {
     // Generate the destination file - we use the crate name for the file name
@@ -55,25 +252,21 @@ pub fn gexport<FD: Serialize + Clone, GD: Serialize + Clone>(
         .into_iter()
         .map(|id| (id, id_to_file.get(&id).unwrap().clone()))
         .collect();
-    let id_to_file = &id_to_file;
-
-    // Serialize
-    // Note that we replace the maps with vectors (the declarations contain
-    // their ids, so it is easy to reconstruct the maps from there).
-    let types = ctx.type_decls.iter().cloned().collect();
-    let functions = fun_decls.iter().cloned().collect();
-    let globals = global_decls.iter().cloned().collect();
-    let trait_decls = ctx.trait_decls.iter().cloned().collect();
-    let trait_impls = ctx.trait_impls.iter().cloned().collect();
-    let crate_serializer = GCrateSerializer {
-        name: crate_name,
-        id_to_file,
+
+    let krate = CrateData {
+        header: &header,
+        name: &crate_name,
+        prelude: &Prelude::new(),
+        target_info: ctx.target_info,
+        id_to_file: &id_to_file,
         declarations: ctx.ordered_decls.as_ref().unwrap(),
-        types,
-        functions,
-        globals,
-        trait_decls,
-        trait_impls,
+        types: &ctx.type_decls,
+        functions: fun_decls,
+        globals: global_decls,
+        trait_decls: &ctx.trait_decls,
+        trait_impls: &ctx.trait_impls,
+        impl_blocks: &ctx.impl_blocks,
+        opaque_warnings: &ctx.opaque_warnings,
     };
 
     // Create the directory, if necessary (note that if the target directory
@@ -91,8 +284,9 @@ pub fn gexport<FD: Serialize + Clone, GD: Serialize + Clone>(
     };
 
     // Write to the file
+    let write = |outfile: File| write_crate(BufWriter::new(outfile), &krate, format);
     match File::create(target_filename.clone()) {
-        std::io::Result::Ok(outfile) => match serde_json::to_writer(&outfile, &crate_serializer) {
+        std::io::Result::Ok(outfile) => match write(outfile) {
             std::result::Result::Ok(()) => {
                 // We canonicalize (i.e., make absolute) the path before printing it:
                 // this makes it clearer to the user where to find the file.
@@ -124,11 +318,22 @@ pub fn gexport<FD: Serialize + Clone, GD: Serialize + Clone>(
 pub fn export_ullbc(
     ctx: &TransCtx,
     crate_name: String,
+    header: Header,
     fun_decls: &ullbc_ast::FunDecls,
     global_decls: &ullbc_ast::GlobalDecls,
     dest_dir: &Option<PathBuf>,
+    format: OutputFormat,
 ) -> Result<(), ()> {
-    gexport(ctx, crate_name, fun_decls, global_decls, dest_dir, "ullbc")
+    gexport(
+        ctx,
+        crate_name,
+        header,
+        fun_decls,
+        global_decls,
+        dest_dir,
+        format,
+        "ullbc",
+    )
 }
 
 /// Export the translated LLBC definitions to a JSON file.
@@ -136,9 +341,123 @@ pub fn export_ullbc(
 pub fn export_llbc(
     ctx: &TransCtx,
     crate_name: String,
+    header: Header,
     fun_decls: &llbc_ast::FunDecls,
     global_decls: &llbc_ast::GlobalDecls,
     dest_dir: &Option<PathBuf>,
+    format: OutputFormat,
 ) -> Result<(), ()> {
-    gexport(ctx, crate_name, fun_decls, global_decls, dest_dir, "llbc")
+    gexport(
+        ctx,
+        crate_name,
+        header,
+        fun_decls,
+        global_decls,
+        dest_dir,
+        format,
+        "llbc",
+    )
+}
+
+/// Export a value to a JSON file generated next to the crate
+/// (`CRATE.SUFFIX`): the reports, the manifest, the statistics, etc.
+///
+/// This must be called after we exported the crate, which created the
+/// destination directory.
+#[allow(clippy::result_unit_err)]
+pub fn export_side_file<T: Serialize + ?Sized>(
+    crate_name: &str,
+    suffix: &str,
+    value: &T,
+    dest_dir: &Option<PathBuf>,
+) -> Result<(), ()> {
+    write_side_file(crate_name, suffix, dest_dir, |outfile| {
+        serde_json::to_writer(outfile, value).map_err(std::io::Error::from)
+    })
+}
+
+/// Same as [export_side_file], but for a text file (the stubs, etc.).
+#[allow(clippy::result_unit_err)]
+pub fn export_side_text_file(
+    crate_name: &str,
+    suffix: &str,
+    text: &str,
+    dest_dir: &Option<PathBuf>,
+) -> Result<(), ()> {
+    write_side_file(crate_name, suffix, dest_dir, |outfile| {
+        outfile.write_all(text.as_bytes())
+    })
+}
+
+fn write_side_file(
+    crate_name: &str,
+    suffix: &str,
+    dest_dir: &Option<PathBuf>,
+    write: impl FnOnce(&mut File) -> std::io::Result<()>,
+) -> Result<(), ()> {
+    let mut target_filename = dest_dir
+        .as_deref()
+        .map_or_else(PathBuf::new, |d| d.to_path_buf());
+    target_filename.push(format!("{crate_name}.{suffix}"));
+
+    trace!("Target file: {:?}", target_filename);
+
+    match File::create(target_filename.clone()) {
+        std::io::Result::Ok(mut outfile) => match write(&mut outfile) {
+            std::result::Result::Ok(()) => {
+                let path = std::fs::canonicalize(target_filename).unwrap();
+                info!("Generated the file: {}", path.to_str().unwrap());
+                Ok(())
+            }
+            std::result::Result::Err(_) => {
+                error!("Could not write to: {:?}", target_filename);
+                Err(())
+            }
+        },
+        std::io::Result::Err(_) => {
+            error!("Could not open: {:?}", target_filename);
+            Err(())
+        }
+    }
+}
+
+/// Export a crate we built from generated files (for instance, by merging
+/// several crates: see [crate::batch]).
+pub fn export_crate<FD: Serialize, GD: Serialize>(
+    krate: &GCrate<FD, GD>,
+    target_filename: &Path,
+    format: OutputFormat,
+) -> Result<(), String> {
+    // Sort the file table to make the serialized file as stable as possible
+    let mut id_to_file: Vec<(FileId::Id, FileName)> = krate
+        .id_to_file
+        .iter()
+        .map(|(id, file)| (*id, file.clone()))
+        .collect();
+    id_to_file.sort_by_key(|(id, _)| *id);
+
+    let data = CrateData {
+        header: &krate.header,
+        name: &krate.name,
+        prelude: &krate.prelude,
+        target_info: krate.target_info,
+        id_to_file: &id_to_file,
+        declarations: &krate.declarations,
+        types: &krate.type_decls,
+        functions: &krate.fun_decls,
+        globals: &krate.global_decls,
+        trait_decls: &krate.trait_decls,
+        trait_impls: &krate.trait_impls,
+        impl_blocks: &krate.impl_blocks,
+        opaque_warnings: &krate.opaque_warnings,
+    };
+
+    let outfile = match File::create(target_filename) {
+        Ok(outfile) => outfile,
+        Err(err) => return Err(format!("Could not open {target_filename:?}: {err}")),
+    };
+    match write_crate(BufWriter::new(outfile), &data, format) {
+        Ok(()) => Ok(()),
+        Err(err) => Err(format!("Could not write to {target_filename:?}: {err}")),
+    }
 }
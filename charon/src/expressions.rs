@@ -6,10 +6,10 @@ use crate::types::*;
 pub use crate::values::VarId;
 use crate::values::*;
 use macros::{EnumAsGetters, EnumIsA, EnumToGetters, VariantIndexArity, VariantName};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use std::vec::Vec;
 
-#[derive(Debug, PartialEq, Eq, Clone, Serialize)]
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub struct Place {
     // TODO: update to transform to a recursive type
     pub var_id: VarId::Id,
@@ -27,7 +27,16 @@ pub type Projection = Vec<ProjectionElem>;
 /// In MIR, downcasts always happen before field projections: in our internal
 /// language, we thus merge downcasts and field projections.
 #[derive(
-    Debug, PartialEq, Eq, Clone, EnumIsA, EnumAsGetters, EnumToGetters, VariantName, Serialize,
+    Debug,
+    PartialEq,
+    Eq,
+    Clone,
+    EnumIsA,
+    EnumAsGetters,
+    EnumToGetters,
+    VariantName,
+    Serialize,
+    Deserialize,
 )]
 pub enum ProjectionElem {
     /// Dereference a shared/mutable reference.
@@ -61,7 +70,7 @@ pub enum ProjectionElem {
     Index(VarId::Id, Ty),
 }
 
-#[derive(Debug, PartialEq, Eq, Copy, Clone, EnumIsA, EnumAsGetters, Serialize)]
+#[derive(Debug, PartialEq, Eq, Copy, Clone, EnumIsA, EnumAsGetters, Serialize, Deserialize)]
 pub enum FieldProjKind {
     #[serde(rename = "ProjAdt")]
     Adt(TypeDeclId::Id, Option<VariantId::Id>),
@@ -74,7 +83,7 @@ pub enum FieldProjKind {
     ClosureState,
 }
 
-#[derive(Debug, PartialEq, Eq, Copy, Clone, EnumIsA, EnumAsGetters, Serialize)]
+#[derive(Debug, PartialEq, Eq, Copy, Clone, EnumIsA, EnumAsGetters, Serialize, Deserialize)]
 pub enum BorrowKind {
     Shared,
     Mut,
@@ -90,7 +99,7 @@ pub enum BorrowKind {
 }
 
 /// Unary operation
-#[derive(Debug, PartialEq, Eq, Clone, EnumIsA, VariantName, Serialize)]
+#[derive(Debug, PartialEq, Eq, Clone, EnumIsA, VariantName, Serialize, Deserialize)]
 pub enum UnOp {
     Not,
     /// This can overflow. In practice, rust introduces an assert before
@@ -111,16 +120,49 @@ pub enum UnOp {
 
 /// For all the variants: the first type gives the source type, the second one gives
 /// the destination type.
-#[derive(Debug, PartialEq, Eq, Clone, EnumIsA, VariantName, Serialize)]
+#[derive(Debug, PartialEq, Eq, Clone, EnumIsA, VariantName, Serialize, Deserialize)]
 pub enum CastKind {
     /// Conversion between types in {Integer, Bool}
     /// Remark: for now we don't support conversions with Char.
     Scalar(LiteralTy, LiteralTy),
-    FnPtr(Ty, Ty),
+    /// Reification of a function item to a function pointer.
+    ReifyFnPointer(Ty, Ty),
+    /// Coercion of a non-capturing closure to a function pointer.
+    ClosureFnPointer(Ty, Ty),
+    /// Unsizing coercion (for instance, from `&T` to `&dyn Trait`).
+    ///
+    /// If the target is a (pointer to a) trait object, we also store the
+    /// trait instance which implements the trait for the source type (i.e.,
+    /// the vtable).
+    ///
+    /// Remark: the coercions from references to arrays to references to
+    /// slices are translated to [UnOp::ArrayToSlice].
+    Unsize(Ty, Ty, Option<TraitRef>),
+    /// Conversion between raw pointers (or from a function pointer to a raw
+    /// pointer).
+    PtrToPtr(Ty, Ty),
+    /// Conversion from a raw pointer to an integer (`p as usize`).
+    PtrToInt(Ty, Ty),
+    /// Conversion from an integer to a raw pointer (`n as *const T`).
+    IntToPtr(Ty, Ty),
+    /// Reinterpretation of the bits of a value (`std::mem::transmute`).
+    Transmute(Ty, Ty),
+}
+
+/// Nullary operations, which compute information about a type.
+#[derive(Debug, PartialEq, Eq, Copy, Clone, EnumIsA, VariantName, Serialize, Deserialize)]
+pub enum NullOp {
+    /// The size of the type (`core::mem::size_of::<T>()`)
+    SizeOf,
+    /// The minimal alignment of the type (`core::mem::align_of::<T>()`)
+    AlignOf,
 }
 
 /// Binary operations.
-#[derive(Debug, PartialEq, Eq, Copy, Clone, EnumIsA, VariantName, Serialize)]
+///
+/// Remark: the comparisons also apply to the raw pointers, in which case they
+/// compare the addresses.
+#[derive(Debug, PartialEq, Eq, Copy, Clone, EnumIsA, VariantName, Serialize, Deserialize)]
 pub enum BinOp {
     BitXor,
     BitAnd,
@@ -145,11 +187,28 @@ pub enum BinOp {
     Shl,
     /// Can fail if the shift is too big
     Shr,
-    // No Offset binary operation: this is an operation on raw pointers
+    /// Lazy boolean conjunction (`&&`). MIR encodes it with a switch: we
+    /// reconstruct it in [crate::reconstruct_boolean_ops].
+    And,
+    /// Lazy boolean disjunction (`||`). See [BinOp::And].
+    Or,
+    /// Pointer offset (`p.offset(n)`): the first operand is a raw pointer, the
+    /// second one is a number of elements (not of bytes). Undefined behaviour
+    /// if the resulting pointer is out of the bounds of the allocated object.
+    Offset,
 }
 
 #[derive(
-    Debug, PartialEq, Eq, Clone, EnumIsA, EnumToGetters, EnumAsGetters, VariantName, Serialize,
+    Debug,
+    PartialEq,
+    Eq,
+    Clone,
+    EnumIsA,
+    EnumToGetters,
+    EnumAsGetters,
+    VariantName,
+    Serialize,
+    Deserialize,
 )]
 pub enum Operand {
     Copy(Place),
@@ -159,7 +218,9 @@ pub enum Operand {
 }
 
 /// A function identifier. See [crate::ullbc_ast::Terminator]
-#[derive(Debug, Clone, PartialEq, Eq, EnumIsA, EnumAsGetters, VariantName, Serialize)]
+#[derive(
+    Debug, Clone, PartialEq, Eq, EnumIsA, EnumAsGetters, VariantName, Serialize, Deserialize,
+)]
 pub enum FunId {
     /// A "regular" function (function local to the crate, external function
     /// not treated as a primitive one).
@@ -172,9 +233,14 @@ pub enum FunId {
 
 /// An assumed function identifier, identifying a function coming from a
 /// standard library.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIsA, EnumAsGetters, VariantName, Serialize)]
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, EnumIsA, EnumAsGetters, VariantName, Serialize, Deserialize,
+)]
 pub enum AssumedFunId {
     /// `alloc::boxed::Box::new`
+    ///
+    /// We also introduce it when the MIR allocates and initializes a box
+    /// without calling `Box::new` (see [crate::reconstruct_box_new]).
     BoxNew,
     /// `alloc::alloc::box_free`
     /// This is actually an unsafe function, but the rust compiler sometimes
@@ -217,14 +283,81 @@ pub enum AssumedFunId {
     /// Converted from [ProjectionElem::Index].
     ///
     /// Signature: `fn<T>(&[T], usize) -> &T`
+    ///
+    /// We also introduce it for the calls to `<[T] as Index<usize>>::index`.
     SliceIndexShared,
     /// Converted from [ProjectionElem::Index].
     ///
     /// Signature: `fn<T>(&mut [T], usize) -> &mut T`
+    ///
+    /// We also introduce it for the calls to `<[T] as IndexMut<usize>>::index_mut`.
     SliceIndexMut,
+    /// `alloc::vec::Vec::new`
+    ///
+    /// Signature: `fn<T>() -> Vec<T>`
+    VecNew,
+    /// `alloc::vec::Vec::push`
+    ///
+    /// Signature: `fn<T>(&mut Vec<T>, T)`
+    VecPush,
+    /// `alloc::vec::Vec::len`
+    ///
+    /// Signature: `fn<T>(&Vec<T>) -> usize`
+    VecLen,
+    /// `<Vec<T> as Index<usize>>::index`
+    ///
+    /// Signature: `fn<T>(&Vec<T>, usize) -> &T`
+    VecIndexShared,
+    /// `<Vec<T> as IndexMut<usize>>::index_mut`
+    ///
+    /// Signature: `fn<T>(&mut Vec<T>, usize) -> &mut T`
+    VecIndexMut,
+    /// `core::option::Option::unwrap`
+    ///
+    /// Signature: `fn<T>(Option<T>) -> T`
+    OptionUnwrap,
+    /// `core::option::Option::is_some`
+    ///
+    /// Signature: `fn<T>(&Option<T>) -> bool`
+    OptionIsSome,
+    /// `core::mem::swap`
+    ///
+    /// Signature: `fn<T>(&mut T, &mut T)`
+    MemSwap,
+    /// `core::mem::replace`
+    ///
+    /// Signature: `fn<T>(&mut T, T) -> T`
+    MemReplace,
+    /// `core::mem::take`
+    ///
+    /// Signature: `fn<T: Default>(&mut T) -> T` (we keep the reference to
+    /// the implementation of `Default`)
+    MemTake,
+    /// `core::intrinsics::transmute`
+    ///
+    /// Signature: `fn<T, U>(T) -> U`
+    Transmute,
+    /// `core::intrinsics::copy_nonoverlapping`
+    ///
+    /// Signature: `fn<T>(*const T, *mut T, usize)`
+    CopyNonOverlapping,
+    /// `core::intrinsics::discriminant_value`
+    ///
+    /// Signature: `fn<T>(&T) -> <T as DiscriminantKind>::Discriminant`
+    DiscriminantValue,
+    /// `core::intrinsics::size_of_val`
+    ///
+    /// Signature: `fn<T>(*const T) -> usize`
+    SizeOfVal,
+    /// A call to a function of the formatting machinery (`core::fmt`), like
+    /// `Arguments::new_v1` or the methods of `Formatter`, that we don't extract
+    /// (see [crate::cli_options::CliOpts::fmt_machinery]).
+    ///
+    /// We don't keep the generic arguments: the signature is unknown.
+    Fmt,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, EnumAsGetters)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, EnumAsGetters)]
 pub enum FunIdOrTraitMethodRef {
     Fun(FunId),
     /// If a trait: the reference to the trait and the id of the trait method.
@@ -233,7 +366,7 @@ pub enum FunIdOrTraitMethodRef {
     Trait(TraitRef, TraitItemName, FunDeclId::Id),
 }
 
-#[derive(Debug, PartialEq, Eq, Clone, Serialize)]
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub struct FnPtr {
     pub func: FunIdOrTraitMethodRef,
     pub generics: GenericArgs,
@@ -258,6 +391,7 @@ pub struct FnPtr {
 /// - sometimes, Rust stores the initialization of an ADT as a constant
 ///   (if all the fields are constant) rather than as an aggregated value
 /// We later desugar those to regular ADTs, see [regularize_constant_adts.rs].
+/// The same goes for the constant arrays.
 ///
 /// [Global] case: access to a global variable. We later desugar it to
 /// a separate statement.
@@ -270,7 +404,9 @@ pub struct FnPtr {
 /// Remark:
 /// MIR seems to forbid more complex expressions like paths. For instance,
 /// reading the constant `a.b` is translated to `{ _1 = const a; _2 = (_1.0) }`.
-#[derive(Debug, PartialEq, Eq, Clone, Serialize, VariantName, EnumIsA, EnumAsGetters)]
+#[derive(
+    Debug, PartialEq, Eq, Clone, Serialize, Deserialize, VariantName, EnumIsA, EnumAsGetters,
+)]
 pub enum RawConstantExpr {
     Literal(Literal),
     ///
@@ -283,6 +419,12 @@ pub enum RawConstantExpr {
     /// We eliminate this case in a micro-pass.
     Adt(Option<VariantId::Id>, Vec<ConstantExpr>),
     ///
+    /// A constant array, like the ADTs above (e.g., `[0u8; 4]` or `[1, 2, 3]`).
+    /// The values are given in order.
+    ///
+    /// We eliminate this case in a micro-pass.
+    Array(Vec<ConstantExpr>),
+    ///
     /// The value is a top-level value.
     ///
     /// We eliminate this case in a micro-pass.
@@ -311,9 +453,18 @@ pub enum RawConstantExpr {
     Var(ConstGenericVarId::Id),
     /// Function pointer
     FnPtr(FnPtr),
+    /// The address of a static (see [crate::gast::GlobalKind::Static]). The
+    /// statics are referenced by address, and the type of the constant gives
+    /// the type of the reference: `&T` for the immutable statics, `*mut T`
+    /// for the mutable statics.
+    ///
+    /// Contrary to [RawConstantExpr::Ref] applied to a
+    /// [RawConstantExpr::Global], this doesn't copy the value of the static
+    /// (which matters if it is mutated, or if it has interior mutability).
+    GlobalDeclRef(GlobalDeclId::Id),
 }
 
-#[derive(Debug, PartialEq, Eq, Clone, Serialize)]
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub struct ConstantExpr {
     pub value: RawConstantExpr,
     pub ty: Ty,
@@ -321,14 +472,22 @@ pub struct ConstantExpr {
 
 /// TODO: we could factor out [Rvalue] and function calls (for LLBC, not ULLBC).
 /// We can also factor out the unops, binops with the function calls.
-#[derive(Debug, Clone, Serialize, EnumToGetters, EnumAsGetters, EnumIsA)]
+#[derive(
+    Debug, PartialEq, Eq, Clone, Serialize, Deserialize, EnumToGetters, EnumAsGetters, EnumIsA,
+)]
 pub enum Rvalue {
     Use(Operand),
     Ref(Place, BorrowKind),
+    /// Raw borrow (`&raw const p`, `&raw mut p`, or `addr_of!(p)`): creates a raw
+    /// pointer to the place without going through a reference.
+    AddressOf(Place, RefKind),
     /// Unary operation (not, neg)
     UnaryOp(UnOp, Operand),
     /// Binary operations (note that we merge "checked" and "unchecked" binops)
     BinaryOp(BinOp, Operand, Operand),
+    /// Nullary operations (size and alignment of a type). Those are used by the
+    /// low-level code, and by the allocation of the boxes.
+    Nullary(NullOp, Ty),
     /// Discriminant (for enumerations).
     /// Note that discriminant values have type isize. We also store the identifier
     /// of the type from which we read the discriminant.
@@ -370,13 +529,23 @@ pub enum Rvalue {
     /// where `x` is a slice or an array, they actually call a non-primitive
     /// function.
     Len(Place, Ty, Option<ConstGeneric>),
-    /// [Repeat(x, n)] creates an array where [x] is copied [n] times.
+    /// [Repeat(x, ty, n)] creates an array where [x] is copied [n] times. We
+    /// store the type of the elements ([ty]), and the length of the array ([n]).
     ///
-    /// We desugar this to a function call.
+    /// We desugar this to a function call in the LLBC (see
+    /// [AssumedFunId::ArrayRepeat]).
     Repeat(Operand, Ty, ConstGeneric),
+    /// [ShallowInitBox(ptr, ty)] transforms a pointer to freshly allocated
+    /// memory (returned by `alloc::alloc::exchange_malloc`) into a box of type
+    /// `Box<ty>`, whose content is not initialized yet.
+    ///
+    /// We reconstruct calls to [AssumedFunId::BoxNew] from this pattern (see
+    /// [crate::reconstruct_box_new]): it only remains if we couldn't recognize
+    /// the pattern.
+    ShallowInitBox(Operand, Ty),
 }
 
-#[derive(Debug, Clone, VariantIndexArity, Serialize)]
+#[derive(Debug, PartialEq, Eq, Clone, VariantIndexArity, Serialize, Deserialize)]
 pub enum AggregateKind {
     Adt(TypeId, Option<VariantId::Id>, GenericArgs),
     /// We don't put this with the ADT cas because this is the only assumed type
@@ -386,4 +555,50 @@ pub enum AggregateKind {
     /// Aggregated values for closures group the function id together with its
     /// state.
     Closure(FunDeclId::Id, GenericArgs),
+    /// Union values are built by initializing a single field: the active field.
+    /// The aggregate then contains exactly one operand.
+    Union(TypeDeclId::Id, FieldId::Id, GenericArgs),
+    /// Aggregated values for generators group the id of the generator body
+    /// together with its state (the upvars).
+    Generator(FunDeclId::Id, GenericArgs),
+}
+
+/// A pure expression. We compute those for the small functions without loops,
+/// panics or mutable borrows, if the user asks for it (see [crate::pure_forms]):
+/// the proof assistants can consume them directly as definitions.
+///
+/// We model the shared borrows and the boxes as values: the borrows and the
+/// dereferences disappear.
+#[derive(Debug, Clone, EnumIsA, VariantName, Serialize, Deserialize)]
+pub enum PureExpr {
+    /// A local variable: an input, or a variable introduced by a [PureExpr::Let]
+    Var(VarId::Id),
+    Const(ConstantExpr),
+    Global(GlobalDeclId::Id),
+    /// A field projection
+    Field(FieldProjKind, FieldId::Id, Box<PureExpr>),
+    UnaryOp(UnOp, Box<PureExpr>),
+    BinaryOp(BinOp, Box<PureExpr>, Box<PureExpr>),
+    Aggregate(AggregateKind, Vec<PureExpr>),
+    /// The application of a function (which also has a pure form)
+    App(FnPtr, Vec<PureExpr>),
+    /// `let x = e1 in e2`
+    Let(VarId::Id, Box<PureExpr>, Box<PureExpr>),
+    /// `if e0 then e1 else e2`
+    Ite(Box<PureExpr>, Box<PureExpr>, Box<PureExpr>),
+    /// A match over an integer (see [crate::llbc_ast::Switch::SwitchInt])
+    SwitchInt(
+        Box<PureExpr>,
+        IntegerTy,
+        Vec<(Vec<SwitchValue>, PureExpr)>,
+        Box<PureExpr>,
+    ),
+    /// A match over an enumeration (see [crate::llbc_ast::Switch::Match]). The
+    /// branches don't bind variables: they read the fields of the scrutinee
+    /// with [PureExpr::Field] projections.
+    Match(
+        Box<PureExpr>,
+        Vec<(Vec<VariantId::Id>, PureExpr)>,
+        Option<Box<PureExpr>>,
+    ),
 }
@@ -1,7 +1,10 @@
 //! This file groups everything which is linked to implementations about [crate::expressions]
+use crate::common::TAB_INCR;
 use crate::expressions::*;
 use crate::formatter::{AstFormatter, FmtCtx};
-use crate::gast::{AssumedFunId, Call, FnOperand, FunId, FunIdOrTraitMethodRef, TraitItemName};
+use crate::gast::{
+    AssertKind, AssumedFunId, Call, FnOperand, FunId, FunIdOrTraitMethodRef, TraitItemName,
+};
 use crate::types::*;
 use crate::ullbc_ast::GlobalDeclId;
 use crate::values::*;
@@ -33,12 +36,29 @@ impl CastKind {
     where
         C: AstFormatter,
     {
-        match self {
-            CastKind::Scalar(src, tgt) => format!("cast<{src},{tgt}>"),
-            CastKind::FnPtr(src, tgt) => {
-                format!("cast<{},{}>", src.fmt_with_ctx(ctx), tgt.fmt_with_ctx(ctx))
+        let (kind, src, tgt) = match self {
+            CastKind::Scalar(src, tgt) => return format!("cast<{src},{tgt}>"),
+            CastKind::ReifyFnPointer(src, tgt) => ("reify_fn_pointer", src, tgt),
+            CastKind::ClosureFnPointer(src, tgt) => ("closure_fn_pointer", src, tgt),
+            CastKind::Unsize(src, tgt, None) => ("unsize", src, tgt),
+            CastKind::Unsize(src, tgt, Some(vtable)) => {
+                return format!(
+                    "cast_unsize<{},{}>[{}]",
+                    src.fmt_with_ctx(ctx),
+                    tgt.fmt_with_ctx(ctx),
+                    vtable.fmt_with_ctx(ctx)
+                )
             }
-        }
+            CastKind::PtrToPtr(src, tgt) => ("ptr_to_ptr", src, tgt),
+            CastKind::PtrToInt(src, tgt) => ("ptr_to_int", src, tgt),
+            CastKind::IntToPtr(src, tgt) => ("int_to_ptr", src, tgt),
+            CastKind::Transmute(src, tgt) => ("transmute", src, tgt),
+        };
+        format!(
+            "cast_{kind}<{},{}>",
+            src.fmt_with_ctx(ctx),
+            tgt.fmt_with_ctx(ctx)
+        )
     }
 }
 
@@ -56,6 +76,15 @@ impl UnOp {
     }
 }
 
+impl std::fmt::Display for NullOp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
+        match self {
+            NullOp::SizeOf => write!(f, "size_of"),
+            NullOp::AlignOf => write!(f, "align_of"),
+        }
+    }
+}
+
 impl std::fmt::Display for BinOp {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
         match self {
@@ -75,6 +104,9 @@ impl std::fmt::Display for BinOp {
             BinOp::Mul => write!(f, "*"),
             BinOp::Shl => write!(f, "<<"),
             BinOp::Shr => write!(f, ">>"),
+            BinOp::And => write!(f, "&&"),
+            BinOp::Or => write!(f, "||"),
+            BinOp::Offset => write!(f, "offset"),
         }
     }
 }
@@ -85,35 +117,56 @@ impl Place {
         C: AstFormatter,
     {
         let mut out = ctx.format_object(self.var_id);
+        // Whether we can directly append a field access or an index to the
+        // current output (i.e., whether it is a variable or a projection
+        // like `x.f`), or whether we need to wrap it in parentheses first
+        // (for instance, if it is a dereference `*(x)`).
+        let mut is_atomic = true;
 
         for p in &self.projection {
+            let wrapped = if is_atomic {
+                out.clone()
+            } else {
+                format!("({out})")
+            };
             match p {
                 ProjectionElem::Deref => {
                     out = format!("*({out})");
+                    is_atomic = false;
                 }
                 ProjectionElem::DerefBox => {
                     out = format!("deref_box ({out})");
+                    is_atomic = false;
                 }
                 ProjectionElem::DerefRawPtr => {
                     out = format!("deref_raw_ptr ({out})");
+                    is_atomic = false;
+                }
+                ProjectionElem::Field(proj_kind, field_id) => {
+                    match proj_kind {
+                        FieldProjKind::Adt(adt_id, opt_variant_id) => {
+                            let field_name =
+                                ctx.format_object((*adt_id, *opt_variant_id, *field_id));
+                            out = match opt_variant_id {
+                                None => format!("{wrapped}.{field_name}"),
+                                Some(variant_id) => {
+                                    format!("({out} as variant @{variant_id}).{field_name}")
+                                }
+                            };
+                        }
+                        FieldProjKind::Tuple(_) => {
+                            out = format!("{wrapped}.{field_id}");
+                        }
+                        FieldProjKind::ClosureState => {
+                            out = format!("{wrapped}.@closure_state_field_{field_id}");
+                        }
+                    };
+                    is_atomic = true;
+                }
+                ProjectionElem::Index(i, _) => {
+                    out = format!("{wrapped}[{}]", ctx.format_object(*i));
+                    is_atomic = true;
                 }
-                ProjectionElem::Field(proj_kind, field_id) => match proj_kind {
-                    FieldProjKind::Adt(adt_id, opt_variant_id) => {
-                        let field_name = ctx.format_object((*adt_id, *opt_variant_id, *field_id));
-                        let downcast = match opt_variant_id {
-                            None => "".to_string(),
-                            Some(variant_id) => format!(" as variant @{variant_id}"),
-                        };
-                        out = format!("({out}{downcast}).{field_name}");
-                    }
-                    FieldProjKind::Tuple(_) => {
-                        out = format!("({out}).{field_id}");
-                    }
-                    FieldProjKind::ClosureState => {
-                        out = format!("({out}).@closure_state_field_{field_id}");
-                    }
-                },
-                ProjectionElem::Index(i, _) => out = format!("({out})[{}]", ctx.format_object(*i)),
             }
         }
 
@@ -154,6 +207,10 @@ impl RawConstantExpr {
                 let values: Vec<String> = values.iter().map(|v| v.fmt_with_ctx(ctx)).collect();
                 format!("ConstAdt {} [{}]", variant_id, values.join(", "))
             }
+            RawConstantExpr::Array(values) => {
+                let values: Vec<String> = values.iter().map(|v| v.fmt_with_ctx(ctx)).collect();
+                format!("[{}]", values.join(", "))
+            }
             RawConstantExpr::Global(id) => ctx.format_object(*id),
             RawConstantExpr::TraitConst(trait_ref, substs, name) => {
                 format!(
@@ -169,6 +226,11 @@ impl RawConstantExpr {
             RawConstantExpr::FnPtr(f) => {
                 format!("{}", f.fmt_with_ctx(ctx),)
             }
+            RawConstantExpr::GlobalDeclRef(id) => match &self.ty {
+                Ty::RawPtr(_, RefKind::Mut) => format!("&raw mut {}", ctx.format_object(*id)),
+                Ty::RawPtr(_, RefKind::Shared) => format!("&raw const {}", ctx.format_object(*id)),
+                _ => format!("&{}", ctx.format_object(*id)),
+            },
         }
     }
 }
@@ -220,6 +282,64 @@ impl std::fmt::Display for Operand {
     }
 }
 
+/// Format an aggregate, whose operands are already formatted.
+fn fmt_aggregate<C>(ctx: &C, kind: &AggregateKind, ops: &[String]) -> String
+where
+    C: AstFormatter,
+{
+    match kind {
+        AggregateKind::Adt(def_id, variant_id, _) => {
+            match def_id {
+                TypeId::Tuple => format!("({})", ops.join(", ")),
+                TypeId::Assumed(_) => unreachable!(),
+                TypeId::Adt(def_id) => {
+                    // Format every field
+                    let mut fields = vec![];
+                    for (i, op) in ops.iter().enumerate() {
+                        let field_id = FieldId::Id::new(i);
+                        let field_name = ctx.format_object((*def_id, *variant_id, field_id));
+                        fields.push(format!("{}: {}", field_name, op));
+                    }
+
+                    let variant = match variant_id {
+                        None => ctx.format_object(*def_id),
+                        Some(variant_id) => ctx.format_object((*def_id, *variant_id)),
+                    };
+                    format!("{} {{ {} }}", variant, fields.join(", "))
+                }
+            }
+        }
+        AggregateKind::Array(_, len) => {
+            format!("[{}; {}]", ops.join(", "), len.fmt_with_ctx(ctx))
+        }
+        AggregateKind::Closure(fn_id, generics) => {
+            format!(
+                "{{{}{}}} {{{}}}",
+                ctx.format_object(*fn_id),
+                generics.fmt_with_ctx_split_trait_refs(ctx),
+                ops.join(", ")
+            )
+        }
+        AggregateKind::Union(def_id, field_id, _) => {
+            let field_name = ctx.format_object((*def_id, None, *field_id));
+            format!(
+                "{} {{ {}: {} }}",
+                ctx.format_object(*def_id),
+                field_name,
+                ops.join(", ")
+            )
+        }
+        AggregateKind::Generator(fn_id, generics) => {
+            format!(
+                "{{generator {}{}}} {{{}}}",
+                ctx.format_object(*fn_id),
+                generics.fmt_with_ctx_split_trait_refs(ctx),
+                ops.join(", ")
+            )
+        }
+    }
+}
+
 impl Rvalue {
     pub fn fmt_with_ctx<C>(&self, ctx: &C) -> String
     where
@@ -235,62 +355,36 @@ impl Rvalue {
                 }
                 BorrowKind::Shallow => format!("&shallow {}", place.fmt_with_ctx(ctx)),
             },
+            Rvalue::AddressOf(place, kind) => match kind {
+                RefKind::Shared => format!("&raw const {}", place.fmt_with_ctx(ctx)),
+                RefKind::Mut => format!("&raw mut {}", place.fmt_with_ctx(ctx)),
+            },
             Rvalue::UnaryOp(unop, x) => {
                 format!("{}({})", unop.fmt_with_ctx(ctx), x.fmt_with_ctx(ctx))
             }
             Rvalue::BinaryOp(binop, x, y) => {
                 format!("{} {} {}", x.fmt_with_ctx(ctx), binop, y.fmt_with_ctx(ctx))
             }
+            Rvalue::Nullary(nullop, ty) => format!("{}<{}>", nullop, ty.fmt_with_ctx(ctx)),
             Rvalue::Discriminant(p, _) => {
                 format!("@discriminant({})", p.fmt_with_ctx(ctx),)
             }
             Rvalue::Aggregate(kind, ops) => {
-                let ops_s: Vec<String> = ops.iter().map(|op| op.fmt_with_ctx(ctx)).collect();
-                match kind {
-                    AggregateKind::Adt(def_id, variant_id, _) => {
-                        match def_id {
-                            TypeId::Tuple => format!("({})", ops_s.join(", ")),
-                            TypeId::Assumed(_) => unreachable!(),
-                            TypeId::Adt(def_id) => {
-                                // Format every field
-                                let mut fields = vec![];
-                                for (i, op) in ops.iter().enumerate() {
-                                    let field_id = FieldId::Id::new(i);
-                                    let field_name =
-                                        ctx.format_object((*def_id, *variant_id, field_id));
-                                    fields.push(format!(
-                                        "{}: {}",
-                                        field_name,
-                                        op.fmt_with_ctx(ctx)
-                                    ));
-                                }
-
-                                let variant = match variant_id {
-                                    None => ctx.format_object(*def_id),
-                                    Some(variant_id) => ctx.format_object((*def_id, *variant_id)),
-                                };
-                                format!("{} {{ {} }}", variant, fields.join(", "))
-                            }
-                        }
-                    }
-                    AggregateKind::Array(_, len) => {
-                        format!("[{}; {}]", ops_s.join(", "), len.fmt_with_ctx(ctx))
-                    }
-                    AggregateKind::Closure(fn_id, generics) => {
-                        format!(
-                            "{{{}{}}} {{{}}}",
-                            ctx.format_object(*fn_id),
-                            generics.fmt_with_ctx_split_trait_refs(ctx),
-                            ops_s.join(", ")
-                        )
-                    }
-                }
+                let ops: Vec<String> = ops.iter().map(|op| op.fmt_with_ctx(ctx)).collect();
+                fmt_aggregate(ctx, kind, &ops)
             }
             Rvalue::Global(gid) => ctx.format_object(*gid),
             Rvalue::Len(place, ..) => format!("len({})", place.fmt_with_ctx(ctx)),
             Rvalue::Repeat(op, _ty, cg) => {
                 format!("[{}; {}]", op.fmt_with_ctx(ctx), cg.fmt_with_ctx(ctx))
             }
+            Rvalue::ShallowInitBox(op, ty) => {
+                format!(
+                    "shallow_init_box<{}>({})",
+                    ty.fmt_with_ctx(ctx),
+                    op.fmt_with_ctx(ctx)
+                )
+            }
         }
     }
 }
@@ -301,6 +395,125 @@ impl std::fmt::Display for Rvalue {
     }
 }
 
+impl PureExpr {
+    pub fn fmt_with_ctx<C>(&self, tab: &str, ctx: &C) -> String
+    where
+        C: AstFormatter,
+    {
+        let inner_tab1 = format!("{tab}{TAB_INCR}");
+        let inner_tab2 = format!("{inner_tab1}{TAB_INCR}");
+        match self {
+            PureExpr::Let(x, e1, e2) => format!(
+                "{tab}let {} = {} in\n{}",
+                ctx.format_object(*x),
+                e1.fmt_inline(ctx),
+                e2.fmt_with_ctx(tab, ctx)
+            ),
+            PureExpr::Ite(cond, e1, e2) => format!(
+                "{tab}if {} then\n{}\n{tab}else\n{}",
+                cond.fmt_inline(ctx),
+                e1.fmt_with_ctx(&inner_tab1, ctx),
+                e2.fmt_with_ctx(&inner_tab1, ctx)
+            ),
+            PureExpr::SwitchInt(scrut, _, branches, otherwise) => {
+                let mut branches: Vec<String> = branches
+                    .iter()
+                    .map(|(values, e)| {
+                        let values: Vec<String> = values.iter().map(|v| v.to_string()).collect();
+                        format!(
+                            "{inner_tab1}{} =>\n{}",
+                            values.join(" | "),
+                            e.fmt_with_ctx(&inner_tab2, ctx)
+                        )
+                    })
+                    .collect();
+                branches.push(format!(
+                    "{inner_tab1}_ =>\n{}",
+                    otherwise.fmt_with_ctx(&inner_tab2, ctx)
+                ));
+                format!(
+                    "{tab}match {} {{\n{}\n{tab}}}",
+                    scrut.fmt_inline(ctx),
+                    branches.join(",\n")
+                )
+            }
+            PureExpr::Match(scrut, branches, otherwise) => {
+                let mut branches: Vec<String> = branches
+                    .iter()
+                    .map(|(variants, e)| {
+                        let variants: Vec<String> =
+                            variants.iter().map(|v| v.to_string()).collect();
+                        format!(
+                            "{inner_tab1}{} =>\n{}",
+                            variants.join(" | "),
+                            e.fmt_with_ctx(&inner_tab2, ctx)
+                        )
+                    })
+                    .collect();
+                if let Some(otherwise) = otherwise {
+                    branches.push(format!(
+                        "{inner_tab1}_ =>\n{}",
+                        otherwise.fmt_with_ctx(&inner_tab2, ctx)
+                    ));
+                }
+                format!(
+                    "{tab}match {} {{\n{}\n{tab}}}",
+                    scrut.fmt_inline(ctx),
+                    branches.join(",\n")
+                )
+            }
+            _ => format!("{tab}{}", self.fmt_inline(ctx)),
+        }
+    }
+
+    /// Format an expression on a single line. The sub-expressions of the
+    /// operations and of the applications are always of this shape.
+    fn fmt_inline<C>(&self, ctx: &C) -> String
+    where
+        C: AstFormatter,
+    {
+        match self {
+            PureExpr::Var(id) => ctx.format_object(*id),
+            PureExpr::Const(c) => c.fmt_with_ctx(ctx),
+            PureExpr::Global(id) => ctx.format_object(*id),
+            PureExpr::Field(kind, field_id, e) => {
+                let e = e.fmt_inline(ctx);
+                match kind {
+                    FieldProjKind::Adt(adt_id, None) => {
+                        format!("{e}.{}", ctx.format_object((*adt_id, None, *field_id)))
+                    }
+                    FieldProjKind::Adt(adt_id, Some(variant_id)) => format!(
+                        "({e} as variant @{variant_id}).{}",
+                        ctx.format_object((*adt_id, Some(*variant_id), *field_id))
+                    ),
+                    FieldProjKind::Tuple(_) => format!("{e}.{field_id}"),
+                    FieldProjKind::ClosureState => format!("{e}.@closure_state_field_{field_id}"),
+                }
+            }
+            PureExpr::UnaryOp(unop, e) => {
+                format!("{}({})", unop.fmt_with_ctx(ctx), e.fmt_inline(ctx))
+            }
+            PureExpr::BinaryOp(binop, x, y) => {
+                format!("({} {} {})", x.fmt_inline(ctx), binop, y.fmt_inline(ctx))
+            }
+            PureExpr::Aggregate(kind, es) => {
+                let es: Vec<String> = es.iter().map(|e| e.fmt_inline(ctx)).collect();
+                fmt_aggregate(ctx, kind, &es)
+            }
+            PureExpr::App(fn_ptr, args) => {
+                let args: Vec<String> = args.iter().map(|e| e.fmt_inline(ctx)).collect();
+                format!("{}({})", fn_ptr.fmt_with_ctx(ctx), args.join(", "))
+            }
+            PureExpr::Let(..)
+            | PureExpr::Ite(..)
+            | PureExpr::SwitchInt(..)
+            | PureExpr::Match(..) => {
+                format!("({})", self.fmt_with_ctx("", ctx))
+            }
+        }
+    }
+}
+
 // Derive two implementations at once: one which uses shared borrows, and one
 // which uses mutable borrows.
 // Generates the traits: `SharedExprVisitor` and `MutExprVisitor`.
@@ -380,6 +593,11 @@ pub trait ExprVisitor: crate::types::TypeVisitor {
         match expr {
             Literal(lit) => self.visit_literal(lit),
             Adt(oid, ops) => self.visit_constant_expr_adt(oid, ops),
+            Array(ops) => {
+                for op in ops {
+                    self.visit_constant_expr(op)
+                }
+            }
             Global(id) => self.visit_global_decl_id(id),
             TraitConst(trait_ref, generics, _name) => {
                 self.visit_trait_ref(trait_ref);
@@ -403,13 +621,16 @@ pub trait ExprVisitor: crate::types::TypeVisitor {
         match rv {
             Rvalue::Use(o) => self.visit_use(o),
             Rvalue::Ref(p, bkind) => self.visit_ref(p, bkind),
+            Rvalue::AddressOf(p, kind) => self.visit_address_of(p, kind),
             Rvalue::UnaryOp(op, o1) => self.visit_unary_op(op, o1),
             Rvalue::BinaryOp(op, o1, o2) => self.visit_binary_op(op, o1, o2),
+            Rvalue::Nullary(op, ty) => self.visit_nullary(op, ty),
             Rvalue::Discriminant(p, adt_id) => self.visit_discriminant(p, adt_id),
             Rvalue::Aggregate(kind, ops) => self.visit_aggregate(kind, ops),
             Rvalue::Global(gid) => self.visit_global(gid),
             Rvalue::Len(p, ty, cg) => self.visit_len(p, ty, cg),
             Rvalue::Repeat(op, ty, cg) => self.visit_repeat(op, ty, cg),
+            Rvalue::ShallowInitBox(op, ty) => self.visit_shallow_init_box(op, ty),
         }
     }
 
@@ -425,10 +646,28 @@ pub trait ExprVisitor: crate::types::TypeVisitor {
         self.visit_place(p)
     }
 
+    fn visit_address_of(&mut self, p: &Place, _: &RefKind) {
+        self.visit_place(p)
+    }
+
     fn visit_unary_op(&mut self, unop: &UnOp, o1: &Operand) {
         match unop {
             UnOp::Not | UnOp::Neg | UnOp::Cast(CastKind::Scalar(_, _)) => (),
-            UnOp::Cast(CastKind::FnPtr(src, tgt)) => {
+            UnOp::Cast(CastKind::Unsize(src, tgt, vtable)) => {
+                self.visit_ty(src);
+                self.visit_ty(tgt);
+                if let Some(vtable) = vtable {
+                    self.visit_trait_ref(vtable);
+                }
+            }
+            UnOp::Cast(
+                CastKind::ReifyFnPointer(src, tgt)
+                | CastKind::ClosureFnPointer(src, tgt)
+                | CastKind::PtrToPtr(src, tgt)
+                | CastKind::PtrToInt(src, tgt)
+                | CastKind::IntToPtr(src, tgt)
+                | CastKind::Transmute(src, tgt),
+            ) => {
                 self.visit_ty(src);
                 self.visit_ty(tgt);
             }
@@ -445,6 +684,10 @@ pub trait ExprVisitor: crate::types::TypeVisitor {
         self.visit_operand(o2);
     }
 
+    fn visit_nullary(&mut self, _: &NullOp, ty: &Ty) {
+        self.visit_ty(ty);
+    }
+
     fn visit_discriminant(&mut self, p: &Place, adt_id: &TypeDeclId::Id) {
         self.visit_place(p);
         self.visit_type_decl_id(adt_id);
@@ -470,10 +713,14 @@ pub trait ExprVisitor: crate::types::TypeVisitor {
                 self.visit_ty(ty);
                 self.visit_const_generic(cg);
             }
-            Closure(fn_id, generics) => {
+            Closure(fn_id, generics) | Generator(fn_id, generics) => {
                 self.visit_fun_decl_id(fn_id);
                 self.visit_generic_args(generics);
             }
+            Union(adt_id, _, generics) => {
+                self.visit_type_decl_id(adt_id);
+                self.visit_generic_args(generics);
+            }
         }
     }
 
@@ -494,6 +741,11 @@ pub trait ExprVisitor: crate::types::TypeVisitor {
         self.visit_const_generic(cg);
     }
 
+    fn visit_shallow_init_box(&mut self, op: &Operand, ty: &Ty) {
+        self.visit_operand(op);
+        self.visit_ty(ty);
+    }
+
     fn visit_call(&mut self, c: &Call) {
         let Call {
             func,
@@ -507,6 +759,22 @@ pub trait ExprVisitor: crate::types::TypeVisitor {
         self.visit_place(dest);
     }
 
+    fn visit_assert_kind(&mut self, kind: &AssertKind) {
+        match kind {
+            AssertKind::IndexOutOfBounds { len, index } => {
+                self.visit_operand(len);
+                self.visit_operand(index);
+            }
+            AssertKind::Overflow(_)
+            | AssertKind::OverflowNeg
+            | AssertKind::DivisionByZero
+            | AssertKind::RemainderByZero
+            | AssertKind::ResumedGenerator
+            | AssertKind::MisalignedPointerDereference
+            | AssertKind::User => (),
+        }
+    }
+
     fn visit_fn_ptr(&mut self, fn_ptr: &FnPtr) {
         let FnPtr { func, generics, trait_and_method_generic_args } = fn_ptr;
         self.visit_fun_id_or_trait_ref(func);
@@ -523,6 +791,10 @@ pub trait ExprVisitor: crate::types::TypeVisitor {
         match fn_op {
             FnOperand::Regular(func) => self.visit_fn_ptr(func),
             FnOperand::Move(p) => self.visit_place(p),
+            FnOperand::Virtual(trait_ref, _, generics) => {
+                self.visit_trait_decl_ref(trait_ref);
+                self.visit_generic_args(generics);
+            }
         }
     }
 
@@ -0,0 +1,329 @@
+//! # Micro-pass (optional, see [crate::cli_options::CliOpts::fold_constants]):
+//! evaluate the operations on constant scalars, simplify the switches over
+//! constant values, and propagate the constants stored in temporaries.
+//!
+//! The MIR (especially at the `built` level) contains a lot of noise of the
+//! shape: `tmp = const 1; tmp1 = const 2 + move tmp; switch move tmp1 ...`.
+//! This pass cleans it up, so that the consumers of the generated files don't
+//! have to.
+//!
+//! Remarks:
+//! - we don't evaluate the operations which fail (divisions by zero,
+//!   overflows, etc.): they must fail at runtime
+//! - we leave the checked operations (whose result is a pair) unchanged: the
+//!   overflow checks are handled in [crate::remove_dynamic_checks]
+//! - we don't remove the blocks which become unreachable: they are ignored
+//!   by the control-flow reconstruction
+use crate::expressions::*;
+use crate::formatter::{Formatter, IntoFormatter};
+use crate::gast::Var;
+use crate::id_vector::ToUsize;
+use crate::target::TargetInfo;
+use crate::translate_ctx::TransCtx;
+use crate::types::*;
+use crate::ullbc_ast::{
+    ExprBody, MutAstVisitor, RawStatement, RawTerminator, SharedAstVisitor, SwitchTargets,
+};
+use crate::values::*;
+use std::collections::{HashMap, HashSet};
+
+/// Return the literal stored in an operand, if it is a constant literal.
+fn operand_literal(op: &Operand) -> Option<&Literal> {
+    match op {
+        Operand::Const(ConstantExpr {
+            value: RawConstantExpr::Literal(v),
+            ..
+        }) => Some(v),
+        _ => None,
+    }
+}
+
+/// Evaluate an arithmetic or bitwise operation on integers. We return `None`
+/// if the operation fails, or if the result is out of bounds.
+macro_rules! eval_int_binop {
+    ($binop:expr, $x:expr, $y:expr) => {
+        match $binop {
+            BinOp::Add => $x.checked_add($y),
+            BinOp::Sub => $x.checked_sub($y),
+            BinOp::Mul => $x.checked_mul($y),
+            BinOp::Div => $x.checked_div($y),
+            BinOp::Rem => $x.checked_rem($y),
+            BinOp::BitAnd => Some($x & $y),
+            BinOp::BitOr => Some($x | $y),
+            BinOp::BitXor => Some($x ^ $y),
+            _ => None,
+        }
+    };
+}
+
+fn eval_unop(unop: &UnOp, v: &Literal) -> Option<Literal> {
+    match (unop, v) {
+        (UnOp::Not, Literal::Bool(b)) => Some(Literal::Bool(!b)),
+        (UnOp::Not, Literal::Scalar(v)) if v.is_int() => {
+            let v = ScalarValue::from_int(v.get_integer_ty(), !v.as_int().ok()?).ok()?;
+            Some(Literal::Scalar(v))
+        }
+        (UnOp::Neg, Literal::Scalar(v)) if v.is_int() => {
+            let v = ScalarValue::from_int(v.get_integer_ty(), v.as_int().ok()?.checked_neg()?);
+            Some(Literal::Scalar(v.ok()?))
+        }
+        _ => None,
+    }
+}
+
+fn eval_binop(binop: BinOp, v1: &Literal, v2: &Literal) -> Option<Literal> {
+    // The comparisons: we can use the order on the literals, provided they
+    // have the same kind
+    let same_kind = match (v1, v2) {
+        (Literal::Scalar(x), Literal::Scalar(y)) => x.get_integer_ty() == y.get_integer_ty(),
+        (Literal::Bool(_), Literal::Bool(_)) | (Literal::Char(_), Literal::Char(_)) => true,
+        _ => false,
+    };
+    if !same_kind {
+        return None;
+    }
+    let cmp = match binop {
+        BinOp::Eq => Some(v1 == v2),
+        BinOp::Ne => Some(v1 != v2),
+        BinOp::Lt => Some(v1 < v2),
+        BinOp::Le => Some(v1 <= v2),
+        BinOp::Gt => Some(v1 > v2),
+        BinOp::Ge => Some(v1 >= v2),
+        _ => None,
+    };
+    if let Some(b) = cmp {
+        return Some(Literal::Bool(b));
+    }
+
+    match (v1, v2) {
+        (Literal::Bool(x), Literal::Bool(y)) => match binop {
+            BinOp::BitAnd | BinOp::And => Some(Literal::Bool(*x && *y)),
+            BinOp::BitOr | BinOp::Or => Some(Literal::Bool(*x || *y)),
+            BinOp::BitXor => Some(Literal::Bool(*x ^ *y)),
+            _ => None,
+        },
+        (Literal::Scalar(x), Literal::Scalar(y)) => {
+            let ty = x.get_integer_ty();
+            let v = if x.is_int() {
+                let v = eval_int_binop!(binop, x.as_int().ok()?, y.as_int().ok()?)?;
+                ScalarValue::from_int(ty, v)
+            } else {
+                let v = eval_int_binop!(binop, x.as_uint().ok()?, y.as_uint().ok()?)?;
+                ScalarValue::from_uint(ty, v)
+            };
+            Some(Literal::Scalar(v.ok()?))
+        }
+        _ => None,
+    }
+}
+
+/// Check that the result of an evaluation is in the bounds of its type. We
+/// evaluate the operations with the bounds of the host, which may be larger
+/// than the bounds of the target (for `usize` and `isize`).
+fn literal_in_bounds(target: &TargetInfo, v: &Literal) -> bool {
+    match v {
+        Literal::Scalar(v) => target.scalar_in_bounds(v),
+        _ => true,
+    }
+}
+
+/// Evaluate an rvalue, if its operands are constant literals.
+fn eval_rvalue(rv: &Rvalue) -> Option<Literal> {
+    match rv {
+        Rvalue::UnaryOp(unop, op) => eval_unop(unop, operand_literal(op)?),
+        Rvalue::BinaryOp(binop, op1, op2) => {
+            eval_binop(*binop, operand_literal(op1)?, operand_literal(op2)?)
+        }
+        _ => None,
+    }
+}
+
+/// Evaluate the operations on constants, and simplify the switches over
+/// constants. Return `true` if we changed something.
+fn fold_body(target: &TargetInfo, body: &mut ExprBody) -> bool {
+    let mut changed = false;
+    for block in body.body.iter_mut() {
+        for st in &mut block.statements {
+            let RawStatement::Assign(p, rv) = &mut st.content else {
+                continue;
+            };
+            // We only fold the operations whose destination is a local
+            // of literal type (this excludes the checked operations)
+            if !p.projection.is_empty() {
+                continue;
+            }
+            let ty = &body.locals.get(p.var_id).unwrap().ty;
+            if ty.is_literal()
+                && let Some(value) = eval_rvalue(rv)
+                && literal_in_bounds(target, &value)
+            {
+                *rv = Rvalue::Use(Operand::Const(ConstantExpr {
+                    value: RawConstantExpr::Literal(value),
+                    ty: ty.clone(),
+                }));
+                changed = true;
+            }
+        }
+
+        let RawTerminator::Switch { discr, targets } = &block.terminator.content else {
+            continue;
+        };
+        let target = match (operand_literal(discr), targets) {
+            (Some(Literal::Bool(b)), SwitchTargets::If(then_tgt, else_tgt)) => {
+                if *b {
+                    *then_tgt
+                } else {
+                    *else_tgt
+                }
+            }
+            (Some(Literal::Scalar(v)), SwitchTargets::SwitchInt(_, branches, otherwise)) => {
+                branches
+                    .iter()
+                    .find(|(bv, _)| bv.contains(v))
+                    .map_or(*otherwise, |(_, tgt)| *tgt)
+            }
+            _ => continue,
+        };
+        block.terminator.content = RawTerminator::Goto { target };
+        changed = true;
+    }
+    changed
+}
+
+/// Compute the temporaries which are assigned exactly once, with a constant
+/// literal, and which are only read through `copy` and `move` operands (in
+/// particular, they are never borrowed).
+struct ConstantTemporaries {
+    values: HashMap<VarId::Id, ConstantExpr>,
+    /// The locals which we can't propagate
+    excluded: HashSet<VarId::Id>,
+}
+
+impl SharedTypeVisitor for ConstantTemporaries {}
+
+impl SharedExprVisitor for ConstantTemporaries {
+    fn visit_operand(&mut self, op: &Operand) {
+        match op {
+            Operand::Copy(p) | Operand::Move(p) if p.projection.is_empty() => (),
+            _ => self.default_visit_operand(op),
+        }
+    }
+
+    fn visit_var_id(&mut self, id: &VarId::Id) {
+        let _ = self.excluded.insert(*id);
+    }
+}
+
+impl SharedAstVisitor for ConstantTemporaries {
+    fn visit_assign(&mut self, p: &Place, rv: &Rvalue) {
+        if p.projection.is_empty()
+            && let Rvalue::Use(Operand::Const(c)) = rv
+            && c.value.is_literal()
+        {
+            if self.values.insert(p.var_id, c.clone()).is_some() {
+                // Assigned several times
+                let _ = self.excluded.insert(p.var_id);
+            }
+        } else {
+            self.visit_place(p);
+            self.visit_rvalue(rv);
+        }
+    }
+
+    fn visit_storage_dead(&mut self, _: &VarId::Id) {}
+}
+
+/// Replace the reads of the constant temporaries with their values.
+struct Propagate<'a> {
+    values: &'a HashMap<VarId::Id, ConstantExpr>,
+}
+
+impl MutTypeVisitor for Propagate<'_> {}
+
+impl MutExprVisitor for Propagate<'_> {
+    fn visit_operand(&mut self, op: &mut Operand) {
+        if let Operand::Copy(p) | Operand::Move(p) = op
+            && p.projection.is_empty()
+            && let Some(c) = self.values.get(&p.var_id)
+        {
+            *op = Operand::Const(c.clone());
+        } else {
+            self.default_visit_operand(op)
+        }
+    }
+}
+
+impl MutAstVisitor for Propagate<'_> {}
+
+/// Propagate the constants stored in temporaries. Return `true` if we changed
+/// something.
+fn propagate_body(body: &mut ExprBody) -> bool {
+    let mut temps = ConstantTemporaries {
+        values: HashMap::new(),
+        excluded: HashSet::new(),
+    };
+    for block in body.body.iter() {
+        temps.visit_block_data(block);
+    }
+
+    // Only keep the temporaries (we don't want to lose the variables
+    // introduced by the user)
+    let locals = &body.locals;
+    let is_temporary = |id: &VarId::Id| {
+        let var: &Var = locals.get(*id).unwrap();
+        id.to_usize() > body.arg_count && var.name.is_none()
+    };
+    let values: HashMap<VarId::Id, ConstantExpr> = temps
+        .values
+        .into_iter()
+        .filter(|(id, _)| !temps.excluded.contains(id) && is_temporary(id))
+        .collect();
+    if values.is_empty() {
+        return false;
+    }
+
+    let mut propagate = Propagate { values: &values };
+    for block in body.body.iter_mut() {
+        propagate.visit_block_data(block);
+        block.statements.retain(|st| match &st.content {
+            RawStatement::Assign(p, _) => {
+                !(p.projection.is_empty() && values.contains_key(&p.var_id))
+            }
+            // We remove all the storage markers of the temporary: note that
+            // we only have `StorageDead` (we ignore the `StorageLive`
+            // statements when translating from MIR)
+            RawStatement::StorageDead(id) => !values.contains_key(id),
+            _ => true,
+        });
+    }
+    true
+}
+
+pub fn transform(ctx: &mut TransCtx) {
+    // Slightly annoying: we have to clone because of borrowing issues
+    let mut fun_decls = ctx.fun_decls.clone();
+    let mut global_decls = ctx.global_decls.clone();
+    let target = ctx.target_info;
+
+    ctx.iter_bodies(&mut fun_decls, &mut global_decls, |ctx, name, b| {
+        let fmt_ctx = ctx.into_fmt();
+        trace!(
+            "# About to fold the constants in decl: {}:\n{}",
+            name.fmt_with_ctx(&fmt_ctx),
+            fmt_ctx.format_object(&*b)
+        );
+
+        // Folding the constants may allow to propagate more constants, and
+        // vice versa: we iterate until we reach a fixed point
+        loop {
+            let folded = fold_body(&target, b);
+            let propagated = propagate_body(b);
+            if !folded && !propagated {
+                break;
+            }
+        }
+    });
+
+    ctx.fun_decls = fun_decls;
+    ctx.global_decls = global_decls;
+}
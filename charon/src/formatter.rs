@@ -1,7 +1,10 @@
+use crate::cli_options::NameOpts;
 use crate::common::TAB_INCR;
+use crate::expressions::{ConstantExpr, Operand, Place, RawConstantExpr, Rvalue};
 use crate::gast;
 use crate::llbc_ast;
 use crate::llbc_ast::*;
+use crate::names::{Name, PathElem};
 use crate::types::*;
 use crate::ullbc_ast;
 use crate::ullbc_ast as ast;
@@ -49,6 +52,77 @@ where
     }
 }
 
+/// An object which can be formatted with a [FmtCtx].
+///
+/// The `fmt_with_ctx` functions are generic in the formatting context: we
+/// can't manipulate the objects to format as trait objects. This trait fixes
+/// the context, and is object-safe. We use it for the error messages (see
+/// [crate::translate_ctx::TransCtx::fmt_for_error]): the functions which report
+/// the errors can take any object to print, and format the ids with the names
+/// of the declarations when those are available (we fall back to the ids, like
+/// `@Fun12`, otherwise).
+pub trait FmtWithCtx {
+    fn fmt_with_fmt_ctx(&self, ctx: &FmtCtx) -> String;
+}
+
+impl<T: FmtWithCtx + ?Sized> FmtWithCtx for &T {
+    fn fmt_with_fmt_ctx(&self, ctx: &FmtCtx) -> String {
+        (*self).fmt_with_fmt_ctx(ctx)
+    }
+}
+
+/// Implement [FmtWithCtx] for types which have a `fmt_with_ctx` function.
+macro_rules! impl_fmt_with_ctx {
+    ($($ty:ty),* $(,)?) => {
+        $(
+            impl FmtWithCtx for $ty {
+                fn fmt_with_fmt_ctx(&self, ctx: &FmtCtx) -> String {
+                    self.fmt_with_ctx(ctx)
+                }
+            }
+        )*
+    };
+}
+
+impl_fmt_with_ctx!(
+    Ty,
+    Region,
+    ConstGeneric,
+    GenericArgs,
+    TraitDeclRef,
+    TraitRef,
+    TraitInstanceId,
+    FunSig,
+    Name,
+    Place,
+    Operand,
+    Rvalue,
+    ConstantExpr,
+    RawConstantExpr,
+    UnifyError,
+);
+
+/// Implement [FmtWithCtx] for the ids of the declarations.
+macro_rules! impl_fmt_with_ctx_for_id {
+    ($($ty:ty),* $(,)?) => {
+        $(
+            impl FmtWithCtx for $ty {
+                fn fmt_with_fmt_ctx(&self, ctx: &FmtCtx) -> String {
+                    ctx.format_object(*self)
+                }
+            }
+        )*
+    };
+}
+
+impl_fmt_with_ctx_for_id!(
+    TypeDeclId::Id,
+    FunDeclId::Id,
+    GlobalDeclId::Id,
+    TraitDeclId::Id,
+    TraitImplId::Id,
+);
+
 pub trait IntoFormatter {
     type C: AstFormatter;
 
@@ -77,6 +151,7 @@ impl<'a, 'b> SetGenerics<'a> for FmtCtx<'b> {
             type_vars: _,
             const_generic_vars: _,
             locals,
+            names,
         } = self;
 
         let type_decls = type_decls.as_deref();
@@ -95,6 +170,7 @@ impl<'a, 'b> SetGenerics<'a> for FmtCtx<'b> {
             type_vars: Some(&generics.types),
             const_generic_vars: Some(&generics.const_generics),
             locals,
+            names: *names,
         }
     }
 }
@@ -121,6 +197,7 @@ impl<'a, 'b> SetLocals<'a> for FmtCtx<'b> {
             type_vars,
             const_generic_vars,
             locals: _,
+            names,
         } = self;
 
         let type_decls = type_decls.as_deref();
@@ -140,6 +217,7 @@ impl<'a, 'b> SetLocals<'a> for FmtCtx<'b> {
             type_vars,
             const_generic_vars,
             locals: Some(locals),
+            names: *names,
         }
     }
 }
@@ -165,6 +243,7 @@ impl<'a, 'b> PushBoundRegions<'a> for FmtCtx<'b> {
             type_vars,
             const_generic_vars,
             locals,
+            names,
         } = self;
 
         let type_decls = type_decls.as_deref();
@@ -187,6 +266,7 @@ impl<'a, 'b> PushBoundRegions<'a> for FmtCtx<'b> {
             type_vars,
             const_generic_vars,
             locals,
+            names: *names,
         }
     }
 }
@@ -205,6 +285,7 @@ pub trait AstFormatter = Formatter<TypeVarId::Id>
     + Formatter<(TypeDeclId::Id, Option<VariantId::Id>, FieldId::Id)>
     + for<'a> Formatter<&'a ullbc_ast::BlockId::Vector<ullbc_ast::BlockData>>
     + for<'a> Formatter<&'a llbc_ast::Statement>
+    + for<'a> Formatter<&'a Name>
     + for<'a> SetGenerics<'a>
     + for<'a> SetLocals<'a>
     + for<'a> PushBoundRegions<'a>;
@@ -230,6 +311,40 @@ pub struct FmtCtx<'a> {
     pub type_vars: Option<&'a TypeVarId::Vector<TypeVar>>,
     pub const_generic_vars: Option<&'a ConstGenericVarId::Vector<ConstGenericVar>>,
     pub locals: Option<&'a VarId::Vector<ast::Var>>,
+    pub names: NameFmtOptions<'a>,
+}
+
+/// How to print the names of the declarations (see [crate::cli_options::NameOpts]).
+#[derive(Debug, Clone, Copy)]
+pub struct NameFmtOptions<'a> {
+    /// Print the crate prefixes of the names
+    pub crate_prefix: bool,
+    /// If set: the name of the current crate, for which we omit the crate
+    /// prefix (we still print the crate prefixes of the external names).
+    pub current_crate: Option<&'a str>,
+}
+
+impl<'a> NameFmtOptions<'a> {
+    pub fn new(opts: &NameOpts, crate_name: &'a str) -> Self {
+        NameFmtOptions {
+            crate_prefix: !opts.no_crate_prefix,
+            current_crate: if opts.relative_names {
+                Some(crate_name)
+            } else {
+                None
+            },
+        }
+    }
+}
+
+impl<'a> Default for NameFmtOptions<'a> {
+    /// By default, we print the full names
+    fn default() -> Self {
+        NameFmtOptions {
+            crate_prefix: true,
+            current_crate: None,
+        }
+    }
 }
 
 impl<'a> IntoFormatter for FmtCtx<'a> {
@@ -252,6 +367,7 @@ impl<'a> FmtCtx<'a> {
             type_vars: None,
             const_generic_vars: None,
             locals: None,
+            names: NameFmtOptions::default(),
         }
     }
 }
@@ -262,6 +378,25 @@ impl<'a> Default for FmtCtx<'a> {
     }
 }
 
+/// Print a name, following the [NameFmtOptions]. Note that we always print the
+/// crate prefix of the names which are reduced to it (i.e., the crates).
+impl<'a, 'b> Formatter<&'b Name> for FmtCtx<'a> {
+    fn format_object(&self, name: &'b Name) -> String {
+        let skip_prefix = match name.name.as_slice() {
+            [PathElem::Ident(krate, _), _, ..] => {
+                !self.names.crate_prefix || self.names.current_crate == Some(krate.as_str())
+            }
+            _ => false,
+        };
+        name.name
+            .iter()
+            .skip(usize::from(skip_prefix))
+            .map(|x| x.fmt_with_ctx(self))
+            .collect::<Vec<String>>()
+            .join("::")
+    }
+}
+
 impl<'a> Formatter<TypeDeclId::Id> for FmtCtx<'a> {
     fn format_object(&self, id: TypeDeclId::Id) -> String {
         match &self.type_decls {
@@ -399,64 +534,28 @@ impl<'a> Formatter<(TypeDeclId::Id, VariantId::Id)> for FmtCtx<'a> {
 
 /// For struct/enum values: retrieve a field name
 impl<'a> Formatter<(TypeDeclId::Id, Option<VariantId::Id>, FieldId::Id)> for FmtCtx<'a> {
+    /// Format the name of a field. If the field doesn't have a name (tuple
+    /// structs) or if the type definition is not available, we use the field
+    /// index.
     fn format_object(&self, id: (TypeDeclId::Id, Option<VariantId::Id>, FieldId::Id)) -> String {
         let (def_id, opt_variant_id, field_id) = id;
-        match &self.type_decls {
-            None => match opt_variant_id {
-                Option::None => format!(
-                    "{}::{}",
-                    def_id.to_pretty_string(),
-                    field_id.to_pretty_string()
-                ),
-                Option::Some(variant_id) => format!(
-                    "{}::{}::{}",
-                    def_id.to_pretty_string(),
-                    variant_id.to_pretty_string(),
-                    field_id.to_pretty_string()
-                ),
-            },
-            Some(type_decls) =>
-            // The definition may not be available yet, especially if we
-            // print-debug while translating the crate
-            {
-                match type_decls.get(def_id) {
-                    Option::None => match opt_variant_id {
-                        Option::None => format!(
-                            "{}::{}",
-                            def_id.to_pretty_string(),
-                            field_id.to_pretty_string()
-                        ),
-                        Option::Some(variant_id) => format!(
-                            "{}::{}::{}",
-                            def_id.to_pretty_string(),
-                            variant_id.to_pretty_string(),
-                            field_id.to_pretty_string()
-                        ),
-                    },
-                    Option::Some(gen_def) => match (&gen_def.kind, opt_variant_id) {
-                        (TypeDeclKind::Enum(variants), Some(variant_id)) => {
-                            let field = variants
-                                .get(variant_id)
-                                .unwrap()
-                                .fields
-                                .get(field_id)
-                                .unwrap();
-                            match &field.name {
-                                Option::Some(name) => name.clone(),
-                                Option::None => field_id.to_string(),
-                            }
-                        }
-                        (TypeDeclKind::Struct(fields), None) => {
-                            let field = fields.get(field_id).unwrap();
-                            match &field.name {
-                                Option::Some(name) => name.clone(),
-                                Option::None => field_id.to_string(),
-                            }
-                        }
-                        _ => unreachable!(),
-                    },
+        // The definition may not be available yet, especially if we
+        // print-debug while translating the crate
+        let field = match self.type_decls.and_then(|decls| decls.get(def_id)) {
+            None => None,
+            Some(def) => match (&def.kind, opt_variant_id) {
+                (TypeDeclKind::Enum(variants), Some(variant_id)) => variants
+                    .get(variant_id)
+                    .and_then(|variant| variant.fields.get(field_id)),
+                (TypeDeclKind::Struct(fields) | TypeDeclKind::Union(fields), None) => {
+                    fields.get(field_id)
                 }
-            }
+                _ => None,
+            },
+        };
+        match field.and_then(|field| field.name.as_ref()) {
+            Some(name) => name.clone(),
+            None => field_id.to_string(),
         }
     }
 }
@@ -554,10 +653,10 @@ impl<'a> Formatter<&gast::TraitImpl> for FmtCtx<'a> {
 impl<'a> DeclFormatter<TypeDeclId::Id> for FmtCtx<'a> {
     fn format_decl(&self, id: TypeDeclId::Id) -> String {
         match &self.type_decls {
-            None => format!("Unknown decl: {:?}", id),
+            None => format!("Unknown decl: {}", id.to_pretty_string()),
             Some(decls) => match decls.get(id) {
                 None => {
-                    format!("Unknown decl: {:?}", id)
+                    format!("Unknown decl: {}", id.to_pretty_string())
                 }
                 Some(d) => d.fmt_with_ctx(self),
             },
@@ -568,10 +667,10 @@ impl<'a> DeclFormatter<TypeDeclId::Id> for FmtCtx<'a> {
 impl<'a> DeclFormatter<GlobalDeclId::Id> for FmtCtx<'a> {
     fn format_decl(&self, id: GlobalDeclId::Id) -> String {
         match &self.global_decls {
-            None => format!("Unknown decl: {:?}", id),
+            None => format!("Unknown decl: {}", id.to_pretty_string()),
             Some(decls) => match decls.get(id) {
                 None => {
-                    format!("Unknown decl: {:?}", id)
+                    format!("Unknown decl: {}", id.to_pretty_string())
                 }
                 Some(d) => d.fmt_with_ctx(self),
             },
@@ -582,10 +681,10 @@ impl<'a> DeclFormatter<GlobalDeclId::Id> for FmtCtx<'a> {
 impl<'a> DeclFormatter<FunDeclId::Id> for FmtCtx<'a> {
     fn format_decl(&self, id: FunDeclId::Id) -> String {
         match &self.fun_decls {
-            None => format!("Unknown decl: {:?}", id),
+            None => format!("Unknown decl: {}", id.to_pretty_string()),
             Some(decls) => match decls.get(id) {
                 None => {
-                    format!("Unknown decl: {:?}", id)
+                    format!("Unknown decl: {}", id.to_pretty_string())
                 }
                 Some(d) => d.fmt_with_ctx(self),
             },
@@ -596,10 +695,10 @@ impl<'a> DeclFormatter<FunDeclId::Id> for FmtCtx<'a> {
 impl<'a> DeclFormatter<TraitDeclId::Id> for FmtCtx<'a> {
     fn format_decl(&self, id: TraitDeclId::Id) -> String {
         match &self.trait_decls {
-            None => format!("Unknown decl: {:?}", id),
+            None => format!("Unknown decl: {}", id.to_pretty_string()),
             Some(decls) => match decls.get(id) {
                 None => {
-                    format!("Unknown decl: {:?}", id)
+                    format!("Unknown decl: {}", id.to_pretty_string())
                 }
                 Some(d) => d.fmt_with_ctx(self),
             },
@@ -610,10 +709,10 @@ impl<'a> DeclFormatter<TraitDeclId::Id> for FmtCtx<'a> {
 impl<'a> DeclFormatter<TraitImplId::Id> for FmtCtx<'a> {
     fn format_decl(&self, id: TraitImplId::Id) -> String {
         match &self.trait_impls {
-            None => format!("Unknown impl: {:?}", id),
+            None => format!("Unknown impl: {}", id.to_pretty_string()),
             Some(decls) => match decls.get(id) {
                 None => {
-                    format!("Unknown impl: {:?}", id)
+                    format!("Unknown impl: {}", id.to_pretty_string())
                 }
                 Some(d) => d.fmt_with_ctx(self),
             },
@@ -1,7 +1,7 @@
 //! Definitions common to [crate::ullbc_ast] and [crate::llbc_ast]
 pub use crate::expressions::*;
 pub use crate::gast_utils::*;
-use crate::meta::Meta;
+use crate::meta::{AttrInfo, BacktraceId, Meta, Span};
 use crate::names::Name;
 pub use crate::types::GlobalDeclId;
 pub use crate::types::TraitClauseId;
@@ -9,13 +9,14 @@ use crate::types::*;
 pub use crate::types::{
     GenericArgs, GenericParams, TraitDeclId, TraitImplId, TraitInstanceId, TraitRef,
 };
-use macros::generate_index_type;
-use serde::Serialize;
+use macros::{generate_index_type, EnumIsA};
+use serde::{Deserialize, Serialize};
 
 generate_index_type!(FunDeclId);
+generate_index_type!(ImplBlockId);
 
 /// A variable
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Var {
     /// Unique index identifying the variable
     pub index: VarId::Id,
@@ -26,10 +27,30 @@ pub struct Var {
     pub ty: Ty,
 }
 
+/// The debug information of a variable of the source code (see
+/// [GExprBody::var_debug_info]).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VarDebugInfo {
+    /// The name of the variable in the source code
+    pub name: String,
+    /// The span of the declaration of the variable
+    pub span: Span,
+    /// The span of the scope in which the variable is visible
+    pub scope: Span,
+    /// The local which stores the value of the variable. This is `None` if
+    /// the value is not stored in a local of its own: it can be stored in a
+    /// field of a local (for instance for the variables captured by a
+    /// closure), or be a constant (in the optimized MIR). It is also `None`
+    /// if the micro-passes removed the local.
+    pub var: Option<VarId::Id>,
+    /// If the variable is an argument of the function: its (1-based) index.
+    pub argument_index: Option<usize>,
+}
+
 /// An expression body.
 /// TODO: arg_count should be stored in GFunDecl below. But then,
 ///       the print is obfuscated and Aeneas may need some refactoring.
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GExprBody<T> {
     pub meta: Meta,
     /// The number of local variables used for the input arguments.
@@ -40,6 +61,15 @@ pub struct GExprBody<T> {
     /// - the input arguments
     /// - the remaining locals, used for the intermediate computations
     pub locals: VarId::Vector<Var>,
+    /// The debug information of the variables of the source code. Note that
+    /// the locals are not in one-to-one correspondance with those variables:
+    /// several locals may be introduced for the same variable, and a variable
+    /// may be stored in a temporary. This information allows mapping the
+    /// locals back to the variables the user wrote.
+    pub var_debug_info: Vec<VarDebugInfo>,
+    /// The macro backtraces of the statements of the body (see
+    /// [crate::meta::Meta::backtrace]).
+    pub backtraces: BacktraceId::Vector<Vec<Span>>,
     pub body: T,
 }
 
@@ -61,13 +91,16 @@ pub struct GExprBody<T> {
 /// fn test(...) { ... } // regular
 ///
 /// impl Type {
-///   fn test(...) { ... } // regular
+///   fn test(...) { ... } // inherent method
 /// }
 /// ```
-#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub enum FunKind {
     /// A "normal" function
     Regular,
+    /// Method defined in an inherent impl block (i.e., an impl block which
+    /// doesn't implement a trait). We give the id of the impl block.
+    InherentMethod(ImplBlockId::Id),
     /// Trait method implementation
     TraitMethodImpl {
         /// The trait implementation block the method belongs to
@@ -84,16 +117,43 @@ pub enum FunKind {
     /// Trait method provided function (trait method declaration which defines
     /// a default implementation at the same time)
     TraitMethodProvided(TraitDeclId::Id, TraitItemName),
+    /// Function declared in an `extern` block: it is implemented in another
+    /// language, and thus doesn't have a body. The ABI is given by the
+    /// signature (see [FunSig::abi]), and the symbol by the `#[link_name]`
+    /// attribute, if there is one (see [crate::meta::Attribute::LinkName]).
+    Foreign,
+}
+
+/// A bound on the depth of the nested calls performed by a function (see
+/// [crate::call_depth]).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIsA, Serialize, Deserialize)]
+pub enum CallDepth {
+    /// The function performs at most this number of nested calls (0 if it
+    /// doesn't call any function). We consider that the assumed functions
+    /// don't call other functions.
+    Bounded(usize),
+    /// The function may be recursive, or calls a function which may be
+    /// recursive.
+    Unbounded,
+    /// We can't bound the depth, because the function (transitively) calls
+    /// opaque functions, or performs calls we can't resolve statically (calls
+    /// depending on trait clauses, or through trait objects or function
+    /// pointers).
+    Unknown,
 }
 
 /// A function definition
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GFunDecl<T> {
     pub def_id: FunDeclId::Id,
-    #[serde(skip)]
+    /// The rust id is not serialized: when deserializing a crate, we replace
+    /// it with a dummy id (see [crate::gast_utils::dummy_def_id]).
+    #[serde(skip, default = "crate::gast_utils::dummy_def_id")]
     pub rust_id: rustc_hir::def_id::DefId,
     /// The meta data associated with the declaration.
     pub meta: Meta,
+    /// The doc comments and the attributes of the declaration.
+    pub attr_info: AttrInfo,
     /// [true] if the decl is a local decl, [false] if it comes from
     /// an external crate.
     pub is_local: bool,
@@ -107,25 +167,59 @@ pub struct GFunDecl<T> {
     /// Opaque functions are: external functions, or local functions tagged
     /// as opaque.
     pub body: Option<GExprBody<T>>,
+    /// The pure form of the function, if the user asked for it (see
+    /// [crate::cli_options::CliOpts::emit_pure_forms]) and the function
+    /// qualified (see [crate::pure_forms]).
+    pub pure_form: Option<PureExpr>,
+    /// The bound on the depth of the nested calls, if the user asked for it
+    /// (see [crate::cli_options::CliOpts::call_depth]) and the function has a
+    /// body.
+    pub call_depth: Option<CallDepth>,
+}
+
+/// The kind of a global declaration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIsA, Serialize, Deserialize)]
+pub enum GlobalKind {
+    /// A constant: `const` item, associated constant or promoted constant
+    Const,
+    /// A `static` item. The function bodies reference the statics by address
+    /// (see [crate::expressions::RawConstantExpr::GlobalDeclRef]).
+    Static {
+        /// `true` if the static is mutable (`static mut`)
+        is_mut: bool,
+        /// `true` if the type of the static has interior mutability (ex.:
+        /// `static COUNTER: AtomicUsize`): the static may then be mutated
+        /// through shared references.
+        interior_mut: bool,
+    },
 }
 
 /// A global variable definition, either opaque or transparent.
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GGlobalDecl<T> {
     pub def_id: GlobalDeclId::Id,
-    #[serde(skip)]
+    /// The rust id is not serialized: when deserializing a crate, we replace
+    /// it with a dummy id (see [crate::gast_utils::dummy_def_id]).
+    #[serde(skip, default = "crate::gast_utils::dummy_def_id")]
     pub rust_id: rustc_hir::def_id::DefId,
     /// The meta data associated with the declaration.
     pub meta: Meta,
+    /// The doc comments and the attributes of the declaration.
+    pub attr_info: AttrInfo,
     /// [true] if the decl is a local decl, [false] if it comes from
     /// an external crate.
     pub is_local: bool,
     pub name: Name,
+    /// Optional field, for the associated constants (see the comments in
+    /// [ParamsInfo]): the generics of the parent block (trait declaration or
+    /// impl block) are in scope in the type and the body of the constant.
+    pub parent_params_info: Option<ParamsInfo>,
     pub ty: Ty,
+    pub kind: GlobalKind,
     pub body: Option<GExprBody<T>>,
 }
 
-#[derive(Debug, Clone, Serialize, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct TraitItemName(pub String);
 
 /// A trait **declaration**.
@@ -161,7 +255,7 @@ pub struct TraitItemName(pub String);
 /// Of course, this forbids other useful use cases such as visitors implemented
 /// by means of traits.
 #[allow(clippy::type_complexity)]
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TraitDecl {
     pub def_id: TraitDeclId::Id,
     /// [true] if the decl is a local decl, [false] if it comes from
@@ -169,6 +263,10 @@ pub struct TraitDecl {
     pub is_local: bool,
     pub name: Name,
     pub meta: Meta,
+    /// The doc comments and the attributes of the declaration.
+    pub attr_info: AttrInfo,
+    /// [true] if this is an auto trait, like [core::marker::Send].
+    pub is_auto: bool,
     pub generics: GenericParams,
     pub preds: Predicates,
     /// The "parent" clauses: the supertraits.
@@ -220,18 +318,34 @@ pub struct TraitDecl {
 ///   fn baz(...) { ... }
 /// }
 /// ```
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TraitImpl {
     pub def_id: TraitImplId::Id,
     /// [true] if the decl is a local decl, [false] if it comes from
     /// an external crate.
     pub is_local: bool,
     pub name: Name,
+    /// The span of the impl block header (i.e., `impl<T> Foo for List<T>`).
     pub meta: Meta,
-    /// The information about the implemented trait.
+    /// The doc comments and the attributes of the declaration.
+    pub attr_info: AttrInfo,
+    /// The information about the implemented trait, as written in the
+    /// source (we do not normalize the types).
     /// Note that this contains the instantiation of the "parent"
     /// clauses.
     pub impl_trait: TraitDeclRef,
+    /// The type implementing the trait, as written in the source (this is
+    /// the first type argument of [Self::impl_trait]).
+    pub self_ty: Ty,
+    /// The source code of the impl block header, if we could retrieve it.
+    /// This is useful to report errors.
+    pub header: Option<String>,
+    /// [true] if this is a negative implementation, like `impl !Send for X`.
+    /// The negative implementations don't have any items.
+    pub is_negative: bool,
+    /// [true] if this is a blanket implementation, i.e., an implementation
+    /// for a type parameter like `impl<T: Foo> Bar for T`.
+    pub is_blanket: bool,
     pub generics: GenericParams,
     pub preds: Predicates,
     /// The trait references for the parent clauses (see [TraitDecl]).
@@ -246,20 +360,106 @@ pub struct TraitImpl {
     pub provided_methods: Vec<(TraitItemName, FunDeclId::Id)>,
 }
 
+/// An inherent impl block, i.e., an impl block which doesn't implement a
+/// trait:
+/// ```text
+/// impl<T> List<T> {
+///   fn new() -> Self { ... }
+///
+///   fn push(&mut self, x: T) { ... }
+/// }
+/// ```
+///
+/// The methods are translated as functions (see [FunKind::InherentMethod]):
+/// we use the impl blocks to group them, so that the consumers can regenerate
+/// the impl blocks and resolve the method calls.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImplBlock {
+    pub def_id: ImplBlockId::Id,
+    /// [true] if the decl is a local decl, [false] if it comes from
+    /// an external crate.
+    pub is_local: bool,
+    pub name: Name,
+    /// The span of the impl block header (i.e., `impl<T> List<T>`).
+    pub meta: Meta,
+    /// The doc comments and the attributes of the declaration.
+    pub attr_info: AttrInfo,
+    /// The type the methods are implemented for.
+    pub self_ty: Ty,
+    pub generics: GenericParams,
+    pub preds: Predicates,
+    /// The methods of the impl block which we translated (we only translate
+    /// the methods which are used, in particular for the external impl
+    /// blocks), ordered by id.
+    pub methods: Vec<FunDeclId::Id>,
+}
+
+/// A warning about a declaration which became opaque (or, for the types,
+/// erroneous) because we failed to translate it, for instance because it uses
+/// an unsupported feature, rather than because the user asked for it. We
+/// export those warnings with the crate, so that the consumers can report
+/// them.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OpaqueWarning {
+    pub name: Name,
+    pub span: Span,
+    /// The error which prevented the translation
+    pub reason: String,
+}
+
 /// A function operand is used in function calls.
 /// It either designates a top-level function, or a place in case
-/// we are using function pointers stored in local variables.
-#[derive(Debug, Clone, Serialize)]
+/// we are using function pointers stored in local variables, or a
+/// method of a trait object.
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub enum FnOperand {
     /// Regular case: call to a top-level function, trait method, etc.
     Regular(FnPtr),
     /// Use of a function pointer stored in a local variable
     Move(Place),
+    /// Virtual call: call to a method of a trait object (i.e., a method called
+    /// on a `dyn Trait` receiver). The method to call must be resolved at runtime,
+    /// by looking up the vtable of the receiver, which is the first argument of
+    /// the call.
+    ///
+    /// We store: the reference to the trait declaration (the `Self` type is the
+    /// `dyn Trait` type), the name of the method, and the generic arguments
+    /// of the method.
+    Virtual(TraitDeclRef, TraitItemName, GenericArgs),
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub struct Call {
     pub func: FnOperand,
     pub args: Vec<Operand>,
     pub dest: Place,
 }
+
+/// The kind of an assertion, which tells why the program panics if the
+/// assertion fails.
+///
+/// The assertions introduced by rustc come with a message, from which we
+/// retrieve the kind of the dynamic check (see [crate::remove_dynamic_checks]).
+#[derive(Debug, PartialEq, Eq, Clone, EnumIsA, Serialize, Deserialize)]
+pub enum AssertKind {
+    /// Array/slice bounds check: we check that `index < len`
+    IndexOutOfBounds {
+        len: Operand,
+        index: Operand,
+    },
+    /// Check that an arithmetic operation doesn't overflow (addition,
+    /// shift, signed division of `INT::MIN` by `-1`, etc.)
+    Overflow(BinOp),
+    /// Check that the negation of a signed integer doesn't overflow
+    OverflowNeg,
+    DivisionByZero,
+    RemainderByZero,
+    /// Check that we resume a generator which didn't already return or panic
+    ResumedGenerator,
+    /// Check that we dereference a properly aligned pointer
+    MisalignedPointerDereference,
+    /// An assertion which doesn't come from a MIR assertion, but from a
+    /// conditional panic like `if b { panic!(...) }` (we introduce those
+    /// in [crate::reconstruct_asserts]).
+    User,
+}
@@ -5,10 +5,17 @@ use crate::formatter::{AstFormatter, Formatter, SetGenerics, SetLocals};
 use crate::gast::*;
 use crate::names::Name;
 use crate::types::*;
+use crate::ullbc_ast;
 use crate::values::*;
 use rustc_hir::def_id::DefId;
 use std::cmp::max;
 
+/// The id we use for the [GFunDecl::rust_id] and [GGlobalDecl::rust_id] fields
+/// when deserializing a crate (the rust ids are not serialized).
+pub fn dummy_def_id() -> DefId {
+    rustc_hir::def_id::CRATE_DEF_ID.to_def_id()
+}
+
 /// Iterate on the declarations' non-empty bodies with their corresponding name and type.
 /// TODO: generalize this with visitors
 pub fn iter_function_bodies<T>(
@@ -146,7 +153,8 @@ impl TraitDecl {
             }
         };
 
-        format!("trait {name}{generics}{clauses}{items}")
+        let auto = if self.is_auto { "auto " } else { "" };
+        format!("{auto}trait {name}{generics}{clauses}{items}")
     }
 }
 
@@ -209,8 +217,9 @@ impl TraitImpl {
             }
         };
 
+        let negative = if self.is_negative { "!" } else { "" };
         let impl_trait = self.impl_trait.fmt_with_ctx(ctx);
-        format!("impl{generics} {name}{generics} : {impl_trait}{clauses}{items}")
+        format!("impl{generics} {name}{generics} : {negative}{impl_trait}{clauses}{items}")
     }
 }
 
@@ -222,6 +231,35 @@ impl FnOperand {
         match self {
             FnOperand::Regular(func) => func.fmt_with_ctx(ctx),
             FnOperand::Move(p) => format!("(move {})", p.fmt_with_ctx(ctx)),
+            FnOperand::Virtual(trait_ref, method_name, generics) => {
+                let trait_ref = trait_ref.fmt_with_ctx(ctx);
+                let generics = generics.fmt_with_ctx_split_trait_refs(ctx);
+                format!("(dyn {trait_ref})::{}{generics}", method_name.0)
+            }
+        }
+    }
+}
+
+impl AssertKind {
+    pub fn fmt_with_ctx<C>(&self, ctx: &C) -> String
+    where
+        C: AstFormatter,
+    {
+        match self {
+            AssertKind::IndexOutOfBounds { len, index } => format!(
+                "index_out_of_bounds(len: {}, index: {})",
+                len.fmt_with_ctx(ctx),
+                index.fmt_with_ctx(ctx)
+            ),
+            AssertKind::Overflow(binop) => format!("overflow({binop})"),
+            AssertKind::OverflowNeg => "overflow_neg".to_string(),
+            AssertKind::DivisionByZero => "division_by_zero".to_string(),
+            AssertKind::RemainderByZero => "remainder_by_zero".to_string(),
+            AssertKind::ResumedGenerator => "resumed_generator".to_string(),
+            AssertKind::MisalignedPointerDereference => {
+                "misaligned_pointer_dereference".to_string()
+            }
+            AssertKind::User => "user".to_string(),
         }
     }
 }
@@ -240,7 +278,7 @@ where
             .trait_and_method_generic_args
             .as_ref()
             .map(|generics| generics.fmt_with_ctx_split_trait_refs(ctx)),
-        FnOperand::Move(_) => Option::None,
+        FnOperand::Move(_) | FnOperand::Virtual(..) => Option::None,
     };
 
     let f = call.func.fmt_with_ctx(ctx);
@@ -311,6 +349,25 @@ impl<T> GExprBody<T> {
 }
 
 impl<T> GFunDecl<T> {
+    /// Copy the declaration without its body. This is useful to build a
+    /// formatting context (see [crate::formatter::FmtCtx]) from LLBC
+    /// declarations.
+    pub fn without_body(&self) -> ullbc_ast::FunDecl {
+        GFunDecl {
+            def_id: self.def_id,
+            rust_id: self.rust_id,
+            meta: self.meta,
+            attr_info: self.attr_info.clone(),
+            is_local: self.is_local,
+            name: self.name.clone(),
+            signature: self.signature.clone(),
+            kind: self.kind.clone(),
+            body: None,
+            pure_form: self.pure_form.clone(),
+            call_depth: self.call_depth,
+        }
+    }
+
     /// This is an auxiliary function for printing definitions. One may wonder
     /// why we require a formatter to format, for instance, (type) var ids,
     /// because the function definition already has the information to print
@@ -332,12 +389,8 @@ impl<T> GFunDecl<T> {
         // Update the context
         let ctx = &ctx.set_generics(&self.signature.generics);
 
-        // Unsafe keyword
-        let unsafe_kw = if self.signature.is_unsafe {
-            "unsafe ".to_string()
-        } else {
-            "".to_string()
-        };
+        // Qualifiers (`unsafe`, etc.)
+        let qualifiers = self.signature.fmt_qualifiers();
 
         // Function name
         let name = self.name.fmt_with_ctx(ctx);
@@ -355,6 +408,9 @@ impl<T> GFunDecl<T> {
                 format!("{}: {}", id.to_pretty_string(), arg_ty.fmt_with_ctx(ctx)).to_string(),
             );
         }
+        if self.signature.is_variadic {
+            args.push("...".to_string());
+        }
         let args = args.join(", ");
 
         // Return type
@@ -378,16 +434,25 @@ impl<T> GFunDecl<T> {
         match &self.body {
             Option::None => {
                 // Put everything together
-                format!("{tab}{unsafe_kw}fn {name}{params}({args}){ret_ty}{preds}")
+                format!("{tab}{qualifiers}fn {name}{params}({args}){ret_ty}{preds}")
             }
             Option::Some(body) => {
-                // Body
+                // Pure form (see [crate::pure_forms])
                 let body_tab = format!("{tab}{TAB_INCR}");
+                let pure_form = match &self.pure_form {
+                    Option::None => "".to_string(),
+                    Option::Some(e) => {
+                        let e = e.fmt_with_ctx(&body_tab, &ctx.set_locals(&body.locals));
+                        format!("\n{tab}pure\n{tab}{{\n{e}\n{tab}}}")
+                    }
+                };
+
+                // Body
                 let body = body.fmt_with_ctx(&body_tab, ctx);
 
                 // Put everything together
                 format!(
-                    "{tab}{unsafe_kw}fn {name}{params}({args}){ret_ty}{preds}\n{tab}{{\n{body}\n{tab}}}",
+                    "{tab}{qualifiers}fn {name}{params}({args}){ret_ty}{preds}\n{tab}{{\n{body}\n{tab}}}{pure_form}",
                 )
             }
         }
@@ -395,6 +460,22 @@ impl<T> GFunDecl<T> {
 }
 
 impl<T> GGlobalDecl<T> {
+    /// Copy the declaration without its body (see [GFunDecl::without_body]).
+    pub fn without_body(&self) -> ullbc_ast::GlobalDecl {
+        GGlobalDecl {
+            def_id: self.def_id,
+            rust_id: self.rust_id,
+            meta: self.meta,
+            attr_info: self.attr_info.clone(),
+            is_local: self.is_local,
+            name: self.name.clone(),
+            parent_params_info: self.parent_params_info.clone(),
+            ty: self.ty.clone(),
+            kind: self.kind,
+            body: None,
+        }
+    }
+
     /// This is an auxiliary function for printing definitions. One may wonder
     /// why we require a formatter to format, for instance, (type) var ids,
     /// because the global definition already has the information to print
@@ -414,11 +495,18 @@ impl<T> GGlobalDecl<T> {
         // Decl name
         let name = self.name.fmt_with_ctx(ctx);
 
+        // Keyword
+        let kw = match self.kind {
+            GlobalKind::Const => "global",
+            GlobalKind::Static { is_mut: false, .. } => "static",
+            GlobalKind::Static { is_mut: true, .. } => "static mut",
+        };
+
         // Case disjunction on the presence of a body (transparent/opaque definition)
         match &self.body {
             Option::None => {
                 // Put everything together
-                format!("{tab}global {name}")
+                format!("{tab}{kw} {name}")
             }
             Option::Some(body) => {
                 // Body
@@ -426,7 +514,7 @@ impl<T> GGlobalDecl<T> {
                 let body = body.fmt_with_ctx(&body_tab, ctx);
 
                 // Put everything together
-                format!("{tab}global {name} {{\n{body}\n{tab}}}")
+                format!("{tab}{kw} {name} {{\n{body}\n{tab}}}")
             }
         }
     }
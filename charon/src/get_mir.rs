@@ -1,8 +1,11 @@
 //! Various utilities to load MIR.
 //! Allow to easily load the MIR code generated by a specific pass.
 
+use rustc_hir::def::DefKind;
 use rustc_hir::def_id::{DefId, LocalDefId};
-use rustc_middle::mir::Body;
+use rustc_hir::ConstContext;
+use rustc_index::IndexVec;
+use rustc_middle::mir::{Body, Promoted};
 use rustc_middle::ty::TyCtxt;
 
 /// TODO: maybe we should always target MIR Built, this would make things
@@ -63,8 +66,77 @@ pub fn get_mir_for_def_id_and_level(
                 krate: rustc_hir::def_id::LOCAL_CRATE,
                 index: def_id.local_def_index,
             };
+            // The optimized MIR is not available for the initializers of the
+            // constants and statics: we retrieve the MIR used by the constant
+            // evaluator, which went through the same optimizations. This way,
+            // the global bodies are available at all the levels, like the
+            // function bodies.
+            let is_global = match tcx.hir().body_const_context(def_id.expect_local()) {
+                None | Some(ConstContext::ConstFn) => false,
+                Some(ConstContext::Const | ConstContext::Static(_)) => true,
+            };
+            // We clone to be sure there are no problems with locked values
+            if is_global {
+                tcx.mir_for_ctfe(def_id).clone()
+            } else {
+                tcx.optimized_mir(def_id).clone()
+            }
+        }
+    }
+}
+
+/// Is the body of an item from another crate a global initializer (in which
+/// case only the MIR used by the constant evaluator is available)?
+fn external_item_is_global(tcx: TyCtxt<'_>, def_id: DefId) -> bool {
+    matches!(
+        tcx.def_kind(def_id),
+        DefKind::Const | DefKind::AssocConst | DefKind::Static(_)
+    )
+}
+
+/// Check if the MIR of an item from another crate is available in the metadata
+/// of this crate. Note that rustc only exports the bodies it may need when
+/// compiling the downstream crates (the generic and inline functions, the
+/// constants, etc.), unless the crate was compiled with `-Zalways-encode-mir`.
+pub fn external_mir_is_available(tcx: TyCtxt<'_>, def_id: DefId) -> bool {
+    if external_item_is_global(tcx, def_id) {
+        tcx.is_ctfe_mir_available(def_id)
+    } else {
+        tcx.is_mir_available(def_id)
+    }
+}
+
+/// Query the MIR of an item from another crate (see
+/// [external_mir_is_available]). The crate metadata only contains the
+/// optimized MIR: this is always a [MirLevel::Optimized] body.
+pub fn get_external_mir(tcx: TyCtxt<'_>, def_id: DefId) -> Body<'_> {
+    assert!(!def_id.is_local());
+    // We clone to be sure there are no problems with locked values
+    if external_item_is_global(tcx, def_id) {
+        tcx.mir_for_ctfe(def_id).clone()
+    } else {
+        tcx.optimized_mir(def_id).clone()
+    }
+}
+
+/// Query the MIR of the promoted constants of a function (or of a global) at a
+/// specific level.
+///
+/// Note that the constants are promoted by the pass which produces the
+/// [MirLevel::Promoted] MIR: the built MIR doesn't contain any promoted
+/// constant.
+pub fn get_promoted_mir_for_def_id_and_level(
+    tcx: TyCtxt<'_>,
+    def_id: LocalDefId,
+    level: MirLevel,
+) -> IndexVec<Promoted, Body<'_>> {
+    match level {
+        MirLevel::Built => IndexVec::new(),
+        MirLevel::Promoted => {
+            let (_, promoted) = tcx.mir_promoted(def_id);
             // We clone to be sure there are no problems with locked values
-            tcx.optimized_mir(def_id).clone()
+            promoted.borrow().clone()
         }
+        MirLevel::Optimized => tcx.promoted_mir(def_id.to_def_id()).clone(),
     }
 }
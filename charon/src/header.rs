@@ -0,0 +1,64 @@
+//! The header of the generated files (`.ullbc` and `.llbc`).
+//!
+//! The header records the version of the format of the generated files,
+//! together with information about the toolchain and the crate. We check the
+//! version of the format when loading a file (see [crate::deserialize]): this
+//! allows the consumers to detect the files which were generated by an
+//! incompatible version of Charon, instead of failing with an obscure
+//! deserialization error.
+
+use rustc_session::Session;
+use serde::{Deserialize, Serialize};
+
+/// The version of the format of the generated files.
+///
+/// This must be incremented whenever we make a change to the serialized
+/// ASTs which is not backward compatible, and kept in sync with
+/// `format_version` in `charon-ml/src/GAstOfJson.ml`.
+pub const FORMAT_VERSION: u32 = 14;
+
+/// The header of a generated file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Header {
+    /// The version of the format (see [FORMAT_VERSION])
+    pub format_version: u32,
+    /// The version of Charon
+    pub charon_version: String,
+    /// The version of the Rust compiler used for the extraction
+    pub rustc_version: String,
+    /// The revision of hax used for the extraction
+    pub hax_version: String,
+    pub crate_name: String,
+    /// The edition of the crate (`2018`, `2021`, etc.)
+    pub edition: String,
+    /// The target triple
+    pub target: String,
+}
+
+impl Header {
+    /// Compute the header of the file we generate for a crate.
+    pub fn new(sess: &Session, crate_name: &str) -> Self {
+        Header {
+            format_version: FORMAT_VERSION,
+            charon_version: env!("CARGO_PKG_VERSION").to_string(),
+            rustc_version: sess.cfg_version.to_string(),
+            hax_version: env!("CHARON_HAX_VERSION").to_string(),
+            crate_name: crate_name.to_string(),
+            edition: sess.edition().to_string(),
+            target: sess.opts.target_triple.to_string(),
+        }
+    }
+
+    /// Check that we can read a file with this header.
+    pub fn check(&self) -> Result<(), String> {
+        if self.format_version != FORMAT_VERSION {
+            return Err(format!(
+                "The file was generated by Charon {} with the format version {}, \
+                 while this version of Charon expects the format version {}: \
+                 please regenerate the file",
+                self.charon_version, self.format_version, FORMAT_VERSION
+            ));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,135 @@
+//! Warn the user when an opaque declaration hides effects.
+//!
+//! When the user makes a local item opaque (with `--opaque`, or through the
+//! hooks), we don't extract its body: the consumers of the generated file only
+//! see its signature, and will typically model it as a pure, total function.
+//! This is unsound if the body may panic, contains unsafe code or accesses a
+//! mutable static. We inspect the MIR of the local items before making them
+//! opaque, and emit a warning listing the effects we found, so that the user
+//! knows they need to take them into account in their model.
+//!
+//! Remark: the analysis is purely local (we don't look at the callees), and
+//! conservative (a runtime check which can't fail still counts as a panic).
+
+use crate::get_mir::get_mir_for_def_id_and_level;
+use crate::translate_ctx::TransCtx;
+use rustc_hir::def_id::DefId;
+use rustc_middle::mir::visit::Visitor;
+use rustc_middle::mir::{
+    Body, ClearCrossCrate, Constant, Location, Safety, Terminator, TerminatorKind,
+};
+use rustc_middle::ty::{TyCtxt, TyKind};
+use std::collections::BTreeSet;
+
+/// An effect hidden by an opaque declaration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum HiddenEffect {
+    /// The body may panic (it contains runtime checks or calls to the panic
+    /// functions)
+    Panic,
+    /// The body contains unsafe code
+    Unsafe,
+    /// The body accesses a mutable static
+    MutableStatic,
+}
+
+impl std::fmt::Display for HiddenEffect {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HiddenEffect::Panic => write!(f, "may panic"),
+            HiddenEffect::Unsafe => write!(f, "contains unsafe code"),
+            HiddenEffect::MutableStatic => write!(f, "accesses a mutable static"),
+        }
+    }
+}
+
+/// Check if a function is one of the functions of the standard library which
+/// panic (they are all defined in the `panicking` modules).
+fn is_panic_fun(tcx: TyCtxt<'_>, id: DefId) -> bool {
+    let krate = tcx.crate_name(id.krate);
+    (krate.as_str() == "core" || krate.as_str() == "std")
+        && tcx.opt_parent(id).map_or(false, |parent| {
+            tcx.opt_item_name(parent)
+                .map_or(false, |name| name.as_str() == "panicking")
+        })
+}
+
+struct ComputeEffects<'tcx> {
+    tcx: TyCtxt<'tcx>,
+    effects: BTreeSet<HiddenEffect>,
+}
+
+impl<'tcx> Visitor<'tcx> for ComputeEffects<'tcx> {
+    fn visit_terminator(&mut self, terminator: &Terminator<'tcx>, location: Location) {
+        match &terminator.kind {
+            TerminatorKind::Assert { .. } => {
+                let _ = self.effects.insert(HiddenEffect::Panic);
+            }
+            TerminatorKind::Call { func, .. } => {
+                if let Some(constant) = func.constant()
+                    && let TyKind::FnDef(id, _) = constant.literal.ty().kind()
+                    && is_panic_fun(self.tcx, *id)
+                {
+                    let _ = self.effects.insert(HiddenEffect::Panic);
+                }
+            }
+            _ => (),
+        }
+        self.super_terminator(terminator, location)
+    }
+
+    fn visit_constant(&mut self, constant: &Constant<'tcx>, location: Location) {
+        if let Some(id) = constant.check_static_ptr(self.tcx)
+            && self.tcx.is_mutable_static(id)
+        {
+            let _ = self.effects.insert(HiddenEffect::MutableStatic);
+        }
+        self.super_constant(constant, location)
+    }
+}
+
+/// Compute the effects of a body.
+pub fn compute_effects<'tcx>(tcx: TyCtxt<'tcx>, body: &Body<'tcx>) -> BTreeSet<HiddenEffect> {
+    let mut visitor = ComputeEffects {
+        tcx,
+        effects: BTreeSet::new(),
+    };
+    visitor.visit_body(body);
+
+    // The unsafe blocks and the bodies of the unsafe functions. We ignore the
+    // unsafe code introduced by the compiler (e.g., when desugaring `await`).
+    let is_unsafe = body.source_scopes.iter().any(|scope| {
+        matches!(&scope.local_data, ClearCrossCrate::Set(data)
+            if matches!(data.safety, Safety::FnUnsafe | Safety::ExplicitUnsafe(_)))
+    });
+    if is_unsafe {
+        let _ = visitor.effects.insert(HiddenEffect::Unsafe);
+    }
+    visitor.effects
+}
+
+/// Warn the user if the body of a local item we make opaque has effects.
+pub(crate) fn warn_hidden_effects(ctx: &TransCtx, id: DefId) {
+    // We can only analyze the local items which have a body
+    let Some(local_id) = id.as_local() else {
+        return;
+    };
+    if ctx.tcx.hir().maybe_body_owned_by(local_id).is_none() {
+        return;
+    }
+
+    let body = get_mir_for_def_id_and_level(ctx.tcx, local_id, ctx.mir_level);
+    let effects = compute_effects(ctx.tcx, &body);
+    if effects.is_empty() {
+        return;
+    }
+    let effects: Vec<String> = effects.iter().map(|e| e.to_string()).collect();
+    ctx.session.span_warn(
+        ctx.tcx.def_span(id),
+        format!(
+            "The opaque declaration {} hides effects: its body {}",
+            ctx.tcx.def_path_str(id),
+            effects.join(", ")
+        ),
+    );
+}
@@ -0,0 +1,75 @@
+//! Hooks which allow the library users to customize the extraction (see
+//! [ExtractionHook]): to ignore some items, to attach metadata to the items
+//! (for instance, specifications coming from an external database), or to
+//! rename the items.
+//!
+//! The hook is given through [crate::driver::CharonCallbacks::hook].
+
+use crate::names::Name;
+use crate::reorder_decls::AnyTransId;
+use crate::translate_ctx::TransCtx;
+use serde::Serialize;
+
+/// A hook called during the extraction.
+///
+/// All the methods have a default implementation which does nothing.
+pub trait ExtractionHook {
+    /// Called the first time we encounter a type, a function or a global.
+    /// If it returns `true`, we don't translate the definition of the item:
+    /// we treat it as if it were opaque (see
+    /// [crate::cli_options::CliOpts::opaque_modules]).
+    fn veto_item(&mut self, _name: &Name) -> bool {
+        false
+    }
+
+    /// Called on every translated item, after the translation from MIR to ULLBC.
+    /// The metadata returned by this function is exported to `CRATE.metadata.json`.
+    fn item_metadata(&mut self, _id: AnyTransId, _name: &Name) -> Option<serde_json::Value> {
+        None
+    }
+
+    /// Called on the name of every translated item, after [Self::item_metadata].
+    /// This allows to rename the items in the generated files.
+    fn rewrite_name(&mut self, _name: &mut Name) {}
+}
+
+/// The metadata of an item, as returned by [ExtractionHook::item_metadata].
+#[derive(Debug, Clone, Serialize)]
+pub struct ItemMetadata {
+    pub id: AnyTransId,
+    pub metadata: serde_json::Value,
+}
+
+/// Call the hook on all the translated items: collect their metadata and
+/// rewrite their names.
+///
+/// We do this on the ULLBC: the LLBC declarations are derived from it.
+pub fn apply(ctx: &mut TransCtx) -> Vec<ItemMetadata> {
+    let Some(hook) = &mut ctx.hook else {
+        return Vec::new();
+    };
+    let mut metadata = Vec::new();
+    let mut visit = |id: AnyTransId, name: &mut Name| {
+        if let Some(m) = hook.item_metadata(id, name) {
+            metadata.push(ItemMetadata { id, metadata: m });
+        }
+        hook.rewrite_name(name);
+    };
+
+    for d in ctx.type_decls.iter_mut() {
+        visit(AnyTransId::Type(d.def_id), &mut d.name);
+    }
+    for d in ctx.fun_decls.iter_mut() {
+        visit(AnyTransId::Fun(d.def_id), &mut d.name);
+    }
+    for d in ctx.global_decls.iter_mut() {
+        visit(AnyTransId::Global(d.def_id), &mut d.name);
+    }
+    for d in ctx.trait_decls.iter_mut() {
+        visit(AnyTransId::TraitDecl(d.def_id), &mut d.name);
+    }
+    for d in ctx.trait_impls.iter_mut() {
+        visit(AnyTransId::TraitImpl(d.def_id), &mut d.name);
+    }
+    metadata
+}
@@ -3,7 +3,7 @@
 //! This data-structure is mostly meant to be used with the index types defined
 //! with [macros::generate_index_type]: by using custom index types, we
 //! leverage the type checker to prevent us from mixing them.
-use serde::{Serialize, Serializer};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 pub use std::collections::btree_map::Iter as IterAll;
 pub use std::collections::btree_map::IterMut as IterAllMut;
 pub use std::collections::BTreeMap;
@@ -84,6 +84,19 @@ impl<Id: Serialize, T: Clone + Serialize> Serialize for Map<Id, T> {
     }
 }
 
+impl<'de, Id: std::cmp::Ord + Deserialize<'de>, T: Clone + Deserialize<'de>> Deserialize<'de>
+    for Map<Id, T>
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        // The map is serialized as a sequence of pairs
+        let bindings: Vec<(Id, T)> = Vec::deserialize(deserializer)?;
+        Ok(Map::from_iter(bindings))
+    }
+}
+
 impl<Id, T> FromIterator<(Id, T)> for Map<Id, T>
 where
     Id: std::cmp::Ord,
@@ -9,7 +9,7 @@
 //!
 //! TODO: Rustc already provides an `index_vector`. Use it?
 
-use serde::{Serialize, Serializer};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::iter::{FromIterator, IntoIterator};
 
 pub use std::collections::hash_map::Iter as IterAll;
@@ -269,3 +269,14 @@ impl<I: ToUsize, T: Clone + Serialize> Serialize for Vector<I, T> {
         seq.end()
     }
 }
+
+impl<'de, I: ToUsize, T: Clone + Deserialize<'de>> Deserialize<'de> for Vector<I, T> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        // The vector is serialized as a sequence
+        let v: Vec<T> = Vec::deserialize(deserializer)?;
+        Ok(Vector::from(v))
+    }
+}
@@ -54,7 +54,7 @@ impl<'a> Transform<'a> {
                     _ => unreachable!(),
                 };
 
-                let elem_ty = generics.types[0].clone();
+                let elem_ty = buf_ty.as_array_or_slice().unwrap().clone();
 
                 // We need to introduce intermediate statements (and
                 // temporary variables)
@@ -147,7 +147,8 @@ impl<'a> MutExprVisitor for Transform<'a> {
     fn visit_rvalue(&mut self, rv: &mut Rvalue) {
         use Rvalue::*;
         match rv {
-            Use(_) | UnaryOp(..) | BinaryOp(..) | Aggregate(..) | Global(..) | Repeat(..) => {
+            Use(_) | UnaryOp(..) | BinaryOp(..) | Nullary(..) | Aggregate(..) | Global(..)
+            | Repeat(..) | ShallowInitBox(..) => {
                 // We don't access places here, only operands
                 self.default_visit_rvalue(rv)
             }
@@ -162,6 +163,10 @@ impl<'a> MutExprVisitor for Transform<'a> {
                     }
                 }
             }
+            AddressOf(p, kind) => match *kind {
+                RefKind::Mut => self.visit_transform_place(true, p),
+                RefKind::Shared => self.visit_transform_place(false, p),
+            },
             Discriminant(p, _) | Len(p, _, _) => {
                 // We access places, but those places are used to access
                 // elements without mutating them
@@ -194,14 +199,15 @@ impl<'a> MutAstVisitor for Transform<'a> {
         use RawStatement::*;
         // The match is explicit on purpose: we want to make sure we intercept changes
         match st {
-            Sequence(..) => {
+            Block(..) => {
                 // Do nothing: we don't want to dive
             }
             FakeRead(p) => {
                 self.visit_transform_place(false, p);
             }
             Assign(..) | SetDiscriminant(..) | Drop(..) | Assert(..) | Call(..) | Panic
-            | Return | Break(..) | Continue(..) | Nop | Switch(..) | Loop(..) => {
+            | Return | Break(..) | Continue(..) | Nop | Switch(..) | Loop(..) | ForLoop(..)
+            | QuestionMark(..) => {
                 // Explore
                 self.default_visit_raw_statement(st)
             }
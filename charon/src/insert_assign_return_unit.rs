@@ -26,7 +26,7 @@ fn transform_st(st: &mut Statement) -> Option<Vec<Statement>> {
         );
         let assign_st = Statement::new(st.meta, RawStatement::Assign(ret_place, unit_value));
         let ret_st = Statement::new(st.meta, RawStatement::Return);
-        st.content = RawStatement::Sequence(Box::new(assign_st), Box::new(ret_st));
+        st.content = RawStatement::Block(vec![assign_st, ret_st]);
     };
     None
 }
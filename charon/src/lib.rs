@@ -27,38 +27,58 @@ extern crate linked_hash_set;
 extern crate log;
 extern crate rustc_abi;
 extern crate rustc_ast;
+extern crate rustc_ast_pretty;
 extern crate rustc_borrowck;
 extern crate rustc_const_eval;
+extern crate rustc_data_structures;
 extern crate rustc_driver;
 extern crate rustc_error_messages;
 extern crate rustc_errors;
 extern crate rustc_hir;
 extern crate rustc_index;
+extern crate rustc_infer;
 extern crate rustc_interface;
 extern crate rustc_middle;
 extern crate rustc_mir_dataflow;
 extern crate rustc_mir_transform;
 extern crate rustc_monomorphize;
+extern crate rustc_query_system;
 extern crate rustc_resolve;
 extern crate rustc_session;
 extern crate rustc_span;
 extern crate rustc_target;
+extern crate rustc_trait_selection;
 extern crate take_mut;
 
 #[macro_use]
 pub mod common;
 pub mod assumed;
+pub mod batch;
+pub mod blanket_impls;
+pub mod cache;
+pub mod call_depth;
+pub mod check_generic_args;
+pub mod check_regions;
+pub mod check_trait_impls;
 pub mod cli_options;
+pub mod dependencies;
 pub mod deps_errors;
+pub mod deserialize;
+pub mod devirtualization;
+pub mod diff;
 pub mod driver;
 pub mod export;
 pub mod expressions;
 pub mod expressions_utils;
+pub mod fold_constants;
 pub mod formatter;
 pub mod gast;
 pub mod gast_utils;
 pub mod get_mir;
 pub mod graphs;
+pub mod header;
+pub mod hidden_effects;
+pub mod hooks;
 pub mod id_map;
 pub mod id_vector;
 pub mod index_to_function_calls;
@@ -66,19 +86,37 @@ pub mod insert_assign_return_unit;
 pub mod llbc_ast;
 pub mod llbc_ast_utils;
 pub mod logger;
+pub mod manifest;
 pub mod meta;
 pub mod meta_utils;
+pub mod metrics;
 pub mod names;
 pub mod names_utils;
 pub mod ops_to_function_calls;
+pub mod print_crate;
+pub mod progress;
+pub mod pure_forms;
+pub mod query;
+pub mod reachability;
+pub mod reconstruct_aggregates;
 pub mod reconstruct_asserts;
+pub mod reconstruct_boolean_ops;
+pub mod reconstruct_box_new;
+pub mod reconstruct_for_loops;
+pub mod reconstruct_question_marks;
+pub mod remove_dead_assignments;
 pub mod remove_drop_never;
 pub mod remove_dynamic_checks;
+pub mod remove_fmt_calls;
 pub mod remove_nops;
 pub mod remove_read_discriminant;
 pub mod remove_unused_locals;
 pub mod reorder_decls;
+pub mod simplify_cfg;
 pub mod simplify_constants;
+pub mod stats;
+pub mod stubs;
+pub mod target;
 pub mod translate_constants;
 pub mod translate_crate_to_ullbc;
 pub mod translate_ctx;
@@ -86,6 +124,7 @@ pub mod translate_functions_to_ullbc;
 pub mod translate_predicates;
 pub mod translate_traits;
 pub mod translate_types;
+pub mod type_check;
 pub mod types;
 pub mod types_utils;
 pub mod ullbc_ast;
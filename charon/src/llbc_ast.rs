@@ -13,24 +13,35 @@ use crate::types::*;
 pub use crate::ullbc_ast::{Call, FunDeclId, GlobalDeclId, Var};
 use crate::values::*;
 use macros::{EnumAsGetters, EnumIsA, EnumToGetters, VariantIndexArity, VariantName};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 /// Asserts are special constructs introduced by Rust to perform dynamic
 /// checks, to detect out-of-bounds accesses or divisions by zero for
 /// instance. We eliminate the assertions in [crate::remove_dynamic_checks],
 /// then introduce other dynamic checks in [crate::reconstruct_asserts].
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub struct Assert {
     pub cond: Operand,
     pub expected: bool,
+    /// The kind of the check (out-of-bounds access, overflow, etc.)
+    pub kind: AssertKind,
 }
 
+/// A loop label. We introduce labels only for the loops which are the targets
+/// of a `break` or a `continue` exiting several loops at once (in which case the
+/// relative loop indices are hard to read).
+pub type LoopLabel = usize;
+
 /// A raw statement: a statement without meta data.
-#[derive(Debug, Clone, EnumIsA, EnumToGetters, EnumAsGetters, Serialize)]
+#[derive(
+    Debug, PartialEq, Eq, Clone, EnumIsA, EnumToGetters, EnumAsGetters, Serialize, Deserialize,
+)]
 pub enum RawStatement {
     Assign(Place, Rvalue),
     FakeRead(Place),
     SetDiscriminant(Place, VariantId::Id),
+    /// Drop the value stored in a place. The semantics of the drop is given
+    /// by the drop glue of the type of the place (see [crate::types::DropGlue]).
     Drop(Place),
     Assert(Assert),
     Call(Call),
@@ -42,32 +53,113 @@ pub enum RawStatement {
     /// * 0: break to first outer loop (the current loop)
     /// * 1: break to second outer loop
     /// * ...
-    Break(usize),
+    ///
+    /// If we break to a loop which is not the current loop, we also give the
+    /// label of this loop.
+    Break(usize, Option<LoopLabel>),
     /// Continue to outer loops.
     /// The `usize` gives the index of the outer loop to continue to:
     /// * 0: continue to first outer loop (the current loop)
     /// * 1: continue to second outer loop
     /// * ...
-    Continue(usize),
+    ///
+    /// The label works the same way as for [RawStatement::Break].
+    Continue(usize, Option<LoopLabel>),
     /// No-op.
     Nop,
-    /// The left statement must NOT be a sequence.
-    /// For instance, `(s0; s1); s2` is forbidden and should be rewritten
-    /// to the semantically equivalent statement `s0; (s1; s2)`
-    /// To ensure that, use [crate::llbc_ast_utils::new_sequence] to build sequences.
-    Sequence(Box<Statement>, Box<Statement>),
+    /// A block of statements, executed in sequence.
+    ///
+    /// The statements of a block must NOT be blocks: nested blocks are
+    /// flattened. For instance, `{ s0; { s1; s2 } }` is forbidden and should be
+    /// rewritten to the semantically equivalent statement `{ s0; s1; s2 }`.
+    /// To ensure that, use [crate::llbc_ast_utils::new_sequence] and
+    /// [crate::llbc_ast_utils::new_block] to build blocks.
+    Block(Vec<Statement>),
     Switch(Switch),
-    Loop(Box<Statement>),
+    /// A loop, with an optional label (see [LoopLabel]).
+    Loop(Box<Statement>, Option<LoopLabel>),
+    /// A `for` loop. Those are introduced by [crate::reconstruct_for_loops]
+    /// (which is optional).
+    ForLoop(ForLoop),
+    /// An application of the `?` operator. Those are introduced by
+    /// [crate::reconstruct_question_marks] (which is optional).
+    QuestionMark(QuestionMark),
+}
+
+/// A `for` loop, reconstructed from its desugaring. rustc desugars:
+/// ```text
+/// for x in e { body }
+/// ```
+/// to:
+/// ```text
+/// iter_var = IntoIterator::into_iter(e);
+/// loop {
+///   match Iterator::next(&mut iter_var) {
+///     None => break,
+///     Some(y) => { x = y; body }
+///   }
+/// }
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub struct ForLoop {
+    /// The value we iterate over (i.e., the argument of `into_iter`)
+    pub iter: Operand,
+    /// The local variable which holds the iterator
+    pub iter_var: VarId::Id,
+    /// The function we call to convert [ForLoop::iter] to an iterator (an
+    /// implementation of `IntoIterator::into_iter`)
+    pub into_iter: FnPtr,
+    /// The function we call to retrieve the next item (an implementation of
+    /// `Iterator::next`)
+    pub next: FnPtr,
+    /// The place in which we store the items produced by the iterator (`None`
+    /// if the items are ignored, as in `for _ in e { ... }`)
+    pub binder: Option<Place>,
+    pub body: Box<Statement>,
+    /// The label of the loop (see [LoopLabel])
+    pub label: Option<LoopLabel>,
+}
+
+/// An application of the `?` operator, reconstructed from its desugaring.
+/// rustc desugars `x = e?` to:
+/// ```text
+/// match Try::branch(e) {
+///   ControlFlow::Continue(y) => x = y,
+///   ControlFlow::Break(r) => return FromResidual::from_residual(r),
+/// }
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub struct QuestionMark {
+    /// The operand we apply `?` to (i.e., the argument of `branch`)
+    pub op: Operand,
+    /// The function we call to decide whether to continue or to return early
+    /// (an implementation of `Try::branch`)
+    pub branch: FnPtr,
+    /// The function we call to convert the residual to the returned value, when
+    /// we return early (an implementation of `FromResidual::from_residual`)
+    pub from_residual: FnPtr,
+    /// The place in which we store the output (`None` if the output is ignored)
+    pub dest: Option<Place>,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub struct Statement {
     pub meta: Meta,
     pub content: RawStatement,
 }
 
 #[derive(
-    Debug, Clone, EnumIsA, EnumToGetters, EnumAsGetters, Serialize, VariantName, VariantIndexArity,
+    Debug,
+    PartialEq,
+    Eq,
+    Clone,
+    EnumIsA,
+    EnumToGetters,
+    EnumAsGetters,
+    Serialize,
+    Deserialize,
+    VariantName,
+    VariantIndexArity,
 )]
 pub enum Switch {
     /// Gives the `if` block and the `else` block
@@ -89,7 +181,7 @@ pub enum Switch {
     SwitchInt(
         Operand,
         IntegerTy,
-        Vec<(Vec<ScalarValue>, Statement)>,
+        Vec<(Vec<SwitchValue>, Statement)>,
         Box<Statement>,
     ),
     /// A match over an ADT.
@@ -3,59 +3,50 @@
 use crate::common::*;
 use crate::expressions::{MutExprVisitor, Operand, Place, Rvalue};
 use crate::formatter::{AstFormatter, Formatter};
-use crate::llbc_ast::{Assert, FunDecl, GlobalDecl, RawStatement, Statement, Switch};
+use crate::llbc_ast::{
+    Assert, ForLoop, FunDecl, GlobalDecl, QuestionMark, RawStatement, Statement, Switch,
+};
 use crate::meta;
 use crate::meta::Meta;
 use crate::types::*;
 pub use crate::ullbc_ast::fmt_call;
 use crate::values::*;
 use macros::make_generic_in_borrows;
-use std::ops::DerefMut;
 use take_mut::take;
 
-/// Goes from e.g. `(A; B; C) ; D` to `(A; (B; (C; D)))`.
-pub fn chain_statements(firsts: Vec<Statement>, last: Statement) -> Statement {
-    firsts.into_iter().rev().fold(last, |cont, bind| {
-        assert!(!bind.content.is_sequence());
-        let meta = meta::combine_meta(&bind.meta, &cont.meta);
-        Statement::new(meta, RawStatement::Sequence(Box::new(bind), Box::new(cont)))
-    })
+/// Builds a statement from a list of statements executed in sequence, by
+/// flattening the nested blocks (see [RawStatement::Block]). If there is only
+/// one statement, we return it directly, and if there are none we return a
+/// [RawStatement::Nop].
+pub fn new_block(meta: Meta, statements: Vec<Statement>) -> Statement {
+    let mut flattened = Vec::with_capacity(statements.len());
+    for st in statements {
+        match st.content {
+            RawStatement::Block(sts) => flattened.extend(sts),
+            content => flattened.push(Statement::new(st.meta, content)),
+        }
+    }
+    match flattened.len() {
+        0 => Statement::new(meta, RawStatement::Nop),
+        1 => flattened.pop().unwrap(),
+        _ => Statement::new(meta, RawStatement::Block(flattened)),
+    }
 }
 
-/// Utility function for [new_sequence].
-/// Efficiently appends a new statement at the rightmost place of a well-formed sequence.
-fn append_rightmost(seq: &mut Statement, r: Box<Statement>) {
-    let (_l1, l2) = match &mut seq.content {
-        RawStatement::Sequence(l1, l2) => (l1, l2),
-        _ => unreachable!(),
-    };
-    if l2.content.is_sequence() {
-        append_rightmost(l2, r);
-    } else {
-        take(l2.deref_mut(), move |l2| {
-            let meta = meta::combine_meta(&l2.meta, &r.meta);
-            Statement::new(meta, RawStatement::Sequence(Box::new(l2), r))
-        });
-    }
+/// Goes from e.g. `{ A; B; C }` and `D` to `{ A; B; C; D }`.
+pub fn chain_statements(mut firsts: Vec<Statement>, last: Statement) -> Statement {
+    let meta = meta::combine_meta_iter(firsts.iter().map(|st| &st.meta).chain([&last.meta]));
+    firsts.push(last);
+    new_block(meta, firsts)
 }
 
-/// Builds a sequence from well-formed statements.
-/// Ensures that the left statement will not be a sequence in the new sequence:
-/// Must be used instead of the raw [RawStatement::Sequence] constructor,
-/// unless you're sure that the left statement is not a sequence.
-pub fn new_sequence(mut l: Statement, r: Statement) -> Statement {
+/// Builds a sequence from two statements, making sure the blocks are
+/// well-formed (i.e., flattened). Must be used instead of the raw
+/// [RawStatement::Block] constructor, unless you're sure that the statements
+/// are not blocks.
+pub fn new_sequence(l: Statement, r: Statement) -> Statement {
     let meta = meta::combine_meta(&l.meta, &r.meta);
-
-    let r = Box::new(r);
-    let nst = match l.content {
-        RawStatement::Sequence(_, _) => {
-            append_rightmost(&mut l, r);
-            l.content
-        }
-        lc => RawStatement::Sequence(Box::new(Statement::new(l.meta, lc)), r),
-    };
-
-    Statement::new(meta, nst)
+    new_block(meta, vec![l, r])
 }
 
 /// Combine the meta information from a [Switch]
@@ -136,10 +127,11 @@ impl Statement {
                 format!("{}drop {}", tab, place.fmt_with_ctx(ctx))
             }
             RawStatement::Assert(assert) => format!(
-                "{}assert({} == {})",
+                "{}assert({} == {}, {})",
                 tab,
                 assert.cond.fmt_with_ctx(ctx),
                 assert.expected,
+                assert.kind.fmt_with_ctx(ctx),
             ),
             RawStatement::Call(call) => {
                 let (call_s, _) = fmt_call(ctx, call);
@@ -147,14 +139,16 @@ impl Statement {
             }
             RawStatement::Panic => format!("{tab}panic"),
             RawStatement::Return => format!("{tab}return"),
-            RawStatement::Break(index) => format!("{tab}break {index}"),
-            RawStatement::Continue(index) => format!("{tab}continue {index}"),
+            RawStatement::Break(_, Some(label)) => format!("{tab}break 'l{label}"),
+            RawStatement::Break(index, None) => format!("{tab}break {index}"),
+            RawStatement::Continue(_, Some(label)) => format!("{tab}continue 'l{label}"),
+            RawStatement::Continue(index, None) => format!("{tab}continue {index}"),
             RawStatement::Nop => format!("{tab}nop"),
-            RawStatement::Sequence(st1, st2) => format!(
-                "{}\n{}",
-                st1.fmt_with_ctx(tab, ctx),
-                st2.fmt_with_ctx(tab, ctx)
-            ),
+            RawStatement::Block(sts) => sts
+                .iter()
+                .map(|st| st.fmt_with_ctx(tab, ctx))
+                .collect::<Vec<String>>()
+                .join("\n"),
             RawStatement::Switch(switch) => match switch {
                 Switch::If(discr, true_st, false_st) => {
                     let inner_tab = format!("{tab}{TAB_INCR}");
@@ -238,15 +232,54 @@ impl Statement {
                     )
                 }
             },
-            RawStatement::Loop(body) => {
+            RawStatement::Loop(body, label) => {
                 let inner_tab = format!("{tab}{TAB_INCR}");
+                let label = match label {
+                    Some(label) => format!("'l{label}: "),
+                    None => "".to_string(),
+                };
                 format!(
-                    "{}loop {{\n{}\n{}}}",
+                    "{}{}loop {{\n{}\n{}}}",
                     tab,
+                    label,
                     body.fmt_with_ctx(&inner_tab, ctx),
                     tab
                 )
             }
+            RawStatement::ForLoop(lp) => {
+                let inner_tab = format!("{tab}{TAB_INCR}");
+                let label = match lp.label {
+                    Some(label) => format!("'l{label}: "),
+                    None => "".to_string(),
+                };
+                format!(
+                    "{}{}for {} in ({} := {}({})) {{\n{}\n{}}}",
+                    tab,
+                    label,
+                    lp.binder
+                        .as_ref()
+                        .map_or("_".to_string(), |binder| binder.fmt_with_ctx(ctx)),
+                    ctx.format_object(lp.iter_var),
+                    lp.into_iter.fmt_with_ctx(ctx),
+                    lp.iter.fmt_with_ctx(ctx),
+                    lp.body.fmt_with_ctx(&inner_tab, ctx),
+                    tab
+                )
+            }
+            RawStatement::QuestionMark(qm) => {
+                let dest = match &qm.dest {
+                    Some(dest) => format!("{} := ", dest.fmt_with_ctx(ctx)),
+                    None => "".to_string(),
+                };
+                format!(
+                    "{}{}{}({})? (from_residual: {})",
+                    tab,
+                    dest,
+                    qm.branch.fmt_with_ctx(ctx),
+                    qm.op.fmt_with_ctx(ctx),
+                    qm.from_residual.fmt_with_ctx(ctx)
+                )
+            }
         }
     }
 }
@@ -329,16 +362,18 @@ pub trait AstVisitor: crate::expressions::ExprVisitor {
                 self.visit_panic();
             }
             RawStatement::Return => self.visit_return(),
-            RawStatement::Break(i) => {
+            RawStatement::Break(i, _) => {
                 self.visit_break(i);
             }
-            RawStatement::Continue(i) => {
+            RawStatement::Continue(i, _) => {
                 self.visit_continue(i);
             }
             RawStatement::Nop => self.visit_nop(),
-            RawStatement::Sequence(st1, st2) => self.visit_sequence(st1, st2),
+            RawStatement::Block(sts) => self.visit_block(sts),
             RawStatement::Switch(s) => self.visit_switch(s),
-            RawStatement::Loop(lp) => self.visit_loop(lp),
+            RawStatement::Loop(lp, _) => self.visit_loop(lp),
+            RawStatement::ForLoop(lp) => self.visit_for_loop(lp),
+            RawStatement::QuestionMark(qm) => self.visit_question_mark(qm),
         }
     }
 
@@ -365,6 +400,7 @@ pub trait AstVisitor: crate::expressions::ExprVisitor {
 
     fn visit_assert(&mut self, a: &Assert) {
         self.visit_operand(&a.cond);
+        self.visit_assert_kind(&a.kind);
     }
 
     fn visit_panic(&mut self) {}
@@ -373,9 +409,10 @@ pub trait AstVisitor: crate::expressions::ExprVisitor {
     fn visit_continue(&mut self, _: &usize) {}
     fn visit_nop(&mut self) {}
 
-    fn visit_sequence(&mut self, st1: &Statement, st2: &Statement) {
-        self.visit_statement(st1);
-        self.visit_statement(st2);
+    fn visit_block(&mut self, sts: &Vec<Statement>) {
+        for st in sts {
+            self.visit_statement(st);
+        }
     }
 
     fn default_visit_switch(&mut self, s: &Switch) {
@@ -407,7 +444,7 @@ pub trait AstVisitor: crate::expressions::ExprVisitor {
         &mut self,
         scrut: &Operand,
         _: &IntegerTy,
-        branches: &Vec<(Vec<ScalarValue>, Statement)>,
+        branches: &Vec<(Vec<SwitchValue>, Statement)>,
         otherwise: &Statement,
     ) {
         self.visit_operand(scrut);
@@ -437,6 +474,27 @@ pub trait AstVisitor: crate::expressions::ExprVisitor {
     fn visit_loop(&mut self, lp: &Statement) {
         self.visit_statement(lp)
     }
+
+    fn visit_for_loop(&mut self, lp: &ForLoop) {
+        self.visit_operand(&lp.iter);
+        self.visit_var_id(&lp.iter_var);
+        self.visit_fn_ptr(&lp.into_iter);
+        self.visit_fn_ptr(&lp.next);
+        if let Some(binder) = &lp.binder {
+            self.visit_place(binder);
+        }
+        // The body is executed in a loop
+        self.visit_loop(&lp.body)
+    }
+
+    fn visit_question_mark(&mut self, qm: &QuestionMark) {
+        self.visit_operand(&qm.op);
+        self.visit_fn_ptr(&qm.branch);
+        self.visit_fn_ptr(&qm.from_residual);
+        if let Some(dest) = &qm.dest {
+            self.visit_place(dest);
+        }
+    }
 }
 
 } // make_generic_in_borrows
@@ -460,17 +518,18 @@ impl<'a, F: FnMut(&mut Statement) -> Option<Vec<Statement>>> MutAstVisitor
 {
     fn visit_statement(&mut self, st: &mut Statement) {
         match &mut st.content {
-            RawStatement::Sequence(st1, st2) => {
-                // Bottom-up
-                self.visit_statement(st2);
-                self.default_visit_raw_statement(&mut st1.content);
-
-                // Transform the current statement
-                let st_seq = (self.tr)(st1);
-                if let Some(seq) = st_seq && !seq.is_empty() {
-                    take(st, |st| chain_statements(seq, st))
+            RawStatement::Block(sts) => {
+                // Bottom-up, starting with the last statement
+                for st in sts.iter_mut().rev() {
+                    self.visit_statement(st);
                 }
-                // TODO: we might want to apply tr to the whole resulting sequence
+
+                // The transformer may have introduced statements, or
+                // transformed some statements into blocks: flatten the block
+                take(st, |st| {
+                    let Statement { meta, content } = st;
+                    new_block(meta, content.to_block())
+                })
             }
             _ => {
                 // Bottom-up
@@ -492,6 +551,36 @@ impl<'a, F: FnMut(&mut Statement) -> Option<Vec<Statement>>> MutAstVisitor
     fn merge(&mut self) {}
 }
 
+/// Helper for [Statement::transform_blocks]
+struct TransformBlocks<'a, F: FnMut(&mut Vec<Statement>)> {
+    tr: &'a mut F,
+}
+
+impl<'a, F: FnMut(&mut Vec<Statement>)> MutTypeVisitor for TransformBlocks<'a, F> {}
+impl<'a, F: FnMut(&mut Vec<Statement>)> MutExprVisitor for TransformBlocks<'a, F> {}
+
+impl<'a, F: FnMut(&mut Vec<Statement>)> MutAstVisitor for TransformBlocks<'a, F> {
+    fn visit_statement(&mut self, st: &mut Statement) {
+        // Bottom-up
+        self.default_visit_raw_statement(&mut st.content);
+
+        // Transform the current block
+        if let RawStatement::Block(sts) = &mut st.content {
+            (self.tr)(sts);
+            take(st, |st| {
+                let Statement { meta, content } = st;
+                new_block(meta, content.to_block())
+            })
+        }
+    }
+
+    fn spawn(&mut self, visitor: &mut dyn FnMut(&mut Self)) {
+        visitor(self)
+    }
+
+    fn merge(&mut self) {}
+}
+
 impl Statement {
     /// Apply a transformer to all the statements, in a bottom-up manner.
     ///
@@ -499,13 +588,27 @@ impl Statement {
     /// - mutate the current statement in place
     /// - return the sequence of statements to introduce before the current statement
     ///
-    /// We do the transformation in such a way that the
-    /// sequences of statements are properly chained. In particuliar,
-    /// if in `s1; s2` we transform `s1` to the sequence `s1_1; s1_2`,
-    /// then the resulting statement is `s1_1; s1_2; s2` and **not**
-    /// `{ s1_1; s1_2 }; s2`.
+    /// We do the transformation in such a way that the blocks are properly
+    /// flattened. In particuliar, if in `{ s1; s2 }` we transform `s1` to the
+    /// sequence `s1_1; s1_2`, then the resulting statement is
+    /// `{ s1_1; s1_2; s2 }` and **not** `{ { s1_1; s1_2 }; s2 }`.
+    ///
+    /// Note that the transformer is never applied to blocks: see
+    /// [Statement::transform_blocks] for the transformations which need to
+    /// look at several statements in a row.
     pub fn transform<F: FnMut(&mut Statement) -> Option<Vec<Statement>>>(&mut self, f: &mut F) {
         let mut visitor = TransformStatements { tr: f };
         visitor.visit_statement(self);
     }
+
+    /// Apply a transformer to the lists of statements of all the blocks, in a
+    /// bottom-up manner.
+    ///
+    /// The transformer can freely update the statements of a block: we flatten
+    /// the resulting block afterwards (if the block ends up empty, it is
+    /// replaced with a [RawStatement::Nop]).
+    pub fn transform_blocks<F: FnMut(&mut Vec<Statement>)>(&mut self, f: &mut F) {
+        let mut visitor = TransformBlocks { tr: f };
+        visitor.visit_statement(self);
+    }
 }
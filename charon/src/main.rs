@@ -37,10 +37,10 @@ extern crate rustc_tools_util;
 mod cli_options;
 mod logger;
 
-use cli_options::{CliOpts, CHARON_ARGS};
+use cli_options::{CliOpts, Command as CharonCommand, MergeOpts, BATCH_INDEX, CHARON_ARGS};
 use log::trace;
 use std::env;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 use structopt::StructOpt;
 
@@ -51,7 +51,7 @@ pub fn main() {
     logger::initialize_logger();
 
     // Parse the command-line
-    let options = CliOpts::from_args();
+    let mut options = CliOpts::from_args();
     trace!("Arguments: {:?}", std::env::args());
 
     // Check that the options are meaningful
@@ -70,7 +70,22 @@ pub fn main() {
         "Can't use --abort-on-error and --errors-as-warnings at the same time"
     );
 
-    if let Err(code) = process(&options) {
+    assert!(
+        options.batch.is_empty() || (options.crate_name.is_none() && options.input_file.is_none()),
+        "Can't use --batch together with --crate or --input"
+    );
+
+    assert!(
+        !options.batch_merge || !options.batch.is_empty(),
+        "Can't use --batch-merge without --batch"
+    );
+
+    let res = match &options.command {
+        None if !options.batch.is_empty() => process_batch(&mut options),
+        None => process(&options, None),
+        Some(_) => run_command(&options),
+    };
+    if let Err(code) = res {
         std::process::exit(code);
     }
 }
@@ -87,7 +102,72 @@ fn path() -> PathBuf {
     path
 }
 
-fn process(options: &CliOpts) -> Result<(), i32> {
+/// Run a subcommand (see [cli_options::Command]).
+///
+/// The subcommands don't need to call Cargo: we directly call charon-driver,
+/// which will detect the subcommand in the options and execute it. We need to
+/// do so because the subcommands use the Charon library, which depends on the
+/// Rustc libraries (only charon-driver links with them).
+fn run_command(options: &CliOpts) -> Result<(), i32> {
+    let mut cmd = if options.cargo_no_rust_version {
+        Command::new(path())
+    } else {
+        // Use Rustup to call charon-driver with the proper toolchain (so that
+        // it finds the Rustc libraries).
+        let mut cmd = Command::new("rustup");
+        cmd.arg("run");
+        cmd.arg(RUST_VERSION.trim_start_matches('+'));
+        cmd.arg(path());
+        cmd
+    };
+    cmd.env(CHARON_ARGS, serde_json::to_string(&options).unwrap());
+
+    let exit_status = cmd
+        .spawn()
+        .expect("could not run charon-driver")
+        .wait()
+        .expect("failed to wait for charon-driver?");
+
+    if exit_status.success() {
+        Ok(())
+    } else {
+        Err(exit_status.code().unwrap_or(-1))
+    }
+}
+
+/// Extract the crates given with `--batch`, in order, while sharing a batch
+/// index (see [cli_options::CliOpts::batch]).
+fn process_batch(options: &mut CliOpts) -> Result<(), i32> {
+    // We call Cargo from the roots of the crates: the paths must be absolute
+    let current_dir = env::current_dir().expect("invalid current directory");
+    let dest_dir = current_dir.join(options.dest_dir.clone().unwrap_or_default());
+    let index = dest_dir.join(BATCH_INDEX);
+    options.dest_dir = Some(dest_dir);
+    options.batch_index = Some(index.clone());
+
+    // Start from a fresh index
+    if index.exists() {
+        std::fs::remove_file(&index).expect("could not remove the previous batch index");
+    }
+
+    for root in options.batch.clone() {
+        process(options, Some(&current_dir.join(root)))?;
+    }
+
+    // Merge the generated files, if necessary
+    if options.batch_merge {
+        options.command = Some(CharonCommand::Merge(MergeOpts {
+            index,
+            name: "batch".to_string(),
+        }));
+        run_command(options)?;
+    }
+    Ok(())
+}
+
+/// Call Cargo to extract a crate. If a directory is given, we call Cargo from
+/// there (otherwise, we use the current directory).
+fn process(options: &CliOpts, root: Option<&Path>) -> Result<(), i32> {
     // Compute the arguments of the command to call cargo
     //let cargo_subcommand = "build";
     let cargo_subcommand = "rustc";
@@ -95,6 +175,9 @@ fn process(options: &CliOpts) -> Result<(), i32> {
     let rust_version = RUST_VERSION;
 
     let mut cmd = Command::new("cargo");
+    if let Some(root) = root {
+        cmd.current_dir(root);
+    }
     cmd.env("RUSTC_WORKSPACE_WRAPPER", path());
     cmd.env(CHARON_ARGS, serde_json::to_string(&options).unwrap());
 
@@ -113,6 +196,11 @@ fn process(options: &CliOpts) -> Result<(), i32> {
         cmd.arg(options.bin.as_ref().unwrap().clone());
     }
 
+    if let Some(target) = &options.target {
+        cmd.arg("--target");
+        cmd.arg(target);
+    }
+
     // Always compile in release mode: in effect, we want to analyze the released
     // code. Also, rustc inserts a lot of dynamic checks in debug mode, that we
     // have to clean.
@@ -0,0 +1,119 @@
+//! Generate a manifest describing an extraction (the input crate, the
+//! toolchain, the options, the micro-passes we applied, the hash of the
+//! generated file, etc.) and export it next to the generated `.llbc` (or
+//! `.ullbc`) file. The goal is to allow auditing the verification results,
+//! and reproducing the extraction bit-for-bit later.
+//!
+//! Remark: we compute the hashes with the [DefaultHasher] of the standard
+//! library. Its algorithm is not guaranteed to be stable across releases of
+//! Rust, but as the toolchain is pinned (and recorded in the manifest), this is
+//! enough to check that a file was not modified.
+
+use crate::cli_options::CliOpts;
+use crate::meta::FileName;
+use crate::target::TargetInfo;
+use crate::translate_ctx::TransCtx;
+use rustc_session::Session;
+use serde::Serialize;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::Hasher;
+use std::path::{Path, PathBuf};
+
+/// The manifest of an extraction.
+#[derive(Serialize)]
+pub struct Manifest<'a> {
+    /// The version of Charon
+    pub charon_version: &'static str,
+    /// The version of the Rust compiler used for the extraction
+    pub rustc_version: &'static str,
+    /// The target triple
+    pub target: String,
+    /// The information about the target the extraction assumed (the width
+    /// of the pointers, etc.)
+    pub target_info: TargetInfo,
+    pub crate_name: String,
+    /// The hash of the source files of the crate, sorted by path.
+    /// We only consider the local files (i.e., not the files of the standard
+    /// library).
+    pub crate_hash: String,
+    /// The options given to Charon
+    pub options: &'a CliOpts,
+    /// The list of micro-passes applied to the crate, in order
+    pub passes: &'a [&'static str],
+    /// The name of the generated file
+    pub output_file: String,
+    /// The hash of the generated file
+    pub output_hash: String,
+}
+
+/// Hash a file. Return `None` if we couldn't read it.
+fn hash_file(path: &Path) -> Option<u64> {
+    let content = std::fs::read(path).ok()?;
+    let mut hasher = DefaultHasher::new();
+    hasher.write(&content);
+    Some(hasher.finish())
+}
+
+/// Compute the hash of the local source files of the crate.
+fn compute_crate_hash(ctx: &TransCtx) -> String {
+    let mut files: Vec<&String> = ctx
+        .id_to_file
+        .values()
+        .filter_map(|file| match file {
+            FileName::Local(path) => Some(path),
+            FileName::Virtual(_) | FileName::NotReal(_) => None,
+        })
+        .collect();
+    files.sort();
+
+    let mut hasher = DefaultHasher::new();
+    for file in files {
+        hasher.write(file.as_bytes());
+        match hash_file(Path::new(file)) {
+            Some(hash) => hasher.write_u64(hash),
+            None => log::warn!("Could not read the source file: {}", file),
+        }
+    }
+    format!("{:016x}", hasher.finish())
+}
+
+/// Compute the manifest of the extraction (we export it to
+/// `CRATE.manifest.json`).
+///
+/// This must be called after we exported the crate (we need to read the
+/// generated file to compute its hash).
+#[allow(clippy::result_unit_err)]
+pub fn compute<'a>(
+    ctx: &TransCtx,
+    sess: &Session,
+    crate_name: &str,
+    options: &'a CliOpts,
+    passes: &'a [&'static str],
+    extension: &str,
+) -> Result<Manifest<'a>, ()> {
+    let dest_dir = options
+        .dest_dir
+        .as_deref()
+        .map_or_else(PathBuf::new, |d| d.to_path_buf());
+    let output_file = format!("{crate_name}.{extension}");
+    let output_hash = match hash_file(&dest_dir.join(&output_file)) {
+        Some(hash) => format!("{hash:016x}"),
+        None => {
+            error!("Could not read the generated file: {}", output_file);
+            return Err(());
+        }
+    };
+
+    Ok(Manifest {
+        charon_version: env!("CARGO_PKG_VERSION"),
+        rustc_version: sess.cfg_version,
+        target: sess.opts.target_triple.to_string(),
+        target_info: ctx.target_info,
+        crate_name: crate_name.to_string(),
+        crate_hash: compute_crate_hash(ctx),
+        options,
+        passes,
+        output_file,
+        output_hash,
+    })
+}
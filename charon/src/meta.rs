@@ -2,17 +2,29 @@
 
 pub use crate::meta_utils::*;
 use macros::{generate_index_type, EnumAsGetters, EnumIsA};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 generate_index_type!(LocalFileId);
 generate_index_type!(VirtualFileId);
+generate_index_type!(BacktraceId);
 
 #[allow(non_snake_case)]
 pub mod FileId {
     use crate::meta::*;
 
     #[derive(
-        Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord, EnumIsA, EnumAsGetters, Serialize,
+        Debug,
+        Clone,
+        Copy,
+        Hash,
+        PartialEq,
+        Eq,
+        PartialOrd,
+        Ord,
+        EnumIsA,
+        EnumAsGetters,
+        Serialize,
+        Deserialize,
     )]
     pub enum Id {
         LocalId(LocalFileId::Id),
@@ -20,16 +32,20 @@ pub mod FileId {
     }
 }
 
-#[derive(Debug, Copy, Clone, Serialize)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct Loc {
     /// The (1-based) line number.
     pub line: usize,
     /// The (0-based) column offset.
     pub col: usize,
+    /// The (0-based) byte offset from the beginning of the file. Together with
+    /// the column, it allows mapping the spans back onto the source text
+    /// without recomputing the line breaks.
+    pub offset: usize,
 }
 
 /// Span information
-#[derive(Debug, Copy, Clone, Serialize)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct Span {
     pub file_id: FileId::Id,
     pub beg: Loc,
@@ -41,7 +57,7 @@ pub struct Span {
 }
 
 /// Meta information about a piece of code (block, statement, etc.)
-#[derive(Debug, Copy, Clone, Serialize)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Meta {
     /// The source code span.
     ///
@@ -65,13 +81,56 @@ pub struct Meta {
     pub span: Span,
     /// Where the code actually comes from, in case of macro expansion/inlining/etc.
     pub generated_from_span: Option<Span>,
+    /// The full macro backtrace, in case of nested macro expansions/inlinings:
+    /// the call sites of the macros the code was expanded from, followed by
+    /// the spans of the scopes it was inlined from, from the innermost to the
+    /// outermost. This allows pointing at both the macro definitions and the
+    /// call sites when reporting errors on generated code.
+    ///
+    /// As we want [Meta] to be [Copy], we don't store the backtrace here, but
+    /// in the body the code belongs to (see [crate::gast::GExprBody::backtraces]).
+    pub backtrace: Option<BacktraceId::Id>,
+}
+
+/// An attribute of an item that we keep (see [AttrInfo]).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Attribute {
+    /// `#[inline]`, `#[inline(always)]` or `#[inline(never)]`
+    Inline(Option<String>),
+    /// `#[must_use]`, with the optional message
+    MustUse(Option<String>),
+    /// `#[link_name = "..."]`, which gives the symbol of an item of an
+    /// `extern` block
+    LinkName(String),
+    /// `#[no_mangle]`
+    NoMangle,
+    /// A tool attribute, ex.: `#[verify::requires(x > 0)]`. We keep the path
+    /// (`verify::requires`) and the arguments (`x > 0`) as strings.
+    ///
+    /// We ignore the attributes of `rustfmt` and `clippy`.
+    Tool { path: String, args: Option<String> },
+}
+
+/// The information the user gave about an item through its doc comments and
+/// its attributes. Verification frontends can use it to retrieve annotations
+/// (pre and post-conditions, etc.).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AttrInfo {
+    /// The lines of the doc comments, in order (`/// Abc` gives ` Abc`)
+    pub doc_comments: Vec<String>,
+    pub attributes: Vec<Attribute>,
+    /// The exact source text of the item, if the user asked for it (see
+    /// [crate::cli_options::CliOpts::emit_source]). The text starts at the
+    /// beginning of the span of the item: the byte offsets of the spans of
+    /// its body allow retrieving their text without the original sources.
+    pub source_text: Option<String>,
 }
 
-#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
 pub struct FileInfo {}
 
 /// A filename.
-#[derive(Debug, PartialEq, Eq, Clone, Hash, PartialOrd, Ord, Serialize)]
+#[derive(Debug, PartialEq, Eq, Clone, Hash, PartialOrd, Ord, Serialize, Deserialize)]
 pub enum FileName {
     /// A remapped path (namely paths into stdlib)
     Virtual(String),
@@ -1,4 +1,5 @@
 //! This file groups everything which is linked to implementations about [crate::meta]
+use crate::cli_options::PathPrefix;
 use crate::meta::*;
 use hax_frontend_exporter as hax;
 use rustc_hir::def_id::DefId;
@@ -15,13 +16,91 @@ pub fn get_rspan_from_def_id(ctx: TyCtxt, def_id: DefId) -> rustc_span::Span {
     ctx.def_span(def_id)
 }
 
+/// Retrieve the source text of a definition. Contrary to [get_rspan_from_def_id],
+/// we take the whole definition into account (including the body of the
+/// functions). Note that the source text of the external definitions is not
+/// always available.
+pub fn get_source_text_from_def_id(sess: &Session, ctx: TyCtxt, def_id: DefId) -> Option<String> {
+    let span = match def_id.as_local() {
+        Some(local_id) => ctx
+            .hir()
+            .span_with_body(ctx.hir().local_def_id_to_hir_id(local_id)),
+        None => ctx.def_span(def_id),
+    };
+    sess.source_map().span_to_snippet(span).ok()
+}
+
+/// The tools whose attributes we ignore (see [Attribute::Tool]).
+const IGNORED_TOOLS: [&str; 2] = ["rustfmt", "clippy"];
+
+/// Translate an attribute, if it is one of the attributes we keep (see
+/// [Attribute]).
+fn translate_attribute(attr: &rustc_ast::Attribute) -> Option<Attribute> {
+    use rustc_ast_pretty::pprust;
+    use rustc_span::symbol::sym;
+
+    if attr.has_name(sym::inline) {
+        let arg = attr
+            .meta_item_list()
+            .and_then(|items| items.first().map(|item| item.name_or_empty().to_string()));
+        return Some(Attribute::Inline(arg));
+    }
+    if attr.has_name(sym::must_use) {
+        return Some(Attribute::MustUse(attr.value_str().map(|s| s.to_string())));
+    }
+    if attr.has_name(sym::link_name) {
+        return attr.value_str().map(|s| Attribute::LinkName(s.to_string()));
+    }
+    if attr.has_name(sym::no_mangle) {
+        return Some(Attribute::NoMangle);
+    }
+
+    // Tool attributes: their paths have several segments
+    let item = match &attr.kind {
+        rustc_ast::AttrKind::Normal(normal) => &normal.item,
+        rustc_ast::AttrKind::DocComment(..) => return None,
+    };
+    let segments = &item.path.segments;
+    if segments.len() < 2 || IGNORED_TOOLS.contains(&segments[0].ident.as_str()) {
+        return None;
+    }
+    let tokens = item.args.inner_tokens();
+    let args = if tokens.is_empty() {
+        None
+    } else {
+        Some(pprust::tts_to_string(&tokens))
+    };
+    Some(Attribute::Tool {
+        path: pprust::path_to_string(&item.path),
+        args,
+    })
+}
+
+/// Retrieve the doc comments and the attributes we keep from a def id.
+pub fn get_attr_info_from_def_id(ctx: TyCtxt, def_id: DefId) -> AttrInfo {
+    let mut info = AttrInfo::default();
+    for attr in ctx.get_attrs_unchecked(def_id) {
+        // This handles both the `///` comments and the `#[doc = "..."]`
+        // attributes
+        if let Some(doc) = attr.doc_str() {
+            info.doc_comments.push(doc.to_string());
+        } else if let Some(attr) = translate_attribute(attr) {
+            info.attributes.push(attr);
+        }
+    }
+    info
+}
+
 impl Loc {
     fn min(l0: &Loc, l1: &Loc) -> Loc {
         match l0.line.cmp(&l1.line) {
-            Ordering::Equal => Loc {
-                line: l0.line,
-                col: std::cmp::min(l0.col, l1.col),
-            },
+            Ordering::Equal => {
+                if l0.col <= l1.col {
+                    *l0
+                } else {
+                    *l1
+                }
+            }
             Ordering::Less => *l0,
             Ordering::Greater => *l1,
         }
@@ -29,10 +108,13 @@ impl Loc {
 
     fn max(l0: &Loc, l1: &Loc) -> Loc {
         match l0.line.cmp(&l1.line) {
-            Ordering::Equal => Loc {
-                line: l0.line,
-                col: std::cmp::max(l0.col, l1.col),
-            },
+            Ordering::Equal => {
+                if l0.col >= l1.col {
+                    *l0
+                } else {
+                    *l1
+                }
+            }
             Ordering::Greater => *l0,
             Ordering::Less => *l1,
         }
@@ -51,12 +133,13 @@ pub fn combine_meta(m0: &Meta, m1: &Meta) -> Meta {
             rust_span: m0.span.rust_span.to(m1.span.rust_span),
         };
 
-        // We don't attempt to merge the "generated from" spans: they might
-        // come from different files, and even if they come from the same files
-        // they might come from different macros, etc.
+        // We don't attempt to merge the "generated from" spans and the
+        // backtraces: they might come from different files, and even if they
+        // come from the same files they might come from different macros, etc.
         Meta {
             span,
             generated_from_span: None,
+            backtrace: None,
         }
     } else {
         // It happens that the spans don't come from the same file. In this
@@ -108,10 +191,37 @@ pub fn convert_filename(name: &hax::FileName) -> FileName {
     }
 }
 
-pub fn convert_loc(loc: hax::Loc) -> Loc {
+/// Convert a location. The hax locations don't contain the byte offsets: we
+/// compute them from the Rust position.
+pub fn convert_loc(sess: &Session, loc: hax::Loc, pos: rustc_span::BytePos) -> Loc {
+    let offset = sess.source_map().lookup_byte_offset(pos).pos.0 as usize;
     Loc {
         line: loc.line,
         col: loc.col,
+        offset,
+    }
+}
+
+/// Rewrite a filename with the path prefix map (see
+/// [crate::cli_options::CliOpts::path_prefix_map]). As with the
+/// `--remap-path-prefix` option of rustc, if several prefixes match, the last
+/// one wins.
+pub fn remap_filename(map: &[PathPrefix], name: FileName) -> FileName {
+    let remap = |path: String| {
+        let p = std::path::Path::new(&path);
+        let remapped = map
+            .iter()
+            .rev()
+            .find_map(|m| Some(m.to.join(p.strip_prefix(&m.from).ok()?)));
+        match remapped {
+            Some(remapped) => remapped.to_string_lossy().into_owned(),
+            None => path,
+        }
+    };
+    match name {
+        FileName::Local(path) => FileName::Local(remap(path)),
+        FileName::Virtual(path) => FileName::Virtual(remap(path)),
+        FileName::NotReal(_) => name,
     }
 }
 
@@ -0,0 +1,151 @@
+//! Compute metrics about the function bodies (number of statements, of loops,
+//! etc.) and export them to a JSON file. When working on a large crate, this
+//! allows the users to sort the functions by complexity, for instance to
+//! decide which ones to verify first.
+
+use crate::expressions::SharedExprVisitor;
+use crate::formatter::IntoFormatter;
+use crate::llbc_ast;
+use crate::translate_ctx::TransCtx;
+use crate::types::*;
+use crate::ullbc_ast::{FunDeclId, RawTerminator, SharedAstVisitor};
+use serde::Serialize;
+
+/// The metrics of a function.
+#[derive(Debug, Clone, Serialize)]
+pub struct FunMetrics {
+    pub def_id: FunDeclId::Id,
+    pub name: String,
+    /// The number of statements, including the terminators of the blocks
+    /// (computed on the ULLBC).
+    pub statements: usize,
+    /// The number of blocks (computed on the ULLBC).
+    pub blocks: usize,
+    /// The number of loops. We can only compute it if we reconstructed the
+    /// control-flow (i.e., if we generate LLBC).
+    pub loops: Option<usize>,
+    /// The maximum nesting depth of the branchings and the loops. We can only
+    /// compute it if we reconstructed the control-flow (i.e., if we generate LLBC).
+    pub max_nesting_depth: Option<usize>,
+    /// The number of function calls.
+    pub calls: usize,
+    /// The number of trait instances we failed to resolve (i.e., the number of
+    /// occurrences of [TraitInstanceId::Unknown], in the signature and the body).
+    pub unresolved_traits: usize,
+}
+
+/// Counts the unresolved trait instances.
+struct UnresolvedTraitsCounter {
+    count: usize,
+}
+
+impl SharedTypeVisitor for UnresolvedTraitsCounter {
+    fn visit_trait_instance_id(&mut self, id: &TraitInstanceId) {
+        if let TraitInstanceId::Unknown(_) = id {
+            self.count += 1;
+        }
+        self.default_visit_trait_instance_id(id)
+    }
+}
+
+impl SharedExprVisitor for UnresolvedTraitsCounter {}
+impl SharedAstVisitor for UnresolvedTraitsCounter {}
+
+/// Computes the number of loops and the maximum nesting depth of an LLBC
+/// statement.
+struct LoopsAndDepth {
+    loops: usize,
+    /// The nesting depth of the statement we are currently exploring
+    depth: usize,
+    max_depth: usize,
+}
+
+impl LoopsAndDepth {
+    fn enter(&mut self) {
+        self.depth += 1;
+        self.max_depth = self.max_depth.max(self.depth);
+    }
+}
+
+impl SharedTypeVisitor for LoopsAndDepth {}
+impl SharedExprVisitor for LoopsAndDepth {}
+
+impl llbc_ast::SharedAstVisitor for LoopsAndDepth {
+    fn spawn(&mut self, visitor: &mut dyn FnMut(&mut Self)) {
+        visitor(self)
+    }
+
+    fn merge(&mut self) {}
+
+    fn visit_switch(&mut self, s: &llbc_ast::Switch) {
+        self.enter();
+        self.default_visit_switch(s);
+        self.depth -= 1;
+    }
+
+    fn visit_loop(&mut self, lp: &llbc_ast::Statement) {
+        self.loops += 1;
+        self.enter();
+        self.visit_statement(lp);
+        self.depth -= 1;
+    }
+}
+
+/// Compute the metrics of all the functions which have a body.
+///
+/// The LLBC function definitions are optional: we use them if we reconstructed
+/// the control-flow.
+pub fn compute(ctx: &TransCtx, llbc_funs: Option<&llbc_ast::FunDecls>) -> Vec<FunMetrics> {
+    let fmt_ctx = ctx.into_fmt();
+    let mut metrics = Vec::new();
+    for decl in ctx.fun_decls.iter() {
+        let Some(body) = &decl.body else { continue };
+
+        let blocks = body.body.len();
+        let statements: usize = body
+            .body
+            .iter()
+            .map(|block| block.statements.len() + 1)
+            .sum();
+        let calls = body
+            .body
+            .iter()
+            .filter(|block| matches!(block.terminator.content, RawTerminator::Call { .. }))
+            .count();
+
+        let mut counter = UnresolvedTraitsCounter { count: 0 };
+        counter.visit_fun_sig(&decl.signature);
+        for block in &body.body {
+            counter.visit_block_data(block);
+        }
+
+        let (loops, max_nesting_depth) = match llbc_funs
+            .and_then(|funs| funs.get(decl.def_id))
+            .and_then(|decl| decl.body.as_ref())
+        {
+            None => (None, None),
+            Some(body) => {
+                use llbc_ast::SharedAstVisitor;
+                let mut visitor = LoopsAndDepth {
+                    loops: 0,
+                    depth: 0,
+                    max_depth: 0,
+                };
+                visitor.visit_statement(&body.body);
+                (Some(visitor.loops), Some(visitor.max_depth))
+            }
+        };
+
+        metrics.push(FunMetrics {
+            def_id: decl.def_id,
+            name: decl.name.fmt_with_ctx(&fmt_ctx),
+            statements,
+            blocks,
+            loops,
+            max_nesting_depth,
+            calls,
+            unresolved_traits: counter.count,
+        });
+    }
+    metrics
+}
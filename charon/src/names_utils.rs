@@ -2,7 +2,7 @@
 //!
 //! For now, we have one function per object kind (type, trait, function,
 //! module): many of them could be factorized (will do).
-use crate::formatter::AstFormatter;
+use crate::formatter::{AstFormatter, Formatter};
 use crate::names::*;
 use crate::translate_ctx::*;
 use hax_frontend_exporter as hax;
@@ -54,16 +54,13 @@ impl ImplElem {
 }
 
 impl Name {
+    /// Note that the way we print the names depends on the options of the
+    /// context (see [crate::formatter::NameFmtOptions]).
     pub fn fmt_with_ctx<C>(&self, ctx: &C) -> String
     where
         C: AstFormatter,
     {
-        let name = self
-            .name
-            .iter()
-            .map(|x| x.fmt_with_ctx(ctx))
-            .collect::<Vec<String>>();
-        name.join("::")
+        ctx.format_object(self)
     }
 }
 
@@ -259,6 +256,10 @@ impl<'tcx, 'ctx> TransCtx<'tcx, 'ctx> {
                     // instance to filter opaque modules.
                     name.push(PathElem::Ident(symbol.clone(), disambiguator));
                 }
+                ExtendedDefPathItem::ForeignMod => {
+                    // The `extern` blocks don't introduce a namespace: their
+                    // items are in the namespace of the enclosing module
+                }
                 ExtendedDefPathItem::ClosureExpr => {
                     // TODO: this is not very satisfactory, but on the other hand
                     // we should be able to extract closures in local let-bindings
@@ -334,6 +335,7 @@ impl<'tcx, 'ctx> TransCtx<'tcx, 'ctx> {
             | ItemKind::Const(_, _)
             | ItemKind::Static(_, _, _)
             | ItemKind::Macro(_, _)
+            | ItemKind::ForeignMod { .. }
             | ItemKind::Trait(..) => Option::Some(self.extended_def_id_to_name(&def_id)),
             _ => {
                 unimplemented!("{:?}", item.kind);
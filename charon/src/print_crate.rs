@@ -0,0 +1,115 @@
+//! Implementation of the `print` subcommand, which loads a `.llbc` (or
+//! `.ullbc`) file and pretty-prints (some of) its declarations, without
+//! re-running the extraction.
+
+use crate::cli_options::{NameOpts, PrintOpts};
+use crate::deserialize;
+use crate::deserialize::GCrate;
+use crate::formatter::{FmtCtx, Formatter, NameFmtOptions};
+use crate::gast::*;
+use crate::reorder_decls::DeclarationGroup;
+use regex::Regex;
+
+fn print_crate<T>(
+    krate: &GCrate<GFunDecl<T>, GGlobalDecl<T>>,
+    filter: Option<&Regex>,
+    names: &NameOpts,
+) where
+    for<'a, 'b> FmtCtx<'a>: Formatter<&'b GFunDecl<T>> + Formatter<&'b GGlobalDecl<T>>,
+{
+    // The formatting context expects ULLBC declarations: we only use them to
+    // print the names of the functions and globals, so we can simply remove
+    // the bodies.
+    let fun_decls = krate
+        .fun_decls
+        .iter()
+        .map(|d| (d.def_id, d.without_body()))
+        .collect();
+    let global_decls = krate
+        .global_decls
+        .iter()
+        .map(|d| (d.def_id, d.without_body()))
+        .collect();
+    let ctx = FmtCtx {
+        type_decls: Some(&krate.type_decls),
+        fun_decls: Some(&fun_decls),
+        global_decls: Some(&global_decls),
+        trait_decls: Some(&krate.trait_decls),
+        trait_impls: Some(&krate.trait_impls),
+        names: NameFmtOptions::new(names, &krate.name),
+        ..FmtCtx::new()
+    };
+
+    let matches = |name: &crate::names::Name| match filter {
+        None => true,
+        Some(filter) => filter.is_match(&name.fmt_with_ctx(&ctx)),
+    };
+
+    // We print the declarations in the order in which they were exported
+    for group in &krate.declarations {
+        use DeclarationGroup::*;
+        let decls: Vec<String> = match group {
+            Type(group) => group
+                .get_ids()
+                .into_iter()
+                .filter_map(|id| krate.type_decls.get(id))
+                .filter(|d| matches(&d.name))
+                .map(|d| ctx.format_object(d))
+                .collect(),
+            Fun(group) => group
+                .get_ids()
+                .into_iter()
+                .filter_map(|id| krate.fun_decls.get(id))
+                .filter(|d| matches(&d.name))
+                .map(|d| ctx.format_object(d))
+                .collect(),
+            Global(group) => group
+                .get_ids()
+                .into_iter()
+                .filter_map(|id| krate.global_decls.get(id))
+                .filter(|d| matches(&d.name))
+                .map(|d| ctx.format_object(d))
+                .collect(),
+            TraitDecl(group) => group
+                .get_ids()
+                .into_iter()
+                .filter_map(|id| krate.trait_decls.get(id))
+                .filter(|d| matches(&d.name))
+                .map(|d| ctx.format_object(d))
+                .collect(),
+            TraitImpl(group) => group
+                .get_ids()
+                .into_iter()
+                .filter_map(|id| krate.trait_impls.get(id))
+                .filter(|d| matches(&d.name))
+                .map(|d| ctx.format_object(d))
+                .collect(),
+        };
+        for decl in decls {
+            println!("{decl}\n");
+        }
+    }
+}
+
+/// Load the file given as input and print its declarations.
+///
+/// We determine the kind of the file (LLBC or ULLBC) from its extension.
+pub fn print(opts: &PrintOpts) -> Result<(), String> {
+    let filter = match &opts.filter {
+        None => None,
+        Some(filter) => match Regex::new(filter) {
+            Ok(filter) => Some(filter),
+            Err(err) => return Err(format!("Invalid filter: {err}")),
+        },
+    };
+
+    let is_ullbc = opts.file.extension().map_or(false, |ext| ext == "ullbc");
+    if is_ullbc {
+        let krate = deserialize::load_ullbc(&opts.file)?;
+        print_crate(&krate, filter.as_ref(), &opts.names);
+    } else {
+        let krate = deserialize::load_llbc(&opts.file)?;
+        print_crate(&krate, filter.as_ref(), &opts.names);
+    }
+    Ok(())
+}
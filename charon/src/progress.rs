@@ -0,0 +1,60 @@
+//! Report the progress of the extraction, by emitting events (item started,
+//! pass started, warning emitted, etc.) on a file descriptor given by the user
+//! (see [crate::cli_options::CliOpts::progress_fd]). This allows IDEs and build
+//! orchestrators to display live progress for long extractions.
+//!
+//! The events are serialized in JSON, one event per line (JSON-lines).
+
+use serde::Serialize;
+use std::fs::File;
+use std::io::Write;
+use std::os::unix::io::FromRawFd;
+
+/// A progress event.
+#[derive(Debug, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event<'a> {
+    /// We started translating an item
+    ItemStarted { name: &'a str },
+    /// We finished translating an item
+    ItemFinished { name: &'a str },
+    /// We started applying a micro-pass
+    PassStarted { pass: &'a str },
+    /// We emitted a warning (or an error)
+    Warning {
+        message: &'a str,
+        /// The location of the warning, if there is one
+        span: Option<&'a str>,
+        /// `true` if the warning is actually an error
+        is_error: bool,
+    },
+}
+
+/// Writes the progress events to a file descriptor.
+pub struct ProgressReporter {
+    out: File,
+}
+
+impl ProgressReporter {
+    /// Create a reporter which writes to the given file descriptor.
+    ///
+    /// The file descriptor must be open, and must be owned by the reporter
+    /// (it gets closed when the reporter is dropped).
+    pub fn from_fd(fd: i32) -> Self {
+        let out = unsafe { File::from_raw_fd(fd) };
+        ProgressReporter { out }
+    }
+
+    /// Emit an event. We ignore the errors: reporting the progress must not
+    /// interrupt the extraction.
+    pub fn emit(&self, event: &Event) {
+        match serde_json::to_string(event) {
+            Ok(line) => {
+                if writeln!(&self.out, "{line}").is_err() {
+                    log::warn!("Could not write the progress event: {:?}", event);
+                }
+            }
+            Err(_) => log::warn!("Could not serialize the progress event: {:?}", event),
+        }
+    }
+}
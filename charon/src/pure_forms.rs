@@ -0,0 +1,316 @@
+//! # Micro-pass (optional, see [crate::cli_options::CliOpts::emit_pure_forms]):
+//! compute the pure forms of the small functions (see [PureExpr]), so that the
+//! proof assistants can consume them directly as definitions.
+//!
+//! A function qualifies if:
+//! - it is a safe, local function, and its signature doesn't mention mutable
+//!   borrows or raw pointers
+//! - its body doesn't contain loops, panics or assertions, doesn't mutate
+//!   places in place and doesn't perform operations which may fail (like
+//!   the arithmetic operations, which may overflow)
+//! - it only calls functions which themselves qualify (in particular, we
+//!   don't handle the recursive functions, the calls to trait methods and
+//!   the calls to the assumed functions)
+//! - its pure form is small (see [MAX_SIZE])
+//!
+//! The statements which follow a switch are duplicated in all its branches.
+
+use crate::expressions::*;
+use crate::formatter::{IntoFormatter, SetLocals};
+use crate::gast::{Call, FnOperand};
+use crate::llbc_ast::*;
+use crate::translate_ctx::TransCtx;
+use crate::types::*;
+use std::collections::HashMap;
+
+/// The maximal number of nodes of a pure form. Note that this also prevents
+/// the duplication of the statements which follow the switches from blowing up.
+const MAX_SIZE: usize = 256;
+
+/// Check if a type contains mutable borrows or raw pointers
+struct MutableRefs {
+    found: bool,
+}
+
+impl SharedTypeVisitor for MutableRefs {
+    fn visit_ty_ref(&mut self, r: &Region, ty: &Box<Ty>, rk: &RefKind) {
+        if *rk == RefKind::Mut {
+            self.found = true;
+        }
+        self.visit_region(r);
+        self.visit_ty(ty);
+    }
+
+    fn visit_ty_raw_ptr(&mut self, _ty: &Box<Ty>, _rk: &RefKind) {
+        self.found = true;
+    }
+}
+
+fn has_mutable_refs(sig: &FunSig) -> bool {
+    let mut visitor = MutableRefs { found: false };
+    for ty in sig.inputs.iter().chain(std::iter::once(&sig.output)) {
+        visitor.visit_ty(ty);
+    }
+    visitor.found
+}
+
+/// Check if a binary operation may fail (division by zero, overflow, etc.)
+fn binop_can_fail(binop: &BinOp) -> bool {
+    matches!(
+        binop,
+        BinOp::Div
+            | BinOp::Rem
+            | BinOp::Add
+            | BinOp::Sub
+            | BinOp::Mul
+            | BinOp::Shl
+            | BinOp::Shr
+            | BinOp::Offset
+    )
+}
+
+struct Translator<'a> {
+    /// The functions which have a pure form
+    pure_funs: &'a HashMap<FunDeclId::Id, PureExpr>,
+    /// The number of nodes we can still introduce (see [MAX_SIZE])
+    budget: usize,
+}
+
+impl<'a> Translator<'a> {
+    /// Account for a new node: fail if we exceeded the budget
+    fn consume(&mut self) -> Option<()> {
+        self.budget = self.budget.checked_sub(1)?;
+        Some(())
+    }
+
+    fn translate_place(&mut self, p: &Place) -> Option<PureExpr> {
+        self.consume()?;
+        let mut e = PureExpr::Var(p.var_id);
+        for pe in &p.projection {
+            match pe {
+                // We model the shared borrows and the boxes as values
+                ProjectionElem::Deref | ProjectionElem::DerefBox => (),
+                ProjectionElem::Field(kind, field_id) => {
+                    self.consume()?;
+                    e = PureExpr::Field(*kind, *field_id, Box::new(e));
+                }
+                ProjectionElem::DerefRawPtr | ProjectionElem::Index(..) => return None,
+            }
+        }
+        Some(e)
+    }
+
+    fn translate_operand(&mut self, op: &Operand) -> Option<PureExpr> {
+        match op {
+            Operand::Copy(p) | Operand::Move(p) => self.translate_place(p),
+            Operand::Const(cv) => {
+                self.consume()?;
+                Some(PureExpr::Const(cv.clone()))
+            }
+        }
+    }
+
+    fn translate_operands(&mut self, ops: &[Operand]) -> Option<Vec<PureExpr>> {
+        ops.iter().map(|op| self.translate_operand(op)).collect()
+    }
+
+    fn translate_rvalue(&mut self, rv: &Rvalue) -> Option<PureExpr> {
+        match rv {
+            Rvalue::Use(op) => self.translate_operand(op),
+            Rvalue::Ref(p, BorrowKind::Shared) => self.translate_place(p),
+            Rvalue::UnaryOp(unop @ (UnOp::Not | UnOp::Cast(_)), op) => {
+                self.consume()?;
+                let op = self.translate_operand(op)?;
+                Some(PureExpr::UnaryOp(unop.clone(), Box::new(op)))
+            }
+            Rvalue::BinaryOp(binop, op0, op1) if !binop_can_fail(binop) => {
+                self.consume()?;
+                let op0 = self.translate_operand(op0)?;
+                let op1 = self.translate_operand(op1)?;
+                Some(PureExpr::BinaryOp(*binop, Box::new(op0), Box::new(op1)))
+            }
+            Rvalue::Aggregate(kind, ops) => {
+                self.consume()?;
+                Some(PureExpr::Aggregate(
+                    kind.clone(),
+                    self.translate_operands(ops)?,
+                ))
+            }
+            Rvalue::Global(id) => {
+                self.consume()?;
+                Some(PureExpr::Global(*id))
+            }
+            Rvalue::Ref(..)
+            | Rvalue::AddressOf(..)
+            | Rvalue::UnaryOp(..)
+            | Rvalue::BinaryOp(..)
+            | Rvalue::Nullary(..)
+            | Rvalue::Discriminant(..)
+            | Rvalue::Len(..)
+            | Rvalue::Repeat(..)
+            | Rvalue::ShallowInitBox(..) => None,
+        }
+    }
+
+    /// We only translate the calls to the regular functions which have a pure
+    /// form.
+    fn translate_call(&mut self, call: &Call) -> Option<PureExpr> {
+        let FnOperand::Regular(fn_ptr) = &call.func else {
+            return None;
+        };
+        let FunIdOrTraitMethodRef::Fun(FunId::Regular(fun_id)) = &fn_ptr.func else {
+            return None;
+        };
+        if !self.pure_funs.contains_key(fun_id) {
+            return None;
+        }
+        self.consume()?;
+        Some(PureExpr::App(
+            fn_ptr.clone(),
+            self.translate_operands(&call.args)?,
+        ))
+    }
+
+    /// Translate a switch branch: the statements which follow the switch are
+    /// appended to the branch.
+    fn translate_branch(&mut self, st: &Statement, next: &[&Statement]) -> Option<PureExpr> {
+        let sts: Vec<&Statement> = std::iter::once(st).chain(next.iter().copied()).collect();
+        self.translate_statements(&sts)
+    }
+
+    /// Translate a sequence of statements, which must end with a return.
+    fn translate_statements(&mut self, sts: &[&Statement]) -> Option<PureExpr> {
+        let (st, next) = sts.split_first()?;
+        match &st.content {
+            RawStatement::Assign(dest, rv) if dest.projection.is_empty() => {
+                self.consume()?;
+                let rv = self.translate_rvalue(rv)?;
+                let next = self.translate_statements(next)?;
+                Some(PureExpr::Let(dest.var_id, Box::new(rv), Box::new(next)))
+            }
+            RawStatement::Call(call) if call.dest.projection.is_empty() => {
+                self.consume()?;
+                let app = self.translate_call(call)?;
+                let next = self.translate_statements(next)?;
+                Some(PureExpr::Let(
+                    call.dest.var_id,
+                    Box::new(app),
+                    Box::new(next),
+                ))
+            }
+            RawStatement::FakeRead(_) | RawStatement::Drop(_) | RawStatement::Nop => {
+                self.translate_statements(next)
+            }
+            RawStatement::Return => {
+                self.consume()?;
+                Some(PureExpr::Var(VarId::Id::new(0)))
+            }
+            RawStatement::Block(block) => {
+                let sts: Vec<&Statement> = block.iter().chain(next.iter().copied()).collect();
+                self.translate_statements(&sts)
+            }
+            RawStatement::Switch(switch) => {
+                self.consume()?;
+                match switch {
+                    Switch::If(op, st0, st1) => {
+                        let op = self.translate_operand(op)?;
+                        let e0 = self.translate_branch(st0, next)?;
+                        let e1 = self.translate_branch(st1, next)?;
+                        Some(PureExpr::Ite(Box::new(op), Box::new(e0), Box::new(e1)))
+                    }
+                    Switch::SwitchInt(op, int_ty, branches, otherwise) => {
+                        let op = self.translate_operand(op)?;
+                        let branches = branches
+                            .iter()
+                            .map(|(values, st)| {
+                                Some((values.clone(), self.translate_branch(st, next)?))
+                            })
+                            .collect::<Option<Vec<_>>>()?;
+                        let otherwise = self.translate_branch(otherwise, next)?;
+                        Some(PureExpr::SwitchInt(
+                            Box::new(op),
+                            *int_ty,
+                            branches,
+                            Box::new(otherwise),
+                        ))
+                    }
+                    Switch::Match(p, branches, otherwise) => {
+                        let p = self.translate_place(p)?;
+                        let branches = branches
+                            .iter()
+                            .map(|(variants, st)| {
+                                Some((variants.clone(), self.translate_branch(st, next)?))
+                            })
+                            .collect::<Option<Vec<_>>>()?;
+                        let otherwise = match otherwise {
+                            Some(st) => Some(Box::new(self.translate_branch(st, next)?)),
+                            None => None,
+                        };
+                        Some(PureExpr::Match(Box::new(p), branches, otherwise))
+                    }
+                }
+            }
+            RawStatement::Assign(..)
+            | RawStatement::Call(_)
+            | RawStatement::SetDiscriminant(..)
+            | RawStatement::Assert(_)
+            | RawStatement::Panic
+            | RawStatement::Break(..)
+            | RawStatement::Continue(..)
+            | RawStatement::Loop(..)
+            | RawStatement::ForLoop(_)
+            | RawStatement::QuestionMark(_) => None,
+        }
+    }
+}
+
+/// Compute the pure form of a function, given the functions which already
+/// have a pure form.
+fn compute_pure_form(
+    pure_funs: &HashMap<FunDeclId::Id, PureExpr>,
+    decl: &FunDecl,
+) -> Option<PureExpr> {
+    if !decl.is_local || decl.signature.is_unsafe || has_mutable_refs(&decl.signature) {
+        return None;
+    }
+    let body = decl.body.as_ref()?;
+    let mut translator = Translator {
+        pure_funs,
+        budget: MAX_SIZE,
+    };
+    translator.translate_statements(&[&body.body])
+}
+
+pub fn transform(ctx: &TransCtx, funs: &mut FunDecls) {
+    // Compute the least fixed point: a function qualifies once all the
+    // functions it calls qualify (we thus never qualify recursive functions).
+    let mut pure_funs: HashMap<FunDeclId::Id, PureExpr> = HashMap::new();
+    loop {
+        let mut changed = false;
+        for decl in funs.iter() {
+            if pure_funs.contains_key(&decl.def_id) {
+                continue;
+            }
+            if let Some(e) = compute_pure_form(&pure_funs, decl) {
+                let _ = pure_funs.insert(decl.def_id, e);
+                changed = true;
+            }
+        }
+        if !changed {
+            break;
+        }
+    }
+
+    for decl in funs.iter_mut() {
+        decl.pure_form = pure_funs.remove(&decl.def_id);
+        if let (Some(e), Some(body)) = (&decl.pure_form, &decl.body) {
+            let fmt_ctx = ctx.into_fmt();
+            let fmt_ctx = fmt_ctx.set_locals(&body.locals);
+            trace!(
+                "# Pure form of {}:\n{}",
+                decl.name.fmt_with_ctx(&fmt_ctx),
+                e.fmt_with_ctx("", &fmt_ctx)
+            );
+        }
+    }
+}
@@ -0,0 +1,198 @@
+//! Implementation of the `query` subcommand, which loads a `.llbc` (or
+//! `.ullbc`) file and answers simple questions about the extracted crate
+//! (who calls a given function, what is the type of a given function or
+//! global, which types implement a given trait, etc.).
+//!
+//! The declarations are designated by their names, as printed by Charon
+//! (ex.: `core::option::Option`, `crate_name::module::function`).
+
+use crate::cli_options::{Query, QueryOpts};
+use crate::deserialize;
+use crate::deserialize::GCrate;
+use crate::expressions::SharedExprVisitor;
+use crate::formatter::{FmtCtx, Formatter};
+use crate::gast::*;
+use crate::llbc_ast;
+use crate::types::*;
+use crate::ullbc_ast;
+use std::collections::HashSet;
+
+/// Collects the functions referenced in a body (called, or used as function
+/// pointers). Note that for the calls to trait methods, we collect the method
+/// declared in the trait.
+struct CalledFuns {
+    funs: HashSet<FunDeclId::Id>,
+}
+
+impl SharedTypeVisitor for CalledFuns {
+    fn visit_fun_decl_id(&mut self, id: &FunDeclId::Id) {
+        self.funs.insert(*id);
+    }
+}
+
+impl SharedExprVisitor for CalledFuns {}
+impl ullbc_ast::SharedAstVisitor for CalledFuns {}
+impl llbc_ast::SharedAstVisitor for CalledFuns {}
+
+/// The bodies in which we can look for function calls.
+///
+/// This allows us to implement the queries generically for LLBC and ULLBC.
+trait CallsInBody {
+    fn collect_calls(&self, calls: &mut CalledFuns);
+}
+
+impl CallsInBody for ullbc_ast::BlockId::Vector<ullbc_ast::BlockData> {
+    fn collect_calls(&self, calls: &mut CalledFuns) {
+        use ullbc_ast::SharedAstVisitor;
+        for block in self.iter() {
+            calls.visit_block_data(block);
+        }
+    }
+}
+
+impl CallsInBody for llbc_ast::Statement {
+    fn collect_calls(&self, calls: &mut CalledFuns) {
+        use llbc_ast::SharedAstVisitor;
+        calls.visit_statement(self);
+    }
+}
+
+/// List the functions which call (or reference) the functions named `name`.
+fn callers_of<T: CallsInBody>(
+    ctx: &FmtCtx,
+    krate: &GCrate<GFunDecl<T>, GGlobalDecl<T>>,
+    name: &str,
+) -> Result<Vec<String>, String> {
+    let callees: HashSet<FunDeclId::Id> = krate
+        .fun_decls
+        .iter()
+        .filter(|d| d.name.fmt_with_ctx(ctx) == name)
+        .map(|d| d.def_id)
+        .collect();
+    if callees.is_empty() {
+        return Err(format!("Could not find a function named: {name}"));
+    }
+
+    let mut callers = Vec::new();
+    for decl in krate.fun_decls.iter() {
+        let Some(body) = &decl.body else { continue };
+        let mut calls = CalledFuns {
+            funs: HashSet::new(),
+        };
+        body.body.collect_calls(&mut calls);
+        if !calls.funs.is_disjoint(&callees) {
+            callers.push(decl.name.fmt_with_ctx(ctx));
+        }
+    }
+    Ok(callers)
+}
+
+/// Print the signatures of the functions named `name`, the types of the globals
+/// named `name`, and the definitions of the types named `name`.
+fn type_of<T>(
+    ctx: &FmtCtx,
+    krate: &GCrate<GFunDecl<T>, GGlobalDecl<T>>,
+    name: &str,
+) -> Result<Vec<String>, String> {
+    let funs = krate
+        .fun_decls
+        .iter()
+        .filter(|d| d.name.fmt_with_ctx(ctx) == name)
+        .map(|d| format!("{name}: {}", d.signature.fmt_with_ctx(ctx)));
+    let globals = krate
+        .global_decls
+        .iter()
+        .filter(|d| d.name.fmt_with_ctx(ctx) == name)
+        .map(|d| format!("{name}: {}", d.ty.fmt_with_ctx(ctx)));
+    let types = krate
+        .type_decls
+        .iter()
+        .filter(|d| d.name.fmt_with_ctx(ctx) == name)
+        .map(|d| ctx.format_object(d));
+    let res: Vec<String> = funs.chain(globals).chain(types).collect();
+    if res.is_empty() {
+        Err(format!("Could not find a declaration named: {name}"))
+    } else {
+        Ok(res)
+    }
+}
+
+/// List the implementations of the traits named `name`.
+fn impls_of<T>(
+    ctx: &FmtCtx,
+    krate: &GCrate<GFunDecl<T>, GGlobalDecl<T>>,
+    name: &str,
+) -> Result<Vec<String>, String> {
+    let traits: HashSet<TraitDeclId::Id> = krate
+        .trait_decls
+        .iter()
+        .filter(|d| d.name.fmt_with_ctx(ctx) == name)
+        .map(|d| d.def_id)
+        .collect();
+    if traits.is_empty() {
+        return Err(format!("Could not find a trait named: {name}"));
+    }
+
+    Ok(krate
+        .trait_impls
+        .iter()
+        .filter(|d| traits.contains(&d.impl_trait.trait_id))
+        .map(|d| {
+            format!(
+                "{}: {}",
+                d.name.fmt_with_ctx(ctx),
+                d.impl_trait.fmt_with_ctx(ctx)
+            )
+        })
+        .collect())
+}
+
+fn query_crate<T: CallsInBody>(
+    krate: &GCrate<GFunDecl<T>, GGlobalDecl<T>>,
+    query: &Query,
+) -> Result<(), String> {
+    // The formatting context expects ULLBC declarations: as in
+    // [crate::print_crate], we simply remove the bodies.
+    let fun_decls = krate
+        .fun_decls
+        .iter()
+        .map(|d| (d.def_id, d.without_body()))
+        .collect();
+    let global_decls = krate
+        .global_decls
+        .iter()
+        .map(|d| (d.def_id, d.without_body()))
+        .collect();
+    let ctx = FmtCtx {
+        type_decls: Some(&krate.type_decls),
+        fun_decls: Some(&fun_decls),
+        global_decls: Some(&global_decls),
+        trait_decls: Some(&krate.trait_decls),
+        trait_impls: Some(&krate.trait_impls),
+        ..FmtCtx::new()
+    };
+
+    let res = match query {
+        Query::CallersOf { name } => callers_of(&ctx, krate, name)?,
+        Query::TypeOf { name } => type_of(&ctx, krate, name)?,
+        Query::ImplsOf { name } => impls_of(&ctx, krate, name)?,
+    };
+    for line in res {
+        println!("{line}");
+    }
+    Ok(())
+}
+
+/// Load the file given as input and answer the query.
+///
+/// We determine the kind of the file (LLBC or ULLBC) from its extension.
+pub fn query(opts: &QueryOpts) -> Result<(), String> {
+    let is_ullbc = opts.file.extension().map_or(false, |ext| ext == "ullbc");
+    if is_ullbc {
+        let krate = deserialize::load_ullbc(&opts.file)?;
+        query_crate(&krate, &opts.query)
+    } else {
+        let krate = deserialize::load_llbc(&opts.file)?;
+        query_crate(&krate, &opts.query)
+    }
+}
@@ -0,0 +1,198 @@
+//! Prune the declarations which are not reachable from the entry points of
+//! the crate.
+//!
+//! The entry points are given with `--start-from` (see
+//! [crate::cli_options::CliOpts::start_from]), or marked with the
+//! `#[charon::entry]` attribute (the crate must then register the tool with
+//! `#![register_tool(charon)]`). If there are no entry points, we keep all the
+//! declarations.
+//!
+//! We explore the dependency graph we use to order the declarations (see
+//! [crate::reorder_decls::compute_dependencies]). As we don't explore the
+//! bodies of the opaque declarations, the opaque frontier is kept: the
+//! reachable opaque declarations remain, together with the declarations their
+//! signatures refer to. This shrinks the output a lot for the application
+//! crates, which tend to use a small part of their dependencies.
+
+use crate::formatter::IntoFormatter;
+use crate::gast::FunKind;
+use crate::meta::{AttrInfo, Attribute};
+use crate::names::Name;
+use crate::reorder_decls::{compute_dependencies, AnyTransId};
+use crate::translate_ctx::TransCtx;
+use rustc_hir::def_id::CRATE_DEF_ID;
+use std::collections::{HashMap, HashSet};
+
+/// The path of the attribute which marks the entry points.
+const ENTRY_ATTRIBUTE: &str = "charon::entry";
+
+fn is_marked_entry(attr_info: &AttrInfo) -> bool {
+    attr_info
+        .attributes
+        .iter()
+        .any(|attr| matches!(attr, Attribute::Tool { path, .. } if path == ENTRY_ATTRIBUTE))
+}
+
+/// Find the entry points: the declarations whose names (as printed by Charon)
+/// were given by the user, and the declarations marked with the entry
+/// attribute.
+fn find_entry_points(ctx: &mut TransCtx, start_from: &[String]) -> Vec<AnyTransId> {
+    let mut entries = Vec::new();
+    let mut found: HashSet<String> = HashSet::new();
+    {
+        let fmt_ctx = ctx.into_fmt();
+        let mut check = |name: String, attr_info: &AttrInfo, id: AnyTransId| {
+            let is_named = start_from.contains(&name);
+            if is_named || is_marked_entry(attr_info) {
+                entries.push(id);
+            }
+            if is_named {
+                found.insert(name);
+            }
+        };
+        for d in ctx.type_decls.iter() {
+            let name = d.name.fmt_with_ctx(&fmt_ctx);
+            check(name, &d.attr_info, AnyTransId::Type(d.def_id));
+        }
+        for d in ctx.fun_decls.iter() {
+            let name = d.name.fmt_with_ctx(&fmt_ctx);
+            check(name, &d.attr_info, AnyTransId::Fun(d.def_id));
+        }
+        for d in ctx.global_decls.iter() {
+            let name = d.name.fmt_with_ctx(&fmt_ctx);
+            check(name, &d.attr_info, AnyTransId::Global(d.def_id));
+        }
+        for d in ctx.trait_decls.iter() {
+            let name = d.name.fmt_with_ctx(&fmt_ctx);
+            check(name, &d.attr_info, AnyTransId::TraitDecl(d.def_id));
+        }
+        for d in ctx.trait_impls.iter() {
+            let name = d.name.fmt_with_ctx(&fmt_ctx);
+            check(name, &d.attr_info, AnyTransId::TraitImpl(d.def_id));
+        }
+    }
+
+    // Report the entry points we couldn't find: we would otherwise silently
+    // remove everything
+    for name in start_from {
+        if !found.contains(name) {
+            let span = ctx.tcx.def_span(CRATE_DEF_ID);
+            ctx.span_err(span, &format!("Could not find the entry point: {name}"));
+        }
+    }
+    entries
+}
+
+/// Compute the declarations reachable from the entry points.
+fn compute_reachable(ctx: &TransCtx, entries: Vec<AnyTransId>) -> HashSet<AnyTransId> {
+    let graph: HashMap<AnyTransId, Vec<AnyTransId>> =
+        compute_dependencies(ctx).into_iter().collect();
+
+    // The dependency graph doesn't contain the edges which would introduce
+    // spurious cycles when ordering the declarations, but which we need to
+    // generate consistent files: the methods of the traits, and the parents
+    // of the methods.
+    let extra_deps = |id: AnyTransId| -> Vec<AnyTransId> {
+        match id {
+            AnyTransId::TraitDecl(id) => match ctx.trait_decls.get(id) {
+                Some(d) => d
+                    .required_methods
+                    .iter()
+                    .map(|(_, id)| *id)
+                    .chain(d.provided_methods.iter().filter_map(|(_, id)| *id))
+                    .map(AnyTransId::Fun)
+                    .collect(),
+                None => Vec::new(),
+            },
+            AnyTransId::Fun(id) => match ctx.fun_decls.get(id).map(|d| &d.kind) {
+                Some(FunKind::TraitMethodImpl { impl_id, .. }) => {
+                    vec![AnyTransId::TraitImpl(*impl_id)]
+                }
+                Some(FunKind::TraitMethodDecl(trait_id, _))
+                | Some(FunKind::TraitMethodProvided(trait_id, _)) => {
+                    vec![AnyTransId::TraitDecl(*trait_id)]
+                }
+                _ => Vec::new(),
+            },
+            _ => Vec::new(),
+        }
+    };
+
+    let mut reachable: HashSet<AnyTransId> = HashSet::new();
+    let mut stack = entries;
+    while let Some(id) = stack.pop() {
+        if !reachable.insert(id) {
+            continue;
+        }
+        if let Some(deps) = graph.get(&id) {
+            stack.extend(deps.iter().copied());
+        }
+        stack.extend(extra_deps(id));
+    }
+    reachable
+}
+
+/// Remove the declarations which are not reachable from the entry points (see
+/// the explanations at the top of the file).
+pub fn prune(ctx: &mut TransCtx, start_from: &[String]) {
+    let entries = find_entry_points(ctx, start_from);
+    if entries.is_empty() {
+        // No entry points: we keep everything
+        return;
+    }
+    let reachable = compute_reachable(ctx, entries);
+
+    let count = ctx.all_ids.len();
+    ctx.all_ids = ctx
+        .all_ids
+        .iter()
+        .filter(|id| reachable.contains(id))
+        .copied()
+        .collect();
+    ctx.type_decls
+        .map
+        .retain(|id, _| reachable.contains(&AnyTransId::Type(*id)));
+    ctx.fun_decls
+        .map
+        .retain(|id, _| reachable.contains(&AnyTransId::Fun(*id)));
+    ctx.global_decls
+        .map
+        .retain(|id, _| reachable.contains(&AnyTransId::Global(*id)));
+    ctx.trait_decls
+        .map
+        .retain(|id, _| reachable.contains(&AnyTransId::TraitDecl(*id)));
+    ctx.trait_impls
+        .map
+        .retain(|id, _| reachable.contains(&AnyTransId::TraitImpl(*id)));
+
+    // Remove the methods we pruned from the impl blocks, and the impl blocks
+    // which become empty
+    for impl_block in ctx.impl_blocks.iter_mut() {
+        impl_block
+            .methods
+            .retain(|id| reachable.contains(&AnyTransId::Fun(*id)));
+    }
+    ctx.impl_blocks
+        .map
+        .retain(|_, impl_block| !impl_block.methods.is_empty());
+
+    // Remove the warnings about the declarations we pruned (see
+    // [crate::gast::OpaqueWarning]). Those warnings are not numerous: we
+    // simply look for the names.
+    let mut warnings = std::mem::take(&mut ctx.opaque_warnings);
+    let names: Vec<&Name> = ctx
+        .type_decls
+        .iter()
+        .map(|d| &d.name)
+        .chain(ctx.fun_decls.iter().map(|d| &d.name))
+        .chain(ctx.global_decls.iter().map(|d| &d.name))
+        .collect();
+    warnings.retain(|w| names.contains(&&w.name));
+    ctx.opaque_warnings = warnings;
+
+    info!(
+        "Pruned {} of {} declarations, unreachable from the entry points",
+        count - ctx.all_ids.len(),
+        count
+    );
+}
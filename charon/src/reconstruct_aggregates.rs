@@ -0,0 +1,149 @@
+//! In some situations, the MIR doesn't build enumeration values with a single
+//! aggregate assignment, but rather initializes the fields of the variant one
+//! by one before setting the discriminant:
+//! ```text
+//! (x as Some).0 = move y;
+//! discriminant(x) = 1;
+//! ```
+//!
+//! This micro-pass reconstructs the aggregate, so that the enumeration value
+//! is built atomically:
+//! ```text
+//! x = Option::Some(move y);
+//! ```
+//!
+//! We only perform the transformation if the destination is a local variable
+//! (i.e., a place without projections), and if all the fields of the variant
+//! are initialized exactly once, by operands which don't read the destination.
+//! Otherwise, we leave the statements unchanged.
+
+use crate::expressions::*;
+use crate::formatter::{Formatter, IntoFormatter};
+use crate::id_vector::ToUsize;
+use crate::llbc_ast::*;
+use crate::meta::combine_meta;
+use crate::translate_ctx::TransCtx;
+use crate::types::*;
+use crate::values::*;
+
+/// If the statement is an assignment of the shape `(x as V).f = op` where
+/// `x` is a local variable, return `(x, adt_id, V, f, op)`.
+fn as_field_assign(
+    st: &Statement,
+) -> Option<(
+    VarId::Id,
+    TypeDeclId::Id,
+    VariantId::Id,
+    FieldId::Id,
+    &Operand,
+)> {
+    match &st.content {
+        RawStatement::Assign(p, Rvalue::Use(op)) => match p.projection.as_slice() {
+            [ProjectionElem::Field(FieldProjKind::Adt(adt_id, Some(variant_id)), field_id)] => {
+                Some((p.var_id, *adt_id, *variant_id, *field_id, op))
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Check if the statements start with field initializations followed by a
+/// [RawStatement::SetDiscriminant] that we can merge into a single aggregate.
+/// If it is the case, return the aggregate assignment and the number of
+/// statements it replaces.
+fn try_reconstruct(
+    type_decls: &TypeDecls,
+    locals: &VarId::Vector<Var>,
+    sts: &[Statement],
+) -> Option<(Statement, usize)> {
+    // Find the discriminant assignment, and remember the field initializations
+    // which precede it.
+    let mut assigns: Vec<(&Statement, FieldId::Id, &Operand)> = Vec::new();
+    let mut sts = sts.iter();
+    let (set_discr, var_id, variant_id) = loop {
+        let first = sts.next()?;
+        match &first.content {
+            RawStatement::SetDiscriminant(p, variant_id) => {
+                if !p.projection.is_empty() {
+                    return None;
+                }
+                break (first, p.var_id, *variant_id);
+            }
+            _ => {
+                // We check below that all the assignments update the same
+                // variant of the same variable
+                let (_, _, _, field_id, op) = as_field_assign(first)?;
+                assigns.push((first, field_id, op));
+            }
+        }
+    };
+
+    // The type of the destination gives us the generics of the aggregate
+    let (adt_id, generics) = match &locals.get(var_id)?.ty {
+        Ty::Adt(TypeId::Adt(adt_id), generics) => (*adt_id, generics),
+        _ => return None,
+    };
+    let variants = match &type_decls.get(adt_id)?.kind {
+        TypeDeclKind::Enum(variants) => variants,
+        _ => return None,
+    };
+    let num_fields = variants.get(variant_id)?.fields.len();
+
+    // Check that the fields are initialized exactly once, with operands
+    // which don't read the destination.
+    let mut fields: Vec<Option<Operand>> = vec![None; num_fields];
+    for (st, field_id, op) in &assigns {
+        let (st_var_id, st_adt_id, st_variant_id, _, _) = as_field_assign(st)?;
+        if st_var_id != var_id || st_adt_id != adt_id || st_variant_id != variant_id {
+            return None;
+        }
+        match op {
+            Operand::Copy(p) | Operand::Move(p) if p.var_id == var_id => return None,
+            _ => (),
+        }
+        let field = fields.get_mut(field_id.to_usize())?;
+        if field.is_some() {
+            return None;
+        }
+        *field = Some((*op).clone());
+    }
+    let ops: Vec<Operand> = fields.into_iter().collect::<Option<Vec<Operand>>>()?;
+
+    let meta = assigns.iter().fold(set_discr.meta, |meta, (st, _, _)| {
+        combine_meta(&meta, &st.meta)
+    });
+    let aggregate = Rvalue::Aggregate(
+        AggregateKind::Adt(TypeId::Adt(adt_id), Some(variant_id), generics.clone()),
+        ops,
+    );
+    let st = Statement::new(meta, RawStatement::Assign(Place::new(var_id), aggregate));
+    Some((st, assigns.len() + 1))
+}
+
+fn transform_block(type_decls: &TypeDecls, locals: &VarId::Vector<Var>, sts: &mut Vec<Statement>) {
+    let mut i = 0;
+    while i < sts.len() {
+        if let Some((nst, num_replaced)) = try_reconstruct(type_decls, locals, &sts[i..]) {
+            // Replace the statements with the aggregate assignment
+            sts.splice(i..i + num_replaced, [nst]);
+        }
+        i += 1;
+    }
+}
+
+pub fn transform(ctx: &mut TransCtx, funs: &mut FunDecls, globals: &mut GlobalDecls) {
+    ctx.iter_bodies(funs, globals, |ctx, name, b| {
+        let fmt_ctx = ctx.into_fmt();
+        trace!(
+            "# About to reconstruct the aggregates in decl: {}:\n{}",
+            name.fmt_with_ctx(&fmt_ctx),
+            fmt_ctx.format_object(&*b)
+        );
+
+        let type_decls = &ctx.type_decls;
+        let locals = &b.locals;
+        b.body
+            .transform_blocks(&mut |sts| transform_block(type_decls, locals, sts));
+    })
+}
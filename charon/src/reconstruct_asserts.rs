@@ -22,10 +22,10 @@ fn transform_st(st: &mut Statement) -> Option<Vec<Statement>> {
                     RawStatement::Assert(Assert {
                         cond: op,
                         expected: false,
+                        kind: AssertKind::User,
                     }),
                 );
-                let st1 = Box::new(st1);
-                RawStatement::Sequence(st1, st2)
+                new_sequence(st1, *st2).content
             });
         }
     }
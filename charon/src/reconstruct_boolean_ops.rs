@@ -0,0 +1,280 @@
+//! MIR doesn't have lazy boolean operators: `a && b` and `a || b` are encoded
+//! with switches. This pass recognizes those encodings and rebuilds the
+//! boolean operations, which makes the conditions a lot shorter.
+//!
+//! We handle two kinds of encodings. When the boolean is used as a value, we
+//! get (for `x = a && b`):
+//! ```text
+//! if a { x = b } else { x = false }
+//! ```
+//! which we rewrite to `x = a && b`. When the boolean is used as the condition
+//! of an `if`, we get (for `if a && b { s1 } else { s2 }`):
+//! ```text
+//! if a { if b { s1 } else { s2 } } else { s2 }
+//! ```
+//! which we rewrite to `tmp = a && b; if tmp { s1 } else { s2 }`.
+//! The `||` operator is handled in a symmetric manner.
+//!
+//! Rewriting the encodings may lead to evaluating `b` even when `a` is false:
+//! we only rewrite them if evaluating `b` (and the statements computing it)
+//! can't fail and has no side effects.
+
+use crate::expressions::*;
+use crate::formatter::{Formatter, IntoFormatter};
+use crate::gast::Var;
+use crate::llbc_ast::*;
+use crate::translate_ctx::TransCtx;
+use crate::types::*;
+use crate::values::{Literal, VarId};
+
+/// Return `true` if reading the place can't fail.
+///
+/// We forbid the dereferences (we may dereference a null pointer, etc.), the
+/// index projections (the index may be out of bounds) and the projections on
+/// enumeration variants (the value may be another variant).
+fn is_pure_place(p: &Place) -> bool {
+    p.projection.iter().all(|pe| match pe {
+        ProjectionElem::Field(kind, _) => !matches!(kind, FieldProjKind::Adt(_, Some(_))),
+        ProjectionElem::Deref
+        | ProjectionElem::DerefBox
+        | ProjectionElem::DerefRawPtr
+        | ProjectionElem::Index(..) => false,
+    })
+}
+
+pub(crate) fn is_pure_operand(op: &Operand) -> bool {
+    match op {
+        Operand::Copy(p) | Operand::Move(p) => is_pure_place(p),
+        Operand::Const(_) => true,
+    }
+}
+
+/// Return `true` if evaluating the rvalue can't fail and has no side effects.
+pub(crate) fn is_pure_rvalue(rv: &Rvalue) -> bool {
+    match rv {
+        Rvalue::Use(op) | Rvalue::UnaryOp(UnOp::Not, op) => is_pure_operand(op),
+        Rvalue::BinaryOp(binop, op1, op2) => {
+            let can_fail = matches!(
+                binop,
+                BinOp::Div
+                    | BinOp::Rem
+                    | BinOp::Add
+                    | BinOp::Sub
+                    | BinOp::Mul
+                    | BinOp::Shl
+                    | BinOp::Shr
+                    | BinOp::Offset
+            );
+            !can_fail && is_pure_operand(op1) && is_pure_operand(op2)
+        }
+        _ => false,
+    }
+}
+
+/// The statements of a branch, without the no-ops.
+pub(crate) fn branch_statements(st: &Statement) -> Vec<&Statement> {
+    match &st.content {
+        RawStatement::Block(sts) => sts.iter().filter(|st| !st.content.is_nop()).collect(),
+        RawStatement::Nop => Vec::new(),
+        _ => vec![st],
+    }
+}
+
+/// Same as [branch_statements], but consumes the branch.
+fn into_branch_statements(st: Statement) -> Vec<Statement> {
+    match st.content {
+        RawStatement::Block(sts) => sts.into_iter().filter(|st| !st.content.is_nop()).collect(),
+        RawStatement::Nop => Vec::new(),
+        _ => vec![st],
+    }
+}
+
+/// The operands of an rvalue, if it is pure (see [is_pure_rvalue]).
+fn pure_rvalue_operands(rv: &Rvalue) -> Vec<&Operand> {
+    match rv {
+        Rvalue::Use(op) | Rvalue::UnaryOp(_, op) => vec![op],
+        Rvalue::BinaryOp(_, op1, op2) => vec![op1, op2],
+        _ => Vec::new(),
+    }
+}
+
+/// Decompose a branch into a sequence of pure assignments to local variables,
+/// followed by a last statement. The assignments must not update the place
+/// read by the condition `cond` of the switch.
+///
+/// The assignments get hoisted out of the branch: they must only copy their
+/// operands, or move the temporaries introduced by the previous assignments
+/// (moving a variable defined outside the branch would invalidate it in the
+/// other branch).
+fn split_pure_prefix<'a>(cond: &Operand, st: &'a Statement) -> Option<&'a Statement> {
+    let cond_var = match cond {
+        Operand::Copy(p) | Operand::Move(p) => Some(p.var_id),
+        Operand::Const(_) => None,
+    };
+    let (last, prefix) = branch_statements(st)
+        .split_last()
+        .map(|(l, p)| (*l, p.to_vec()))?;
+    let mut assigned: Vec<VarId::Id> = Vec::new();
+    for st in prefix {
+        let RawStatement::Assign(p, rv) = &st.content else {
+            return None;
+        };
+        let hoistable = pure_rvalue_operands(rv).into_iter().all(|op| match op {
+            Operand::Copy(_) | Operand::Const(_) => true,
+            Operand::Move(q) => q.projection.is_empty() && assigned.contains(&q.var_id),
+        });
+        if !p.projection.is_empty()
+            || Some(p.var_id) == cond_var
+            || !is_pure_rvalue(rv)
+            || !hoistable
+        {
+            return None;
+        }
+        assigned.push(p.var_id);
+    }
+    Some(last)
+}
+
+/// Check if a statement is of the shape: `x = const b`. If so, return `x`.
+fn assigned_bool_const(st: &Statement, b: bool) -> Option<&Place> {
+    let &[st] = branch_statements(st).as_slice() else {
+        return None;
+    };
+    match &st.content {
+        RawStatement::Assign(
+            x,
+            Rvalue::Use(Operand::Const(ConstantExpr {
+                value: RawConstantExpr::Literal(Literal::Bool(v)),
+                ..
+            })),
+        ) if *v == b => Some(x),
+        _ => None,
+    }
+}
+
+/// If the statement is a switch `if _ { st1 } else { st2 }`, return its
+/// branches.
+fn if_branches(st: &Statement) -> Option<(&Statement, &Statement)> {
+    match &st.content {
+        RawStatement::Switch(Switch::If(_, st1, st2)) => Some((st1, st2)),
+        _ => None,
+    }
+}
+
+/// Check if two statements are the same. The branches duplicated by the
+/// reconstruction of the control-flow come from the same blocks: they are
+/// equal, including their meta information.
+fn same_statements(st1: &Statement, st2: &Statement) -> bool {
+    st1 == st2
+}
+
+/// The shape of an encoding of a lazy boolean operation.
+enum Shape {
+    /// The boolean is assigned to a place.
+    Value,
+    /// The boolean is the condition of a switch.
+    Nested,
+}
+
+/// Look for an encoding of `&&` or `||` in the switch `if cond { st1 } else { st2 }`.
+fn recognize(cond: &Operand, st1: &Statement, st2: &Statement) -> Option<(BinOp, Shape)> {
+    let cond_reads = |x: &Place| match cond {
+        Operand::Copy(p) | Operand::Move(p) => p.var_id == x.var_id,
+        Operand::Const(_) => false,
+    };
+    let assigns = |st: &Statement, x: &Place| match &st.content {
+        RawStatement::Assign(y, rv) => x == y && !cond_reads(x) && is_pure_rvalue(rv),
+        _ => false,
+    };
+
+    // `&&`, used as a value
+    if let Some(x) = assigned_bool_const(st2, false)
+        && let Some(last) = split_pure_prefix(cond, st1)
+        && assigns(last, x)
+    {
+        return Some((BinOp::And, Shape::Value));
+    }
+    // `||`, used as a value
+    if let Some(x) = assigned_bool_const(st1, true)
+        && let Some(last) = split_pure_prefix(cond, st2)
+        && assigns(last, x)
+    {
+        return Some((BinOp::Or, Shape::Value));
+    }
+    // `&&`, used as a condition
+    if let Some(last) = split_pure_prefix(cond, st1)
+        && let Some((_, else_st)) = if_branches(last)
+        && same_statements(else_st, st2)
+    {
+        return Some((BinOp::And, Shape::Nested));
+    }
+    // `||`, used as a condition
+    if let Some(last) = split_pure_prefix(cond, st2)
+        && let Some((then_st, _)) = if_branches(last)
+        && same_statements(then_st, st1)
+    {
+        return Some((BinOp::Or, Shape::Nested));
+    }
+    None
+}
+
+fn transform_st(locals: &mut VarId::Vector<Var>, st: &mut Statement) -> Option<Vec<Statement>> {
+    let RawStatement::Switch(Switch::If(cond, st1, st2)) = &st.content else {
+        return None;
+    };
+    let (binop, shape) = recognize(cond, st1, st2)?;
+
+    // Decompose the switch. The branch which evaluates the second operand
+    // is the "long" branch: we drop the other one.
+    let (cond, st1, st2) = std::mem::replace(&mut st.content, RawStatement::Nop)
+        .to_switch()
+        .to_if();
+    let long_branch = match binop {
+        BinOp::And => *st1,
+        _ => *st2,
+    };
+    let mut before = into_branch_statements(long_branch);
+    let last = before.pop().unwrap();
+
+    st.content = match (shape, last.content) {
+        (Shape::Value, RawStatement::Assign(x, rv)) => {
+            let op = match rv {
+                Rvalue::Use(op) => op,
+                rv => {
+                    // The second operand is not an operand: we evaluate it
+                    // first (it is pure).
+                    before.push(Statement::new(
+                        last.meta,
+                        RawStatement::Assign(x.clone(), rv),
+                    ));
+                    Operand::Move(x.clone())
+                }
+            };
+            RawStatement::Assign(x, Rvalue::BinaryOp(binop, cond, op))
+        }
+        (Shape::Nested, RawStatement::Switch(Switch::If(op, then_st, else_st))) => {
+            let tmp = Place::new(locals.fresh_var(None, Ty::Literal(LiteralTy::Bool)));
+            let rv = Rvalue::BinaryOp(binop, cond, op);
+            before.push(Statement::new(
+                st.meta,
+                RawStatement::Assign(tmp.clone(), rv),
+            ));
+            RawStatement::Switch(Switch::If(Operand::Move(tmp), then_st, else_st))
+        }
+        _ => unreachable!(),
+    };
+    Some(before)
+}
+
+pub fn transform(ctx: &mut TransCtx, funs: &mut FunDecls, globals: &mut GlobalDecls) {
+    ctx.iter_bodies(funs, globals, |ctx, name, b| {
+        let fmt_ctx = ctx.into_fmt();
+        trace!(
+            "# About to reconstruct the boolean operations in decl: {}\n{}",
+            name.fmt_with_ctx(&fmt_ctx),
+            fmt_ctx.format_object(&*b)
+        );
+        let locals = &mut b.locals;
+        b.body.transform(&mut |st| transform_st(locals, st));
+    })
+}
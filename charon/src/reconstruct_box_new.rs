@@ -0,0 +1,298 @@
+//! # Micro-pass: reconstruct the calls to `Box::new`.
+//!
+//! The MIR doesn't always call `Box::new` to create a box: this happens when
+//! the code uses `#[rustc_box]` (which is the case of the expansion of
+//! `vec![...]`), or in the optimized MIR once `Box::new` is inlined. The MIR
+//! then allocates the memory, and initializes it in place:
+//! ```text
+//! size := size_of<T>
+//! align := align_of<T>
+//! ptr := alloc::alloc::exchange_malloc(move size, move align) -> bb1
+//!
+//! bb1:
+//! b := shallow_init_box<T>(move ptr)
+//! ...
+//! *b := v
+//! ```
+//! We simplify this pattern to a call to [AssumedFunId::BoxNew]:
+//! ```text
+//! ...
+//! tmp := v
+//! b := @BoxNew<T>(move tmp) -> bb1
+//!
+//! bb1:
+//! ```
+//!
+//! We leave the pattern unchanged if we don't recognize it (if the box is used
+//! before being initialized, for instance). Note that we do this on the ULLBC,
+//! because the pattern spans several blocks.
+use crate::assumed;
+use crate::expressions::{NullOp, Operand, Place, ProjectionElem, Rvalue, SharedExprVisitor};
+use crate::formatter::{Formatter, IntoFormatter};
+use crate::translate_ctx::TransCtx;
+use crate::types::*;
+use crate::ullbc_ast::*;
+use crate::values::VarId;
+use std::collections::HashMap;
+
+/// Check if a variable appears in a statement.
+struct MentionsVar {
+    var_id: VarId::Id,
+    found: bool,
+}
+
+impl SharedTypeVisitor for MentionsVar {}
+
+impl SharedExprVisitor for MentionsVar {
+    fn visit_var_id(&mut self, id: &VarId::Id) {
+        if *id == self.var_id {
+            self.found = true;
+        }
+    }
+}
+
+impl SharedAstVisitor for MentionsVar {}
+
+fn statement_mentions_var(var_id: VarId::Id, st: &Statement) -> bool {
+    let mut visitor = MentionsVar {
+        var_id,
+        found: false,
+    };
+    visitor.visit_statement(st);
+    visitor.found
+}
+
+fn rvalue_mentions_var(var_id: VarId::Id, rv: &Rvalue) -> bool {
+    let mut visitor = MentionsVar {
+        var_id,
+        found: false,
+    };
+    visitor.visit_rvalue(rv);
+    visitor.found
+}
+
+/// Check if a place is of the shape `*b` (we allow field projections before
+/// the dereference, as the optimized MIR dereferences the pointer stored
+/// inside the box).
+fn is_deref_of(p: &Place, b: VarId::Id) -> bool {
+    p.var_id == b
+        && matches!(
+            p.projection.last(),
+            Some(ProjectionElem::Deref | ProjectionElem::DerefBox | ProjectionElem::DerefRawPtr)
+        )
+}
+
+/// Count the number of predecessors of every block.
+struct CountPredecessors {
+    count: HashMap<BlockId::Id, usize>,
+}
+
+impl SharedTypeVisitor for CountPredecessors {}
+impl SharedExprVisitor for CountPredecessors {}
+
+impl SharedAstVisitor for CountPredecessors {
+    fn visit_block_id(&mut self, id: &BlockId::Id) {
+        *self.count.entry(*id).or_insert(0) += 1;
+    }
+}
+
+fn count_predecessors(body: &ExprBody) -> HashMap<BlockId::Id, usize> {
+    let mut visitor = CountPredecessors {
+        count: HashMap::new(),
+    };
+    for block in body.body.iter() {
+        visitor.visit_terminator(&block.terminator);
+    }
+    visitor.count
+}
+
+/// Check if a function is `alloc::alloc::exchange_malloc`.
+fn is_exchange_malloc(ctx: &TransCtx, func: &FnOperand) -> bool {
+    match func {
+        FnOperand::Regular(FnPtr {
+            func: FunIdOrTraitMethodRef::Fun(FunId::Regular(fid)),
+            ..
+        }) => ctx.fun_decls.get(*fid).map_or(false, |d| {
+            d.name.equals_ref_name(&assumed::EXCHANGE_MALLOC_NAME)
+        }),
+        _ => false,
+    }
+}
+
+/// If the operand is the move or the copy of a local variable, return it.
+fn as_var(op: &Operand) -> Option<VarId::Id> {
+    match op {
+        Operand::Move(p) | Operand::Copy(p) if p.projection.is_empty() => Some(p.var_id),
+        _ => None,
+    }
+}
+
+/// The pattern we found (see the explanations at the top of the file).
+struct BoxNew {
+    /// The block which calls `exchange_malloc`
+    block_id: BlockId::Id,
+    /// The block in which we initialize the box
+    target: BlockId::Id,
+    /// The size and the alignment
+    layout_vars: Vec<VarId::Id>,
+    /// The box
+    dest: Place,
+    /// The type of the boxed value
+    ty: Ty,
+    /// The index of the assignment `*b := v` in the target block
+    init_index: usize,
+}
+
+/// Look for the pattern, starting from a block which calls `exchange_malloc`.
+fn find_box_new(
+    ctx: &TransCtx,
+    body: &ExprBody,
+    predecessors: &HashMap<BlockId::Id, usize>,
+    block_id: BlockId::Id,
+) -> Option<BoxNew> {
+    let block = body.body.get(block_id).unwrap();
+    let (call, target) = match &block.terminator.content {
+        RawTerminator::Call { call, target, .. } if is_exchange_malloc(ctx, &call.func) => {
+            (call, *target)
+        }
+        _ => return None,
+    };
+    if !call.dest.projection.is_empty() || predecessors.get(&target) != Some(&1) {
+        return None;
+    }
+    let ptr = call.dest.var_id;
+
+    // The first statement of the target must be: `b := shallow_init_box<T>(move ptr)`
+    let target_block = body.body.get(target).unwrap();
+    let (dest, ty) = match target_block.statements.first().map(|st| &st.content) {
+        Some(RawStatement::Assign(dest, Rvalue::ShallowInitBox(op, ty)))
+            if as_var(op) == Some(ptr) && dest.projection.is_empty() =>
+        {
+            (dest.clone(), ty.clone())
+        }
+        _ => return None,
+    };
+    let b = dest.var_id;
+
+    // Look for the initialization `*b := v`: the box must not be used before
+    let mut init_index = None;
+    for (i, st) in target_block.statements.iter().enumerate().skip(1) {
+        if let RawStatement::Assign(p, rv) = &st.content && is_deref_of(p, b) {
+            // The boxed value must not refer to the box itself
+            if rvalue_mentions_var(b, rv) {
+                return None;
+            }
+            init_index = Some(i);
+            break;
+        }
+        if statement_mentions_var(b, st) || statement_mentions_var(ptr, st) {
+            return None;
+        }
+    }
+    let init_index = init_index?;
+
+    // Retrieve the variables which store the size and the alignment, so that
+    // we can remove their assignments
+    let layout_vars = call.args.iter().filter_map(as_var).collect();
+
+    Some(BoxNew {
+        block_id,
+        target,
+        layout_vars,
+        dest,
+        ty,
+        init_index,
+    })
+}
+
+/// Rewrite the pattern.
+fn reconstruct(body: &mut ExprBody, box_new: BoxNew) {
+    let BoxNew {
+        block_id,
+        target,
+        layout_vars,
+        dest,
+        ty,
+        init_index,
+    } = box_new;
+
+    // Remove the prefix of the target block, up to the initialization (we
+    // move the intermediate statements to the calling block)
+    let target_block = body.body.get_mut(target).unwrap();
+    let mut prefix: Vec<Statement> = target_block.statements.drain(0..=init_index).collect();
+    let init = prefix.pop().unwrap();
+    // Remove the shallow initialization
+    prefix.remove(0);
+
+    // Introduce a temporary for the boxed value if needed
+    let (init_meta, init_rv) = match init.content {
+        RawStatement::Assign(_, rv) => (init.meta, rv),
+        _ => unreachable!(),
+    };
+    let arg = match init_rv {
+        Rvalue::Use(op) => op,
+        rv => {
+            let tmp = body.locals.fresh_var(None, ty.clone());
+            prefix.push(Statement::new(
+                init_meta,
+                RawStatement::Assign(Place::new(tmp), rv),
+            ));
+            Operand::Move(Place::new(tmp))
+        }
+    };
+
+    // Update the calling block
+    let block = body.body.get_mut(block_id).unwrap();
+    block.statements.retain(|st| match &st.content {
+        RawStatement::Assign(p, Rvalue::Nullary(NullOp::SizeOf | NullOp::AlignOf, _)) => {
+            !(p.projection.is_empty() && layout_vars.contains(&p.var_id))
+        }
+        _ => true,
+    });
+    block.statements.append(&mut prefix);
+    let unwind = match &block.terminator.content {
+        RawTerminator::Call { unwind, .. } => *unwind,
+        _ => unreachable!(),
+    };
+    let call = Call {
+        func: FnOperand::Regular(FnPtr {
+            func: FunIdOrTraitMethodRef::mk_assumed(AssumedFunId::BoxNew),
+            generics: GenericArgs::new_from_types(vec![ty]),
+            trait_and_method_generic_args: None,
+        }),
+        args: vec![arg],
+        dest,
+    };
+    block.terminator.content = RawTerminator::Call {
+        call,
+        target,
+        unwind,
+    };
+}
+
+pub fn transform(ctx: &mut TransCtx) {
+    // Slightly annoying: we have to clone because of borrowing issues
+    let mut fun_decls = ctx.fun_decls.clone();
+    let mut global_decls = ctx.global_decls.clone();
+
+    ctx.iter_bodies(&mut fun_decls, &mut global_decls, |ctx, name, b| {
+        let fmt_ctx = ctx.into_fmt();
+        trace!(
+            "# About to reconstruct the calls to Box::new in decl: {}:\n{}",
+            name.fmt_with_ctx(&fmt_ctx),
+            fmt_ctx.format_object(&*b)
+        );
+
+        // The rewritings don't change the predecessors of the blocks
+        let predecessors = count_predecessors(b);
+        let block_ids: Vec<BlockId::Id> = b.body.iter_indices().collect();
+        for block_id in block_ids {
+            if let Some(box_new) = find_box_new(ctx, b, &predecessors, block_id) {
+                reconstruct(b, box_new);
+            }
+        }
+    });
+
+    ctx.fun_decls = fun_decls;
+    ctx.global_decls = global_decls;
+}
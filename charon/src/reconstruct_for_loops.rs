@@ -0,0 +1,271 @@
+//! # Micro-pass (optional, see [crate::cli_options::CliOpts::reconstruct_for_loops]):
+//! reconstruct the `for` loops.
+//!
+//! rustc desugars the `for` loops to calls to `IntoIterator::into_iter` and
+//! `Iterator::next` (see [crate::llbc_ast::ForLoop]). After the reconstruction
+//! of the control-flow, `for x in e { body }` gives:
+//! ```text
+//! tmp = into_iter(move e);
+//! iter = move tmp;
+//! loop {
+//!   r = &mut iter;
+//!   r1 = &mut *r;
+//!   next = next(move r1);
+//!   match next {
+//!     None => break 0,
+//!     Some => { x = move (next as Some).0; body }
+//!   }
+//! }
+//! ```
+//! which we rewrite to `for x in (iter := into_iter(move e)) { body }`.
+//!
+//! We must apply this pass after [crate::remove_read_discriminant] (we need the
+//! matches). The temporaries which are not used anymore are removed by
+//! [crate::remove_unused_locals].
+use crate::expressions::*;
+use crate::formatter::{Formatter, IntoFormatter};
+use crate::gast::Var;
+use crate::llbc_ast::*;
+use crate::meta_utils::combine_meta;
+use crate::reconstruct_boolean_ops::branch_statements;
+use crate::remove_unused_locals::ComputeUsedLocals;
+use crate::translate_ctx::TransCtx;
+use crate::types::*;
+use crate::values::VarId;
+
+const INTO_ITERATOR: [&str; 5] = ["core", "iter", "traits", "collect", "IntoIterator"];
+const ITERATOR: [&str; 5] = ["core", "iter", "traits", "iterator", "Iterator"];
+const OPTION: [&str; 3] = ["core", "option", "Option"];
+
+/// If the statement is a call of the form `x = Trait::method(arg)`, where `x`
+/// is a local, return the function, the argument and `x`.
+pub(crate) fn trait_method_call<'a>(
+    ctx: &TransCtx,
+    st: &'a Statement,
+    trait_name: &[&str],
+    method: &str,
+) -> Option<(&'a FnPtr, &'a Operand, VarId::Id)> {
+    let RawStatement::Call(Call {
+        func: FnOperand::Regular(fn_ptr),
+        args,
+        dest,
+    }) = &st.content
+    else {
+        return None;
+    };
+    let FunIdOrTraitMethodRef::Trait(trait_ref, method_name, _) = &fn_ptr.func else {
+        return None;
+    };
+    let trait_decl = ctx.trait_decls.get(trait_ref.trait_decl_ref.trait_id)?;
+    let [arg] = args.as_slice() else {
+        return None;
+    };
+    (trait_decl.name.equals_ref_name(trait_name)
+        && method_name.0 == method
+        && dest.projection.is_empty())
+    .then_some((fn_ptr, arg, dest.var_id))
+}
+
+/// Check if the statement is `break 0`.
+fn is_break_outer(st: &Statement) -> bool {
+    matches!(
+        branch_statements(st).as_slice(),
+        [Statement {
+            content: RawStatement::Break(0, _),
+            ..
+        }]
+    )
+}
+
+/// Check if the type of a local is `Option`, and return its id.
+fn option_local(
+    ctx: &TransCtx,
+    locals: &VarId::Vector<Var>,
+    var_id: VarId::Id,
+) -> Option<TypeDeclId::Id> {
+    let Ty::Adt(TypeId::Adt(id), _) = &locals.get(var_id)?.ty else {
+        return None;
+    };
+    let decl = ctx.type_decls.get(*id)?;
+    decl.name.equals_ref_name(&OPTION).then_some(*id)
+}
+
+/// Look for the desugaring of the body of a `for` loop over the iterator stored
+/// in `iter_var`. Return the function which retrieves the next item, the binder
+/// and the body of the user loop.
+fn recognize_loop_body(
+    ctx: &TransCtx,
+    locals: &VarId::Vector<Var>,
+    iter_var: VarId::Id,
+    body: &Statement,
+) -> Option<(FnPtr, Option<Place>, Statement)> {
+    let sts = branch_statements(body);
+
+    // The (re)borrows of the iterator
+    let mut temps = Vec::new();
+    let mut borrowed = Place::new(iter_var);
+    for st in &sts {
+        match &st.content {
+            RawStatement::Assign(p, Rvalue::Ref(q, BorrowKind::Mut))
+                if p.projection.is_empty() && *q == borrowed =>
+            {
+                temps.push(p.var_id);
+                borrowed = Place {
+                    var_id: p.var_id,
+                    projection: vec![ProjectionElem::Deref],
+                };
+            }
+            _ => break,
+        }
+    }
+    let borrow = *temps.last()?;
+
+    // The call to `next`, followed by the match over its result
+    let [call, switch] = &sts[temps.len()..] else {
+        return None;
+    };
+    let (next, arg, next_var) = trait_method_call(ctx, call, &ITERATOR, "next")?;
+    if !matches!(arg, Operand::Move(p) if *p == Place::new(borrow)) {
+        return None;
+    }
+    let option_id = option_local(ctx, locals, next_var)?;
+    let RawStatement::Switch(Switch::Match(scrut, branches, otherwise)) = &switch.content else {
+        return None;
+    };
+    if *scrut != Place::new(next_var)
+        || !otherwise.as_ref().map_or(true, |st| st.content.is_panic())
+    {
+        return None;
+    }
+    let [(ids0, st0), (ids1, st1)] = branches.as_slice() else {
+        return None;
+    };
+    let (some_ids, some_branch) = match (is_break_outer(st0), is_break_outer(st1)) {
+        (true, false) => (ids1, st1),
+        (false, true) => (ids0, st0),
+        _ => return None,
+    };
+    let &[some_variant] = some_ids.as_slice() else {
+        return None;
+    };
+
+    // The binder: the branch of `Some` starts by reading the content of the
+    // option (unless the items are ignored)
+    let mut user_body: Vec<Statement> = branch_statements(some_branch)
+        .into_iter()
+        .cloned()
+        .collect();
+    let content = Place {
+        var_id: next_var,
+        projection: vec![ProjectionElem::Field(
+            FieldProjKind::Adt(option_id, Some(some_variant)),
+            FieldId::Id::new(0),
+        )],
+    };
+    let binder = match user_body.first().map(|st| &st.content) {
+        Some(RawStatement::Assign(binder, Rvalue::Use(Operand::Move(p) | Operand::Copy(p))))
+            if *p == content =>
+        {
+            let binder = binder.clone();
+            user_body.remove(0);
+            Some(binder)
+        }
+        _ => None,
+    };
+    let user_body = new_block(some_branch.meta, user_body);
+
+    // The body of the user loop must not use the locals introduced by the
+    // desugaring
+    let used = ComputeUsedLocals::compute_in_statement(&user_body);
+    if temps
+        .iter()
+        .chain([&next_var, &iter_var])
+        .any(|id| used.contains_key(id))
+    {
+        return None;
+    }
+    Some((next.clone(), binder, user_body))
+}
+
+/// Look for a `for` loop, whose loop statement is `sts[i]`. If we find one,
+/// return the indices of the statements which prepare the iterator (which
+/// we must remove) and the reconstructed loop.
+fn recognize_for_loop(
+    ctx: &TransCtx,
+    locals: &VarId::Vector<Var>,
+    sts: &[Statement],
+    i: usize,
+) -> Option<(Vec<usize>, ForLoop)> {
+    let RawStatement::Loop(body, label) = &sts[i].content else {
+        return None;
+    };
+
+    // The call to `into_iter`, whose result may be moved to another local
+    let mut prev = (0..i).rev().filter(|j| !sts[*j].content.is_nop());
+    let j = prev.next()?;
+    let (moved, call_idx) = match &sts[j].content {
+        RawStatement::Assign(p, Rvalue::Use(Operand::Move(q)))
+            if p.projection.is_empty() && q.projection.is_empty() =>
+        {
+            (Some((p.var_id, q.var_id)), prev.next()?)
+        }
+        _ => (None, j),
+    };
+    let (into_iter, iter, tmp) =
+        trait_method_call(ctx, &sts[call_idx], &INTO_ITERATOR, "into_iter")?;
+    let (iter_var, removed) = match moved {
+        Some((iter_var, src)) if src == tmp => (iter_var, vec![call_idx, j]),
+        Some(_) => return None,
+        None => (tmp, vec![call_idx]),
+    };
+
+    // The temporary holding the result of `into_iter` must not be used after
+    // the loop
+    if iter_var != tmp
+        && sts[i + 1..]
+            .iter()
+            .any(|st| ComputeUsedLocals::compute_in_statement(st).contains_key(&tmp))
+    {
+        return None;
+    }
+
+    let (next, binder, body) = recognize_loop_body(ctx, locals, iter_var, body)?;
+    let for_loop = ForLoop {
+        iter: iter.clone(),
+        iter_var,
+        into_iter: into_iter.clone(),
+        next,
+        binder,
+        body: Box::new(body),
+        label: *label,
+    };
+    Some((removed, for_loop))
+}
+
+fn transform_block(ctx: &TransCtx, locals: &VarId::Vector<Var>, sts: &mut [Statement]) {
+    for i in 0..sts.len() {
+        let Some((removed, for_loop)) = recognize_for_loop(ctx, locals, sts, i) else {
+            continue;
+        };
+        for j in removed {
+            sts[i].meta = combine_meta(&sts[j].meta, &sts[i].meta);
+            sts[j].content = RawStatement::Nop;
+        }
+        sts[i].content = RawStatement::ForLoop(for_loop);
+    }
+}
+
+pub fn transform(ctx: &mut TransCtx, funs: &mut FunDecls, globals: &mut GlobalDecls) {
+    ctx.iter_bodies(funs, globals, |ctx, name, b| {
+        let fmt_ctx = ctx.into_fmt();
+        trace!(
+            "# About to reconstruct the for loops in decl: {}:\n{}",
+            name.fmt_with_ctx(&fmt_ctx),
+            fmt_ctx.format_object(&*b)
+        );
+        let ctx = &*ctx;
+        let locals = &b.locals;
+        b.body
+            .transform_blocks(&mut |sts| transform_block(ctx, locals, sts));
+    })
+}
@@ -0,0 +1,201 @@
+//! # Micro-pass (optional, see [crate::cli_options::CliOpts::reconstruct_question_marks]):
+//! reconstruct the applications of the `?` operator.
+//!
+//! rustc desugars the `?` operator to calls to `Try::branch` and
+//! `FromResidual::from_residual` (see [crate::llbc_ast::QuestionMark]). After
+//! the reconstruction of the control-flow, `x = e?; s` gives:
+//! ```text
+//! cf = branch(move e);
+//! match cf {
+//!   Continue => { x = move (cf as Continue).0; s }
+//!   Break => {
+//!     r = move (cf as Break).0;
+//!     @return = from_residual(move r);
+//!     return
+//!   }
+//! }
+//! ```
+//! which we rewrite to `x := branch(move e)?; s`. This is a lot more readable
+//! for the code which propagates errors a lot.
+//!
+//! We must apply this pass after [crate::remove_read_discriminant] (we need the
+//! matches). Note that we don't rewrite the early returns which drop values
+//! before returning: the dedicated statement can't express those drops.
+use crate::expressions::*;
+use crate::formatter::{Formatter, IntoFormatter};
+use crate::gast::Var;
+use crate::llbc_ast::*;
+use crate::meta_utils::combine_meta;
+use crate::reconstruct_boolean_ops::branch_statements;
+use crate::reconstruct_for_loops::trait_method_call;
+use crate::remove_unused_locals::ComputeUsedLocals;
+use crate::translate_ctx::TransCtx;
+use crate::types::*;
+use crate::values::VarId;
+
+const TRY: [&str; 4] = ["core", "ops", "try_trait", "Try"];
+const FROM_RESIDUAL: [&str; 4] = ["core", "ops", "try_trait", "FromResidual"];
+const CONTROL_FLOW: [&str; 4] = ["core", "ops", "control_flow", "ControlFlow"];
+
+/// The place `(p as variant).0`.
+fn variant_content(var_id: VarId::Id, type_id: TypeDeclId::Id, variant: VariantId::Id) -> Place {
+    Place {
+        var_id,
+        projection: vec![ProjectionElem::Field(
+            FieldProjKind::Adt(type_id, Some(variant)),
+            FieldId::Id::new(0),
+        )],
+    }
+}
+
+/// If the statement is of the shape `x = move p`, where `x` is a local, return
+/// `x`.
+fn moved_to_local(st: &Statement, p: &Place) -> Option<Place> {
+    match &st.content {
+        RawStatement::Assign(x, Rvalue::Use(Operand::Move(q)))
+            if q == p && x.projection.is_empty() =>
+        {
+            Some(x.clone())
+        }
+        _ => None,
+    }
+}
+
+/// If the type of a local is `ControlFlow`, return the type id, and the ids of
+/// the variants `Continue` and `Break`.
+fn control_flow_local(
+    ctx: &TransCtx,
+    locals: &VarId::Vector<Var>,
+    var_id: VarId::Id,
+) -> Option<(TypeDeclId::Id, VariantId::Id, VariantId::Id)> {
+    let Ty::Adt(TypeId::Adt(id), _) = &locals.get(var_id)?.ty else {
+        return None;
+    };
+    let decl = ctx.type_decls.get(*id)?;
+    let TypeDeclKind::Enum(variants) = &decl.kind else {
+        return None;
+    };
+    if !decl.name.equals_ref_name(&CONTROL_FLOW) {
+        return None;
+    }
+    let variant = |name: &str| {
+        variants
+            .iter_indexed_values()
+            .find(|(_, v)| v.name == name)
+            .map(|(id, _)| id)
+    };
+    Some((*id, variant("Continue")?, variant("Break")?))
+}
+
+/// Check if the branch taken when the operand is a residual has the expected
+/// shape, and return the function which converts the residual.
+fn recognize_break_branch(ctx: &TransCtx, residual: &Place, st: &Statement) -> Option<FnPtr> {
+    let [mv, call, ret] = branch_statements(st).as_slice() else {
+        return None;
+    };
+    let r = moved_to_local(mv, residual)?;
+    let (from_residual, arg, dest) = trait_method_call(ctx, call, &FROM_RESIDUAL, "from_residual")?;
+    if !matches!(arg, Operand::Move(p) if *p == r)
+        || dest != VarId::Id::new(0)
+        || !ret.content.is_return()
+    {
+        return None;
+    }
+    Some(from_residual.clone())
+}
+
+/// Look for an application of the `?` operator, whose match is `sts[i]`. If we
+/// find one, return the index of the call to `branch`, the reconstructed
+/// statement, and the statements we must execute after it.
+fn recognize(
+    ctx: &TransCtx,
+    locals: &VarId::Vector<Var>,
+    sts: &[Statement],
+    i: usize,
+) -> Option<(usize, QuestionMark, Vec<Statement>)> {
+    let RawStatement::Switch(Switch::Match(scrut, branches, otherwise)) = &sts[i].content else {
+        return None;
+    };
+    if !scrut.projection.is_empty() || otherwise.is_some() {
+        return None;
+    }
+
+    // The call to `branch`
+    let call_idx = (0..i).rev().find(|j| !sts[*j].content.is_nop())?;
+    let (branch, op, cf) = trait_method_call(ctx, &sts[call_idx], &TRY, "branch")?;
+    if cf != scrut.var_id {
+        return None;
+    }
+
+    // The branches
+    let (type_id, continue_id, break_id) = control_flow_local(ctx, locals, cf)?;
+    let branch_of = |variant: VariantId::Id| {
+        branches
+            .iter()
+            .find(|(ids, _)| ids.as_slice() == [variant])
+            .map(|(_, st)| st)
+    };
+    let continue_branch = branch_of(continue_id)?;
+    let break_branch = branch_of(break_id)?;
+    let residual = variant_content(cf, type_id, break_id);
+    let from_residual = recognize_break_branch(ctx, &residual, break_branch)?;
+
+    // The output: the branch taken when we continue starts by reading it
+    // (unless it is ignored)
+    let mut rest: Vec<Statement> = branch_statements(continue_branch)
+        .into_iter()
+        .cloned()
+        .collect();
+    let output = variant_content(cf, type_id, continue_id);
+    let dest = rest.first().and_then(|st| moved_to_local(st, &output));
+    if dest.is_some() {
+        rest.remove(0);
+    }
+
+    // The control-flow value must not be used afterwards
+    if rest
+        .iter()
+        .chain(&sts[i + 1..])
+        .any(|st| ComputeUsedLocals::compute_in_statement(st).contains_key(&cf))
+    {
+        return None;
+    }
+
+    let question_mark = QuestionMark {
+        op: op.clone(),
+        branch: branch.clone(),
+        from_residual,
+        dest,
+    };
+    Some((call_idx, question_mark, rest))
+}
+
+fn transform_block(ctx: &TransCtx, locals: &VarId::Vector<Var>, sts: &mut Vec<Statement>) {
+    // We insert the statements executed after the `?` in the block: we
+    // explore them in the following iterations (they may contain other `?`)
+    let mut i = 0;
+    while i < sts.len() {
+        if let Some((call_idx, question_mark, rest)) = recognize(ctx, locals, sts, i) {
+            let meta = combine_meta(&sts[call_idx].meta, &sts[i].meta);
+            sts[call_idx].content = RawStatement::Nop;
+            let st = Statement::new(meta, RawStatement::QuestionMark(question_mark));
+            let _ = sts.splice(i..i + 1, std::iter::once(st).chain(rest));
+        }
+        i += 1;
+    }
+}
+
+pub fn transform(ctx: &mut TransCtx, funs: &mut FunDecls, globals: &mut GlobalDecls) {
+    ctx.iter_bodies(funs, globals, |ctx, name, b| {
+        let fmt_ctx = ctx.into_fmt();
+        trace!(
+            "# About to reconstruct the question marks in decl: {}:\n{}",
+            name.fmt_with_ctx(&fmt_ctx),
+            fmt_ctx.format_object(&*b)
+        );
+        let ctx = &*ctx;
+        let locals = &b.locals;
+        b.body
+            .transform_blocks(&mut |sts| transform_block(ctx, locals, sts));
+    })
+}
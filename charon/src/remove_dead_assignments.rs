@@ -0,0 +1,104 @@
+//! # Micro-pass: remove the assignments to locals which are never read.
+//!
+//! After the previous simplifications, many locals are written but never read
+//! (the unit temporaries, the results of the operations whose checks were
+//! removed, etc.). We remove the assignments to those locals, provided that
+//! evaluating the assigned value can't fail and has no side effects. Removing
+//! an assignment may make other locals dead: we iterate until we reach a fixed
+//! point.
+//!
+//! The locals which are not used anymore are then removed (and the remaining
+//! locals renumbered) by [crate::remove_unused_locals].
+use crate::expressions::*;
+use crate::formatter::{Formatter, IntoFormatter};
+use crate::id_vector::ToUsize;
+use crate::llbc_ast::*;
+use crate::reconstruct_boolean_ops::{is_pure_operand, is_pure_rvalue};
+use crate::translate_ctx::TransCtx;
+use crate::types::*;
+use crate::values::*;
+use std::collections::HashSet;
+
+/// Compute the set of locals which are read.
+///
+/// A local which is only (entirely) overwritten is not read. We consider all
+/// the other occurrences of the locals (partial updates, drops, etc.) as reads.
+struct ComputeReadLocals {
+    read: HashSet<VarId::Id>,
+}
+
+impl SharedTypeVisitor for ComputeReadLocals {}
+
+impl SharedExprVisitor for ComputeReadLocals {
+    fn visit_var_id(&mut self, id: &VarId::Id) {
+        let _ = self.read.insert(*id);
+    }
+}
+
+impl SharedAstVisitor for ComputeReadLocals {
+    fn spawn(&mut self, visitor: &mut dyn FnMut(&mut Self)) {
+        visitor(self)
+    }
+
+    fn merge(&mut self) {}
+
+    fn visit_assign(&mut self, p: &Place, rv: &Rvalue) {
+        if !p.projection.is_empty() {
+            self.visit_place(p);
+        }
+        self.visit_rvalue(rv);
+    }
+}
+
+/// Compute the set of locals which are read in a body (see [ComputeReadLocals]).
+pub(crate) fn compute_read_locals(body: &Statement) -> HashSet<VarId::Id> {
+    let mut visitor = ComputeReadLocals {
+        read: HashSet::new(),
+    };
+    visitor.visit_statement(body);
+    visitor.read
+}
+
+/// Return `true` if we can remove an assignment of this rvalue (because
+/// evaluating it can't fail and has no side effects).
+fn is_removable_rvalue(rv: &Rvalue) -> bool {
+    match rv {
+        Rvalue::Aggregate(_, ops) => ops.iter().all(is_pure_operand),
+        Rvalue::Global(_) | Rvalue::Nullary(..) => true,
+        _ => is_pure_rvalue(rv),
+    }
+}
+
+/// Remove the dead assignments. Return `true` if we changed something.
+pub(crate) fn remove_dead_assignments(arg_count: usize, body: &mut Statement) -> bool {
+    let read = compute_read_locals(body);
+
+    let mut changed = false;
+    body.transform(&mut |st: &mut Statement| {
+        if let RawStatement::Assign(p, rv) = &st.content
+            && p.projection.is_empty()
+            // We never remove the assignments to the return value and to the
+            // input arguments
+            && p.var_id.to_usize() > arg_count
+            && !read.contains(&p.var_id)
+            && is_removable_rvalue(rv)
+        {
+            st.content = RawStatement::Nop;
+            changed = true;
+        }
+        None
+    });
+    changed
+}
+
+pub fn transform(ctx: &mut TransCtx, funs: &mut FunDecls, globals: &mut GlobalDecls) {
+    ctx.iter_bodies(funs, globals, |ctx, name, b| {
+        let fmt_ctx = ctx.into_fmt();
+        trace!(
+            "# About to remove the dead assignments in decl: {}:\n{}",
+            name.fmt_with_ctx(&fmt_ctx),
+            fmt_ctx.format_object(&*b)
+        );
+        while remove_dead_assignments(b.arg_count, &mut b.body) {}
+    })
+}
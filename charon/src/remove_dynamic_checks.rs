@@ -20,277 +20,345 @@ impl<'tcx, 'ctx, 'a> MutExprVisitor for RemoveDynChecks<'tcx, 'ctx, 'a> {}
 
 /// Check that a statement is exactly:
 /// ```text
-/// assert(move p == expected)
+/// p := ...
 /// ```
-fn is_assert_move(p: &Place, s: &Statement, expected: bool) -> bool {
-    if let RawStatement::Assert(Assert {
-        cond: Operand::Move(ap),
-        expected: aexpected,
-    }) = &s.content
-    {
-        return ap == p && *aexpected == expected;
+/// and return the assigned rvalue.
+fn assigns<'a>(s: &'a Statement, p: &Place) -> Option<&'a Rvalue> {
+    match &s.content {
+        RawStatement::Assign(dest, rv) if dest == p => Some(rv),
+        _ => None,
     }
-    // Default
-    false
+}
+
+/// Check that a statement is exactly:
+/// ```text
+/// r := x op y // where op is `+`, `-` or `*`
+/// ```
+/// and return `r`.
+///
+/// Remark: we merge the checked and the unchecked binary operations when
+/// translating the MIR, so `r` is actually a pair (result, overflow flag).
+fn checked_binop_dest(s: &Statement) -> Option<&Place> {
+    match &s.content {
+        RawStatement::Assign(r, Rvalue::BinaryOp(BinOp::Add | BinOp::Sub | BinOp::Mul, _, _))
+            if r.projection.is_empty() =>
+        {
+            Some(r)
+        }
+        _ => None,
+    }
+}
+
+/// Check that a place is exactly `r.i`, where `r` is a pair, and return `i`.
+fn tuple_field_of(p: &Place, r: &Place) -> Option<usize> {
+    use crate::id_vector::ToUsize;
+    match p.projection[..] {
+        [ProjectionElem::Field(FieldProjKind::Tuple(2), fid)] if p.var_id == r.var_id => {
+            Some(fid.to_usize())
+        }
+        _ => None,
+    }
+}
+
+/// Check that a statement is exactly:
+/// ```text
+/// z := move r.0 // or: copy r.0
+/// ```
+fn is_first_field_use(s: &Statement, r: &Place) -> bool {
+    match &s.content {
+        RawStatement::Assign(_, Rvalue::Use(Operand::Move(p) | Operand::Copy(p))) => {
+            tuple_field_of(p, r) == Some(0)
+        }
+        _ => false,
+    }
+}
+
+/// Check that the statements start with:
+/// ```text
+/// r := x op y;
+/// z := move r.0;
+/// ...
+/// ```
+/// where `op` is an operation which can overflow.
+fn is_checked_binop_result(prefix: &[Statement]) -> bool {
+    match prefix {
+        [s0, s1, _, ..] => match checked_binop_dest(s0) {
+            Some(r) => is_first_field_use(s1, r),
+            None => false,
+        },
+        _ => false,
+    }
+}
+
+/// Collapse the statements starting at index `i`, of the shape described in
+/// [is_checked_binop_result], into:
+/// ```text
+/// z := x op y;
+/// ...
+/// ```
+fn collapse_checked_binop(sts: &mut Vec<Statement>, i: usize) {
+    let s1 = sts.remove(i + 1);
+    let (dest, _) = s1.content.to_assign();
+    take(&mut sts[i].content, |content| {
+        let (_, op) = content.to_assign();
+        RawStatement::Assign(dest, op)
+    });
+}
+
+/// Visitor to look for an access to an array/slice at a given index.
+struct FindIndexAccess<'a> {
+    /// The array/slice, if we know it
+    buf: Option<&'a Place>,
+    index: VarId::Id,
+    found: bool,
+}
+
+impl<'a> SharedTypeVisitor for FindIndexAccess<'a> {}
+
+impl<'a> SharedExprVisitor for FindIndexAccess<'a> {
+    fn visit_place(&mut self, p: &Place) {
+        for (i, pe) in p.projection.iter().enumerate() {
+            if let ProjectionElem::Index(index, _) = pe
+                && *index == self.index
+            {
+                let buf_matches = match self.buf {
+                    None => true,
+                    Some(buf) => buf.var_id == p.var_id && buf.projection[..] == p.projection[..i],
+                };
+                self.found = self.found || buf_matches;
+            }
+        }
+    }
+}
+
+/// Check that a statement accesses the array/slice `buf` (if we know it) at
+/// the given index (which must be a local variable).
+fn is_index_access(s: Option<&Statement>, buf: Option<&Place>, index: &Operand) -> bool {
+    let (Some(s), Operand::Copy(index) | Operand::Move(index)) = (s, index) else {
+        return false;
+    };
+    if !index.projection.is_empty() {
+        return false;
+    }
+    let mut visitor = FindIndexAccess {
+        buf,
+        index: index.var_id,
+        found: false,
+    };
+    match &s.content {
+        RawStatement::Assign(p, rv) => {
+            visitor.visit_place(p);
+            visitor.visit_rvalue(rv);
+        }
+        RawStatement::FakeRead(p) | RawStatement::Drop(p) => visitor.visit_place(p),
+        RawStatement::Call(call) => visitor.visit_call(call),
+        _ => (),
+    }
+    visitor.found
 }
 
 impl<'tcx, 'ctx, 'a> RemoveDynChecks<'tcx, 'ctx, 'a> {
     /// Return [true] if we simplified the statements, [false] otherwise.
-    /// TODO: we need a way of simplifying all this...
     ///
-    /// We simply detect sequences of the following shapes, and remove them:
-    /// # 1. Division/remainder/multiplication
-    /// ======================================
+    /// We look for an assertion introduced by rustc in the statements of the
+    /// block starting at index `i`, and use its kind to remove it together with the statements
+    /// computing its condition. We check the shapes below (the assertion is
+    /// always preceded by the computation of its condition):
+    /// # 1. Division/remainder by zero
+    /// ================================
     /// ```text
-    /// b := copy x == const 0
+    /// b := copy y == const 0
     /// assert(move b == false)
     /// ```
     ///
-    /// **Special case**: division/remainder for signed integers. Rust checks
-    /// that we don't have, for instance: `i32::min / (-1)`:
+    /// # 2. Overflow of a signed division/remainder
+    /// ============================================
+    /// Rust checks that we don't have, for instance: `i32::min / (-1)`:
     /// ```text
     /// b_y := y == const (-1)
     /// b_x := x == const INT::min
     /// b := move (b_y) & move (b_x)
     /// assert(move b == false)
-    /// z := x / y
     /// ```
     ///
-    /// # 2. Addition/substraction/multiplication.
-    /// ==========================================
+    /// # 3. Overflow of an addition/subtraction/multiplication
+    /// =======================================================
     /// In release mode, the rust compiler inserts assertions only inside the
-    /// body of global constants.
+    /// body of global constants. We collapse the checked operation and the
+    /// projection of its result into one assignment.
     /// ```text
     /// r := x + y;
     /// assert(move r.1 == false);
     /// z := move r.0;
     /// ```
+    /// Note that the assertion may have been removed by rustc if it could
+    /// prove that the operation doesn't overflow (at higher MIR levels).
+    /// Also note that the calls to the `wrapping_*` or `unchecked_*`
+    /// intrinsics are not checked: they are translated to function calls.
     ///
-    /// # 3. Arrays/slices
-    /// ==================
+    /// # 4. Overflow of a shift
+    /// ========================
     /// ```text
-    /// l := len(a)
-    /// b := copy x < copy l
-    /// assert(move b == true)
+    /// x := ...; // optional
+    /// b := move x < const 32; // or another constant
+    /// assert(move b == true);
     /// ```
     ///
-    /// # Shifts
-    /// ========
+    /// # 5. Overflow of a negation
+    /// ===========================
     /// ```text
-    /// x := ...;
-    /// b := move x < const 32; // or another constant
-    /// assert(move b == true);
+    /// b := copy x == const INT::min
+    /// assert(move b == false)
     /// ```
-    fn simplify(&mut self, s: &mut Statement) -> bool {
-        if let RawStatement::Sequence(s0, s1) = &s.content {
-            if let RawStatement::Sequence(s1, s2) = &s1.content {
-                // Arrays/Slices
-                if let (
-                    // s0 should be: `l := len(a)`
-                    RawStatement::Assign(dest_l_p, Rvalue::Len(..)),
-                    // s1 should be: `b := copy x < copy l`
-                    RawStatement::Assign(
-                        dest_b_p,
-                        Rvalue::BinaryOp(BinOp::Lt, _, Operand::Copy(l_op_place)),
-                    ),
-                    // s2
-                    RawStatement::Sequence(s2, _),
-                ) = (&s0.content, &s1.content, &s2.content)
-                {
-                    // s2 should be: `assert(move b == true)`
-                    if dest_l_p == l_op_place && is_assert_move(dest_b_p, s2, true) {
-                        // Eliminate the first three statements
-                        take(s, |s| {
-                            let (_, s1) = s.content.to_sequence();
-                            let (_, s2) = s1.content.to_sequence();
-                            let (_, s3) = s2.content.to_sequence();
-                            *s3
-                        });
-                        // A simplification happened
-                        return true;
-                    }
+    ///
+    /// # 6. Arrays/slices bounds checks
+    /// ================================
+    /// ```text
+    /// l := len(a) // if the length is not a constant
+    /// b := copy x < copy l
+    /// assert(move b == true)
+    /// ... a[x] ... // the statement performing the access
+    /// ```
+    /// We collapse this sequence into the statement which performs the access:
+    /// the bounds check is the precondition of the indexing operation (see
+    /// [AssertKind::IndexOutOfBounds]), which is taken into account in the
+    /// semantics of the array/slice index functions introduced by
+    /// [crate::index_to_function_calls].
+    ///
+    /// If the shape doesn't match, we don't simplify: the leftover assertion
+    /// is handled by [Self::visit_statement].
+    fn simplify(&mut self, sts: &mut Vec<Statement>, i: usize) -> bool {
+        // The longest shape has 4 statements before the statement following
+        // the assertion
+        let prefix = &sts[i..(i + 5).min(sts.len())];
+
+        // The overflow check of an addition/subtraction/multiplication may have
+        // been folded away (at higher MIR levels), in which case we only find
+        // the operation followed by the projection of its result.
+        if is_checked_binop_result(prefix) {
+            collapse_checked_binop(sts, i);
+            return true;
+        }
+
+        let Some(assert_index) = prefix.iter().position(|st| st.content.is_assert()) else {
+            return false;
+        };
+        let RawStatement::Assert(Assert {
+            cond: Operand::Move(b),
+            expected,
+            kind,
+        }) = &prefix[assert_index].content
+        else {
+            return false;
+        };
+        // The statements before the assertion
+        let before = &prefix[0..assert_index];
+
+        // Compute the number of statements to remove
+        let removed = match (kind, before) {
+            (
+                AssertKind::DivisionByZero | AssertKind::RemainderByZero | AssertKind::OverflowNeg,
+                [s0],
+            ) => match assigns(s0, b) {
+                // `b := x == const ...`
+                Some(Rvalue::BinaryOp(BinOp::Eq, _, _)) if !*expected => 2,
+                _ => return false,
+            },
+            (AssertKind::Overflow(BinOp::Div | BinOp::Rem), [s0, s1, s2]) => {
+                // TODO: check the operands of the comparisons
+                match (&s0.content, &s1.content, assigns(s2, b)) {
+                    (
+                        RawStatement::Assign(b_y, Rvalue::BinaryOp(BinOp::Eq, _, _)),
+                        RawStatement::Assign(b_x, Rvalue::BinaryOp(BinOp::Eq, _, _)),
+                        Some(Rvalue::BinaryOp(
+                            BinOp::BitAnd,
+                            Operand::Move(b_y1),
+                            Operand::Move(b_x1),
+                        )),
+                    ) if b_y == b_y1 && b_x == b_x1 && !*expected => 4,
+                    _ => return false,
                 }
-                // Shift left
-                else if let (
-                    // s0 should be an assignment
-                    RawStatement::Assign(dest_x_p, _),
-                    // s1 should be: `b := copy x < const ...`
-                    RawStatement::Assign(
-                        dest_b_p,
-                        Rvalue::BinaryOp(BinOp::Lt, Operand::Move(x_place), Operand::Const(..)),
-                    ),
-                    RawStatement::Sequence(s2, _),
-                ) = (&s0.content, &s1.content, &s2.content)
-                {
-                    // s2 should be: `assert(move b == true)`
-                    if dest_x_p == x_place && is_assert_move(dest_b_p, s2, true) {
-                        // Eliminate the first three statements
-                        take(s, |s| {
-                            let (_, s1) = s.content.to_sequence();
-                            let (_, s2) = s1.content.to_sequence();
-                            let (_, s3) = s2.content.to_sequence();
-                            *s3
-                        });
-                        // A simplification happened
-                        return true;
-                    }
+            }
+            (AssertKind::Overflow(BinOp::Add | BinOp::Sub | BinOp::Mul), [s0]) => {
+                // `b` should be `r.1`, and the statement after the assertion
+                // should be `z := move r.0` (and followed by other statements)
+                let Some(r) = checked_binop_dest(s0) else {
+                    return false;
+                };
+                let is_checked_binop = match prefix[2..] {
+                    [s2, _, ..] => is_first_field_use(s2, r),
+                    _ => false,
+                };
+                if *expected || tuple_field_of(b, r) != Some(1) || !is_checked_binop {
+                    return false;
                 }
-                // Signed division and remainder
-                // TODO: check x_op and y_op
-                else if let (
-                    // b_y_p := y == (-1)
-                    RawStatement::Assign(
-                        b_y_p,
-                        Rvalue::BinaryOp(
-                            BinOp::Eq,
-                            _y_op,
-                            Operand::Const(ConstantExpr {
-                                value: RawConstantExpr::Literal(Literal::Scalar(_)),
-                                ty: _,
-                            }),
-                        ),
-                    ),
-                    // b_x_p := x == INT::min
-                    // TODO: check min_op
-                    RawStatement::Assign(b_x_p, Rvalue::BinaryOp(BinOp::Eq, _x_op, _min_op)),
-                    // s2
-                    // s3
-                    RawStatement::Sequence(s2, s3),
-                ) = (&s0.content, &s1.content, &s2.content)
-                {
-                    if let RawStatement::Sequence(s3, s4) = &s3.content {
-                        if let (
-                            // b := move (b_y) & move (b_x)
-                            RawStatement::Assign(
-                                b_p,
-                                Rvalue::BinaryOp(
-                                    BinOp::BitAnd,
-                                    Operand::Move(b_y_p1),
-                                    Operand::Move(b_x_p1),
-                                ),
-                            ),
-                            // assert(move b == false)
-                            RawStatement::Assert(Assert {
-                                cond: Operand::Move(b_p_1),
-                                expected: false,
-                            }),
-                            // z := x / y;
-                            // ...
-                            RawStatement::Sequence(s4, _),
-                        ) = (&s2.content, &s3.content, &s4.content)
-                        {
-                            if b_x_p == b_x_p1
-                                && b_y_p1 == b_y_p
-                                && b_p == b_p_1
-                                // x := x / y
-                                && matches!(
-                                    &s4.content,
-                                    RawStatement::Assign(
-                                        _,
-                                        Rvalue::BinaryOp(BinOp::Div | BinOp::Rem, _, _)
-                                    )
-                                )
-                            {
-                                // Eliminate the first 4 statements
-                                take(s, |s| {
-                                    let (_, s1) = s.content.to_sequence();
-                                    let (_, s2) = s1.content.to_sequence();
-                                    let (_, s3) = s2.content.to_sequence();
-                                    let (_, s4) = s3.content.to_sequence();
-                                    *s4
-                                });
-                                return true;
-                            }
-                        }
-                    }
+                // Remove the assertion, then collapse the operation and the
+                // projection into one assignment: `z := x + y`
+                sts.remove(i + 1);
+                collapse_checked_binop(sts, i);
+                // A simplification happened
+                return true;
+            }
+            (AssertKind::Overflow(BinOp::Shl | BinOp::Shr), [s0, s1]) => {
+                // `x := ...; b := move x < const ...`
+                match (&s0.content, assigns(s1, b)) {
+                    (
+                        RawStatement::Assign(x, _),
+                        Some(Rvalue::BinaryOp(BinOp::Lt, Operand::Move(x1), Operand::Const(..))),
+                    ) if x == x1 && *expected => 3,
+                    _ => return false,
+                }
+            }
+            (AssertKind::Overflow(BinOp::Shl | BinOp::Shr), [s0]) => {
+                // `b := copy x < const ...`
+                match assigns(s0, b) {
+                    Some(Rvalue::BinaryOp(BinOp::Lt, _, Operand::Const(..))) if *expected => 2,
+                    _ => return false,
                 }
-                // Division/remainder/addition/etc.
-                else if let RawStatement::Assign(dest_p, Rvalue::BinaryOp(binop, _, _)) =
-                    &s0.content
-                {
-                    // We don't check that the second operand is 0 in
-                    // case we are in the division/remainder case
-                    if matches!(binop, BinOp::Eq) && is_assert_move(dest_p, s1, false) {
-                        // This should be the division/remainder case
-                        // Eliminate the first two statements
-                        take(s, |s| {
-                            let (_, s1) = s.content.to_sequence();
-                            let (_, s2) = s1.content.to_sequence();
-                            *s2
-                        });
-                        // We performed a change
-                        return true;
-                    } else if let (
-                        RawStatement::Assert(Assert {
-                            cond: Operand::Move(move_p),
-                            ..
-                        }),
-                        RawStatement::Sequence(s2, _),
-                    ) = (&s1.content, &s2.content)
+            }
+            (AssertKind::IndexOutOfBounds { len, index }, [s0, s1]) => {
+                // `l := len(a); b := copy x < copy l`, followed by an access
+                // to `a[x]`
+                let Operand::Copy(l) = len else {
+                    return false;
+                };
+                match (assigns(s0, l), assigns(s1, b)) {
+                    (
+                        Some(Rvalue::Len(a, ..)),
+                        Some(Rvalue::BinaryOp(BinOp::Lt, x, Operand::Copy(l1))),
+                    ) if l == l1
+                        && x == index
+                        && *expected
+                        && is_index_access(prefix.get(3), Some(a), index) =>
                     {
-                        // TODO: the last statement is not necessarily a sequence
-                        // This should be the addition/subtraction/etc. case
-                        error_assert_then!(
-                            self.ctx,
-                            s0.meta.span.rust_span,
-                            matches!(binop, BinOp::Add | BinOp::Sub | BinOp::Mul),
-                            // TODO: we could replace the whole statement with an "ERROR" statement
-                            // A simplification should have happened but was missed:
-                            // stop the simplification here.
-                            return true,
-                            format!(
-                                "Unexpected binop while removing dynamic checks: {:?}",
-                                binop
-                            )
-                        );
-
-                        if let RawStatement::Assign(_, Rvalue::Use(Operand::Move(move_p1))) =
-                            &s2.content
-                        {
-                            // move_p should be: r.1
-                            // move_p1 should be: r.0
-                            if move_p.var_id == move_p1.var_id
-                                && move_p.projection.len() == 1
-                                && move_p1.projection.len() == 1
-                            {
-                                if let (
-                                    ProjectionElem::Field(FieldProjKind::Tuple(..), fid0),
-                                    ProjectionElem::Field(FieldProjKind::Tuple(..), fid1),
-                                ) = (&move_p.projection[0], &move_p1.projection[0])
-                                {
-                                    use crate::id_vector::ToUsize;
-                                    if fid0.to_usize() == 1 && fid1.to_usize() == 0 {
-                                        // Collapse into one assignment
-                                        take(s, |s| {
-                                            let (s0, s1) = s.content.to_sequence();
-                                            let (_, s2) = s1.content.to_sequence();
-                                            let (s2, s3) = s2.content.to_sequence();
-                                            let (_, op) = s0.content.to_assign();
-                                            let (dest, _) = s2.content.to_assign();
-                                            let meta0 = s0.meta;
-                                            let s0 = RawStatement::Assign(dest, op);
-                                            let s0 = Statement {
-                                                meta: meta0,
-                                                content: s0,
-                                            };
-                                            Statement {
-                                                meta: s2.meta,
-                                                content: RawStatement::Sequence(Box::new(s0), s3),
-                                            }
-                                        });
-                                        // A simplification happened
-                                        return true;
-                                    }
-                                }
-                            }
-                        }
+                        3
+                    }
+                    _ => return false,
+                }
+            }
+            (AssertKind::IndexOutOfBounds { len, index }, [s0]) => {
+                // The length is a constant: `b := copy x < const n`, followed
+                // by an access to an array at index `x`
+                match (len, assigns(s0, b)) {
+                    (
+                        Operand::Const(_),
+                        Some(Rvalue::BinaryOp(BinOp::Lt, x, Operand::Const(_))),
+                    ) if x == index && *expected && is_index_access(prefix.get(2), None, index) => {
+                        2
                     }
+                    _ => return false,
                 }
             }
+            _ => return false,
         };
 
-        // No simplification
-        false
+        // The assertion must be followed by at least one statement
+        if prefix.len() <= removed {
+            return false;
+        }
+        sts.drain(i..i + removed);
+        // A simplification happened
+        true
     }
 }
 
@@ -301,35 +369,60 @@ impl<'tcx, 'ctx, 'a> MutAstVisitor for RemoveDynChecks<'tcx, 'ctx, 'a> {
 
     fn merge(&mut self) {}
 
+    fn visit_block(&mut self, sts: &mut Vec<Statement>) {
+        let mut i = 0;
+        while i < sts.len() {
+            // Simplify, as long as simplifications happen (the simplified
+            // statements are always followed by another statement, so there is
+            // still a statement at index `i`)
+            while self.simplify(sts, i) {}
+            self.visit_statement(&mut sts[i]);
+            i += 1;
+        }
+    }
+
     fn visit_statement(&mut self, s: &mut Statement) {
-        // Simplify
-        if self.simplify(s) {
-            // A simplification happened: visit again the updated statement
-            self.visit_statement(s)
-        } else {
-            // No simplification: dive in.
-            // Make sure we eliminated all the asserts and all the `len`
+        if let RawStatement::Assert(Assert {
+            cond:
+                Operand::Const(ConstantExpr {
+                    value: RawConstantExpr::Literal(Literal::Bool(b)),
+                    ..
+                }),
+            expected,
+            ..
+        }) = &s.content
+        {
+            // The condition was folded to a constant by rustc: the
+            // assertion either always succeeds or always fails
+            s.content = if b == expected {
+                RawStatement::Nop
+            } else {
+                RawStatement::Panic
+            };
+            return;
+        }
+        if s.content.is_assert() {
+            // We didn't recognize the shape of the dynamic check: we leave
+            // the assertion in place (its kind tells which check it is)
+            self.ctx.session.span_warn(
+                s.meta.span.rust_span,
+                "Could not remove a dynamic check".to_string(),
+            );
+            return;
+        }
+        // Make sure we eliminated all the `len`
+        if s.content.is_assign() {
+            let (_, rv) = s.content.as_assign();
             error_assert_then!(
                 self.ctx,
                 s.meta.span.rust_span,
-                !s.content.is_assert(),
+                !rv.is_len(),
                 // Return so as to stop the exploration
                 return,
-                "Found an assert which was not simplified"
+                "Found an occurrence of Len which was not simplified"
             );
-            if s.content.is_assign() {
-                let (_, rv) = s.content.as_assign();
-                error_assert_then!(
-                    self.ctx,
-                    s.meta.span.rust_span,
-                    !rv.is_len(),
-                    // Return so as to stop the exploration
-                    return,
-                    "Found an occurrence of Len which was not simplified"
-                );
-            }
-            self.default_visit_raw_statement(&mut s.content);
         }
+        self.default_visit_raw_statement(&mut s.content);
     }
 }
 
@@ -0,0 +1,77 @@
+//! # Micro-pass (optional, see [crate::cli_options::CliOpts::fmt_machinery]):
+//! remove the calls to the formatting machinery whose result is never used,
+//! together with the calls to `print!` and `eprint!`.
+//!
+//! The calls to the formatting functions were replaced with calls to
+//! [AssumedFunId::Fmt] during the translation. Removing a call may make the
+//! locals it reads dead: we interleave this pass with the removal of the dead
+//! assignments (see [crate::remove_dead_assignments]) until we reach a fixed
+//! point, so that we also remove the temporaries used to build the arguments.
+use crate::assumed;
+use crate::expressions::*;
+use crate::formatter::{Formatter, IntoFormatter};
+use crate::gast::{Call, FnOperand};
+use crate::id_vector::ToUsize;
+use crate::llbc_ast::*;
+use crate::remove_dead_assignments::{compute_read_locals, remove_dead_assignments};
+use crate::translate_ctx::TransCtx;
+use crate::types::*;
+use std::collections::HashSet;
+
+/// Return `true` if we can remove a call: this is a call to the formatting
+/// machinery whose result is never read, or a call to `print!` or `eprint!`.
+fn is_removable_call(
+    ctx: &TransCtx,
+    arg_count: usize,
+    read: &HashSet<VarId::Id>,
+    call: &Call,
+) -> bool {
+    let FnOperand::Regular(fn_ptr) = &call.func else {
+        return false;
+    };
+    match &fn_ptr.func {
+        FunIdOrTraitMethodRef::Fun(FunId::Assumed(AssumedFunId::Fmt)) => {
+            call.dest.projection.is_empty()
+                // We never remove the assignments to the return value and to
+                // the input arguments
+                && call.dest.var_id.to_usize() > arg_count
+                && !read.contains(&call.dest.var_id)
+        }
+        FunIdOrTraitMethodRef::Fun(FunId::Regular(id)) => ctx
+            .fun_decls
+            .get(*id)
+            .map_or(false, |decl| assumed::is_print(&decl.name)),
+        _ => false,
+    }
+}
+
+/// Remove the calls. Return `true` if we changed something.
+fn remove_fmt_calls(ctx: &TransCtx, arg_count: usize, body: &mut Statement) -> bool {
+    let read = compute_read_locals(body);
+
+    let mut changed = false;
+    body.transform(&mut |st: &mut Statement| {
+        if let RawStatement::Call(call) = &st.content
+            && is_removable_call(ctx, arg_count, &read, call)
+        {
+            st.content = RawStatement::Nop;
+            changed = true;
+        }
+        None
+    });
+    changed
+}
+
+pub fn transform(ctx: &mut TransCtx, funs: &mut FunDecls, globals: &mut GlobalDecls) {
+    ctx.iter_bodies(funs, globals, |ctx, name, b| {
+        let fmt_ctx = ctx.into_fmt();
+        trace!(
+            "# About to remove the calls to the formatting machinery in decl: {}:\n{}",
+            name.fmt_with_ctx(&fmt_ctx),
+            fmt_ctx.format_object(&*b)
+        );
+        while remove_fmt_calls(ctx, b.arg_count, &mut b.body)
+            | remove_dead_assignments(b.arg_count, &mut b.body)
+        {}
+    })
+}
@@ -1,23 +1,11 @@
 //! Remove the useless no-ops.
 
 use crate::formatter::{Formatter, IntoFormatter};
-use crate::llbc_ast::{FunDecls, GlobalDecls, RawStatement, Statement};
-use crate::meta::combine_meta;
+use crate::llbc_ast::{FunDecls, GlobalDecls, Statement};
 use crate::translate_ctx::TransCtx;
-use take_mut::take;
 
-fn transform_st(s: &mut Statement) {
-    if let RawStatement::Sequence(s1, _) = &s.content {
-        if s1.content.is_nop() {
-            take(s, |s| {
-                let (s1, s2) = s.content.to_sequence();
-                Statement {
-                    content: s2.content,
-                    meta: combine_meta(&s1.meta, &s2.meta),
-                }
-            })
-        }
-    }
+fn transform_block(sts: &mut Vec<Statement>) {
+    sts.retain(|st| !st.content.is_nop())
 }
 
 pub fn transform(ctx: &mut TransCtx, funs: &mut FunDecls, globals: &mut GlobalDecls) {
@@ -29,10 +17,6 @@ pub fn transform(ctx: &mut TransCtx, funs: &mut FunDecls, globals: &mut GlobalDe
             fmt_ctx.format_object(&*b)
         );
 
-        // Compute the set of local variables
-        b.body.transform(&mut |st| {
-            transform_st(st);
-            None
-        });
+        b.body.transform_blocks(&mut transform_block);
     })
 }
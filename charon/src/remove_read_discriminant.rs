@@ -8,6 +8,7 @@ use crate::llbc_ast::*;
 use crate::meta::combine_meta;
 use crate::translate_ctx::*;
 use crate::types::*;
+use crate::values::{ScalarValue, SwitchValue};
 use std::collections::HashSet;
 use std::iter::FromIterator;
 
@@ -16,119 +17,130 @@ struct Visitor<'a, 'tcx, 'ctx> {
 }
 
 impl<'a, 'tcx, 'ctx> Visitor<'a, 'tcx, 'ctx> {
-    fn update_statement(&mut self, st: &mut Statement) {
-        match &mut st.content {
-            RawStatement::Sequence(
-                box Statement {
-                    content: RawStatement::Assign(dest, Rvalue::Discriminant(p, adt_id)),
-                    meta: meta1,
-                },
-                box st2,
-            ) => {
-                // The destination should be a variable
-                assert!(dest.projection.is_empty());
-
-                // Take st2
-                let st2 = std::mem::replace(
-                    st2,
-                    Statement {
-                        content: RawStatement::Nop,
-                        meta: st2.meta,
-                    },
-                );
+    /// Merge the discriminant reads with the switches which follow them.
+    fn update_block(&mut self, sts: &mut Vec<Statement>) {
+        let mut i = 0;
+        while i < sts.len() {
+            if let RawStatement::Assign(_, Rvalue::Discriminant(..)) = &sts[i].content {
+                // A discriminant read must be immediately followed by a switch int
+                let switch = sts.remove(i + 1);
+                let read =
+                    std::mem::replace(&mut sts[i], Statement::new(switch.meta, RawStatement::Nop));
+                sts[i] = self.merge_read_and_switch(read, switch);
+            }
+            i += 1;
+        }
+    }
 
-                // A discriminant read must be immediately followed by a switch int.
-                // Note that it may be contained in a sequence, of course.
-                let (meta2, switch, st3_opt) = match st2.content {
-                    RawStatement::Sequence(
-                        box Statement {
-                            content: RawStatement::Switch(switch),
-                            meta: meta2,
-                        },
-                        box st3,
-                    ) => (meta2, switch, Some(st3)),
-                    RawStatement::Switch(switch) => (st2.meta, switch, None),
-                    _ => unreachable!(),
-                };
+    /// Merge a discriminant read `dest := discriminant(p)` and the switch over
+    /// `dest` which follows it into a match over `p`.
+    fn merge_read_and_switch(&mut self, read: Statement, switch: Statement) -> Statement {
+        let RawStatement::Assign(dest, Rvalue::Discriminant(p, adt_id)) = read.content else {
+            unreachable!()
+        };
+        // The destination should be a variable
+        assert!(dest.projection.is_empty());
 
-                let Switch::SwitchInt(Operand::Move(op_p), int_ty, targets, otherwise) = switch
-                else { unreachable!() };
-                assert!(int_ty.is_isize());
-                assert!(op_p.projection.is_empty() && op_p.var_id == dest.var_id);
+        let RawStatement::Switch(Switch::SwitchInt(
+            Operand::Move(op_p),
+            int_ty,
+            targets,
+            otherwise,
+        )) = switch.content
+        else {
+            unreachable!()
+        };
+        assert!(op_p.projection.is_empty() && op_p.var_id == dest.var_id);
 
-                let targets = Vec::from_iter(targets.into_iter().map(|(v, e)| {
-                    (
-                        Vec::from_iter(
-                            v.into_iter()
-                                .map(|x| VariantId::Id::new(*x.as_isize() as usize)),
-                        ),
-                        e,
-                    )
-                }));
-                // Filter the otherwise branch, if it is not necessary.
-                use crate::id_vector::ToUsize;
-                let covered_variants: HashSet<usize> =
-                    targets.iter().fold(HashSet::new(), |mut hs, (ids, _)| {
-                        ids.iter().for_each(|id| {
-                            let _ = hs.insert(id.to_usize());
-                        });
-                        hs
-                    });
-                let covers_all = {
-                    // Lookup the type of the scrutinee
-                    match self.ctx.type_decls.get(*adt_id) {
-                        None => {
-                            // This can happen if there was an error while
-                            // extracting the definitions
-                            assert!(self.ctx.error_count > 0);
-                            // For safety, we consider that not all the patterns
-                            // were covered
-                            false
-                        }
-                        Some(d) => {
-                            match &d.kind {
-                                TypeDeclKind::Struct(_) | TypeDeclKind::Opaque => {
-                                    // We shouldn't get there
-                                    register_error_or_panic!(
-                                        self.ctx,
-                                        st.meta.span.rust_span,
-                                        "Unreachable case"
-                                    );
-                                    false
-                                }
-                                TypeDeclKind::Error(_) => false,
-                                TypeDeclKind::Enum(variants) => {
-                                    // Check that all the variants are covered
-                                    variants
-                                        .iter()
-                                        .enumerate()
-                                        .all(|(i, _)| covered_variants.contains(&i))
-                                }
-                            }
-                        }
+        // Lookup the type of the scrutinee, to retrieve the discriminants
+        // of the variants
+        let discriminants: Option<Vec<ScalarValue>> = match self.ctx.type_decls.get(adt_id) {
+            None => {
+                // This can happen if there was an error while
+                // extracting the definitions
+                assert!(self.ctx.error_count > 0);
+                None
+            }
+            Some(d) => {
+                match &d.kind {
+                    TypeDeclKind::Struct(_) | TypeDeclKind::Union(_) | TypeDeclKind::Opaque => {
+                        // We shouldn't get there
+                        let msg = format!(
+                            "Unexpected read of the discriminant of a non-enumeration type: {}",
+                            self.ctx.fmt_for_error(&adt_id)
+                        );
+                        register_error_or_panic!(self.ctx, read.meta.span.rust_span, msg);
+                        None
                     }
-                };
-                let otherwise = if covers_all { None } else { Some(otherwise) };
-
-                let switch = RawStatement::Switch(Switch::Match(p.clone(), targets, otherwise));
+                    TypeDeclKind::Error(_) => None,
+                    TypeDeclKind::Enum(variants) => {
+                        Some(variants.iter().map(|v| v.discriminant).collect())
+                    }
+                }
+            }
+        };
 
-                // Add the next statement if there is one
-                st.content = if let Some(st3) = st3_opt {
-                    let meta = combine_meta(meta1, &meta2);
-                    let switch = Statement {
-                        meta,
-                        content: switch,
+        // Convert the discriminant values (or ranges of values) to variant ids.
+        let to_variant_ids = |x: SwitchValue| -> Vec<VariantId::Id> {
+            match &discriminants {
+                Some(discriminants) => {
+                    assert!(x.bounds().0.get_integer_ty() == int_ty);
+                    let ids: Vec<VariantId::Id> = discriminants
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, d)| x.contains(d))
+                        .map(|(i, _)| VariantId::Id::new(i))
+                        .collect();
+                    assert!(!ids.is_empty());
+                    ids
+                }
+                None => {
+                    // We don't have the type definition: we use the
+                    // fact that by default the discriminants are
+                    // equal to the variant indices.
+                    let to_index = |x: ScalarValue| {
+                        if x.is_int() {
+                            x.as_int().unwrap() as usize
+                        } else {
+                            x.as_uint().unwrap() as usize
+                        }
                     };
-                    new_sequence(switch, st3).content
-                } else {
-                    switch
-                };
+                    let (lo, hi) = x.bounds();
+                    (to_index(lo)..=to_index(hi))
+                        .map(VariantId::Id::new)
+                        .collect()
+                }
             }
-            RawStatement::Assign(_, Rvalue::Discriminant(_, _)) => {
-                // We failed to remove a [Discriminant]
-                unreachable!();
+        };
+        let targets = Vec::from_iter(
+            targets
+                .into_iter()
+                .map(|(v, e)| (Vec::from_iter(v.into_iter().flat_map(&to_variant_ids)), e)),
+        );
+        // Filter the otherwise branch, if it is not necessary.
+        use crate::id_vector::ToUsize;
+        let covered_variants: HashSet<usize> =
+            targets.iter().fold(HashSet::new(), |mut hs, (ids, _)| {
+                ids.iter().for_each(|id| {
+                    let _ = hs.insert(id.to_usize());
+                });
+                hs
+            });
+        // For safety, if we don't have the type definition, we consider
+        // that not all the patterns were covered
+        let covers_all = match &discriminants {
+            None => false,
+            Some(discriminants) => {
+                // Check that all the variants are covered
+                (0..discriminants.len()).all(|i| covered_variants.contains(&i))
             }
-            _ => (),
+        };
+        let otherwise = if covers_all { None } else { Some(otherwise) };
+
+        let content = RawStatement::Switch(Switch::Match(p, targets, otherwise));
+        Statement {
+            meta: combine_meta(&read.meta, &switch.meta),
+            content,
         }
     }
 }
@@ -142,12 +154,20 @@ impl<'a, 'tcx, 'ctx> MutAstVisitor for Visitor<'a, 'tcx, 'ctx> {
 
     fn merge(&mut self) {}
 
-    fn visit_statement(&mut self, st: &mut Statement) {
-        self.update_statement(st);
+    fn visit_block(&mut self, sts: &mut Vec<Statement>) {
+        self.update_block(sts);
 
-        // Visit again, to make sure we transform the branches and
-        // the next statement, in case we updated, or to update the
-        // sub-statements, in case we didn't perform any updates.
+        // Visit the statements, to update the branches of the switches
+        for st in sts {
+            self.visit_statement(st);
+        }
+    }
+
+    fn visit_statement(&mut self, st: &mut Statement) {
+        if let RawStatement::Assign(_, Rvalue::Discriminant(_, _)) = &st.content {
+            // We failed to remove a [Discriminant]
+            unreachable!();
+        }
         self.default_visit_statement(st);
     }
 }
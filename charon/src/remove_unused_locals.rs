@@ -136,6 +136,11 @@ pub fn transform(ctx: &mut TransCtx, funs: &mut FunDecls, globals: &mut GlobalDe
             let (locals, vids_map) = update_locals(b.arg_count, b.locals, &b.body);
             b.locals = locals;
             trace!("vids_maps: {:?}", vids_map);
+            // Update the debug information (the variables stored in the
+            // removed locals are not stored in a local anymore)
+            for info in &mut b.var_debug_info {
+                info.var = info.var.and_then(|v| vids_map.get(&v).copied());
+            }
             UpdateUsedLocals::update_statement(vids_map, &mut b.body);
             b
         });
@@ -10,13 +10,13 @@ use linked_hash_set::LinkedHashSet;
 use macros::{EnumAsGetters, EnumIsA, VariantIndexArity, VariantName};
 use petgraph::algo::tarjan_scc;
 use petgraph::graphmap::DiGraphMap;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use std::fmt::{Debug, Display, Error};
 use std::vec::Vec;
 
 /// A (group of) top-level declaration(s), properly reordered.
 /// "G" stands for "generic"
-#[derive(Debug, VariantIndexArity, VariantName, Serialize)]
+#[derive(Debug, VariantIndexArity, VariantName, Serialize, Deserialize)]
 pub enum GDeclarationGroup<Id> {
     /// A non-recursive declaration
     NonRec(Id),
@@ -25,7 +25,7 @@ pub enum GDeclarationGroup<Id> {
 }
 
 /// A (group of) top-level declaration(s), properly reordered.
-#[derive(Debug, VariantIndexArity, VariantName, Serialize)]
+#[derive(Debug, VariantIndexArity, VariantName, Serialize, Deserialize)]
 pub enum DeclarationGroup {
     /// A type declaration group
     Type(GDeclarationGroup<TypeDeclId::Id>),
@@ -170,6 +170,8 @@ impl DeclarationGroup {
     Debug,
     PartialOrd,
     Ord,
+    Serialize,
+    Deserialize,
 )]
 pub enum AnyDeclId<TypeId, FunId, GlobalId, TraitDeclId, TraitImplId> {
     Type(TypeId),
@@ -422,7 +424,7 @@ impl Deps {
 }
 
 impl AnyTransId {
-    fn fmt_with_ctx(&self, ctx: &TransCtx) -> String {
+    pub(crate) fn fmt_with_ctx(&self, ctx: &TransCtx) -> String {
         use AnyDeclId::*;
         let ctx = ctx.into_fmt();
         match self {
@@ -454,10 +456,8 @@ impl Deps {
     }
 }
 
-pub fn reorder_declarations(ctx: &mut TransCtx) {
-    trace!();
-
-    // Step 1: explore the declarations to build the graph
+/// Explore the declarations to build the dependency graph.
+fn compute_graph(ctx: &TransCtx) -> Deps {
     let mut graph = Deps::new();
     for id in &ctx.all_ids {
         graph.set_current_id(ctx, *id);
@@ -471,7 +471,7 @@ pub fn reorder_declarations(ctx: &mut TransCtx) {
 
                     // Visit the body
                     match &d.kind {
-                        Struct(fields) => {
+                        Struct(fields) | Union(fields) => {
                             for f in fields {
                                 graph.visit_ty(&f.ty)
                             }
@@ -605,7 +605,28 @@ pub fn reorder_declarations(ctx: &mut TransCtx) {
         }
         graph.unset_current_id();
     }
+    graph
+}
 
+/// Compute the dependencies of every declaration: this is the graph we use to
+/// order the declarations. The declarations and their dependencies are given
+/// in the order in which we explored them.
+///
+/// Remark: as we explore the ULLBC bodies, the dependencies introduced or
+/// removed by the LLBC micro-passes are not taken into account.
+pub fn compute_dependencies(ctx: &TransCtx) -> Vec<(AnyTransId, Vec<AnyTransId>)> {
+    compute_graph(ctx)
+        .graph
+        .into_iter()
+        .map(|(id, deps)| (id, deps.into_iter().collect()))
+        .collect()
+}
+
+pub fn reorder_declarations(ctx: &mut TransCtx) {
+    trace!();
+
+    // Step 1: explore the declarations to build the graph
+    let graph = compute_graph(ctx);
     trace!("Graph:\n{}\n", graph.fmt_with_ctx(ctx));
 
     // Step 2: Apply Tarjan's SCC (Strongly Connected Components) algorithm
@@ -0,0 +1,172 @@
+//! # Micro-pass: simplify the control-flow graph of the ULLBC bodies, before
+//! the control-flow reconstruction. We:
+//! - thread the jumps: when a block jumps to a "trampoline" block (a block
+//!   without statements which ends with a `goto`), we make it jump directly to
+//!   the final destination
+//! - merge the identical blocks (blocks with the same statements and the same
+//!   terminator, ignoring the meta information): we make all the jumps go to
+//!   the first of those blocks (the merged blocks thus share the meta
+//!   information of the first block)
+//!
+//! The optimized MIR contains a lot of such blocks. Removing them reduces the
+//! size of the generated code (the reconstruction may duplicate the blocks)
+//! and makes the reconstruction of the control-flow more likely to succeed.
+//!
+//! Remark: we don't remove the blocks which become unreachable: they are
+//! ignored by the control-flow reconstruction.
+use crate::expressions::MutExprVisitor;
+use crate::formatter::{Formatter, IntoFormatter};
+use crate::translate_ctx::TransCtx;
+use crate::types::MutTypeVisitor;
+use crate::ullbc_ast::{BlockData, BlockId, ExprBody, MutAstVisitor, RawStatement, RawTerminator};
+use std::collections::{HashMap, HashSet};
+use std::mem::{discriminant, Discriminant};
+
+/// If the block is a trampoline, return its target.
+fn trampoline_target(block: &BlockData) -> Option<BlockId::Id> {
+    match &block.terminator.content {
+        RawTerminator::Goto { target } if block.statements.is_empty() => Some(*target),
+        _ => None,
+    }
+}
+
+/// Compute the final destinations of the trampolines.
+fn compute_threaded_jumps(body: &ExprBody) -> HashMap<BlockId::Id, BlockId::Id> {
+    let mut redirect = HashMap::new();
+    for id in body.body.iter_indices() {
+        let mut dest = id;
+        let mut visited = HashSet::new();
+        while let Some(target) = trampoline_target(body.body.get(dest).unwrap()) {
+            // The trampolines may form a cycle (this is an infinite loop):
+            // we stop when we come back to a block we already visited.
+            if !visited.insert(dest) {
+                break;
+            }
+            dest = target;
+        }
+        if dest != id {
+            redirect.insert(id, dest);
+        }
+    }
+    redirect
+}
+
+/// Check if two blocks are identical. We compare the contents of the
+/// statements and of the terminators, ignoring the meta information.
+fn same_blocks(block0: &BlockData, block1: &BlockData) -> bool {
+    block0.statements.len() == block1.statements.len()
+        && block0
+            .statements
+            .iter()
+            .zip(block1.statements.iter())
+            .all(|(st0, st1)| st0.content == st1.content)
+        && block0.terminator.content == block1.terminator.content
+}
+
+/// A summary of the contents of a block: the kinds of its statements, the kind
+/// of its terminator and the targets of the terminator. Identical blocks have
+/// the same summary: we group the blocks by summary, and only compare the
+/// blocks of a same group.
+type BlockShape = (
+    Vec<Discriminant<RawStatement>>,
+    Discriminant<RawTerminator>,
+    Vec<BlockId::Id>,
+);
+
+fn block_shape(block: &BlockData) -> BlockShape {
+    let statements = block
+        .statements
+        .iter()
+        .map(|st| discriminant(&st.content))
+        .collect();
+    let targets = match &block.terminator.content {
+        RawTerminator::Goto { target }
+        | RawTerminator::Drop { target, .. }
+        | RawTerminator::Call { target, .. }
+        | RawTerminator::Assert { target, .. } => vec![*target],
+        RawTerminator::Switch { targets, .. } => targets.get_targets(),
+        RawTerminator::Panic(_) | RawTerminator::Return | RawTerminator::Unreachable => Vec::new(),
+    };
+    (statements, discriminant(&block.terminator.content), targets)
+}
+
+/// Map the blocks to the first block which is identical to them. Note that the
+/// jumps then go to the first block, and thus use its meta information.
+fn compute_merged_blocks(body: &ExprBody) -> HashMap<BlockId::Id, BlockId::Id> {
+    // The representatives, grouped by shape
+    let mut representatives: HashMap<BlockShape, Vec<BlockId::Id>> = HashMap::new();
+    let mut redirect = HashMap::new();
+    for id in body.body.iter_indices() {
+        let block = body.body.get(id).unwrap();
+        let candidates = representatives.entry(block_shape(block)).or_default();
+        let repr = candidates
+            .iter()
+            .find(|repr| same_blocks(body.body.get(**repr).unwrap(), block));
+        match repr {
+            Some(repr) => {
+                redirect.insert(id, *repr);
+            }
+            None => candidates.push(id),
+        }
+    }
+    redirect
+}
+
+/// Update the targets of the terminators.
+struct Redirect {
+    redirect: HashMap<BlockId::Id, BlockId::Id>,
+    /// `true` if we updated a target
+    changed: bool,
+}
+
+impl MutTypeVisitor for Redirect {}
+impl MutExprVisitor for Redirect {}
+
+impl MutAstVisitor for Redirect {
+    fn visit_block_id(&mut self, id: &mut BlockId::Id) {
+        if let Some(target) = self.redirect.get(id) {
+            *id = *target;
+            self.changed = true;
+        }
+    }
+}
+
+/// Apply a redirection to all the blocks. Return `true` if we changed something.
+fn apply_redirect(body: &mut ExprBody, redirect: HashMap<BlockId::Id, BlockId::Id>) -> bool {
+    let mut visitor = Redirect {
+        redirect,
+        changed: false,
+    };
+    for block in body.body.iter_mut() {
+        visitor.visit_terminator(&mut block.terminator);
+    }
+    visitor.changed
+}
+
+pub fn transform(ctx: &mut TransCtx) {
+    // Slightly annoying: we have to clone because of borrowing issues
+    let mut fun_decls = ctx.fun_decls.clone();
+    let mut global_decls = ctx.global_decls.clone();
+
+    ctx.iter_bodies(&mut fun_decls, &mut global_decls, |ctx, name, b| {
+        let fmt_ctx = ctx.into_fmt();
+        trace!(
+            "# About to simplify the control-flow graph of decl: {}:\n{}",
+            name.fmt_with_ctx(&fmt_ctx),
+            fmt_ctx.format_object(&*b)
+        );
+
+        // Merging blocks may make other blocks identical, or create new
+        // trampolines: we iterate until we reach a fixed point
+        loop {
+            let threaded = apply_redirect(b, compute_threaded_jumps(b));
+            let merged = apply_redirect(b, compute_merged_blocks(b));
+            if !threaded && !merged {
+                break;
+            }
+        }
+    });
+
+    ctx.fun_decls = fun_decls;
+    ctx.global_decls = global_decls;
+}
@@ -40,7 +40,8 @@ fn transform_constant_expr<F: FnMut(Ty) -> VarId::Id>(
         RawConstantExpr::Literal(_)
         | RawConstantExpr::Var(_)
         | RawConstantExpr::TraitConst(..)
-        | RawConstantExpr::FnPtr(..) => {
+        | RawConstantExpr::FnPtr(..)
+        | RawConstantExpr::GlobalDeclRef(_) => {
             // Nothing to do
             // TODO: for trait const: might come from a top-level impl, so we might
             // want to introduce an intermediate statement to be able to evaluate
@@ -94,6 +95,28 @@ fn transform_constant_expr<F: FnMut(Ty) -> VarId::Id>(
                 RawStatement::Assign(Place::new(var_id), rval),
             ));
 
+            // Return the new operand
+            Operand::Move(Place::new(var_id))
+        }
+        RawConstantExpr::Array(values) => {
+            // Recurse on the values
+            let values: Vec<Operand> = values
+                .into_iter()
+                .map(|v| transform_constant_expr(meta, nst, v, make_new_var))
+                .collect();
+
+            // Introduce an intermediate assignment for the aggregated array
+            let (_, generics) = val.ty.as_adt();
+            let kind = AggregateKind::Array(
+                generics.types[0].clone(),
+                generics.const_generics[0].clone(),
+            );
+            let var_id = make_new_var(val.ty);
+            nst.push(Statement::new(
+                *meta,
+                RawStatement::Assign(Place::new(var_id), Rvalue::Aggregate(kind, values)),
+            ));
+
             // Return the new operand
             Operand::Move(Place::new(var_id))
         }
@@ -0,0 +1,201 @@
+//! Compute statistics about the extraction (number of translated, opaque and
+//! erroneous declarations, errors by kind, slowest items) and export them to
+//! a JSON file. On real crates, this allows to triage where the time and the
+//! failures go.
+
+use crate::dependencies::is_opaque;
+use crate::reorder_decls::AnyTransId;
+use crate::translate_ctx::TransCtx;
+use crate::types::TypeDeclKind;
+use rustc_hir::def_id::DefId;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+use std::time::Duration;
+
+/// The number of items we list in [Stats::slowest_items].
+const SLOWEST_ITEMS: usize = 20;
+
+/// The statistics we collect during the translation, if the user asked for
+/// them (see [crate::cli_options::CliOpts::stats]).
+#[derive(Default)]
+pub struct TranslationStats {
+    /// The time spent translating every item, in the order in which we
+    /// translated them
+    pub item_times: Vec<(String, Duration)>,
+    /// The number of errors of every kind (see [error_kind])
+    pub errors: BTreeMap<String, usize>,
+}
+
+impl TranslationStats {
+    pub(crate) fn register_error(&mut self, msg: &str) {
+        *self.errors.entry(error_kind(msg)).or_default() += 1;
+    }
+}
+
+/// The kind of an error: the error messages start with a description of the
+/// unsupported feature, optionally followed by the details after a colon
+/// (ex.: `Unsupported type: foreign type: ...`). We keep this description.
+fn error_kind(msg: &str) -> String {
+    let msg = msg.lines().next().unwrap_or("");
+    let kind = match msg.find(": ") {
+        Some(i) => &msg[..i],
+        None => msg,
+    };
+    kind.trim().to_string()
+}
+
+/// The number of declarations of some kind.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DeclCounts {
+    pub translated: usize,
+    /// The number of opaque declarations (see [crate::dependencies::is_opaque])
+    pub opaque: usize,
+    /// The number of declarations whose translation generated errors
+    pub errors: usize,
+}
+
+/// The time spent translating an item.
+#[derive(Debug, Clone, Serialize)]
+pub struct ItemTime {
+    pub name: String,
+    pub milliseconds: f64,
+}
+
+/// The statistics about the extraction.
+#[derive(Debug, Clone, Serialize)]
+pub struct Stats {
+    pub types: DeclCounts,
+    pub functions: DeclCounts,
+    pub globals: DeclCounts,
+    pub trait_decls: DeclCounts,
+    pub trait_impls: DeclCounts,
+    /// The number of declarations we completely failed to translate, and
+    /// ignored
+    pub ignored: usize,
+    /// The number of errors of every kind
+    pub errors_by_kind: BTreeMap<String, usize>,
+    /// The total time spent translating the items (from MIR to ULLBC)
+    pub translation_milliseconds: f64,
+    /// The slowest items, from the slowest to the fastest
+    pub slowest_items: Vec<ItemTime>,
+}
+
+/// Compute the statistics. The timings are empty if we didn't collect them
+/// during the translation.
+pub fn compute(ctx: &TransCtx) -> Stats {
+    // The declarations whose translation generated errors
+    let mut error_ids: HashSet<AnyTransId> = HashSet::new();
+    let with_errors = |rust_id: &DefId| ctx.decls_with_errors.contains(rust_id);
+    for (rust_id, id) in ctx.type_id_map.map.iter() {
+        if with_errors(rust_id) {
+            error_ids.insert(AnyTransId::Type(*id));
+        }
+    }
+    for (rust_id, id) in ctx.fun_id_map.map.iter() {
+        if with_errors(rust_id) {
+            error_ids.insert(AnyTransId::Fun(*id));
+        }
+    }
+    for (rust_id, id) in ctx.global_id_map.map.iter() {
+        if with_errors(rust_id) {
+            error_ids.insert(AnyTransId::Global(*id));
+        }
+    }
+    for (rust_id, id) in ctx.trait_decl_id_map.map.iter() {
+        if with_errors(rust_id) {
+            error_ids.insert(AnyTransId::TraitDecl(*id));
+        }
+    }
+    for (rust_id, id) in ctx.trait_impl_id_map.map.iter() {
+        if with_errors(rust_id) {
+            error_ids.insert(AnyTransId::TraitImpl(*id));
+        }
+    }
+    for decl in ctx.type_decls.iter() {
+        if let TypeDeclKind::Error(_) = decl.kind {
+            error_ids.insert(AnyTransId::Type(decl.def_id));
+        }
+    }
+
+    let count = |ids: Vec<AnyTransId>| {
+        let mut counts = DeclCounts::default();
+        for id in ids {
+            counts.translated += 1;
+            counts.opaque += is_opaque(ctx, id) as usize;
+            counts.errors += error_ids.contains(&id) as usize;
+        }
+        counts
+    };
+    let types = count(
+        ctx.type_decls
+            .iter()
+            .map(|d| AnyTransId::Type(d.def_id))
+            .collect(),
+    );
+    let functions = count(
+        ctx.fun_decls
+            .iter()
+            .map(|d| AnyTransId::Fun(d.def_id))
+            .collect(),
+    );
+    let globals = count(
+        ctx.global_decls
+            .iter()
+            .map(|d| AnyTransId::Global(d.def_id))
+            .collect(),
+    );
+    let trait_decls = count(
+        ctx.trait_decls
+            .iter()
+            .map(|d| AnyTransId::TraitDecl(d.def_id))
+            .collect(),
+    );
+    let trait_impls = count(
+        ctx.trait_impls
+            .iter()
+            .map(|d| AnyTransId::TraitImpl(d.def_id))
+            .collect(),
+    );
+
+    let (errors_by_kind, mut item_times) = match &ctx.stats {
+        Some(stats) => (stats.errors.clone(), stats.item_times.clone()),
+        None => (BTreeMap::new(), Vec::new()),
+    };
+    let translation_milliseconds = item_times
+        .iter()
+        .map(|(_, time)| time.as_secs_f64() * 1000.0)
+        .sum();
+    // Note that the sort is stable: the items which took the same time stay
+    // in translation order
+    item_times.sort_by(|(_, t0), (_, t1)| t1.cmp(t0));
+    let slowest_items = item_times
+        .into_iter()
+        .take(SLOWEST_ITEMS)
+        .map(|(name, time)| ItemTime {
+            name,
+            milliseconds: time.as_secs_f64() * 1000.0,
+        })
+        .collect();
+
+    let stats = Stats {
+        types,
+        functions,
+        globals,
+        trait_decls,
+        trait_impls,
+        ignored: ctx.ignored_failed_decls.len(),
+        errors_by_kind,
+        translation_milliseconds,
+        slowest_items,
+    };
+    info!(
+        "Statistics: {} functions ({} opaque, {} with errors), {} ignored declarations, \
+         translated in {:.0}ms",
+        stats.functions.translated,
+        stats.functions.opaque,
+        stats.functions.errors,
+        stats.ignored,
+        stats.translation_milliseconds
+    );
+    stats
+}
@@ -0,0 +1,134 @@
+//! Generate Rust stubs for the opaque functions (i.e., the functions whose
+//! bodies we did not extract), and export them to an auxiliary file
+//! (`CRATE.stubs.rs`). This allows the users to inspect (or re-use) the API
+//! surface that the extracted code relies upon.
+//!
+//! Remark: the stubs are meant to be read: the types are printed with their
+//! full paths, and we don't attempt to generate a file which compiles.
+
+use crate::formatter::{AstFormatter, IntoFormatter};
+use crate::gast::FunKind;
+use crate::names::PathElem;
+use crate::translate_ctx::TransCtx;
+use crate::types::*;
+use crate::ullbc_ast::FunDecl;
+
+/// Print a trait clause in Rust syntax: `T: Trait<U>`.
+///
+/// The first type argument of the clause is the type implementing the trait.
+fn clause_to_rust<C: AstFormatter>(ctx: &C, clause: &TraitClause) -> String {
+    let mut generics = clause.generics.clone();
+    let self_ty = if generics.types.is_empty() {
+        "Self".to_string()
+    } else {
+        generics.types.remove(0).fmt_with_ctx(ctx)
+    };
+    let trait_name = ctx.format_object(clause.trait_id);
+    let generics = if generics.is_empty() {
+        "".to_string()
+    } else {
+        generics.fmt_with_ctx(ctx)
+    };
+    format!("{self_ty}: {trait_name}{generics}")
+}
+
+/// Print the stub of a function, in Rust syntax.
+fn fun_to_stub(ctx: &impl AstFormatter, decl: &FunDecl) -> String {
+    let sig = &decl.signature;
+    let ctx = &ctx.set_generics(&sig.generics);
+
+    let name = match decl.name.name.last() {
+        Some(PathElem::Ident(name, _)) => name.clone(),
+        _ => "_".to_string(),
+    };
+
+    // Generic parameters
+    let regions = sig.generics.regions.iter().map(|r| r.to_string());
+    let types = sig.generics.types.iter().map(|ty| ty.to_string());
+    let const_generics = sig
+        .generics
+        .const_generics
+        .iter()
+        .map(|cg| format!("const {}: {}", cg.name, cg.ty.fmt_with_ctx(ctx)));
+    let params: Vec<String> = regions.chain(types).chain(const_generics).collect();
+    let params = if params.is_empty() {
+        "".to_string()
+    } else {
+        format!("<{}>", params.join(", "))
+    };
+
+    // Inputs and output
+    let inputs: Vec<String> = sig
+        .inputs
+        .iter()
+        .enumerate()
+        .map(|(i, ty)| format!("arg{i}: {}", ty.fmt_with_ctx(ctx)))
+        .collect();
+    let inputs = inputs.join(", ");
+    let output = if sig.output.is_unit() {
+        "".to_string()
+    } else {
+        format!(" -> {}", sig.output.fmt_with_ctx(ctx))
+    };
+
+    // Where clauses
+    let clauses = sig
+        .generics
+        .trait_clauses
+        .iter()
+        .map(|c| clause_to_rust(ctx, c));
+    let types_outlive = sig
+        .preds
+        .types_outlive
+        .iter()
+        .map(|OutlivesPred(x, y)| format!("{}: {}", x.fmt_with_ctx(ctx), y.fmt_with_ctx(ctx)));
+    let regions_outlive = sig
+        .preds
+        .regions_outlive
+        .iter()
+        .map(|OutlivesPred(x, y)| format!("{}: {}", x.fmt_with_ctx(ctx), y.fmt_with_ctx(ctx)));
+    let clauses: Vec<String> = clauses
+        .chain(types_outlive)
+        .chain(regions_outlive)
+        .collect();
+    let clauses = if clauses.is_empty() {
+        "".to_string()
+    } else {
+        let clauses: Vec<String> = clauses.into_iter().map(|c| format!("\n    {c},")).collect();
+        format!("\nwhere{}", clauses.join(""))
+    };
+
+    // The constraints on the associated types can't be written in a where
+    // clause: we print them in comments
+    let constraints: Vec<String> = sig
+        .preds
+        .trait_type_constraints
+        .iter()
+        .map(|c| format!("// Constraint: {}\n", c.fmt_with_ctx(ctx)))
+        .collect();
+
+    let unsafe_kw = if sig.is_unsafe { "unsafe " } else { "" };
+    format!(
+        "// {}\n{}{unsafe_kw}fn {name}{params}({inputs}){output}{clauses};\n",
+        decl.name.fmt_with_ctx(ctx),
+        constraints.join("")
+    )
+}
+
+/// Generate the stubs of all the opaque functions.
+///
+/// We ignore the trait method declarations and the foreign functions: they
+/// don't have bodies, but they are not opaque.
+pub fn generate(ctx: &TransCtx) -> String {
+    let fmt_ctx = ctx.into_fmt();
+    let stubs: Vec<String> = ctx
+        .fun_decls
+        .iter()
+        .filter(|d| {
+            d.body.is_none() && !matches!(d.kind, FunKind::TraitMethodDecl(..) | FunKind::Foreign)
+        })
+        .map(|d| fun_to_stub(&fmt_ctx, d))
+        .collect();
+    info!("Generated {} stubs for the opaque functions", stubs.len());
+    stubs.join("\n")
+}
@@ -0,0 +1,91 @@
+//! The information about the target platform which the extraction depends on.
+//!
+//! The same crate extracted for two different targets may lead to different
+//! files: the size of `usize` and `isize` depends on the width of the
+//! pointers, which changes the bounds of those integers (and thus the constants
+//! we can evaluate, the layouts of the types, etc.). The target is given to
+//! Charon with `--target` (see [crate::cli_options::CliOpts::target]), and we
+//! record the information we assumed in the generated files and in the
+//! manifest (see [crate::manifest]).
+
+use crate::types::IntegerTy;
+use crate::values::ScalarValue;
+use serde::{Deserialize, Serialize};
+
+/// The information about the target which the extraction depends on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TargetInfo {
+    /// The width of the pointers, in bits. This is also the width of `usize`
+    /// and `isize`.
+    pub pointer_width: u32,
+    /// The order of the bytes in the memory representation of the integers.
+    pub endianness: Endianness,
+}
+
+/// The endianness of a target.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Endianness {
+    Little,
+    Big,
+}
+
+/// The bounds of an integer type (included).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IntegerBounds {
+    pub min: i128,
+    pub max: u128,
+}
+
+impl TargetInfo {
+    /// Compute the information we need from a target specification.
+    pub fn from_target(target: &rustc_target::spec::Target) -> Self {
+        let endianness = match target.endian {
+            rustc_target::abi::Endian::Little => Endianness::Little,
+            rustc_target::abi::Endian::Big => Endianness::Big,
+        };
+        TargetInfo {
+            pointer_width: target.pointer_width,
+            endianness,
+        }
+    }
+
+    /// The information about the target we are compiling for.
+    pub fn from_session(sess: &rustc_session::Session) -> Self {
+        Self::from_target(&sess.target)
+    }
+
+    /// The size of a pointer, in bytes.
+    pub fn pointer_size(&self) -> usize {
+        (self.pointer_width / 8) as usize
+    }
+
+    /// The bounds of an integer type, for this target.
+    pub fn integer_bounds(&self, ty: IntegerTy) -> IntegerBounds {
+        let bits = (ty.size(self) * 8) as u32;
+        if ty.is_signed() {
+            IntegerBounds {
+                min: -(1i128 << (bits - 1)),
+                max: (1u128 << (bits - 1)) - 1,
+            }
+        } else {
+            IntegerBounds {
+                min: 0,
+                max: u128::MAX >> (128 - bits),
+            }
+        }
+    }
+
+    /// Check if a scalar value is in the bounds of its type, for this target.
+    ///
+    /// The scalar values are always in the bounds of the host: this is only
+    /// relevant for `usize` and `isize` when the target pointers are smaller
+    /// than the host pointers.
+    pub fn scalar_in_bounds(&self, v: &ScalarValue) -> bool {
+        let bounds = self.integer_bounds(v.get_integer_ty());
+        match (v.as_int(), v.as_uint()) {
+            (Ok(v), _) => bounds.min <= v && v <= bounds.max as i128,
+            (_, Ok(v)) => v <= bounds.max,
+            _ => unreachable!(),
+        }
+    }
+}
@@ -6,15 +6,54 @@ use crate::types::*;
 use crate::values::*;
 use hax_frontend_exporter as hax;
 
+/// Convert a constant value to a const generic. The structured values (which
+/// appear with `#![feature(adt_const_params)]`) are converted recursively.
+/// Return `None` if the value can't be used as a const generic.
+fn constant_value_to_const_generic(value: &RawConstantExpr) -> Option<ConstGeneric> {
+    match value {
+        RawConstantExpr::Literal(v) => Some(ConstGeneric::Value(v.clone())),
+        RawConstantExpr::Global(v) => Some(ConstGeneric::Global(*v)),
+        RawConstantExpr::Var(v) => Some(ConstGeneric::Var(*v)),
+        RawConstantExpr::Adt(variant_id, fields) => {
+            let fields = fields
+                .iter()
+                .map(|f| constant_value_to_const_generic(&f.value))
+                .collect::<Option<Vec<ConstGeneric>>>()?;
+            Some(ConstGeneric::Adt(*variant_id, fields))
+        }
+        RawConstantExpr::TraitConst(trait_ref, generics, name) => Some(ConstGeneric::TraitConst(
+            trait_ref.clone(),
+            generics.clone(),
+            name.clone(),
+        )),
+        RawConstantExpr::Array(_)
+        | RawConstantExpr::Ref(_)
+        | RawConstantExpr::FnPtr { .. }
+        | RawConstantExpr::GlobalDeclRef(_) => None,
+    }
+}
+
 impl<'tcx, 'ctx, 'ctx1> BodyTransCtx<'tcx, 'ctx, 'ctx1> {
     fn translate_constant_literal_to_raw_constant_expr(
         &mut self,
         span: rustc_span::Span,
+        ty: &hax::Ty,
         v: &hax::ConstantLiteral,
     ) -> Result<RawConstantExpr, Error> {
         let lit = match v {
-            hax::ConstantLiteral::ByteStr(..) => {
-                error_or_panic!(self, span, "byte str constants are not supported yet");
+            hax::ConstantLiteral::ByteStr(bytes, _) => {
+                // The string literals (`&str`) are also given as byte strings:
+                // we use the type to distinguish them
+                if matches!(ty, hax::Ty::Ref(_, ty, _) if matches!(**ty, hax::Ty::Str)) {
+                    match String::from_utf8(bytes.clone()) {
+                        Ok(s) => Literal::Str(s),
+                        Err(_) => {
+                            error_or_panic!(self, span, "Invalid UTF-8 in a string literal")
+                        }
+                    }
+                } else {
+                    Literal::ByteStr(bytes.clone())
+                }
             }
             hax::ConstantLiteral::Char(c) => Literal::Char(*c),
             hax::ConstantLiteral::Bool(b) => Literal::Bool(*b),
@@ -50,6 +89,21 @@ impl<'tcx, 'ctx, 'ctx1> BodyTransCtx<'tcx, 'ctx, 'ctx1> {
         Ok(RawConstantExpr::Literal(lit))
     }
 
+    /// If the constant is the name of a static, return its id.
+    fn static_global_name(&self, v: &hax::ConstantExprKind) -> Option<rustc_hir::def_id::DefId> {
+        match v {
+            hax::ConstantExprKind::GlobalName { id } => {
+                let id = id.rust_def_id.unwrap();
+                if self.t_ctx.tcx.is_static(id) {
+                    Some(id)
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        }
+    }
+
     pub(crate) fn translate_constant_expr_kind_to_constant_expr(
         &mut self,
         span: rustc_span::Span,
@@ -60,7 +114,7 @@ impl<'tcx, 'ctx, 'ctx1> BodyTransCtx<'tcx, 'ctx, 'ctx1> {
         let erase_regions = true;
         let value = match v {
             ConstantExprKind::Literal(lit) => {
-                self.translate_constant_literal_to_raw_constant_expr(span, lit)?
+                self.translate_constant_literal_to_raw_constant_expr(span, ty, lit)?
             }
             ConstantExprKind::Adt {
                 info: _,
@@ -74,8 +128,12 @@ impl<'tcx, 'ctx, 'ctx1> BodyTransCtx<'tcx, 'ctx, 'ctx1> {
                 let vid = vid.map(VariantId::Id::new);
                 RawConstantExpr::Adt(vid, fields)
             }
-            ConstantExprKind::Array { .. } => {
-                error_or_panic!(self, span, "array constants are not supported yet")
+            ConstantExprKind::Array { fields } => {
+                let fields: Vec<ConstantExpr> = fields
+                    .iter()
+                    .map(|f| self.translate_constant_expr_to_constant_expr(span, f))
+                    .try_collect()?;
+                RawConstantExpr::Array(fields)
             }
             ConstantExprKind::Tuple { fields } => {
                 let fields: Vec<ConstantExpr> = fields
@@ -106,12 +164,27 @@ impl<'tcx, 'ctx, 'ctx1> BodyTransCtx<'tcx, 'ctx, 'ctx1> {
                 let name = TraitItemName(name.clone());
                 RawConstantExpr::TraitConst(trait_ref, generics, name)
             }
+            ConstantExprKind::GlobalName { id }
+                if matches!(ty, hax::Ty::Ref(..) | hax::Ty::RawPtr(..))
+                    && self.t_ctx.tcx.is_static(id.rust_def_id.unwrap()) =>
+            {
+                // A static referenced by address
+                RawConstantExpr::GlobalDeclRef(
+                    self.translate_global_decl_id(span, id.rust_def_id.unwrap()),
+                )
+            }
             ConstantExprKind::GlobalName { id } => RawConstantExpr::Global(
                 self.translate_global_decl_id(span, id.rust_def_id.unwrap()),
             ),
             ConstantExprKind::Borrow(be) => {
-                let be = self.translate_constant_expr_to_constant_expr(span, be)?;
-                RawConstantExpr::Ref(Box::new(be))
+                if let Some(id) = self.static_global_name(&be.contents) {
+                    // A reference to a static (`&STATIC`, or the raw pointer
+                    // to a mutable static): we don't copy the static.
+                    RawConstantExpr::GlobalDeclRef(self.translate_global_decl_id(span, id))
+                } else {
+                    let be = self.translate_constant_expr_to_constant_expr(span, be)?;
+                    RawConstantExpr::Ref(Box::new(be))
+                }
             }
             ConstantExprKind::ConstRef { id } => {
                 let var_id = self.const_generic_vars_map.get(&id.index);
@@ -140,8 +213,15 @@ impl<'tcx, 'ctx, 'ctx1> BodyTransCtx<'tcx, 'ctx, 'ctx1> {
                     trait_refs,
                     trait_info,
                 )?;
-                let SubstFunIdOrPanic::Fun(fn_id) = fn_id else  { unreachable!() };
-                RawConstantExpr::FnPtr(fn_id.func)
+                match fn_id {
+                    SubstFunIdOrPanic::Fun(fn_id) => RawConstantExpr::FnPtr(fn_id.func),
+                    SubstFunIdOrPanic::Virtual(_) => error_or_panic!(
+                        self,
+                        span,
+                        "Unsupported function pointer to a method of a trait object"
+                    ),
+                    SubstFunIdOrPanic::Panic => unreachable!(),
+                }
             }
             ConstantExprKind::Todo(msg) => {
                 // Case not yet handled by hax
@@ -173,20 +253,18 @@ impl<'tcx, 'ctx, 'ctx1> BodyTransCtx<'tcx, 'ctx, 'ctx1> {
         let value = self
             .translate_constant_expr_to_constant_expr(span, v)?
             .value;
-        match value {
-            RawConstantExpr::Literal(v) => Ok(ConstGeneric::Value(v)),
-            RawConstantExpr::Global(v) => Ok(ConstGeneric::Global(v)),
-            RawConstantExpr::Adt(..)
-            | RawConstantExpr::TraitConst { .. }
-            | RawConstantExpr::Ref(_)
-            | RawConstantExpr::FnPtr { .. } => {
+        match constant_value_to_const_generic(&value) {
+            Some(cg) => Ok(cg),
+            None => {
                 error_or_panic!(
                     self,
                     span,
-                    format!("Unexpected constant generic: {:?}", value)
+                    format!(
+                        "Unexpected constant generic: {}",
+                        self.fmt_for_error(&value)
+                    )
                 )
             }
-            RawConstantExpr::Var(v) => Ok(ConstGeneric::Var(v)),
         }
     }
 
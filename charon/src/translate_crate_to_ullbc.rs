@@ -1,6 +1,13 @@
+use crate::assumed::AssumedFuns;
+use crate::batch::BatchIndex;
+use crate::cache::ExtractionCache;
 use crate::cli_options::CliOpts;
 use crate::get_mir::{extract_constants_at_top_level, MirLevel};
+use crate::hooks::ExtractionHook;
 use crate::meta;
+use crate::progress::{Event, ProgressReporter};
+use crate::stats::TranslationStats;
+use crate::target::TargetInfo;
 use crate::translate_ctx::*;
 use crate::translate_functions_to_ullbc;
 use crate::types as ty;
@@ -8,7 +15,9 @@ use crate::ullbc_ast as ast;
 use hax_frontend_exporter as hax;
 use hax_frontend_exporter::SInto;
 use linked_hash_set::LinkedHashSet;
-use rustc_hir::{Defaultness, ImplItem, ImplItemKind, Item, ItemKind};
+use rustc_hir::{
+    Defaultness, ForeignItem, ForeignItemKind, ImplItem, ImplItemKind, Item, ItemKind,
+};
 use rustc_middle::ty::TyCtxt;
 use rustc_session::Session;
 use std::collections::{BTreeSet, HashMap, HashSet};
@@ -59,6 +68,28 @@ impl<'tcx, 'ctx> TransCtx<'tcx, 'ctx> {
         }
     }
 
+    fn register_local_hir_foreign_item(&mut self, foreign_item: &ForeignItem) {
+        let def_id = foreign_item.owner_id.to_def_id();
+
+        // Match on the foreign item kind
+        match &foreign_item.kind {
+            ForeignItemKind::Fn(..) => {
+                // A function declared in an `extern` block:
+                // ```
+                // extern "C" {
+                //   fn abs(x: i32) -> i32; // HERE
+                // }
+                // ```
+                let _ = self.translate_fun_decl_id(&None, def_id);
+            }
+            ForeignItemKind::Static(..) | ForeignItemKind::Type => {
+                // We don't support the foreign statics and types for now: we
+                // only report an error if they are used
+                trace!("Ignoring the foreign item: {:?}", def_id);
+            }
+        }
+    }
+
     /// General function to register a MIR item. It is called on all the top-level
     /// items. This includes: crate inclusions and `use` instructions (which are
     /// ignored), but also type and functions declarations.
@@ -104,8 +135,7 @@ impl<'tcx, 'ctx> TransCtx<'tcx, 'ctx> {
                 // We ignore the type aliases - it seems they are inlined
             }
             ItemKind::OpaqueTy(_) => unimplemented!(),
-            ItemKind::Union(..) => unimplemented!(),
-            ItemKind::Enum(..) | ItemKind::Struct(_, _) => {
+            ItemKind::Enum(..) | ItemKind::Struct(_, _) | ItemKind::Union(..) => {
                 let _ = self.translate_type_decl_id(&None, def_id);
             }
             ItemKind::Fn(_, _, _) => {
@@ -159,6 +189,18 @@ impl<'tcx, 'ctx> TransCtx<'tcx, 'ctx> {
                     self.register_local_hir_impl_item(false, impl_item);
                 }
             }
+            ItemKind::ForeignMod { items, .. } => {
+                trace!("extern block");
+                // Explore the items
+                let hir_map = self.tcx.hir();
+                for foreign_item_ref in *items {
+                    // foreign_item_ref only gives the reference of the foreign
+                    // item: we need to look it up
+                    let foreign_item = hir_map.foreign_item(foreign_item_ref.id);
+
+                    self.register_local_hir_foreign_item(foreign_item);
+                }
+            }
             ItemKind::Use(_, _) => {
                 // Ignore
             }
@@ -200,9 +242,13 @@ impl<'tcx, 'ctx> TransCtx<'tcx, 'ctx> {
 }
 
 /// Translate all the declarations in the crate.
+#[allow(clippy::too_many_arguments)]
 pub fn translate<'tcx, 'ctx>(
     crate_info: CrateInfo,
     options: &CliOpts,
+    batch_index: Option<&BatchIndex>,
+    assumed_funs: AssumedFuns,
+    hook: Option<Box<dyn ExtractionHook>>,
     session: &'ctx Session,
     tcx: TyCtxt<'tcx>,
     mir_level: MirLevel,
@@ -213,6 +259,11 @@ pub fn translate<'tcx, 'ctx>(
             inline_macro_calls: Vec::new(),
         },
     );
+    // Load the extraction cache, if the user asked for it (see [crate::cache])
+    let cache = options.cache.as_ref().map(|dir| {
+        let key = ExtractionCache::compute_key(tcx, options);
+        ExtractionCache::load(dir, &crate_info.crate_name, key)
+    });
     let mut ctx = TransCtx {
         session,
         tcx,
@@ -223,6 +274,22 @@ pub fn translate<'tcx, 'ctx>(
         errors_as_warnings: options.errors_as_warnings,
         error_count: 0,
         no_code_duplication: options.no_code_duplication,
+        keep_unwind: options.keep_unwind,
+        keep_marker_traits: options.keep_marker_traits,
+        extract_layouts: options.layouts,
+        fmt_machinery: options.fmt_machinery,
+        intrinsics: options.intrinsics,
+        assumed_funs,
+        target_info: TargetInfo::from_session(session),
+        path_prefix_map: options.path_prefix_map.clone(),
+        emit_source: options.emit_source,
+        name_opts: options.names.clone(),
+        progress: options.progress_fd.map(ProgressReporter::from_fd),
+        applied_passes: Vec::new(),
+        hook,
+        cache,
+        stats: options.stats.then(TranslationStats::default),
+        vetoed_ids: HashSet::new(),
         all_ids: LinkedHashSet::new(),
         stack: BTreeSet::new(),
         def_id: None,
@@ -230,6 +297,7 @@ pub fn translate<'tcx, 'ctx>(
         id_to_file: HashMap::new(),
         real_file_counter: meta::LocalFileId::Generator::new(),
         virtual_file_counter: meta::VirtualFileId::Generator::new(),
+        linked_ids: HashMap::new(),
         dep_sources: HashMap::new(),
         decls_with_errors: HashSet::new(),
         ignored_failed_decls: HashSet::new(),
@@ -238,15 +306,25 @@ pub fn translate<'tcx, 'ctx>(
         fun_id_map: ast::FunDeclId::MapGenerator::new(),
         fun_decls: ast::FunDeclId::Map::new(),
         global_id_map: ast::GlobalDeclId::MapGenerator::new(),
+        promoted_id_map: HashMap::new(),
         global_decls: ast::GlobalDeclId::Map::new(),
         trait_decl_id_map: ast::TraitDeclId::MapGenerator::new(),
         trait_decls: ast::TraitDeclId::Map::new(),
         trait_impl_id_map: ast::TraitImplId::MapGenerator::new(),
         trait_impl_id_to_def_id: HashMap::new(),
         trait_impls: ast::TraitImplId::Map::new(),
+        impl_block_id_map: ast::ImplBlockId::MapGenerator::new(),
+        impl_blocks: ast::ImplBlockId::Map::new(),
+        opaque_warnings: Vec::new(),
         ordered_decls: None,
     };
 
+    // If we extract the crate as part of a batch, start from the state
+    // recorded in the batch index (see [crate::batch])
+    if let Some(index) = batch_index {
+        index.seed(&mut ctx);
+    }
+
     // First push all the items in the stack of items to translate.
     //
     // We explore the crate by starting with the root module.
@@ -283,16 +361,56 @@ pub fn translate<'tcx, 'ctx>(
     //
     // Note that the order in which we translate the definitions doesn't matter:
     // we never need to lookup a translated definition, and only use the map
-    // from Rust ids to translated ids.
-    while let Some(id) = ctx.stack.pop_first() {
+    // from Rust ids to translated ids. It does however determine the ids we
+    // assign to the definitions we encounter: the order of the stack is stable
+    // across runs (see [StackItem]), so that the output is reproducible.
+    while let Some(StackItem { id, .. }) = ctx.stack.pop_first() {
         trace!("About to translate id: {:?}", id);
+        // We only compute the name of the item if we need to report the progress
+        let name = ctx.progress.as_ref().map(|_| tcx.def_path_str(id.get_id()));
+        if let Some(name) = &name {
+            ctx.report_progress(&Event::ItemStarted { name });
+        }
+        let start = ctx.stats.as_ref().map(|_| std::time::Instant::now());
         match id {
             OrdRustId::Type(id) => ctx.translate_type(id),
-            OrdRustId::Fun(id) | OrdRustId::ConstFun(id) => ctx.translate_function(id),
-            OrdRustId::Global(id) => ctx.translate_global(id),
+            OrdRustId::Fun(id) | OrdRustId::ConstFun(id) => {
+                ctx.translate_with_cache(id, TransCtx::translate_function)
+            }
+            OrdRustId::Global(id) => ctx.translate_with_cache(id, TransCtx::translate_global),
+            OrdRustId::Promoted(id, promoted) => ctx.translate_promoted(id, promoted),
             OrdRustId::TraitDecl(id) => ctx.translate_trait_decl(id),
             OrdRustId::TraitImpl(id) => ctx.translate_trait_impl(id),
+            OrdRustId::ImplBlock(id) => ctx.translate_impl_block(id),
+        }
+        if let Some(start) = start
+            && let Some(stats) = &mut ctx.stats
+        {
+            let elapsed = start.elapsed();
+            stats
+                .item_times
+                .push((tcx.def_path_str(id.get_id()), elapsed));
         }
+        if let Some(name) = &name {
+            ctx.report_progress(&Event::ItemFinished { name });
+        }
+    }
+
+    // Fill the lists of methods of the inherent impl blocks. We can only do
+    // this once all the functions are translated, because we only translate
+    // the methods which are used.
+    for decl in ctx.fun_decls.iter() {
+        if let ast::FunKind::InherentMethod(impl_id) = decl.kind
+            && let Some(impl_block) = ctx.impl_blocks.get_mut(impl_id)
+        {
+            impl_block.methods.push(decl.def_id);
+        }
+    }
+
+    // Save the extraction cache. Note that the errors are not fatal: we
+    // simply won't be able to reuse the translations in the next run.
+    if let Some(cache) = &ctx.cache {
+        let _ = cache.save();
     }
 
     // Return the context
@@ -1,12 +1,21 @@
 //! The translation contexts.
-use crate::formatter::{DeclFormatter, FmtCtx, Formatter, IntoFormatter};
+use crate::assumed::AssumedFuns;
+use crate::cache::ExtractionCache;
+use crate::cli_options::{FmtMachinery, Intrinsics, NameOpts, PathPrefix};
+use crate::formatter::{
+    DeclFormatter, FmtCtx, FmtWithCtx, Formatter, IntoFormatter, NameFmtOptions,
+};
 use crate::gast::*;
-use crate::get_mir::MirLevel;
+use crate::get_mir::{external_mir_is_available, MirLevel};
+use crate::hooks::ExtractionHook;
 use crate::llbc_ast;
 use crate::meta;
-use crate::meta::{FileId, FileName, LocalFileId, Meta, VirtualFileId};
+use crate::meta::{AttrInfo, BacktraceId, FileId, FileName, LocalFileId, Meta, VirtualFileId};
 use crate::names::Name;
+use crate::progress::{Event, ProgressReporter};
 use crate::reorder_decls::{AnyTransId, DeclarationGroup, DeclarationsGroups, GDeclarationGroup};
+use crate::stats::TranslationStats;
+use crate::target::TargetInfo;
 use crate::translate_predicates::NonLocalTraitClause;
 use crate::types::*;
 use crate::ullbc_ast as ast;
@@ -17,9 +26,10 @@ use im::OrdMap;
 use linked_hash_set::LinkedHashSet;
 use macros::VariantIndexArity;
 use rustc_error_messages::MultiSpan;
-use rustc_hir::def_id::DefId;
+use rustc_hir::def_id::{DefId, DefPathHash};
 use rustc_middle::ty::TyCtxt;
 use rustc_session::Session;
+use rustc_span::hygiene::{ExpnKind, MacroKind};
 use std::cmp::{Ord, Ordering, PartialOrd};
 use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
 use std::fmt;
@@ -32,6 +42,7 @@ macro_rules! error_or_panic {
             let e = crate::common::Error {
                 span: $span,
                 msg: $msg.to_string(),
+                skipped: false,
             };
             return (Err(e));
         } else {
@@ -48,7 +59,11 @@ macro_rules! error_assert {
             let msg = format!("assertion failure: {:?}", stringify!($b));
             $ctx.span_err($span, &msg);
             if $ctx.continue_on_failure() {
-                let e = crate::common::Error { span: $span, msg };
+                let e = crate::common::Error {
+                    span: $span,
+                    msg,
+                    skipped: false,
+                };
                 return (Err(e));
             } else {
                 panic!("{}", msg);
@@ -62,6 +77,7 @@ macro_rules! error_assert {
                 let e = crate::common::Error {
                     span: $span,
                     msg: $msg.to_string(),
+                    skipped: false,
                 };
                 return (Err(e));
             } else {
@@ -115,6 +131,22 @@ macro_rules! register_error_or_panic {
 }
 pub(crate) use register_error_or_panic;
 
+/// Generate the id of a declaration we just encountered. If the declaration
+/// was extracted by a previous crate of the batch, we reuse its id (see
+/// [TransCtx::linked_ids]).
+macro_rules! link_or_insert_id {
+    ($ctx:expr, $map:ident, $kind:ident, $id:expr) => {{
+        match $ctx.linked_ids.get(&$id) {
+            Some(AnyTransId::$kind(linked_id)) => {
+                let linked_id = *linked_id;
+                $ctx.$map.map.insert($id, linked_id);
+                linked_id
+            }
+            _ => $ctx.$map.insert($id),
+        }
+    }};
+}
+
 /// We use this to save the origin of an id. This is useful for the external
 /// dependencies, especially if some external dependencies don't extract:
 /// we use this information to tell the user what is the code which
@@ -134,6 +166,12 @@ impl DepSource {
 pub struct CrateInfo {
     pub crate_name: String,
     pub opaque_mods: HashSet<String>,
+    /// The traits whose derived implementations we consider as opaque (see
+    /// [crate::cli_options::CliOpts::skip_derived]).
+    pub skip_derived: HashSet<String>,
+    /// The dependency crates whose items we translate transparently (see
+    /// [crate::cli_options::CliOpts::extract_dependencies]).
+    pub extract_dependencies: HashSet<String>,
 }
 
 impl CrateInfo {
@@ -154,20 +192,26 @@ impl CrateInfo {
 #[derive(Clone, Copy, Debug, Eq, PartialEq, VariantIndexArity)]
 pub enum OrdRustId {
     Global(DefId),
+    /// A promoted constant: the id of the function (or global) it comes from,
+    /// and its index (see [TransCtx::promoted_id_map]).
+    Promoted(DefId, usize),
     ConstFun(DefId),
     TraitDecl(DefId),
     TraitImpl(DefId),
+    ImplBlock(DefId),
     Fun(DefId),
     Type(DefId),
 }
 
 impl OrdRustId {
-    fn get_id(&self) -> DefId {
+    pub(crate) fn get_id(&self) -> DefId {
         match self {
             OrdRustId::Global(id)
+            | OrdRustId::Promoted(id, _)
             | OrdRustId::ConstFun(id)
             | OrdRustId::TraitDecl(id)
             | OrdRustId::TraitImpl(id)
+            | OrdRustId::ImplBlock(id)
             | OrdRustId::Fun(id)
             | OrdRustId::Type(id) => *id,
         }
@@ -183,7 +227,12 @@ impl PartialOrd for OrdRustId {
         } else {
             let id0 = self.get_id();
             let id1 = other.get_id();
-            Option::Some(id0.cmp(&id1))
+            // The promoted constants coming from the same item have the same id
+            let index = |id: &OrdRustId| match id {
+                OrdRustId::Promoted(_, index) => *index,
+                _ => 0,
+            };
+            Option::Some(id0.cmp(&id1).then(index(self).cmp(&index(other))))
         }
     }
 }
@@ -194,6 +243,50 @@ impl Ord for OrdRustId {
     }
 }
 
+/// An item of the stack of declarations to translate (see [TransCtx::stack]).
+///
+/// We order the items by kind, like the [OrdRustId], but then by the hashes of
+/// their definition paths rather than by their [DefId]: the hashes don't
+/// depend on the order in which rustc loads the crates, and are stable across
+/// runs. As we assign the ids of the declarations when we first encounter
+/// them, the order in which we translate the declarations determines those
+/// ids: this makes the generated files reproducible.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct StackItem {
+    pub id: OrdRustId,
+    hash: DefPathHash,
+}
+
+impl StackItem {
+    pub(crate) fn new(tcx: TyCtxt, id: OrdRustId) -> Self {
+        StackItem {
+            id,
+            hash: tcx.def_path_hash(id.get_id()),
+        }
+    }
+}
+
+impl PartialOrd for StackItem {
+    fn partial_cmp(&self, other: &StackItem) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for StackItem {
+    fn cmp(&self, other: &StackItem) -> Ordering {
+        let (vid0, _) = self.id.variant_index_arity();
+        let (vid1, _) = other.id.variant_index_arity();
+        // The promoted constants coming from the same item have the same hash
+        let index = |id: &OrdRustId| match id {
+            OrdRustId::Promoted(_, index) => *index,
+            _ => 0,
+        };
+        vid0.cmp(&vid1)
+            .then(self.hash.cmp(&other.hash))
+            .then(index(&self.id).cmp(&index(&other.id)))
+    }
+}
+
 /// Translation context containing the top-level definitions.
 pub struct TransCtx<'tcx, 'ctx> {
     /// The compiler session
@@ -216,12 +309,54 @@ pub struct TransCtx<'tcx, 'ctx> {
     /// reconstruction (note that because several patterns in a match may lead
     /// to the same branch, it is node always possible not to duplicate code).
     pub no_code_duplication: bool,
+    /// Keep the unwinding edges (see [crate::cli_options::CliOpts::keep_unwind]).
+    pub keep_unwind: bool,
+    /// Keep the marker traits (see
+    /// [crate::cli_options::CliOpts::keep_marker_traits]).
+    pub keep_marker_traits: bool,
+    /// How to print the names (see [crate::cli_options::CliOpts::names]).
+    pub name_opts: NameOpts,
+    /// Compute the layouts of the types (see [crate::cli_options::CliOpts::layouts]).
+    pub extract_layouts: bool,
+    /// How to handle the formatting machinery (see
+    /// [crate::cli_options::CliOpts::fmt_machinery]).
+    pub fmt_machinery: FmtMachinery,
+    /// How to handle the intrinsics (see
+    /// [crate::cli_options::CliOpts::intrinsics]).
+    pub intrinsics: Intrinsics,
+    /// The registry of the assumed functions (see
+    /// [crate::cli_options::CliOpts::assumed_funs]).
+    pub assumed_funs: AssumedFuns,
+    /// The information about the target (see [crate::target]).
+    pub target_info: TargetInfo,
+    /// How to rewrite the file names (see
+    /// [crate::cli_options::CliOpts::path_prefix_map]).
+    pub path_prefix_map: Vec<PathPrefix>,
+    /// Store the source text of the items (see
+    /// [crate::cli_options::CliOpts::emit_source]).
+    pub emit_source: bool,
+    /// Where to report the progress of the extraction, if the user asked for it
+    /// (see [crate::progress]).
+    pub progress: Option<ProgressReporter>,
+    /// The micro-passes we applied to the crate, in order (we record them in
+    /// the manifest: see [crate::manifest]).
+    pub applied_passes: Vec<&'static str>,
+    /// The hook given by the user, if there is one (see [crate::hooks]).
+    pub hook: Option<Box<dyn ExtractionHook>>,
+    /// The extraction cache, if the user asked for it (see [crate::cache]).
+    pub cache: Option<ExtractionCache>,
+    /// The statistics about the translation, if the user asked for them (see
+    /// [crate::stats]).
+    pub stats: Option<TranslationStats>,
+    /// The ids of the items vetoed by the hook: we treat them as opaque.
+    pub vetoed_ids: HashSet<DefId>,
     /// All the ids, in the order in which we encountered them
     pub all_ids: LinkedHashSet<AnyTransId>,
     /// The declarations we came accross and which we haven't translated yet.
     /// We use an ordered set to make sure we translate them in a specific
-    /// order (this avoids stealing issues when querying the MIR bodies).
-    pub stack: BTreeSet<OrdRustId>,
+    /// order (this avoids stealing issues when querying the MIR bodies, and
+    /// makes the ids we assign reproducible: see [StackItem]).
+    pub stack: BTreeSet<StackItem>,
     /// The id of the definition we are exploring
     pub def_id: Option<DefId>,
     /// File names to ids and vice-versa
@@ -229,6 +364,10 @@ pub struct TransCtx<'tcx, 'ctx> {
     pub id_to_file: HashMap<FileId::Id, FileName>,
     pub real_file_counter: LocalFileId::Generator,
     pub virtual_file_counter: VirtualFileId::Generator,
+    /// The declarations of the crates previously extracted in the batch we
+    /// belong to (see [crate::batch]): when we refer to one of them, we reuse
+    /// its id rather than generating a fresh one.
+    pub linked_ids: HashMap<DefId, AnyTransId>,
     /// The map from Rust type ids to translated type ids
     pub type_id_map: TypeDeclId::MapGenerator<DefId>,
     /// The translated type definitions
@@ -247,6 +386,11 @@ pub struct TransCtx<'tcx, 'ctx> {
     pub fun_decls: ast::FunDecls,
     /// The map from Rust global ids to translated global ids
     pub global_id_map: ast::GlobalDeclId::MapGenerator<DefId>,
+    /// The map from the promoted constants to translated global ids. The
+    /// promoted constants don't have a Rust id: we identify them with the id
+    /// of the function (or global) they come from, and their index. Their ids
+    /// are generated by [Self::global_id_map].
+    pub promoted_id_map: HashMap<(DefId, usize), ast::GlobalDeclId::Id>,
     /// The translated global definitions
     pub global_decls: ast::GlobalDecls,
     /// The map from Rust trait decl ids to translated trait decl ids
@@ -258,6 +402,13 @@ pub struct TransCtx<'tcx, 'ctx> {
     pub trait_impl_id_to_def_id: HashMap<ast::TraitImplId::Id, DefId>,
     /// The translated trait declarations
     pub trait_impls: ast::TraitImpls,
+    /// The map from Rust inherent impl blocks ids to translated impl blocks ids
+    pub impl_block_id_map: ast::ImplBlockId::MapGenerator<DefId>,
+    /// The translated inherent impl blocks
+    pub impl_blocks: ast::ImplBlocks,
+    /// The declarations which became opaque because we failed to translate
+    /// them (see [OpaqueWarning]).
+    pub opaque_warnings: Vec<OpaqueWarning>,
     /// The re-ordered groups of declarations, initialized as empty.
     pub ordered_decls: Option<DeclarationsGroups>,
 }
@@ -356,6 +507,25 @@ pub(crate) struct BodyTransCtx<'tcx, 'ctx, 'ctx1> {
     /// writing the translation functions as recursive functions. We do
     /// so because we had stack overflows in the past.
     pub blocks_stack: VecDeque<hax::BasicBlock>,
+    /// The trait instances used by the unsizing coercions to trait objects
+    /// (i.e., the vtables), indexed by the span of the coercion. We resolve
+    /// them on the rustc MIR before translating the body, because the hax
+    /// AST doesn't give us this information.
+    pub unsize_vtables: HashMap<rustc_span::Span, Vec<hax::ImplSource>>,
+    /// The promoted constants used in the body, indexed by their span: the
+    /// id of the item they come from, and their index. We retrieve them on
+    /// the rustc MIR before translating the body, because the hax AST doesn't
+    /// distinguish them from the other constants.
+    pub promoted_constants: HashMap<rustc_span::Span, Vec<(DefId, usize)>>,
+    /// The macro backtraces of the statements and the terminators of the body
+    /// (see [Meta::backtrace]).
+    pub backtraces: BacktraceId::Vector<Vec<meta::Span>>,
+    /// We use this map to share the identical backtraces.
+    pub backtraces_map: HashMap<Vec<meta::Span>, BacktraceId::Id>,
+    /// The level of the MIR of the body we translate. This is the level of
+    /// [TransCtx], except for the bodies of the items from the other crates,
+    /// which are always optimized (see [crate::get_mir::get_external_mir]).
+    pub mir_level: MirLevel,
 }
 
 impl<'tcx, 'ctx> TransCtx<'tcx, 'ctx> {
@@ -364,6 +534,18 @@ impl<'tcx, 'ctx> TransCtx<'tcx, 'ctx> {
     }
 
     pub fn span_err_no_register<S: Into<MultiSpan>>(&self, span: S, msg: &str) {
+        let span: MultiSpan = span.into();
+        if let Some(progress) = &self.progress {
+            let location = span
+                .primary_span()
+                .map(|span| self.session.source_map().span_to_diagnostic_string(span));
+            progress.emit(&Event::Warning {
+                message: msg,
+                span: location.as_deref(),
+                is_error: !self.errors_as_warnings,
+            });
+        }
+
         let msg = msg.to_string();
         if self.errors_as_warnings {
             self.session.span_warn(span, msg);
@@ -372,24 +554,63 @@ impl<'tcx, 'ctx> TransCtx<'tcx, 'ctx> {
         }
     }
 
+    /// Record that a declaration became opaque because we failed to translate
+    /// it (see [OpaqueWarning]).
+    pub(crate) fn register_opaque_warning(&mut self, name: &Name, span: meta::Span, reason: &str) {
+        self.opaque_warnings.push(OpaqueWarning {
+            name: name.clone(),
+            span,
+            reason: reason.to_string(),
+        });
+    }
+
     /// Span an error and register the error.
     pub fn span_err<S: Into<MultiSpan>>(&mut self, span: S, msg: &str) {
         self.span_err_no_register(span, msg);
         self.increment_error_count();
+        if let Some(stats) = &mut self.stats {
+            stats.register_error(msg);
+        }
         if let Some(id) = self.def_id {
             let _ = self.decls_with_errors.insert(id);
         }
     }
 
+    /// Format an object for an error message. We resolve the ids to the names
+    /// of the declarations translated so far.
+    pub(crate) fn fmt_for_error(&self, x: &dyn FmtWithCtx) -> String {
+        x.fmt_with_fmt_ctx(&self.into_fmt())
+    }
+
+    /// Record that we start applying a micro-pass, and report it.
+    pub(crate) fn start_pass(&mut self, pass: &'static str) {
+        self.applied_passes.push(pass);
+        self.report_progress(&Event::PassStarted { pass });
+    }
+
+    /// Report a progress event, if the user asked for it (see [crate::progress]).
+    pub(crate) fn report_progress(&self, event: &Event) {
+        if let Some(progress) = &self.progress {
+            progress.emit(event)
+        }
+    }
+
     fn increment_error_count(&mut self) {
         self.error_count += 1;
     }
 
     /// Register a file if it is a "real" file and was not already registered
-    fn register_file(&mut self, filename: FileName) -> FileId::Id {
+    pub(crate) fn register_file(&mut self, filename: FileName) -> FileId::Id {
         // Lookup the file if it was already registered
         match self.file_to_id.get(&filename) {
-            Option::Some(id) => *id,
+            Option::Some(id) => {
+                // The file may come from the batch index (see [crate::batch]),
+                // in which case it is not in [Self::id_to_file] yet
+                let id = *id;
+                self.record_file(&filename, id);
+                self.id_to_file.entry(id).or_insert(filename);
+                id
+            }
             Option::None => {
                 // Generate the fresh id
                 let id = match &filename {
@@ -400,6 +621,7 @@ impl<'tcx, 'ctx> TransCtx<'tcx, 'ctx> {
                     FileName::NotReal(_) => unimplemented!(),
                 };
                 self.file_to_id.insert(filename.clone(), id);
+                self.record_file(&filename, id);
                 self.id_to_file.insert(id, filename);
                 id
             }
@@ -414,8 +636,20 @@ impl<'tcx, 'ctx> TransCtx<'tcx, 'ctx> {
         self.translate_meta_from_rspan(rspan)
     }
 
+    /// Retrieve the doc comments and the attributes of a Rust definition
+    /// identified by its id, together with its source text if the user asked
+    /// for it.
+    pub(crate) fn translate_attr_info_from_rid(&self, def_id: DefId) -> AttrInfo {
+        let mut info = meta::get_attr_info_from_def_id(self.tcx, def_id);
+        if self.emit_source {
+            info.source_text = meta::get_source_text_from_def_id(self.session, self.tcx, def_id);
+        }
+        info
+    }
+
     pub fn translate_span(&mut self, rspan: hax::Span) -> meta::Span {
         let filename = meta::convert_filename(&rspan.filename);
+        let filename = meta::remap_filename(&self.path_prefix_map, filename);
         let file_id = match &filename {
             FileName::NotReal(_) => {
                 // For now we forbid not real filenames
@@ -424,8 +658,8 @@ impl<'tcx, 'ctx> TransCtx<'tcx, 'ctx> {
             FileName::Virtual(_) | FileName::Local(_) => self.register_file(filename),
         };
 
-        let beg = meta::convert_loc(rspan.lo);
-        let end = meta::convert_loc(rspan.hi);
+        let beg = meta::convert_loc(self.session, rspan.lo, rspan.rust_span.lo());
+        let end = meta::convert_loc(self.session, rspan.hi, rspan.rust_span.hi());
 
         // Put together
         meta::Span {
@@ -458,13 +692,44 @@ impl<'tcx, 'ctx> TransCtx<'tcx, 'ctx> {
             Meta {
                 span: parent_span,
                 generated_from_span: Some(span),
+                backtrace: None,
             }
         } else {
             Meta {
                 span,
                 generated_from_span: None,
+                backtrace: None,
+            }
+        }
+    }
+
+    /// Compute the macro backtrace of a statement or a terminator (see
+    /// [Meta::backtrace]).
+    pub fn translate_backtrace(
+        &mut self,
+        source_scopes: &hax::IndexVec<hax::SourceScope, hax::SourceScopeData>,
+        source_info: &hax::SourceInfo,
+    ) -> Vec<meta::Span> {
+        let mut backtrace = Vec::new();
+
+        // The macro expansions
+        for expn_data in source_info.span.rust_span.macro_backtrace() {
+            let call_site: hax::Span = expn_data.call_site.sinto(&self.hax_state);
+            // We ignore the spans which don't come from real files
+            if let FileName::NotReal(_) = meta::convert_filename(&call_site.filename) {
+                continue;
             }
+            backtrace.push(self.translate_span(call_site));
         }
+
+        // The inlinings
+        let mut scope_data = source_scopes.get(source_info.scope).unwrap();
+        while let Some(parent_scope) = scope_data.inlined_parent_scope {
+            scope_data = source_scopes.get(parent_scope).unwrap();
+            backtrace.push(self.translate_span(scope_data.span.clone()));
+        }
+
+        backtrace
     }
 
     // TODO: rename
@@ -475,22 +740,72 @@ impl<'tcx, 'ctx> TransCtx<'tcx, 'ctx> {
         Meta {
             span,
             generated_from_span: None,
+            backtrace: None,
         }
     }
 
     pub(crate) fn id_is_opaque(&mut self, id: DefId) -> bool {
+        if self.vetoed_ids.contains(&id) || self.is_skipped_derived_item(id) {
+            return true;
+        }
         let name = self.item_def_id_to_name(id);
         self.crate_info.is_opaque_decl(&name)
     }
 
+    /// Return `true` if `id` is an item of a trait implementation produced by
+    /// one of the derive macros the user asked us to skip (see
+    /// [crate::cli_options::CliOpts::skip_derived]).
+    fn is_skipped_derived_item(&self, id: DefId) -> bool {
+        if self.crate_info.skip_derived.is_empty() {
+            return false;
+        }
+        let Some(impl_id) = self.tcx.impl_of_method(id) else {
+            return false;
+        };
+        // We look at the macro expansion which produced the impl
+        match self.tcx.def_span(impl_id).ctxt().outer_expn_data().kind {
+            ExpnKind::Macro(MacroKind::Derive, name) => {
+                self.crate_info.skip_derived.contains(name.as_str())
+            }
+            _ => false,
+        }
+    }
+
     pub(crate) fn id_is_transparent(&mut self, id: DefId) -> bool {
         !self.id_is_opaque(id)
     }
 
+    /// Return `true` if `id` is an item from a dependency crate whose body we
+    /// should extract, and whose MIR is available (see
+    /// [crate::cli_options::CliOpts::extract_dependencies]).
+    pub(crate) fn id_is_extracted_dependency(&self, id: DefId) -> bool {
+        !id.is_local()
+            && self
+                .crate_info
+                .extract_dependencies
+                .contains(self.tcx.crate_name(id.krate).as_str())
+            && external_mir_is_available(self.tcx, id)
+    }
+
     pub(crate) fn push_id(&mut self, _rust_id: DefId, id: OrdRustId, trans_id: AnyTransId) {
         // Add the id to the stack of declarations to translate
-        self.stack.insert(id);
+        self.stack.insert(StackItem::new(self.tcx, id));
         self.all_ids.insert(trans_id);
+        self.record_decl(id, trans_id);
+    }
+
+    /// Ask the hook, if there is one, whether we should veto the item we just
+    /// registered (see [crate::hooks::ExtractionHook::veto_item]).
+    fn apply_veto_hook(&mut self, id: DefId) {
+        if self.hook.is_none() {
+            return;
+        }
+        // Note that computing the name may register other items: we must
+        // not borrow the hook at this point.
+        let name = self.item_def_id_to_name(id);
+        if let Some(hook) = &mut self.hook && hook.veto_item(&name) {
+            self.vetoed_ids.insert(id);
+        }
     }
 
     /// Register the fact that `id` is a dependency of `src` (if `src` is not `None`).
@@ -516,11 +831,15 @@ impl<'tcx, 'ctx> TransCtx<'tcx, 'ctx> {
     ) -> TypeDeclId::Id {
         self.register_dep_source(src, id);
         match self.type_id_map.get(&id) {
-            Option::Some(id) => id,
+            Option::Some(trans_id) => {
+                self.record_decl(OrdRustId::Type(id), AnyTransId::Type(trans_id));
+                trans_id
+            }
             Option::None => {
                 let rid = OrdRustId::Type(id);
-                let trans_id = self.type_id_map.insert(id);
+                let trans_id = link_or_insert_id!(self, type_id_map, Type, id);
                 self.push_id(id, rid, AnyTransId::Type(trans_id));
+                self.apply_veto_hook(id);
                 trans_id
             }
         }
@@ -542,41 +861,50 @@ impl<'tcx, 'ctx> TransCtx<'tcx, 'ctx> {
     ) -> ast::FunDeclId::Id {
         self.register_dep_source(src, id);
         match self.fun_id_map.get(&id) {
-            Option::Some(tid) => tid,
+            Option::Some(tid) => {
+                // The kind of the id doesn't matter for the recording
+                self.record_decl(OrdRustId::Fun(id), AnyTransId::Fun(tid));
+                tid
+            }
             Option::None => {
                 let rid = if self.tcx.is_const_fn_raw(id) {
                     OrdRustId::ConstFun(id)
                 } else {
                     OrdRustId::Fun(id)
                 };
-                let trans_id = self.fun_id_map.insert(id);
+                let trans_id = link_or_insert_id!(self, fun_id_map, Fun, id);
                 self.push_id(id, rid, AnyTransId::Fun(trans_id));
+                self.apply_veto_hook(id);
                 trans_id
             }
         }
     }
 
     /// Returns an [Option] because we may ignore some builtin or auto traits
-    /// like [core::marker::Sized] or [core::marker::Sync].
+    /// like [core::marker::Sized] or [core::marker::Sync] (see
+    /// [crate::cli_options::CliOpts::keep_marker_traits]).
     pub(crate) fn register_trait_decl_id(
         &mut self,
         src: &Option<DepSource>,
         id: DefId,
     ) -> Option<ast::TraitDeclId::Id> {
         use crate::assumed;
-        if assumed::IGNORE_BUILTIN_MARKER_TRAITS {
-            let name = self.item_def_id_to_name(id);
-            if assumed::is_marker_trait(&name) {
-                return None;
-            }
+        let name = self.item_def_id_to_name(id);
+        if assumed::is_ignored_trait(&name)
+            || (!self.keep_marker_traits && assumed::is_marker_trait(&name))
+        {
+            return None;
         }
 
         self.register_dep_source(src, id);
         match self.trait_decl_id_map.get(&id) {
-            Option::Some(id) => Some(id),
+            Option::Some(trans_id) => {
+                self.record_decl(OrdRustId::TraitDecl(id), AnyTransId::TraitDecl(trans_id));
+                Some(trans_id)
+            }
             Option::None => {
                 let rid = OrdRustId::TraitDecl(id);
-                let trans_id = self.trait_decl_id_map.insert(id);
+                let trans_id = link_or_insert_id!(self, trait_decl_id_map, TraitDecl, id);
                 self.push_id(id, rid, AnyTransId::TraitDecl(trans_id));
                 Some(trans_id)
             }
@@ -599,10 +927,13 @@ impl<'tcx, 'ctx> TransCtx<'tcx, 'ctx> {
 
         self.register_dep_source(src, rust_id);
         match self.trait_impl_id_map.get(&rust_id) {
-            Option::Some(id) => Some(id),
+            Option::Some(id) => {
+                self.record_decl(OrdRustId::TraitImpl(rust_id), AnyTransId::TraitImpl(id));
+                Some(id)
+            }
             Option::None => {
                 let rid = OrdRustId::TraitImpl(rust_id);
-                let trans_id = self.trait_impl_id_map.insert(rust_id);
+                let trans_id = link_or_insert_id!(self, trait_impl_id_map, TraitImpl, rust_id);
                 self.trait_impl_id_to_def_id.insert(trans_id, rust_id);
                 self.push_id(rust_id, rid, AnyTransId::TraitImpl(trans_id));
                 Some(trans_id)
@@ -610,6 +941,29 @@ impl<'tcx, 'ctx> TransCtx<'tcx, 'ctx> {
         }
     }
 
+    /// Register an inherent impl block (see [ast::ImplBlock]).
+    ///
+    /// The impl blocks are not declarations (they only group methods): we
+    /// don't register them in [TransCtx::all_ids].
+    pub(crate) fn register_impl_block_id(
+        &mut self,
+        src: &Option<DepSource>,
+        rust_id: DefId,
+    ) -> ast::ImplBlockId::Id {
+        self.register_dep_source(src, rust_id);
+        let trans_id = match self.impl_block_id_map.get(&rust_id) {
+            Option::Some(id) => id,
+            Option::None => {
+                let trans_id = self.impl_block_id_map.insert(rust_id);
+                self.stack
+                    .insert(StackItem::new(self.tcx, OrdRustId::ImplBlock(rust_id)));
+                trans_id
+            }
+        };
+        self.record_impl_block(rust_id, trans_id);
+        trans_id
+    }
+
     pub(crate) fn translate_fun_decl_id(
         &mut self,
         src: &Option<DepSource>,
@@ -618,6 +972,14 @@ impl<'tcx, 'ctx> TransCtx<'tcx, 'ctx> {
         self.register_fun_decl_id(src, id)
     }
 
+    pub(crate) fn translate_impl_block_id(
+        &mut self,
+        src: &Option<DepSource>,
+        id: DefId,
+    ) -> ast::ImplBlockId::Id {
+        self.register_impl_block_id(src, id)
+    }
+
     /// Returns an [Option] because we may ignore some builtin or auto traits
     /// like [core::marker::Sized] or [core::marker::Sync].
     pub(crate) fn translate_trait_decl_id(
@@ -645,11 +1007,15 @@ impl<'tcx, 'ctx> TransCtx<'tcx, 'ctx> {
     ) -> GlobalDeclId::Id {
         self.register_dep_source(src, id);
         match self.global_id_map.get(&id) {
-            Option::Some(id) => id,
+            Option::Some(trans_id) => {
+                self.record_decl(OrdRustId::Global(id), AnyTransId::Global(trans_id));
+                trans_id
+            }
             Option::None => {
                 let rid = OrdRustId::Global(id);
-                let trans_id = self.global_id_map.insert(id);
+                let trans_id = link_or_insert_id!(self, global_id_map, Global, id);
                 self.push_id(id, rid, AnyTransId::Global(trans_id));
+                self.apply_veto_hook(id);
                 trans_id
             }
         }
@@ -663,6 +1029,32 @@ impl<'tcx, 'ctx> TransCtx<'tcx, 'ctx> {
         self.register_global_decl_id(src, id)
     }
 
+    /// Register a promoted constant, which we translate to an anonymous global
+    /// (see [Self::promoted_id_map]).
+    pub(crate) fn register_promoted_global_decl_id(
+        &mut self,
+        src: &Option<DepSource>,
+        id: DefId,
+        promoted: usize,
+    ) -> GlobalDeclId::Id {
+        self.register_dep_source(src, id);
+        match self.promoted_id_map.get(&(id, promoted)) {
+            Option::Some(trans_id) => {
+                let trans_id = *trans_id;
+                let rid = OrdRustId::Promoted(id, promoted);
+                self.record_decl(rid, AnyTransId::Global(trans_id));
+                trans_id
+            }
+            Option::None => {
+                let rid = OrdRustId::Promoted(id, promoted);
+                let trans_id = self.global_id_map.fresh_id();
+                let _ = self.promoted_id_map.insert((id, promoted), trans_id);
+                self.push_id(id, rid, AnyTransId::Global(trans_id));
+                trans_id
+            }
+        }
+    }
+
     pub(crate) fn with_def_id<F, T>(&mut self, def_id: DefId, f: F) -> T
     where
         F: FnOnce(&mut Self) -> T,
@@ -692,6 +1084,7 @@ impl<'tcx, 'ctx, 'ctx1> BodyTransCtx<'tcx, 'ctx, 'ctx1> {
     /// Create a new `ExecContext`.
     pub(crate) fn new(def_id: DefId, t_ctx: &'ctx mut TransCtx<'tcx, 'ctx1>) -> Self {
         let hax_state = t_ctx.make_hax_state_with_id(def_id);
+        let mir_level = t_ctx.mir_level;
         let mut trait_clauses_counter = TraitClauseId::Generator::new();
         let trait_instance_id_gen = Box::new(move || {
             let id = trait_clauses_counter.fresh_id();
@@ -720,6 +1113,11 @@ impl<'tcx, 'ctx, 'ctx1> BodyTransCtx<'tcx, 'ctx, 'ctx1> {
             blocks: im::OrdMap::new(),
             blocks_map: ast::BlockId::MapGenerator::new(),
             blocks_stack: VecDeque::new(),
+            unsize_vtables: HashMap::new(),
+            promoted_constants: HashMap::new(),
+            backtraces: BacktraceId::Vector::new(),
+            backtraces_map: HashMap::new(),
+            mir_level,
         }
     }
 
@@ -731,14 +1129,53 @@ impl<'tcx, 'ctx, 'ctx1> BodyTransCtx<'tcx, 'ctx, 'ctx1> {
         self.t_ctx.span_err(span, msg)
     }
 
+    /// Same as [TransCtx::fmt_for_error], but we also resolve the local
+    /// variables and the generic parameters of the current declaration.
+    pub(crate) fn fmt_for_error(&self, x: &dyn FmtWithCtx) -> String {
+        x.fmt_with_fmt_ctx(&self.into_fmt())
+    }
+
     pub(crate) fn translate_meta_from_rid(&mut self, def_id: DefId) -> Meta {
         self.t_ctx.translate_meta_from_rid(def_id)
     }
 
+    pub(crate) fn translate_attr_info_from_rid(&self, def_id: DefId) -> AttrInfo {
+        self.t_ctx.translate_attr_info_from_rid(def_id)
+    }
+
     pub(crate) fn translate_meta_from_rspan(&mut self, rspan: hax::Span) -> Meta {
         self.t_ctx.translate_meta_from_rspan(rspan)
     }
 
+    /// Compute the meta information of a statement or a terminator, and
+    /// register its macro backtrace (see [Meta::backtrace]).
+    pub(crate) fn translate_meta_from_source_info(
+        &mut self,
+        source_scopes: &hax::IndexVec<hax::SourceScope, hax::SourceScopeData>,
+        source_info: &hax::SourceInfo,
+    ) -> Meta {
+        let mut meta = self
+            .t_ctx
+            .translate_meta_from_source_info(source_scopes, source_info);
+        let backtrace = self.t_ctx.translate_backtrace(source_scopes, source_info);
+        if !backtrace.is_empty() {
+            meta.backtrace = Some(self.register_backtrace(backtrace));
+        }
+        meta
+    }
+
+    fn register_backtrace(&mut self, backtrace: Vec<meta::Span>) -> BacktraceId::Id {
+        match self.backtraces_map.get(&backtrace) {
+            Some(id) => *id,
+            None => {
+                let id = BacktraceId::Id::new(self.backtraces.len());
+                self.backtraces_map.insert(backtrace.clone(), id);
+                self.backtraces.push_back(backtrace);
+                id
+            }
+        }
+    }
+
     pub(crate) fn get_local(&self, local: &hax::Local) -> Option<VarId::Id> {
         use rustc_index::Idx;
         self.vars_map.get(&local.index())
@@ -780,6 +1217,17 @@ impl<'tcx, 'ctx, 'ctx1> BodyTransCtx<'tcx, 'ctx, 'ctx1> {
         self.t_ctx.translate_global_decl_id(&src, id)
     }
 
+    pub(crate) fn translate_promoted_global_decl_id(
+        &mut self,
+        span: rustc_span::Span,
+        id: DefId,
+        promoted: usize,
+    ) -> ast::GlobalDeclId::Id {
+        let src = self.make_dep_source(span);
+        self.t_ctx
+            .register_promoted_global_decl_id(&src, id, promoted)
+    }
+
     /// Returns an [Option] because we may ignore some builtin or auto traits
     /// like [core::marker::Sized] or [core::marker::Sync].
     pub(crate) fn translate_trait_decl_id(
@@ -896,14 +1344,27 @@ impl<'tcx, 'ctx, 'ctx1> BodyTransCtx<'tcx, 'ctx, 'ctx1> {
         use crate::id_vector::ToUsize;
         let var_id = self.type_vars_map.insert(rindex);
         assert!(var_id.to_usize() == self.type_vars.len());
+        // The variable is `?Sized` until we find a [core::marker::Sized] clause
+        // about it (see [Self::register_sized_type_var])
         let var = TypeVar {
             index: var_id,
             name,
+            sized: false,
         };
         self.type_vars.insert(var_id, var);
         var_id
     }
 
+    /// Record that a type variable is [core::marker::Sized], if the type is a
+    /// variable.
+    pub(crate) fn register_sized_type_var(&mut self, ty: &hax::Ty) {
+        if let hax::Ty::Param(param) = ty
+            && let Some(var_id) = self.type_vars_map.get(&param.index)
+        {
+            self.type_vars.get_mut(var_id).unwrap().sized = true;
+        }
+    }
+
     pub(crate) fn push_var(&mut self, rid: usize, ty: Ty, name: Option<String>) {
         use crate::id_vector::ToUsize;
         let var_id = self.vars_map.insert(rid);
@@ -916,7 +1377,7 @@ impl<'tcx, 'ctx, 'ctx1> BodyTransCtx<'tcx, 'ctx, 'ctx1> {
         self.vars.insert(var_id, var);
     }
 
-    pub(crate) fn push_const_generic_var(&mut self, rid: u32, ty: LiteralTy, name: String) {
+    pub(crate) fn push_const_generic_var(&mut self, rid: u32, ty: Ty, name: String) {
         use crate::id_vector::ToUsize;
         let var_id = self.const_generic_vars_map.insert(rid);
         assert!(var_id.to_usize() == self.const_generic_vars.len());
@@ -1046,6 +1507,7 @@ impl<'tcx, 'ctx, 'a> IntoFormatter for &'a TransCtx<'tcx, 'ctx> {
             type_vars: None,
             const_generic_vars: None,
             locals: None,
+            names: NameFmtOptions::new(&self.name_opts, &self.crate_info.crate_name),
         }
     }
 }
@@ -1064,6 +1526,7 @@ impl<'tcx, 'ctx, 'ctx1, 'a> IntoFormatter for &'a BodyTransCtx<'tcx, 'ctx, 'ctx1
             type_vars: Some(&self.type_vars),
             const_generic_vars: Some(&self.const_generic_vars),
             locals: Some(&self.vars),
+            names: NameFmtOptions::new(&self.t_ctx.name_opts, &self.t_ctx.crate_info.crate_name),
         }
     }
 }
@@ -4,10 +4,17 @@
 //! independently.
 
 use crate::assumed;
+use crate::cli_options::{FmtMachinery, Intrinsics};
 use crate::common::*;
 use crate::expressions::*;
 use crate::formatter::{Formatter, IntoFormatter};
-use crate::get_mir::{boxes_are_desugared, get_mir_for_def_id_and_level};
+use crate::get_mir::{
+    boxes_are_desugared, get_external_mir, get_mir_for_def_id_and_level,
+    get_promoted_mir_for_def_id_and_level, MirLevel,
+};
+use crate::hidden_effects;
+use crate::meta::AttrInfo;
+use crate::names::{Disambiguator, PathElem};
 use crate::translate_ctx::*;
 use crate::translate_types;
 use crate::types::*;
@@ -15,9 +22,10 @@ use crate::ullbc_ast::*;
 use crate::values::*;
 use hax_frontend_exporter as hax;
 use hax_frontend_exporter::SInto;
-use rustc_hir::def_id::{DefId, LocalDefId};
+use rustc_hir::def_id::DefId;
 use rustc_middle::mir::START_BLOCK;
 use rustc_middle::ty;
+use std::collections::HashMap;
 use translate_types::translate_bound_region_kind_name;
 
 pub(crate) struct SubstFunId {
@@ -25,9 +33,18 @@ pub(crate) struct SubstFunId {
     pub args: Option<Vec<Operand>>,
 }
 
+/// A call to a method of a trait object (see [FnOperand::Virtual]).
+pub(crate) struct SubstVirtualFun {
+    pub trait_ref: TraitDeclRef,
+    pub method_name: TraitItemName,
+    pub generics: GenericArgs,
+    pub args: Option<Vec<Operand>>,
+}
+
 pub(crate) enum SubstFunIdOrPanic {
     Panic,
     Fun(SubstFunId),
+    Virtual(SubstVirtualFun),
 }
 
 fn translate_variant_id(id: hax::VariantIdx) -> VariantId::Id {
@@ -86,7 +103,7 @@ pub(crate) fn check_impl_item(impl_item: &rustc_hir::Impl<'_>) {
 impl<'tcx, 'ctx> TransCtx<'tcx, 'ctx> {
     fn translate_binaryop_kind(
         &mut self,
-        span: rustc_span::Span,
+        _span: rustc_span::Span,
         binop: hax::BinOp,
     ) -> Result<BinOp, Error> {
         match binop {
@@ -106,9 +123,7 @@ impl<'tcx, 'ctx> TransCtx<'tcx, 'ctx> {
             hax::BinOp::Mul => Ok(BinOp::Mul),
             hax::BinOp::Shl => Ok(BinOp::Shl),
             hax::BinOp::Shr => Ok(BinOp::Shr),
-            hax::BinOp::Offset => {
-                error_or_panic!(self, span, "Unsupported binary operation: offset")
-            }
+            hax::BinOp::Offset => Ok(BinOp::Offset),
         }
     }
 
@@ -148,7 +163,11 @@ impl<'tcx, 'ctx> TransCtx<'tcx, 'ctx> {
                     //   fn bar() { ... } // trait_item_def_id: Some(Foo_bar)
                     // }
                     match assoc.trait_item_def_id {
-                        None => FunKind::Regular,
+                        None => {
+                            // This is a method of an inherent impl block
+                            let impl_id = self.translate_impl_block_id(src, tcx.parent(rust_id));
+                            FunKind::InherentMethod(impl_id)
+                        }
                         Some(trait_method_id) => {
                             let trait_id = tcx.trait_of_item(trait_method_id).unwrap();
                             let trait_id = self.translate_trait_decl_id(src, trait_id);
@@ -172,9 +191,10 @@ impl<'tcx, 'ctx> TransCtx<'tcx, 'ctx> {
                             let provided = match self.get_fun_kind(src, trait_method_id) {
                                 FunKind::TraitMethodDecl(..) => false,
                                 FunKind::TraitMethodProvided(..) => true,
-                                FunKind::Regular | FunKind::TraitMethodImpl { .. } => {
-                                    unreachable!()
-                                }
+                                FunKind::Regular
+                                | FunKind::InherentMethod(_)
+                                | FunKind::TraitMethodImpl { .. }
+                                | FunKind::Foreign => unreachable!(),
                             };
 
                             FunKind::TraitMethodImpl {
@@ -218,6 +238,8 @@ impl<'tcx, 'ctx> TransCtx<'tcx, 'ctx> {
                     }
                 }
             }
+        } else if tcx.is_foreign_item(rust_id) {
+            FunKind::Foreign
         } else {
             FunKind::Regular
         }
@@ -246,6 +268,37 @@ impl<'tcx, 'ctx, 'ctx1> BodyTransCtx<'tcx, 'ctx, 'ctx1> {
         Ok(())
     }
 
+    /// Translate the debug information of the variables of the source code
+    /// (see [VarDebugInfo]). The local variables should already have been
+    /// translated. The hax AST doesn't give us this information: we retrieve
+    /// it on the rustc MIR.
+    fn translate_var_debug_info(
+        &mut self,
+        body: &rustc_middle::mir::Body<'tcx>,
+    ) -> Vec<VarDebugInfo> {
+        use rustc_middle::mir::VarDebugInfoContents;
+        let mut infos = Vec::new();
+        for info in &body.var_debug_info {
+            let var = match &info.value {
+                VarDebugInfoContents::Place(place) if place.projection.is_empty() => {
+                    self.vars_map.get(&place.local.as_usize())
+                }
+                _ => None,
+            };
+            let span = info.source_info.span.sinto(&self.hax_state);
+            let scope = body.source_scopes[info.source_info.scope].span;
+            let scope = scope.sinto(&self.hax_state);
+            infos.push(VarDebugInfo {
+                name: info.name.to_string(),
+                span: self.t_ctx.translate_span(span),
+                scope: self.t_ctx.translate_span(scope),
+                var,
+                argument_index: info.argument_index.map(|i| i as usize),
+            });
+        }
+        infos
+    }
+
     /// Translate an expression's body (either a function or a global).
     ///
     /// The local variables should already have been translated and inserted in
@@ -366,7 +419,7 @@ impl<'tcx, 'ctx, 'ctx1> BodyTransCtx<'tcx, 'ctx, 'ctx1> {
                             }
                             Ty::Adt(TypeId::Assumed(AssumedTy::Box), generics) => {
                                 // This case only happens in some MIR levels
-                                assert!(!boxes_are_desugared(self.t_ctx.mir_level));
+                                assert!(!boxes_are_desugared(self.mir_level));
                                 assert!(generics.regions.is_empty());
                                 assert!(generics.types.len() == 1);
                                 assert!(generics.const_generics.is_empty());
@@ -413,7 +466,7 @@ impl<'tcx, 'ctx, 'ctx1> BodyTransCtx<'tcx, 'ctx, 'ctx1> {
                                         ProjectionElem::Field(proj_kind, field_id)
                                     }
                                     Ty::Adt(TypeId::Assumed(AssumedTy::Box), generics) => {
-                                        assert!(!boxes_are_desugared(self.t_ctx.mir_level));
+                                        assert!(!boxes_are_desugared(self.mir_level));
 
                                         // Some more sanity checks
                                         assert!(generics.regions.is_empty());
@@ -479,13 +532,41 @@ impl<'tcx, 'ctx, 'ctx1> BodyTransCtx<'tcx, 'ctx, 'ctx1> {
                 Ok((Operand::Move(p), ty))
             }
             hax::Operand::Constant(constant) => {
-                let constant = self.translate_constant_to_constant_expr(span, constant)?;
+                let constant = match self.promoted_constants.get(&constant.span.rust_span) {
+                    Some(promoted) => self.translate_promoted_constant(span, constant, promoted)?,
+                    None => self.translate_constant_to_constant_expr(span, constant)?,
+                };
                 let ty = constant.ty.clone();
                 Ok((Operand::Const(constant), ty))
             }
         }
     }
 
+    /// Translate a promoted constant to a reference to the anonymous global we
+    /// generate for it (see [Self::resolve_promoted_constants]).
+    fn translate_promoted_constant(
+        &mut self,
+        span: rustc_span::Span,
+        constant: &hax::Constant,
+        promoted: &[(DefId, usize)],
+    ) -> Result<ConstantExpr, Error> {
+        // Several promoted constants may have the same span (this happens
+        // with the macros): we can't tell them apart
+        let [(id, index), others @ ..] = promoted else {
+            unreachable!()
+        };
+        if others.iter().any(|p| p != &(*id, *index)) {
+            error_or_panic!(self, span, "Could not identify a promoted constant")
+        }
+        let global_id = self.translate_promoted_global_decl_id(span, *id, *index);
+        let erase_regions = true;
+        let ty = self.translate_ty(span, erase_regions, &constant.literal.constant_kind.ty)?;
+        Ok(ConstantExpr {
+            value: RawConstantExpr::Global(global_id),
+            ty,
+        })
+    }
+
     /// Translate an operand
     fn translate_operand(
         &mut self,
@@ -529,6 +610,39 @@ impl<'tcx, 'ctx, 'ctx1> BodyTransCtx<'tcx, 'ctx, 'ctx1> {
     }
 
     /// Translate an rvalue
+    /// Retrieve the vtable of an unsizing coercion, if the target is a
+    /// (pointer to a) trait object. See [Self::resolve_unsize_vtables].
+    fn translate_unsize_vtable(
+        &mut self,
+        span: rustc_span::Span,
+        erase_regions: bool,
+        tgt_ty: &Ty,
+    ) -> Result<Option<TraitRef>, Error> {
+        let pointee = match tgt_ty {
+            Ty::Ref(_, ty, _) | Ty::RawPtr(ty, _) => Some(ty.as_ref()),
+            Ty::Adt(TypeId::Assumed(AssumedTy::Box), generics) => generics.types.get(0),
+            _ => None,
+        };
+        let trait_id = match pointee {
+            Some(Ty::DynTrait(trait_ref, _)) => trait_ref.trait_id,
+            _ => return Ok(None),
+        };
+        let impl_sources = self.unsize_vtables.get(&span).cloned().unwrap_or_default();
+        for impl_source in &impl_sources {
+            let trait_ref = self.translate_trait_impl_source(span, erase_regions, impl_source)?;
+            if let Some(trait_ref) = trait_ref
+                && trait_ref.trait_decl_ref.trait_id == trait_id
+            {
+                return Ok(Some(trait_ref));
+            }
+        }
+        error_or_panic!(
+            self,
+            span,
+            "Could not resolve the vtable of an unsizing coercion"
+        )
+    }
+
     fn translate_rvalue(
         &mut self,
         span: rustc_span::Span,
@@ -547,7 +661,8 @@ impl<'tcx, 'ctx, 'ctx1> BodyTransCtx<'tcx, 'ctx, 'ctx1> {
             hax::Rvalue::Repeat(operand, cnst) => {
                 let c = self.translate_constant_expr_to_const_generic(span, cnst)?;
                 let (operand, t) = self.translate_operand_with_type(span, operand)?;
-                // Remark: we could desugar this into a function call later.
+                // Remark: we desugar this into a function call in the LLBC
+                // (see [crate::ops_to_function_calls]).
                 Ok(Rvalue::Repeat(operand, t, c))
             }
             hax::Rvalue::Ref(_region, borrow_kind, place) => {
@@ -558,30 +673,25 @@ impl<'tcx, 'ctx, 'ctx1> BodyTransCtx<'tcx, 'ctx, 'ctx1> {
             hax::Rvalue::ThreadLocalRef(_) => {
                 error_or_panic!(self, span, "Unsupported rvalue: thread local ref");
             }
-            hax::Rvalue::AddressOf(_, _) => {
-                error_or_panic!(self, span, "Unsupported rvalue: address of");
+            hax::Rvalue::AddressOf(mutbl, place) => {
+                let place = self.translate_place(span, place)?;
+                let kind = if *mutbl {
+                    RefKind::Mut
+                } else {
+                    RefKind::Shared
+                };
+                Ok(Rvalue::AddressOf(place, kind))
             }
             hax::Rvalue::Len(place) => {
                 let (place, ty) = self.translate_place_with_type(span, place)?;
-                let cg = match &ty {
-                    Ty::Adt(
-                        TypeId::Assumed(aty @ (AssumedTy::Array | AssumedTy::Slice)),
-                        generics,
-                    ) => {
-                        if aty.is_array() {
-                            Option::Some(generics.const_generics[0].clone())
-                        } else {
-                            Option::None
-                        }
-                    }
-                    _ => unreachable!(),
-                };
+                assert!(ty.as_array_or_slice().is_some());
+                let cg = ty.array_len().cloned();
                 Ok(Rvalue::Len(place, ty, cg))
             }
             hax::Rvalue::Cast(cast_kind, operand, tgt_ty) => {
                 trace!("Rvalue::Cast: {:?}", rvalue);
-                // Put aside the pointer casts (which we don't support), I think
-                // casts should only be from integers/booleans to integer/booleans.
+                // Note that we don't support the casts involving floats, nor
+                // the `dyn*` casts.
 
                 // Translate the target type
                 let tgt_ty = self.translate_ty(span, erase_regions, tgt_ty)?;
@@ -604,74 +714,78 @@ impl<'tcx, 'ctx, 'ctx1> BodyTransCtx<'tcx, 'ctx, 'ctx1> {
                         hax::CastKind::Pointer(hax::PointerCast::Unsize),
                         Ty::Ref(_, t1, kind1),
                         Ty::Ref(_, t2, kind2),
-                    ) => {
+                    ) if t1.is_array() && t2.is_slice() => {
                         // In MIR terminology, we go from &[T; l] to &[T] which means we
                         // effectively "unsize" the type, as `l` no longer appears in the
                         // destination type. At runtime, the converse happens: the length
                         // materializes into the fat pointer.
-                        match (&**t1, &**t2) {
-                            (
-                                Ty::Adt(TypeId::Assumed(AssumedTy::Array), generics),
-                                Ty::Adt(TypeId::Assumed(AssumedTy::Slice), generics1),
-                            ) => {
-                                assert!(
-                                    generics.types.len() == 1 && generics.const_generics.len() == 1
-                                );
-                                assert!(generics.types[0] == generics1.types[0]);
-                                assert!(kind1 == kind2);
-                                Ok(Rvalue::UnaryOp(
-                                    UnOp::ArrayToSlice(
-                                        *kind1,
-                                        generics.types[0].clone(),
-                                        generics.const_generics[0].clone(),
-                                    ),
-                                    op,
-                                ))
-                            }
-                            _ => {
-                                error_or_panic!(
-                                    self,
-                                    span,
-                                    format!(
-                                        "Unsupported cast:\n\n- rvalue: {:?}\n\n- src={:?}\n\n- dst={:?}",
-                                        rvalue, src_ty, tgt_ty
-                                    )
-                                )
-                            }
-                        }
+                        let (elem_ty, len) = t1.as_array().unwrap();
+                        assert!(elem_ty == t2.as_slice().unwrap());
+                        assert!(kind1 == kind2);
+                        Ok(Rvalue::UnaryOp(
+                            UnOp::ArrayToSlice(*kind1, elem_ty.clone(), len.clone()),
+                            op,
+                        ))
                     }
-                    (
-                        hax::CastKind::Pointer(hax::PointerCast::ClosureFnPointer(unsafety)),
-                        src_ty @ Ty::Arrow(..),
-                        tgt_ty @ Ty::Arrow(..),
-                    ) => {
-                        assert!(*unsafety == hax::Unsafety::Normal);
-                        let src_ty = src_ty.clone();
-                        let tgt_ty = tgt_ty.clone();
+                    (hax::CastKind::Pointer(hax::PointerCast::Unsize), _, _) => {
+                        let vtable = self.translate_unsize_vtable(span, erase_regions, &tgt_ty)?;
                         Ok(Rvalue::UnaryOp(
-                            UnOp::Cast(CastKind::FnPtr(src_ty, tgt_ty)),
+                            UnOp::Cast(CastKind::Unsize(src_ty.clone(), tgt_ty.clone(), vtable)),
                             op,
                         ))
                     }
                     (
-                        hax::CastKind::Pointer(hax::PointerCast::ReifyFnPointer),
-                        src_ty @ Ty::Arrow(..),
-                        tgt_ty @ Ty::Arrow(..),
+                        hax::CastKind::Pointer(hax::PointerCast::ClosureFnPointer(unsafety)),
+                        Ty::Arrow(..),
+                        Ty::Arrow(..),
                     ) => {
-                        let src_ty = src_ty.clone();
-                        let tgt_ty = tgt_ty.clone();
+                        assert!(*unsafety == hax::Unsafety::Normal);
                         Ok(Rvalue::UnaryOp(
-                            UnOp::Cast(CastKind::FnPtr(src_ty, tgt_ty)),
+                            UnOp::Cast(CastKind::ClosureFnPointer(src_ty.clone(), tgt_ty.clone())),
                             op,
                         ))
                     }
+                    (
+                        hax::CastKind::Pointer(hax::PointerCast::ReifyFnPointer),
+                        Ty::Arrow(..),
+                        Ty::Arrow(..),
+                    ) => Ok(Rvalue::UnaryOp(
+                        UnOp::Cast(CastKind::ReifyFnPointer(src_ty.clone(), tgt_ty.clone())),
+                        op,
+                    )),
+                    (
+                        hax::CastKind::PtrToPtr
+                        | hax::CastKind::FnPtrToPtr
+                        | hax::CastKind::Pointer(
+                            hax::PointerCast::MutToConstPointer | hax::PointerCast::ArrayToPointer,
+                        ),
+                        _,
+                        _,
+                    ) => Ok(Rvalue::UnaryOp(
+                        UnOp::Cast(CastKind::PtrToPtr(src_ty.clone(), tgt_ty.clone())),
+                        op,
+                    )),
+                    (hax::CastKind::PointerExposeAddress, _, _) => Ok(Rvalue::UnaryOp(
+                        UnOp::Cast(CastKind::PtrToInt(src_ty.clone(), tgt_ty.clone())),
+                        op,
+                    )),
+                    (hax::CastKind::PointerFromExposedAddress, _, _) => Ok(Rvalue::UnaryOp(
+                        UnOp::Cast(CastKind::IntToPtr(src_ty.clone(), tgt_ty.clone())),
+                        op,
+                    )),
+                    (hax::CastKind::Transmute, _, _) => Ok(Rvalue::UnaryOp(
+                        UnOp::Cast(CastKind::Transmute(src_ty.clone(), tgt_ty.clone())),
+                        op,
+                    )),
                     _ => {
                         error_or_panic!(
                             self,
                             span,
                             format!(
-                                "Unsupported cast:\n\n- rvalue: {:?}\n\n- src={:?}\n\n- dst={:?}",
-                                rvalue, src_ty, tgt_ty
+                                "Unsupported cast:\n\n- rvalue: {:?}\n\n- src={}\n\n- dst={}",
+                                rvalue,
+                                self.fmt_for_error(&src_ty),
+                                self.fmt_for_error(&tgt_ty)
                             )
                         )
                     }
@@ -687,11 +801,17 @@ impl<'tcx, 'ctx, 'ctx1> BodyTransCtx<'tcx, 'ctx, 'ctx1> {
                     self.translate_operand(span, right)?,
                 ))
             }
-            hax::Rvalue::NullaryOp(nullop, _ty) => {
+            hax::Rvalue::NullaryOp(nullop, ty) => {
                 trace!("NullOp: {:?}", nullop);
-                // Nullary operations are very low-level and shouldn't be necessary
-                // unless one needs to write unsafe code.
-                error_or_panic!(self, span, "Nullary operations are not supported");
+                let nullop = match nullop {
+                    hax::NullOp::SizeOf => NullOp::SizeOf,
+                    hax::NullOp::AlignOf => NullOp::AlignOf,
+                    hax::NullOp::OffsetOf(..) => {
+                        error_or_panic!(self, span, "Unsupported nullary operation: offset_of")
+                    }
+                };
+                let ty = self.translate_ty(span, erase_regions, ty)?;
+                Ok(Rvalue::Nullary(nullop, ty))
             }
             hax::Rvalue::UnaryOp(unop, operand) => Ok(Rvalue::UnaryOp(
                 translate_unaryop_kind(*unop),
@@ -707,7 +827,7 @@ impl<'tcx, 'ctx, 'ctx1> BodyTransCtx<'tcx, 'ctx, 'ctx1> {
                         span,
                         format!(
                             "Unexpected scrutinee type for ReadDiscriminant: {}",
-                            ty.fmt_with_ctx(&self.into_fmt())
+                            self.fmt_for_error(&ty)
                         )
                     )
                 }
@@ -766,7 +886,6 @@ impl<'tcx, 'ctx, 'ctx1> BodyTransCtx<'tcx, 'ctx, 'ctx1> {
                         // is a field index, while it makes more sense for it to be
                         // the 5th, and I don't know how I should use it anyway).
                         error_assert!(self, span, user_annotation.is_none());
-                        error_assert!(self, span, field_index.is_none());
 
                         // Translate the substitution
                         let generics = self.translate_substs_and_trait_refs(
@@ -782,18 +901,34 @@ impl<'tcx, 'ctx, 'ctx1> BodyTransCtx<'tcx, 'ctx, 'ctx1> {
                         matches!(&type_id, TypeId::Adt(_));
 
                         use hax::AdtKind;
-                        let variant_id = match kind {
-                            AdtKind::Struct => Option::None,
+                        let akind = match kind {
+                            AdtKind::Struct => {
+                                error_assert!(self, span, field_index.is_none());
+                                AggregateKind::Adt(type_id, None, generics)
+                            }
                             AdtKind::Enum => {
+                                error_assert!(self, span, field_index.is_none());
                                 let variant_id = translate_variant_id(*variant_idx);
-                                Some(variant_id)
+                                AggregateKind::Adt(type_id, Some(variant_id), generics)
                             }
                             AdtKind::Union => {
-                                error_or_panic!(self, span, "Union values are not supported");
+                                // For unions, the field index gives the active field
+                                let Some(field_index) = field_index else {
+                                    error_or_panic!(
+                                        self,
+                                        span,
+                                        "Missing active field in union aggregate"
+                                    );
+                                };
+                                error_assert!(self, span, operands_t.len() == 1);
+                                let TypeId::Adt(adt_id) = type_id else {
+                                    error_or_panic!(self, span, "Unexpected union type id");
+                                };
+                                let field_id = translate_field_id(*field_index);
+                                AggregateKind::Union(adt_id, field_id, generics)
                             }
                         };
 
-                        let akind = AggregateKind::Adt(type_id, variant_id, generics);
                         Ok(Rvalue::Aggregate(akind, operands_t))
                     }
                     hax::AggregateKind::Closure(def_id, substs, trait_refs, sig) => {
@@ -813,13 +948,30 @@ impl<'tcx, 'ctx, 'ctx1> BodyTransCtx<'tcx, 'ctx, 'ctx1> {
 
                         Ok(Rvalue::Aggregate(akind, operands_t))
                     }
-                    hax::AggregateKind::Generator(_def_id, _subst, _movability) => {
-                        error_or_panic!(self, span, "Generators are not supported");
+                    hax::AggregateKind::Generator(def_id, substs, _movability) => {
+                        trace!("Generator:\n\n- def_id: {:?}", def_id);
+
+                        // Translate the substitution (generators have no trait refs)
+                        let (regions, types, const_generics) =
+                            self.translate_substs(span, erase_regions, None, substs)?;
+                        let generics = GenericArgs::new(regions, types, const_generics, Vec::new());
+
+                        let Some(rust_id) = def_id.rust_def_id else {
+                            error_or_panic!(self, span, "Missing id in generator aggregate");
+                        };
+                        let def_id = self.translate_fun_decl_id(span, rust_id);
+                        let akind = AggregateKind::Generator(def_id, generics);
+
+                        Ok(Rvalue::Aggregate(akind, operands_t))
                     }
                 }
             }
-            hax::Rvalue::ShallowInitBox(_, _) => {
-                error_or_panic!(self, span, "Unsupported rvalue: shallow init box");
+            hax::Rvalue::ShallowInitBox(op, ty) => {
+                // We reconstruct a call to `Box::new` in a micro-pass (see
+                // [crate::reconstruct_box_new])
+                let op = self.translate_operand(span, op)?;
+                let ty = self.translate_ty(span, erase_regions, ty)?;
+                Ok(Rvalue::ShallowInitBox(op, ty))
             }
         }
     }
@@ -850,18 +1002,70 @@ impl<'tcx, 'ctx, 'ctx1> BodyTransCtx<'tcx, 'ctx, 'ctx1> {
         let is_local = rust_id.is_local();
 
         // Check if this function is a actually `panic`
-        if name.equals_ref_name(&assumed::PANIC_NAME)
-            || name.equals_ref_name(&assumed::BEGIN_PANIC_NAME)
-            || name.equals_ref_name(&assumed::ASSERT_FAILED_NAME)
-        {
+        if self.t_ctx.assumed_funs.is_panic(&name) {
             return Ok(SubstFunIdOrPanic::Panic);
         }
 
+        // Check if this function belongs to the formatting machinery, that we
+        // may not want to extract: if so, we replace it with an opaque call
+        // (we don't translate the generics, which may refer to the types and
+        // traits of the formatting machinery).
+        if self.t_ctx.fmt_machinery != FmtMachinery::Extract
+            && !is_local
+            && assumed::is_fmt_machinery(&name)
+        {
+            let args = args
+                .map(|args| self.translate_arguments(span, None, args))
+                .transpose()?;
+            let func = FnPtr {
+                func: FunIdOrTraitMethodRef::mk_assumed(AssumedFunId::Fmt),
+                generics: GenericArgs::empty(),
+                trait_and_method_generic_args: None,
+            };
+            return Ok(SubstFunIdOrPanic::Fun(SubstFunId { func, args }));
+        }
+
+        // Check if this function is one of the intrinsics we model (see
+        // [crate::cli_options::CliOpts::intrinsics])
+        if let Some(aid) = assumed::get_intrinsic_id(&name) {
+            if self.t_ctx.intrinsics == Intrinsics::Opaque {
+                // We fail on purpose: the body of the function which calls the
+                // intrinsic is not extracted (this is not an error)
+                let msg = format!(
+                    "Not extracting the body of {:?} because it calls the intrinsic {:?}",
+                    self.def_id, rust_id
+                );
+                self.t_ctx.session.span_warn(span, msg.clone());
+                return Err(Error {
+                    span,
+                    msg,
+                    skipped: true,
+                });
+            }
+
+            let generics = self.translate_substs_and_trait_refs(
+                span,
+                erase_regions,
+                None,
+                substs,
+                trait_refs,
+            )?;
+            let args = args
+                .map(|args| self.translate_arguments(span, None, args))
+                .transpose()?;
+            let func = FnPtr {
+                func: FunIdOrTraitMethodRef::mk_assumed(aid),
+                generics,
+                trait_and_method_generic_args: None,
+            };
+            return Ok(SubstFunIdOrPanic::Fun(SubstFunId { func, args }));
+        }
+
         // There is something annoying: when going to MIR, the rust compiler
         // sometimes introduces very low-level functions, which we need to
         // catch early - in particular, before we start translating types and
         // arguments, because we won't be able to translate some of them.
-        if name.equals_ref_name(&assumed::BOX_FREE_NAME) {
+        if self.t_ctx.assumed_funs.is_box_free(&name) {
             assert!(!is_local);
 
             // This deallocates a box.
@@ -904,12 +1108,9 @@ impl<'tcx, 'ctx, 'ctx1> BodyTransCtx<'tcx, 'ctx, 'ctx1> {
             let (used_type_args, used_args) = if is_local {
                 (Option::None, Option::None)
             } else {
-                match assumed::function_to_info(&name) {
+                match self.t_ctx.assumed_funs.function_to_info(&name) {
                     Option::None => (Option::None, Option::None),
-                    Option::Some(used) => (
-                        Option::Some(used.used_type_params),
-                        Option::Some(used.used_args),
-                    ),
+                    Option::Some(used) => (used.used_type_params, used.used_args),
                 }
             };
 
@@ -927,12 +1128,28 @@ impl<'tcx, 'ctx, 'ctx1> BodyTransCtx<'tcx, 'ctx, 'ctx1> {
                 .map(|args| self.translate_arguments(span, used_args, args))
                 .transpose()?;
 
+            // Some trait methods are modelled by assumed functions when they
+            // are called on specific types (ex.: indexing a vector)
+            if !is_local
+                && let Some((aid, generics)) = assumed::trait_method_to_assumed(&name, &generics)
+            {
+                let func = FnPtr {
+                    func: FunIdOrTraitMethodRef::mk_assumed(aid),
+                    generics,
+                    trait_and_method_generic_args: None,
+                };
+                return Ok(SubstFunIdOrPanic::Fun(SubstFunId { func, args }));
+            }
+
             // Check if the function is considered primitive: primitive
             // functions benefit from special treatment.
             let is_prim = if is_local {
                 false
             } else {
-                assumed::get_fun_id_from_name(&name).is_some()
+                self.t_ctx
+                    .assumed_funs
+                    .get_fun_id_from_name(&name)
+                    .is_some()
             };
 
             // Trait information
@@ -963,6 +1180,27 @@ impl<'tcx, 'ctx, 'ctx1> BodyTransCtx<'tcx, 'ctx, 'ctx1> {
                         Ok(SubstFunIdOrPanic::Fun(sfid))
                     }
                     Option::Some(trait_info) => {
+                        if let hax::ImplSourceKind::Object(_) = &trait_info.impl_source.kind {
+                            // Method of a trait object: we can't resolve the method
+                            // statically, it will be looked up in the vtable of the
+                            // receiver at runtime.
+                            let trait_ref = self.translate_trait_decl_ref(
+                                span,
+                                erase_regions,
+                                &trait_info.impl_source.trait_ref,
+                            )?;
+                            // Marker traits don't have methods
+                            let trait_ref = trait_ref.unwrap();
+                            let method_name = self.t_ctx.translate_trait_item_name(rust_id);
+                            let sfid = SubstVirtualFun {
+                                trait_ref,
+                                method_name,
+                                generics,
+                                args,
+                            };
+                            return Ok(SubstFunIdOrPanic::Virtual(sfid));
+                        }
+
                         // Trait method
                         let rust_id = def_id.rust_def_id.unwrap();
                         let impl_source = self.translate_trait_impl_source(
@@ -1045,7 +1283,7 @@ impl<'tcx, 'ctx, 'ctx1> BodyTransCtx<'tcx, 'ctx, 'ctx1> {
                 // (the type parameter is not `Box<T>` but `T`).
                 assert!(trait_info.is_none());
 
-                let aid = assumed::get_fun_id_from_name(&name).unwrap();
+                let aid = self.t_ctx.assumed_funs.get_fun_id_from_name(&name).unwrap();
 
                 // Note that some functions are actually traits (deref, index, etc.):
                 // we assume that they are called only on a limited set of types
@@ -1059,11 +1297,19 @@ impl<'tcx, 'ctx, 'ctx1> BodyTransCtx<'tcx, 'ctx, 'ctx1> {
                 // We have to retrieve the type `Box<u32>` and check that it is of the
                 // form `Box<T>` (and we generate `box_deref<u32>`).
                 match aid {
-                    AssumedFunId::BoxNew => {
+                    AssumedFunId::BoxNew
+                    | AssumedFunId::VecNew
+                    | AssumedFunId::VecPush
+                    | AssumedFunId::VecLen
+                    | AssumedFunId::OptionUnwrap
+                    | AssumedFunId::OptionIsSome
+                    | AssumedFunId::MemSwap
+                    | AssumedFunId::MemReplace
+                    | AssumedFunId::MemTake => {
                         // Nothing to do
                     }
-                    AssumedFunId::BoxFree => {
-                        // Special case handled elsewhere
+                    AssumedFunId::BoxFree | AssumedFunId::Fmt => {
+                        // Special cases handled elsewhere
                         unreachable!();
                     }
                     AssumedFunId::ArrayIndexShared
@@ -1078,6 +1324,19 @@ impl<'tcx, 'ctx, 'ctx1> BodyTransCtx<'tcx, 'ctx, 'ctx1> {
                         // operations (for ArrayToSlice for instance) to function calls.
                         unreachable!()
                     }
+                    AssumedFunId::VecIndexShared | AssumedFunId::VecIndexMut => {
+                        // Those cases are trait methods, handled above (see
+                        // [assumed::trait_method_to_assumed])
+                        unreachable!()
+                    }
+                    AssumedFunId::Transmute
+                    | AssumedFunId::CopyNonOverlapping
+                    | AssumedFunId::DiscriminantValue
+                    | AssumedFunId::SizeOfVal => {
+                        // The intrinsics are handled above (see
+                        // [assumed::get_intrinsic_id])
+                        unreachable!()
+                    }
                 };
 
                 let func = FnPtr {
@@ -1182,7 +1441,6 @@ impl<'tcx, 'ctx, 'ctx1> BodyTransCtx<'tcx, 'ctx, 'ctx1> {
             None => Ok(None),
             Some(t_statement) => {
                 let meta = self
-                    .t_ctx
                     .translate_meta_from_source_info(&body.source_scopes, &statement.source_info);
 
                 Ok(Some(Statement::new(meta, t_statement)))
@@ -1191,6 +1449,32 @@ impl<'tcx, 'ctx, 'ctx1> BodyTransCtx<'tcx, 'ctx, 'ctx1> {
     }
 
     /// Translate a terminator
+    /// Translate the message of a MIR assertion to the kind of the assertion.
+    fn translate_assert_kind(
+        &mut self,
+        span: rustc_span::Span,
+        msg: &hax::AssertKind,
+    ) -> Result<AssertKind, Error> {
+        match msg {
+            hax::AssertKind::BoundsCheck { len, index } => Ok(AssertKind::IndexOutOfBounds {
+                len: self.translate_operand(span, len)?,
+                index: self.translate_operand(span, index)?,
+            }),
+            hax::AssertKind::Overflow(binop, _, _) => Ok(AssertKind::Overflow(
+                self.t_ctx.translate_binaryop_kind(span, *binop)?,
+            )),
+            hax::AssertKind::OverflowNeg(_) => Ok(AssertKind::OverflowNeg),
+            hax::AssertKind::DivisionByZero(_) => Ok(AssertKind::DivisionByZero),
+            hax::AssertKind::RemainderByZero(_) => Ok(AssertKind::RemainderByZero),
+            hax::AssertKind::ResumedAfterReturn(_) | hax::AssertKind::ResumedAfterPanic(_) => {
+                Ok(AssertKind::ResumedGenerator)
+            }
+            hax::AssertKind::MisalignedPointerDereference { .. } => {
+                Ok(AssertKind::MisalignedPointerDereference)
+            }
+        }
+    }
+
     fn translate_terminator(
         &mut self,
         body: &hax::MirBody<()>,
@@ -1201,9 +1485,8 @@ impl<'tcx, 'ctx, 'ctx1> BodyTransCtx<'tcx, 'ctx, 'ctx1> {
 
         // Compute the meta information beforehand (we might need it to introduce
         // intermediate statements - we desugar some terminators)
-        let meta = self
-            .t_ctx
-            .translate_meta_from_source_info(&body.source_scopes, &terminator.source_info);
+        let meta =
+            self.translate_meta_from_source_info(&body.source_scopes, &terminator.source_info);
 
         // Translate the terminator
         use hax::TerminatorKind;
@@ -1222,21 +1505,27 @@ impl<'tcx, 'ctx, 'ctx1> BodyTransCtx<'tcx, 'ctx, 'ctx1> {
                 RawTerminator::Switch { discr, targets }
             }
             TerminatorKind::Resume => {
-                // This is used to correctly unwind. We shouldn't get there: if
-                // we panic, the state gets stuck.
-                error_or_panic!(self, span, "Unexpected terminator: resume");
+                // This is used at the end of the cleanup blocks, to resume the
+                // unwinding. We only explore the cleanup blocks if we keep the
+                // unwinding edges: otherwise, we shouldn't get there (if we
+                // panic, the state gets stuck).
+                if !self.t_ctx.keep_unwind {
+                    error_or_panic!(self, span, "Unexpected terminator: resume");
+                }
+                RawTerminator::Panic(PanicKind::Unwind(None))
             }
             TerminatorKind::Return => RawTerminator::Return,
             TerminatorKind::Unreachable => RawTerminator::Unreachable,
-            TerminatorKind::Terminate => unimplemented!(),
+            TerminatorKind::Terminate => RawTerminator::Panic(PanicKind::Abort),
             TerminatorKind::Drop {
                 place,
                 target,
-                unwind: _, // We consider that panic is an error, and don't model unwinding
+                unwind,
                 replace: _,
             } => RawTerminator::Drop {
                 place: self.translate_place(span, place)?,
                 target: self.translate_basic_block_id(*target),
+                unwind: self.translate_unwind_action(unwind),
             },
             TerminatorKind::Call {
                 fun,
@@ -1246,32 +1535,39 @@ impl<'tcx, 'ctx, 'ctx1> BodyTransCtx<'tcx, 'ctx, 'ctx1> {
                 target,
                 trait_refs,
                 trait_info,
-                unwind: _, // We consider that panic is an error, and don't model unwinding
+                unwind,
                 from_hir_call: _,
                 fn_span: _,
-            } => self.translate_function_call(
-                span,
-                fun,
-                substs,
-                args,
-                destination,
-                target,
-                trait_refs,
-                trait_info,
-            )?,
+            } => {
+                let unwind = self.translate_unwind_action(unwind);
+                self.translate_function_call(
+                    span,
+                    fun,
+                    substs,
+                    args,
+                    destination,
+                    target,
+                    unwind,
+                    trait_refs,
+                    trait_info,
+                )?
+            }
             TerminatorKind::Assert {
                 cond,
                 expected,
-                msg: _,
+                msg,
                 target,
-                unwind: _, // We consider that panic is an error, and don't model unwinding
+                unwind,
             } => {
                 let cond = self.translate_operand(span, cond)?;
+                let kind = self.translate_assert_kind(span, msg)?;
                 let target = self.translate_basic_block_id(*target);
                 RawTerminator::Assert {
                     cond,
                     expected: *expected,
+                    kind,
                     target,
+                    unwind: self.translate_unwind_action(unwind),
                 }
             }
             TerminatorKind::Yield {
@@ -1321,6 +1617,27 @@ impl<'tcx, 'ctx, 'ctx1> BodyTransCtx<'tcx, 'ctx, 'ctx1> {
         Ok(Terminator::new(meta, t_terminator))
     }
 
+    /// Translate the unwinding action of a terminator, by returning the cleanup
+    /// block to go to if the operation panics.
+    ///
+    /// We ignore the unwinding edges unless the user asked us to keep them (see
+    /// [crate::cli_options::CliOpts::keep_unwind]). Note that translating the id of
+    /// a cleanup block registers it, so that it gets translated.
+    fn translate_unwind_action(&mut self, unwind: &hax::UnwindAction) -> Option<BlockId::Id> {
+        if !self.t_ctx.keep_unwind {
+            return None;
+        }
+        match unwind {
+            hax::UnwindAction::Cleanup(block) => Some(self.translate_basic_block_id(*block)),
+            // If there is no cleanup block, the unwinding continues in the caller
+            // (or the program aborts, in which case the terminator is considered
+            // as a panic anyway).
+            hax::UnwindAction::Continue
+            | hax::UnwindAction::Unreachable
+            | hax::UnwindAction::Terminate => None,
+        }
+    }
+
     /// Translate switch targets
     fn translate_switch_targets(
         &mut self,
@@ -1344,6 +1661,7 @@ impl<'tcx, 'ctx, 'ctx1> BodyTransCtx<'tcx, 'ctx, 'ctx1> {
                         Ok((v, tgt))
                     })
                     .try_collect()?;
+                let targets_map = SwitchValue::group_targets(targets_map);
                 let otherwise = self.translate_basic_block_id(*otherwise);
                 Ok(SwitchTargets::SwitchInt(int_ty, targets_map, otherwise))
             }
@@ -1363,6 +1681,7 @@ impl<'tcx, 'ctx, 'ctx1> BodyTransCtx<'tcx, 'ctx, 'ctx1> {
         args: &Vec<hax::Operand>,
         destination: &hax::Place,
         target: &Option<hax::BasicBlock>,
+        unwind: Option<BlockId::Id>,
         trait_refs: &Vec<hax::ImplSource>,
         trait_info: &Option<hax::TraitInfo>,
     ) -> Result<RawTerminator, Error> {
@@ -1398,7 +1717,7 @@ impl<'tcx, 'ctx, 'ctx1> BodyTransCtx<'tcx, 'ctx, 'ctx1> {
                         assert!(target.is_none());
 
                         // We ignore the arguments
-                        Ok(RawTerminator::Panic)
+                        Ok(RawTerminator::Panic(PanicKind::Unwind(unwind)))
                     }
                     SubstFunIdOrPanic::Fun(fid) => {
                         let next_block = target.unwrap_or_else(|| {
@@ -1418,6 +1737,32 @@ impl<'tcx, 'ctx, 'ctx1> BodyTransCtx<'tcx, 'ctx, 'ctx1> {
                         Ok(RawTerminator::Call {
                             call,
                             target: next_block,
+                            unwind,
+                        })
+                    }
+                    SubstFunIdOrPanic::Virtual(sfid) => {
+                        let next_block = target.unwrap_or_else(|| {
+                            panic!("Expected a next block after the call to {:?}.\n\nSubsts: {:?}\n\nArgs: {:?}:", rust_id, substs, args)
+                        });
+
+                        // Translate the target
+                        let lval = self.translate_place(span, destination)?;
+                        let next_block = self.translate_basic_block_id(next_block);
+
+                        let call = Call {
+                            func: FnOperand::Virtual(
+                                sfid.trait_ref,
+                                sfid.method_name,
+                                sfid.generics,
+                            ),
+                            args: sfid.args.unwrap(),
+                            dest: lval,
+                        };
+
+                        Ok(RawTerminator::Call {
+                            call,
+                            target: next_block,
+                            unwind,
                         })
                     }
                 }
@@ -1449,6 +1794,7 @@ impl<'tcx, 'ctx, 'ctx1> BodyTransCtx<'tcx, 'ctx, 'ctx1> {
                 Ok(RawTerminator::Call {
                     call,
                     target: next_block,
+                    unwind,
                 })
             }
         }
@@ -1493,11 +1839,120 @@ impl<'tcx, 'ctx, 'ctx1> BodyTransCtx<'tcx, 'ctx, 'ctx1> {
         Ok(t_args)
     }
 
-    fn translate_body(mut self, local_id: LocalDefId, arg_count: usize) -> Result<ExprBody, Error> {
+    /// Resolve the trait instances used by the unsizing coercions to trait
+    /// objects (i.e., the vtables), and store them in [Self::unsize_vtables].
+    ///
+    /// We have to do this on the rustc MIR: the hax AST doesn't give us the
+    /// source type of the casts, and we need rustc types to solve the
+    /// trait obligations.
+    fn resolve_unsize_vtables(&mut self, body: &rustc_middle::mir::Body<'tcx>) {
+        use rustc_middle::mir::{CastKind, Rvalue, StatementKind};
+        use rustc_middle::ty::adjustment::PointerCast;
         let tcx = self.t_ctx.tcx;
+        let param_env = tcx.param_env(self.def_id);
+        for block in body.basic_blocks.iter() {
+            for st in &block.statements {
+                let StatementKind::Assign(box (
+                    _,
+                    Rvalue::Cast(CastKind::Pointer(PointerCast::Unsize), op, tgt_ty),
+                )) = &st.kind
+                else {
+                    continue;
+                };
+                // Retrieve the pointed types: we only consider the coercions
+                // between references, raw pointers and boxes.
+                let src_ty = op.ty(body, tcx);
+                let (Some(src_ty), Some(tgt_ty)) =
+                    (src_ty.builtin_deref(true), tgt_ty.builtin_deref(true))
+                else {
+                    continue;
+                };
+                // We ignore the coercions between trait objects
+                if src_ty.ty.is_trait() {
+                    continue;
+                }
+                if let ty::TyKind::Dynamic(preds, _, _) = tgt_ty.ty.kind()
+                    && let Some(principal) = preds.principal()
+                {
+                    let trait_ref = principal.with_self_ty(tcx, src_ty.ty);
+                    let impl_source = hax::solve_trait(&self.hax_state, param_env, trait_ref);
+                    self.unsize_vtables
+                        .entry(st.source_info.span)
+                        .or_default()
+                        .push(impl_source);
+                }
+            }
+        }
+    }
+
+    /// Retrieve the promoted constants used in a body, and store them in
+    /// [Self::promoted_constants].
+    ///
+    /// The promoted constants are the constant expressions (like `&[1, 2, 3]`)
+    /// which rustc lifts out of the function bodies, so that the references to
+    /// them have the `'static` lifetime. They don't have a Rust id, and hax
+    /// evaluates them: we translate them to anonymous globals (see
+    /// [TransCtx::translate_promoted]), and have to identify them on the rustc
+    /// MIR.
+    fn resolve_promoted_constants(&mut self, body: &rustc_middle::mir::Body<'tcx>) {
+        use rustc_middle::mir::visit::Visitor;
+        use rustc_middle::mir::{Constant, ConstantKind, Location};
+
+        struct Collect(HashMap<rustc_span::Span, Vec<(DefId, usize)>>);
+
+        impl<'tcx> Visitor<'tcx> for Collect {
+            fn visit_constant(&mut self, constant: &Constant<'tcx>, _: Location) {
+                // We can only retrieve the bodies of the local promoted
+                // constants (the optimized MIR may contain the promoted
+                // constants of functions inlined from other crates)
+                if let ConstantKind::Unevaluated(uv, _) = constant.literal
+                    && let Some(promoted) = uv.promoted
+                    && uv.def.is_local()
+                {
+                    self.0
+                        .entry(constant.span)
+                        .or_default()
+                        .push((uv.def, promoted.as_usize()));
+                }
+            }
+        }
 
+        let mut collect = Collect(HashMap::new());
+        collect.visit_body(body);
+        self.promoted_constants = collect.0;
+    }
+
+    /// Translate the body of an item. If the item comes from another crate,
+    /// its MIR must be available (see [TransCtx::id_is_extracted_dependency]).
+    fn translate_body(mut self, def_id: DefId, arg_count: usize) -> Result<ExprBody, Error> {
         // Retrive the body
-        let body = get_mir_for_def_id_and_level(tcx, local_id, self.t_ctx.mir_level);
+        let body = match def_id.as_local() {
+            Some(local_id) => {
+                get_mir_for_def_id_and_level(self.t_ctx.tcx, local_id, self.t_ctx.mir_level)
+            }
+            None => {
+                self.mir_level = MirLevel::Optimized;
+                get_external_mir(self.t_ctx.tcx, def_id)
+            }
+        };
+        self.translate_rustc_body(def_id, body, arg_count)
+    }
+
+    /// Translate a body we already retrieved (see [Self::translate_body]).
+    /// `def_id` is the id of the item which owns the body.
+    fn translate_rustc_body(
+        mut self,
+        def_id: DefId,
+        body: rustc_middle::mir::Body<'tcx>,
+        arg_count: usize,
+    ) -> Result<ExprBody, Error> {
+        let tcx = self.t_ctx.tcx;
+
+        // Resolve the vtables of the unsizing coercions
+        self.resolve_unsize_vtables(&body);
+
+        // Retrieve the promoted constants
+        self.resolve_promoted_constants(&body);
 
         // Here, we have to create a MIR state, which contains the body
         let state = hax::state::State::new_from_mir(
@@ -1508,10 +1963,11 @@ impl<'tcx, 'ctx, 'ctx1> BodyTransCtx<'tcx, 'ctx, 'ctx1> {
             // Yes, we have to clone, this is annoying: we end up cloning the body twice
             body.clone(),
             // Owner id
-            local_id.to_def_id(),
+            def_id,
         );
         // Translate
-        let body: hax::MirBody<()> = body.sinto(&state);
+        let rustc_body = body;
+        let body: hax::MirBody<()> = rustc_body.sinto(&state);
 
         // Initialize the local variables
         trace!("Translating the body locals");
@@ -1521,6 +1977,9 @@ impl<'tcx, 'ctx, 'ctx1> BodyTransCtx<'tcx, 'ctx, 'ctx1> {
         trace!("Translating the expression body");
         self.translate_transparent_expression_body(&body)?;
 
+        // Translate the debug information of the variables
+        let var_debug_info = self.translate_var_debug_info(&rustc_body);
+
         // Compute the meta information
         let meta = self.translate_meta_from_rspan(body.span);
 
@@ -1539,6 +1998,8 @@ impl<'tcx, 'ctx, 'ctx1> BodyTransCtx<'tcx, 'ctx, 'ctx1> {
             meta,
             arg_count,
             locals: self.vars,
+            var_debug_info,
+            backtraces: self.backtraces,
             body: blocks,
         })
     }
@@ -1546,7 +2007,16 @@ impl<'tcx, 'ctx, 'ctx1> BodyTransCtx<'tcx, 'ctx, 'ctx1> {
     /// Translate a function's signature, and initialize a body translation context
     /// at the same time - the function signature gives us the list of region and
     /// type parameters, that we put in the translation context.
-    fn translate_function_signature(&mut self, def_id: DefId) -> Result<FunSig, Error> {
+    ///
+    /// If `tolerant` is `true`, we ignore the failures which happen when
+    /// translating the predicates and the closure information: we use this mode
+    /// for the opaque functions, for which we only need a typed declaration
+    /// (see [TransCtx::translate_opaque_function_signature]).
+    fn translate_function_signature(
+        &mut self,
+        def_id: DefId,
+        tolerant: bool,
+    ) -> Result<FunSig, Error> {
         let tcx = self.t_ctx.tcx;
         let erase_regions = false;
         let span = self.t_ctx.tcx.def_span(def_id);
@@ -1644,6 +2114,9 @@ impl<'tcx, 'ctx, 'ctx1> BodyTransCtx<'tcx, 'ctx, 'ctx1> {
 
             (substs, fn_sig, None)
         };
+        // The hax signature doesn't give us the ABI
+        let abi = signature.abi().name().to_string();
+        let is_variadic = signature.c_variadic();
         let signature: hax::MirPolyFnSig = signature.sinto(&self.hax_state);
 
         // Start by translating the early-bound parameters (those are contained by `substs`).
@@ -1661,6 +2134,9 @@ impl<'tcx, 'ctx, 'ctx1> BodyTransCtx<'tcx, 'ctx, 'ctx1> {
             hax::Unsafety::Unsafe => true,
             hax::Unsafety::Normal => false,
         };
+        let is_const = tcx.is_const_fn_raw(def_id);
+        // Only the functions can be `async` (the closures can't)
+        let is_async = !is_closure && tcx.asyncness(def_id).is_async();
         let bvar_names = signature
             .bound_vars
             .into_iter()
@@ -1685,10 +2161,10 @@ impl<'tcx, 'ctx, 'ctx1> BodyTransCtx<'tcx, 'ctx, 'ctx1> {
         let fun_kind = &self.t_ctx.get_fun_kind(&dep_src, def_id);
 
         // Add the trait clauses
-        self.while_registering_trait_clauses(move |ctx| {
+        let res = self.while_registering_trait_clauses(move |ctx| {
             // Add the ctx trait clause if it is a trait decl item
             match fun_kind {
-                FunKind::Regular => (),
+                FunKind::Regular | FunKind::InherentMethod(_) | FunKind::Foreign => (),
                 FunKind::TraitMethodImpl { impl_id, .. } => {
                     ctx.add_trait_impl_self_trait_clause(*impl_id)?;
                 }
@@ -1701,7 +2177,10 @@ impl<'tcx, 'ctx, 'ctx1> BodyTransCtx<'tcx, 'ctx, 'ctx1> {
 
             // Translate the predicates (in particular, the trait clauses)
             match &fun_kind {
-                FunKind::Regular | FunKind::TraitMethodImpl { .. } => {
+                FunKind::Regular
+                | FunKind::InherentMethod(_)
+                | FunKind::TraitMethodImpl { .. }
+                | FunKind::Foreign => {
                     ctx.translate_predicates_of(None, def_id)?;
                 }
                 FunKind::TraitMethodProvided(trait_decl_id, ..)
@@ -1713,7 +2192,18 @@ impl<'tcx, 'ctx, 'ctx1> BodyTransCtx<'tcx, 'ctx, 'ctx1> {
             // Solve the unsolved obligations
             ctx.solve_trait_obligations_in_trait_clauses(span);
             Ok(())
-        })?;
+        });
+        match res {
+            Ok(()) => (),
+            Err(err) if tolerant => {
+                // We keep the clauses we managed to translate
+                self.t_ctx.session.span_warn(
+                    span,
+                    format!("Ignoring some predicates of {def_id:?}: {}", err.msg),
+                );
+            }
+            Err(err) => return Err(err),
+        }
 
         // Translate the signature
         trace!("signature of {def_id:?}:\n{:?}", signature);
@@ -1740,30 +2230,27 @@ impl<'tcx, 'ctx, 'ctx1> BodyTransCtx<'tcx, 'ctx, 'ctx1> {
             let state = state_tys
                 .into_iter()
                 .map(|ty| self.translate_ty(span, erase_regions, &ty.sinto(&self.hax_state)))
-                .try_collect::<Vec<Ty>>()?;
+                .try_collect::<Vec<Ty>>();
 
-            Some(ClosureInfo { kind, state })
+            match state {
+                Ok(state) => Some(ClosureInfo { kind, state }),
+                Err(_) if tolerant => None,
+                Err(err) => return Err(err),
+            }
         } else {
             None
         };
 
-        let mut parent_params_info = self.get_function_parent_params_info(&dep_src, def_id);
-        // If this is a trait decl method, we need to adjust the number of parent clauses
-        if matches!(
-            fun_kind,
-            FunKind::TraitMethodProvided(..) | FunKind::TraitMethodDecl(..)
-        ) {
-            if let Some(info) = &mut parent_params_info {
-                // All the trait clauses are registered as parent (of Self)
-                // trait clauses, not as local trait clauses.
-                info.num_trait_clauses = 0;
-            }
-        }
+        let parent_params_info = self.get_assoc_item_parent_params_info(def_id);
 
         Ok(FunSig {
             generics: self.get_generics(),
             preds: self.get_predicates(),
             is_unsafe,
+            is_const,
+            is_async,
+            abi,
+            is_variadic,
             is_closure,
             closure_info,
             parent_params_info,
@@ -1771,22 +2258,6 @@ impl<'tcx, 'ctx, 'ctx1> BodyTransCtx<'tcx, 'ctx, 'ctx1> {
             output,
         })
     }
-
-    fn get_function_parent_params_info(
-        &mut self,
-        src: &Option<DepSource>,
-        def_id: DefId,
-    ) -> Option<ParamsInfo> {
-        let kind = self.t_ctx.get_fun_kind(src, def_id);
-        match kind {
-            FunKind::Regular => None,
-            FunKind::TraitMethodImpl { .. }
-            | FunKind::TraitMethodDecl { .. }
-            | FunKind::TraitMethodProvided { .. } => {
-                Some(self.get_parent_params_info(def_id).unwrap())
-            }
-        }
-    }
 }
 
 impl<'tcx, 'ctx> TransCtx<'tcx, 'ctx> {
@@ -1814,9 +2285,13 @@ impl<'tcx, 'ctx> TransCtx<'tcx, 'ctx> {
         let def_id = self.translate_fun_decl_id(&None, rust_id);
         let is_transparent = self.id_is_transparent(rust_id);
         let def_span = self.tcx.def_span(rust_id);
+        if !is_transparent {
+            hidden_effects::warn_hidden_effects(self, rust_id);
+        }
 
         // Compute the meta information
         let meta = self.translate_meta_from_rid(rust_id);
+        let attr_info = self.translate_attr_info_from_rid(rust_id);
 
         // Initialize the body translation context
         let mut bt_ctx = BodyTransCtx::new(rust_id, self);
@@ -1826,35 +2301,54 @@ impl<'tcx, 'ctx> TransCtx<'tcx, 'ctx> {
             .t_ctx
             .extended_def_id_to_name(&rust_id.sinto(&bt_ctx.hax_state));
 
-        // Check whether this function is a method declaration for a trait definition.
-        // If this is the case, it shouldn't contain a body.
+        // Check whether this function is a method declaration for a trait
+        // definition or a function declared in an `extern` block. If this is
+        // the case, it doesn't contain a body.
         let kind = bt_ctx
             .t_ctx
             .get_fun_kind(&DepSource::make(rust_id, def_span), rust_id);
-        let is_trait_method_decl = match &kind {
+        let has_no_body = match &kind {
             FunKind::Regular
+            | FunKind::InherentMethod(_)
             | FunKind::TraitMethodImpl { .. }
             | FunKind::TraitMethodProvided(..) => false,
-            FunKind::TraitMethodDecl(..) => true,
+            FunKind::TraitMethodDecl(..) | FunKind::Foreign => true,
         };
 
-        // Translate the function signature
-        trace!("Translating function signature");
-        let signature = bt_ctx.translate_function_signature(rust_id)?;
-
-        // Check if the type is opaque or transparent
+        // Check if the type is opaque or transparent: we only extract the
+        // bodies of the local functions, and of the functions of the
+        // dependencies the user selected
         let is_local = rust_id.is_local();
+        let is_extracted = is_local || bt_ctx.t_ctx.id_is_extracted_dependency(rust_id);
+        let is_opaque = !is_transparent || !is_extracted || has_no_body;
 
-        let body = if !is_transparent || !is_local || is_trait_method_decl {
-            None
+        // Translate the function signature
+        trace!("Translating function signature");
+        let signature = bt_ctx.translate_function_signature(rust_id, false);
+
+        let (signature, body) = if is_opaque {
+            let signature = match signature {
+                Ok(signature) => signature,
+                // We don't need the body: retry in a more tolerant manner,
+                // so that we at least have a typed declaration
+                Err(_) => self.translate_opaque_function_signature(rust_id)?,
+            };
+            (signature, None)
         } else {
-            match bt_ctx.translate_body(rust_id.expect_local(), signature.inputs.len()) {
+            let signature = signature?;
+            let body = match bt_ctx.translate_body(rust_id, signature.inputs.len()) {
                 Ok(body) => Some(body),
-                Err(_) => {
-                    // Error case: we could have a variant for this
+                Err(err) => {
+                    // Error case: we could have a variant for this. Note that
+                    // the body may have been skipped on purpose (see
+                    // [Error::skipped]).
+                    if !err.skipped {
+                        self.register_opaque_warning(&name, meta.span, &err.msg);
+                    }
                     None
                 }
-            }
+            };
+            (signature, body)
         };
 
         // Save the new function
@@ -1862,6 +2356,7 @@ impl<'tcx, 'ctx> TransCtx<'tcx, 'ctx> {
             def_id,
             FunDecl {
                 meta,
+                attr_info,
                 def_id,
                 rust_id,
                 is_local,
@@ -1869,12 +2364,35 @@ impl<'tcx, 'ctx> TransCtx<'tcx, 'ctx> {
                 signature,
                 kind,
                 body,
+                pure_form: None,
+                call_depth: None,
             },
         );
 
         Ok(())
     }
 
+    /// Translate the signature of a function whose body we don't extract,
+    /// after the regular translation failed (see [Self::translate_function_aux]).
+    ///
+    /// We start from a fresh body translation context, and ignore the
+    /// failures which don't prevent us from computing the types of the inputs
+    /// and the output (the predicates we can't translate, etc.). This way,
+    /// the functions we refer to at least get a typed opaque declaration.
+    fn translate_opaque_function_signature(&mut self, rust_id: DefId) -> Result<FunSig, Error> {
+        let mut bt_ctx = BodyTransCtx::new(rust_id, self);
+        let signature = bt_ctx.translate_function_signature(rust_id, true)?;
+        let span = self.tcx.def_span(rust_id);
+        self.session.span_warn(
+            span,
+            format!(
+                "Translated a degraded signature for the opaque function {:?}",
+                rust_id
+            ),
+        );
+        Ok(signature)
+    }
+
     /// Translate one global.
     pub(crate) fn translate_global(&mut self, rust_id: DefId) {
         self.with_def_id(rust_id, |ctx| {
@@ -1902,7 +2420,11 @@ impl<'tcx, 'ctx> TransCtx<'tcx, 'ctx> {
 
         // Compute the meta information
         let meta = self.translate_meta_from_rid(rust_id);
+        let attr_info = self.translate_attr_info_from_rid(rust_id);
         let is_transparent = self.id_is_transparent(rust_id);
+        if !is_transparent {
+            hidden_effects::warn_hidden_effects(self, rust_id);
+        }
 
         // Initialize the body translation context
         let mut bt_ctx = BodyTransCtx::new(rust_id, self);
@@ -1917,12 +2439,29 @@ impl<'tcx, 'ctx> TransCtx<'tcx, 'ctx> {
         let mir_ty = bt_ctx.t_ctx.tcx.type_of(rust_id).subst_identity();
         let erase_regions = false; // This doesn't matter: there shouldn't be any regions
         let ty = bt_ctx.translate_ty(span, erase_regions, &mir_ty.sinto(hax_state))?;
+        let parent_params_info = bt_ctx.get_assoc_item_parent_params_info(rust_id);
+
+        // Compute the kind: for the statics, we need to know if they can be
+        // mutated
+        let tcx = bt_ctx.t_ctx.tcx;
+        let kind = match tcx.static_mutability(rust_id) {
+            None => GlobalKind::Const,
+            Some(mutability) => GlobalKind::Static {
+                is_mut: mutability.is_mut(),
+                interior_mut: !mir_ty.is_freeze(tcx, tcx.param_env(rust_id)),
+            },
+        };
 
-        let body = if rust_id.is_local() && is_transparent {
-            // It's a local and transparent global: we extract its body as for functions.
-            match bt_ctx.translate_body(rust_id.expect_local(), 0) {
-                Err(_) => {
+        let is_extracted = rust_id.is_local() || bt_ctx.t_ctx.id_is_extracted_dependency(rust_id);
+        let body = if is_extracted && is_transparent {
+            // It's a local (or extracted) and transparent global: we extract
+            // its body as for functions.
+            match bt_ctx.translate_body(rust_id, 0) {
+                Err(err) => {
                     // Error case: we could have a specific variant
+                    if !err.skipped {
+                        self.register_opaque_warning(&name, meta.span, &err.msg);
+                    }
                     None
                 }
                 Ok(body) => Some(body),
@@ -1939,9 +2478,104 @@ impl<'tcx, 'ctx> TransCtx<'tcx, 'ctx> {
                 def_id,
                 rust_id,
                 meta,
+                attr_info,
                 is_local: rust_id.is_local(),
                 name,
+                parent_params_info,
+                ty,
+                kind,
+                body,
+            },
+        );
+
+        Ok(())
+    }
+
+    /// Translate a promoted constant: we translate it to an anonymous global,
+    /// whose name is the name of the item it comes from, followed by
+    /// `promoted` (the disambiguator is the index of the constant).
+    ///
+    /// Remark: the promoted constants can't refer to the generic parameters of
+    /// the item they come from (we fail if they do).
+    pub(crate) fn translate_promoted(&mut self, rust_id: DefId, promoted: usize) {
+        self.with_def_id(rust_id, |ctx| {
+            if ctx.translate_promoted_aux(rust_id, promoted).is_err() {
+                let span = ctx.tcx.def_span(rust_id);
+                ctx.span_err(
+                    span,
+                    &format!(
+                        "Ignoring the following promoted constant due to an error: {:?}[{}]",
+                        rust_id, promoted
+                    ),
+                );
+            }
+        });
+    }
+
+    /// Auxliary helper to properly handle errors, see [translate_promoted].
+    pub fn translate_promoted_aux(&mut self, rust_id: DefId, promoted: usize) -> Result<(), Error> {
+        trace!(
+            "About to translate promoted constant:\n{:?}[{}]",
+            rust_id,
+            promoted
+        );
+        let def_id = self.register_promoted_global_decl_id(&None, rust_id, promoted);
+        let is_transparent = self.id_is_transparent(rust_id);
+
+        // Retrieve the body: we only register the local promoted constants
+        let local_id = rust_id.expect_local();
+        let mut bodies = get_promoted_mir_for_def_id_and_level(self.tcx, local_id, self.mir_level);
+        let body = bodies.swap_remove(rustc_middle::mir::Promoted::from_usize(promoted));
+
+        // Initialize the body translation context
+        let mut bt_ctx = BodyTransCtx::new(rust_id, self);
+        let hax_state = &bt_ctx.hax_state;
+
+        // Compute the name and the meta information
+        let mut name = bt_ctx
+            .t_ctx
+            .extended_def_id_to_name(&rust_id.sinto(hax_state));
+        name.name.push(PathElem::Ident(
+            "promoted".to_string(),
+            Disambiguator::Id::new(promoted),
+        ));
+        let meta = bt_ctx.translate_meta_from_rspan(body.span.sinto(hax_state));
+
+        trace!("Translating promoted constant type");
+        let hax_state = &bt_ctx.hax_state;
+        let mir_ty = body.local_decls[rustc_middle::mir::RETURN_PLACE].ty;
+        let erase_regions = true;
+        let ty =
+            bt_ctx.translate_ty(meta.span.rust_span, erase_regions, &mir_ty.sinto(hax_state))?;
+
+        let body = if is_transparent {
+            match bt_ctx.translate_rustc_body(rust_id, body, 0) {
+                Ok(body) => Some(body),
+                Err(err) => {
+                    if !err.skipped {
+                        self.register_opaque_warning(&name, meta.span, &err.msg);
+                    }
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
+        // Save the new global
+        self.global_decls.insert(
+            def_id,
+            GlobalDecl {
+                def_id,
+                rust_id,
+                meta,
+                // The promoted constants are introduced by the compiler
+                attr_info: AttrInfo::default(),
+                is_local: true,
+                name,
+                parent_params_info: None,
                 ty,
+                kind: GlobalKind::Const,
                 body,
             },
         );
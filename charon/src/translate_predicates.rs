@@ -1,3 +1,4 @@
+use crate::assumed;
 use crate::common::*;
 use crate::formatter::AstFormatter;
 use crate::formatter::IntoFormatter;
@@ -38,6 +39,7 @@ pub(crate) struct NonLocalTraitClause {
     /// [Some] if this is the top clause, [None] if this is about a parent/
     /// associated type clause.
     pub meta: Option<Meta>,
+    pub origin: TraitClauseOrigin,
     pub trait_id: TraitDeclId::Id,
     pub generics: GenericArgs,
 }
@@ -48,6 +50,7 @@ impl NonLocalTraitClause {
             Some(TraitClause {
                 clause_id: *id,
                 meta: self.meta,
+                origin: self.origin,
                 trait_id: self.trait_id,
                 generics: self.generics.clone(),
             })
@@ -63,6 +66,7 @@ impl NonLocalTraitClause {
         get_id(&self.clause_id).map(|clause_id| TraitClause {
             clause_id,
             meta: self.meta,
+            origin: self.origin,
             trait_id: self.trait_id,
             generics: self.generics.clone(),
         })
@@ -134,6 +138,25 @@ impl<'tcx, 'ctx, 'ctx1> BodyTransCtx<'tcx, 'ctx, 'ctx1> {
         }
     }
 
+    /// Compute the information about the generics of the parent block, if
+    /// the definition is an associated item (a method or an associated
+    /// constant, in a trait declaration or an impl block). We compute this
+    /// information in the same way for all the associated items, so that the
+    /// consumers can split their generics between the parent and the item.
+    pub(crate) fn get_assoc_item_parent_params_info(
+        &mut self,
+        def_id: DefId,
+    ) -> Option<ParamsInfo> {
+        let assoc = self.t_ctx.tcx.opt_associated_item(def_id)?;
+        let mut params_info = self.get_parent_params_info(def_id)?;
+        if let rustc_middle::ty::AssocItemContainer::TraitContainer = assoc.container {
+            // All the trait clauses of a trait declaration are registered as
+            // parent (of Self) trait clauses, not as local trait clauses.
+            params_info.num_trait_clauses = 0;
+        }
+        Some(params_info)
+    }
+
     pub(crate) fn get_predicates_of(
         &mut self,
         def_id: DefId,
@@ -396,6 +419,36 @@ impl<'tcx, 'ctx, 'ctx1> BodyTransCtx<'tcx, 'ctx, 'ctx1> {
         Ok(())
     }
 
+    /// Find out whether a trait clause of the current definition comes from a
+    /// `where` clause or from an inline bound, by looking for the bound in the
+    /// HIR generics of the definition and of its parents.
+    fn trait_clause_origin(&self, span: rustc_span::Span) -> TraitClauseOrigin {
+        use rustc_hir::{PredicateOrigin, WherePredicate};
+        let tcx = self.t_ctx.tcx;
+        let mut def_id = Some(self.def_id);
+        while let Some(id) = def_id {
+            if let Some(local_id) = id.as_local()
+                && let Some(generics) = tcx.hir().get_generics(local_id)
+            {
+                for pred in generics.predicates {
+                    if let WherePredicate::BoundPredicate(pred) = pred
+                        && pred.span.contains(span)
+                    {
+                        return match pred.origin {
+                            PredicateOrigin::WhereClause => TraitClauseOrigin::WhereClause,
+                            PredicateOrigin::GenericParam | PredicateOrigin::ImplTrait => {
+                                TraitClauseOrigin::InlineBound
+                            }
+                        };
+                    }
+                }
+            }
+            def_id = tcx.generics_of(id).parent;
+        }
+        // We can't find the bound: this happens for the external definitions
+        TraitClauseOrigin::WhereClause
+    }
+
     /// Returns an [Option] because we may filter clauses about builtin or
     /// auto traits like [core::marker::Sized] and [core::marker::Sync].
     ///
@@ -416,7 +469,17 @@ impl<'tcx, 'ctx, 'ctx1> BodyTransCtx<'tcx, 'ctx, 'ctx1> {
         let erase_regions = false;
 
         let trait_ref = &trait_pred.trait_ref;
-        let trait_id = self.translate_trait_decl_id(span, trait_ref.def_id.rust_def_id.unwrap());
+        let rust_id = trait_ref.def_id.rust_def_id.unwrap();
+
+        // Record the `Sized` type variables (we may filter the clause below)
+        let name = self.t_ctx.item_def_id_to_name(rust_id);
+        if name.equals_ref_name(&assumed::MARKER_SIZED_NAME)
+            && let Some(hax::GenericArg::Type(self_ty)) = trait_ref.generic_args.first()
+        {
+            self.register_sized_type_var(self_ty);
+        }
+
+        let trait_id = self.translate_trait_decl_id(span, rust_id);
         // We might have to ignore the trait
         let trait_id = if let Some(trait_id) = trait_id {
             trait_id
@@ -432,12 +495,18 @@ impl<'tcx, 'ctx, 'ctx1> BodyTransCtx<'tcx, 'ctx, 'ctx1> {
         // Compute the current clause id
         let clause_id = (self.trait_instance_id_gen)();
         let meta = self.translate_meta_from_rspan(hspan.clone());
+        let origin = match &clause_id {
+            TraitInstanceId::Clause(_) => self.trait_clause_origin(span),
+            TraitInstanceId::ParentClause(..) => TraitClauseOrigin::Supertrait,
+            _ => TraitClauseOrigin::Elaborated,
+        };
 
         // Immediately register the clause (we may need to refer to it in the parent/
         // item clauses)
         let trait_clause = NonLocalTraitClause {
             clause_id: clause_id.clone(),
             meta: Some(meta),
+            origin,
             trait_id,
             generics,
         };
@@ -625,6 +694,32 @@ impl<'tcx, 'ctx, 'ctx1> BodyTransCtx<'tcx, 'ctx, 'ctx1> {
         Ok(res.into_iter().flatten().collect())
     }
 
+    /// Returns [None] if the trait is a marker trait that we ignore (see
+    /// [Self::translate_trait_impl_source]).
+    pub(crate) fn translate_trait_decl_ref(
+        &mut self,
+        span: rustc_span::Span,
+        erase_regions: bool,
+        trait_ref: &hax::TraitRef,
+    ) -> Result<Option<TraitDeclRef>, Error> {
+        let trait_id = self.translate_trait_decl_id(span, trait_ref.def_id.rust_def_id.unwrap());
+        let trait_id = if let Some(trait_id) = trait_id {
+            trait_id
+        } else {
+            return Ok(None);
+        };
+
+        let parent_trait_refs = Vec::new();
+        let generics = self.translate_substs_and_trait_refs(
+            span,
+            erase_regions,
+            None,
+            &trait_ref.generic_args,
+            &parent_trait_refs,
+        )?;
+        Ok(Some(TraitDeclRef { trait_id, generics }))
+    }
+
     /// Returns an [Option] because we may ignore some builtin or auto traits
     /// like [core::marker::Sized] or [core::marker::Sync].
     pub(crate) fn translate_trait_impl_source(
@@ -633,27 +728,12 @@ impl<'tcx, 'ctx, 'ctx1> BodyTransCtx<'tcx, 'ctx, 'ctx1> {
         erase_regions: bool,
         impl_source: &hax::ImplSource,
     ) -> Result<Option<TraitRef>, Error> {
-        let trait_decl_ref = {
-            let trait_ref = &impl_source.trait_ref;
-            let trait_id =
-                self.translate_trait_decl_id(span, trait_ref.def_id.rust_def_id.unwrap());
-            let trait_id = if let Some(trait_id) = trait_id {
-                trait_id
-            } else {
-                return Ok(None);
+        let trait_decl_ref =
+            match self.translate_trait_decl_ref(span, erase_regions, &impl_source.trait_ref)? {
+                Some(trait_decl_ref) => trait_decl_ref,
+                None => return Ok(None),
             };
 
-            let parent_trait_refs = Vec::new();
-            let generics = self.translate_substs_and_trait_refs(
-                span,
-                erase_regions,
-                None,
-                &trait_ref.generic_args,
-                &parent_trait_refs,
-            )?;
-            TraitDeclRef { trait_id, generics }
-        };
-
         match self.translate_trait_impl_source_aux(
             span,
             erase_regions,
@@ -788,8 +868,40 @@ impl<'tcx, 'ctx, 'ctx1> BodyTransCtx<'tcx, 'ctx, 'ctx1> {
                 }
             }
             ImplSourceKind::FnPointer(data) => {
-                let ty = self.translate_ty(span, erase_regions, &data.fn_ty)?;
-                let trait_id = TraitInstanceId::FnPointer(Box::new(ty));
+                // Retrieve the trait which is implemented
+                let trait_def_id = impl_source.trait_ref.def_id.rust_def_id.unwrap();
+                let kind = match self.t_ctx.tcx.fn_trait_kind_from_def_id(trait_def_id) {
+                    Some(rustc_middle::ty::ClosureKind::Fn) => ClosureKind::Fn,
+                    Some(rustc_middle::ty::ClosureKind::FnMut) => ClosureKind::FnMut,
+                    Some(rustc_middle::ty::ClosureKind::FnOnce) => ClosureKind::FnOnce,
+                    None => error_or_panic!(
+                        self,
+                        span,
+                        "Unexpected trait implemented by a function pointer"
+                    ),
+                };
+                // The arguments are given by the tuple which parameterizes the
+                // trait (the first type argument is `Self`)
+                let inputs = match trait_decl_ref.generics.types.get(1) {
+                    Some(Ty::Adt(TypeId::Tuple, args)) => args.types.clone(),
+                    _ => error_or_panic!(
+                        self,
+                        span,
+                        "Unexpected arguments for a function pointer trait instance"
+                    ),
+                };
+                // The output is given by the signature of the function. It may
+                // refer to the regions bound by the signature: we erase them.
+                let output = match self.translate_ty(span, erase_regions, &data.fn_ty)? {
+                    Ty::Arrow(regions, _, box output) if regions.is_empty() => output,
+                    Ty::Arrow(_, _, box output) => output.erase_regions(),
+                    _ => error_or_panic!(
+                        self,
+                        span,
+                        "Unexpected type for a function pointer trait instance"
+                    ),
+                };
+                let trait_id = TraitInstanceId::FnPointer(kind, inputs, Box::new(output));
                 let trait_refs =
                     self.translate_trait_impl_sources(span, erase_regions, &data.nested)?;
                 let generics = GenericArgs {
@@ -424,6 +424,8 @@ impl<'tcx, 'ctx> TransCtx<'tcx, 'ctx> {
             is_local: rust_id.is_local(),
             name,
             meta: self.translate_meta_from_rid(rust_id),
+            attr_info: self.translate_attr_info_from_rid(rust_id),
+            is_auto: tcx.trait_is_auto(rust_id),
             generics,
             preds,
             parent_clauses,
@@ -645,12 +647,30 @@ impl<'tcx, 'ctx> TransCtx<'tcx, 'ctx> {
             }
         }
 
+        // The type implementing the trait is the first type argument of the
+        // trait reference
+        let self_ty = implemented_trait.generics.types[0].clone();
+        let header = tcx.sess.source_map().span_to_snippet(span).ok();
+        let is_negative = matches!(
+            tcx.impl_polarity(rust_id),
+            rustc_middle::ty::ImplPolarity::Negative
+        );
+        let is_blanket = tcx
+            .trait_impls_of(implemented_trait_rust_id)
+            .blanket_impls()
+            .contains(&rust_id);
+
         let trait_impl = ast::TraitImpl {
             def_id,
             is_local: rust_id.is_local(),
             name,
             meta: bt_ctx.t_ctx.translate_meta_from_rid(rust_id),
+            attr_info: bt_ctx.translate_attr_info_from_rid(rust_id),
             impl_trait: implemented_trait,
+            self_ty,
+            header,
+            is_negative,
+            is_blanket,
             generics: bt_ctx.get_generics(),
             preds: bt_ctx.get_predicates(),
             parent_trait_refs,
@@ -663,4 +683,67 @@ impl<'tcx, 'ctx> TransCtx<'tcx, 'ctx> {
 
         Ok(())
     }
+
+    pub(crate) fn translate_impl_block(&mut self, rust_id: DefId) {
+        self.with_def_id(rust_id, |ctx| {
+            if ctx.translate_impl_block_aux(rust_id).is_err() {
+                let span = ctx.tcx.def_span(rust_id);
+                ctx.span_err(
+                    span,
+                    &format!(
+                        "Ignoring the following impl block due to an error: {:?}",
+                        rust_id
+                    ),
+                );
+                // Save the definition
+                let _ = ctx.ignored_failed_decls.insert(rust_id);
+            }
+        });
+    }
+
+    /// Auxliary helper to properly handle errors, see [translate_impl_block].
+    fn translate_impl_block_aux(&mut self, rust_id: DefId) -> Result<(), Error> {
+        trace!("About to translate impl block:\n{:?}", rust_id);
+
+        let def_id = self.impl_block_id_map.get(&rust_id).unwrap();
+        let tcx = self.tcx;
+        let span = tcx.def_span(rust_id);
+        let mut bt_ctx = BodyTransCtx::new(rust_id, self);
+
+        let name = bt_ctx
+            .t_ctx
+            .extended_def_id_to_name(&rust_id.sinto(&bt_ctx.hax_state));
+        let erase_regions = false;
+
+        // Translate the generics and the predicates
+        bt_ctx.translate_generic_params(rust_id)?;
+        bt_ctx.while_registering_trait_clauses(move |bt_ctx| {
+            bt_ctx.translate_predicates_of(None, rust_id)?;
+            bt_ctx.solve_trait_obligations_in_trait_clauses(span);
+            Ok(())
+        })?;
+
+        // Translate the type for which the methods are implemented
+        let self_ty = tcx
+            .type_of(rust_id)
+            .subst_identity()
+            .sinto(&bt_ctx.hax_state);
+        let self_ty = bt_ctx.translate_ty(span, erase_regions, &self_ty)?;
+
+        let impl_block = ast::ImplBlock {
+            def_id,
+            is_local: rust_id.is_local(),
+            name,
+            meta: bt_ctx.t_ctx.translate_meta_from_rid(rust_id),
+            attr_info: bt_ctx.translate_attr_info_from_rid(rust_id),
+            self_ty,
+            generics: bt_ctx.get_generics(),
+            preds: bt_ctx.get_predicates(),
+            // We fill the methods once all the functions are translated
+            methods: Vec::new(),
+        };
+        self.impl_blocks.insert(def_id, impl_block);
+
+        Ok(())
+    }
 }
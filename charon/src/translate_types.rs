@@ -2,13 +2,48 @@ use crate::assumed;
 use crate::common::*;
 use crate::formatter::IntoFormatter;
 use crate::gast::*;
+use crate::id_vector::ToUsize;
 use crate::translate_ctx::*;
 use crate::types::*;
+use crate::values::ScalarValue;
 use core::convert::*;
 use hax_frontend_exporter as hax;
 use hax_frontend_exporter::SInto;
 use rustc_hir::def_id::DefId;
 
+/// Translate an integer type, as found in the representation options of ADTs.
+fn translate_integer_type(int: rustc_abi::IntegerType) -> IntegerTy {
+    use rustc_abi::{Integer, IntegerType};
+    match int {
+        IntegerType::Pointer(true) => IntegerTy::Isize,
+        IntegerType::Pointer(false) => IntegerTy::Usize,
+        IntegerType::Fixed(int, signed) => match (int, signed) {
+            (Integer::I8, true) => IntegerTy::I8,
+            (Integer::I16, true) => IntegerTy::I16,
+            (Integer::I32, true) => IntegerTy::I32,
+            (Integer::I64, true) => IntegerTy::I64,
+            (Integer::I128, true) => IntegerTy::I128,
+            (Integer::I8, false) => IntegerTy::U8,
+            (Integer::I16, false) => IntegerTy::U16,
+            (Integer::I32, false) => IntegerTy::U32,
+            (Integer::I64, false) => IntegerTy::U64,
+            (Integer::I128, false) => IntegerTy::U128,
+        },
+    }
+}
+
+/// Translate the representation options of an ADT (`#[repr(...)]`).
+fn translate_repr_options(repr: &rustc_middle::ty::ReprOptions) -> ReprOptions {
+    let int = repr.int.map(translate_integer_type);
+    ReprOptions {
+        c: repr.c(),
+        transparent: repr.transparent(),
+        int,
+        pack: repr.pack.map(|align| align.bytes()),
+        align: repr.align.map(|align| align.bytes()),
+    }
+}
+
 /// Small helper: we ignore some region names (when they are equal to "'_")
 fn check_region_name(s: Option<String>) -> Option<String> {
     if s.is_some() && s.as_ref().unwrap() == "'_" {
@@ -227,7 +262,7 @@ impl<'tcx, 'ctx, 'ctx1> BodyTransCtx<'tcx, 'ctx, 'ctx1> {
                     Option::None
                 } else {
                     let name = self.t_ctx.def_id_to_name(def_id);
-                    assumed::type_to_used_params(&name)
+                    assumed::type_to_used_params(&name, substs.len())
                 };
 
                 // Translate the type parameters instantiation
@@ -255,20 +290,14 @@ impl<'tcx, 'ctx, 'ctx1> BodyTransCtx<'tcx, 'ctx, 'ctx1> {
                 trace!("Array");
 
                 let c = self.translate_constant_expr_to_const_generic(span, const_param)?;
-                let tys = vec![self.translate_ty(span, erase_regions, ty)?];
-                let cgs = vec![c];
-                let id = TypeId::Assumed(AssumedTy::Array);
-                Ok(Ty::Adt(
-                    id,
-                    GenericArgs::new(Vec::new(), tys, cgs, Vec::new()),
-                ))
+                let ty = self.translate_ty(span, erase_regions, ty)?;
+                Ok(Ty::mk_array(ty, c))
             }
             hax::Ty::Slice(ty) => {
                 trace!("Slice");
 
-                let tys = vec![self.translate_ty(span, erase_regions, ty)?];
-                let id = TypeId::Assumed(AssumedTy::Slice);
-                Ok(Ty::Adt(id, GenericArgs::new_from_types(tys)))
+                let ty = self.translate_ty(span, erase_regions, ty)?;
+                Ok(Ty::mk_slice(ty))
             }
             hax::Ty::Ref(region, ty, mutability) => {
                 trace!("Ref");
@@ -344,9 +373,36 @@ impl<'tcx, 'ctx, 'ctx1> BodyTransCtx<'tcx, 'ctx, 'ctx1> {
                 error_or_panic!(self, span, "Unsupported type: infer type")
             }
 
-            hax::Ty::Dynamic(_, _, _) => {
+            hax::Ty::Dynamic(preds, region, _) => {
                 trace!("Dynamic");
-                error_or_panic!(self, span, "Dynamic types are not supported yet")
+
+                // We only keep the principal trait: we ignore the auto traits
+                // and the constraints over the associated types.
+                let trait_ref = preds.iter().find_map(|pred| match &pred.value {
+                    hax::ExistentialPredicate::Trait(trait_ref) => Some(trait_ref),
+                    _ => None,
+                });
+                let Some(trait_ref) = trait_ref else {
+                    error_or_panic!(self, span, "Unsupported dynamic type: no principal trait")
+                };
+                let Some(trait_id) =
+                    self.translate_trait_decl_id(span, trait_ref.def_id.rust_def_id.unwrap())
+                else {
+                    error_or_panic!(
+                        self,
+                        span,
+                        "Unsupported dynamic type: the principal trait is a marker trait"
+                    )
+                };
+                let generics = self.translate_substs_and_trait_refs(
+                    span,
+                    erase_regions,
+                    None,
+                    &trait_ref.generic_args,
+                    &Vec::new(),
+                )?;
+                let region = self.translate_region(span, erase_regions, region)?;
+                Ok(Ty::DynTrait(TraitDeclRef { trait_id, generics }, region))
             }
 
             hax::Ty::Generator(_, _, _) => {
@@ -530,7 +586,7 @@ impl<'tcx, 'ctx, 'ctx1> BodyTransCtx<'tcx, 'ctx, 'ctx1> {
         let is_transparent = is_local
             || match &adt.adt_kind {
                 hax::AdtKind::Enum => true,
-                hax::AdtKind::Struct => {
+                hax::AdtKind::Struct | hax::AdtKind::Union => {
                     // Check the unique variant
                     error_assert!(self, def_span, adt.variants.raw.len() == 1);
                     adt.variants.raw[0]
@@ -538,15 +594,28 @@ impl<'tcx, 'ctx, 'ctx1> BodyTransCtx<'tcx, 'ctx, 'ctx1> {
                         .iter()
                         .all(|f| matches!(f.vis, hax::Visibility::Public))
                 }
-                hax::AdtKind::Union => {
-                    error_or_panic!(self, def_span, "Unions are not supported")
-                }
             };
 
         if !is_transparent {
             return Ok(TypeDeclKind::Opaque);
         }
 
+        // Compute the discriminants of the variants. Note that the
+        // discriminants are stored as `u128` by rustc: we need to reinterpret
+        // the bits to get the proper value (see [ScalarValue::from_le_bytes]).
+        let rust_adt = self.t_ctx.tcx.adt_def(adt.did.rust_def_id.unwrap());
+        let discr_ty = translate_integer_type(rust_adt.repr().discr_type());
+        let discriminants: Vec<ScalarValue> = if rust_adt.is_enum() {
+            rust_adt
+                .discriminants(self.t_ctx.tcx)
+                .map(|(_, discr)| ScalarValue::from_le_bytes(discr_ty, discr.val.to_le_bytes()))
+                .collect()
+        } else {
+            // Structures and unions have a unique variant, whose discriminant
+            // is irrelevant (we only keep its fields)
+            vec![ScalarValue::from_le_bytes(discr_ty, 0u128.to_le_bytes())]
+        };
+
         // The type is transparent: explore the variants
         let mut var_id = VariantId::Id::new(0); // Variant index
         let mut variants: Vec<Variant> = vec![];
@@ -601,6 +670,7 @@ impl<'tcx, 'ctx, 'ctx1> BodyTransCtx<'tcx, 'ctx, 'ctx1> {
                 meta,
                 name: variant_name,
                 fields: FieldId::Vector::from(fields),
+                discriminant: discriminants[var_id.to_usize()],
             });
 
             var_id.incr();
@@ -611,15 +681,179 @@ impl<'tcx, 'ctx, 'ctx1> BodyTransCtx<'tcx, 'ctx, 'ctx1> {
         let type_def_kind: TypeDeclKind = match adt.adt_kind {
             AdtKind::Struct => TypeDeclKind::Struct(variants[0].fields.clone()),
             AdtKind::Enum => TypeDeclKind::Enum(VariantId::Vector::from(variants)),
-            AdtKind::Union => {
-                let span = self.t_ctx.tcx.def_span(adt.did.rust_def_id.unwrap());
-                error_or_panic!(self, span, "Union types are not supported")
-            }
+            AdtKind::Union => TypeDeclKind::Union(variants[0].fields.clone()),
         };
 
         Ok(type_def_kind)
     }
 
+    /// Compute the drop glue of a type declaration (see [DropGlue]).
+    ///
+    /// Note that this registers the implementation of [core::ops::Drop] for
+    /// this type, if there is one, so that its `drop` method gets translated.
+    fn translate_drop_glue(&mut self, rust_id: DefId, kind: &TypeDeclKind) -> DropGlue {
+        let tcx = self.t_ctx.tcx;
+        let span = tcx.def_span(rust_id);
+        let param_env = tcx.param_env(rust_id);
+
+        // The destructor is the `drop` method of the implementation of `Drop`
+        let drop_impl = tcx
+            .adt_destructor(rust_id)
+            .and_then(|destructor| self.translate_trait_impl_id(span, tcx.parent(destructor.did)));
+
+        let needs_drop = tcx
+            .type_of(rust_id)
+            .subst_identity()
+            .needs_drop(tcx, param_env);
+
+        let fields_drop_order = match kind {
+            TypeDeclKind::Struct(_) | TypeDeclKind::Enum(_) | TypeDeclKind::Union(_) => tcx
+                .adt_def(rust_id)
+                .variants()
+                .iter()
+                .map(|variant| {
+                    variant
+                        .fields
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, field)| {
+                            tcx.type_of(field.did)
+                                .subst_identity()
+                                .needs_drop(tcx, param_env)
+                        })
+                        .map(|(i, _)| FieldId::Id::new(i))
+                        .collect()
+                })
+                .collect(),
+            TypeDeclKind::Opaque | TypeDeclKind::Error(_) => Vec::new(),
+        };
+
+        DropGlue {
+            drop_impl,
+            needs_drop,
+            fields_drop_order,
+        }
+    }
+
+    /// Compute the variances of the generic parameters of a type declaration
+    /// (see [Variances]).
+    fn translate_variances(&self, rust_id: DefId) -> Variances {
+        use rustc_middle::ty::subst::GenericArgKind;
+        let tcx = self.t_ctx.tcx;
+        let substs = rustc_middle::ty::subst::InternalSubsts::identity_for_item(tcx, rust_id);
+        // The variances are given in the order of the generic parameters, which
+        // is the order of the identity substitution
+        let mut variances = Variances::default();
+        for (arg, variance) in substs.iter().zip(tcx.variances_of(rust_id).iter()) {
+            let variance = match variance {
+                rustc_middle::ty::Variance::Covariant => Variance::Covariant,
+                rustc_middle::ty::Variance::Invariant => Variance::Invariant,
+                rustc_middle::ty::Variance::Contravariant => Variance::Contravariant,
+                rustc_middle::ty::Variance::Bivariant => Variance::Bivariant,
+            };
+            match arg.unpack() {
+                GenericArgKind::Lifetime(_) => variances.regions.push(variance),
+                GenericArgKind::Type(_) => variances.types.push(variance),
+                GenericArgKind::Const(_) => (),
+            }
+        }
+        variances
+    }
+
+    /// Compute the layout of a type declaration (see [Layout]).
+    ///
+    /// Returns `None` if rustc can't compute the layout, for instance because it
+    /// depends on the type parameters.
+    fn translate_layout(&self, rust_id: DefId) -> Option<Layout> {
+        use rustc_abi::{IntegerType, Primitive, TagEncoding, Variants};
+        let tcx = self.t_ctx.tcx;
+        let param_env = tcx.param_env(rust_id);
+        let ty = tcx.type_of(rust_id).subst_identity();
+        let layout = tcx.layout_of(param_env.and(ty)).ok()?;
+
+        let discriminant = match &layout.variants {
+            Variants::Single { .. } => None,
+            Variants::Multiple {
+                tag,
+                tag_encoding,
+                tag_field,
+                ..
+            } => {
+                let ty = match tag.primitive() {
+                    Primitive::Int(int, signed) => {
+                        translate_integer_type(IntegerType::Fixed(int, signed))
+                    }
+                    Primitive::Pointer(_) => IntegerTy::Usize,
+                    // Tags are always integers or pointers
+                    Primitive::F32 | Primitive::F64 => unreachable!(),
+                };
+                let tag = Tag {
+                    offset: layout.fields.offset(*tag_field).bytes(),
+                    ty,
+                };
+                match tag_encoding {
+                    TagEncoding::Direct => Some(DiscriminantEncoding::Direct(tag)),
+                    TagEncoding::Niche {
+                        untagged_variant,
+                        niche_variants,
+                        niche_start,
+                    } => Some(DiscriminantEncoding::Niche {
+                        tag,
+                        untagged_variant: VariantId::Id::new(untagged_variant.as_usize()),
+                        niche_variants: (
+                            VariantId::Id::new(niche_variants.start().as_usize()),
+                            VariantId::Id::new(niche_variants.end().as_usize()),
+                        ),
+                        niche_start: *niche_start,
+                    }),
+                }
+            }
+        };
+
+        Some(Layout {
+            size: layout.size.bytes(),
+            align: layout.align.abi.bytes(),
+            discriminant,
+        })
+    }
+
+    /// Compute the auto traits that rustc implements for a type declaration,
+    /// without an explicit (positive or negative) implementation.
+    ///
+    /// Note that this registers the auto traits, so that they get translated.
+    fn translate_auto_trait_impls(&mut self, rust_id: DefId) -> Vec<TraitDeclId::Id> {
+        use rustc_infer::infer::TyCtxtInferExt;
+        use rustc_trait_selection::infer::InferCtxtExt;
+        let tcx = self.t_ctx.tcx;
+        let span = tcx.def_span(rust_id);
+        let param_env = tcx.param_env(rust_id);
+        let ty = tcx.type_of(rust_id).subst_identity();
+        let infcx = tcx.infer_ctxt().build();
+
+        let has_explicit_impl = |trait_id: DefId| {
+            tcx.all_impls(trait_id).any(|impl_id| {
+                tcx.type_of(impl_id)
+                    .subst_identity()
+                    .ty_adt_def()
+                    .map_or(false, |adt| adt.did() == rust_id)
+            })
+        };
+        let auto_traits: Vec<DefId> = tcx
+            .all_traits()
+            .filter(|trait_id| tcx.trait_is_auto(*trait_id) && !has_explicit_impl(*trait_id))
+            .filter(|trait_id| {
+                infcx
+                    .type_implements_trait(*trait_id, [ty], param_env)
+                    .must_apply_modulo_regions()
+            })
+            .collect();
+
+        auto_traits
+            .into_iter()
+            .filter_map(|trait_id| self.translate_trait_decl_id(span, trait_id))
+            .collect()
+    }
+
     /// Sanity check: region names are pairwise distinct (this caused trouble
     /// when generating names for the backward functions in Aeneas): at some
     /// point, Rustc introduced names equal to `Some("'_")` for the anonymous
@@ -685,10 +919,9 @@ impl<'tcx, 'ctx, 'ctx1> BodyTransCtx<'tcx, 'ctx, 'ctx1> {
                     let _ = self.push_free_region(region.clone(), name);
                 }
                 Const(c) => {
-                    // The type should be primitive, meaning it shouldn't contain variables,
-                    // non-primitive adts, etc. As a result, we can use an empty context.
+                    // The type should be primitive, or (with `#![feature(adt_const_params)]`)
+                    // an ADT: it shouldn't contain variables.
                     let ty = self.translate_ty(span, erase_regions, &c.ty)?;
-                    let ty = ty.to_literal();
                     if let hax::ConstantExprKind::ConstRef { id: cp } = &*c.contents {
                         self.push_const_generic_var(cp.index, ty, cp.name.clone());
                     } else {
@@ -761,18 +994,51 @@ impl<'tcx, 'ctx> TransCtx<'tcx, 'ctx> {
             .t_ctx
             .extended_def_id_to_name(&rust_id.sinto(&bt_ctx.hax_state));
         let generics = bt_ctx.get_generics();
+        let variances = bt_ctx.translate_variances(rust_id);
 
         // Translate the span information
         let meta = bt_ctx.translate_meta_from_rid(rust_id);
+        if let TypeDeclKind::Error(msg) = &kind {
+            bt_ctx.t_ctx.register_opaque_warning(&name, meta.span, msg);
+        }
+
+        // Translate the representation options. Note that we do so even if
+        // the type is opaque, because they are relevant for FFI types.
+        let repr = translate_repr_options(&bt_ctx.t_ctx.tcx.adt_def(rust_id).repr());
+
+        // Compute the drop glue. As for the representation options, we do so
+        // even if the type is opaque.
+        let drop_glue = bt_ctx.translate_drop_glue(rust_id, &kind);
+
+        // Compute the layout, if the user asked for it
+        let layout = if bt_ctx.t_ctx.extract_layouts {
+            bt_ctx.translate_layout(rust_id)
+        } else {
+            None
+        };
+
+        // Compute the auto trait implementations, if the user asked to keep
+        // the marker traits
+        let auto_trait_impls = if bt_ctx.t_ctx.keep_marker_traits {
+            bt_ctx.translate_auto_trait_impls(rust_id)
+        } else {
+            Vec::new()
+        };
 
         let type_def = TypeDecl {
             def_id: trans_id,
             meta,
+            attr_info: bt_ctx.translate_attr_info_from_rid(rust_id),
             is_local,
             name,
             generics,
+            variances,
             preds: bt_ctx.get_predicates(),
             kind,
+            repr,
+            drop_glue,
+            layout,
+            auto_trait_impls,
         };
 
         trace!("translate_type: preds: {:?}", &type_def.preds);
@@ -0,0 +1,646 @@
+//! # Micro-pass (optional): type-check the bodies of the translated functions
+//! and globals against the translated declarations, and report the
+//! inconsistencies as errors (see [crate::cli_options::CliOpts::check]).
+//!
+//! We check that:
+//! - the projections of the places apply to the types of the places they
+//!   project (dereferences, field accesses, indexing)
+//! - the types of the assigned values match the types of the places they are
+//!   assigned to
+//! - the aggregates have the expected number of operands, of the expected
+//!   types
+//! - the calls have the expected number of arguments, of the expected types,
+//!   and that the types of the destinations match the outputs of the callees
+//! - the types of the input and return variables match the signatures
+//!
+//! Those inconsistencies can only come from bugs in the translation or in the
+//! micro-passes: we check the ULLBC after the ULLBC micro-passes, and the LLBC
+//! after the LLBC micro-passes.
+//!
+//! Remarks:
+//! - we compare the types up to the regions (the bodies only contain erased
+//!   regions)
+//! - we don't compare the types which contain associated types, function
+//!   pointer types or the never type, nor the types which contain constants
+//!   other than literals and variables: checking those would require
+//!   normalizing or unifying the types (for instance, the MIR may use
+//!   `<T as Trait>::Ty` where the signature uses `T::Ty`'s normalized form)
+//! - we don't type-check the calls to the assumed functions, nor the virtual
+//!   calls
+use crate::expressions::*;
+use crate::formatter::{AstFormatter, IntoFormatter, SetGenerics, SetLocals};
+use crate::gast::{Call, FnOperand, GExprBody, Var};
+use crate::id_vector::ToUsize;
+use crate::llbc_ast;
+use crate::meta::Meta;
+use crate::names::Name;
+use crate::translate_ctx::TransCtx;
+use crate::types::*;
+use crate::ullbc_ast;
+use crate::values::*;
+
+/// An inconsistency found by the type checker.
+#[derive(Debug, Clone)]
+pub enum TypeError {
+    /// A projection of the place doesn't apply to the type of the value it
+    /// projects (we give this type).
+    Projection { place: Place, ty: Ty },
+    /// The type of a value doesn't match the type we expected
+    Mismatch { expected: Ty, found: Ty },
+    /// An aggregate or a call doesn't have the expected number of operands
+    Arity {
+        kind: &'static str,
+        expected: usize,
+        found: usize,
+    },
+    /// An aggregate refers to a variant which doesn't exist
+    UnknownVariant {
+        type_id: TypeDeclId::Id,
+        variant_id: Option<VariantId::Id>,
+    },
+}
+
+impl TypeError {
+    pub fn fmt_with_ctx<C>(&self, ctx: &C) -> String
+    where
+        C: AstFormatter,
+    {
+        match self {
+            TypeError::Projection { place, ty } => format!(
+                "ill-typed place {}: invalid projection of a value of type {}",
+                place.fmt_with_ctx(ctx),
+                ty.fmt_with_ctx(ctx)
+            ),
+            TypeError::Mismatch { expected, found } => format!(
+                "mismatched types: expected {}, found {}",
+                expected.fmt_with_ctx(ctx),
+                found.fmt_with_ctx(ctx)
+            ),
+            TypeError::Arity {
+                kind,
+                expected,
+                found,
+            } => format!("expected {expected} {kind}, found {found}"),
+            TypeError::UnknownVariant {
+                type_id,
+                variant_id,
+            } => match variant_id {
+                Some(variant_id) => format!(
+                    "the type {} has no variant {variant_id}",
+                    ctx.format_object(*type_id)
+                ),
+                None => format!(
+                    "the type {} is not a structure",
+                    ctx.format_object(*type_id)
+                ),
+            },
+        }
+    }
+}
+
+/// Check if we can compare a type with another type, without normalizing or
+/// unifying them (see the remarks at the top of the file).
+struct IsComparable {
+    comparable: bool,
+}
+
+impl SharedTypeVisitor for IsComparable {
+    fn visit_ty(&mut self, ty: &Ty) {
+        match ty {
+            Ty::TraitType(..) | Ty::Arrow(..) | Ty::Never => self.comparable = false,
+            _ => self.default_visit_ty(ty),
+        }
+    }
+
+    fn visit_const_generic(&mut self, cg: &ConstGeneric) {
+        match cg {
+            ConstGeneric::Var(_) | ConstGeneric::Value(_) => (),
+            _ => self.comparable = false,
+        }
+    }
+}
+
+fn is_comparable(ty: &Ty) -> bool {
+    let mut visitor = IsComparable { comparable: true };
+    visitor.visit_ty(ty);
+    visitor.comparable
+}
+
+fn mk_usize() -> Ty {
+    Ty::Literal(LiteralTy::Integer(IntegerTy::Usize))
+}
+
+fn mk_bool() -> Ty {
+    Ty::Literal(LiteralTy::Bool)
+}
+
+/// Check if the generic arguments have the expected number of arguments of
+/// every kind (we can't instantiate the declaration otherwise). The
+/// mismatches are reported by [crate::check_generic_args].
+fn args_match_params(params: &GenericParams, args: &GenericArgs) -> bool {
+    params.types.len() == args.types.len()
+        && params.const_generics.len() == args.const_generics.len()
+        && params.trait_clauses.len() == args.trait_refs.len()
+}
+
+/// Instantiate a type which appears in a declaration with the generic
+/// arguments given to this declaration. We erase the regions beforehand: we
+/// compare the types up to the regions, and the late-bound regions are not
+/// instantiated by the generic arguments.
+fn instantiate(ty: &Ty, args: &GenericArgs) -> Ty {
+    ty.clone().erase_regions().substitute(args)
+}
+
+/// Type-check the body of a declaration.
+struct BodyChecker<'a, 'tcx, 'ctx> {
+    ctx: &'a TransCtx<'tcx, 'ctx>,
+    locals: &'a VarId::Vector<Var>,
+    /// The meta information of the statement we are exploring
+    meta: Meta,
+    errors: Vec<(Meta, TypeError)>,
+}
+
+impl<'a, 'tcx, 'ctx> BodyChecker<'a, 'tcx, 'ctx> {
+    fn error(&mut self, err: TypeError) {
+        self.errors.push((self.meta, err));
+    }
+
+    /// Check that a value has the expected type, if we know its type.
+    fn check_ty(&mut self, expected: &Ty, found: &Option<Ty>) {
+        let Some(found) = found else { return };
+        if !is_comparable(expected) || !is_comparable(found) {
+            return;
+        }
+        let expected = expected.clone().erase_regions();
+        let found = found.clone().erase_regions();
+        if expected != found {
+            self.error(TypeError::Mismatch { expected, found });
+        }
+    }
+
+    fn check_arity(&mut self, kind: &'static str, expected: usize, found: usize) {
+        if expected != found {
+            self.error(TypeError::Arity {
+                kind,
+                expected,
+                found,
+            });
+        }
+    }
+
+    /// Lookup the fields of a structure or of a variant of an enumeration.
+    /// Return `Ok(None)` if the type is opaque.
+    fn lookup_fields(
+        &self,
+        id: TypeDeclId::Id,
+        variant_id: Option<VariantId::Id>,
+    ) -> Result<Option<(&'a GenericParams, &'a FieldId::Vector<Field>)>, ()> {
+        // The declaration may be missing if its translation failed: the
+        // error was already reported
+        let Some(decl) = self.ctx.type_decls.get(id) else {
+            return Ok(None);
+        };
+        match (&decl.kind, variant_id) {
+            (TypeDeclKind::Struct(fields) | TypeDeclKind::Union(fields), None) => {
+                Ok(Some((&decl.generics, fields)))
+            }
+            (TypeDeclKind::Enum(variants), Some(variant_id)) => match variants.get(variant_id) {
+                Some(variant) => Ok(Some((&decl.generics, &variant.fields))),
+                None => Err(()),
+            },
+            (TypeDeclKind::Opaque | TypeDeclKind::Error(_), _) => Ok(None),
+            _ => Err(()),
+        }
+    }
+
+    /// Compute the type of the value we get by applying a projection element
+    /// to a value of type `ty`. Return `Ok(None)` if we can't compute it, and
+    /// `Err(())` if the projection doesn't apply to the type.
+    fn project(&self, ty: &Ty, elem: &ProjectionElem) -> Result<Option<Ty>, ()> {
+        let projected = match (elem, ty) {
+            (ProjectionElem::Deref, Ty::Ref(_, ty, _)) => Some(*ty.clone()),
+            (ProjectionElem::DerefBox, _) if ty.is_box() => ty.as_box().cloned(),
+            (ProjectionElem::DerefRawPtr, Ty::RawPtr(ty, _)) => Some(*ty.clone()),
+            (
+                ProjectionElem::Field(FieldProjKind::Adt(id, variant_id), field_id),
+                Ty::Adt(TypeId::Adt(ty_id), args),
+            ) if id == ty_id => match self.lookup_fields(*id, *variant_id)? {
+                Some((params, fields)) => {
+                    let field = fields.get(*field_id).ok_or(())?;
+                    if args_match_params(params, args) {
+                        Some(instantiate(&field.ty, args))
+                    } else {
+                        None
+                    }
+                }
+                None => None,
+            },
+            (
+                ProjectionElem::Field(FieldProjKind::Tuple(arity), field_id),
+                Ty::Adt(TypeId::Tuple, args),
+            ) if *arity == args.types.len() => {
+                Some(args.types.get(field_id.to_usize()).ok_or(())?.clone())
+            }
+            (ProjectionElem::Field(FieldProjKind::ClosureState, _), _) => None,
+            (ProjectionElem::Index(..), _) if ty.as_array_or_slice().is_some() => {
+                ty.as_array_or_slice().cloned()
+            }
+            // We can't conclude on the types we can't compare
+            _ if !is_comparable(ty) => None,
+            _ => return Err(()),
+        };
+        Ok(projected)
+    }
+
+    fn place_ty(&mut self, p: &Place) -> Option<Ty> {
+        let mut ty = self.locals.get(p.var_id)?.ty.clone();
+        for elem in &p.projection {
+            if let ProjectionElem::Index(var_id, _) = elem {
+                let index_ty = self.locals.get(*var_id).map(|v| v.ty.clone());
+                self.check_ty(&mk_usize(), &index_ty);
+            }
+            match self.project(&ty, elem) {
+                Ok(Some(projected)) => ty = projected,
+                Ok(None) => return None,
+                Err(()) => {
+                    self.error(TypeError::Projection {
+                        place: p.clone(),
+                        ty,
+                    });
+                    return None;
+                }
+            }
+        }
+        Some(ty)
+    }
+
+    fn operand_ty(&mut self, op: &Operand) -> Option<Ty> {
+        match op {
+            Operand::Copy(p) | Operand::Move(p) => self.place_ty(p),
+            Operand::Const(cv) => Some(cv.ty.clone()),
+        }
+    }
+
+    fn cast_ty(&mut self, kind: &CastKind, op: &Operand) -> Option<Ty> {
+        let op_ty = self.operand_ty(op);
+        let (src, tgt) = match kind {
+            CastKind::Scalar(src, tgt) => (Ty::Literal(*src), Ty::Literal(*tgt)),
+            CastKind::ReifyFnPointer(src, tgt)
+            | CastKind::ClosureFnPointer(src, tgt)
+            | CastKind::Unsize(src, tgt, _)
+            | CastKind::PtrToPtr(src, tgt)
+            | CastKind::PtrToInt(src, tgt)
+            | CastKind::IntToPtr(src, tgt)
+            | CastKind::Transmute(src, tgt) => (src.clone(), tgt.clone()),
+        };
+        self.check_ty(&src, &op_ty);
+        Some(tgt)
+    }
+
+    fn aggregate_ty(&mut self, kind: &AggregateKind, ops: &[Operand]) -> Option<Ty> {
+        let ops_tys: Vec<Option<Ty>> = ops.iter().map(|op| self.operand_ty(op)).collect();
+        match kind {
+            AggregateKind::Adt(TypeId::Adt(id), variant_id, args) => {
+                match self.lookup_fields(*id, *variant_id) {
+                    Ok(Some((params, fields))) => {
+                        self.check_arity("fields", fields.len(), ops.len());
+                        if args_match_params(params, args) {
+                            for (field, op_ty) in fields.iter().zip(&ops_tys) {
+                                self.check_ty(&instantiate(&field.ty, args), op_ty);
+                            }
+                        }
+                    }
+                    Ok(None) => (),
+                    Err(()) => self.error(TypeError::UnknownVariant {
+                        type_id: *id,
+                        variant_id: *variant_id,
+                    }),
+                }
+                Some(Ty::Adt(TypeId::Adt(*id), args.clone()))
+            }
+            AggregateKind::Adt(TypeId::Tuple, _, args) => {
+                self.check_arity("fields", args.types.len(), ops.len());
+                for (ty, op_ty) in args.types.iter().zip(&ops_tys) {
+                    self.check_ty(ty, op_ty);
+                }
+                Some(Ty::Adt(TypeId::Tuple, args.clone()))
+            }
+            AggregateKind::Adt(id @ TypeId::Assumed(_), _, args) => {
+                Some(Ty::Adt(id.clone(), args.clone()))
+            }
+            AggregateKind::Array(ty, len) => {
+                if let ConstGeneric::Value(Literal::Scalar(len)) = len
+                    && let Ok(len) = len.as_uint()
+                {
+                    self.check_arity("elements", len as usize, ops.len());
+                }
+                for op_ty in &ops_tys {
+                    self.check_ty(ty, op_ty);
+                }
+                Some(Ty::mk_array(ty.clone(), len.clone()))
+            }
+            AggregateKind::Union(id, _, args) => {
+                self.check_arity("fields", 1, ops.len());
+                Some(Ty::Adt(TypeId::Adt(*id), args.clone()))
+            }
+            AggregateKind::Closure(..) | AggregateKind::Generator(..) => None,
+        }
+    }
+
+    fn rvalue_ty(&mut self, rv: &Rvalue) -> Option<Ty> {
+        match rv {
+            Rvalue::Use(op) => self.operand_ty(op),
+            Rvalue::Ref(p, kind) => {
+                let kind = match kind {
+                    BorrowKind::Shared | BorrowKind::Shallow => RefKind::Shared,
+                    BorrowKind::Mut | BorrowKind::TwoPhaseMut => RefKind::Mut,
+                };
+                let ty = self.place_ty(p)?;
+                Some(Ty::Ref(Region::Erased, Box::new(ty), kind))
+            }
+            Rvalue::AddressOf(p, kind) => {
+                let ty = self.place_ty(p)?;
+                Some(Ty::RawPtr(Box::new(ty), *kind))
+            }
+            Rvalue::UnaryOp(UnOp::Not | UnOp::Neg, op) => self.operand_ty(op),
+            Rvalue::UnaryOp(UnOp::Cast(kind), op) => self.cast_ty(kind, op),
+            Rvalue::UnaryOp(UnOp::ArrayToSlice(kind, ty, len), op) => {
+                let op_ty = self.operand_ty(op);
+                let array_ty = Ty::mk_array(ty.clone(), len.clone());
+                self.check_ty(&Ty::Ref(Region::Erased, Box::new(array_ty), *kind), &op_ty);
+                let slice_ty = Ty::mk_slice(ty.clone());
+                Some(Ty::Ref(Region::Erased, Box::new(slice_ty), *kind))
+            }
+            Rvalue::BinaryOp(binop, op1, op2) => {
+                let ty1 = self.operand_ty(op1);
+                let _ = self.operand_ty(op2);
+                use BinOp::*;
+                match binop {
+                    Eq | Lt | Le | Ne | Ge | Gt | And | Or => Some(mk_bool()),
+                    Offset => ty1,
+                    // The checked operations return a pair: we don't look
+                    // at those
+                    BitXor | BitAnd | BitOr | Div | Rem | Add | Sub | Mul | Shl | Shr => None,
+                }
+            }
+            Rvalue::Nullary(..) => Some(mk_usize()),
+            Rvalue::Discriminant(p, _) => {
+                let _ = self.place_ty(p);
+                None
+            }
+            Rvalue::Aggregate(kind, ops) => self.aggregate_ty(kind, ops),
+            Rvalue::Global(id) => self.ctx.global_decls.get(*id).map(|decl| decl.ty.clone()),
+            Rvalue::Len(p, ..) => {
+                let _ = self.place_ty(p);
+                Some(mk_usize())
+            }
+            Rvalue::Repeat(op, ty, len) => {
+                let op_ty = self.operand_ty(op);
+                self.check_ty(ty, &op_ty);
+                Some(Ty::mk_array(ty.clone(), len.clone()))
+            }
+            Rvalue::ShallowInitBox(op, ty) => {
+                let _ = self.operand_ty(op);
+                Some(Ty::Adt(
+                    TypeId::Assumed(AssumedTy::Box),
+                    GenericArgs::new_from_types(vec![ty.clone()]),
+                ))
+            }
+        }
+    }
+
+    fn check_assign(&mut self, p: &Place, rv: &Rvalue) {
+        let rv_ty = self.rvalue_ty(rv);
+        if let Some(p_ty) = self.place_ty(p) {
+            self.check_ty(&p_ty, &rv_ty);
+        }
+    }
+
+    /// Compute the inputs and the output of a function, instantiated with the
+    /// given generic arguments, and whether the function is variadic.
+    fn instantiate_sig(
+        &self,
+        id: FunDeclId::Id,
+        args: &GenericArgs,
+    ) -> Option<(Vec<Ty>, Ty, bool)> {
+        let sig = &self.ctx.fun_decls.get(id)?.signature;
+        if !args_match_params(&sig.generics, args) {
+            return None;
+        }
+        let inputs = sig.inputs.iter().map(|ty| instantiate(ty, args)).collect();
+        Some((inputs, instantiate(&sig.output, args), sig.is_variadic))
+    }
+
+    fn check_call(&mut self, call: &Call) {
+        let args_tys: Vec<Option<Ty>> = call.args.iter().map(|op| self.operand_ty(op)).collect();
+        let dest_ty = self.place_ty(&call.dest);
+
+        let sig = match &call.func {
+            FnOperand::Regular(fn_ptr) => match &fn_ptr.func {
+                FunIdOrTraitMethodRef::Fun(FunId::Regular(id)) => {
+                    self.instantiate_sig(*id, &fn_ptr.generics)
+                }
+                // The signature of the method declaration is parameterized by
+                // the parameters of the trait and of the method
+                FunIdOrTraitMethodRef::Trait(_, _, id) => fn_ptr
+                    .trait_and_method_generic_args
+                    .as_ref()
+                    .and_then(|args| self.instantiate_sig(*id, args)),
+                FunIdOrTraitMethodRef::Fun(FunId::Assumed(_)) => None,
+            },
+            FnOperand::Move(p) => match self.place_ty(p) {
+                Some(Ty::Arrow(_, inputs, output)) => {
+                    let inputs = inputs.into_iter().map(|ty| ty.erase_regions()).collect();
+                    Some((inputs, (*output).erase_regions(), false))
+                }
+                _ => None,
+            },
+            FnOperand::Virtual(..) => None,
+        };
+
+        let Some((inputs, output, is_variadic)) = sig else {
+            return;
+        };
+        if !(is_variadic && args_tys.len() >= inputs.len()) {
+            self.check_arity("arguments", inputs.len(), args_tys.len());
+        }
+        for (input, arg_ty) in inputs.iter().zip(&args_tys) {
+            self.check_ty(input, arg_ty);
+        }
+        if let Some(dest_ty) = dest_ty {
+            self.check_ty(&dest_ty, &Some(output));
+        }
+    }
+
+    /// Check the types of the input and return variables.
+    fn check_locals(&mut self, inputs: &[Ty], output: &Ty) {
+        let output_ty = self.locals.get(VarId::ZERO).map(|v| v.ty.clone());
+        self.check_ty(output, &output_ty);
+        for (i, input) in inputs.iter().enumerate() {
+            let input_ty = self.locals.get(VarId::Id::new(i + 1)).map(|v| v.ty.clone());
+            self.check_ty(input, &input_ty);
+        }
+    }
+}
+
+impl<'a, 'tcx, 'ctx> SharedTypeVisitor for BodyChecker<'a, 'tcx, 'ctx> {}
+
+/// We don't dive into the places, operands, etc.: we compute their types,
+/// which checks them as a side effect.
+impl<'a, 'tcx, 'ctx> SharedExprVisitor for BodyChecker<'a, 'tcx, 'ctx> {
+    fn visit_place(&mut self, p: &Place) {
+        let _ = self.place_ty(p);
+    }
+
+    fn visit_operand(&mut self, op: &Operand) {
+        let _ = self.operand_ty(op);
+    }
+
+    fn visit_rvalue(&mut self, rv: &Rvalue) {
+        let _ = self.rvalue_ty(rv);
+    }
+
+    fn visit_call(&mut self, call: &Call) {
+        self.check_call(call)
+    }
+}
+
+impl<'a, 'tcx, 'ctx> ullbc_ast::SharedAstVisitor for BodyChecker<'a, 'tcx, 'ctx> {
+    fn visit_meta(&mut self, meta: &Meta) {
+        self.meta = *meta;
+    }
+
+    fn visit_assign(&mut self, p: &Place, rv: &Rvalue) {
+        self.check_assign(p, rv)
+    }
+}
+
+impl<'a, 'tcx, 'ctx> llbc_ast::SharedAstVisitor for BodyChecker<'a, 'tcx, 'ctx> {
+    fn spawn(&mut self, visitor: &mut dyn FnMut(&mut Self)) {
+        visitor(self)
+    }
+
+    fn merge(&mut self) {}
+
+    fn visit_meta(&mut self, meta: &Meta) {
+        self.meta = *meta;
+    }
+
+    fn visit_assign(&mut self, p: &Place, rv: &Rvalue) {
+        self.check_assign(p, rv)
+    }
+}
+
+/// Type-check a body, and return the error messages. `sig` gives the types of
+/// the input and of the return variables, and `visit` gives the way of
+/// exploring the statements of the body.
+fn check_body<T>(
+    ctx: &TransCtx,
+    name: &Name,
+    generics: &GenericParams,
+    sig: Option<(&[Ty], &Ty)>,
+    body: &GExprBody<T>,
+    visit: impl FnOnce(&mut BodyChecker, &T),
+) -> Vec<(Meta, String)> {
+    let mut checker = BodyChecker {
+        ctx,
+        locals: &body.locals,
+        meta: body.meta,
+        errors: Vec::new(),
+    };
+    if let Some((inputs, output)) = sig {
+        checker.check_locals(inputs, output);
+    }
+    visit(&mut checker, &body.body);
+
+    let fmt_ctx = ctx.into_fmt();
+    let generics_ctx = fmt_ctx.set_generics(generics);
+    let body_ctx = generics_ctx.set_locals(&body.locals);
+    checker
+        .errors
+        .into_iter()
+        .map(|(meta, err)| {
+            let msg = format!(
+                "Type error in {}: {}",
+                name.fmt_with_ctx(&body_ctx),
+                err.fmt_with_ctx(&body_ctx)
+            );
+            (meta, msg)
+        })
+        .collect()
+}
+
+fn report(ctx: &mut TransCtx, errors: Vec<(Meta, String)>) {
+    for (meta, msg) in errors {
+        ctx.span_err(meta.span.rust_span, &msg);
+    }
+}
+
+/// Type-check the ULLBC bodies.
+pub fn check_ullbc(ctx: &mut TransCtx) {
+    let mut errors = Vec::new();
+    for decl in ctx.fun_decls.iter() {
+        let Some(body) = &decl.body else { continue };
+        let sig = &decl.signature;
+        // The signatures of the closures don't match the bodies yet (see
+        // [crate::update_closure_signatures])
+        let inputs_output = (!sig.is_closure).then_some((&sig.inputs[..], &sig.output));
+        errors.extend(check_body(
+            ctx,
+            &decl.name,
+            &sig.generics,
+            inputs_output,
+            body,
+            |checker, blocks| {
+                for block in blocks.iter() {
+                    ullbc_ast::SharedAstVisitor::visit_block_data(checker, block);
+                }
+            },
+        ));
+    }
+    for decl in ctx.global_decls.iter() {
+        let Some(body) = &decl.body else { continue };
+        errors.extend(check_body(
+            ctx,
+            &decl.name,
+            &GenericParams::empty(),
+            Some((&[], &decl.ty)),
+            body,
+            |checker, blocks| {
+                for block in blocks.iter() {
+                    ullbc_ast::SharedAstVisitor::visit_block_data(checker, block);
+                }
+            },
+        ));
+    }
+    report(ctx, errors);
+}
+
+/// Type-check the LLBC bodies.
+pub fn check_llbc(ctx: &mut TransCtx, funs: &llbc_ast::FunDecls, globals: &llbc_ast::GlobalDecls) {
+    let mut errors = Vec::new();
+    for decl in funs.iter() {
+        let Some(body) = &decl.body else { continue };
+        let sig = &decl.signature;
+        errors.extend(check_body(
+            ctx,
+            &decl.name,
+            &sig.generics,
+            Some((&sig.inputs, &sig.output)),
+            body,
+            |checker, st| llbc_ast::SharedAstVisitor::visit_statement(checker, st),
+        ));
+    }
+    for decl in globals.iter() {
+        let Some(body) = &decl.body else { continue };
+        errors.extend(check_body(
+            ctx,
+            &decl.name,
+            &GenericParams::empty(),
+            Some((&[], &decl.ty)),
+            body,
+            |checker, st| llbc_ast::SharedAstVisitor::visit_statement(checker, st),
+        ));
+    }
+    report(ctx, errors);
+}
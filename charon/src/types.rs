@@ -1,13 +1,13 @@
 pub use crate::gast::{FunDeclId, TraitItemName};
-use crate::meta::Meta;
+use crate::meta::{AttrInfo, Meta};
 use crate::names::Name;
 pub use crate::types_utils::*;
-use crate::values::Literal;
+use crate::values::{Literal, ScalarValue};
 use derivative::Derivative;
 use macros::{
     generate_index_type, EnumAsGetters, EnumIsA, EnumToGetters, VariantIndexArity, VariantName,
 };
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 pub type FieldName = String;
 
@@ -27,16 +27,21 @@ generate_index_type!(GlobalDeclId);
 /// Type variable.
 /// We make sure not to mix variables and type variables by having two distinct
 /// definitions.
-#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct TypeVar {
     /// Unique index identifying the variable
     pub index: TypeVarId::Id,
     /// Variable name
     pub name: String,
+    /// `false` if the variable may be instantiated with a dynamically sized
+    /// type, i.e., if it is declared with `?Sized` (`T` in `fn f<T: ?Sized>(x:
+    /// &T)`). We need to record this explicitly, because we filter the
+    /// [core::marker::Sized] clauses.
+    pub sized: bool,
 }
 
 /// Region variable.
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Hash, PartialOrd, Ord)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Hash, PartialOrd, Ord)]
 pub struct RegionVar {
     /// Unique index identifying the variable
     pub index: RegionId::Id,
@@ -45,24 +50,38 @@ pub struct RegionVar {
 }
 
 /// Const Generic Variable
-#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ConstGenericVar {
     /// Unique index identifying the variable
     pub index: ConstGenericVarId::Id,
     /// Const generic name
     pub name: String,
-    /// Type of the const generic
-    pub ty: LiteralTy,
+    /// Type of the const generic.
+    ///
+    /// This is a literal type, unless the crate uses `#![feature(adt_const_params)]`,
+    /// in which case it can also be an ADT.
+    pub ty: Ty,
 }
 
-#[derive(Debug, PartialEq, Eq, Copy, Clone, Hash, PartialOrd, Ord, Serialize)]
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Hash, PartialOrd, Ord, Serialize, Deserialize)]
 #[serde(transparent)]
 pub struct DeBruijnId {
     pub index: usize,
 }
 
 #[derive(
-    Debug, PartialEq, Eq, Copy, Clone, Hash, PartialOrd, Ord, EnumIsA, EnumAsGetters, Serialize,
+    Debug,
+    PartialEq,
+    Eq,
+    Copy,
+    Clone,
+    Hash,
+    PartialOrd,
+    Ord,
+    EnumIsA,
+    EnumAsGetters,
+    Serialize,
+    Deserialize,
 )]
 pub enum Region {
     /// Static region
@@ -103,7 +122,7 @@ pub enum Region {
 /// definition. Note that every path designated by [TraitInstanceId] refers
 /// to a *trait instance*, which is why the [Clause] variant may seem redundant
 /// with some of the other variants.
-#[derive(Debug, Clone, Serialize, PartialEq, Eq, Hash, Ord, PartialOrd)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Ord, PartialOrd)]
 pub enum TraitInstanceId {
     ///
     /// A specific implementation
@@ -195,7 +214,12 @@ pub enum TraitInstanceId {
     ///   a.map(incr)
     /// }
     /// ```
-    FnPointer(Box<Ty>),
+    ///
+    /// We store the trait which is implemented, the types of the arguments
+    /// (the trait is parameterized by the tuple of those types) and the output
+    /// type. In the example above: `FnPointer(FnMut, [u32], u32)`, which we
+    /// print as `impl FnMut(u32) -> u32`.
+    FnPointer(ClosureKind, Vec<Ty>, Box<Ty>),
     /// Similar to [FnPointer], but where we use a closure.
     ///
     /// It is important to differentiate the cases, because closures have a
@@ -223,7 +247,7 @@ pub enum TraitInstanceId {
 }
 
 /// A reference to a trait
-#[derive(Debug, Clone, Serialize, PartialEq, Eq, Hash, Ord, PartialOrd)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Ord, PartialOrd)]
 pub struct TraitRef {
     pub trait_id: TraitInstanceId,
     pub generics: GenericArgs,
@@ -239,14 +263,14 @@ pub struct TraitRef {
 /// ```
 ///
 /// The substitution is: `[String, bool]`.
-#[derive(Debug, Clone, Serialize, PartialEq, Eq, Hash, Ord, PartialOrd)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Ord, PartialOrd)]
 pub struct TraitDeclRef {
     pub trait_id: TraitDeclId::Id,
     pub generics: GenericArgs,
 }
 
 /// .0 outlives .1
-#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct OutlivesPred<T, U>(pub T, pub U);
 
 pub type RegionOutlives = OutlivesPred<Region, Region>;
@@ -259,7 +283,7 @@ pub type TypeOutlives = OutlivesPred<Ty, Region>;
 /// T : Foo<S = String>
 ///         ^^^^^^^^^^
 /// ```
-#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct TraitTypeConstraint {
     pub trait_ref: TraitRef,
     pub generics: GenericArgs,
@@ -268,7 +292,7 @@ pub struct TraitTypeConstraint {
 }
 
 /// The predicates which apply to a definition
-#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct Predicates {
     /// The first region in the pair outlives the second region
     pub regions_outlive: Vec<RegionOutlives>,
@@ -278,7 +302,7 @@ pub struct Predicates {
     pub trait_type_constraints: Vec<TraitTypeConstraint>,
 }
 
-#[derive(Debug, Clone, Eq, PartialEq, Serialize, Hash, Ord, PartialOrd)]
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize, Hash, Ord, PartialOrd)]
 pub struct GenericArgs {
     pub regions: Vec<Region>,
     pub types: Vec<Ty>,
@@ -294,7 +318,7 @@ pub struct GenericArgs {
 /// be filled. We group in a different place the predicates which are not
 /// trait clauses, because those enforce constraints but do not need to
 /// be filled with witnesses/instances.
-#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct GenericParams {
     pub regions: RegionId::Vector<RegionVar>,
     pub types: TypeVarId::Vector<TypeVar>,
@@ -307,7 +331,30 @@ generate_index_type!(TraitClauseId);
 generate_index_type!(TraitDeclId);
 generate_index_type!(TraitImplId);
 
-#[derive(Debug, Clone, Serialize, Derivative)]
+/// Where a trait clause comes from. This allows the back-ends to cite the
+/// bound written by the user in their error messages (the span of the bound
+/// is given by the [Meta] of the clause).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIsA, Serialize, Deserialize)]
+pub enum TraitClauseOrigin {
+    /// A bound in a `where` clause: `where T: Trait`
+    ///
+    /// Remark: we can't distinguish the inline bounds from the `where` clauses
+    /// for the external definitions: we consider they all come from `where`
+    /// clauses.
+    WhereClause,
+    /// A bound written next to the generic parameter (`<T: Trait>`), or an
+    /// `impl Trait` argument.
+    InlineBound,
+    /// A clause implied by a supertrait (ex.: `T: PartialEq` for `T: Eq`). The
+    /// span is the one of the bound which implies it.
+    Supertrait,
+    /// A clause introduced by the elaboration of the bounds (the bounds on the
+    /// associated types of a trait, the `Self` clause of a trait, etc.). The
+    /// span is the one of the bound which implies it, if there is one.
+    Elaborated,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Derivative)]
 #[derivative(PartialEq)]
 pub struct TraitClause {
     /// We use this id when solving trait constraints, to be able to refer
@@ -316,6 +363,8 @@ pub struct TraitClause {
     pub clause_id: TraitClauseId::Id,
     #[derivative(PartialEq = "ignore")]
     pub meta: Option<Meta>,
+    #[derivative(PartialEq = "ignore")]
+    pub origin: TraitClauseOrigin,
     pub trait_id: TraitDeclId::Id,
     /// Remark: the trait refs list in the [generics] field should be empty.
     pub generics: GenericArgs,
@@ -336,25 +385,156 @@ impl Eq for TraitClause {}
 ///
 /// A type can only be an ADT (structure or enumeration), as type aliases are
 /// inlined in MIR.
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TypeDecl {
     pub def_id: TypeDeclId::Id,
     /// Meta information associated with the type.
     pub meta: Meta,
+    /// The doc comments and the attributes of the declaration.
+    pub attr_info: AttrInfo,
     /// [true] if the type decl is a local type decl, [false] if it comes from
     /// an external crate.
     pub is_local: bool,
     pub name: Name,
     pub generics: GenericParams,
+    /// The variances of the generic parameters, as computed by rustc.
+    pub variances: Variances,
     pub preds: Predicates,
     /// The type kind: enum, struct, or opaque.
     pub kind: TypeDeclKind,
+    /// The representation options (`#[repr(...)]` attributes).
+    pub repr: ReprOptions,
+    /// The information needed to drop the values of this type.
+    pub drop_glue: DropGlue,
+    /// The layout of the type, if the user asked for it (see
+    /// [crate::cli_options::CliOpts::layouts]) and we could compute it.
+    pub layout: Option<Layout>,
+    /// The auto traits (like [core::marker::Send]) that rustc implements for
+    /// this type without an explicit implementation. We only compute them if
+    /// the user asked to keep the marker traits (see
+    /// [crate::cli_options::CliOpts::keep_marker_traits]), and we only list
+    /// the traits which are implemented whatever the type parameters.
+    pub auto_trait_impls: Vec<TraitDeclId::Id>,
+}
+
+/// The variance of a generic parameter of a type.
+///
+/// For instance, `&'a T` is covariant in `'a` and `T`, while `&'a mut T` is
+/// covariant in `'a` but invariant in `T`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Variance {
+    Covariant,
+    Invariant,
+    Contravariant,
+    /// The parameter is not used.
+    Bivariant,
+}
+
+/// The variances of the generic parameters of a type declaration. The const
+/// generics don't have variances.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Variances {
+    /// In the order of [GenericParams::regions]
+    pub regions: Vec<Variance>,
+    /// In the order of [GenericParams::types]
+    pub types: Vec<Variance>,
+}
+
+/// The "drop glue" of a type, which describes what happens when a value of
+/// this type is dropped (this gives its semantics to
+/// [crate::llbc_ast::RawStatement::Drop]).
+///
+/// When dropping a value, we first call the `drop` method of the implementation
+/// of [core::ops::Drop] for its type, if there is one, then drop its fields
+/// (for enumerations: the fields of the active variant).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DropGlue {
+    /// The implementation of [core::ops::Drop] for this type, if there is one.
+    pub drop_impl: Option<TraitImplId::Id>,
+    /// `false` if dropping a value of this type is a no-op. Note that this is
+    /// an over-approximation for the polymorphic types: if a field has a
+    /// generic type, we consider that the field needs to be dropped.
+    pub needs_drop: bool,
+    /// For every variant (there is exactly one for the structures), the fields
+    /// which need to be dropped, in the order in which they are dropped (i.e.,
+    /// in declaration order). Empty if the type is opaque.
+    pub fields_drop_order: Vec<Vec<FieldId::Id>>,
 }
 
-#[derive(Debug, Clone, EnumIsA, EnumAsGetters, Serialize)]
+/// The representation options of a type, as given by the `#[repr(...)]`
+/// attributes. This is needed to reason about the discriminant values and
+/// the layout of FFI types.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ReprOptions {
+    /// `#[repr(C)]`
+    pub c: bool,
+    /// `#[repr(transparent)]`
+    pub transparent: bool,
+    /// The integer type of the discriminant, for enumerations (`#[repr(u8)]`,
+    /// `#[repr(i32)]`, etc.).
+    pub int: Option<IntegerTy>,
+    /// `#[repr(packed(N))]`: the alignment of the fields, in bytes.
+    pub pack: Option<u64>,
+    /// `#[repr(align(N))]`: the alignment of the type, in bytes.
+    pub align: Option<u64>,
+}
+
+/// The layout of a type, as computed by rustc for the target.
+///
+/// We can only compute the layouts of the types whose layout doesn't depend on
+/// their type parameters (ex.: we can compute the layout of `Option<&T>`, but
+/// not the layout of `Option<T>`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Layout {
+    /// The size of the type, in bytes.
+    pub size: u64,
+    /// The ABI alignment of the type, in bytes.
+    pub align: u64,
+    /// How the discriminant is encoded in memory, for the enumerations with
+    /// several variants.
+    pub discriminant: Option<DiscriminantEncoding>,
+}
+
+/// The tag which stores the discriminant of an enumeration (see
+/// [DiscriminantEncoding]).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Tag {
+    /// The offset of the tag in the value, in bytes.
+    pub offset: u64,
+    /// The integer type of the tag. Pointer tags (used by the niche
+    /// optimization, ex.: for `Option<&T>`) are encoded as `usize`.
+    pub ty: IntegerTy,
+}
+
+/// How the discriminant of an enumeration is encoded in memory.
+#[derive(Debug, Clone, EnumIsA, Serialize, Deserialize)]
+pub enum DiscriminantEncoding {
+    /// The tag stores the discriminant of the variant.
+    Direct(Tag),
+    /// The niche optimization: the tag is a field of `untagged_variant`, and
+    /// the other variants are encoded with values which are invalid for this
+    /// field (ex.: `Option<&T>` is encoded as a nullable pointer, where `0`
+    /// encodes `None`).
+    ///
+    /// A variant `v` in the range `niche_variants` (inclusive) is encoded by
+    /// the value `niche_start + (v - niche_variants.0)`, with a wrapping
+    /// arithmetic on the type of the tag. Any other value of the tag means
+    /// that the active variant is `untagged_variant`.
+    Niche {
+        tag: Tag,
+        untagged_variant: VariantId::Id,
+        niche_variants: (VariantId::Id, VariantId::Id),
+        niche_start: u128,
+    },
+}
+
+#[derive(Debug, Clone, EnumIsA, EnumAsGetters, Serialize, Deserialize)]
 pub enum TypeDeclKind {
     Struct(FieldId::Vector<Field>),
     Enum(VariantId::Vector<Variant>),
+    /// A union: all the fields share the same memory, and only one of them
+    /// is active at a time.
+    Union(FieldId::Vector<Field>),
     /// An opaque type.
     ///
     /// Either a local type marked as opaque, or an external type.
@@ -364,14 +544,19 @@ pub enum TypeDeclKind {
     Error(String),
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Variant {
     pub meta: Meta,
     pub name: String,
     pub fields: FieldId::Vector<Field>,
+    /// The discriminant of the variant. Its integer type is the type of the
+    /// discriminant of the enumeration (by default `isize`, but it can be
+    /// changed with `#[repr(...)]` attributes), and it is not necessarily
+    /// equal to the variant index (`enum E { A = 3, B = 7 }`).
+    pub discriminant: ScalarValue,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Field {
     pub meta: Meta,
     pub name: Option<String>,
@@ -379,7 +564,18 @@ pub struct Field {
 }
 
 #[derive(
-    Debug, PartialEq, Eq, Copy, Clone, EnumIsA, VariantName, Serialize, Hash, Ord, PartialOrd,
+    Debug,
+    PartialEq,
+    Eq,
+    Copy,
+    Clone,
+    EnumIsA,
+    VariantName,
+    Serialize,
+    Deserialize,
+    Hash,
+    Ord,
+    PartialOrd,
 )]
 pub enum IntegerTy {
     Isize,
@@ -397,7 +593,18 @@ pub enum IntegerTy {
 }
 
 #[derive(
-    Debug, PartialEq, Eq, Clone, Copy, Hash, VariantName, EnumIsA, Serialize, Ord, PartialOrd,
+    Debug,
+    PartialEq,
+    Eq,
+    Clone,
+    Copy,
+    Hash,
+    VariantName,
+    EnumIsA,
+    Serialize,
+    Deserialize,
+    Ord,
+    PartialOrd,
 )]
 pub enum RefKind {
     Mut,
@@ -417,6 +624,7 @@ pub enum RefKind {
     EnumAsGetters,
     EnumIsA,
     Serialize,
+    Deserialize,
     Hash,
     Ord,
     PartialOrd,
@@ -452,6 +660,7 @@ pub type TypeDecls = TypeDeclId::Map<TypeDecl>;
     EnumAsGetters,
     VariantIndexArity,
     Serialize,
+    Deserialize,
     Hash,
     Ord,
     PartialOrd,
@@ -462,6 +671,29 @@ pub enum LiteralTy {
     Char,
 }
 
+/// The validity invariant of a literal type: the bit patterns which are valid
+/// values of this type, given as a list of inclusive ranges. For instance, the
+/// only valid values of a boolean are 0 and 1.
+///
+/// Reading an invalid value is undefined behavior: the back-ends which model
+/// the memory at the byte level can assume that the literals are valid.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LiteralValidity {
+    pub ty: LiteralTy,
+    /// The size of the type, in bytes
+    pub size: usize,
+    pub valid_ranges: Vec<(u128, u128)>,
+}
+
+/// Information which doesn't depend on the crate, but that we export with
+/// every crate so that the back-ends don't have to hard-code it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Prelude {
+    /// The validity invariants of the literal types which have invalid bit
+    /// patterns (the integers don't have any).
+    pub literal_validity: Vec<LiteralValidity>,
+}
+
 /// Const Generic Values. Either a primitive value, or a variable corresponding to a primitve value
 #[derive(
     Debug,
@@ -473,6 +705,7 @@ pub enum LiteralTy {
     EnumAsGetters,
     VariantIndexArity,
     Serialize,
+    Deserialize,
     Hash,
     Ord,
     PartialOrd,
@@ -484,6 +717,15 @@ pub enum ConstGeneric {
     Var(ConstGenericVarId::Id),
     /// A concrete value
     Value(Literal),
+    /// A structured value (only with `#![feature(adt_const_params)]`).
+    ///
+    /// This mirrors [crate::expressions::RawConstantExpr::Adt]: the variant id
+    /// is `None` if the value is a structure.
+    Adt(Option<VariantId::Id>, Vec<ConstGeneric>),
+    /// A trait associated constant, for instance `<T as Foo>::LEN`.
+    ///
+    /// This mirrors [crate::expressions::RawConstantExpr::TraitConst].
+    TraitConst(TraitRef, GenericArgs, TraitItemName),
 }
 
 /// A type.
@@ -499,6 +741,7 @@ pub enum ConstGeneric {
     EnumToGetters,
     VariantIndexArity,
     Serialize,
+    Deserialize,
     Ord,
     PartialOrd,
 )]
@@ -548,6 +791,14 @@ pub enum Ty {
     /// arrow types can only contain generic lifetime parameters
     /// (no generic types), no predicates, etc.
     Arrow(RegionId::Vector<RegionVar>, Vec<Ty>, Box<Ty>),
+    /// A trait object (`dyn Trait`).
+    ///
+    /// We only keep the principal trait: we ignore the auto traits (like
+    /// [core::marker::Send]) and the constraints over the associated types.
+    /// Note that the generics of the trait reference don't contain the `Self`
+    /// type (which is existentially quantified). The region is the lifetime
+    /// bound of the trait object.
+    DynTrait(TraitDeclRef, Region),
 }
 
 /// Assumed types identifiers.
@@ -555,7 +806,8 @@ pub enum Ty {
 /// WARNING: for now, all the assumed types are covariant in the generic
 /// parameters (if there are). Adding types which don't satisfy this
 /// will require to update the code abstracting the signatures (to properly
-/// take into account the lifetime constraints).
+/// take into account the lifetime constraints). Note that this doesn't apply
+/// to the user types, whose variances are given by [TypeDecl::variances].
 ///
 /// TODO: update to not hardcode the types (except `Box` maybe) and be more
 /// modular.
@@ -570,6 +822,7 @@ pub enum Ty {
     EnumAsGetters,
     VariantName,
     Serialize,
+    Deserialize,
     Hash,
     Ord,
     PartialOrd,
@@ -582,6 +835,17 @@ pub enum AssumedTy {
     PtrUnique,
     /// Same comments as for [AssumedTy::PtrUnique]
     PtrNonNull,
+    /// `alloc::vec::Vec`: we ignore the allocator. The operations on vectors
+    /// are modelled by assumed functions (ex.: [crate::ullbc_ast::AssumedFunId::VecPush]).
+    Vec,
+    /// `alloc::rc::Rc`: we ignore the allocator.
+    Rc,
+    /// `alloc::sync::Arc`: we ignore the allocator.
+    Arc,
+    /// `core::cell::Cell`
+    Cell,
+    /// `core::cell::RefCell`
+    RefCell,
     /// Primitive type
     Array,
     /// Primitive type
@@ -628,11 +892,16 @@ pub enum AssumedTy {
 /// }
 /// ```
 ///
+/// We store this information for all the associated items, that is the
+/// methods and the associated constants, both in trait declarations and in
+/// impl blocks (see [FunSig::parent_params_info] and
+/// [crate::gast::GGlobalDecl::parent_params_info]).
+///
 /// **Remark**: Rust only allows refering to the generics of the immediately
 /// outer block. For this reason, when we need to store the information about
 /// the generics of the outer block(s), we need to do it only for one level
 /// (this definitely makes things simpler).
-#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ParamsInfo {
     pub num_region_params: usize,
     pub num_type_params: usize,
@@ -643,7 +912,7 @@ pub struct ParamsInfo {
     pub num_trait_type_constraints: usize,
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
 pub enum ClosureKind {
     Fn,
     FnMut,
@@ -652,7 +921,7 @@ pub enum ClosureKind {
 
 /// Additional information for closures.
 /// We mostly use it in micro-passes like [crate::update_closure_signature].
-#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ClosureInfo {
     pub kind: ClosureKind,
     /// Contains the types of the fields in the closure state.
@@ -670,10 +939,20 @@ pub struct ClosureInfo {
 }
 
 /// A function signature.
-#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct FunSig {
     /// Is the function unsafe or not
     pub is_unsafe: bool,
+    /// Is the function a `const fn`
+    pub is_const: bool,
+    /// `true` if the function is an `async fn`. Note that the body is then the
+    /// desugaring of the function: it builds and returns a future.
+    pub is_async: bool,
+    /// The ABI of the function, ex.: `"Rust"` (the default), `"C"` for the
+    /// `extern "C"` functions, `"rust-call"` for the closures.
+    pub abi: String,
+    /// Is the function variadic (ex.: `extern "C" fn f(x: u32, ...)`)
+    pub is_variadic: bool,
     /// `true` if the signature is for a closure.
     ///
     /// Importantly: if the signature is for a closure, then:
@@ -685,7 +964,8 @@ pub struct FunSig {
     pub closure_info: Option<ClosureInfo>,
     pub generics: GenericParams,
     pub preds: Predicates,
-    /// Optional fields, for trait methods only (see the comments in [ParamsInfo]).
+    /// Optional field, for the methods (in trait declarations and in impl
+    /// blocks) only: see the comments in [ParamsInfo].
     pub parent_params_info: Option<ParamsInfo>,
     pub inputs: Vec<Ty>,
     pub output: Ty,
@@ -2,6 +2,8 @@
 use crate::assumed::get_name_from_type_id;
 use crate::common::TAB_INCR;
 use crate::formatter::{AstFormatter, FmtCtx};
+use crate::id_vector::ToUsize;
+use crate::target::TargetInfo;
 use crate::types::*;
 use crate::values::*;
 use hax_frontend_exporter as hax;
@@ -41,13 +43,28 @@ impl Region {
 
 impl TypeVar {
     pub fn new(index: TypeVarId::Id, name: String) -> TypeVar {
-        TypeVar { index, name }
+        TypeVar {
+            index,
+            name,
+            sized: true,
+        }
     }
 
     pub fn fresh(name: String, gen: &mut TypeVarId::Generator) -> TypeVar {
         TypeVar {
             index: gen.fresh_id(),
             name,
+            sized: true,
+        }
+    }
+
+    /// Format the variable as a parameter (with its `?Sized` bound, if it has
+    /// one).
+    pub fn fmt_as_param(&self) -> String {
+        if self.sized {
+            self.name.to_string()
+        } else {
+            format!("{} : ?Sized", self.name)
         }
     }
 }
@@ -67,9 +84,12 @@ impl std::string::ToString for RegionVar {
     }
 }
 
-impl std::string::ToString for ConstGenericVar {
-    fn to_string(&self) -> String {
-        format!("const {} : {}", self.name, self.ty.to_string())
+impl ConstGenericVar {
+    pub fn fmt_with_ctx<C>(&self, ctx: &C) -> String
+    where
+        C: AstFormatter,
+    {
+        format!("const {} : {}", self.name, self.ty.fmt_with_ctx(ctx))
     }
 }
 
@@ -115,10 +135,10 @@ impl GenericParams {
                 params.push(x.to_string());
             }
             for x in types {
-                params.push(x.to_string());
+                params.push(x.fmt_as_param());
             }
             for x in const_generics {
-                params.push(x.to_string());
+                params.push(x.fmt_with_ctx(ctx));
             }
             for x in trait_clauses {
                 params.push(x.fmt_with_ctx(ctx));
@@ -142,10 +162,10 @@ impl GenericParams {
             params.push(x.to_string());
         }
         for x in types {
-            params.push(x.to_string());
+            params.push(x.fmt_as_param());
         }
         for x in const_generics {
-            params.push(x.to_string());
+            params.push(x.fmt_with_ctx(ctx));
         }
         let params = if params.is_empty() {
             "".to_string()
@@ -447,8 +467,13 @@ impl TraitInstanceId {
             TraitInstanceId::TraitImpl(id) => ctx.format_object(*id),
             TraitInstanceId::Clause(id) => ctx.format_object(*id),
             TraitInstanceId::BuiltinOrAuto(id) => ctx.format_object(*id),
-            TraitInstanceId::FnPointer(box ty) => {
-                format!("(fn_ptr:{})", ty.fmt_with_ctx(ctx))
+            TraitInstanceId::FnPointer(kind, inputs, box output) => {
+                let inputs = inputs
+                    .iter()
+                    .map(|x| x.fmt_with_ctx(ctx))
+                    .collect::<Vec<String>>()
+                    .join(", ");
+                format!("impl {kind:?}({inputs}) -> {}", output.fmt_with_ctx(ctx))
             }
             TraitInstanceId::Closure(fid, generics) => {
                 format!(
@@ -501,7 +526,7 @@ impl TypeDecl {
     ) -> Result<&FieldId::Vector<Field>, ()> {
         match &self.kind {
             TypeDeclKind::Enum(variants) => Ok(&variants.get(variant_id.unwrap()).unwrap().fields),
-            TypeDeclKind::Struct(fields) => {
+            TypeDeclKind::Struct(fields) | TypeDeclKind::Union(fields) => {
                 assert!(variant_id.is_none());
                 Ok(fields)
             }
@@ -527,7 +552,13 @@ impl TypeDecl {
         };
         let preds = fmt_where_clauses_with_ctx(ctx, "  ", &None, trait_clauses, &self.preds);
 
-        match &self.kind {
+        let repr = if self.repr.is_default() {
+            "".to_string()
+        } else {
+            format!("{}\n", self.repr)
+        };
+
+        let decl = match &self.kind {
             TypeDeclKind::Struct(fields) => {
                 if !fields.is_empty() {
                     let fields: Vec<String> = fields
@@ -557,6 +588,18 @@ impl TypeDecl {
                     self.name.fmt_with_ctx(ctx)
                 )
             }
+            TypeDeclKind::Union(fields) => {
+                // Note that the unions have at least one field
+                let fields: Vec<String> = fields
+                    .iter()
+                    .map(|f| format!("\n  {}", f.fmt_with_ctx(ctx)))
+                    .collect();
+                let fields = fields.join(",");
+                format!(
+                    "union {}{params}{preds}{eq_space}=\n{{{fields}\n}}",
+                    self.name.fmt_with_ctx(ctx)
+                )
+            }
             TypeDeclKind::Opaque => {
                 format!("opaque type {}{params}{preds}", self.name.fmt_with_ctx(ctx))
             }
@@ -566,7 +609,44 @@ impl TypeDecl {
                     self.name.fmt_with_ctx(ctx),
                 )
             }
+        };
+        format!("{repr}{decl}")
+    }
+}
+
+impl ReprOptions {
+    /// Return [true] if there are no `#[repr(...)]` attributes.
+    pub fn is_default(&self) -> bool {
+        let ReprOptions {
+            c,
+            transparent,
+            int,
+            pack,
+            align,
+        } = self;
+        !c && !transparent && int.is_none() && pack.is_none() && align.is_none()
+    }
+}
+
+impl std::fmt::Display for ReprOptions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
+        let mut attrs = Vec::new();
+        if self.c {
+            attrs.push("C".to_string());
+        }
+        if self.transparent {
+            attrs.push("transparent".to_string());
         }
+        if let Some(int) = &self.int {
+            attrs.push(int.to_string());
+        }
+        if let Some(pack) = &self.pack {
+            attrs.push(format!("packed({pack})"));
+        }
+        if let Some(align) = &self.align {
+            attrs.push(format!("align({align})"));
+        }
+        write!(f, "#[repr({})]", attrs.join(", "))
     }
 }
 
@@ -646,17 +726,18 @@ impl IntegerTy {
         !(self.is_signed())
     }
 
-    /// Return the size (in bytes) of an integer of the proper type
-    pub fn size(&self) -> usize {
+    /// Return the size (in bytes) of an integer of the proper type. The size of
+    /// `isize` and `usize` depends on the target.
+    pub fn size(&self, target: &TargetInfo) -> usize {
         use std::mem::size_of;
         match self {
-            IntegerTy::Isize => size_of::<isize>(),
+            IntegerTy::Isize => target.pointer_size(),
             IntegerTy::I8 => size_of::<i8>(),
             IntegerTy::I16 => size_of::<i16>(),
             IntegerTy::I32 => size_of::<i32>(),
             IntegerTy::I64 => size_of::<i64>(),
             IntegerTy::I128 => size_of::<i128>(),
-            IntegerTy::Usize => size_of::<isize>(),
+            IntegerTy::Usize => target.pointer_size(),
             IntegerTy::U8 => size_of::<u8>(),
             IntegerTy::U16 => size_of::<u16>(),
             IntegerTy::U32 => size_of::<u32>(),
@@ -753,6 +834,32 @@ impl std::fmt::Display for LiteralTy {
     }
 }
 
+impl Prelude {
+    pub fn new() -> Self {
+        let bool_validity = LiteralValidity {
+            ty: LiteralTy::Bool,
+            size: 1,
+            valid_ranges: vec![(0, 1)],
+        };
+        // A char is a Unicode scalar value: the surrogate code points are
+        // excluded
+        let char_validity = LiteralValidity {
+            ty: LiteralTy::Char,
+            size: 4,
+            valid_ranges: vec![(0, 0xD7FF), (0xE000, 0x10FFFF)],
+        };
+        Prelude {
+            literal_validity: vec![bool_validity, char_validity],
+        }
+    }
+}
+
+impl Default for Prelude {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 // IntTy is not defined in the current crate
 pub fn intty_to_string(ty: hax::IntTy) -> String {
     use hax::IntTy::*;
@@ -801,6 +908,22 @@ impl ConstGeneric {
             ConstGeneric::Var(id) => ctx.format_object(*id),
             ConstGeneric::Value(v) => v.to_string(),
             ConstGeneric::Global(id) => ctx.format_object(*id),
+            ConstGeneric::Adt(variant_id, fields) => {
+                // Same as for the constant expressions: we don't have the type
+                let variant_id = match variant_id {
+                    Option::Some(id) => format!("Some({id})"),
+                    Option::None => "None".to_string(),
+                };
+                let fields: Vec<String> = fields.iter().map(|v| v.fmt_with_ctx(ctx)).collect();
+                format!("ConstAdt {} [{}]", variant_id, fields.join(", "))
+            }
+            ConstGeneric::TraitConst(trait_ref, generics, name) => {
+                format!(
+                    "{}{}::{name}",
+                    trait_ref.fmt_with_ctx(ctx),
+                    generics.fmt_with_ctx_split_trait_refs(ctx)
+                )
+            }
         }
     }
 }
@@ -917,6 +1040,13 @@ impl Ty {
                     format!("fn{regions}({inputs}) -> {output}")
                 }
             }
+            Ty::DynTrait(trait_ref, region) => {
+                format!(
+                    "dyn ({} + {})",
+                    trait_ref.fmt_with_ctx(ctx),
+                    region.fmt_with_ctx(ctx)
+                )
+            }
         }
     }
 
@@ -944,6 +1074,76 @@ impl Ty {
             _ => None,
         }
     }
+
+    /// Build the array type `[elem; len]`
+    pub fn mk_array(elem: Ty, len: ConstGeneric) -> Ty {
+        Ty::Adt(
+            TypeId::Assumed(AssumedTy::Array),
+            GenericArgs::new(Vec::new(), vec![elem], vec![len], Vec::new()),
+        )
+    }
+
+    /// Build the slice type `[elem]`
+    pub fn mk_slice(elem: Ty) -> Ty {
+        Ty::Adt(
+            TypeId::Assumed(AssumedTy::Slice),
+            GenericArgs::new_from_types(vec![elem]),
+        )
+    }
+
+    /// Return true if the type is an array
+    pub fn is_array(&self) -> bool {
+        self.as_array().is_some()
+    }
+
+    /// Return true if the type is a slice
+    pub fn is_slice(&self) -> bool {
+        self.as_slice().is_some()
+    }
+
+    /// If the type is an array `[T; N]`, return `T` and `N`.
+    ///
+    /// Arrays always have exactly one type parameter and one const generic
+    /// parameter: we check it here.
+    pub fn as_array(&self) -> Option<(&Ty, &ConstGeneric)> {
+        match self {
+            Ty::Adt(TypeId::Assumed(AssumedTy::Array), generics) => {
+                assert!(generics.regions.is_empty());
+                assert!(generics.types.len() == 1);
+                assert!(generics.const_generics.len() == 1);
+                assert!(generics.trait_refs.is_empty());
+                Some((&generics.types[0], &generics.const_generics[0]))
+            }
+            _ => None,
+        }
+    }
+
+    /// If the type is a slice `[T]`, return `T`.
+    pub fn as_slice(&self) -> Option<&Ty> {
+        match self {
+            Ty::Adt(TypeId::Assumed(AssumedTy::Slice), generics) => {
+                assert!(generics.regions.is_empty());
+                assert!(generics.types.len() == 1);
+                assert!(generics.const_generics.is_empty());
+                assert!(generics.trait_refs.is_empty());
+                Some(&generics.types[0])
+            }
+            _ => None,
+        }
+    }
+
+    /// If the type is an array or a slice, return the type of its elements.
+    pub fn as_array_or_slice(&self) -> Option<&Ty> {
+        match self.as_array() {
+            Some((elem, _)) => Some(elem),
+            None => self.as_slice(),
+        }
+    }
+
+    /// If the type is an array `[T; N]`, return `N`.
+    pub fn array_len(&self) -> Option<&ConstGeneric> {
+        self.as_array().map(|(_, len)| len)
+    }
 }
 
 impl std::fmt::Display for Region {
@@ -967,7 +1167,7 @@ impl Ty {
                 // so we don't need to explore the trait ref
                 args.types.iter().any(|ty| ty.contains_never())
             }
-            Ty::TypeVar(_) | Ty::Literal(_) => false,
+            Ty::TypeVar(_) | Ty::Literal(_) | Ty::DynTrait(..) => false,
             Ty::Ref(_, ty, _) | Ty::RawPtr(ty, _) => ty.contains_never(),
             Ty::Arrow(_, inputs, box output) => {
                 inputs.iter().any(|ty| ty.contains_never()) || output.contains_never()
@@ -976,6 +1176,57 @@ impl Ty {
     }
 }
 
+/// The reason why we failed to unify two values (see [TySubst]).
+///
+/// The values are given in the order: source, target.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UnifyError {
+    Regions(Region, Region),
+    Types(Ty, Ty),
+    ConstGenerics(ConstGeneric, ConstGeneric),
+    TraitInstances(TraitInstanceId, TraitInstanceId),
+    TraitDecls(TraitDeclId::Id, TraitDeclId::Id),
+    /// The lists of arguments (generic arguments, inputs of a function, etc.)
+    /// don't have the same length.
+    Arity {
+        src: usize,
+        tgt: usize,
+    },
+}
+
+impl UnifyError {
+    pub fn fmt_with_ctx<C>(&self, ctx: &C) -> String
+    where
+        C: AstFormatter,
+    {
+        let (kind, src, tgt) = match self {
+            UnifyError::Regions(src, tgt) => {
+                ("regions", src.fmt_with_ctx(ctx), tgt.fmt_with_ctx(ctx))
+            }
+            UnifyError::Types(src, tgt) => ("types", src.fmt_with_ctx(ctx), tgt.fmt_with_ctx(ctx)),
+            UnifyError::ConstGenerics(src, tgt) => (
+                "const generics",
+                src.fmt_with_ctx(ctx),
+                tgt.fmt_with_ctx(ctx),
+            ),
+            UnifyError::TraitInstances(src, tgt) => (
+                "trait instances",
+                src.fmt_with_ctx(ctx),
+                tgt.fmt_with_ctx(ctx),
+            ),
+            UnifyError::TraitDecls(src, tgt) => {
+                ("traits", ctx.format_object(*src), ctx.format_object(*tgt))
+            }
+            UnifyError::Arity { src, tgt } => {
+                ("numbers of arguments", src.to_string(), tgt.to_string())
+            }
+        };
+        format!("Mismatched {kind}: {src} (source) and {tgt} (target)")
+    }
+}
+
+/// Matches a source value (a type, a signature, etc.) against a target value,
+/// by computing a substitution for the variables appearing in the source.
 pub struct TySubst {
     pub ignore_regions: bool,
     /// This map is from regions to regions, not from region ids to regions.
@@ -984,28 +1235,21 @@ pub struct TySubst {
     pub regions_map: HashMap<Region, Region>,
     pub type_vars_map: HashMap<TypeVarId::Id, Ty>,
     pub const_generics_map: HashMap<ConstGenericVarId::Id, ConstGeneric>,
+    pub trait_clauses_map: HashMap<TraitClauseId::Id, TraitInstanceId>,
 }
 
-macro_rules! check_ok_return {
-    ( $x:expr ) => {{
-        if $x {
-            return Ok(());
-        } else {
-            return Err(());
-        }
-    }};
-}
-
-macro_rules! check_ok {
-    ( $x:expr ) => {{
-        if !$x {
-            return Err(());
+/// Check that two values are equal, and otherwise return the given error.
+macro_rules! check_eq {
+    ( $src:expr, $tgt:expr, $err:expr ) => {{
+        if $src != $tgt {
+            return Err($err);
         }
     }};
 }
 
 impl TySubst {
-    fn new() -> Self {
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
         let mut regions_map = HashMap::new();
         // Fix the static and erased regions
         regions_map.insert(Region::Static, Region::Static);
@@ -1015,120 +1259,266 @@ impl TySubst {
             regions_map,
             type_vars_map: HashMap::new(),
             const_generics_map: HashMap::new(),
+            trait_clauses_map: HashMap::new(),
         }
     }
 
-    fn unify_regions(&mut self, src: &Region, tgt: &Region) -> Result<(), ()> {
-        use Result::*;
+    pub fn unify_regions(&mut self, src: &Region, tgt: &Region) -> Result<(), UnifyError> {
         match self.regions_map.get(src) {
             None => {
-                check_ok_return!(self.regions_map.insert(*src, *tgt).is_none());
+                self.regions_map.insert(*src, *tgt);
+                Ok(())
             }
-            Some(src) => {
-                check_ok_return!(src == tgt);
+            Some(r) => {
+                check_eq!(r, tgt, UnifyError::Regions(*src, *tgt));
+                Ok(())
             }
         }
     }
 
-    fn unify_const_generics(&mut self, src: &ConstGeneric, tgt: &ConstGeneric) -> Result<(), ()> {
+    pub fn unify_const_generics(
+        &mut self,
+        src: &ConstGeneric,
+        tgt: &ConstGeneric,
+    ) -> Result<(), UnifyError> {
         use ConstGeneric::*;
-        use Result::*;
-        if let Var(v) = src {
-            check_ok_return!(self.const_generics_map.insert(*v, tgt.clone()).is_none());
-        }
+        let err = || UnifyError::ConstGenerics(src.clone(), tgt.clone());
         match (src, tgt) {
-            (Global(src), Global(tgt)) => {
-                check_ok_return!(src == tgt);
+            (Var(v), _) => match self.const_generics_map.get(v) {
+                None => {
+                    self.const_generics_map.insert(*v, tgt.clone());
+                    Ok(())
+                }
+                Some(cg) => {
+                    check_eq!(cg, tgt, err());
+                    Ok(())
+                }
+            },
+            (Global(src_id), Global(tgt_id)) => {
+                check_eq!(src_id, tgt_id, err());
+                Ok(())
+            }
+            (Value(src_v), Value(tgt_v)) => {
+                check_eq!(src_v, tgt_v, err());
+                Ok(())
             }
-            (Value(src), Value(tgt)) => {
-                check_ok_return!(src == tgt);
+            (Adt(src_variant, src_fields), Adt(tgt_variant, tgt_fields)) => {
+                check_eq!(src_variant, tgt_variant, err());
+                self.unify_lists(src_fields, tgt_fields, Self::unify_const_generics)
             }
-            _ => Err(()),
+            (TraitConst(src_ref, src_args, src_name), TraitConst(tgt_ref, tgt_args, tgt_name)) => {
+                check_eq!(src_name, tgt_name, err());
+                self.unify_trait_refs(src_ref, tgt_ref)?;
+                self.unify_args(src_args, tgt_args)
+            }
+            _ => Err(err()),
         }
     }
 
-    fn unify_types(&mut self, src: &Ty, tgt: &Ty) -> Result<(), ()> {
-        use Result::*;
+    pub fn unify_types(&mut self, src: &Ty, tgt: &Ty) -> Result<(), UnifyError> {
         use Ty::*;
-
-        if let TypeVar(v) = src {
-            check_ok_return!(self.type_vars_map.insert(*v, tgt.clone()).is_none());
-        }
-
+        let err = || UnifyError::Types(src.clone(), tgt.clone());
         match (src, tgt) {
+            (TypeVar(v), _) => match self.type_vars_map.get(v) {
+                None => {
+                    self.type_vars_map.insert(*v, tgt.clone());
+                    Ok(())
+                }
+                Some(ty) => {
+                    check_eq!(ty, tgt, err());
+                    Ok(())
+                }
+            },
             (Adt(src_id, src_args), Adt(tgt_id, tgt_args)) => {
-                check_ok!(src_id == tgt_id);
+                check_eq!(src_id, tgt_id, err());
                 self.unify_args(src_args, tgt_args)
             }
-            (Literal(src), Literal(tgt)) => {
-                check_ok_return!(src == tgt);
+            (Literal(src_lit), Literal(tgt_lit)) => {
+                check_eq!(src_lit, tgt_lit, err());
+                Ok(())
             }
             (Never, Never) => Ok(()),
             (Ref(src_r, box src_ty, src_kind), Ref(tgt_r, box tgt_ty, tgt_kind)) => {
+                check_eq!(src_kind, tgt_kind, err());
                 if !self.ignore_regions {
                     self.unify_regions(src_r, tgt_r)?;
                 }
-                self.unify_types(src_ty, tgt_ty)?;
-                check_ok_return!(src_kind == tgt_kind);
+                self.unify_types(src_ty, tgt_ty)
             }
             (RawPtr(box src_ty, src_kind), RawPtr(box tgt_ty, tgt_kind)) => {
-                self.unify_types(src_ty, tgt_ty)?;
-                check_ok_return!(src_kind == tgt_kind);
+                check_eq!(src_kind, tgt_kind, err());
+                self.unify_types(src_ty, tgt_ty)
             }
-            _ => Err(()),
+            (TraitType(src_ref, src_args, src_name), TraitType(tgt_ref, tgt_args, tgt_name)) => {
+                check_eq!(src_name, tgt_name, err());
+                self.unify_trait_refs(src_ref, tgt_ref)?;
+                self.unify_args(src_args, tgt_args)
+            }
+            (
+                Arrow(src_regions, src_inputs, box src_output),
+                Arrow(tgt_regions, tgt_inputs, box tgt_output),
+            ) => {
+                // The bound regions are matched by the regions map (they are
+                // identified by their De Bruijn indices and their ids)
+                check_eq!(src_regions.len(), tgt_regions.len(), err());
+                self.unify_types_lists(src_inputs, tgt_inputs)?;
+                self.unify_types(src_output, tgt_output)
+            }
+            (DynTrait(src_ref, src_r), DynTrait(tgt_ref, tgt_r)) => {
+                if !self.ignore_regions {
+                    self.unify_regions(src_r, tgt_r)?;
+                }
+                self.unify_trait_decl_refs(src_ref, tgt_ref)
+            }
+            _ => Err(err()),
         }
     }
 
-    fn unify_regions_lists(&mut self, src: &[Region], tgt: &[Region]) -> Result<(), ()> {
-        check_ok!(src.len() == tgt.len());
-        for (src, tgt) in src.iter().zip(tgt.iter()) {
-            self.unify_regions(src, tgt)?;
+    pub fn unify_trait_instance_ids(
+        &mut self,
+        src: &TraitInstanceId,
+        tgt: &TraitInstanceId,
+    ) -> Result<(), UnifyError> {
+        use TraitInstanceId::*;
+        let err = || UnifyError::TraitInstances(src.clone(), tgt.clone());
+        match (src, tgt) {
+            (Clause(id), _) => match self.trait_clauses_map.get(id) {
+                None => {
+                    self.trait_clauses_map.insert(*id, tgt.clone());
+                    Ok(())
+                }
+                Some(tid) => {
+                    check_eq!(tid, tgt, err());
+                    Ok(())
+                }
+            },
+            (SelfId, SelfId) => Ok(()),
+            (TraitImpl(src_id), TraitImpl(tgt_id)) => {
+                check_eq!(src_id, tgt_id, err());
+                Ok(())
+            }
+            (BuiltinOrAuto(src_id), BuiltinOrAuto(tgt_id)) => {
+                check_eq!(src_id, tgt_id, err());
+                Ok(())
+            }
+            (
+                ParentClause(box src_id, src_decl, src_clause),
+                ParentClause(box tgt_id, tgt_decl, tgt_clause),
+            ) => {
+                check_eq!((src_decl, src_clause), (tgt_decl, tgt_clause), err());
+                self.unify_trait_instance_ids(src_id, tgt_id)
+            }
+            (
+                ItemClause(box src_id, src_decl, src_name, src_clause),
+                ItemClause(box tgt_id, tgt_decl, tgt_name, tgt_clause),
+            ) => {
+                check_eq!(
+                    (src_decl, src_name, src_clause),
+                    (tgt_decl, tgt_name, tgt_clause),
+                    err()
+                );
+                self.unify_trait_instance_ids(src_id, tgt_id)
+            }
+            (
+                FnPointer(src_kind, src_inputs, box src_output),
+                FnPointer(tgt_kind, tgt_inputs, box tgt_output),
+            ) => {
+                check_eq!(src_kind, tgt_kind, err());
+                self.unify_types_lists(src_inputs, tgt_inputs)?;
+                self.unify_types(src_output, tgt_output)
+            }
+            (Closure(src_id, src_args), Closure(tgt_id, tgt_args)) => {
+                check_eq!(src_id, tgt_id, err());
+                self.unify_args(src_args, tgt_args)
+            }
+            (Unsolved(src_id, src_args), Unsolved(tgt_id, tgt_args)) => {
+                check_eq!(src_id, tgt_id, err());
+                self.unify_args(src_args, tgt_args)
+            }
+            // We can't say anything about the unknown instances
+            _ => Err(err()),
         }
-        Ok(())
     }
 
-    fn unify_const_generics_lists(
+    pub fn unify_trait_refs(&mut self, src: &TraitRef, tgt: &TraitRef) -> Result<(), UnifyError> {
+        self.unify_trait_instance_ids(&src.trait_id, &tgt.trait_id)?;
+        self.unify_args(&src.generics, &tgt.generics)?;
+        self.unify_trait_decl_refs(&src.trait_decl_ref, &tgt.trait_decl_ref)
+    }
+
+    pub fn unify_trait_decl_refs(
         &mut self,
-        src: &[ConstGeneric],
-        tgt: &[ConstGeneric],
-    ) -> Result<(), ()> {
-        check_ok!(src.len() == tgt.len());
-        for (src, tgt) in src.iter().zip(tgt.iter()) {
-            self.unify_const_generics(src, tgt)?;
-        }
-        Ok(())
+        src: &TraitDeclRef,
+        tgt: &TraitDeclRef,
+    ) -> Result<(), UnifyError> {
+        check_eq!(
+            src.trait_id,
+            tgt.trait_id,
+            UnifyError::TraitDecls(src.trait_id, tgt.trait_id)
+        );
+        self.unify_args(&src.generics, &tgt.generics)
     }
 
-    fn unify_types_lists(&mut self, src: &[Ty], tgt: &[Ty]) -> Result<(), ()> {
-        check_ok!(src.len() == tgt.len());
+    /// Unify two lists element-wise, after checking that they have the same length.
+    fn unify_lists<T>(
+        &mut self,
+        src: &[T],
+        tgt: &[T],
+        unify: impl Fn(&mut Self, &T, &T) -> Result<(), UnifyError>,
+    ) -> Result<(), UnifyError> {
+        check_eq!(
+            src.len(),
+            tgt.len(),
+            UnifyError::Arity {
+                src: src.len(),
+                tgt: tgt.len()
+            }
+        );
         for (src, tgt) in src.iter().zip(tgt.iter()) {
-            self.unify_types(src, tgt)?;
+            unify(self, src, tgt)?;
         }
         Ok(())
     }
 
-    fn unify_args(
+    fn unify_types_lists(&mut self, src: &[Ty], tgt: &[Ty]) -> Result<(), UnifyError> {
+        self.unify_lists(src, tgt, Self::unify_types)
+    }
+
+    pub fn unify_args(
         &mut self,
         src: &crate::gast::GenericArgs,
         tgt: &crate::gast::GenericArgs,
-    ) -> Result<(), ()> {
+    ) -> Result<(), UnifyError> {
         if !self.ignore_regions {
-            self.unify_regions_lists(&src.regions, &tgt.regions)?;
+            self.unify_lists(&src.regions, &tgt.regions, Self::unify_regions)?;
         }
         self.unify_types_lists(&src.types, &tgt.types)?;
-        self.unify_const_generics_lists(&src.const_generics, &tgt.const_generics)?;
-        Ok(())
+        self.unify_lists(
+            &src.const_generics,
+            &tgt.const_generics,
+            Self::unify_const_generics,
+        )?;
+        self.unify_lists(&src.trait_refs, &tgt.trait_refs, Self::unify_trait_refs)
+    }
+
+    /// Unify the inputs and the outputs of two function signatures.
+    ///
+    /// Note that we don't attempt to unify the generic parameters and the
+    /// predicates: we match the types appearing in the source signature
+    /// against the types appearing in the target signature.
+    pub fn unify_fun_sigs(&mut self, src: &FunSig, tgt: &FunSig) -> Result<(), UnifyError> {
+        self.unify_types_lists(&src.inputs, &tgt.inputs)?;
+        self.unify_types(&src.output, &tgt.output)
     }
 }
 
 impl TySubst {
-    #[allow(clippy::result_unit_err)]
     pub fn unify_args_with_fixed(
         fixed_type_vars: impl std::iter::Iterator<Item = TypeVarId::Id>,
         fixed_const_generic_vars: impl std::iter::Iterator<Item = ConstGenericVarId::Id>,
         src: &crate::gast::GenericArgs,
         tgt: &crate::gast::GenericArgs,
-    ) -> Result<Self, ()> {
+    ) -> Result<Self, UnifyError> {
         let mut s = TySubst::new();
         for v in fixed_type_vars {
             s.type_vars_map.insert(v, Ty::TypeVar(v));
@@ -1143,26 +1533,308 @@ impl TySubst {
     }
 }
 
-/// Visitor to replace the [TraitInstanceId::SelfId] inside a type
+/// Folder to replace the [TraitInstanceId::SelfId] inside a type
 struct TraitInstanceIdSelfReplacer {
     new_id: TraitInstanceId,
 }
 
-impl MutTypeVisitor for TraitInstanceIdSelfReplacer {
-    fn visit_trait_instance_id(&mut self, id: &mut TraitInstanceId) {
+impl TypeFolder for TraitInstanceIdSelfReplacer {
+    fn fold_trait_instance_id(&mut self, id: TraitInstanceId) -> TraitInstanceId {
         match id {
-            TraitInstanceId::SelfId => *id = self.new_id.clone(),
-            TraitInstanceId::ParentClause(box id, _, _)
-            | TraitInstanceId::ItemClause(box id, _, _, _) => self.visit_trait_instance_id(id),
-            TraitInstanceId::TraitImpl(_)
-            | TraitInstanceId::Clause(_)
-            | TraitInstanceId::BuiltinOrAuto(_)
-            | TraitInstanceId::FnPointer(_)
-            | TraitInstanceId::Closure(..)
-            | TraitInstanceId::Unsolved(..)
-            | TraitInstanceId::Unknown(_) => (),
+            TraitInstanceId::SelfId => self.new_id.clone(),
+            _ => self.default_fold_trait_instance_id(id),
+        }
+    }
+}
+
+/// Folder to erase the regions
+struct RegionEraser;
+
+impl TypeFolder for RegionEraser {
+    fn fold_region(&mut self, _: Region) -> Region {
+        Region::Erased
+    }
+
+    fn fold_arrow(
+        &mut self,
+        regions: RegionId::Vector<RegionVar>,
+        inputs: Vec<Ty>,
+        output: Ty,
+    ) -> Ty {
+        // The regions bound by the arrow are still bound: we keep them, and
+        // erase the regions below the binder like the other regions.
+        let inputs = inputs.into_iter().map(|ty| self.fold_ty(ty)).collect();
+        Ty::Arrow(regions, inputs, Box::new(self.fold_ty(output)))
+    }
+}
+
+/// Visitor to shift the bound regions which are not bound inside the visited
+/// value, because we move this value below `shift` region groups.
+struct RegionShifter {
+    shift: usize,
+    /// The number of region groups we dived into
+    depth: usize,
+}
+
+impl MutTypeVisitor for RegionShifter {
+    fn visit_region(&mut self, r: &mut Region) {
+        if let Region::BVar(grid, _) = r
+            && grid.index >= self.depth
+        {
+            grid.index += self.shift;
         }
     }
+
+    fn enter_region_group(
+        &mut self,
+        _regions: &mut RegionId::Vector<RegionVar>,
+        visitor: &mut dyn FnMut(&mut Self),
+    ) {
+        self.depth += 1;
+        visitor(self);
+        self.depth -= 1;
+    }
+}
+
+/// A substitution for the generic parameters of a declaration (regions, types,
+/// const generics and trait clauses), indexed by the ids of the parameters.
+#[derive(Debug, Clone)]
+pub struct SubstEnv {
+    pub regions: Vec<Region>,
+    pub types: Vec<Ty>,
+    pub const_generics: Vec<ConstGeneric>,
+    pub trait_refs: Vec<TraitRef>,
+}
+
+impl SubstEnv {
+    /// Create the substitution which maps the generic parameters `params` to
+    /// the generic arguments `args`.
+    ///
+    /// Panics if the arguments don't match the parameters.
+    pub fn new(params: &GenericParams, args: &GenericArgs) -> Self {
+        assert!(
+            params.regions.len() == args.regions.len()
+                && params.types.len() == args.types.len()
+                && params.const_generics.len() == args.const_generics.len()
+                && params.trait_clauses.len() == args.trait_refs.len(),
+            "The generic arguments don't match the generic parameters"
+        );
+        Self::from_args(args)
+    }
+
+    /// Create the substitution which maps the generic parameters of the current
+    /// declaration to the generic arguments `args` (the i-th argument of each
+    /// kind is substituted for the parameter with id i).
+    pub fn from_args(args: &GenericArgs) -> Self {
+        let GenericArgs {
+            regions,
+            types,
+            const_generics,
+            trait_refs,
+        } = args.clone();
+        SubstEnv {
+            regions,
+            types,
+            const_generics,
+            trait_refs,
+        }
+    }
+}
+
+/// Visitor to substitute the generic parameters of a declaration (regions,
+/// types, const generics and trait clauses) following a [SubstEnv].
+///
+/// The regions of the declaration are the regions bound at the top-level
+/// (i.e., with a De Bruijn index equal to the number of region groups we
+/// dived into): when we substitute them below region groups, we must shift
+/// the regions appearing in the substitution accordingly.
+struct Substituter<'a> {
+    env: &'a SubstEnv,
+    /// The number of region groups we dived into
+    depth: usize,
+}
+
+impl<'a> Substituter<'a> {
+    fn shift<T>(&self, mut x: T, visit: impl FnOnce(&mut RegionShifter, &mut T)) -> T {
+        if self.depth > 0 {
+            let mut shifter = RegionShifter {
+                shift: self.depth,
+                depth: 0,
+            };
+            visit(&mut shifter, &mut x);
+        }
+        x
+    }
+}
+
+impl<'a> MutTypeVisitor for Substituter<'a> {
+    fn visit_ty(&mut self, ty: &mut Ty) {
+        if let Ty::TypeVar(id) = ty {
+            let new_ty = self.env.types.get(id.to_usize()).unwrap().clone();
+            *ty = self.shift(new_ty, |s, ty| s.visit_ty(ty));
+        } else {
+            self.default_visit_ty(ty)
+        }
+    }
+
+    fn visit_region(&mut self, r: &mut Region) {
+        if let Region::BVar(grid, id) = r
+            && grid.index == self.depth
+        {
+            let new_r = *self.env.regions.get(id.to_usize()).unwrap();
+            *r = self.shift(new_r, |s, r| s.visit_region(r));
+        }
+    }
+
+    fn visit_const_generic(&mut self, cg: &mut ConstGeneric) {
+        if let ConstGeneric::Var(id) = cg {
+            *cg = self.env.const_generics.get(id.to_usize()).unwrap().clone();
+        } else {
+            self.default_visit_const_generic(cg)
+        }
+    }
+
+    fn visit_trait_instance_id(&mut self, id: &mut TraitInstanceId) {
+        if let TraitInstanceId::Clause(clause_id) = id {
+            let trait_ref = self.env.trait_refs.get(clause_id.to_usize()).unwrap();
+            *id = self.shift(trait_ref.trait_id.clone(), |s, id| {
+                s.visit_trait_instance_id(id)
+            });
+        } else {
+            self.default_visit_trait_instance_id(id)
+        }
+    }
+
+    fn enter_region_group(
+        &mut self,
+        _regions: &mut RegionId::Vector<RegionVar>,
+        visitor: &mut dyn FnMut(&mut Self),
+    ) {
+        self.depth += 1;
+        visitor(self);
+        self.depth -= 1;
+    }
+}
+
+/// Apply a substitution inside a value which appears at the top-level of the
+/// declaration whose parameters we substitute (a type, a trait reference,
+/// etc.). The `visit` function gives the way of exploring the value.
+fn substitute<T: Clone>(env: &SubstEnv, x: &T, visit: impl FnOnce(&mut Substituter, &mut T)) -> T {
+    let mut x = x.clone();
+    let mut substituter = Substituter { env, depth: 0 };
+    visit(&mut substituter, &mut x);
+    x
+}
+
+impl Ty {
+    /// Substitute the generic parameters of the current declaration with
+    /// the given generic arguments.
+    pub fn substitute(&self, generics: &GenericArgs) -> Ty {
+        self.substitute_with(&SubstEnv::from_args(generics))
+    }
+
+    /// Apply a substitution.
+    pub fn substitute_with(&self, env: &SubstEnv) -> Ty {
+        substitute(env, self, |s, ty| s.visit_ty(ty))
+    }
+
+    /// Replace the occurrences of [TraitInstanceId::SelfId] with `new_id`.
+    pub fn replace_self_trait_instance(self, new_id: TraitInstanceId) -> Ty {
+        TraitInstanceIdSelfReplacer { new_id }.fold_ty(self)
+    }
+
+    /// Erase all the regions.
+    pub fn erase_regions(self) -> Ty {
+        RegionEraser.fold_ty(self)
+    }
+}
+
+impl GenericArgs {
+    /// Erase all the regions.
+    pub fn erase_regions(self) -> GenericArgs {
+        RegionEraser.fold_generic_args(self)
+    }
+
+    /// Substitute the generic parameters of the current declaration with
+    /// the given generic arguments.
+    pub fn substitute(&self, generics: &GenericArgs) -> GenericArgs {
+        self.substitute_with(&SubstEnv::from_args(generics))
+    }
+
+    /// Apply a substitution.
+    pub fn substitute_with(&self, env: &SubstEnv) -> GenericArgs {
+        substitute(env, self, |s, args| s.visit_generic_args(args))
+    }
+}
+
+impl TraitRef {
+    /// Substitute the generic parameters of the current declaration with
+    /// the given generic arguments.
+    pub fn substitute(&self, generics: &GenericArgs) -> TraitRef {
+        self.substitute_with(&SubstEnv::from_args(generics))
+    }
+
+    /// Apply a substitution.
+    pub fn substitute_with(&self, env: &SubstEnv) -> TraitRef {
+        substitute(env, self, |s, tr| s.visit_trait_ref(tr))
+    }
+}
+
+impl TraitClause {
+    /// Instantiate the clause with the generic arguments given to the
+    /// declaration which introduces it.
+    ///
+    /// This gives the reference to the trait that the corresponding trait
+    /// ref (i.e., `generics.trait_refs[clause_id]`) must implement.
+    pub fn instantiate(&self, generics: &GenericArgs) -> TraitDeclRef {
+        TraitDeclRef {
+            trait_id: self.trait_id,
+            generics: self.generics.substitute(generics),
+        }
+    }
+}
+
+impl Predicates {
+    /// Instantiate the predicates with the generic arguments given to the
+    /// declaration which introduces them.
+    pub fn instantiate(&self, generics: &GenericArgs) -> Predicates {
+        substitute(&SubstEnv::from_args(generics), self, |s, preds| {
+            s.visit_predicates(preds)
+        })
+    }
+}
+
+impl FunSig {
+    /// Apply a substitution to the inputs and the output of the signature.
+    /// The substitution must map *all* the generic parameters of the signature
+    /// (i.e., including the parameters of the parent, for the methods).
+    pub fn substitute_inputs_output(&self, env: &SubstEnv) -> (Vec<Ty>, Ty) {
+        let inputs = self
+            .inputs
+            .iter()
+            .map(|ty| ty.substitute_with(env))
+            .collect();
+        (inputs, self.output.substitute_with(env))
+    }
+
+    /// Format the qualifiers of the function, in the order in which they
+    /// appear in Rust (ex.: `const unsafe extern "C" `).
+    pub fn fmt_qualifiers(&self) -> String {
+        let mut qualifiers = String::new();
+        if self.is_const {
+            qualifiers.push_str("const ");
+        }
+        if self.is_async {
+            qualifiers.push_str("async ");
+        }
+        if self.is_unsafe {
+            qualifiers.push_str("unsafe ");
+        }
+        if self.abi != "Rust" && !self.is_closure {
+            qualifiers.push_str(&format!("extern \"{}\" ", self.abi));
+        }
+        qualifiers
+    }
 }
 
 // Derive two implementations at once: one which uses shared borrows, and one
@@ -1199,6 +1871,10 @@ pub trait TypeVisitor {
                 self.visit_generic_args(generics);
             }
             Arrow(regions, inputs, box output) => self.visit_arrow(regions, inputs, output),
+            DynTrait(trait_ref, r) => {
+                self.visit_trait_decl_ref(trait_ref);
+                self.visit_region(r);
+            }
         }
     }
 
@@ -1271,11 +1947,24 @@ pub trait TypeVisitor {
     fn visit_assumed_ty(&mut self, _: &AssumedTy) {}
 
     fn visit_const_generic(&mut self, cg: &ConstGeneric) {
+        self.default_visit_const_generic(cg)
+    }
+
+    fn default_visit_const_generic(&mut self, cg: &ConstGeneric) {
         use ConstGeneric::*;
         match cg {
             Global(id) => self.visit_global_decl_id(id),
             Var(id) => self.visit_const_generic_var_id(id),
             Value(lit) => self.visit_literal(lit),
+            Adt(_, fields) => {
+                for cg in fields {
+                    self.visit_const_generic(cg);
+                }
+            }
+            TraitConst(trait_ref, generics, _name) => {
+                self.visit_trait_ref(trait_ref);
+                self.visit_generic_args(generics);
+            }
         }
     }
 
@@ -1335,8 +2024,11 @@ pub trait TypeVisitor {
                 self.visit_trait_decl_id(decl_id);
                 self.visit_trait_clause_id(clause_id)
             },
-            TraitInstanceId::FnPointer(box ty) => {
-                self.visit_ty(ty);
+            TraitInstanceId::FnPointer(_, inputs, box output) => {
+                for ty in inputs {
+                    self.visit_ty(ty);
+                }
+                self.visit_ty(output);
             }
             TraitInstanceId::Closure(fid, generics) => {
                 self.visit_fun_decl_id(fid);
@@ -1385,7 +2077,13 @@ pub trait TypeVisitor {
     }
 
     fn visit_trait_clause(&mut self, c: &TraitClause) {
-        let TraitClause { clause_id, meta: _, trait_id, generics } = c;
+        let TraitClause {
+            clause_id,
+            meta: _,
+            origin: _,
+            trait_id,
+            generics,
+        } = c;
         self.visit_trait_clause_id(clause_id);
         self.visit_trait_decl_id(trait_id);
         self.visit_generic_args(generics);
@@ -1421,6 +2119,10 @@ pub trait TypeVisitor {
     fn visit_fun_sig(&mut self, sig: &FunSig) {
         let FunSig {
             is_unsafe : _,
+            is_const: _,
+            is_async: _,
+            abi: _,
+            is_variadic: _,
             is_closure: _,
             closure_info,
             generics,
@@ -1465,6 +2167,155 @@ pub trait TypeVisitor {
 
 } // make_generic_in_borrows
 
+/// A visitor which folds the types: unlike [MutTypeVisitor], it takes the
+/// values by ownership and returns new values. The default implementation
+/// simply rebuilds the values (i.e., it is the identity).
+///
+/// As for the visitors, the `default_fold_...` functions give access to the
+/// "super" method, and should not be overriden.
+pub trait TypeFolder {
+    /// Called when we dive into a group of bound regions (for instance, the
+    /// regions bound in an arrow type).
+    fn enter_region_group(
+        &mut self,
+        _regions: &RegionId::Vector<RegionVar>,
+        folder: &mut dyn FnMut(&mut Self),
+    ) {
+        folder(self)
+    }
+
+    fn fold_ty(&mut self, ty: Ty) -> Ty {
+        self.default_fold_ty(ty)
+    }
+
+    fn default_fold_ty(&mut self, ty: Ty) -> Ty {
+        match ty {
+            Ty::Adt(id, args) => Ty::Adt(id, self.fold_generic_args(args)),
+            Ty::TypeVar(_) | Ty::Literal(_) | Ty::Never => ty,
+            Ty::Ref(r, box ty, rk) => Ty::Ref(self.fold_region(r), Box::new(self.fold_ty(ty)), rk),
+            Ty::RawPtr(box ty, rk) => Ty::RawPtr(Box::new(self.fold_ty(ty)), rk),
+            Ty::TraitType(trait_ref, generics, name) => Ty::TraitType(
+                self.fold_trait_ref(trait_ref),
+                self.fold_generic_args(generics),
+                name,
+            ),
+            Ty::Arrow(regions, inputs, box output) => self.fold_arrow(regions, inputs, output),
+            Ty::DynTrait(trait_ref, r) => {
+                Ty::DynTrait(self.fold_trait_decl_ref(trait_ref), self.fold_region(r))
+            }
+        }
+    }
+
+    fn fold_arrow(
+        &mut self,
+        regions: RegionId::Vector<RegionVar>,
+        inputs: Vec<Ty>,
+        output: Ty,
+    ) -> Ty {
+        // The folder given to [Self::enter_region_group] can't consume the
+        // values: we move them in and out of options.
+        let mut sig = Some((inputs, output));
+        let mut folded = None;
+        self.enter_region_group(&regions, &mut |f| {
+            let (inputs, output) = sig.take().unwrap();
+            let inputs = inputs.into_iter().map(|ty| f.fold_ty(ty)).collect();
+            folded = Some((inputs, f.fold_ty(output)));
+        });
+        let (inputs, output) = folded.unwrap();
+        Ty::Arrow(regions, inputs, Box::new(output))
+    }
+
+    fn fold_region(&mut self, r: Region) -> Region {
+        r
+    }
+
+    fn fold_const_generic(&mut self, cg: ConstGeneric) -> ConstGeneric {
+        self.default_fold_const_generic(cg)
+    }
+
+    fn default_fold_const_generic(&mut self, cg: ConstGeneric) -> ConstGeneric {
+        match cg {
+            ConstGeneric::TraitConst(trait_ref, generics, name) => ConstGeneric::TraitConst(
+                self.fold_trait_ref(trait_ref),
+                self.fold_generic_args(generics),
+                name,
+            ),
+            _ => cg,
+        }
+    }
+
+    fn fold_generic_args(&mut self, g: GenericArgs) -> GenericArgs {
+        let GenericArgs {
+            regions,
+            types,
+            const_generics,
+            trait_refs,
+        } = g;
+        GenericArgs {
+            regions: regions.into_iter().map(|r| self.fold_region(r)).collect(),
+            types: types.into_iter().map(|ty| self.fold_ty(ty)).collect(),
+            const_generics: const_generics
+                .into_iter()
+                .map(|cg| self.fold_const_generic(cg))
+                .collect(),
+            trait_refs: trait_refs
+                .into_iter()
+                .map(|tr| self.fold_trait_ref(tr))
+                .collect(),
+        }
+    }
+
+    fn fold_trait_ref(&mut self, tr: TraitRef) -> TraitRef {
+        let TraitRef {
+            trait_id,
+            generics,
+            trait_decl_ref,
+        } = tr;
+        TraitRef {
+            trait_id: self.fold_trait_instance_id(trait_id),
+            generics: self.fold_generic_args(generics),
+            trait_decl_ref: self.fold_trait_decl_ref(trait_decl_ref),
+        }
+    }
+
+    fn fold_trait_decl_ref(&mut self, tr: TraitDeclRef) -> TraitDeclRef {
+        let TraitDeclRef { trait_id, generics } = tr;
+        TraitDeclRef {
+            trait_id,
+            generics: self.fold_generic_args(generics),
+        }
+    }
+
+    fn fold_trait_instance_id(&mut self, id: TraitInstanceId) -> TraitInstanceId {
+        self.default_fold_trait_instance_id(id)
+    }
+
+    fn default_fold_trait_instance_id(&mut self, id: TraitInstanceId) -> TraitInstanceId {
+        use TraitInstanceId::*;
+        match id {
+            SelfId | TraitImpl(_) | BuiltinOrAuto(_) | Clause(_) | Unknown(_) => id,
+            ParentClause(box id, decl_id, clause_id) => ParentClause(
+                Box::new(self.fold_trait_instance_id(id)),
+                decl_id,
+                clause_id,
+            ),
+            ItemClause(box id, decl_id, name, clause_id) => ItemClause(
+                Box::new(self.fold_trait_instance_id(id)),
+                decl_id,
+                name,
+                clause_id,
+            ),
+            FnPointer(kind, inputs, box output) => FnPointer(
+                kind,
+                inputs.into_iter().map(|ty| self.fold_ty(ty)).collect(),
+                Box::new(self.fold_ty(output)),
+            ),
+            Closure(fid, generics) => Closure(fid, self.fold_generic_args(generics)),
+            Unsolved(trait_id, generics) => Unsolved(trait_id, self.fold_generic_args(generics)),
+        }
+    }
+}
+
 impl FunSig {
     pub fn fmt_with_ctx<C>(&self, ctx: &C) -> String
     where
@@ -1472,12 +2323,8 @@ impl FunSig {
     {
         let ctx = &ctx.set_generics(&self.generics);
 
-        // Unsafe keyword
-        let unsafe_kw = if self.is_unsafe {
-            "unsafe ".to_string()
-        } else {
-            "".to_string()
-        };
+        // Qualifiers (`unsafe`, etc.)
+        let qualifiers = self.fmt_qualifiers();
 
         // Generic parameters
         let (params, trait_clauses) = self.generics.fmt_with_ctx_with_trait_clauses(ctx);
@@ -1487,6 +2334,9 @@ impl FunSig {
         for ty in &self.inputs {
             args.push(ty.fmt_with_ctx(ctx).to_string());
         }
+        if self.is_variadic {
+            args.push("...".to_string());
+        }
         let args = args.join(", ");
 
         // Return type
@@ -1507,6 +2357,6 @@ impl FunSig {
         );
 
         // Put everything together
-        format!("{unsafe_kw}fn{params}({args}){ret_ty}{clauses}",)
+        format!("{qualifiers}fn{params}({args}){ret_ty}{clauses}",)
     }
 }
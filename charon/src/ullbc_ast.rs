@@ -8,7 +8,7 @@ pub use crate::ullbc_ast_utils::*;
 use crate::values::*;
 use macros::generate_index_type;
 use macros::{EnumAsGetters, EnumIsA, VariantIndexArity, VariantName};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 // Block identifier. Similar to rust's `BasicBlock`.
 generate_index_type!(BlockId);
@@ -26,9 +26,12 @@ pub type GlobalDecls = GlobalDeclId::Map<GlobalDecl>;
 
 pub type TraitDecls = TraitDeclId::Map<TraitDecl>;
 pub type TraitImpls = TraitImplId::Map<TraitImpl>;
+pub type ImplBlocks = ImplBlockId::Map<ImplBlock>;
 
 /// A raw statement: a statement without meta data.
-#[derive(Debug, Clone, EnumIsA, EnumAsGetters, VariantName, Serialize)]
+#[derive(
+    Debug, PartialEq, Eq, Clone, EnumIsA, EnumAsGetters, VariantName, Serialize, Deserialize,
+)]
 pub enum RawStatement {
     Assign(Place, Rvalue),
     FakeRead(Place),
@@ -39,24 +42,54 @@ pub enum RawStatement {
     Deinit(Place),
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Statement {
     pub meta: Meta,
     pub content: RawStatement,
 }
 
-#[derive(Debug, Clone, EnumIsA, EnumAsGetters, VariantName, VariantIndexArity, Serialize)]
+#[derive(
+    Debug,
+    PartialEq,
+    Eq,
+    Clone,
+    EnumIsA,
+    EnumAsGetters,
+    VariantName,
+    VariantIndexArity,
+    Serialize,
+    Deserialize,
+)]
 pub enum SwitchTargets {
     /// Gives the `if` block and the `else` block
     If(BlockId::Id, BlockId::Id),
-    /// Gives the integer type, a map linking values to switch branches, and the
-    /// otherwise block. Note that matches over enumerations are performed by
-    /// switching over the discriminant, which is an integer.
-    SwitchInt(IntegerTy, Vec<(ScalarValue, BlockId::Id)>, BlockId::Id),
+    /// Gives the integer type, a map linking values (or ranges of values, see
+    /// [SwitchValue]) to switch branches, and the otherwise block. Note that
+    /// matches over enumerations are performed by switching over the
+    /// discriminant, which is an integer.
+    SwitchInt(IntegerTy, Vec<(SwitchValue, BlockId::Id)>, BlockId::Id),
+}
+
+/// The kind of a [RawTerminator::Panic].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PanicKind {
+    /// Unwind the stack. If there is a cleanup block, the unwinding continues
+    /// there, otherwise it continues in the caller.
+    ///
+    /// Note that we only have cleanup blocks if we keep the unwinding edges (see
+    /// [crate::cli_options::CliOpts::keep_unwind]). In this case, the cleanup blocks
+    /// end with a panic without cleanup block (i.e., the unwinding resumes).
+    Unwind(Option<BlockId::Id>),
+    /// Abort the program. This happens for instance if we panic while unwinding.
+    Abort,
 }
 
 /// A raw terminator: a terminator without meta data.
-#[derive(Debug, Clone, EnumIsA, EnumAsGetters, Serialize)]
+///
+/// The `unwind` fields give the cleanup blocks to go to if the operation
+/// panics. They are always `None` unless we keep the unwinding edges (see
+/// [crate::cli_options::CliOpts::keep_unwind]).
+#[derive(Debug, PartialEq, Eq, Clone, EnumIsA, EnumAsGetters, Serialize, Deserialize)]
 pub enum RawTerminator {
     Goto {
         target: BlockId::Id,
@@ -65,33 +98,37 @@ pub enum RawTerminator {
         discr: Operand,
         targets: SwitchTargets,
     },
-    Panic,
+    Panic(PanicKind),
     Return,
     Unreachable,
     Drop {
         place: Place,
         target: BlockId::Id,
+        unwind: Option<BlockId::Id>,
     },
     /// Function call.
     /// For now, we only accept calls to top-level functions.
     Call {
         call: Call,
         target: BlockId::Id,
+        unwind: Option<BlockId::Id>,
     },
     Assert {
         cond: Operand,
         expected: bool,
+        kind: AssertKind,
         target: BlockId::Id,
+        unwind: Option<BlockId::Id>,
     },
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Terminator {
     pub meta: Meta,
     pub content: RawTerminator,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BlockData {
     pub statements: Vec<Statement>,
     pub terminator: Terminator,
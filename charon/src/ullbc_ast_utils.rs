@@ -93,30 +93,62 @@ impl Terminator {
                     format!("switch {} -> {}", discr.fmt_with_ctx(ctx), maps)
                 }
             },
-            RawTerminator::Panic => "panic".to_string(),
+            RawTerminator::Panic(PanicKind::Unwind(unwind)) => {
+                format!("panic{}", fmt_unwind(unwind))
+            }
+            RawTerminator::Panic(PanicKind::Abort) => "abort".to_string(),
             RawTerminator::Return => "return".to_string(),
             RawTerminator::Unreachable => "unreachable".to_string(),
-            RawTerminator::Drop { place, target } => {
-                format!("drop {} -> bb{}", place.fmt_with_ctx(ctx), target)
+            RawTerminator::Drop {
+                place,
+                target,
+                unwind,
+            } => {
+                format!(
+                    "drop {} -> bb{}{}",
+                    place.fmt_with_ctx(ctx),
+                    target,
+                    fmt_unwind(unwind)
+                )
             }
-            RawTerminator::Call { call, target } => {
+            RawTerminator::Call {
+                call,
+                target,
+                unwind,
+            } => {
                 let (call_s, _) = fmt_call(ctx, call);
-                format!("{} := {call_s} -> bb{target}", call.dest.fmt_with_ctx(ctx),)
+                let unwind = fmt_unwind(unwind);
+                format!(
+                    "{} := {call_s} -> bb{target}{unwind}",
+                    call.dest.fmt_with_ctx(ctx),
+                )
             }
             RawTerminator::Assert {
                 cond,
                 expected,
+                kind,
                 target,
+                unwind,
             } => format!(
-                "assert({} == {}) -> bb{}",
+                "assert({} == {}, {}) -> bb{}{}",
                 cond.fmt_with_ctx(ctx),
                 expected,
-                target
+                kind.fmt_with_ctx(ctx),
+                target,
+                fmt_unwind(unwind)
             ),
         }
     }
 }
 
+/// Format the cleanup block of a terminator, if there is one.
+fn fmt_unwind(unwind: &Option<BlockId::Id>) -> String {
+    match unwind {
+        None => "".to_string(),
+        Some(unwind) => format!(" (unwind: bb{unwind})"),
+    }
+}
+
 impl BlockData {
     pub fn fmt_with_ctx<C>(&self, tab: &str, ctx: &C) -> String
     where
@@ -200,10 +232,15 @@ impl BlockData {
                     f(meta, nst, op);
                 }
             }
-            Rvalue::Repeat(op, _, _) => {
+            Rvalue::Repeat(op, _, _) | Rvalue::ShallowInitBox(op, _) => {
                 f(meta, nst, op);
             }
-            Rvalue::Global(_) | Rvalue::Discriminant(..) | Rvalue::Ref(_, _) | Rvalue::Len(..) => {
+            Rvalue::Global(_)
+            | Rvalue::Discriminant(..)
+            | Rvalue::Ref(_, _)
+            | Rvalue::AddressOf(_, _)
+            | Rvalue::Len(..)
+            | Rvalue::Nullary(..) => {
                 // No operands: nothing to do
             }
         }
@@ -241,26 +278,19 @@ impl BlockData {
             RawTerminator::Switch { discr, targets: _ } => {
                 f(meta, &mut nst, discr);
             }
-            RawTerminator::Call { call, target: _ } => {
+            RawTerminator::Call { call, .. } => {
                 for arg in &mut call.args {
                     f(meta, &mut nst, arg);
                 }
             }
-            RawTerminator::Assert {
-                cond,
-                expected: _,
-                target: _,
-            } => {
+            RawTerminator::Assert { cond, .. } => {
                 f(meta, &mut nst, cond);
             }
-            RawTerminator::Panic
+            RawTerminator::Panic(_)
             | RawTerminator::Return
             | RawTerminator::Unreachable
             | RawTerminator::Goto { target: _ }
-            | RawTerminator::Drop {
-                place: _,
-                target: _,
-            } => {
+            | RawTerminator::Drop { .. } => {
                 // Nothing to do
             }
         };
@@ -367,21 +397,31 @@ pub trait AstVisitor: crate::expressions::ExprVisitor {
             Switch { discr, targets } => {
                 self.visit_switch(discr, targets);
             }
-            Panic => self.visit_panic(),
+            Panic(kind) => self.visit_panic(kind),
             Return => self.visit_return(),
             Unreachable => self.visit_unreachable(),
-            Drop { place, target } => {
-                self.visit_drop(place, target);
+            Drop {
+                place,
+                target,
+                unwind,
+            } => {
+                self.visit_drop(place, target, unwind);
             }
-            Call { call, target } => {
-                self.visit_call_statement(call, target);
+            Call {
+                call,
+                target,
+                unwind,
+            } => {
+                self.visit_call_statement(call, target, unwind);
             }
             Assert {
                 cond,
                 expected,
+                kind,
                 target,
+                unwind,
             } => {
-                self.visit_assert(cond, expected, target);
+                self.visit_assert(cond, expected, kind, target, unwind);
             }
         }
     }
@@ -399,25 +439,52 @@ pub trait AstVisitor: crate::expressions::ExprVisitor {
         self.visit_switch_targets(targets);
     }
 
-    fn visit_panic(&mut self) {}
+    fn visit_panic(&mut self, kind: &PanicKind) {
+        match kind {
+            PanicKind::Unwind(unwind) => self.visit_unwind(unwind),
+            PanicKind::Abort => (),
+        }
+    }
 
     fn visit_return(&mut self) {}
 
     fn visit_unreachable(&mut self) {}
 
-    fn visit_drop(&mut self, place: &Place, target: &BlockId::Id) {
+    fn visit_drop(&mut self, place: &Place, target: &BlockId::Id, unwind: &Option<BlockId::Id>) {
         self.visit_place(place);
         self.visit_block_id(target);
+        self.visit_unwind(unwind);
     }
 
-    fn visit_call_statement(&mut self, call: &Call, target: &BlockId::Id) {
+    fn visit_call_statement(
+        &mut self,
+        call: &Call,
+        target: &BlockId::Id,
+        unwind: &Option<BlockId::Id>,
+    ) {
         self.visit_call(call);
         self.visit_block_id(target);
+        self.visit_unwind(unwind);
     }
 
-    fn visit_assert(&mut self, cond: &Operand, expected: &bool, target: &BlockId::Id) {
+    fn visit_assert(
+        &mut self,
+        cond: &Operand,
+        expected: &bool,
+        kind: &AssertKind,
+        target: &BlockId::Id,
+        unwind: &Option<BlockId::Id>,
+    ) {
         self.visit_operand(cond);
+        self.visit_assert_kind(kind);
         self.visit_block_id(target);
+        self.visit_unwind(unwind);
+    }
+
+    fn visit_unwind(&mut self, unwind: &Option<BlockId::Id>) {
+        if let Some(unwind) = unwind {
+            self.visit_block_id(unwind);
+        }
     }
 
     fn visit_block_id(&mut self, id: &BlockId::Id) {}
@@ -440,7 +507,7 @@ pub trait AstVisitor: crate::expressions::ExprVisitor {
     fn visit_switch_int(
         &mut self,
         int_ty: &IntegerTy,
-        branches: &Vec<(ScalarValue, BlockId::Id)>,
+        branches: &Vec<(SwitchValue, BlockId::Id)>,
         otherwise: &BlockId::Id,
     ) {
         for (_, br) in branches {
@@ -20,11 +20,13 @@
 //! only be performed by terminators -, meaning that MIR graphs don't have that
 //! many nodes and edges).
 
-use crate::expressions::Place;
+use crate::expressions::{MutExprVisitor, Place};
 use crate::formatter::{Formatter, IntoFormatter};
 use crate::llbc_ast as tgt;
+use crate::llbc_ast::MutAstVisitor;
 use crate::meta::{combine_meta, Meta};
 use crate::translate_ctx::TransCtx;
+use crate::types::MutTypeVisitor;
 use crate::ullbc_ast::FunDeclId;
 use crate::ullbc_ast::{self as src, GlobalDeclId};
 use crate::values as v;
@@ -61,17 +63,15 @@ fn get_block_targets(body: &src::ExprBody, block_id: src::BlockId::Id) -> Vec<sr
 
     match &block.terminator.content {
         src::RawTerminator::Goto { target }
-        | src::RawTerminator::Drop { place: _, target }
-        | src::RawTerminator::Call { call: _, target }
-        | src::RawTerminator::Assert {
-            cond: _,
-            expected: _,
-            target,
-        } => {
+        | src::RawTerminator::Drop { target, .. }
+        | src::RawTerminator::Call { target, .. }
+        | src::RawTerminator::Assert { target, .. } => {
+            // We ignore the unwinding edges: the control-flow reconstruction
+            // doesn't preserve them
             vec![*target]
         }
         src::RawTerminator::Switch { discr: _, targets } => targets.get_targets(),
-        src::RawTerminator::Panic
+        src::RawTerminator::Panic(_)
         | src::RawTerminator::Unreachable
         | src::RawTerminator::Return => {
             vec![]
@@ -157,7 +157,7 @@ fn block_is_error(body: &src::ExprBody, block_id: src::BlockId::Id) -> bool {
     let block = body.body.get(block_id).unwrap();
     use src::RawTerminator::*;
     match &block.terminator.content {
-        Panic | Unreachable => true,
+        Panic(_) | Unreachable => true,
         Goto { .. } | Switch { .. } | Return { .. } | Drop { .. } | Call { .. } | Assert { .. } => {
             false
         }
@@ -1423,11 +1423,7 @@ fn combine_statement_and_statement(
     next_st: Option<Box<tgt::Statement>>,
 ) -> Box<tgt::Statement> {
     match next_st {
-        Some(next_st) => {
-            let meta = combine_meta(&statement.meta, &next_st.meta);
-            let st = tgt::RawStatement::Sequence(statement, next_st);
-            Box::new(tgt::Statement::new(meta, st))
-        }
+        Some(next_st) => Box::new(tgt::new_sequence(*statement, *next_st)),
         None => statement,
     }
 }
@@ -1500,11 +1496,11 @@ fn translate_child_block(
     // Check if this is a backward call
     match get_goto_kind(info.exits_info, parent_loops, switch_exit_blocks, child_id) {
         GotoKind::Break(index) => {
-            let st = tgt::RawStatement::Break(index);
+            let st = tgt::RawStatement::Break(index, None);
             Some(Box::new(tgt::Statement::new(parent_meta, st)))
         }
         GotoKind::Continue(index) => {
-            let st = tgt::RawStatement::Continue(index);
+            let st = tgt::RawStatement::Continue(index, None);
             Some(Box::new(tgt::Statement::new(parent_meta, st)))
         }
         // If we are going to an exit block we simply ignore the goto
@@ -1558,7 +1554,7 @@ fn translate_terminator(
     let src_meta = terminator.meta;
 
     match &terminator.content {
-        src::RawTerminator::Panic | src::RawTerminator::Unreachable => Some(Box::new(
+        src::RawTerminator::Panic(_) | src::RawTerminator::Unreachable => Some(Box::new(
             tgt::Statement::new(src_meta, tgt::RawStatement::Panic),
         )),
         src::RawTerminator::Return => Some(Box::new(tgt::Statement::new(
@@ -1572,7 +1568,7 @@ fn translate_terminator(
             terminator.meta,
             *target,
         ),
-        src::RawTerminator::Drop { place, target } => {
+        src::RawTerminator::Drop { place, target, .. } => {
             let opt_child = translate_child_block(
                 info,
                 parent_loops,
@@ -1586,7 +1582,7 @@ fn translate_terminator(
             ));
             Some(combine_statement_and_statement(st, opt_child))
         }
-        src::RawTerminator::Call { call, target } => {
+        src::RawTerminator::Call { call, target, .. } => {
             let opt_child = translate_child_block(
                 info,
                 parent_loops,
@@ -1601,7 +1597,9 @@ fn translate_terminator(
         src::RawTerminator::Assert {
             cond,
             expected,
+            kind,
             target,
+            ..
         } => {
             let opt_child = translate_child_block(
                 info,
@@ -1613,6 +1611,7 @@ fn translate_terminator(
             let st = tgt::RawStatement::Assert(tgt::Assert {
                 cond: cond.clone(),
                 expected: *expected,
+                kind: kind.clone(),
             });
             let st = Box::new(tgt::Statement::new(src_meta, st));
             Some(combine_statement_and_statement(st, opt_child))
@@ -1666,7 +1665,7 @@ fn translate_terminator(
                     // - translated blocks
                     let mut branches: LinkedHashMap<
                         src::BlockId::Id,
-                        (Vec<v::ScalarValue>, tgt::Statement),
+                        (Vec<v::SwitchValue>, tgt::Statement),
                     > = LinkedHashMap::new();
 
                     // Translate the children expressions
@@ -1693,7 +1692,7 @@ fn translate_terminator(
                             branches.insert(*bid, (vec![*v], *exp));
                         }
                     }
-                    let targets_exps: Vec<(Vec<v::ScalarValue>, tgt::Statement)> =
+                    let targets_exps: Vec<(Vec<v::SwitchValue>, tgt::Statement)> =
                         branches.into_iter().map(|(_, x)| x).collect();
 
                     let otherwise_exp = translate_child_block(
@@ -1731,11 +1730,7 @@ fn combine_expressions(
         None => exp2,
         Some(exp1) => match exp2 {
             None => Some(exp1),
-            Some(exp2) => {
-                let meta = combine_meta(&exp1.meta, &exp2.meta);
-                let st = tgt::RawStatement::Sequence(exp1, exp2);
-                Some(Box::new(tgt::Statement::new(meta, st)))
-            }
+            Some(exp2) => Some(Box::new(tgt::new_sequence(*exp1, *exp2))),
         },
     }
 }
@@ -1759,20 +1754,18 @@ fn is_terminal_explore(num_loops: usize, st: &tgt::Statement) -> bool {
         | tgt::RawStatement::Call(_)
         | tgt::RawStatement::Nop => false,
         tgt::RawStatement::Panic | tgt::RawStatement::Return => true,
-        tgt::RawStatement::Break(index) => *index >= num_loops,
-        tgt::RawStatement::Continue(_index) => true,
-        tgt::RawStatement::Sequence(st1, st2) => {
-            if is_terminal_explore(num_loops, st1) {
-                true
-            } else {
-                is_terminal_explore(num_loops, st2)
-            }
-        }
+        tgt::RawStatement::Break(index, _) => *index >= num_loops,
+        tgt::RawStatement::Continue(..) => true,
+        tgt::RawStatement::Block(sts) => sts.iter().any(|st| is_terminal_explore(num_loops, st)),
         tgt::RawStatement::Switch(switch) => switch
             .get_targets()
             .iter()
             .all(|tgt_st| is_terminal_explore(num_loops, tgt_st)),
-        tgt::RawStatement::Loop(loop_st) => is_terminal_explore(num_loops + 1, loop_st),
+        tgt::RawStatement::Loop(loop_st, _) => is_terminal_explore(num_loops + 1, loop_st),
+        // We exit the `for` loops when the iterator is exhausted
+        tgt::RawStatement::ForLoop(_) => false,
+        // We return early only if the operand is a residual
+        tgt::RawStatement::QuestionMark(_) => false,
     }
 }
 
@@ -1865,7 +1858,10 @@ fn translate_block(
 
         // Put the whole loop body inside a `Loop` wrapper
         let exp = exp.unwrap();
-        let exp = Box::new(tgt::Statement::new(exp.meta, tgt::RawStatement::Loop(exp)));
+        let exp = Box::new(tgt::Statement::new(
+            exp.meta,
+            tgt::RawStatement::Loop(exp, None),
+        ));
 
         // Add the exit block
         if let Some(exit_block_id) = next_block {
@@ -1899,6 +1895,56 @@ fn translate_block(
     }
 }
 
+/// Visitor to insert the loop labels (see [tgt::LoopLabel]).
+struct LoopLabeler {
+    /// The labels of the loops we are currently exploring, from the outermost
+    /// loop to the innermost one.
+    loops: Vec<Option<tgt::LoopLabel>>,
+    /// The number of labels we introduced so far
+    num_labels: usize,
+}
+
+impl LoopLabeler {
+    /// Return the label of the loop a `break` or a `continue` with the given
+    /// relative index goes to, if this loop is not the current loop.
+    fn label_of(&mut self, index: usize) -> Option<tgt::LoopLabel> {
+        if index == 0 {
+            return None;
+        }
+        let i = self.loops.len() - 1 - index;
+        if self.loops[i].is_none() {
+            self.loops[i] = Some(self.num_labels);
+            self.num_labels += 1;
+        }
+        self.loops[i]
+    }
+}
+
+impl MutTypeVisitor for LoopLabeler {}
+impl MutExprVisitor for LoopLabeler {}
+
+impl MutAstVisitor for LoopLabeler {
+    fn spawn(&mut self, visitor: &mut dyn FnMut(&mut Self)) {
+        visitor(self)
+    }
+
+    fn merge(&mut self) {}
+
+    fn visit_raw_statement(&mut self, st: &mut tgt::RawStatement) {
+        match st {
+            tgt::RawStatement::Loop(body, label) => {
+                self.loops.push(None);
+                self.visit_statement(body);
+                *label = self.loops.pop().unwrap();
+            }
+            tgt::RawStatement::Break(index, label) | tgt::RawStatement::Continue(index, label) => {
+                *label = self.label_of(*index)
+            }
+            _ => self.default_visit_raw_statement(st),
+        }
+    }
+}
+
 fn translate_body(no_code_duplication: bool, src_body: &src::ExprBody) -> tgt::ExprBody {
     // Explore the function body to create the control-flow graph without backward
     // edges, and identify the loop entries (which are destinations of backward edges).
@@ -1924,7 +1970,7 @@ fn translate_body(no_code_duplication: bool, src_body: &src::ExprBody) -> tgt::E
         exits_info: &exits_info,
         explored: &mut explored,
     };
-    let stmt = translate_block(
+    let mut stmt = translate_block(
         &mut info,
         &Vector::new(),
         &im::HashSet::new(),
@@ -1932,6 +1978,13 @@ fn translate_body(no_code_duplication: bool, src_body: &src::ExprBody) -> tgt::E
     )
     .unwrap();
 
+    // Label the loops which are the targets of multi-level exits
+    let mut labeler = LoopLabeler {
+        loops: Vec::new(),
+        num_labels: 0,
+    };
+    labeler.visit_statement(&mut stmt);
+
     // Sanity: check that we translated all the blocks
     for (bid, _) in src_body.body.iter_indexed_values() {
         assert!(explored.contains(&bid));
@@ -1941,6 +1994,8 @@ fn translate_body(no_code_duplication: bool, src_body: &src::ExprBody) -> tgt::E
         meta: src_body.meta,
         arg_count: src_body.arg_count,
         locals: src_body.locals.clone(),
+        var_debug_info: src_body.var_debug_info.clone(),
+        backtraces: src_body.backtraces.clone(),
         body: *stmt,
     }
 }
@@ -1960,6 +2015,7 @@ fn translate_function(ctx: &TransCtx, src_def_id: FunDeclId::Id) -> tgt::FunDecl
         def_id: src_def.def_id,
         rust_id: src_def.rust_id,
         meta: src_def.meta,
+        attr_info: src_def.attr_info.clone(),
         is_local: src_def.is_local,
         name: src_def.name.clone(),
         signature: src_def.signature.clone(),
@@ -1968,6 +2024,8 @@ fn translate_function(ctx: &TransCtx, src_def_id: FunDeclId::Id) -> tgt::FunDecl
             .body
             .as_ref()
             .map(|b| translate_body(ctx.no_code_duplication, b)),
+        pure_form: None,
+        call_depth: src_def.call_depth,
     }
 }
 
@@ -1985,9 +2043,12 @@ fn translate_global(ctx: &TransCtx, global_id: GlobalDeclId::Id) -> tgt::GlobalD
         def_id: src_def.def_id,
         rust_id: src_def.rust_id,
         meta: src_def.meta,
+        attr_info: src_def.attr_info.clone(),
         is_local: src_def.is_local,
         name: src_def.name.clone(),
+        parent_params_info: src_def.parent_params_info.clone(),
         ty: src_def.ty.clone(),
+        kind: src_def.kind,
         body: src_def
             .body
             .as_ref()
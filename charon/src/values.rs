@@ -3,7 +3,7 @@
 pub use crate::values_utils::*;
 use core::hash::Hash;
 use macros::{generate_index_type, EnumAsGetters, EnumIsA, VariantIndexArity, VariantName};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 // We need to manipulate a lot of indices for the types, variables, definitions,
 // etc. In order not to confuse them, we define an index type for every one of
@@ -25,6 +25,7 @@ generate_index_type!(VarId);
     EnumIsA,
     EnumAsGetters,
     Serialize,
+    Deserialize,
     Hash,
     PartialOrd,
     Ord,
@@ -33,6 +34,12 @@ pub enum Literal {
     Scalar(ScalarValue),
     Bool(bool),
     Char(char),
+    /// A string literal. The constant expression has type `&str`: the literal
+    /// is the reference itself.
+    Str(String),
+    /// A byte-string literal (ex.: `b"hello"`), whose content we store as raw
+    /// bytes. The constant expression has type `&[u8; N]`.
+    ByteStr(Vec<u8>),
 }
 
 /// It might be a good idea to use a structure:
@@ -73,3 +80,18 @@ pub enum ScalarValue {
     U64(u64),
     U128(u128),
 }
+
+/// The values matched by a branch of a switch over an integer (see
+/// [crate::ullbc_ast::SwitchTargets::SwitchInt]).
+///
+/// We group the consecutive values which lead to the same branch into ranges:
+/// otherwise, matches like `match c { b'0' | b'1' | ... | b'9' => ... }` lead
+/// to a lot of targets.
+#[derive(
+    Debug, PartialEq, Eq, Copy, Clone, EnumIsA, EnumAsGetters, VariantName, Serialize, Deserialize,
+)]
+pub enum SwitchValue {
+    Value(ScalarValue),
+    /// A range of values (the bounds are included).
+    Range(ScalarValue, ScalarValue),
+}
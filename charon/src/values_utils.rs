@@ -1,7 +1,7 @@
 //! Implementations for [crate::values]
 use crate::types::*;
 use crate::values::*;
-use serde::{Serialize, Serializer};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
 impl VarId::Id {
     pub fn to_pretty_string(self) -> String {
@@ -233,12 +233,72 @@ impl std::fmt::Display for ScalarValue {
     }
 }
 
+impl SwitchValue {
+    /// The bounds of the matched values (included).
+    pub fn bounds(&self) -> (ScalarValue, ScalarValue) {
+        match self {
+            SwitchValue::Value(v) => (*v, *v),
+            SwitchValue::Range(lo, hi) => (*lo, *hi),
+        }
+    }
+
+    /// Check if a value is matched.
+    pub fn contains(&self, v: &ScalarValue) -> bool {
+        let (lo, hi) = self.bounds();
+        lo <= *v && *v <= hi
+    }
+
+    /// Group the consecutive values which lead to the same target into ranges
+    /// (we preserve the order of the targets).
+    pub fn group_targets<T: PartialEq>(targets: Vec<(ScalarValue, T)>) -> Vec<(SwitchValue, T)> {
+        let mut grouped: Vec<(SwitchValue, T)> = Vec::new();
+        for (v, tgt) in targets {
+            if let Some((last, last_tgt)) = grouped.last_mut()
+                && *last_tgt == tgt
+            {
+                let (lo, hi) = last.bounds();
+                if is_successor(&hi, &v) {
+                    *last = SwitchValue::Range(lo, v);
+                    continue;
+                }
+            }
+            grouped.push((SwitchValue::Value(v), tgt));
+        }
+        grouped
+    }
+}
+
+/// Check if `y` is `x + 1` (the values must have the same type).
+fn is_successor(x: &ScalarValue, y: &ScalarValue) -> bool {
+    if x.get_integer_ty() != y.get_integer_ty() {
+        return false;
+    }
+    match (x.as_int(), y.as_int()) {
+        (Ok(x), Ok(y)) => x.checked_add(1) == Some(y),
+        _ => match (x.as_uint(), y.as_uint()) {
+            (Ok(x), Ok(y)) => x.checked_add(1) == Some(y),
+            _ => false,
+        },
+    }
+}
+
+impl std::fmt::Display for SwitchValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
+        match self {
+            SwitchValue::Value(v) => write!(f, "{v}"),
+            SwitchValue::Range(lo, hi) => write!(f, "{lo}..={hi}"),
+        }
+    }
+}
+
 impl std::fmt::Display for Literal {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
         match self {
             Literal::Scalar(v) => write!(f, "{v}"),
             Literal::Bool(v) => write!(f, "{v}"),
             Literal::Char(v) => write!(f, "{v}"),
+            Literal::Str(v) => write!(f, "{v:?}"),
+            Literal::ByteStr(v) => write!(f, "b\"{}\"", v.escape_ascii()),
         }
     }
 }
@@ -268,3 +328,47 @@ impl Serialize for ScalarValue {
         serializer.serialize_newtype_variant(enum_name, variant_index, variant_name, &v)
     }
 }
+
+impl<'de> Deserialize<'de> for ScalarValue {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        use serde::de::Error;
+
+        /// The values are serialized to strings: see the implementation of
+        /// [Serialize] for [ScalarValue].
+        #[derive(Deserialize)]
+        enum Repr {
+            Isize(String),
+            I8(String),
+            I16(String),
+            I32(String),
+            I64(String),
+            I128(String),
+            Usize(String),
+            U8(String),
+            U16(String),
+            U32(String),
+            U64(String),
+            U128(String),
+        }
+
+        let invalid = |v: &str| D::Error::custom(format!("Invalid scalar value: {v}"));
+        let v = match Repr::deserialize(deserializer)? {
+            Repr::Isize(v) => ScalarValue::Isize(v.parse().map_err(|_| invalid(&v))?),
+            Repr::I8(v) => ScalarValue::I8(v.parse().map_err(|_| invalid(&v))?),
+            Repr::I16(v) => ScalarValue::I16(v.parse().map_err(|_| invalid(&v))?),
+            Repr::I32(v) => ScalarValue::I32(v.parse().map_err(|_| invalid(&v))?),
+            Repr::I64(v) => ScalarValue::I64(v.parse().map_err(|_| invalid(&v))?),
+            Repr::I128(v) => ScalarValue::I128(v.parse().map_err(|_| invalid(&v))?),
+            Repr::Usize(v) => ScalarValue::Usize(v.parse().map_err(|_| invalid(&v))?),
+            Repr::U8(v) => ScalarValue::U8(v.parse().map_err(|_| invalid(&v))?),
+            Repr::U16(v) => ScalarValue::U16(v.parse().map_err(|_| invalid(&v))?),
+            Repr::U32(v) => ScalarValue::U32(v.parse().map_err(|_| invalid(&v))?),
+            Repr::U64(v) => ScalarValue::U64(v.parse().map_err(|_| invalid(&v))?),
+            Repr::U128(v) => ScalarValue::U128(v.parse().map_err(|_| invalid(&v))?),
+        };
+        Ok(v)
+    }
+}
@@ -0,0 +1,9 @@
+//! The second crate of a batch: the references to the declarations of
+//! `batch_base` must reuse the ids of those declarations.
+
+use batch_base::{incr, Counter};
+
+pub fn incr_twice(c: &mut Counter) {
+    incr(c);
+    incr(c);
+}
@@ -0,0 +1,9 @@
+//! The first crate of a batch (see `test-batch` in the Makefile).
+
+pub struct Counter {
+    pub value: u32,
+}
+
+pub fn incr(c: &mut Counter) {
+    c.value += 1;
+}
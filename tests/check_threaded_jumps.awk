@@ -0,0 +1,50 @@
+# Check that no terminator jumps to a trampoline (a block which only contains
+# a `goto`), in ULLBC bodies pretty-printed by `charon print`. This checks the
+# jump threading performed by the `simplify_cfg` micro-pass.
+
+function check(  b) {
+    for (b in trampolines) {
+        if (b in targets) {
+            print decl ": jump to the trampoline bb" b
+            failed = 1
+        }
+    }
+    delete trampolines
+    delete targets
+}
+
+# The declarations (and their braces) start at the beginning of the line
+/^[^ ]/ {
+    check()
+    if ($0 !~ /^[{}]/)
+        decl = $0
+    next
+}
+
+/^ *bb[0-9]+: \{$/ {
+    match($0, /bb[0-9]+/)
+    block = substr($0, RSTART + 2, RLENGTH - 2)
+    lines = 0
+    next
+}
+
+/^ *\}$/ {
+    if (lines == 1 && last ~ /^ *goto bb[0-9]+;$/)
+        trampolines[block] = 1
+    next
+}
+
+{
+    lines++
+    last = $0
+    s = $0
+    while (match(s, /bb[0-9]+/)) {
+        targets[substr(s, RSTART + 2, RLENGTH - 2)] = 1
+        s = substr(s, RSTART + RLENGTH)
+    }
+}
+
+END {
+    check()
+    exit failed
+}
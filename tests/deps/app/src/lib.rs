@@ -0,0 +1,8 @@
+//! Extracted with `--extract-dependencies deps_lib`: the bodies of the items
+//! of `deps_lib` whose MIR is available must be translated.
+
+use deps_lib::{double, first, triple};
+
+pub fn use_deps(x: u32) -> u32 {
+    first(&[double(x), triple(x)])
+}
@@ -0,0 +1,19 @@
+//! A dependency of `deps_app` (see `test-extract_dependencies` in the
+//! Makefile).
+
+/// Generic: the MIR is in the crate metadata.
+pub fn first<T: Copy>(x: &[T; 2]) -> T {
+    x[0]
+}
+
+/// Inline: the MIR is in the crate metadata.
+#[inline]
+pub fn double(x: u32) -> u32 {
+    x.wrapping_mul(2)
+}
+
+/// Neither generic nor inline: the MIR is not in the crate metadata, and the
+/// function must remain opaque.
+pub fn triple(x: u32) -> u32 {
+    x.wrapping_mul(3)
+}
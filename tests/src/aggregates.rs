@@ -0,0 +1,28 @@
+//! Exercise the reconstruction of the enumeration aggregates (see the
+//! Makefile).
+
+pub enum E {
+    A,
+    B(u32, bool),
+    C { x: u32 },
+}
+
+pub fn build_b(x: u32, b: bool) -> E {
+    E::B(x, b)
+}
+
+pub fn build_c(x: u32) -> E {
+    E::C { x }
+}
+
+pub fn build_some(x: u32) -> Option<u32> {
+    Some(x)
+}
+
+pub fn build_in_branches(b: bool, x: u32) -> E {
+    if b {
+        E::B(x, b)
+    } else {
+        E::A
+    }
+}
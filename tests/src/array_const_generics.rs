@@ -18,3 +18,8 @@ pub fn const_gen_ret<const N: usize>() -> usize {
 pub fn init_array_variable_len<const LEN: usize>() -> [u8; LEN] {
     [0u8; LEN]
 }
+
+// The repeated value is not a constant
+pub fn repeat_value<T: Copy, const N: usize>(x: T) -> [T; N] {
+    [x; N]
+}
@@ -0,0 +1,14 @@
+//! Exercise the user-supplied assumed functions (we extract this file with
+//! and without `assumed.toml`, see the Makefile).
+
+/// Declared as a panic in `assumed.toml`
+pub fn fatal(code: u32) -> ! {
+    std::process::exit(code as i32)
+}
+
+pub fn checked_div(x: u32, y: u32) -> u32 {
+    if y == 0 {
+        fatal(1)
+    }
+    x / y
+}
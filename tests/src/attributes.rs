@@ -0,0 +1,22 @@
+//! Exercise the doc comments and the attributes we keep in the generated
+//! files (see the Makefile). This file is not a module of the test crate:
+//! registering a tool is only allowed at the root of a crate.
+#![feature(register_tool)]
+#![register_tool(verify)]
+
+/// Add two integers.
+///
+/// The result must not overflow.
+#[inline(always)]
+#[must_use = "the sum is not used"]
+#[verify::requires(x <= 100 && y <= 100)]
+pub fn add(x: u32, y: u32) -> u32 {
+    x + y
+}
+
+/// A point in the plane.
+#[verify::invariant]
+pub struct Point {
+    pub x: u32,
+    pub y: u32,
+}
@@ -0,0 +1,22 @@
+//! Exercise the auto traits and the negative implementations (see the
+//! Makefile). This file is not a module of the test crate: the negative
+//! implementations require a feature, which is only allowed at the root of a
+//! crate.
+#![feature(negative_impls)]
+
+/// Rustc implements [Send] and [Sync] for this type.
+pub struct Pair {
+    pub x: u32,
+    pub y: u32,
+}
+
+/// We forbid sending this type to other threads.
+pub struct Local {
+    pub x: u32,
+}
+
+impl !Send for Local {}
+
+pub fn swap(p: Pair) -> Pair {
+    Pair { x: p.y, y: p.x }
+}
@@ -0,0 +1,44 @@
+//! Exercise the reconstruction of the lazy boolean operators (see the
+//! Makefile).
+
+fn check(x: u32) -> bool {
+    x > 0
+}
+
+/// Pure operands, used as a value: must be rewritten.
+pub fn and_value(a: bool, b: bool) -> bool {
+    a && b
+}
+
+/// Pure operands, used as a value: must be rewritten.
+pub fn or_value(a: bool, b: bool) -> bool {
+    a || b
+}
+
+/// Pure operands, used as a condition: must be rewritten.
+pub fn and_cond(x: u32, y: u32) -> u32 {
+    if x > 0 && y > 0 {
+        1
+    } else {
+        0
+    }
+}
+
+/// Pure operands, used as a condition: must be rewritten.
+pub fn or_cond(x: u32, y: u32) -> u32 {
+    if x > 0 || y > 0 {
+        1
+    } else {
+        0
+    }
+}
+
+/// The second operand is a call: must not be rewritten.
+pub fn and_call(a: bool, x: u32) -> bool {
+    a && check(x)
+}
+
+/// The second operand may fail (division by zero): must not be rewritten.
+pub fn or_div(a: bool, x: u32, y: u32) -> bool {
+    a || x / y > 0
+}
@@ -0,0 +1,14 @@
+//! Exercise the allocation of boxes: `vec![...]` and the optimized MIR
+//! allocate and initialize the boxes without calling `Box::new`.
+
+pub fn new_box(x: u32) -> Box<u32> {
+    Box::new(x)
+}
+
+pub fn new_pair(x: u32, y: u32) -> Box<(u32, u32)> {
+    Box::new((x, y))
+}
+
+pub fn new_vec(x: u32, y: u32) -> Vec<u32> {
+    vec![x, y, 0]
+}
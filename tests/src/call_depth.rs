@@ -0,0 +1,54 @@
+//! Exercise the computation of the bounds on the call depth (see the
+//! Makefile).
+
+pub fn leaf(x: u32) -> u32 {
+    x / 2
+}
+
+/// The loop doesn't increase the depth.
+pub fn middle(n: u32) -> u32 {
+    let mut s = 0;
+    let mut i = 0;
+    while i < n {
+        s = leaf(s);
+        i += 1;
+    }
+    s
+}
+
+pub fn top(n: u32) -> u32 {
+    middle(n) + leaf(n)
+}
+
+pub fn recursive(n: u32) -> u32 {
+    if n == 0 {
+        0
+    } else {
+        recursive(n - 1)
+    }
+}
+
+pub fn even(n: u32) -> bool {
+    if n == 0 {
+        true
+    } else {
+        odd(n - 1)
+    }
+}
+
+pub fn odd(n: u32) -> bool {
+    if n == 0 {
+        false
+    } else {
+        even(n - 1)
+    }
+}
+
+pub fn calls_recursive(n: u32) -> u32 {
+    recursive(n)
+}
+
+/// The call depends on the trait clause: we can't bound it.
+pub fn generic<T: Clone>(x: &T) -> T {
+    x.clone()
+}
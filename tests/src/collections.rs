@@ -0,0 +1,33 @@
+//! Exercise the builtin models of the common operations on the collections
+//! of the standard library.
+use std::ops::{Index, IndexMut};
+
+pub fn vec_ops(x: u32) -> u32 {
+    let mut v = Vec::new();
+    v.push(x);
+    v.push(1);
+    v[0] = v[1];
+    v[0] + v.len() as u32
+}
+
+pub fn option_ops(x: Option<u32>) -> u32 {
+    if x.is_some() {
+        x.unwrap()
+    } else {
+        0
+    }
+}
+
+pub fn mem_ops(x: &mut u32, y: &mut u32) -> u32 {
+    std::mem::swap(x, y);
+    let z = std::mem::replace(x, 0);
+    z + std::mem::take(y)
+}
+
+pub fn slice_index<T>(s: &[T], i: usize) -> &T {
+    s.index(i)
+}
+
+pub fn slice_index_mut<T>(s: &mut [T], i: usize) -> &mut T {
+    s.index_mut(i)
+}
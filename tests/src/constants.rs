@@ -81,3 +81,26 @@ pub static S1: u32 = 6;
 pub static S2: u32 = incr(S1);
 pub static S3: Pair<u32, u32> = P3;
 pub static S4: Pair<u32, u32> = mk_pair1(7, 8);
+
+pub fn get_s1_ref() -> &'static u32 {
+    &S1
+}
+
+pub static mut COUNTER: u32 = 0;
+
+pub fn reset_counter() {
+    unsafe {
+        COUNTER = 0;
+    }
+}
+
+pub static CELL: std::sync::atomic::AtomicU32 = std::sync::atomic::AtomicU32::new(0);
+
+// Strings
+
+pub const MSG: &str = "hello";
+pub const BYTES: &[u8; 5] = b"hello";
+
+pub fn msg_len() -> usize {
+    MSG.len()
+}
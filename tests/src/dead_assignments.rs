@@ -0,0 +1,21 @@
+//! Exercise the removal of the dead assignments (see the Makefile).
+
+/// The comparisons are never read and can't fail: they must be removed.
+pub fn dead_pure(x: u32, y: u32) -> u32 {
+    let _eq = x == y;
+    let _lt = x < y;
+    let _copy = x;
+    y
+}
+
+/// The division may fail (division by zero): it must be kept.
+pub fn dead_div(x: u32, y: u32) -> u32 {
+    let _q = x / y;
+    x
+}
+
+/// The (checked) addition may overflow: it must be kept.
+pub fn dead_add(x: u32, y: u32) -> u32 {
+    let _s = x + y;
+    y
+}
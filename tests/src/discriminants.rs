@@ -0,0 +1,48 @@
+//! Exercise the extraction of the discriminants of the variants (see the
+//! Makefile).
+
+/// The discriminants are the variant indices.
+pub enum Default {
+    A,
+    B,
+}
+
+/// The discriminants are neither the variant indices, nor in increasing
+/// order.
+#[repr(u8)]
+pub enum Explicit {
+    A = 3,
+    B = 7,
+    C = 1,
+}
+
+/// The discriminants are implicitly incremented from the last explicit one.
+pub enum Implicit {
+    A = -2,
+    B,
+    C = 5,
+    D,
+}
+
+pub fn match_explicit(x: Explicit) -> u32 {
+    match x {
+        Explicit::A => 10,
+        Explicit::B => 20,
+        Explicit::C => 30,
+    }
+}
+
+pub fn match_implicit(x: Implicit) -> u32 {
+    match x {
+        Implicit::A => 10,
+        Implicit::D => 20,
+        _ => 30,
+    }
+}
+
+pub fn match_default(x: Default) -> u32 {
+    match x {
+        Default::A => 10,
+        Default::B => 20,
+    }
+}
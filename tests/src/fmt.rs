@@ -0,0 +1,24 @@
+//! Exercise the handling of the formatting machinery (we extract this file
+//! with several policies, see the Makefile).
+use std::fmt;
+
+pub struct Point {
+    pub x: u32,
+    pub y: u32,
+}
+
+impl fmt::Display for Point {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "({}, {})", self.x, self.y)
+    }
+}
+
+pub fn show(p: &Point) -> String {
+    format!("{p}")
+}
+
+/// The calls to `println!` are removed with `--fmt-machinery drop`
+pub fn incr(x: u32) -> u32 {
+    println!("incr: {x}");
+    x + 1
+}
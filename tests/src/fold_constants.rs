@@ -0,0 +1,26 @@
+//! Exercise the constant folding (see the Makefile).
+
+/// The unary and binary operations on constants must be evaluated.
+pub fn fold_ops() -> i32 {
+    -(2 * 3) + 1240
+}
+
+/// The results which fit in the type must be evaluated.
+pub fn in_bounds() -> u8 {
+    200 + 55
+}
+
+/// The operations which overflow must not be evaluated.
+#[allow(arithmetic_overflow)]
+pub fn overflow() -> u8 {
+    201 + 100
+}
+
+/// The switches over constants must be simplified to jumps.
+pub fn fold_switch() -> u32 {
+    if 1 < 2 {
+        4321
+    } else {
+        8765
+    }
+}
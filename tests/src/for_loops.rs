@@ -0,0 +1,57 @@
+//! Exercise the reconstruction of the `for` loops (see the Makefile).
+
+/// Must be rewritten, with a binder.
+pub fn sum_range(n: u32) -> u32 {
+    let mut s = 0;
+    for i in 0..n {
+        s += i;
+    }
+    s
+}
+
+/// Must be rewritten, without binder (the items are ignored).
+pub fn count(n: u32) -> u32 {
+    let mut c = 0;
+    for _ in 0..n {
+        c += 1;
+    }
+    c
+}
+
+/// Both loops must be rewritten.
+pub fn nested(n: u32) -> u32 {
+    let mut s = 0;
+    for i in 0..n {
+        for j in 0..i {
+            s += j;
+        }
+    }
+    s
+}
+
+/// Must not be rewritten: there is no call to `into_iter`.
+pub fn manual_next(n: u32) -> u32 {
+    let mut it = 0..n;
+    let mut s = 0;
+    while let Some(i) = it.next() {
+        s += i;
+    }
+    s
+}
+
+/// Must not be rewritten: the body uses the iterator.
+pub fn uses_iterator(n: u32) -> u32 {
+    let mut it = 0..n;
+    let mut s = 0;
+    loop {
+        let r = &mut it;
+        match r.next() {
+            None => break,
+            Some(i) => {
+                s += i;
+                it.next();
+            }
+        }
+    }
+    s
+}
@@ -0,0 +1,25 @@
+//! Exercise the functions declared in `extern` blocks (see the Makefile).
+
+extern "C" {
+    fn abs(x: i32) -> i32;
+    #[link_name = "llabs"]
+    fn abs_i64(x: i64) -> i64;
+    fn printf(format: *const u8, ...) -> i32;
+}
+
+pub fn call_abs(x: i32) -> i32 {
+    unsafe { abs(x) }
+}
+
+pub fn call_abs_i64(x: i64) -> i64 {
+    unsafe { abs_i64(x) }
+}
+
+pub fn print_int(x: i32) -> i32 {
+    unsafe { printf("%d\n\0".as_ptr(), x) }
+}
+
+#[no_mangle]
+pub extern "C" fn exported_abs(x: i32) -> i32 {
+    call_abs(x)
+}
@@ -0,0 +1,18 @@
+//! Exercise the intrinsics we model (we extract this file with the different
+//! policies for the intrinsics, see the Makefile).
+
+#[repr(transparent)]
+pub struct Wrapper(pub u32);
+
+pub fn wrap(x: u32) -> Wrapper {
+    unsafe { std::mem::transmute(x) }
+}
+
+pub fn copy_one(src: &u32, dst: &mut u32) {
+    unsafe { std::ptr::copy_nonoverlapping(src, dst, 1) }
+}
+
+/// Doesn't call any intrinsic
+pub fn incr(x: u32) -> u32 {
+    x + 1
+}
@@ -1,15 +1,27 @@
 pub mod array;
 pub mod array_const_generics;
+pub mod assumed;
 pub mod bitwise;
+pub mod boxes;
 pub mod closures;
+pub mod collections;
 pub mod constants;
 pub mod external;
+pub mod fmt;
+pub mod foreign;
 pub mod hashmap;
+pub mod intrinsics;
 pub mod loops;
 pub mod loops_cfg;
+pub mod macros;
 pub mod matches;
 pub mod nested_borrows;
 pub mod no_nested_borrows;
 pub mod paper;
+pub mod qualifiers;
+pub mod raw_pointers;
+pub mod shared_ownership;
+pub mod targets;
 pub mod traits;
 pub mod traits_special;
+pub mod variances;
@@ -0,0 +1,51 @@
+//! Exercise the labels of the loops (see the Makefile).
+
+/// The `break` exits the two loops: the outer loop must be labelled.
+pub fn break_outer(n: u32) -> u32 {
+    let mut i = 0;
+    'outer: while i < n {
+        let mut j = 0;
+        while j < n {
+            if i * j > 10 {
+                break 'outer;
+            }
+            j += 1;
+        }
+        i += 1;
+    }
+    i
+}
+
+/// The `continue` jumps to the outer loop: the outer loop must be labelled.
+pub fn continue_outer(n: u32) -> u32 {
+    let mut s = 0;
+    let mut i = 0;
+    'outer: while i < n {
+        i += 1;
+        let mut j = 0;
+        while j < i {
+            j += 1;
+            if j == 3 {
+                continue 'outer;
+            }
+            s += j;
+        }
+    }
+    s
+}
+
+/// The breaks only exit the innermost loops: no labels.
+pub fn no_labels(n: u32) -> u32 {
+    let mut i = 0;
+    while i < n {
+        let mut j = 0;
+        while j < n {
+            if j > 5 {
+                break;
+            }
+            j += 1;
+        }
+        i += 1;
+    }
+    i
+}
@@ -0,0 +1,18 @@
+//! Exercise the macro backtraces: the statements of `add_two` come from nested
+//! macro expansions (see the Makefile).
+
+macro_rules! incr {
+    ($x:expr) => {
+        $x + 1
+    };
+}
+
+macro_rules! incr_twice {
+    ($x:expr) => {
+        incr!(incr!($x))
+    };
+}
+
+pub fn add_two(x: u32) -> u32 {
+    incr_twice!(x)
+}
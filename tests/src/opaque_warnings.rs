@@ -0,0 +1,19 @@
+//! Items we fail to translate, and which thus become opaque: the generated
+//! file must list them in its `opaque_warnings` (see the Makefile).
+
+pub fn uses_float(x: u32) -> u32 {
+    let y = 1.5f32;
+    if y > 1.0 {
+        x
+    } else {
+        0
+    }
+}
+
+pub fn no_float(x: u32) -> u32 {
+    x + 1
+}
+
+pub fn entry(x: u32) -> u32 {
+    no_float(x)
+}
@@ -0,0 +1,39 @@
+//! Exercise the printing of the place projections (see the Makefile).
+
+pub struct Point {
+    pub x: u32,
+    pub y: u32,
+}
+
+pub struct Pair(pub u32, pub u32);
+
+pub enum Shape {
+    Dot,
+    Circle { center: Point, radius: u32 },
+}
+
+pub fn get_x(p: Point) -> u32 {
+    p.x
+}
+
+pub fn get_y_ref(r: &Point) -> u32 {
+    r.y
+}
+
+pub fn get_first(t: Pair) -> u32 {
+    t.0
+}
+
+pub fn get_radius(s: Shape) -> u32 {
+    match s {
+        Shape::Dot => 0,
+        Shape::Circle { radius, .. } => radius,
+    }
+}
+
+pub fn get_center_x(s: &Shape) -> u32 {
+    match s {
+        Shape::Dot => 0,
+        Shape::Circle { center, .. } => center.x,
+    }
+}
@@ -0,0 +1,22 @@
+//! Exercise the qualifiers of the functions (see the Makefile).
+
+pub const fn double(x: u32) -> u32 {
+    2 * x
+}
+
+/// # Safety
+///
+/// `x` must be a valid pointer.
+pub unsafe fn read(x: *const u32) -> u32 {
+    *x
+}
+
+pub extern "C" fn add_c(x: u32, y: u32) -> u32 {
+    x.wrapping_add(y)
+}
+
+pub fn call_all(x: u32) -> u32 {
+    let y = double(x);
+    let z = unsafe { read(&y) };
+    add_c(y, z)
+}
@@ -0,0 +1,37 @@
+//! Exercise the reconstruction of the `?` operator (see the Makefile).
+
+pub fn parse_digit(c: u8) -> Result<u32, ()> {
+    if c.is_ascii_digit() {
+        Ok((c - b'0') as u32)
+    } else {
+        Err(())
+    }
+}
+
+/// `?` on a `Result`.
+pub fn on_result(c: u8) -> Result<u32, ()> {
+    let x = parse_digit(c)?;
+    Ok(x + 1)
+}
+
+/// `?` on an `Option`.
+pub fn on_option(x: Option<u32>) -> Option<u32> {
+    let y = x?;
+    Some(y * 2)
+}
+
+/// The second `?` is in the continue branch of the first one: both must be
+/// rewritten, and the statements which follow them must be spliced back.
+pub fn nested(c0: u8, c1: u8) -> Result<u32, ()> {
+    let x = parse_digit(c0)?;
+    let y = parse_digit(c1)?;
+    Ok(x * 10 + y)
+}
+
+/// No `?`: nothing to rewrite.
+pub fn explicit_match(c: u8) -> Result<u32, ()> {
+    match parse_digit(c) {
+        Ok(x) => Ok(x + 1),
+        Err(e) => Err(e),
+    }
+}
@@ -0,0 +1,29 @@
+//! Exercise the operations on raw pointers.
+
+pub fn same_address(p: *const u32, q: *const u32) -> bool {
+    p == q
+}
+
+pub fn is_before(p: *const u32, q: *const u32) -> bool {
+    p < q
+}
+
+pub fn to_address(p: *const u32) -> usize {
+    p as usize
+}
+
+pub fn from_address(a: usize) -> *const u32 {
+    a as *const u32
+}
+
+pub fn to_bytes(p: *mut u32) -> *mut u8 {
+    p as *mut u8
+}
+
+pub fn field_address(p: &(u32, u32)) -> *const u32 {
+    core::ptr::addr_of!(p.1)
+}
+
+pub fn field_address_mut(p: &mut (u32, u32)) -> *mut u32 {
+    core::ptr::addr_of_mut!(p.0)
+}
@@ -0,0 +1,32 @@
+//! Exercise the extraction of the representation options (see the Makefile).
+
+#[repr(C)]
+pub struct CStruct {
+    pub x: u32,
+    pub y: u8,
+}
+
+#[repr(transparent)]
+pub struct Wrapper(pub u64);
+
+#[repr(u8)]
+pub enum Tag {
+    A = 1,
+    B = 2,
+}
+
+#[repr(C, packed(2))]
+pub struct Packed {
+    pub x: u8,
+    pub y: u32,
+}
+
+#[repr(align(16))]
+pub struct Aligned {
+    pub x: u32,
+}
+
+/// No `#[repr(...)]`: nothing must be printed.
+pub struct Plain {
+    pub x: u32,
+}
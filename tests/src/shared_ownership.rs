@@ -0,0 +1,22 @@
+//! Exercise the types of the standard library used for shared ownership and
+//! interior mutability.
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use std::sync::Arc;
+
+pub struct Shared {
+    pub counter: Rc<Cell<u32>>,
+    pub log: Arc<RefCell<Vec<u32>>>,
+}
+
+pub fn share(x: Rc<Cell<u32>>) -> (Rc<Cell<u32>>, Rc<Cell<u32>>) {
+    (x.clone(), x)
+}
+
+pub fn incr(x: &Cell<u32>) {
+    x.set(x.get() + 1)
+}
+
+pub fn get_counter(s: &Shared) -> &Rc<Cell<u32>> {
+    &s.counter
+}
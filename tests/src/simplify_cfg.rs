@@ -0,0 +1,24 @@
+//! Exercise the simplification of the control-flow graph (see the Makefile).
+
+/// The two arms lead to identical blocks, which must be merged.
+pub fn same_arms(x: Option<u32>) -> u32 {
+    match x {
+        Some(_) => 1,
+        None => 1,
+    }
+}
+
+/// The loops introduce chains of `goto` blocks, which must be threaded.
+pub fn nested_loops(n: u32) -> u32 {
+    let mut i = 0;
+    let mut s = 0;
+    while i < n {
+        let mut j = 0;
+        while j < i {
+            s += j;
+            j += 1;
+        }
+        i += 1;
+    }
+    s
+}
@@ -0,0 +1,37 @@
+//! Exercise the grouping of the switch values into ranges (see the Makefile).
+
+/// The digits must be grouped in a single range; `a` and `c` are not
+/// consecutive and must stay separate.
+pub fn classify(c: u8) -> u32 {
+    match c {
+        b'0' | b'1' | b'2' | b'3' | b'4' | b'5' | b'6' | b'7' | b'8' | b'9' => 1,
+        b'a' | b'c' => 2,
+        _ => 0,
+    }
+}
+
+/// Consecutive values leading to different branches: no ranges.
+pub fn distinct(x: i32) -> u32 {
+    match x {
+        -1 => 1,
+        0 => 2,
+        1 => 3,
+        _ => 0,
+    }
+}
+
+pub enum E {
+    A,
+    B,
+    C,
+    D,
+}
+
+/// Several variants lead to the same branch: we must get back the variants
+/// from the range of discriminants.
+pub fn variants(e: E) -> u32 {
+    match e {
+        E::A | E::B | E::C => 1,
+        E::D => 2,
+    }
+}
@@ -0,0 +1,9 @@
+//! Exercise the code whose extraction depends on the target (we extract this
+//! file for several targets, see the Makefile).
+
+pub const USIZE_MAX: usize = usize::MAX;
+pub const ISIZE_MIN: isize = isize::MIN;
+
+pub fn pointer_size() -> usize {
+    core::mem::size_of::<usize>()
+}
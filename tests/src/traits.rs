@@ -77,6 +77,21 @@ impl<T: ToU64> ToU64 for Wrapper<T> {
     }
 }
 
+// A blanket implementation
+pub trait ToU64Twice {
+    fn to_u64_twice(self) -> u64;
+}
+
+impl<T: ToU64 + Copy> ToU64Twice for T {
+    fn to_u64_twice(self) -> u64 {
+        self.to_u64() + self.to_u64()
+    }
+}
+
+pub fn test_blanket(x: u64) -> u64 {
+    x.to_u64_twice()
+}
+
 pub fn h1(x: Wrapper<u64>) -> u64 {
     x.to_u64()
 }
@@ -190,6 +205,13 @@ pub fn use_with_const_ty3<const LEN: usize, H: WithConstTy<LEN>>(x: H::W) -> u64
     x.to_u64()
 }
 
+// Using an associated constant in the length of an array (this is only
+// possible at a concrete instantiation)
+pub fn use_with_const_ty4() -> usize {
+    let a = [0u8; <bool as WithConstTy<32>>::LEN2];
+    a.len()
+}
+
 pub fn test_where1<'a, T: 'a>(_x: &'a T) {}
 pub fn test_where2<T: WithConstTy<32, V = u32>>(_x: T::V) {}
 
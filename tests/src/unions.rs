@@ -0,0 +1,15 @@
+//! Exercise the translation of the union declarations and aggregates (see the
+//! Makefile).
+
+pub union U {
+    pub x: u32,
+    pub y: u64,
+}
+
+pub fn make_x(x: u32) -> U {
+    U { x }
+}
+
+pub fn make_y(y: u64) -> U {
+    U { y }
+}
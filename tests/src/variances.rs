@@ -0,0 +1,15 @@
+//! Exercise the variances of the generic parameters (see the Makefile).
+
+/// Covariant in `'a` and invariant in `T`
+pub struct MutRef<'a, T> {
+    pub r: &'a mut T,
+}
+
+/// Contravariant in `T`
+pub struct Consumer<T> {
+    pub f: fn(T),
+}
+
+pub fn consume<T>(c: Consumer<T>, x: T) {
+    (c.f)(x)
+}